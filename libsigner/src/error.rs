@@ -17,6 +17,7 @@
 use std::io;
 
 use clarity::vm::types::QualifiedContractIdentifier;
+use libstackerdb::object::ObjectError;
 
 /// Errors originating from doing an RPC request to the Stacks node
 #[derive(thiserror::Error, Debug)]
@@ -39,6 +40,12 @@ pub enum RPCError {
     /// HTTP error
     #[error("HTTP code {0}")]
     HttpError(u32),
+    /// Error storing or reassembling a StackerDB object
+    #[error("StackerDB object error: {0}")]
+    Object(#[from] ObjectError),
+    /// Error signing a chunk
+    #[error("Signing error: {0}")]
+    Signing(String),
 }
 
 /// Errors originating from receiving event data from the Stacks node
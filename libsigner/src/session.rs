@@ -16,18 +16,24 @@
 
 use std::net::{SocketAddr, TcpStream};
 use std::str;
+use std::time::Duration;
 
 use clarity::vm::types::QualifiedContractIdentifier;
+use libstackerdb::object::{make_object_chunks, ObjectError, ObjectManifest};
 use libstackerdb::{
-    stackerdb_get_chunk_path, stackerdb_get_metadata_path, stackerdb_post_chunk_path, SlotMetadata,
-    StackerDBChunkAckData, StackerDBChunkData, SIGNERS_STACKERDB_CHUNK_SIZE,
-    STACKERDB_MAX_CHUNK_SIZE,
+    stackerdb_get_chunk_path, stackerdb_get_metadata_path, stackerdb_post_chunk_path,
+    stackerdb_subscribe_path, SlotMetadata, StackerDBChunkAckData, StackerDBChunkData,
+    StackerDBSubscribeData, SIGNERS_STACKERDB_CHUNK_SIZE, STACKERDB_MAX_CHUNK_SIZE,
 };
 use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::StacksPrivateKey;
 
 use crate::error::RPCError;
 use crate::http::run_http_request;
 
+/// How many times to re-read an object whose parts were overwritten while it was being read
+pub const OBJECT_READ_ATTEMPTS: usize = 3;
+
 /// Trait for connecting to and querying a signer Stacker DB replica
 pub trait SignerSession {
     /// connect to the replica
@@ -92,6 +98,70 @@ pub trait SignerSession {
         )
         .transpose()
     }
+
+    /// Upload an object that may be larger than a chunk.
+    /// The object is split across one half of `data_slot_ids`, alternating halves with each
+    /// update, and its manifest is written to `manifest_slot_id` once every part has been
+    /// accepted, so readers keep reading the old object until the new one is complete.
+    /// `chunk_size` is the StackerDB's maximum chunk size.
+    /// Returns the acknowledgement of the first chunk the replica rejected, or of the manifest
+    /// if every chunk was accepted.
+    fn put_object(
+        &mut self,
+        privk: &StacksPrivateKey,
+        manifest_slot_id: u32,
+        data_slot_ids: &[u32],
+        object: &[u8],
+        chunk_size: u32,
+    ) -> Result<StackerDBChunkAckData, RPCError> {
+        let slot_metadata = self.list_chunks()?;
+        let chunks = make_object_chunks(
+            object,
+            manifest_slot_id,
+            data_slot_ids,
+            &slot_metadata,
+            chunk_size,
+        )?;
+        let mut last_ack = None;
+        for mut chunk in chunks {
+            chunk
+                .sign(privk)
+                .map_err(|e| RPCError::Signing(e.to_string()))?;
+            let ack = self.put_chunk(&chunk)?;
+            if !ack.accepted {
+                return Ok(ack);
+            }
+            last_ack = Some(ack);
+        }
+        last_ack.ok_or_else(|| {
+            RPCError::MalformedRequest("An object always has a manifest chunk".into())
+        })
+    }
+
+    /// Get the latest version of an object uploaded with `put_object`, verified against its
+    /// manifest.
+    /// Returns Ok(Some(..)) if the manifest slot holds an object
+    /// Returns Ok(None) if the manifest slot is empty
+    /// Returns Err(..) on transport error, if the object is corrupt, or if it kept changing
+    /// while it was being read
+    fn get_object(&mut self, manifest_slot_id: u32) -> Result<Option<Vec<u8>>, RPCError> {
+        for _ in 0..OBJECT_READ_ATTEMPTS {
+            let manifest_bytes = match self.get_latest_chunk(manifest_slot_id)? {
+                Some(bytes) if !bytes.is_empty() => bytes,
+                _ => return Ok(None),
+            };
+            let manifest = ObjectManifest::consensus_deserialize(&mut manifest_bytes.as_slice())
+                .map_err(|e| RPCError::Deserialize(format!("Invalid object manifest: {e}")))?;
+            let chunks = self.get_chunks(&manifest.part_versions())?;
+            match manifest.assemble(&chunks) {
+                Ok(object) => return Ok(Some(object)),
+                // The object was updated while we read it; read the new manifest
+                Err(ObjectError::Stale) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(ObjectError::Stale.into())
+    }
 }
 
 /// signer session for a stackerdb instance
@@ -157,6 +227,33 @@ impl StackerDBSession {
             run_http_request(sock, &session.host, verb, path, content_type, payload)
         })?
     }
+
+    /// Wait until the replica stores a chunk past the given slot versions (i.e. from
+    /// `list_chunks`), or until `timeout` expires.  Slots past the end of `slot_versions` are
+    /// taken to be at version 0.  The replica caps the timeout at
+    /// `STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS`.
+    /// Return the metadata of the slots that changed, which is empty if the timeout expired.
+    pub fn wait_for_changes(
+        &mut self,
+        slot_versions: &[u32],
+        timeout: Duration,
+    ) -> Result<Vec<SlotMetadata>, RPCError> {
+        let subscription = StackerDBSubscribeData {
+            slot_versions: slot_versions.to_vec(),
+            timeout_secs: timeout.as_secs(),
+        };
+        let payload = serde_json::to_vec(&subscription)
+            .expect("FATAL: failed to serialize infallible structure");
+        let bytes = self.rpc_request(
+            "POST",
+            &stackerdb_subscribe_path(self.stackerdb_contract_id.clone()),
+            Some("application/json"),
+            &payload,
+        )?;
+        let changed: Vec<SlotMetadata> = serde_json::from_slice(&bytes)
+            .map_err(|e| RPCError::Deserialize(format!("{:?}", &e)))?;
+        Ok(changed)
+    }
 }
 
 impl SignerSession for StackerDBSession {
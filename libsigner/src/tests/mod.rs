@@ -28,7 +28,8 @@ use blockstack_lib::chainstate::stacks::boot::SIGNERS_NAME;
 use blockstack_lib::chainstate::stacks::events::StackerDBChunksEvent;
 use blockstack_lib::util_lib::boot::boot_code_id;
 use clarity::vm::types::QualifiedContractIdentifier;
-use libstackerdb::StackerDBChunkData;
+use libstackerdb::object::ObjectError;
+use libstackerdb::{SlotMetadata, StackerDBChunkAckData, StackerDBChunkData};
 use stacks_common::codec::{
     read_next, read_next_at_most, read_next_exact, write_next, Error as CodecError,
     StacksMessageCodec,
@@ -39,7 +40,7 @@ use wsts::net::{DkgBegin, Packet};
 
 use crate::events::{SignerEvent, SignerEventTrait};
 use crate::v1::messages::SignerMessage;
-use crate::{Signer, SignerEventReceiver, SignerRunLoop};
+use crate::{RPCError, Signer, SignerEventReceiver, SignerRunLoop, SignerSession};
 
 /// Simple runloop implementation.  It receives `max_events` events and returns `events` from the
 /// last call to `run_one_pass` as its final state.
@@ -217,3 +218,133 @@ fn test_status_endpoint() {
     assert_eq!(sent_events, accepted_events);
     mock_stacks_node.join().unwrap();
 }
+
+/// An in-memory StackerDB replica with a fixed number of slots
+struct MemorySession {
+    chunks: Vec<StackerDBChunkData>,
+}
+
+impl MemorySession {
+    fn new(num_slots: u32) -> Self {
+        Self {
+            chunks: (0..num_slots)
+                .map(|slot_id| StackerDBChunkData::new(slot_id, 0, vec![]))
+                .collect(),
+        }
+    }
+}
+
+impl SignerSession for MemorySession {
+    fn connect(
+        &mut self,
+        _host: String,
+        _stackerdb_contract_id: QualifiedContractIdentifier,
+    ) -> Result<(), RPCError> {
+        Ok(())
+    }
+
+    fn list_chunks(&mut self) -> Result<Vec<SlotMetadata>, RPCError> {
+        Ok(self
+            .chunks
+            .iter()
+            .map(|chunk| chunk.get_slot_metadata())
+            .collect())
+    }
+
+    fn get_chunks(
+        &mut self,
+        slots_and_versions: &[(u32, u32)],
+    ) -> Result<Vec<Option<Vec<u8>>>, RPCError> {
+        Ok(slots_and_versions
+            .iter()
+            .map(|(slot_id, slot_version)| {
+                self.chunks
+                    .get(*slot_id as usize)
+                    .filter(|chunk| chunk.slot_version == *slot_version)
+                    .map(|chunk| chunk.data.clone())
+            })
+            .collect())
+    }
+
+    fn get_latest_chunks(&mut self, slot_ids: &[u32]) -> Result<Vec<Option<Vec<u8>>>, RPCError> {
+        Ok(slot_ids
+            .iter()
+            .map(|slot_id| {
+                self.chunks
+                    .get(*slot_id as usize)
+                    .map(|chunk| chunk.data.clone())
+            })
+            .collect())
+    }
+
+    fn put_chunk(&mut self, chunk: &StackerDBChunkData) -> Result<StackerDBChunkAckData, RPCError> {
+        let slot = &mut self.chunks[chunk.slot_id as usize];
+        let accepted = chunk.slot_version > slot.slot_version;
+        if accepted {
+            *slot = chunk.clone();
+        }
+        Ok(StackerDBChunkAckData {
+            accepted,
+            reason: None,
+            metadata: Some(slot.get_slot_metadata()),
+            code: None,
+        })
+    }
+}
+
+/// Verify that objects larger than a chunk can be written and read back through a session, and
+/// that a reader never assembles a half-updated object.
+#[test]
+fn test_stackerdb_objects() {
+    let privk = Secp256k1PrivateKey::new();
+    let mut session = MemorySession::new(7);
+    let data_slot_ids = [1, 2, 3, 4, 5, 6];
+    assert_eq!(session.get_object(0).unwrap(), None);
+
+    let first: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+    let ack = session
+        .put_object(&privk, 0, &data_slot_ids, &first, 400)
+        .unwrap();
+    assert!(ack.accepted);
+    assert_eq!(ack.metadata.unwrap().slot_version, 1);
+    assert_eq!(session.get_object(0).unwrap(), Some(first.clone()));
+
+    // A writer that stops after writing some of the new object's parts leaves the old object
+    // readable, since the new parts go in the other half of the data slots
+    let second = vec![0xab; 500];
+    let slot_metadata = session.list_chunks().unwrap();
+    let chunks =
+        libstackerdb::object::make_object_chunks(&second, 0, &data_slot_ids, &slot_metadata, 400)
+            .unwrap();
+    assert_eq!(chunks[0].slot_id, 4);
+    session.put_chunk(&chunks[0]).unwrap();
+    assert_eq!(session.get_object(0).unwrap(), Some(first.clone()));
+
+    // Finishing the update makes the new object readable
+    let ack = session
+        .put_object(&privk, 0, &data_slot_ids, &second, 400)
+        .unwrap();
+    assert!(ack.accepted);
+    assert_eq!(session.get_object(0).unwrap(), Some(second.clone()));
+
+    // The next update goes back to the first half, and the second object stays readable while
+    // it is written
+    let third = vec![0xcd; 900];
+    let slot_metadata = session.list_chunks().unwrap();
+    let chunks =
+        libstackerdb::object::make_object_chunks(&third, 0, &data_slot_ids, &slot_metadata, 400)
+            .unwrap();
+    for chunk in chunks[..3].iter() {
+        assert!(chunk.slot_id <= 3);
+        session.put_chunk(chunk).unwrap();
+    }
+    assert_eq!(session.get_object(0).unwrap(), Some(second));
+    session.put_chunk(&chunks[3]).unwrap();
+    assert_eq!(session.get_object(0).unwrap(), Some(third));
+
+    // Objects that do not fit are refused before anything is written
+    assert!(matches!(
+        session.put_object(&privk, 0, &[1, 2, 3, 4], &[0; 1000], 400),
+        Err(RPCError::Object(ObjectError::TooLarge { .. }))
+    ));
+}
@@ -227,7 +227,9 @@ RejectCodeTypePrefix {
     /// Nonce timeout
     NonceTimeout = 5,
     /// Aggregator error
-    AggregatorError = 6
+    AggregatorError = 6,
+    /// Block policy violation
+    PolicyViolation = 7
 });
 
 impl TryFrom<u8> for RejectCodeTypePrefix {
@@ -249,6 +251,7 @@ impl From<&RejectCode> for RejectCodeTypePrefix {
             RejectCode::ConnectivityIssues => RejectCodeTypePrefix::ConnectivityIssues,
             RejectCode::NonceTimeout(_) => RejectCodeTypePrefix::NonceTimeout,
             RejectCode::AggregatorError(_) => RejectCodeTypePrefix::AggregatorError,
+            RejectCode::PolicyViolation(_) => RejectCodeTypePrefix::PolicyViolation,
         }
     }
 }
@@ -1301,6 +1304,8 @@ pub enum RejectCode {
     MissingTransactions(Vec<StacksTransaction>),
     /// The block was rejected due to connectivity issues with the signer
     ConnectivityIssues,
+    /// The block violates the signer's configured block policy
+    PolicyViolation(String),
 }
 
 impl From<&SignError> for RejectCode {
@@ -1328,7 +1333,9 @@ impl StacksMessageCodec for RejectCode {
             RejectCode::MissingTransactions(missing_transactions) => {
                 write_next(fd, missing_transactions)?
             }
-            RejectCode::AggregatorError(reason) => write_next(fd, &reason.as_bytes().to_vec())?,
+            RejectCode::AggregatorError(reason) | RejectCode::PolicyViolation(reason) => {
+                write_next(fd, &reason.as_bytes().to_vec())?
+            }
             RejectCode::ConnectivityIssues => write_next(fd, &4u8)?,
         };
         Ok(())
@@ -1369,6 +1376,16 @@ impl StacksMessageCodec for RejectCode {
                 })?;
                 RejectCode::AggregatorError(reason)
             }
+            RejectCodeTypePrefix::PolicyViolation => {
+                let reason_bytes = read_next::<Vec<u8>, _>(fd)?;
+                let reason = String::from_utf8(reason_bytes).map_err(|e| {
+                    CodecError::DeserializeError(format!(
+                        "Failed to decode reason string: {:?}",
+                        &e
+                    ))
+                })?;
+                RejectCode::PolicyViolation(reason)
+            }
         };
         Ok(code)
     }
@@ -1405,6 +1422,9 @@ impl std::fmt::Display for RejectCode {
                 "An internal error occurred in the signer when aggregating the signaure: {:?}",
                 reason
             ),
+            RejectCode::PolicyViolation(reason) => {
+                write!(f, "The block violates the signer's block policy: {}", reason)
+            }
         }
     }
 }
@@ -1520,6 +1540,12 @@ mod test {
         let deserialized_code = read_next::<RejectCode, _>(&mut &serialized_code[..])
             .expect("Failed to deserialize RejectCode");
         assert_eq!(code, deserialized_code);
+
+        let code = RejectCode::PolicyViolation("max_block_size: too big".into());
+        let serialized_code = code.serialize_to_vec();
+        let deserialized_code = read_next::<RejectCode, _>(&mut &serialized_code[..])
+            .expect("Failed to deserialize RejectCode");
+        assert_eq!(code, deserialized_code);
     }
 
     #[test]
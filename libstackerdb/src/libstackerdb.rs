@@ -37,7 +37,10 @@ use stacks_common::util::secp256k1::MessageSignature;
 pub const STACKERDB_MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
 /// CHUNK_SIZE constant for signers StackerDBs (2MB)
 pub const SIGNERS_STACKERDB_CHUNK_SIZE: usize = 2 * 1024 * 1024; // 2MB
+/// maximum time a subscription request waits for a slot to be written, in seconds
+pub const STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS: u64 = 60;
 
+pub mod object;
 #[cfg(test)]
 mod tests;
 
@@ -110,6 +113,28 @@ pub struct StackerDBChunkAckData {
     pub code: Option<u32>,
 }
 
+/// StackerDB subscription request: wait until any slot is written past the given versions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackerDBSubscribeData {
+    /// The slot versions the subscriber has already seen, indexed by slot ID.
+    /// Slots past the end of the list are taken to be at version 0.
+    pub slot_versions: Vec<u32>,
+    /// How long to wait for a write, in seconds (at most `STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS`)
+    pub timeout_secs: u64,
+}
+
+impl StackerDBSubscribeData {
+    /// Has the subscriber not seen this version of the slot yet?
+    pub fn is_newer(&self, slot_id: u32, slot_version: u32) -> bool {
+        let seen_version = usize::try_from(slot_id)
+            .ok()
+            .and_then(|idx| self.slot_versions.get(idx))
+            .copied()
+            .unwrap_or(0);
+        slot_version > seen_version
+    }
+}
+
 impl SlotMetadata {
     /// Make a new unsigned slot metadata
     pub fn new_unsigned(
@@ -126,7 +151,7 @@ impl SlotMetadata {
     }
 
     /// Get the digest to sign that authenticates this chunk data and metadata
-    fn auth_digest(&self) -> Sha512Trunc256Sum {
+    pub fn auth_digest(&self) -> Sha512Trunc256Sum {
         let mut hasher = Sha512_256::new();
         hasher.update(self.slot_id.to_be_bytes());
         hasher.update(self.slot_version.to_be_bytes());
@@ -289,3 +314,12 @@ pub fn stackerdb_post_chunk_path(contract_id: QualifiedContractIdentifier) -> St
         &contract_id.name
     )
 }
+
+/// Calculate POST path for a stacker DB subscription
+pub fn stackerdb_subscribe_path(contract_id: QualifiedContractIdentifier) -> String {
+    format!(
+        "/v2/stackerdb/{}/{}/subscribe",
+        &StacksAddress::from(contract_id.issuer),
+        &contract_id.name
+    )
+}
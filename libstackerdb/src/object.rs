@@ -0,0 +1,297 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Objects larger than a StackerDB chunk.
+//!
+//! An object is split into parts, each stored as a chunk in one of the writer's data slots,
+//! and described by a manifest chunk in another of the writer's slots. The manifest pins the
+//! exact version and hash of every part, as well as the hash of the whole object.
+//!
+//! Writers store the data chunks first and the manifest last. The writer's data slots are split
+//! into two halves, and successive versions of the object alternate between them, so writing a
+//! new version never overwrites the parts of the version the current manifest names. Readers keep
+//! reading the old object until the new manifest lands.
+//!
+//! A reader that fetches the parts named by a manifest either gets exactly those versions, or
+//! finds that a part has since been overwritten (because the object was updated twice while it
+//! read) and knows to fetch the manifest again. It never assembles a mix of two objects.
+
+use std::io::{Read, Write};
+use std::{error, fmt};
+
+use stacks_common::codec::{
+    read_next, read_next_at_most, write_next, Error as CodecError, StacksMessageCodec,
+};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+use crate::{SlotMetadata, StackerDBChunkData};
+
+/// Prefix of every object manifest chunk
+pub const OBJECT_MANIFEST_MAGIC: [u8; 4] = *b"SDBO";
+/// Version of the object manifest encoding
+pub const OBJECT_MANIFEST_VERSION: u8 = 1;
+/// Maximum number of parts an object can be split into
+pub const OBJECT_MAX_PARTS: u32 = 4096;
+
+/// Encoded size of an object manifest with no parts
+const OBJECT_MANIFEST_HEADER_SIZE: usize = 4 + 1 + 32 + 8 + 4;
+/// Encoded size of each part in an object manifest
+const OBJECT_PART_SIZE: usize = 4 + 4 + 32;
+
+/// Errors storing or reassembling an object
+#[derive(Debug)]
+pub enum ObjectError {
+    /// The object does not fit in the given slots
+    TooLarge {
+        /// The size of the object
+        object_len: usize,
+        /// The number of bytes the slots can hold
+        capacity: usize,
+    },
+    /// The manifest slot is also listed as a data slot
+    ManifestSlotReused(u32),
+    /// A part named by the manifest has been overwritten, so the object is being updated
+    Stale,
+    /// The parts do not match the manifest
+    Corrupt(String),
+}
+
+impl fmt::Display for ObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjectError::TooLarge {
+                object_len,
+                capacity,
+            } => write!(
+                f,
+                "Object of {object_len} bytes does not fit in {capacity} bytes of slots"
+            ),
+            ObjectError::ManifestSlotReused(slot_id) => {
+                write!(f, "Slot {slot_id} cannot hold both the manifest and data")
+            }
+            ObjectError::Stale => write!(f, "Object is being updated"),
+            ObjectError::Corrupt(s) => write!(f, "Corrupt object: {s}"),
+        }
+    }
+}
+
+impl error::Error for ObjectError {
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
+    }
+}
+
+/// A part of an object, stored as a chunk in a data slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectPart {
+    /// The data slot holding the part
+    pub slot_id: u32,
+    /// The version of the chunk holding the part
+    pub slot_version: u32,
+    /// The hash of the part
+    pub data_hash: Sha512Trunc256Sum,
+}
+
+/// The manifest of an object, stored as a chunk in the writer's manifest slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectManifest {
+    /// The SHA512/256 hash of the whole object
+    pub object_hash: Sha512Trunc256Sum,
+    /// The size of the object in bytes
+    pub object_len: u64,
+    /// The object's parts, in order
+    pub parts: Vec<ObjectPart>,
+}
+
+impl StacksMessageCodec for ObjectPart {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        write_next(fd, &self.slot_id)?;
+        write_next(fd, &self.slot_version)?;
+        write_next(fd, &self.data_hash)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<ObjectPart, CodecError> {
+        let slot_id: u32 = read_next(fd)?;
+        let slot_version: u32 = read_next(fd)?;
+        let data_hash: Sha512Trunc256Sum = read_next(fd)?;
+        Ok(ObjectPart {
+            slot_id,
+            slot_version,
+            data_hash,
+        })
+    }
+}
+
+impl StacksMessageCodec for ObjectManifest {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        fd.write_all(&OBJECT_MANIFEST_MAGIC)
+            .map_err(CodecError::WriteError)?;
+        write_next(fd, &OBJECT_MANIFEST_VERSION)?;
+        write_next(fd, &self.object_hash)?;
+        write_next(fd, &self.object_len)?;
+        write_next(fd, &self.parts)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<ObjectManifest, CodecError> {
+        let mut magic = [0u8; 4];
+        fd.read_exact(&mut magic).map_err(CodecError::ReadError)?;
+        if magic != OBJECT_MANIFEST_MAGIC {
+            return Err(CodecError::DeserializeError(
+                "Not an object manifest".to_string(),
+            ));
+        }
+        let version: u8 = read_next(fd)?;
+        if version != OBJECT_MANIFEST_VERSION {
+            return Err(CodecError::DeserializeError(format!(
+                "Unsupported object manifest version {version}"
+            )));
+        }
+        let object_hash: Sha512Trunc256Sum = read_next(fd)?;
+        let object_len: u64 = read_next(fd)?;
+        let parts: Vec<ObjectPart> = read_next_at_most(fd, OBJECT_MAX_PARTS)?;
+        Ok(ObjectManifest {
+            object_hash,
+            object_len,
+            parts,
+        })
+    }
+}
+
+impl ObjectManifest {
+    /// Get the (slot ID, slot version) pairs of the chunks holding the object's parts, in order
+    pub fn part_versions(&self) -> Vec<(u32, u32)> {
+        self.parts
+            .iter()
+            .map(|part| (part.slot_id, part.slot_version))
+            .collect()
+    }
+
+    /// Reassemble the object from the chunks named by `part_versions()`, verifying each part
+    /// and the whole object against the manifest.
+    /// A missing chunk means its slot has been overwritten since the manifest was written, and
+    /// yields `ObjectError::Stale`: fetch the manifest again and retry.
+    pub fn assemble(&self, chunks: &[Option<Vec<u8>>]) -> Result<Vec<u8>, ObjectError> {
+        if chunks.len() != self.parts.len() {
+            return Err(ObjectError::Corrupt(format!(
+                "Expected {} parts, got {}",
+                self.parts.len(),
+                chunks.len()
+            )));
+        }
+        let mut object = vec![];
+        for (part, chunk) in self.parts.iter().zip(chunks.iter()) {
+            let Some(chunk) = chunk else {
+                return Err(ObjectError::Stale);
+            };
+            if Sha512Trunc256Sum::from_data(chunk) != part.data_hash {
+                return Err(ObjectError::Corrupt(format!(
+                    "Part in slot {} does not match its hash",
+                    part.slot_id
+                )));
+            }
+            object.extend_from_slice(chunk);
+        }
+        if u64::try_from(object.len()).ok() != Some(self.object_len) {
+            return Err(ObjectError::Corrupt(format!(
+                "Expected {} bytes, got {}",
+                self.object_len,
+                object.len()
+            )));
+        }
+        if Sha512Trunc256Sum::from_data(&object) != self.object_hash {
+            return Err(ObjectError::Corrupt(
+                "Object does not match its hash".to_string(),
+            ));
+        }
+        Ok(object)
+    }
+}
+
+/// Split an object into the unsigned chunks that store it.
+///
+/// `data_slot_ids` are the writer's slots available for the object's parts. They are split into
+/// two halves (an odd slot out is unused): the parts go in the first half when the new manifest
+/// version is odd, and in the second half when it is even, so the parts of the previous version
+/// stay intact. Each half is used in order, and slots the object does not need are left alone.
+/// `slot_metadata` is the replica's current slot metadata (i.e. from `list_chunks`), from which
+/// each written slot's version is bumped. `chunk_size` is the StackerDB's maximum chunk size.
+///
+/// The chunks are returned in the order they must be written: the data chunks, then the
+/// manifest chunk. They must be signed before they are written.
+pub fn make_object_chunks(
+    object: &[u8],
+    manifest_slot_id: u32,
+    data_slot_ids: &[u32],
+    slot_metadata: &[SlotMetadata],
+    chunk_size: u32,
+) -> Result<Vec<StackerDBChunkData>, ObjectError> {
+    if data_slot_ids.contains(&manifest_slot_id) {
+        return Err(ObjectError::ManifestSlotReused(manifest_slot_id));
+    }
+    let next_version = |slot_id: u32| {
+        slot_metadata
+            .iter()
+            .find(|md| md.slot_id == slot_id)
+            .map(|md| md.slot_version)
+            .unwrap_or(0)
+            .saturating_add(1)
+    };
+    let manifest_version = next_version(manifest_slot_id);
+    let half_len = data_slot_ids.len() / 2;
+    let data_slot_ids = if manifest_version % 2 == 1 {
+        &data_slot_ids[..half_len]
+    } else {
+        &data_slot_ids[half_len..2 * half_len]
+    };
+
+    let chunk_size = usize::try_from(chunk_size).expect("infallible: u32 fits in usize");
+    // The manifest must fit in a chunk too
+    let max_parts = (chunk_size.saturating_sub(OBJECT_MANIFEST_HEADER_SIZE) / OBJECT_PART_SIZE)
+        .min(data_slot_ids.len())
+        .min(OBJECT_MAX_PARTS as usize);
+    let capacity = max_parts.saturating_mul(chunk_size);
+    if object.len() > capacity || chunk_size == 0 {
+        return Err(ObjectError::TooLarge {
+            object_len: object.len(),
+            capacity,
+        });
+    }
+
+    let mut chunks = vec![];
+    let mut parts = vec![];
+    for (data, slot_id) in object.chunks(chunk_size).zip(data_slot_ids.iter()) {
+        let chunk = StackerDBChunkData::new(*slot_id, next_version(*slot_id), data.to_vec());
+        parts.push(ObjectPart {
+            slot_id: chunk.slot_id,
+            slot_version: chunk.slot_version,
+            data_hash: chunk.data_hash(),
+        });
+        chunks.push(chunk);
+    }
+    let manifest = ObjectManifest {
+        object_hash: Sha512Trunc256Sum::from_data(object),
+        object_len: u64::try_from(object.len()).expect("infallible: usize fits in u64"),
+        parts,
+    };
+    chunks.push(StackerDBChunkData::new(
+        manifest_slot_id,
+        manifest_version,
+        manifest.serialize_to_vec(),
+    ));
+    Ok(chunks)
+}
@@ -16,10 +16,12 @@
 
 use clarity::vm::types::QualifiedContractIdentifier;
 use stacks_common::address::{AddressHashMode, C32_ADDRESS_VERSION_MAINNET_SINGLESIG};
+use stacks_common::codec::StacksMessageCodec;
 use stacks_common::types::chainstate::{StacksAddress, StacksPrivateKey, StacksPublicKey};
 use stacks_common::util::hash::{Hash160, Sha512Trunc256Sum};
 use stacks_common::util::secp256k1::MessageSignature;
 
+use crate::object::*;
 use crate::*;
 
 #[test]
@@ -101,7 +103,130 @@ fn test_stackerdb_paths() {
     );
 
     assert_eq!(
-        stackerdb_post_chunk_path(contract_id),
+        stackerdb_post_chunk_path(contract_id.clone()),
         "/v2/stackerdb/SP1Y0NECNCJ6YDVM7GQ594FF065NN3NT72FASBXB8/hello-world/chunks".to_string()
     );
+
+    assert_eq!(
+        stackerdb_subscribe_path(contract_id),
+        "/v2/stackerdb/SP1Y0NECNCJ6YDVM7GQ594FF065NN3NT72FASBXB8/hello-world/subscribe".to_string()
+    );
+}
+
+#[test]
+fn test_stackerdb_subscribe_newer_versions() {
+    let subscription = StackerDBSubscribeData {
+        slot_versions: vec![3, 0],
+        timeout_secs: 10,
+    };
+    assert!(!subscription.is_newer(0, 3));
+    assert!(subscription.is_newer(0, 4));
+    assert!(!subscription.is_newer(1, 0));
+    assert!(subscription.is_newer(1, 1));
+    // slots the subscriber did not list start at version 0
+    assert!(!subscription.is_newer(5, 0));
+    assert!(subscription.is_newer(5, 1));
+}
+
+#[test]
+fn test_stackerdb_object_split_and_assemble() {
+    let object: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+    let chunk_size = 400;
+    let slot_metadata = vec![
+        SlotMetadata::new_unsigned(0, 3, Sha512Trunc256Sum([0x00; 32])),
+        SlotMetadata::new_unsigned(1, 5, Sha512Trunc256Sum([0x00; 32])),
+    ];
+
+    let data_slot_ids = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    // the manifest's next version is even, so the parts go in the second half of the data slots
+    let chunks =
+        make_object_chunks(&object, 0, &data_slot_ids, &slot_metadata, chunk_size).unwrap();
+    // three data chunks, then the manifest; slot 8 is not needed
+    assert_eq!(chunks.len(), 4);
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|chunk| (chunk.slot_id, chunk.slot_version, chunk.data.len()))
+            .collect::<Vec<_>>()[..3],
+        [(5, 1, 400), (6, 1, 400), (7, 1, 200)]
+    );
+    let manifest_chunk = chunks.last().unwrap();
+    assert_eq!(
+        (manifest_chunk.slot_id, manifest_chunk.slot_version),
+        (0, 4)
+    );
+
+    let manifest =
+        ObjectManifest::consensus_deserialize(&mut manifest_chunk.data.as_slice()).unwrap();
+    assert_eq!(manifest.object_len, 1000);
+    assert_eq!(manifest.part_versions(), vec![(5, 1), (6, 1), (7, 1)]);
+
+    // the next version goes in the first half, leaving this version's parts alone
+    let slot_metadata = vec![
+        SlotMetadata::new_unsigned(0, 4, Sha512Trunc256Sum([0x00; 32])),
+        SlotMetadata::new_unsigned(1, 5, Sha512Trunc256Sum([0x00; 32])),
+    ];
+    let next_chunks =
+        make_object_chunks(&object, 0, &data_slot_ids, &slot_metadata, chunk_size).unwrap();
+    assert_eq!(
+        next_chunks
+            .iter()
+            .map(|chunk| (chunk.slot_id, chunk.slot_version))
+            .collect::<Vec<_>>(),
+        [(1, 6), (2, 1), (3, 1), (0, 5)]
+    );
+
+    let mut parts: Vec<_> = chunks[..3]
+        .iter()
+        .map(|chunk| Some(chunk.data.clone()))
+        .collect();
+    assert_eq!(manifest.assemble(&parts).unwrap(), object);
+
+    // a part that was overwritten means the object is being updated
+    let mut stale_parts = parts.clone();
+    stale_parts[1] = None;
+    assert!(matches!(
+        manifest.assemble(&stale_parts),
+        Err(ObjectError::Stale)
+    ));
+
+    // a tampered part is detected
+    parts[2].as_mut().unwrap()[0] ^= 0xff;
+    assert!(matches!(
+        manifest.assemble(&parts),
+        Err(ObjectError::Corrupt(_))
+    ));
+
+    // other data is not mistaken for a manifest
+    assert!(ObjectManifest::consensus_deserialize(&mut chunks[0].data.as_slice()).is_err());
+}
+
+#[test]
+fn test_stackerdb_object_limits() {
+    // only half of the data slots hold each version of the object
+    assert!(matches!(
+        make_object_chunks(&[0u8; 801], 0, &[1, 2, 3, 4], &[], 400),
+        Err(ObjectError::TooLarge {
+            object_len: 801,
+            capacity: 800
+        })
+    ));
+    assert!(matches!(
+        make_object_chunks(&[0u8; 401], 0, &[1, 2, 3], &[], 400),
+        Err(ObjectError::TooLarge {
+            object_len: 401,
+            capacity: 400
+        })
+    ));
+    assert!(matches!(
+        make_object_chunks(&[0u8; 10], 1, &[1, 2], &[], 400),
+        Err(ObjectError::ManifestSlotReused(1))
+    ));
+
+    // an empty object is just a manifest
+    let chunks = make_object_chunks(&[], 0, &[1, 2], &[], 400).unwrap();
+    assert_eq!(chunks.len(), 1);
+    let manifest = ObjectManifest::consensus_deserialize(&mut chunks[0].data.as_slice()).unwrap();
+    assert_eq!(manifest.assemble(&[]).unwrap(), Vec::<u8>::new());
 }
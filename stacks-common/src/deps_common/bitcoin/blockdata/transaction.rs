@@ -34,7 +34,7 @@ use crate::deps_common::bitcoin::network::serialize::{
     self, serialize, BitcoinHash, SimpleDecoder, SimpleEncoder,
 };
 use crate::deps_common::bitcoin::util::hash::Sha256dHash;
-use crate::util::hash::to_hex;
+use crate::util::hash::{to_hex, Sha256Sum};
 
 /// A reference to a transaction output
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
@@ -447,6 +447,105 @@ impl Transaction {
         Sha256dHash::from_data(&raw_vec)
     }
 
+    /// BIP-341 sighash for a Taproot key-path spend of input `input_index`.
+    /// `prevouts` must contain the output spent by each of this transaction's inputs, in input
+    /// order, since the BIP-341 signature message commits to all of their amounts and
+    /// scriptPubKeys.  `hash_type` is the one-byte Taproot sighash type; 0x00 (SIGHASH_DEFAULT)
+    /// signs the same data as SIGHASH_ALL, but the resulting signature omits the type byte.
+    ///
+    /// Annexes and script-path spends are not supported.
+    ///
+    /// Returns None if `prevouts` does not match the inputs, if `hash_type` is not a valid
+    /// Taproot sighash type, or if SIGHASH_SINGLE is used without a corresponding output.
+    pub fn taproot_key_spend_signature_hash(
+        &self,
+        input_index: usize,
+        prevouts: &[TxOut],
+        hash_type: u8,
+    ) -> Option<Sha256Sum> {
+        if input_index >= self.input.len() || prevouts.len() != self.input.len() {
+            return None;
+        }
+        let (sighash, anyone_can_pay) = match hash_type {
+            0x00 | 0x01 => (SigHashType::All, false),
+            0x02 => (SigHashType::None, false),
+            0x03 => (SigHashType::Single, false),
+            0x81 => (SigHashType::All, true),
+            0x82 => (SigHashType::None, true),
+            0x83 => (SigHashType::Single, true),
+            _ => {
+                return None;
+            }
+        };
+        if sighash == SigHashType::Single && input_index >= self.output.len() {
+            return None;
+        }
+
+        // epoch
+        let mut raw_vec = vec![0x00];
+
+        // control
+        raw_vec.push(hash_type);
+
+        // transaction data
+        raw_vec.extend_from_slice(&self.version.to_le_bytes());
+        raw_vec.extend_from_slice(&self.lock_time.to_le_bytes());
+        if !anyone_can_pay {
+            let mut outpoints = vec![];
+            let mut amounts = vec![];
+            let mut script_pubkeys = vec![];
+            let mut sequences = vec![];
+            for (inp, prevout) in self.input.iter().zip(prevouts.iter()) {
+                let mut outpoint_bytes = serialize(&inp.previous_output)
+                    .expect("FATAL: failed to encode previous output");
+                outpoints.append(&mut outpoint_bytes);
+                amounts.extend_from_slice(&prevout.value.to_le_bytes());
+                let mut script_bytes = serialize(&prevout.script_pubkey)
+                    .expect("FATAL: failed to encode scriptPubKey");
+                script_pubkeys.append(&mut script_bytes);
+                sequences.extend_from_slice(&inp.sequence.to_le_bytes());
+            }
+            raw_vec.extend_from_slice(Sha256Sum::from_data(&outpoints).as_bytes());
+            raw_vec.extend_from_slice(Sha256Sum::from_data(&amounts).as_bytes());
+            raw_vec.extend_from_slice(Sha256Sum::from_data(&script_pubkeys).as_bytes());
+            raw_vec.extend_from_slice(Sha256Sum::from_data(&sequences).as_bytes());
+        }
+        if sighash == SigHashType::All {
+            let mut outputs = vec![];
+            for outp in self.output.iter() {
+                let mut output_bytes = serialize(outp).expect("FATAL: failed to encode output");
+                outputs.append(&mut output_bytes);
+            }
+            raw_vec.extend_from_slice(Sha256Sum::from_data(&outputs).as_bytes());
+        }
+
+        // data about this input: key-path spend, no annex
+        raw_vec.push(0x00);
+        if anyone_can_pay {
+            let inp = &self.input[input_index];
+            let prevout = &prevouts[input_index];
+            let mut outpoint_bytes =
+                serialize(&inp.previous_output).expect("FATAL: failed to encode previous output");
+            raw_vec.append(&mut outpoint_bytes);
+            raw_vec.extend_from_slice(&prevout.value.to_le_bytes());
+            let mut script_bytes =
+                serialize(&prevout.script_pubkey).expect("FATAL: failed to encode scriptPubKey");
+            raw_vec.append(&mut script_bytes);
+            raw_vec.extend_from_slice(&inp.sequence.to_le_bytes());
+        } else {
+            raw_vec.extend_from_slice(&(input_index as u32).to_le_bytes());
+        }
+
+        // data about this output
+        if sighash == SigHashType::Single {
+            let output_bytes =
+                serialize(&self.output[input_index]).expect("FATAL: failed to encode output");
+            raw_vec.extend_from_slice(Sha256Sum::from_data(&output_bytes).as_bytes());
+        }
+
+        Some(Sha256Sum::from_bip340_tagged_data("TapSighash", &raw_vec))
+    }
+
     /// Gets the "weight" of this transaction, as defined by BIP141. For transactions with an empty
     /// witness, this is simply the consensus-serialized size times 4. For transactions with a
     /// witness, this is the non-witness consensus-serialized size multiplied by 3 plus the
@@ -675,7 +774,7 @@ impl SigHashType {
 
 #[cfg(test)]
 mod tests {
-    use super::{SigHashType, Transaction, TxIn};
+    use super::{SigHashType, Transaction, TxIn, TxOut};
     use crate::deps_common;
     use crate::deps_common::bitcoin::blockdata::script::Script;
     use crate::deps_common::bitcoin::network::serialize::{deserialize, BitcoinHash};
@@ -1124,6 +1223,106 @@ mod tests {
         run_test_sighash("cf781855040a755f5ba85eef93837236b34a5d3daeb2dbbdcf58bb811828d806ed05754ab8010000000351ac53ffffffffda1e264727cf55c67f06ebcc56dfe7fa12ac2a994fecd0180ce09ee15c480f7d00000000096351516a51acac00ab53dd49ff9f334befd6d6f87f1a832cddfd826a90b78fd8cf19a52cb8287788af94e939d6020000000700525251ac526310d54a7e8900ed633f0f6f0841145aae7ee0cbbb1e2a0cae724ee4558dbabfdc58ba6855010000000552536a53abfd1b101102c51f910500000000096300656a525252656a300bee010000000009ac52005263635151abe19235c9", "53005365", 2, 1422854188, "d5981bd4467817c1330da72ddb8760d6c2556cd809264b2d85e6d274609fc3a3");
     }
 
+    #[test]
+    fn test_taproot_key_spend_signature_hash() {
+        let tx_hex = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000";
+        let tx: Transaction = deserialize(&hex_bytes(tx_hex).unwrap()[..]).unwrap();
+        let p2tr_script = Script::from(
+            hex_bytes("5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c")
+                .unwrap(),
+        );
+        let prevouts = vec![
+            TxOut {
+                value: 625000000,
+                script_pubkey: p2tr_script.clone(),
+            },
+            TxOut {
+                value: 600000000,
+                script_pubkey: p2tr_script.clone(),
+            },
+        ];
+
+        // SIGHASH_DEFAULT commits to the same data as SIGHASH_ALL, but the hash type differs
+        let default_sighash = tx
+            .taproot_key_spend_signature_hash(0, &prevouts, 0x00)
+            .unwrap();
+        let all_sighash = tx
+            .taproot_key_spend_signature_hash(0, &prevouts, 0x01)
+            .unwrap();
+        assert_ne!(default_sighash, all_sighash);
+
+        // each input gets its own sighash
+        assert_ne!(
+            default_sighash,
+            tx.taproot_key_spend_signature_hash(1, &prevouts, 0x00)
+                .unwrap()
+        );
+
+        // commits to the amounts of all inputs (unlike BIP-143)
+        let mut other_prevouts = prevouts.clone();
+        other_prevouts[1].value += 1;
+        assert_ne!(
+            default_sighash,
+            tx.taproot_key_spend_signature_hash(0, &other_prevouts, 0x00)
+                .unwrap()
+        );
+
+        // ...unless ANYONECANPAY is set
+        assert_eq!(
+            tx.taproot_key_spend_signature_hash(0, &prevouts, 0x81)
+                .unwrap(),
+            tx.taproot_key_spend_signature_hash(0, &other_prevouts, 0x81)
+                .unwrap()
+        );
+
+        // SIGHASH_NONE does not commit to the outputs, but SIGHASH_ALL and SIGHASH_SINGLE do
+        let mut other_tx = tx.clone();
+        other_tx.output[1].value += 1;
+        assert_eq!(
+            tx.taproot_key_spend_signature_hash(0, &prevouts, 0x02)
+                .unwrap(),
+            other_tx
+                .taproot_key_spend_signature_hash(0, &prevouts, 0x02)
+                .unwrap()
+        );
+        assert_ne!(
+            default_sighash,
+            other_tx
+                .taproot_key_spend_signature_hash(0, &prevouts, 0x00)
+                .unwrap()
+        );
+        assert_eq!(
+            tx.taproot_key_spend_signature_hash(0, &prevouts, 0x03)
+                .unwrap(),
+            other_tx
+                .taproot_key_spend_signature_hash(0, &prevouts, 0x03)
+                .unwrap()
+        );
+        assert_ne!(
+            tx.taproot_key_spend_signature_hash(1, &prevouts, 0x03)
+                .unwrap(),
+            other_tx
+                .taproot_key_spend_signature_hash(1, &prevouts, 0x03)
+                .unwrap()
+        );
+
+        // invalid arguments
+        assert!(tx
+            .taproot_key_spend_signature_hash(2, &prevouts, 0x00)
+            .is_none());
+        assert!(tx
+            .taproot_key_spend_signature_hash(0, &prevouts[0..1], 0x00)
+            .is_none());
+        assert!(tx
+            .taproot_key_spend_signature_hash(0, &prevouts, 0x04)
+            .is_none());
+        let mut single_output_tx = tx.clone();
+        single_output_tx.output.pop();
+        assert!(single_output_tx
+            .taproot_key_spend_signature_hash(1, &prevouts, 0x03)
+            .is_none());
+    }
+
     #[test]
     fn test_segwit_signature_hash() {
         // bip 143 test vector
@@ -33,6 +33,8 @@ pub mod deps_common;
 
 pub mod bitvec;
 
+pub mod proofs;
+
 use crate::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash, SortitionId, StacksBlockId};
 
 pub mod consts {
@@ -0,0 +1,1015 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of MARF proofs over Clarity state, for light clients.
+//!
+//! A node can prove that a Clarity state entry (an account balance or nonce, a data var, or a
+//! data map entry) has a particular value as of a given Stacks block.  The proof is a MARF
+//! Merkle proof from the entry's leaf up to the block's `state_index_root`.  If the entry was
+//! last written in an ancestor block, the proof also passes through the tries of one or more
+//! ancestors, and the verifier needs to know which trie root hash belongs to which block.  That
+//! binding comes from the ancestors' block headers, which hash to their block IDs and commit to
+//! their `state_index_root`s.
+//!
+//! A client that trusts a single block ID (e.g. the tip that a threshold of signers signed) can
+//! therefore check any `ClarityProofs` bundle served for that block without trusting the node
+//! that served it.  Note that the MARF cannot prove that an entry is absent.
+//!
+//! Nothing here depends on the chainstate, the Clarity VM, or the node's databases, so a light
+//! client can use this module with `default-features = false`.  The proof and header encodings
+//! decoded here are the ones produced by `TrieMerkleProof`, `StacksBlockHeader`, and
+//! `NakamotoBlockHeader` in `stackslib`, which checks the two against each other in its tests.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::{error, fmt};
+
+use sha2::{Digest, Sha512_256};
+
+use crate::codec::{read_next, write_next, Error as CodecError, StacksMessageCodec};
+use crate::consts::FIRST_STACKS_BLOCK_HASH;
+use crate::types::chainstate::{
+    BlockHeaderHash, ConsensusHash, StacksAddress, StacksBlockId, TrieHash,
+};
+use crate::types::Address;
+use crate::util::hash::{hex_bytes, to_hex};
+
+/// Length of a MARF trie path (the SHA512/256 hash of the MARF key)
+const TRIEPATH_MAX_LEN: usize = 32;
+/// Length of a value stored in a MARF leaf
+const MARF_VALUE_ENCODED_SIZE: usize = 40;
+/// Maximum length of a Clarity name (e.g. a data var or map name)
+const MAX_CLARITY_NAME_LEN: usize = 128;
+
+/// Trie node IDs, as in stackslib's `TrieNodeID`
+const TRIE_NODE_ID_EMPTY: u8 = 0;
+const TRIE_NODE_ID_LEAF: u8 = 1;
+
+/// Clarity `StoreType`s of the entries that can be proven
+const STORE_TYPE_DATA_MAP: u8 = 0x00;
+const STORE_TYPE_VARIABLE: u8 = 0x01;
+const STORE_TYPE_NONCE: u8 = 0x12;
+const STORE_TYPE_STX_BALANCE: u8 = 0x13;
+
+/// Length of a consensus-serialized epoch 2.x `StacksBlockHeader`
+const EPOCH2_HEADER_LEN: usize = 247;
+/// Byte range of `total_work.work` in an epoch 2.x header
+const EPOCH2_HEADER_WORK: (usize, usize) = (9, 17);
+/// Byte range of `state_index_root` in an epoch 2.x header
+const EPOCH2_HEADER_STATE_INDEX_ROOT: (usize, usize) = (195, 227);
+/// Byte range of `consensus_hash` in a Nakamoto header
+const NAKAMOTO_HEADER_CONSENSUS_HASH: (usize, usize) = (17, 37);
+/// Byte range of `state_index_root` in a Nakamoto header
+const NAKAMOTO_HEADER_STATE_INDEX_ROOT: (usize, usize) = (101, 133);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The requested key could not be turned into a MARF key
+    InvalidKey(String),
+    /// An anchor header could not be decoded, or does not match its claimed block ID
+    InvalidHeader(String),
+    /// A proof could not be decoded, or does not verify
+    InvalidProof(String),
+    /// The tip header is not the block the client trusts
+    UntrustedTip(StacksBlockId),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidKey(s) => write!(f, "Invalid key: {}", s),
+            Error::InvalidHeader(s) => write!(f, "Invalid anchor header: {}", s),
+            Error::InvalidProof(s) => write!(f, "Invalid proof: {}", s),
+            Error::UntrustedTip(id) => write!(f, "Untrusted tip: {}", id),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/// A provable piece of Clarity state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClarityProofKey {
+    /// The STX balance of an account
+    AccountBalance { principal: String },
+    /// The nonce of an account
+    AccountNonce { principal: String },
+    /// A contract's data var
+    DataVar { contract: String, var: String },
+    /// A contract's data map entry.  `key` is the hex-encoded serialized Clarity key value.
+    MapEntry {
+        contract: String,
+        map: String,
+        key: String,
+    },
+}
+
+/// Is `name` a valid contract name?  Mirrors Clarity's `ContractName`.
+fn is_contract_name(name: &str) -> bool {
+    if name == "__transient" {
+        return true;
+    }
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    name.len() <= MAX_CLARITY_NAME_LEN
+        && first.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Is `name` a valid Clarity name?  Mirrors Clarity's `ClarityName`.
+fn is_clarity_name(name: &str) -> bool {
+    if matches!(name, "-" | "+" | "=" | "/" | "*" | "<" | ">" | "<=" | ">=") {
+        return true;
+    }
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    name.len() <= MAX_CLARITY_NAME_LEN
+        && first.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || "-_!?+<>=/*".contains(c))
+}
+
+/// Parse a standard principal, and return it in its canonical c32 form
+fn parse_standard_principal(principal: &str) -> Result<String, Error> {
+    StacksAddress::from_string(principal)
+        .map(|addr| addr.to_string())
+        .ok_or_else(|| Error::InvalidKey(format!("invalid principal: {}", principal)))
+}
+
+/// Parse a contract identifier, and return it in its canonical form
+fn parse_contract(contract: &str) -> Result<String, Error> {
+    let Some((issuer, name)) = contract.split_once('.') else {
+        return Err(Error::InvalidKey(format!("invalid contract: {}", contract)));
+    };
+    if !is_contract_name(name) {
+        return Err(Error::InvalidKey(format!(
+            "invalid contract name: {}",
+            name
+        )));
+    }
+    Ok(format!("{}.{}", parse_standard_principal(issuer)?, name))
+}
+
+/// Parse a standard or contract principal, and return it in its canonical form
+fn parse_principal(principal: &str) -> Result<String, Error> {
+    // be permissive about leading single-quote, as Clarity is
+    let principal = principal.strip_prefix('\'').unwrap_or(principal);
+    if principal.contains('.') {
+        parse_contract(principal)
+    } else {
+        parse_standard_principal(principal)
+    }
+}
+
+impl ClarityProofKey {
+    /// Compute the MARF key under which this entry is stored
+    pub fn to_marf_key(&self) -> Result<String, Error> {
+        match self {
+            ClarityProofKey::AccountBalance { principal } => Ok(format!(
+                "vm-account::{}::{}",
+                parse_principal(principal)?,
+                STORE_TYPE_STX_BALANCE
+            )),
+            ClarityProofKey::AccountNonce { principal } => Ok(format!(
+                "vm-account::{}::{}",
+                parse_principal(principal)?,
+                STORE_TYPE_NONCE
+            )),
+            ClarityProofKey::DataVar { contract, var } => {
+                if !is_clarity_name(var) {
+                    return Err(Error::InvalidKey(format!("invalid var name: {}", var)));
+                }
+                Ok(format!(
+                    "vm::{}::{}::{}",
+                    parse_contract(contract)?,
+                    STORE_TYPE_VARIABLE,
+                    var
+                ))
+            }
+            ClarityProofKey::MapEntry { contract, map, key } => {
+                if !is_clarity_name(map) {
+                    return Err(Error::InvalidKey(format!("invalid map name: {}", map)));
+                }
+                let key_bytes = hex_bytes(key.trim_start_matches("0x"))
+                    .map_err(|e| Error::InvalidKey(format!("invalid map key: {:?}", &e)))?;
+                if key_bytes.is_empty() {
+                    return Err(Error::InvalidKey("empty map key".into()));
+                }
+                Ok(format!(
+                    "vm::{}::{}::{}::{}",
+                    parse_contract(contract)?,
+                    STORE_TYPE_DATA_MAP,
+                    map,
+                    to_hex(&key_bytes)
+                ))
+            }
+        }
+    }
+}
+
+/// A Stacks block header, as needed to bind a MARF trie root hash to a block ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofAnchorHeader {
+    /// The block ID this header claims to have
+    pub index_block_hash: StacksBlockId,
+    /// The consensus hash of the block's tenure (needed to compute epoch 2.x block IDs)
+    pub consensus_hash: ConsensusHash,
+    /// Whether or not this is a Nakamoto block header
+    pub nakamoto: bool,
+    /// The hex-encoded consensus serialization of the header
+    pub header: String,
+}
+
+/// Copy the byte range `range` out of `bytes`, which the caller has checked is long enough
+fn header_field<const N: usize>(bytes: &[u8], range: (usize, usize)) -> [u8; N] {
+    let mut field = [0u8; N];
+    field.copy_from_slice(&bytes[range.0..range.1]);
+    field
+}
+
+impl ProofAnchorHeader {
+    /// Decode the header, check that it hashes to `index_block_hash`, and return the
+    /// `state_index_root` it commits to.
+    pub fn verify(&self) -> Result<TrieHash, Error> {
+        let bytes = hex_bytes(&self.header)
+            .map_err(|e| Error::InvalidHeader(format!("invalid hex: {:?}", &e)))?;
+        let (block_id, state_index_root) = if self.nakamoto {
+            // the rest of the header is variable-length, but the block hash covers all of it
+            if bytes.len() < NAKAMOTO_HEADER_STATE_INDEX_ROOT.1 {
+                return Err(Error::InvalidHeader(format!(
+                    "Nakamoto header is too short ({} bytes)",
+                    bytes.len()
+                )));
+            }
+            let consensus_hash =
+                ConsensusHash(header_field(&bytes, NAKAMOTO_HEADER_CONSENSUS_HASH));
+            let block_hash = BlockHeaderHash::from_serialized_header(&bytes);
+            (
+                StacksBlockId::new(&consensus_hash, &block_hash),
+                TrieHash(header_field(&bytes, NAKAMOTO_HEADER_STATE_INDEX_ROOT)),
+            )
+        } else {
+            if bytes.len() != EPOCH2_HEADER_LEN {
+                return Err(Error::InvalidHeader(format!(
+                    "epoch 2.x header must be {} bytes, got {}",
+                    EPOCH2_HEADER_LEN,
+                    bytes.len()
+                )));
+            }
+            let work = u64::from_be_bytes(header_field(&bytes, EPOCH2_HEADER_WORK));
+            let block_hash = if work == 0 {
+                // this is the boot block
+                FIRST_STACKS_BLOCK_HASH
+            } else {
+                BlockHeaderHash::from_serialized_header(&bytes)
+            };
+            (
+                StacksBlockId::new(&self.consensus_hash, &block_hash),
+                TrieHash(header_field(&bytes, EPOCH2_HEADER_STATE_INDEX_ROOT)),
+            )
+        };
+        if block_id != self.index_block_hash {
+            return Err(Error::InvalidHeader(format!(
+                "header hashes to {}, not {}",
+                &block_id, &self.index_block_hash
+            )));
+        }
+        Ok(state_index_root)
+    }
+}
+
+/// A proven Clarity state entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClarityProofEntry {
+    pub key: ClarityProofKey,
+    /// The MARF key the entry is stored under
+    pub marf_key: String,
+    /// The stored value (e.g. a hex-encoded serialized Clarity value), or `None` if the entry
+    /// does not exist.
+    pub value: Option<String>,
+    /// The hex-encoded MARF proof, or `None` if the entry does not exist.
+    pub proof: Option<String>,
+}
+
+/// A set of proven Clarity state entries, together with the block headers needed to verify them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClarityProofs {
+    /// Header of the block at which the entries were read
+    pub tip: ProofAnchorHeader,
+    /// Headers of the ancestor blocks whose tries the proofs pass through
+    pub anchors: Vec<ProofAnchorHeader>,
+    pub entries: Vec<ClarityProofEntry>,
+}
+
+impl ClarityProofs {
+    /// Verify every entry against the block the client trusts.  Returns each entry's key and
+    /// value, in order.  Entries reported as absent are returned with a `None` value; they are
+    /// not (and cannot be) proven absent.
+    pub fn verify(
+        &self,
+        trusted_tip: &StacksBlockId,
+    ) -> Result<Vec<(ClarityProofKey, Option<String>)>, Error> {
+        if &self.tip.index_block_hash != trusted_tip {
+            return Err(Error::UntrustedTip(self.tip.index_block_hash));
+        }
+        let state_index_root = self.tip.verify()?;
+
+        let mut root_to_block = HashMap::new();
+        for anchor in self.anchors.iter() {
+            let anchor_root = anchor.verify()?;
+            root_to_block.insert(anchor_root, anchor.index_block_hash);
+        }
+
+        let mut verified = Vec::with_capacity(self.entries.len());
+        for entry in self.entries.iter() {
+            let marf_key = entry.key.to_marf_key()?;
+            if marf_key != entry.marf_key {
+                return Err(Error::InvalidKey(format!(
+                    "entry claims MARF key {}, but its key maps to {}",
+                    &entry.marf_key, &marf_key
+                )));
+            }
+            match (entry.value.as_ref(), entry.proof.as_ref()) {
+                (Some(value), Some(proof_hex)) => {
+                    let proof_bytes = hex_bytes(proof_hex.trim_start_matches("0x"))
+                        .map_err(|e| Error::InvalidProof(format!("invalid hex: {:?}", &e)))?;
+                    verify_marf_proof(
+                        &marf_key,
+                        value,
+                        &proof_bytes,
+                        &state_index_root,
+                        &root_to_block,
+                    )?;
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(Error::InvalidProof(format!(
+                        "entry for {} must have both a value and a proof, or neither",
+                        &marf_key
+                    )));
+                }
+            }
+            verified.push((entry.key.clone(), entry.value.clone()));
+        }
+        Ok(verified)
+    }
+}
+
+/// Verify that `proof_bytes` proves that `marf_key` maps to `value` in the trie with root hash
+/// `state_index_root`.  `root_to_block` maps the root hashes of the ancestor tries the proof
+/// passes through to their block IDs.
+pub fn verify_marf_proof(
+    marf_key: &str,
+    value: &str,
+    proof_bytes: &[u8],
+    state_index_root: &TrieHash,
+    root_to_block: &HashMap<TrieHash, StacksBlockId>,
+) -> Result<(), Error> {
+    let proof = MarfProof::consensus_deserialize(&mut &proof_bytes[..])
+        .map_err(|e| Error::InvalidProof(format!("{:?}", &e)))?;
+    let path = hash_data(&[marf_key.as_bytes()]);
+    let mut marf_value = [0u8; MARF_VALUE_ENCODED_SIZE];
+    marf_value[..32].copy_from_slice(&hash_data(&[value.as_bytes()]).0);
+    if !proof.verify(&path.0, &marf_value, state_index_root, root_to_block) {
+        return Err(Error::InvalidProof(format!(
+            "proof for {} does not verify against {}",
+            marf_key, state_index_root
+        )));
+    }
+    Ok(())
+}
+
+/// SHA512/256 of the concatenation of `data`, which is how the MARF hashes everything
+fn hash_data<B: AsRef<[u8]>>(data: &[B]) -> TrieHash {
+    let mut hasher = Sha512_256::new();
+    for d in data.iter() {
+        hasher.update(d.as_ref());
+    }
+    let mut ret = [0u8; 32];
+    ret.copy_from_slice(hasher.finalize().as_slice());
+    TrieHash(ret)
+}
+
+/// Read a trie path, which must be no longer than a full trie path
+fn read_path<R: Read>(fd: &mut R) -> Result<Vec<u8>, CodecError> {
+    let path: Vec<u8> = read_next(fd)?;
+    if path.len() > TRIEPATH_MAX_LEN {
+        return Err(CodecError::DeserializeError(format!(
+            "Node path is longer than {} bytes (got {})",
+            TRIEPATH_MAX_LEN,
+            path.len()
+        )));
+    }
+    Ok(path)
+}
+
+/// A child pointer of a trie node in a MARF proof.  Back-pointers name the block whose trie they
+/// point into.
+#[derive(Debug, Clone, PartialEq)]
+struct MarfProofPtr {
+    id: u8,
+    chr: u8,
+    back_block: StacksBlockId,
+}
+
+/// A non-leaf trie node in a MARF proof
+#[derive(Debug, Clone, PartialEq)]
+struct MarfProofTrieNode {
+    id: u8,
+    path: Vec<u8>,
+    ptrs: Vec<MarfProofPtr>,
+}
+
+impl MarfProofTrieNode {
+    /// The node's hash, given its children's hashes
+    fn hash(&self, child_hashes: &[TrieHash]) -> TrieHash {
+        let mut node_bytes = vec![self.id];
+        for ptr in self.ptrs.iter() {
+            node_bytes.extend_from_slice(&[ptr.id, ptr.chr]);
+            node_bytes.extend_from_slice(ptr.back_block.as_bytes());
+        }
+        node_bytes.push(self.path.len() as u8);
+        node_bytes.extend_from_slice(&self.path);
+
+        let mut hasher = Sha512_256::new();
+        hasher.update(&node_bytes);
+        for child_hash in child_hashes.iter() {
+            hasher.update(child_hash.as_bytes());
+        }
+        let mut ret = [0u8; 32];
+        ret.copy_from_slice(hasher.finalize().as_slice());
+        TrieHash(ret)
+    }
+}
+
+/// An item in a MARF proof
+#[derive(Debug, Clone, PartialEq)]
+enum MarfProofItem {
+    /// A Node4, Node16, Node48, or Node256, with the path character the proof descends through,
+    /// and the hashes of all of its children except that one
+    Node(u8, MarfProofTrieNode, Vec<TrieHash>),
+    /// The leaf holding the proven value
+    Leaf(u8, Vec<u8>, [u8; MARF_VALUE_ENCODED_SIZE]),
+    /// A step through the ancestor hashes of a trie root
+    Shunt(i64, Vec<TrieHash>),
+}
+
+/// Type bytes of the proof items, as in stackslib's `TrieMerkleProofTypeIndicator`
+const PROOF_TYPE_NODE4: u8 = 0;
+const PROOF_TYPE_NODE16: u8 = 1;
+const PROOF_TYPE_NODE48: u8 = 2;
+const PROOF_TYPE_NODE256: u8 = 3;
+const PROOF_TYPE_LEAF: u8 = 4;
+const PROOF_TYPE_SHUNT: u8 = 5;
+
+/// Number of children of each type of node
+fn node_size(type_byte: u8) -> Option<usize> {
+    match type_byte {
+        PROOF_TYPE_NODE4 => Some(4),
+        PROOF_TYPE_NODE16 => Some(16),
+        PROOF_TYPE_NODE48 => Some(48),
+        PROOF_TYPE_NODE256 => Some(256),
+        _ => None,
+    }
+}
+
+impl StacksMessageCodec for MarfProofPtr {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        write_next(fd, &self.id)?;
+        write_next(fd, &self.chr)?;
+        write_next(fd, &self.back_block)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<MarfProofPtr, CodecError> {
+        Ok(MarfProofPtr {
+            id: read_next(fd)?,
+            chr: read_next(fd)?,
+            back_block: read_next(fd)?,
+        })
+    }
+}
+
+impl StacksMessageCodec for MarfProofTrieNode {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        write_next(fd, &self.id)?;
+        write_next(fd, &self.path)?;
+        write_next(fd, &self.ptrs)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<MarfProofTrieNode, CodecError> {
+        Ok(MarfProofTrieNode {
+            id: read_next(fd)?,
+            path: read_path(fd)?,
+            ptrs: read_next(fd)?,
+        })
+    }
+}
+
+impl StacksMessageCodec for MarfProofItem {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        match self {
+            MarfProofItem::Node(chr, node, hashes) => {
+                let type_byte = match hashes.len() + 1 {
+                    4 => PROOF_TYPE_NODE4,
+                    16 => PROOF_TYPE_NODE16,
+                    48 => PROOF_TYPE_NODE48,
+                    256 => PROOF_TYPE_NODE256,
+                    _ => {
+                        return Err(CodecError::SerializeError(
+                            "Bad number of hashes in proof node".into(),
+                        ))
+                    }
+                };
+                write_next(fd, &type_byte)?;
+                write_next(fd, chr)?;
+                write_next(fd, node)?;
+                for hash in hashes.iter() {
+                    write_next(fd, hash)?;
+                }
+                Ok(())
+            }
+            MarfProofItem::Leaf(chr, path, value) => {
+                write_next(fd, &PROOF_TYPE_LEAF)?;
+                write_next(fd, chr)?;
+                write_next(fd, path)?;
+                fd.write_all(value).map_err(CodecError::WriteError)
+            }
+            MarfProofItem::Shunt(idx, hashes) => {
+                write_next(fd, &PROOF_TYPE_SHUNT)?;
+                write_next(fd, idx)?;
+                write_next(fd, hashes)
+            }
+        }
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<MarfProofItem, CodecError> {
+        let type_byte: u8 = read_next(fd)?;
+        match type_byte {
+            PROOF_TYPE_LEAF => {
+                let chr = read_next(fd)?;
+                let path = read_path(fd)?;
+                let mut value = [0u8; MARF_VALUE_ENCODED_SIZE];
+                fd.read_exact(&mut value).map_err(CodecError::ReadError)?;
+                Ok(MarfProofItem::Leaf(chr, path, value))
+            }
+            PROOF_TYPE_SHUNT => {
+                let idx = read_next(fd)?;
+                let hashes = read_next(fd)?;
+                Ok(MarfProofItem::Shunt(idx, hashes))
+            }
+            _ => {
+                let size = node_size(type_byte).ok_or_else(|| {
+                    CodecError::DeserializeError("Bad type byte in Trie Merkle Proof".into())
+                })?;
+                let chr = read_next(fd)?;
+                let node = read_next(fd)?;
+                let mut hashes = Vec::with_capacity(size - 1);
+                for _ in 0..size - 1 {
+                    hashes.push(read_next(fd)?);
+                }
+                Ok(MarfProofItem::Node(chr, node, hashes))
+            }
+        }
+    }
+}
+
+/// A MARF Merkle proof, as encoded by stackslib's `TrieMerkleProof`.  It is a list of segment
+/// proofs (a path of trie nodes within one block's trie), each followed by a shunt proof (the
+/// path from that trie's root through its ancestor hashes).
+#[derive(Debug, Clone, PartialEq)]
+struct MarfProof(Vec<MarfProofItem>);
+
+impl StacksMessageCodec for MarfProof {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        write_next(fd, &self.0)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<MarfProof, CodecError> {
+        Ok(MarfProof(read_next(fd)?))
+    }
+}
+
+impl MarfProof {
+    /// Find the end of the run of items starting at `i` that are (or are not) shunts
+    fn end_of_run(&self, i: usize, shunts: bool) -> usize {
+        let mut j = i + 1;
+        while j < self.0.len() && matches!(self.0[j], MarfProofItem::Shunt(..)) == shunts {
+            j += 1;
+        }
+        j
+    }
+
+    /// Given a segment proof, extract the path prefix it encodes
+    fn segment_path_prefix(segment: &[MarfProofItem]) -> Option<Vec<u8>> {
+        let mut path_parts = vec![];
+        for item in segment.iter() {
+            match item {
+                MarfProofItem::Leaf(_, path, _) => path_parts.push(path.clone()),
+                MarfProofItem::Node(chr, node, _) => {
+                    path_parts.push(vec![*chr]);
+                    path_parts.push(node.path.clone());
+                }
+                MarfProofItem::Shunt(..) => return None,
+            }
+        }
+        Some(path_parts.into_iter().rev().flatten().collect())
+    }
+
+    /// Check that the proof is well-formed: it starts with a leaf for `expected_path`, it
+    /// alternates between segment and shunt proofs, and every later segment proof's path is a
+    /// prefix of `expected_path`.
+    fn is_well_formed(&self, expected_path: &[u8]) -> bool {
+        if !matches!(self.0.first(), Some(MarfProofItem::Leaf(..))) {
+            return false;
+        }
+        let mut i = 0;
+        while i < self.0.len() {
+            let j = self.end_of_run(i, false);
+            let Some(segment_path) = MarfProof::segment_path_prefix(&self.0[i..j]) else {
+                return false;
+            };
+            if i == 0 && segment_path != expected_path {
+                trace!(
+                    "Invalid proof -- path bytes {:?} differ from the expected path {:?}",
+                    &segment_path,
+                    expected_path
+                );
+                return false;
+            }
+            if !expected_path.starts_with(&segment_path) {
+                return false;
+            }
+
+            // must be followed by a shunt proof
+            if j >= self.0.len() {
+                return false;
+            }
+            i = self.end_of_run(j, true);
+        }
+        true
+    }
+
+    /// Calculate the root hash of a segment proof, given the hash of its deepest node
+    fn segment_root_hash(segment: &[MarfProofItem], node_hash: &TrieHash) -> Option<TrieHash> {
+        let mut hash = *node_hash;
+        for item in segment.iter() {
+            hash = match item {
+                MarfProofItem::Leaf(_, path, value) => {
+                    hash_data(&[&[TRIE_NODE_ID_LEAF, path.len() as u8][..], path, value])
+                }
+                MarfProofItem::Node(chr, node, hashes) => {
+                    if node.ptrs.len() != hashes.len() + 1 {
+                        return None;
+                    }
+                    let mut child_hashes = Vec::with_capacity(node.ptrs.len());
+                    let mut other_hashes = hashes.iter();
+                    for ptr in node.ptrs.iter() {
+                        if ptr.id != TRIE_NODE_ID_EMPTY && ptr.chr == *chr {
+                            child_hashes.push(hash);
+                        } else {
+                            child_hashes.push(*other_hashes.next()?);
+                        }
+                    }
+                    node.hash(&child_hashes)
+                }
+                MarfProofItem::Shunt(..) => return None,
+            };
+        }
+        Some(hash)
+    }
+
+    /// Hash `hash` into the list of ancestor hashes `hashes` at position `idx` (1-indexed).  At a
+    /// junction with the next segment proof, `first` is that segment's root hash, and goes first.
+    fn shunt_hash(
+        first: Option<&TrieHash>,
+        hash: &TrieHash,
+        idx: i64,
+        hashes: &[TrieHash],
+    ) -> Option<TrieHash> {
+        if idx <= 0 || idx as usize > hashes.len() + 1 {
+            return None;
+        }
+        let mut all_hashes = Vec::with_capacity(hashes.len() + 2);
+        all_hashes.extend(first.copied());
+        all_hashes.extend_from_slice(&hashes[..idx as usize - 1]);
+        all_hashes.push(*hash);
+        all_hashes.extend_from_slice(&hashes[idx as usize - 1..]);
+        Some(hash_data(&all_hashes))
+    }
+
+    /// Verify the head of the first shunt proof: the trie root hash with its ancestor hashes
+    fn shunt_head_hash(node_root_hash: &TrieHash, item: &MarfProofItem) -> Option<TrieHash> {
+        match item {
+            MarfProofItem::Shunt(0, hashes) if hashes.is_empty() => {
+                // a trie with no ancestors (i.e. the first block's)
+                Some(*node_root_hash)
+            }
+            MarfProofItem::Shunt(0, hashes) => {
+                let mut all_hashes = Vec::with_capacity(hashes.len() + 1);
+                all_hashes.push(*node_root_hash);
+                all_hashes.extend_from_slice(hashes);
+                Some(hash_data(&all_hashes))
+            }
+            _ => None,
+        }
+    }
+
+    /// Look up the block ID whose trie has the root hash `trie_hash`.  The block ID is the
+    /// "leaf" hash of the next segment proof.
+    fn block_hash_for_root(
+        trie_hash: &TrieHash,
+        root_to_block: &HashMap<TrieHash, StacksBlockId>,
+    ) -> Option<TrieHash> {
+        let block_id = root_to_block.get(trie_hash);
+        if block_id.is_none() {
+            trace!("Trie hash not found in root-to-block map: {:?}", trie_hash);
+        }
+        block_id.map(|block_id| TrieHash(block_id.0))
+    }
+
+    /// Verify that this proof shows that the leaf at `path` holds `value` in the trie with root
+    /// hash `root_hash`.  This follows `TrieMerkleProof::verify_proof()` in stackslib, except that
+    /// `root_to_block` does not need to map `root_hash` itself, since the tip's block ID is never
+    /// hashed into the proof.
+    fn verify(
+        &self,
+        path: &[u8],
+        value: &[u8; MARF_VALUE_ENCODED_SIZE],
+        root_hash: &TrieHash,
+        root_to_block: &HashMap<TrieHash, StacksBlockId>,
+    ) -> bool {
+        if !self.is_well_formed(path) {
+            trace!("Invalid proof -- proof is not well-formed");
+            return false;
+        }
+        let proof = &self.0;
+
+        // proof must be for this value
+        match proof[0] {
+            MarfProofItem::Leaf(_, _, ref leaf_value) if leaf_value == value => {}
+            _ => return false,
+        }
+
+        // the first segment proof and the head of its shunt proof lead to the root hash of the
+        // entry's trie
+        let j = self.end_of_run(0, false);
+        let Some(node_root_hash) = MarfProof::segment_root_hash(&proof[0..j], &TrieHash([0u8; 32]))
+        else {
+            return false;
+        };
+        let Some(mut trie_hash) = MarfProof::shunt_head_hash(&node_root_hash, &proof[j]) else {
+            return false;
+        };
+
+        let mut i = j + 1;
+        if i < proof.len() && matches!(proof[i], MarfProofItem::Shunt(..)) {
+            // a shunt proof head must be followed by a segment proof
+            return false;
+        }
+        while i < proof.len() {
+            // the next segment proof starts at the block whose trie we just left
+            let Some(node_hash) = MarfProof::block_hash_for_root(&trie_hash, root_to_block) else {
+                return false;
+            };
+            let j = self.end_of_run(i, false);
+            let Some(next_node_root_hash) = MarfProof::segment_root_hash(&proof[i..j], &node_hash)
+            else {
+                return false;
+            };
+
+            // the shunt proof tail walks through the ancestor hashes, up to the junction with
+            // the next segment proof
+            i = j;
+            let mut end = i;
+            while end < proof.len()
+                && matches!(proof[end], MarfProofItem::Shunt(idx, _) if idx != 0)
+            {
+                end += 1;
+            }
+            if end == i {
+                return false;
+            }
+            let junction = end - 1;
+            for item in proof[i..junction].iter() {
+                let MarfProofItem::Shunt(idx, ref hashes) = *item else {
+                    return false;
+                };
+                let Some(h) = MarfProof::shunt_hash(None, &trie_hash, idx, hashes) else {
+                    return false;
+                };
+                trie_hash = h;
+            }
+            let MarfProofItem::Shunt(idx, ref hashes) = proof[junction] else {
+                return false;
+            };
+            let Some(h) =
+                MarfProof::shunt_hash(Some(&next_node_root_hash), &trie_hash, idx, hashes)
+            else {
+                return false;
+            };
+            trie_hash = h;
+            i = junction + 1;
+
+            if &trie_hash == root_hash {
+                break;
+            }
+        }
+
+        &trie_hash == root_hash
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ADDR: &str = "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R";
+
+    #[test]
+    fn test_marf_keys() {
+        let contract = format!("{}.hello-world", ADDR);
+        assert_eq!(
+            ClarityProofKey::AccountBalance {
+                principal: ADDR.into()
+            }
+            .to_marf_key()
+            .unwrap(),
+            format!("vm-account::{}::19", ADDR)
+        );
+        assert_eq!(
+            ClarityProofKey::AccountNonce {
+                principal: contract.clone()
+            }
+            .to_marf_key()
+            .unwrap(),
+            format!("vm-account::{}::18", &contract)
+        );
+        assert_eq!(
+            ClarityProofKey::DataVar {
+                contract: contract.clone(),
+                var: "bar".into()
+            }
+            .to_marf_key()
+            .unwrap(),
+            format!("vm::{}::1::bar", &contract)
+        );
+        assert_eq!(
+            ClarityProofKey::MapEntry {
+                contract: contract.clone(),
+                map: "infos".into(),
+                key: "0x0100000000000000000000000000000001".into()
+            }
+            .to_marf_key()
+            .unwrap(),
+            format!(
+                "vm::{}::0::infos::0100000000000000000000000000000001",
+                &contract
+            )
+        );
+
+        // keys that could alias other entries are rejected
+        let bad_keys = [
+            ClarityProofKey::AccountBalance {
+                principal: "not-a-principal".into(),
+            },
+            ClarityProofKey::AccountBalance {
+                principal: format!("{}.bad::name", ADDR),
+            },
+            ClarityProofKey::DataVar {
+                contract: contract.clone(),
+                var: "bar::baz".into(),
+            },
+            ClarityProofKey::DataVar {
+                contract: ADDR.into(),
+                var: "bar".into(),
+            },
+            ClarityProofKey::MapEntry {
+                contract: contract.clone(),
+                map: "infos".into(),
+                key: "not-hex".into(),
+            },
+        ];
+        for key in bad_keys.iter() {
+            assert!(
+                matches!(key.to_marf_key(), Err(Error::InvalidKey(_))),
+                "{:?}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_epoch2_header() {
+        let mut header_bytes = vec![0u8; EPOCH2_HEADER_LEN];
+        header_bytes[EPOCH2_HEADER_WORK.1 - 1] = 1;
+        header_bytes[EPOCH2_HEADER_STATE_INDEX_ROOT.0..EPOCH2_HEADER_STATE_INDEX_ROOT.1]
+            .copy_from_slice(&[0x11; 32]);
+        let consensus_hash = ConsensusHash([0x22; 20]);
+        let block_id = StacksBlockId::new(
+            &consensus_hash,
+            &BlockHeaderHash::from_serialized_header(&header_bytes),
+        );
+
+        let anchor = ProofAnchorHeader {
+            index_block_hash: block_id,
+            consensus_hash,
+            nakamoto: false,
+            header: to_hex(&header_bytes),
+        };
+        assert_eq!(anchor.verify().unwrap(), TrieHash([0x11; 32]));
+
+        // the header must hash to the claimed block ID
+        let mut tampered = anchor.clone();
+        tampered.consensus_hash = ConsensusHash([0x33; 20]);
+        assert!(matches!(tampered.verify(), Err(Error::InvalidHeader(_))));
+
+        let mut truncated = anchor.clone();
+        truncated.header = to_hex(&header_bytes[..EPOCH2_HEADER_LEN - 1]);
+        assert!(matches!(truncated.verify(), Err(Error::InvalidHeader(_))));
+    }
+
+    #[test]
+    fn test_verify_single_trie_proof() {
+        // a trie whose root has one child: the leaf for the key
+        let key = "vm::SP000000000000000000002Q6VF78.foo::1::bar";
+        let value = "0100000000000000000000000000000001";
+        let path = hash_data(&[key.as_bytes()]).0;
+        let mut leaf_value = [0u8; MARF_VALUE_ENCODED_SIZE];
+        leaf_value[..32].copy_from_slice(&hash_data(&[value.as_bytes()]).0);
+
+        let ptrs: Vec<_> = (0..=255u8)
+            .map(|chr| MarfProofPtr {
+                id: if chr == path[0] {
+                    TRIE_NODE_ID_LEAF
+                } else {
+                    TRIE_NODE_ID_EMPTY
+                },
+                chr,
+                back_block: StacksBlockId([0u8; 32]),
+            })
+            .collect();
+        let root = MarfProofTrieNode {
+            id: 5,
+            path: vec![],
+            ptrs,
+        };
+        let leaf_hash = hash_data(&[&[TRIE_NODE_ID_LEAF, 31][..], &path[1..], &leaf_value]);
+        let mut child_hashes = vec![TrieHash([0u8; 32]); 256];
+        child_hashes[path[0] as usize] = leaf_hash;
+        let root_hash = root.hash(&child_hashes);
+
+        let proof = MarfProof(vec![
+            MarfProofItem::Leaf(path[31], path[1..].to_vec(), leaf_value),
+            MarfProofItem::Node(path[0], root, vec![TrieHash([0u8; 32]); 255]),
+            MarfProofItem::Shunt(0, vec![]),
+        ]);
+        let proof_bytes = proof.serialize_to_vec();
+        let root_to_block = HashMap::new();
+
+        verify_marf_proof(key, value, &proof_bytes, &root_hash, &root_to_block).unwrap();
+
+        // wrong value, wrong key, wrong root, and corrupt proofs do not verify
+        assert!(verify_marf_proof(key, "00", &proof_bytes, &root_hash, &root_to_block).is_err());
+        assert!(verify_marf_proof("foo", value, &proof_bytes, &root_hash, &root_to_block).is_err());
+        assert!(verify_marf_proof(
+            key,
+            value,
+            &proof_bytes,
+            &TrieHash([0u8; 32]),
+            &root_to_block
+        )
+        .is_err());
+        assert!(verify_marf_proof(
+            key,
+            value,
+            &proof_bytes[..proof_bytes.len() - 1],
+            &root_hash,
+            &root_to_block
+        )
+        .is_err());
+
+        let mut flipped = proof_bytes.clone();
+        flipped[10] ^= 0x01;
+        assert!(verify_marf_proof(key, value, &flipped, &root_hash, &root_to_block).is_err());
+    }
+}
@@ -309,6 +309,15 @@ impl Sha256Sum {
     pub fn from_data(data: &[u8]) -> Sha256Sum {
         Sha256Sum(Sha256::digest(data).into())
     }
+    /// BIP-340 tagged hash: sha256(sha256(tag) || sha256(tag) || data)
+    pub fn from_bip340_tagged_data(tag: &str, data: &[u8]) -> Sha256Sum {
+        let tag_hash = Sha256::digest(tag.as_bytes());
+        let mut sha2 = Sha256::new();
+        sha2.update(tag_hash);
+        sha2.update(tag_hash);
+        sha2.update(data);
+        Sha256Sum(sha2.finalize().into())
+    }
     pub fn zero() -> Sha256Sum {
         Sha256Sum([0u8; 32])
     }
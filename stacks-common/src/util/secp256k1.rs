@@ -14,14 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 use rand::{thread_rng, RngCore};
-use secp256k1;
 use secp256k1::ecdsa::{
     RecoverableSignature as LibSecp256k1RecoverableSignature, RecoveryId as LibSecp256k1RecoveryID,
     Signature as LibSecp256k1Signature,
 };
+use secp256k1::schnorr::Signature as LibSecp256k1SchnorrSignature;
 use secp256k1::{
-    constants as LibSecp256k1Constants, Error as LibSecp256k1Error, Message as LibSecp256k1Message,
-    PublicKey as LibSecp256k1PublicKey, Secp256k1, SecretKey as LibSecp256k1PrivateKey,
+    self, constants as LibSecp256k1Constants, Error as LibSecp256k1Error,
+    KeyPair as LibSecp256k1KeyPair, Message as LibSecp256k1Message,
+    PublicKey as LibSecp256k1PublicKey, Scalar as LibSecp256k1Scalar, Secp256k1,
+    SecretKey as LibSecp256k1PrivateKey, XOnlyPublicKey as LibSecp256k1XOnlyPublicKey,
 };
 use serde::de::{Deserialize, Error as de_Error};
 use serde::ser::Error as ser_Error;
@@ -195,6 +197,25 @@ impl Secp256k1PublicKey {
         })
     }
 
+    /// Get the BIP-340 x-only serialization of this public key
+    pub fn to_xonly_bytes(&self) -> [u8; 32] {
+        self.key.x_only_public_key().0.serialize()
+    }
+
+    /// Get the BIP-341 output key for a key-path-only Taproot spend of this public key.
+    /// The internal key is tweaked with `tagged_hash("TapTweak", xonly(P))` per BIP-86 (i.e. no
+    /// script tree).  The result is the 32-byte witness program of the P2TR scriptPubKey.
+    pub fn to_taproot_output_key(&self) -> Result<[u8; 32], &'static str> {
+        _secp256k1.with(|ctx| {
+            let (internal_key, _) = self.key.x_only_public_key();
+            let tweak = taproot_tweak(&internal_key)?;
+            let (output_key, _) = internal_key
+                .add_tweak(ctx, &tweak)
+                .map_err(|_e| "Invalid public key: failed to apply Taproot tweak")?;
+            Ok(output_key.serialize())
+        })
+    }
+
     // for benchmarking
     #[cfg(test)]
     pub fn recover_benchmark(
@@ -350,6 +371,39 @@ impl Secp256k1PrivateKey {
     pub fn as_slice(&self) -> &[u8; 32] {
         self.key.as_ref()
     }
+
+    /// Produce a 64-byte BIP-340 Schnorr signature over a BIP-341 sighash, for spending a
+    /// key-path-only Taproot output whose internal key is this key's public key (see
+    /// `Secp256k1PublicKey::to_taproot_output_key()`).
+    pub fn sign_taproot_key_spend(&self, sighash: &[u8]) -> Result<[u8; 64], &'static str> {
+        _secp256k1.with(|ctx| {
+            let msg = LibSecp256k1Message::from_slice(sighash).map_err(|_e| {
+                "Invalid message: failed to decode data hash: must be a 32-byte hash"
+            })?;
+            let keypair = LibSecp256k1KeyPair::from_secret_key(ctx, &self.key);
+            let (internal_key, _) = keypair.x_only_public_key();
+            let tweak = taproot_tweak(&internal_key)?;
+            let tweaked_keypair = keypair
+                .add_xonly_tweak(ctx, &tweak)
+                .map_err(|_e| "Invalid private key: failed to apply Taproot tweak")?;
+
+            let mut aux_rand = [0u8; 32];
+            thread_rng().fill_bytes(&mut aux_rand);
+            let sig = ctx.sign_schnorr_with_aux_rand(&msg, &tweaked_keypair, &aux_rand);
+            let mut sig_bytes = [0u8; 64];
+            sig_bytes.copy_from_slice(&sig[..]);
+            Ok(sig_bytes)
+        })
+    }
+}
+
+/// Compute the BIP-86 Taproot tweak for an internal key with no script tree
+fn taproot_tweak(
+    internal_key: &LibSecp256k1XOnlyPublicKey,
+) -> Result<LibSecp256k1Scalar, &'static str> {
+    let tweak_hash = Sha256Sum::from_bip340_tagged_data("TapTweak", &internal_key.serialize());
+    LibSecp256k1Scalar::from_be_bytes(tweak_hash.0)
+        .map_err(|_e| "Invalid public key: Taproot tweak is out of range")
 }
 
 impl PrivateKey for Secp256k1PrivateKey {
@@ -439,10 +493,23 @@ pub fn secp256k1_verify(
     })
 }
 
+/// Verify a 64-byte BIP-340 Schnorr signature against a 32-byte x-only public key
+pub fn secp256k1_verify_schnorr(
+    message_arr: &[u8],
+    serialized_signature_arr: &[u8],
+    xonly_pubkey_arr: &[u8],
+) -> Result<(), LibSecp256k1Error> {
+    _secp256k1.with(|ctx| {
+        let message = LibSecp256k1Message::from_slice(message_arr)?;
+        let sig = LibSecp256k1SchnorrSignature::from_slice(serialized_signature_arr)?;
+        let pubkey = LibSecp256k1XOnlyPublicKey::from_slice(xonly_pubkey_arr)?;
+        ctx.verify_schnorr(&sig, &message, &pubkey)
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use secp256k1;
-    use secp256k1::{PublicKey as LibSecp256k1PublicKey, Secp256k1};
+    use secp256k1::{self, PublicKey as LibSecp256k1PublicKey, Secp256k1};
 
     use super::*;
     use crate::util::hash::hex_bytes;
@@ -714,6 +781,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_taproot_output_key() {
+        // BIP-86 test vector: m/86'/0'/0'/0/0
+        let internal_key = Secp256k1PublicKey::from_hex(
+            "02cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+        )
+        .unwrap();
+        assert_eq!(
+            to_hex(&internal_key.to_xonly_bytes()),
+            "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115"
+        );
+        assert_eq!(
+            to_hex(&internal_key.to_taproot_output_key().unwrap()),
+            "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c"
+        );
+
+        // the output key only depends on the x-coordinate of the internal key
+        let odd_internal_key = Secp256k1PublicKey::from_hex(
+            "03cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+        )
+        .unwrap();
+        assert_eq!(
+            odd_internal_key.to_taproot_output_key().unwrap(),
+            internal_key.to_taproot_output_key().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_taproot_key_spend_sign_verify() {
+        for _ in 0..16 {
+            let privk = Secp256k1PrivateKey::new();
+            let pubk = Secp256k1PublicKey::from_private(&privk);
+            let output_key = pubk.to_taproot_output_key().unwrap();
+            let sighash = Sha256Sum::from_data(&privk.to_bytes());
+
+            let sig = privk.sign_taproot_key_spend(sighash.as_bytes()).unwrap();
+            secp256k1_verify_schnorr(sighash.as_bytes(), &sig, &output_key).unwrap();
+
+            // does not verify against the untweaked key
+            assert!(
+                secp256k1_verify_schnorr(sighash.as_bytes(), &sig, &pubk.to_xonly_bytes()).is_err()
+            );
+
+            // does not verify against a different message
+            let other_sighash = Sha256Sum::from_data(sighash.as_bytes());
+            assert!(secp256k1_verify_schnorr(other_sighash.as_bytes(), &sig, &output_key).is_err());
+        }
+
+        // bad message length
+        let privk = Secp256k1PrivateKey::new();
+        assert!(privk.sign_taproot_key_spend(&[0u8; 31]).is_err());
+    }
+
     /*
     #[test]
     fn test_schnorr_signature_serde() {
@@ -27,6 +27,8 @@ use stacks_common::address::{
 };
 use stacks_common::types::chainstate::StacksPrivateKey;
 
+use crate::keys::keystore::DEFAULT_KDF_ITERATIONS;
+
 extern crate alloc;
 
 #[derive(Parser, Debug)]
@@ -55,6 +57,20 @@ pub enum Command {
     GenerateStackingSignature(GenerateStackingSignatureArgs),
     /// Check a configuration file and output config information
     CheckConfig(RunSignerArgs),
+    /// Create a password-encrypted keystore holding a signer key
+    CreateKeystore(CreateKeystoreArgs),
+    /// Re-encrypt a keystore under a new password
+    RotateKeystore(RotateKeystoreArgs),
+    /// Export the blocks this signer has signed, to carry its double-signing protection to another host
+    ExportSigningHistory(ExportSigningHistoryArgs),
+    /// Import a signing history exported by export-signing-history
+    ImportSigningHistory(ImportSigningHistoryArgs),
+    /// List the blocks this signer has seen, with its decision on each
+    BlockHistory(BlockHistoryArgs),
+    /// Export the signer's DKG state and block history to a signed bundle, to move the signer to another host
+    ExportState(ExportStateArgs),
+    /// Import a state bundle exported by export-state. Stop the signer on the old host first.
+    ImportState(ImportStateArgs),
 }
 
 /// Basic arguments for all cyrptographic and stacker-db functionality
@@ -115,6 +131,119 @@ pub struct PutChunkArgs {
     pub data: alloc::vec::Vec<u8>,
 }
 
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the create-keystore command
+pub struct CreateKeystoreArgs {
+    /// Where to write the keystore. Must not already exist.
+    #[arg(long)]
+    pub keystore: PathBuf,
+    /// A file holding the hex-encoded private key to import. If omitted, a new key is generated.
+    #[arg(long)]
+    pub private_key_file: Option<PathBuf>,
+    /// A file holding the keystore password.
+    /// If omitted, the password is read from STACKS_SIGNER_KEYSTORE_PASSWORD.
+    #[arg(long)]
+    pub password_file: Option<PathBuf>,
+    /// The number of PBKDF2 iterations used to derive the encryption key
+    #[arg(long, default_value_t = DEFAULT_KDF_ITERATIONS)]
+    pub kdf_iterations: u32,
+}
+
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the rotate-keystore command
+pub struct RotateKeystoreArgs {
+    /// The keystore to re-encrypt. It is replaced in place.
+    #[arg(long)]
+    pub keystore: PathBuf,
+    /// A file holding the current keystore password.
+    /// If omitted, the password is read from STACKS_SIGNER_KEYSTORE_PASSWORD.
+    #[arg(long)]
+    pub password_file: Option<PathBuf>,
+    /// A file holding the new keystore password.
+    /// If omitted, the password is read from STACKS_SIGNER_KEYSTORE_NEW_PASSWORD.
+    #[arg(long)]
+    pub new_password_file: Option<PathBuf>,
+    /// The number of PBKDF2 iterations used to derive the new encryption key
+    #[arg(long, default_value_t = DEFAULT_KDF_ITERATIONS)]
+    pub kdf_iterations: u32,
+}
+
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the export-signing-history command
+pub struct ExportSigningHistoryArgs {
+    /// Path to the signer config file
+    #[arg(long, short, value_name = "FILE")]
+    pub config: PathBuf,
+    /// Where to write the signing history
+    #[arg(long)]
+    pub output: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the import-signing-history command
+pub struct ImportSigningHistoryArgs {
+    /// Path to the signer config file
+    #[arg(long, short, value_name = "FILE")]
+    pub config: PathBuf,
+    /// The signing history to import
+    #[arg(long)]
+    pub input: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the block-history command
+pub struct BlockHistoryArgs {
+    /// Path to the signer config file
+    #[arg(long, short, value_name = "FILE")]
+    pub config: PathBuf,
+    /// Only list blocks from this reward cycle
+    #[arg(long)]
+    pub reward_cycle: Option<u64>,
+    /// The output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    pub format: OutputFormat,
+    /// Summarize the signer's participation in each reward cycle instead of listing blocks
+    #[arg(long)]
+    pub summary: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the export-state command
+pub struct ExportStateArgs {
+    /// Path to the signer config file
+    #[arg(long, short, value_name = "FILE")]
+    pub config: PathBuf,
+    /// The reward cycle whose DKG state to export
+    #[arg(long)]
+    pub reward_cycle: u64,
+    /// Where to write the state bundle
+    #[arg(long)]
+    pub output: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+/// Arguments for the import-state command
+pub struct ImportStateArgs {
+    /// Path to the signer config file
+    #[arg(long, short, value_name = "FILE")]
+    pub config: PathBuf,
+    /// The reward cycle whose DKG state is being migrated. The bundle must be for this cycle.
+    #[arg(long)]
+    pub reward_cycle: u64,
+    /// The state bundle to import
+    #[arg(long)]
+    pub input: PathBuf,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+/// The output format of reporting commands
+pub enum OutputFormat {
+    /// JSON
+    Json,
+    /// Comma-separated values, with a header row
+    Csv,
+}
+
 #[derive(Parser, Debug, Clone)]
 /// Arguments for the Run command
 pub struct RunSignerArgs {
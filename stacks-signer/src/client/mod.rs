@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+/// The pool of stacks nodes the signer fails over between
+mod nodes;
 /// The stacker db module for communicating with the stackerdb contract
 mod stackerdb;
 /// The stacks node client module for communicating with the stacks node
@@ -24,12 +26,15 @@ use std::time::Duration;
 use clarity::vm::errors::Error as ClarityError;
 use clarity::vm::types::serialization::SerializationError;
 use libstackerdb::Error as StackerDBError;
+pub use nodes::*;
 use slog::slog_debug;
 pub use stackerdb::*;
 pub use stacks_client::*;
 use stacks_common::codec::Error as CodecError;
 use stacks_common::debug;
 
+use crate::keys::KeyError;
+
 /// Backoff timer initial interval in milliseconds
 const BACKOFF_INITIAL_INTERVAL: u64 = 128;
 /// Backoff timer max interval in milliseconds
@@ -86,6 +91,9 @@ pub enum ClientError {
     /// Invalid response from the stacks node
     #[error("Invalid response from the stacks node: {0}")]
     InvalidResponse(String),
+    /// The signer's key provider failed to produce a signature
+    #[error("Key provider failed to sign: {0}")]
+    KeyProviderError(#[from] KeyError),
 }
 
 /// Retry a function F with an exponential backoff and notification on transient failure
@@ -547,8 +555,8 @@ pub(crate) mod tests {
             },
             signer_slot_ids,
             ecdsa_private_key: config.ecdsa_private_key,
-            stacks_private_key: config.stacks_private_key,
-            node_host: config.node_host.to_string(),
+            key_provider: config.key_provider.clone(),
+            nodes: NodePool::new(config.node_host.to_string(), vec![]),
             mainnet: config.network.is_mainnet(),
             dkg_end_timeout: config.dkg_end_timeout,
             dkg_private_timeout: config.dkg_private_timeout,
@@ -558,6 +566,7 @@ pub(crate) mod tests {
             tx_fee_ustx: config.tx_fee_ustx,
             max_tx_fee_ustx: config.max_tx_fee_ustx,
             db_path: config.db_path.clone(),
+            block_policy: config.block_policy.clone(),
         }
     }
 
@@ -0,0 +1,125 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// The stacks nodes a signer can talk to, and which of them is in use.
+///
+/// The first host is the primary node; the rest are backups, in order of preference.
+/// Clones share the active node, so a failover seen by one client is seen by all of them.
+#[derive(Clone, Debug)]
+pub struct NodePool {
+    /// The node hosts, primary first
+    hosts: Arc<Vec<String>>,
+    /// The index into `hosts` of the active node
+    active: Arc<AtomicUsize>,
+}
+
+impl NodePool {
+    /// Create a new node pool with the given primary and backup hosts
+    pub fn new(primary_host: String, backup_hosts: Vec<String>) -> Self {
+        let mut hosts = Vec::with_capacity(backup_hosts.len() + 1);
+        hosts.push(primary_host);
+        hosts.extend(backup_hosts);
+        Self {
+            hosts: Arc::new(hosts),
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Get all node hosts, primary first
+    pub fn hosts(&self) -> &[String] {
+        &self.hosts
+    }
+
+    /// Whether there are backup nodes to fail over to
+    pub fn has_backups(&self) -> bool {
+        self.hosts.len() > 1
+    }
+
+    /// Get the index of the active node
+    pub fn active_index(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Get the host of the active node
+    pub fn active_host(&self) -> &str {
+        &self.hosts[self.active_index()]
+    }
+
+    /// Get the HTTP origin of the active node, e.g. `http://127.0.0.1:20443`
+    pub fn active_origin(&self) -> String {
+        format!("http://{}", self.active_host())
+    }
+
+    /// Make the node at the given index the active node.
+    /// Returns true if the active node changed.
+    pub fn set_active(&self, index: usize) -> bool {
+        assert!(index < self.hosts.len(), "BUG: node index out of range");
+        self.active.swap(index, Ordering::SeqCst) != index
+    }
+
+    /// Move on to the next node after `failed_host`, if it is still the active node.
+    /// Concurrent failures of the same node only advance the active node once.
+    /// Returns the host of the active node.
+    pub fn fail_over(&self, failed_host: &str) -> &str {
+        let current = self.active_index();
+        if self.hosts.len() > 1 && self.hosts[current] == failed_host {
+            let next = (current + 1) % self.hosts.len();
+            // If this fails, another client already failed over
+            let _ = self
+                .active
+                .compare_exchange(current, next, Ordering::SeqCst, Ordering::SeqCst);
+        }
+        self.active_host()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fail_over_cycles_through_nodes() {
+        let nodes = NodePool::new(
+            "127.0.0.1:20443".into(),
+            vec!["127.0.0.1:30443".into(), "127.0.0.1:40443".into()],
+        );
+        let clone = nodes.clone();
+        assert!(nodes.has_backups());
+        assert_eq!(nodes.active_origin(), "http://127.0.0.1:20443");
+
+        assert_eq!(nodes.fail_over("127.0.0.1:20443"), "127.0.0.1:30443");
+        assert_eq!(clone.active_host(), "127.0.0.1:30443");
+        // A stale failure of the old node does not skip the new one
+        assert_eq!(clone.fail_over("127.0.0.1:20443"), "127.0.0.1:30443");
+
+        assert_eq!(nodes.fail_over("127.0.0.1:30443"), "127.0.0.1:40443");
+        assert_eq!(nodes.fail_over("127.0.0.1:40443"), "127.0.0.1:20443");
+
+        assert!(nodes.set_active(2));
+        assert!(!clone.set_active(2));
+        assert_eq!(nodes.active_index(), 2);
+    }
+
+    #[test]
+    fn single_node_never_fails_over() {
+        let nodes = NodePool::new("127.0.0.1:20443".into(), vec![]);
+        assert!(!nodes.has_backups());
+        assert_eq!(nodes.fail_over("127.0.0.1:20443"), "127.0.0.1:20443");
+        assert_eq!(nodes.active_index(), 0);
+    }
+}
@@ -14,21 +14,24 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 //
+use std::sync::Arc;
+
 use blockstack_lib::chainstate::stacks::StacksTransaction;
 use blockstack_lib::net::api::poststackerdbchunk::StackerDBErrorCodes;
 use hashbrown::HashMap;
 use libsigner::v1::messages::{MessageSlotID, SignerMessage};
 use libsigner::{SignerSession, StackerDBSession};
 use libstackerdb::{StackerDBChunkAckData, StackerDBChunkData};
-use slog::{slog_debug, slog_error, slog_warn};
+use slog::{slog_debug, slog_error, slog_info, slog_warn};
 use stacks_common::codec::{read_next, StacksMessageCodec};
 use stacks_common::types::chainstate::StacksPrivateKey;
-use stacks_common::{debug, error, warn};
+use stacks_common::{debug, error, info, warn};
 use wsts::net::Packet;
 
 use super::ClientError;
-use crate::client::retry_with_exponential_backoff;
+use crate::client::{retry_with_exponential_backoff, NodePool};
 use crate::config::SignerConfig;
+use crate::keys::{InMemoryKeyProvider, KeyProvider};
 
 /// The signer StackerDB slot ID, purposefully wrapped to prevent conflation with SignerID
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, PartialOrd, Ord)]
@@ -46,8 +49,8 @@ pub struct StackerDB {
     /// The stacker-db sessions for each signer set and message type.
     /// Maps message ID to the DB session.
     signers_message_stackerdb_sessions: HashMap<MessageSlotID, StackerDBSession>,
-    /// The private key used in all stacks node communications
-    stacks_private_key: StacksPrivateKey,
+    /// The provider of signatures for all stacks node communications
+    key_provider: Arc<dyn KeyProvider>,
     /// A map of a message ID to last chunk version for each session
     slot_versions: HashMap<MessageSlotID, HashMap<SignerSlotID, u32>>,
     /// The signer slot ID -- the index into the signer list for this signer daemon's signing key.
@@ -56,13 +59,15 @@ pub struct StackerDB {
     reward_cycle: u64,
     /// The stacker-db transaction msg session for the NEXT reward cycle
     next_transaction_session: StackerDBSession,
+    /// The stacks nodes the sessions connect to
+    nodes: NodePool,
 }
 
 impl From<&SignerConfig> for StackerDB {
     fn from(config: &SignerConfig) -> Self {
-        Self::new(
-            &config.node_host,
-            config.stacks_private_key,
+        Self::new_with_key_provider(
+            config.nodes.clone(),
+            config.key_provider.clone(),
             config.mainnet,
             config.reward_cycle,
             config.signer_slot_id,
@@ -78,6 +83,25 @@ impl StackerDB {
         reward_cycle: u64,
         signer_slot_id: SignerSlotID,
     ) -> Self {
+        Self::new_with_key_provider(
+            NodePool::new(host.to_string(), vec![]),
+            Arc::new(InMemoryKeyProvider::new(stacks_private_key)),
+            is_mainnet,
+            reward_cycle,
+            signer_slot_id,
+        )
+    }
+
+    /// Create a new StackerDB client which signs its chunks with the given key provider, and
+    /// talks to the active node of the given node pool
+    pub fn new_with_key_provider(
+        nodes: NodePool,
+        key_provider: Arc<dyn KeyProvider>,
+        is_mainnet: bool,
+        reward_cycle: u64,
+        signer_slot_id: SignerSlotID,
+    ) -> Self {
+        let host = nodes.active_host();
         let mut signers_message_stackerdb_sessions = HashMap::new();
         for msg_id in MessageSlotID::ALL {
             signers_message_stackerdb_sessions.insert(
@@ -93,11 +117,28 @@ impl StackerDB {
 
         Self {
             signers_message_stackerdb_sessions,
-            stacks_private_key,
+            key_provider,
             slot_versions: HashMap::new(),
             signer_slot_id,
             reward_cycle,
             next_transaction_session,
+            nodes,
+        }
+    }
+
+    /// Point the stacker-db sessions at the active stacks node, if it has changed
+    fn follow_active_node(&mut self) {
+        let active_host = self.nodes.active_host();
+        if self.next_transaction_session.host == active_host {
+            return;
+        }
+        info!("Switching stacker-db sessions to stacks node {active_host}");
+        for session in self
+            .signers_message_stackerdb_sessions
+            .values_mut()
+            .chain(std::iter::once(&mut self.next_transaction_session))
+        {
+            *session = StackerDBSession::new(active_host, session.stackerdb_contract_id.clone());
         }
     }
 
@@ -118,6 +159,7 @@ impl StackerDB {
         msg_id: &MessageSlotID,
         message_bytes: Vec<u8>,
     ) -> Result<StackerDBChunkAckData, ClientError> {
+        self.follow_active_node();
         let slot_id = self.signer_slot_id;
         loop {
             let mut slot_version = if let Some(versions) = self.slot_versions.get_mut(msg_id) {
@@ -135,7 +177,7 @@ impl StackerDB {
             };
 
             let mut chunk = StackerDBChunkData::new(slot_id.0, slot_version, message_bytes.clone());
-            chunk.sign(&self.stacks_private_key)?;
+            self.key_provider.sign_chunk(&mut chunk)?;
 
             let Some(session) = self.signers_message_stackerdb_sessions.get_mut(msg_id) else {
                 panic!("FATAL: would loop forever trying to send a message with ID {}, for which we don't have a session", msg_id);
@@ -146,7 +188,21 @@ impl StackerDB {
                 &session.stackerdb_contract_id
             );
 
-            let send_request = || session.put_chunk(&chunk).map_err(backoff::Error::transient);
+            let nodes = &self.nodes;
+            let send_request = || {
+                session.put_chunk(&chunk).map_err(|e| {
+                    // Retry on the next stacks node, if there is one
+                    let next_host = nodes.fail_over(&session.host);
+                    if next_host != session.host {
+                        warn!("Failed to send chunk to stacks node {}: {e:?}. Failing over to {next_host}.", session.host);
+                        *session = StackerDBSession::new(
+                            next_host,
+                            session.stackerdb_contract_id.clone(),
+                        );
+                    }
+                    backoff::Error::transient(e)
+                })
+            };
             let chunk_ack: StackerDBChunkAckData = retry_with_exponential_backoff(send_request)?;
 
             if let Some(versions) = self.slot_versions.get_mut(msg_id) {
@@ -232,6 +288,7 @@ impl StackerDB {
             MessageSlotID::DkgEndBegin,
             MessageSlotID::DkgEnd,
         ];
+        self.follow_active_node();
         let slot_ids = signer_ids.iter().map(|id| id.0).collect::<Vec<_>>();
         let mut packets = vec![];
         for packet_slot in packet_slots {
@@ -271,6 +328,7 @@ impl StackerDB {
 
     /// Get this signer's latest transactions from stackerdb
     pub fn get_current_transactions(&mut self) -> Result<Vec<StacksTransaction>, ClientError> {
+        self.follow_active_node();
         let Some(transactions_session) = self
             .signers_message_stackerdb_sessions
             .get_mut(&MessageSlotID::Transactions)
@@ -286,6 +344,7 @@ impl StackerDB {
         signer_ids: &[SignerSlotID],
     ) -> Result<Vec<StacksTransaction>, ClientError> {
         debug!("Getting latest chunks from stackerdb for the following signers: {signer_ids:?}",);
+        self.follow_active_node();
         Self::get_transactions(&mut self.next_transaction_session, signer_ids)
     }
 
@@ -295,6 +354,7 @@ impl StackerDB {
         signer_id: SignerSlotID,
     ) -> Result<Option<Vec<u8>>, ClientError> {
         debug!("Getting the persisted encrypted state for signer {signer_id}");
+        self.follow_active_node();
         let Some(state_session) = self
             .signers_message_stackerdb_sessions
             .get_mut(&MessageSlotID::EncryptedSignerState)
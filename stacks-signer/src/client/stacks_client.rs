@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 
 use blockstack_lib::burnchains::Txid;
 use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
@@ -21,10 +23,11 @@ use blockstack_lib::chainstate::stacks::boot::{
     NakamotoSignerEntry, SIGNERS_VOTING_FUNCTION_NAME, SIGNERS_VOTING_NAME,
 };
 use blockstack_lib::chainstate::stacks::{
-    StacksTransaction, StacksTransactionSigner, TransactionAnchorMode, TransactionAuth,
-    TransactionContractCall, TransactionPayload, TransactionPostConditionMode,
-    TransactionSpendingCondition, TransactionVersion,
+    StacksTransaction, TransactionAnchorMode, TransactionAuth, TransactionContractCall,
+    TransactionPayload, TransactionPostConditionMode, TransactionSpendingCondition,
+    TransactionVersion,
 };
+use blockstack_lib::core::mempool::{decode_tx_stream, MemPoolSyncData};
 use blockstack_lib::net::api::callreadonly::CallReadOnlyResponse;
 use blockstack_lib::net::api::getaccount::AccountEntryResponse;
 use blockstack_lib::net::api::getinfo::RPCPeerInfoData;
@@ -34,31 +37,38 @@ use blockstack_lib::net::api::postblock_proposal::NakamotoBlockProposal;
 use blockstack_lib::net::api::postfeerate::{FeeRateEstimateRequestBody, RPCFeeEstimateResponse};
 use blockstack_lib::util_lib::boot::{boot_code_addr, boot_code_id};
 use clarity::util::hash::to_hex;
+use clarity::vm::costs::ExecutionCost;
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use clarity::vm::{ClarityName, ContractName, Value as ClarityValue};
 use reqwest::header::AUTHORIZATION;
 use serde_json::json;
-use slog::slog_debug;
+use slog::{slog_debug, slog_info, slog_warn};
 use stacks_common::codec::StacksMessageCodec;
 use stacks_common::consts::{CHAIN_ID_MAINNET, CHAIN_ID_TESTNET};
-use stacks_common::debug;
 use stacks_common::types::chainstate::{StacksAddress, StacksPrivateKey, StacksPublicKey};
 use stacks_common::types::StacksEpochId;
+use stacks_common::{debug, info, warn};
 use wsts::curve::point::{Compressed, Point};
 
-use crate::client::{retry_with_exponential_backoff, ClientError};
+use crate::client::{retry_with_exponential_backoff, ClientError, NodePool};
 use crate::config::GlobalConfig;
+use crate::keys::{InMemoryKeyProvider, KeyProvider};
 use crate::runloop::RewardCycleInfo;
 
+/// The number of burn blocks a stacks node may trail the others by and still be healthy
+const MAX_NODE_BURN_BLOCK_LAG: u64 = 1;
+/// The timeout of a stacks node health check request
+const NODE_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// The Stacks signer client used to communicate with the stacks node
 #[derive(Clone, Debug)]
 pub struct StacksClient {
     /// The stacks address of the signer
     stacks_address: StacksAddress,
-    /// The private key used in all stacks node communications
-    stacks_private_key: StacksPrivateKey,
-    /// The stacks node HTTP base endpoint
-    http_origin: String,
+    /// The provider of signatures for all stacks node communications
+    key_provider: Arc<dyn KeyProvider>,
+    /// The stacks nodes to send requests to
+    nodes: NodePool,
     /// The types of transactions
     tx_version: TransactionVersion,
     /// The chain we are interacting with
@@ -74,9 +84,9 @@ pub struct StacksClient {
 impl From<&GlobalConfig> for StacksClient {
     fn from(config: &GlobalConfig) -> Self {
         Self {
-            stacks_private_key: config.stacks_private_key,
+            key_provider: config.key_provider.clone(),
             stacks_address: config.stacks_address,
-            http_origin: format!("http://{}", config.node_host),
+            nodes: NodePool::new(config.node_host.clone(), config.backup_node_hosts.clone()),
             tx_version: config.network.to_transaction_version(),
             chain_id: config.network.to_chain_id(),
             stacks_node_client: reqwest::blocking::Client::new(),
@@ -107,9 +117,9 @@ impl StacksClient {
         };
         let stacks_address = StacksAddress::p2pkh(mainnet, &pubkey);
         Self {
-            stacks_private_key,
+            key_provider: Arc::new(InMemoryKeyProvider::new(stacks_private_key)),
             stacks_address,
-            http_origin: format!("http://{}", node_host),
+            nodes: NodePool::new(node_host.to_string(), vec![]),
             tx_version,
             chain_id,
             stacks_node_client: reqwest::blocking::Client::new(),
@@ -207,15 +217,18 @@ impl StacksClient {
             estimated_len: Some(tx.tx_len()),
             transaction_payload: to_hex(&tx.payload.serialize_to_vec()),
         };
-        let timer =
-            crate::monitoring::new_rpc_call_timer(&self.fees_transaction_path(), &self.http_origin);
+        let timer = crate::monitoring::new_rpc_call_timer(
+            &self.fees_transaction_path(),
+            &self.http_origin(),
+        );
         let send_request = || {
+            let host = self.nodes.active_host();
             self.stacks_node_client
                 .post(self.fees_transaction_path())
                 .header("Content-Type", "application/json")
                 .json(&request)
                 .send()
-                .map_err(backoff::Error::transient)
+                .map_err(|e| backoff::Error::transient(self.fail_over_if_unreachable(host, e)))
         };
         let response = retry_with_exponential_backoff(send_request)?;
         if !response.status().is_success() {
@@ -265,6 +278,21 @@ impl StacksClient {
         }
     }
 
+    /// Get the block limit of the epoch the given burn block height is in
+    pub fn get_block_limit(&self, burn_block_height: u64) -> Result<ExecutionCost, ClientError> {
+        let pox_info = self.get_pox_data()?;
+        pox_info
+            .epochs
+            .into_iter()
+            .find(|epoch| {
+                epoch.start_height <= burn_block_height && burn_block_height < epoch.end_height
+            })
+            .map(|epoch| epoch.block_limit)
+            .ok_or(ClientError::UnsupportedStacksFeature(format!(
+                "/v2/pox must report the epoch of burn block {burn_block_height}"
+            )))
+    }
+
     /// Submit the block proposal to the stacks node. The block will be validated and returned via the HTTP endpoint for Block events.
     pub fn submit_block_for_validation(&self, block: NakamotoBlock) -> Result<(), ClientError> {
         let block_proposal = NakamotoBlockProposal {
@@ -272,15 +300,16 @@ impl StacksClient {
             chain_id: self.chain_id,
         };
         let timer =
-            crate::monitoring::new_rpc_call_timer(&self.block_proposal_path(), &self.http_origin);
+            crate::monitoring::new_rpc_call_timer(&self.block_proposal_path(), &self.http_origin());
         let send_request = || {
+            let host = self.nodes.active_host();
             self.stacks_node_client
                 .post(self.block_proposal_path())
                 .header("Content-Type", "application/json")
                 .header(AUTHORIZATION, self.auth_password.clone())
                 .json(&block_proposal)
                 .send()
-                .map_err(backoff::Error::transient)
+                .map_err(|e| backoff::Error::transient(self.fail_over_if_unreachable(host, e)))
         };
 
         let response = retry_with_exponential_backoff(send_request)?;
@@ -291,6 +320,35 @@ impl StacksClient {
         Ok(())
     }
 
+    /// Retrieve a page of the transactions in the stacks node's mempool.
+    /// The node starts each page at a random transaction, so this is a sample of the mempool if
+    /// it holds more transactions than fit in a page.
+    pub fn get_mempool_transactions(&self) -> Result<Vec<StacksTransaction>, ClientError> {
+        // An empty tag set asks for every transaction
+        let query = MemPoolSyncData::TxTags([0u8; 32], vec![]).serialize_to_vec();
+        let timer =
+            crate::monitoring::new_rpc_call_timer(&self.mempool_query_path(), &self.http_origin());
+        let send_request = || {
+            let host = self.nodes.active_host();
+            self.stacks_node_client
+                .post(self.mempool_query_path())
+                .header("Content-Type", "application/octet-stream")
+                .body(query.clone())
+                .send()
+                .map_err(|e| backoff::Error::transient(self.fail_over_if_unreachable(host, e)))
+        };
+        let response = retry_with_exponential_backoff(send_request)?;
+        timer.stop_and_record();
+        if !response.status().is_success() {
+            return Err(ClientError::RequestFailure(response.status()));
+        }
+        let body = response.bytes()?;
+        let (txs, _next_page_id) = decode_tx_stream(&mut &body[..]).map_err(|e| {
+            ClientError::InvalidResponse(format!("Failed to decode mempool transactions: {e:?}"))
+        })?;
+        Ok(txs)
+    }
+
     /// Retrieve the approved DKG aggregate public key for the given reward cycle
     pub fn get_approved_aggregate_key(
         &self,
@@ -362,12 +420,13 @@ impl StacksClient {
     pub fn get_peer_info(&self) -> Result<RPCPeerInfoData, ClientError> {
         debug!("Getting stacks node info...");
         let timer =
-            crate::monitoring::new_rpc_call_timer(&self.core_info_path(), &self.http_origin);
+            crate::monitoring::new_rpc_call_timer(&self.core_info_path(), &self.http_origin());
         let send_request = || {
+            let host = self.nodes.active_host();
             self.stacks_node_client
                 .get(self.core_info_path())
                 .send()
-                .map_err(backoff::Error::transient)
+                .map_err(|e| backoff::Error::transient(self.fail_over_if_unreachable(host, e)))
         };
         let response = retry_with_exponential_backoff(send_request)?;
         timer.stop_and_record();
@@ -378,6 +437,58 @@ impl StacksClient {
         Ok(peer_info_data)
     }
 
+    /// Probe every stacks node with a single `/v2/info` request, and make the most preferred node
+    /// that is reachable and caught up with the burnchain the active node.
+    /// Returns the active node's host, or None if no node could be reached.
+    pub fn check_node_health(&self) -> Option<&str> {
+        let peer_infos: Vec<_> = self
+            .nodes
+            .hosts()
+            .iter()
+            .map(|host| match self.probe_node(host) {
+                Ok(peer_info) => Some(peer_info),
+                Err(e) => {
+                    warn!("Stacks node {host} failed its health check: {e}");
+                    None
+                }
+            })
+            .collect();
+        let Some(best_burn_height) = peer_infos
+            .iter()
+            .flatten()
+            .map(|info| info.burn_block_height)
+            .max()
+        else {
+            warn!("None of the stacks nodes passed their health check");
+            return None;
+        };
+        let healthy_index = peer_infos
+            .iter()
+            .position(|info| {
+                matches!(info, Some(info)
+                    if info.burn_block_height.saturating_add(MAX_NODE_BURN_BLOCK_LAG) >= best_burn_height)
+            })
+            .expect("BUG: no node reported the highest burn block height");
+        if self.nodes.set_active(healthy_index) {
+            info!("Switched to stacks node {}", self.nodes.active_host());
+        }
+        crate::monitoring::update_active_stacks_node(self.nodes.hosts(), healthy_index);
+        Some(self.nodes.active_host())
+    }
+
+    /// Get the peer info of the given node, without retrying or failing over
+    fn probe_node(&self, host: &str) -> Result<RPCPeerInfoData, ClientError> {
+        let response = self
+            .stacks_node_client
+            .get(format!("http://{host}/v2/info"))
+            .timeout(NODE_HEALTH_CHECK_TIMEOUT)
+            .send()?;
+        if !response.status().is_success() {
+            return Err(ClientError::RequestFailure(response.status()));
+        }
+        Ok(response.json::<RPCPeerInfoData>()?)
+    }
+
     /// Retrieve the last DKG vote round number for the current reward cycle
     pub fn get_last_round(&self, reward_cycle: u64) -> Result<Option<u64>, ClientError> {
         debug!("Getting the last DKG vote round of reward cycle {reward_cycle}...");
@@ -413,13 +524,14 @@ impl StacksClient {
         debug!("Getting reward set for reward cycle {reward_cycle}...");
         let timer = crate::monitoring::new_rpc_call_timer(
             &self.reward_set_path(reward_cycle),
-            &self.http_origin,
+            &self.http_origin(),
         );
         let send_request = || {
+            let host = self.nodes.active_host();
             self.stacks_node_client
                 .get(self.reward_set_path(reward_cycle))
                 .send()
-                .map_err(backoff::Error::transient)
+                .map_err(|e| backoff::Error::transient(self.fail_over_if_unreachable(host, e)))
         };
         let response = retry_with_exponential_backoff(send_request)?;
         timer.stop_and_record();
@@ -434,12 +546,13 @@ impl StacksClient {
     pub fn get_pox_data(&self) -> Result<RPCPoxInfoData, ClientError> {
         debug!("Getting pox data...");
         #[cfg(feature = "monitoring_prom")]
-        let timer = crate::monitoring::new_rpc_call_timer(&self.pox_path(), &self.http_origin);
+        let timer = crate::monitoring::new_rpc_call_timer(&self.pox_path(), &self.http_origin());
         let send_request = || {
+            let host = self.nodes.active_host();
             self.stacks_node_client
                 .get(self.pox_path())
                 .send()
-                .map_err(backoff::Error::transient)
+                .map_err(|e| backoff::Error::transient(self.fail_over_if_unreachable(host, e)))
         };
         let response = retry_with_exponential_backoff(send_request)?;
         #[cfg(feature = "monitoring_prom")]
@@ -481,13 +594,16 @@ impl StacksClient {
         address: &StacksAddress,
     ) -> Result<AccountEntryResponse, ClientError> {
         debug!("Getting account info...");
-        let timer =
-            crate::monitoring::new_rpc_call_timer(&self.accounts_path(address), &self.http_origin);
+        let timer = crate::monitoring::new_rpc_call_timer(
+            &self.accounts_path(address),
+            &self.http_origin(),
+        );
         let send_request = || {
+            let host = self.nodes.active_host();
             self.stacks_node_client
                 .get(self.accounts_path(address))
                 .send()
-                .map_err(backoff::Error::transient)
+                .map_err(|e| backoff::Error::transient(self.fail_over_if_unreachable(host, e)))
         };
         let response = retry_with_exponential_backoff(send_request)?;
         timer.stop_and_record();
@@ -545,7 +661,7 @@ impl StacksClient {
             contract_name,
             function_name,
             &function_args,
-            &self.stacks_private_key,
+            &self.key_provider.public_key(),
             self.tx_version,
             self.chain_id,
             nonce,
@@ -558,8 +674,9 @@ impl StacksClient {
         let txid = tx.txid();
         let tx = tx.serialize_to_vec();
         let timer =
-            crate::monitoring::new_rpc_call_timer(&self.transaction_path(), &self.http_origin);
+            crate::monitoring::new_rpc_call_timer(&self.transaction_path(), &self.http_origin());
         let send_request = || {
+            let host = self.nodes.active_host();
             self.stacks_node_client
                 .post(self.transaction_path())
                 .header("Content-Type", "application/octet-stream")
@@ -567,7 +684,7 @@ impl StacksClient {
                 .send()
                 .map_err(|e| {
                     debug!("Failed to submit transaction to the Stacks node: {e:?}");
-                    backoff::Error::transient(e)
+                    backoff::Error::transient(self.fail_over_if_unreachable(host, e))
                 })
         };
         let response = retry_with_exponential_backoff(send_request)?;
@@ -600,13 +717,15 @@ impl StacksClient {
         let body =
             json!({"sender": self.stacks_address.to_string(), "arguments": args}).to_string();
         let path = self.read_only_path(contract_addr, contract_name, function_name);
-        let timer = crate::monitoring::new_rpc_call_timer(&path, &self.http_origin);
+        let timer = crate::monitoring::new_rpc_call_timer(&path, &self.http_origin());
+        let host = self.nodes.active_host();
         let response = self
             .stacks_node_client
             .post(path)
             .header("Content-Type", "application/json")
             .body(body)
-            .send()?;
+            .send()
+            .map_err(|e| self.fail_over_if_unreachable(host, e))?;
         timer.stop_and_record();
         if !response.status().is_success() {
             return Err(ClientError::RequestFailure(response.status()));
@@ -625,12 +744,34 @@ impl StacksClient {
         Ok(value)
     }
 
+    /// Get the stacks nodes this client sends requests to
+    pub fn node_pool(&self) -> &NodePool {
+        &self.nodes
+    }
+
+    /// The HTTP origin of the active stacks node
+    fn http_origin(&self) -> String {
+        self.nodes.active_origin()
+    }
+
+    /// If a request to `host` failed because the node could not be reached, fail over to the
+    /// next node, so that the request is retried there.
+    fn fail_over_if_unreachable(&self, host: &str, e: reqwest::Error) -> reqwest::Error {
+        if e.is_connect() || e.is_timeout() {
+            let next_host = self.nodes.fail_over(host);
+            if next_host != host {
+                warn!("Stacks node {host} is unreachable: {e}. Failing over to {next_host}.");
+            }
+        }
+        e
+    }
+
     fn pox_path(&self) -> String {
-        format!("{}/v2/pox", self.http_origin)
+        format!("{}/v2/pox", self.http_origin())
     }
 
     fn transaction_path(&self) -> String {
-        format!("{}/v2/transactions", self.http_origin)
+        format!("{}/v2/transactions", self.http_origin())
     }
 
     fn read_only_path(
@@ -641,28 +782,35 @@ impl StacksClient {
     ) -> String {
         format!(
             "{}/v2/contracts/call-read/{contract_addr}/{contract_name}/{function_name}",
-            self.http_origin
+            self.http_origin()
         )
     }
 
     fn block_proposal_path(&self) -> String {
-        format!("{}/v2/block_proposal", self.http_origin)
+        format!("{}/v2/block_proposal", self.http_origin())
+    }
+
+    fn mempool_query_path(&self) -> String {
+        format!("{}/v2/mempool/query", self.http_origin())
     }
 
     fn core_info_path(&self) -> String {
-        format!("{}/v2/info", self.http_origin)
+        format!("{}/v2/info", self.http_origin())
     }
 
     fn accounts_path(&self, stacks_address: &StacksAddress) -> String {
-        format!("{}/v2/accounts/{stacks_address}?proof=0", self.http_origin)
+        format!(
+            "{}/v2/accounts/{stacks_address}?proof=0",
+            self.http_origin()
+        )
     }
 
     fn reward_set_path(&self, reward_cycle: u64) -> String {
-        format!("{}/v2/stacker_set/{reward_cycle}", self.http_origin)
+        format!("{}/v2/stacker_set/{reward_cycle}", self.http_origin())
     }
 
     fn fees_transaction_path(&self) -> String {
-        format!("{}/v2/fees/transaction", self.http_origin)
+        format!("{}/v2/fees/transaction", self.http_origin())
     }
 
     /// Helper function to create a stacks transaction for a modifying contract call
@@ -672,7 +820,7 @@ impl StacksClient {
         contract_name: ContractName,
         function_name: ClarityName,
         function_args: &[ClarityValue],
-        stacks_private_key: &StacksPrivateKey,
+        stacks_public_key: &StacksPublicKey,
         tx_version: TransactionVersion,
         chain_id: u32,
         nonce: u64,
@@ -683,12 +831,11 @@ impl StacksClient {
             function_name,
             function_args: function_args.to_vec(),
         });
-        let public_key = StacksPublicKey::from_private(stacks_private_key);
         let tx_auth = TransactionAuth::Standard(
-            TransactionSpendingCondition::new_singlesig_p2pkh(public_key).ok_or(
+            TransactionSpendingCondition::new_singlesig_p2pkh(*stacks_public_key).ok_or(
                 ClientError::TransactionGenerationFailure(format!(
                     "Failed to create spending condition from public key: {}",
-                    public_key.to_hex()
+                    stacks_public_key.to_hex()
                 )),
             )?,
         );
@@ -707,16 +854,11 @@ impl StacksClient {
         &self,
         unsigned_tx: StacksTransaction,
     ) -> Result<StacksTransaction, ClientError> {
-        let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
-        tx_signer
-            .sign_origin(&self.stacks_private_key)
+        let mut tx = unsigned_tx;
+        self.key_provider
+            .sign_transaction_origin(&mut tx)
             .map_err(|e| ClientError::TransactionGenerationFailure(e.to_string()))?;
-
-        tx_signer
-            .get_tx()
-            .ok_or(ClientError::TransactionGenerationFailure(
-                "Failed to generate transaction from a transaction signer".to_string(),
-            ))
+        Ok(tx)
     }
 }
 
@@ -747,7 +889,7 @@ mod tests {
         build_get_last_round_response, build_get_medium_estimated_fee_ustx_response,
         build_get_peer_info_response, build_get_pox_data_response, build_get_round_info_response,
         build_get_vote_for_aggregate_key_response, build_get_weight_threshold_response,
-        build_read_only_response, write_response, MockServerClient,
+        build_read_only_response, mock_server_random, write_response, MockServerClient,
     };
 
     #[test]
@@ -918,13 +1060,12 @@ mod tests {
     #[test]
     fn transaction_contract_call_should_send_bytes_to_node() {
         let mock = MockServerClient::new();
-        let private_key = StacksPrivateKey::new();
         let unsigned_tx = StacksClient::build_unsigned_contract_call_transaction(
             &mock.client.stacks_address,
             ContractName::from("contract-name"),
             ClarityName::from("function-name"),
             &[],
-            &private_key,
+            &mock.client.key_provider.public_key(),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             0,
@@ -1221,6 +1362,23 @@ mod tests {
         assert!(h.join().unwrap().is_err());
     }
 
+    #[test]
+    fn get_block_limit_should_succeed() {
+        let (pox_response, pox_info) =
+            build_get_pox_data_response(None, None, Some(100), Some(200));
+        for (burn_block_height, expected) in [
+            (150, Some(&pox_info.epochs[0].block_limit)),
+            (200, Some(&pox_info.epochs[1].block_limit)),
+            (1_200, None),
+        ] {
+            let mock = MockServerClient::new();
+            let h = spawn(move || mock.client.get_block_limit(burn_block_height));
+            write_response(mock.server, pox_response.as_bytes());
+            let block_limit = h.join().unwrap();
+            assert_eq!(block_limit.as_ref().ok(), expected);
+        }
+    }
+
     #[test]
     fn submit_block_for_validation_should_succeed() {
         let mock = MockServerClient::new();
@@ -1247,6 +1405,38 @@ mod tests {
         assert!(h.join().unwrap().is_err());
     }
 
+    #[test]
+    fn get_mempool_transactions_should_succeed() {
+        let mock = MockServerClient::new();
+        let private_key = StacksPrivateKey::new();
+        let unsigned_tx = StacksClient::build_unsigned_contract_call_transaction(
+            &mock.client.stacks_address,
+            ContractName::from("contract-name"),
+            ClarityName::from("function-name"),
+            &[],
+            &StacksPublicKey::from_private(&private_key),
+            TransactionVersion::Testnet,
+            CHAIN_ID_TESTNET,
+            0,
+        )
+        .unwrap();
+        let mut response_bytes = b"HTTP/1.1 200 OK\n\n".to_vec();
+        response_bytes.extend(unsigned_tx.serialize_to_vec());
+        response_bytes.extend(Txid([0x01; 32]).serialize_to_vec());
+
+        let h = spawn(move || mock.client.get_mempool_transactions());
+        write_response(mock.server, &response_bytes);
+        assert_eq!(h.join().unwrap().unwrap(), vec![unsigned_tx]);
+    }
+
+    #[test]
+    fn get_mempool_transactions_should_fail() {
+        let mock = MockServerClient::new();
+        let h = spawn(move || mock.client.get_mempool_transactions());
+        write_response(mock.server, b"HTTP/1.1 404 Not Found\n\n");
+        assert!(h.join().unwrap().is_err());
+    }
+
     #[test]
     fn get_peer_info_should_succeed() {
         let mock = MockServerClient::new();
@@ -1256,6 +1446,58 @@ mod tests {
         assert_eq!(h.join().unwrap().unwrap(), peer_info);
     }
 
+    #[test]
+    fn get_peer_info_should_fail_over_to_backup_node() {
+        // Nothing listens on the primary node's port
+        let (primary, primary_addr) = mock_server_random();
+        drop(primary);
+        let (backup, backup_addr) = mock_server_random();
+        let mut config = GlobalConfig::load_from_file("./src/tests/conf/signer-0.toml").unwrap();
+        config.node_host = primary_addr.to_string();
+        config.backup_node_hosts = vec![backup_addr.to_string()];
+        let client = StacksClient::from(&config);
+
+        let (response, peer_info) = build_get_peer_info_response(None, None);
+        let h = spawn(move || {
+            let result = client.get_peer_info();
+            (result, client.node_pool().active_host().to_string())
+        });
+        write_response(backup, response.as_bytes());
+        let (result, active_host) = h.join().unwrap();
+        assert_eq!(result.unwrap(), peer_info);
+        assert_eq!(active_host, backup_addr.to_string());
+    }
+
+    #[test]
+    fn check_node_health_should_prefer_caught_up_nodes() {
+        let (primary, primary_addr) = mock_server_random();
+        let (first_backup, first_backup_addr) = mock_server_random();
+        let (second_backup, second_backup_addr) = mock_server_random();
+        let mut config = GlobalConfig::load_from_file("./src/tests/conf/signer-0.toml").unwrap();
+        config.node_host = primary_addr.to_string();
+        config.backup_node_hosts = vec![
+            first_backup_addr.to_string(),
+            second_backup_addr.to_string(),
+        ];
+        let client = StacksClient::from(&config);
+
+        // The primary node is still catching up after a restart
+        let h = spawn(move || client.check_node_health().map(str::to_string));
+        write_response(
+            primary,
+            build_get_peer_info_response(Some(100), None).0.as_bytes(),
+        );
+        write_response(
+            first_backup,
+            build_get_peer_info_response(Some(110), None).0.as_bytes(),
+        );
+        write_response(
+            second_backup,
+            build_get_peer_info_response(Some(110), None).0.as_bytes(),
+        );
+        assert_eq!(h.join().unwrap(), Some(first_backup_addr.to_string()));
+    }
+
     #[test]
     fn get_last_round_should_succeed() {
         let mock = MockServerClient::new();
@@ -1351,13 +1593,12 @@ mod tests {
     #[test]
     fn get_medium_estimated_fee_ustx_should_succeed() {
         let mock = MockServerClient::new();
-        let private_key = StacksPrivateKey::new();
         let unsigned_tx = StacksClient::build_unsigned_contract_call_transaction(
             &mock.client.stacks_address,
             ContractName::from("contract-name"),
             ClarityName::from("function-name"),
             &[],
-            &private_key,
+            &mock.client.key_provider.public_key(),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             0,
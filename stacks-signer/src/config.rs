@@ -18,6 +18,7 @@ use std::fmt::Display;
 use std::fs;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use blockstack_lib::chainstate::stacks::TransactionVersion;
@@ -28,12 +29,17 @@ use stacks_common::address::{
 };
 use stacks_common::consts::{CHAIN_ID_MAINNET, CHAIN_ID_TESTNET};
 use stacks_common::types::chainstate::{StacksAddress, StacksPrivateKey, StacksPublicKey};
-use stacks_common::types::PrivateKey;
+use stacks_common::util::hash::Sha512Trunc256Sum;
 use wsts::curve::scalar::Scalar;
 
-use crate::client::SignerSlotID;
+use crate::client::{NodePool, SignerSlotID};
+use crate::keys::keystore::{read_keystore_password, KEYSTORE_PASSWORD_ENV_VAR};
+use crate::keys::{InMemoryKeyProvider, KeyProvider, KeystoreKeyProvider};
+use crate::policy::BlockPolicy;
 
 const EVENT_TIMEOUT_MS: u64 = 5000;
+/// Default interval between stacks node health checks, in milliseconds
+const NODE_HEALTH_CHECK_INTERVAL_MS: u64 = 10_000;
 // Default transaction fee to use in microstacks (if unspecificed in the config file)
 const TX_FEE_USTX: u64 = 10_000;
 
@@ -52,6 +58,9 @@ pub enum ConfigError {
     /// An unsupported address version
     #[error("Failed to convert private key to address: unsupported address version.")]
     UnsupportedAddressVersion,
+    /// The signer's key could not be loaded
+    #[error("Failed to load the signer key: {0}")]
+    KeyError(#[from] crate::keys::KeyError),
 }
 
 #[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -127,10 +136,10 @@ pub struct SignerConfig {
     pub signer_slot_ids: Vec<SignerSlotID>,
     /// The Scalar representation of the private key for signer communication
     pub ecdsa_private_key: Scalar,
-    /// The private key for this signer
-    pub stacks_private_key: StacksPrivateKey,
-    /// The node host for this signer
-    pub node_host: String,
+    /// The provider of signatures from this signer's key
+    pub key_provider: Arc<dyn KeyProvider>,
+    /// The stacks nodes for this signer
+    pub nodes: NodePool,
     /// Whether this signer is running on mainnet or not
     pub mainnet: bool,
     /// timeout to gather DkgPublicShares messages
@@ -149,6 +158,8 @@ pub struct SignerConfig {
     pub max_tx_fee_ustx: Option<u64>,
     /// The path to the signer's database file
     pub db_path: PathBuf,
+    /// The policy applied to block proposals before signing them
+    pub block_policy: BlockPolicy,
 }
 
 /// The parsed configuration for the signer
@@ -156,12 +167,17 @@ pub struct SignerConfig {
 pub struct GlobalConfig {
     /// endpoint to the stacks node
     pub node_host: String,
+    /// endpoints to backup stacks nodes, in order of preference
+    pub backup_node_hosts: Vec<String>,
+    /// How often to check the health of the stacks nodes, if there are backup nodes
+    pub node_health_check_interval: Duration,
     /// endpoint to the event receiver
     pub endpoint: SocketAddr,
-    /// The Scalar representation of the private key for signer communication
+    /// The Scalar representation of the signer's Stacks private key, which the v1 (WSTS) signer
+    /// needs in memory
     pub ecdsa_private_key: Scalar,
-    /// The signer's Stacks private key
-    pub stacks_private_key: StacksPrivateKey,
+    /// The provider of signatures from the signer's Stacks key
+    pub key_provider: Arc<dyn KeyProvider>,
     /// The signer's Stacks address
     pub stacks_address: StacksAddress,
     /// The network to use. One of "mainnet" or "testnet".
@@ -188,6 +204,8 @@ pub struct GlobalConfig {
     pub db_path: PathBuf,
     /// Metrics endpoint
     pub metrics_endpoint: Option<SocketAddr>,
+    /// The policy applied to block proposals before signing them
+    pub block_policy: BlockPolicy,
 }
 
 /// Internal struct for loading up the config file
@@ -195,11 +213,22 @@ pub struct GlobalConfig {
 struct RawConfigFile {
     /// endpoint to stacks node
     pub node_host: String,
+    /// endpoints to backup stacks nodes, in order of preference.
+    /// The signer fails over to them if `node_host` is down or falls behind.
+    pub backup_node_hosts: Option<Vec<String>>,
+    /// How often (in millisecs) to check the health of the stacks nodes
+    pub node_health_check_interval_ms: Option<u64>,
     /// endpoint to event receiver
     pub endpoint: String,
     /// The hex representation of the signer's Stacks private key used for communicating
     /// with the Stacks Node, including writing to the Stacker DB instance.
-    pub stacks_private_key: String,
+    /// Exactly one of this and `keystore_path` must be set.
+    pub stacks_private_key: Option<String>,
+    /// The path to a password-encrypted keystore holding the signer's Stacks private key
+    pub keystore_path: Option<String>,
+    /// The path to a file holding the keystore password.
+    /// If not set, the password is read from `STACKS_SIGNER_KEYSTORE_PASSWORD`.
+    pub keystore_password_file: Option<String>,
     /// The network to use. One of "mainnet" or "testnet".
     pub network: Network,
     /// The time to wait (in millisecs) for a response from the stacker-db instance
@@ -225,6 +254,64 @@ struct RawConfigFile {
     pub db_path: String,
     /// Metrics endpoint
     pub metrics_endpoint: Option<String>,
+    /// The block acceptance policy
+    pub block_policy: Option<RawBlockPolicy>,
+}
+
+/// Internal struct for loading the `[block_policy]` section of the config file
+#[derive(Deserialize, Debug, Default)]
+struct RawBlockPolicy {
+    /// The maximum size of a block, in bytes
+    pub max_block_size_bytes: Option<u64>,
+    /// The maximum cost of a block, as a percentage (1-100) of the block limit
+    pub max_block_cost_pct: Option<u64>,
+    /// The number of burn blocks after which a pending transaction must be included
+    pub pending_tx_max_age_burn_blocks: Option<u64>,
+    /// The maximum number of tenure extensions in a tenure
+    pub max_tenure_extends: Option<u64>,
+    /// Banned contract calls, as `<contract id>` or `<contract id>::<function name>`
+    #[serde(default)]
+    pub banned_contract_calls: Vec<String>,
+}
+
+impl TryFrom<RawBlockPolicy> for BlockPolicy {
+    type Error = ConfigError;
+
+    fn try_from(raw_policy: RawBlockPolicy) -> Result<Self, Self::Error> {
+        if let Some(max_block_cost_pct) = raw_policy.max_block_cost_pct {
+            if !(1..=100).contains(&max_block_cost_pct) {
+                return Err(ConfigError::BadField(
+                    "block_policy.max_block_cost_pct".to_string(),
+                    max_block_cost_pct.to_string(),
+                ));
+            }
+        }
+        if raw_policy.pending_tx_max_age_burn_blocks == Some(0) {
+            return Err(ConfigError::BadField(
+                "block_policy.pending_tx_max_age_burn_blocks".to_string(),
+                "0".to_string(),
+            ));
+        }
+        let banned_contract_calls = raw_policy
+            .banned_contract_calls
+            .iter()
+            .map(|banned| {
+                banned.parse().map_err(|_| {
+                    ConfigError::BadField(
+                        "block_policy.banned_contract_calls".to_string(),
+                        banned.clone(),
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            max_block_size_bytes: raw_policy.max_block_size_bytes,
+            max_block_cost_pct: raw_policy.max_block_cost_pct,
+            pending_tx_max_age_burn_blocks: raw_policy.pending_tx_max_age_burn_blocks,
+            max_tenure_extends: raw_policy.max_tenure_extends,
+            banned_contract_calls,
+        })
+    }
 }
 
 impl RawConfigFile {
@@ -239,6 +326,44 @@ impl RawConfigFile {
     pub fn load_from_file(path: &str) -> Result<Self, ConfigError> {
         Self::try_from(&PathBuf::from(path))
     }
+
+    /// Load the signer's key from whichever key source is configured, along with its WSTS scalar
+    fn load_key_provider(&self) -> Result<(Arc<dyn KeyProvider>, Scalar), ConfigError> {
+        match (&self.stacks_private_key, &self.keystore_path) {
+            (Some(stacks_private_key), None) => {
+                let private_key = StacksPrivateKey::from_hex(stacks_private_key).map_err(|_| {
+                    ConfigError::BadField(
+                        "stacks_private_key".to_string(),
+                        stacks_private_key.clone(),
+                    )
+                })?;
+                let key_provider = InMemoryKeyProvider::new(private_key);
+                let ecdsa_private_key = key_provider.ecdsa_private_key().map_err(|_| {
+                    ConfigError::BadField(
+                        "stacks_private_key".to_string(),
+                        stacks_private_key.clone(),
+                    )
+                })?;
+                Ok((Arc::new(key_provider), ecdsa_private_key))
+            }
+            (None, Some(keystore_path)) => {
+                let password = read_keystore_password(
+                    self.keystore_password_file
+                        .as_ref()
+                        .map(PathBuf::from)
+                        .as_deref(),
+                    KEYSTORE_PASSWORD_ENV_VAR,
+                )?;
+                let key_provider =
+                    KeystoreKeyProvider::open(&PathBuf::from(keystore_path), &password)?;
+                let ecdsa_private_key = key_provider.ecdsa_private_key()?;
+                Ok((Arc::new(key_provider), ecdsa_private_key))
+            }
+            _ => Err(ConfigError::InvalidConfig(
+                "exactly one of stacks_private_key and keystore_path must be set".to_string(),
+            )),
+        }
+    }
 }
 
 impl TryFrom<&PathBuf> for RawConfigFile {
@@ -261,6 +386,18 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
             ConfigError::BadField("node_host".to_string(), raw_data.node_host.clone())
         })?;
 
+        let backup_node_hosts = raw_data.backup_node_hosts.clone().unwrap_or_default();
+        for backup_node_host in backup_node_hosts.iter() {
+            url::Url::parse(&format!("http://{backup_node_host}")).map_err(|_| {
+                ConfigError::BadField("backup_node_hosts".to_string(), backup_node_host.clone())
+            })?;
+        }
+        let node_health_check_interval = Duration::from_millis(
+            raw_data
+                .node_health_check_interval_ms
+                .unwrap_or(NODE_HEALTH_CHECK_INTERVAL_MS),
+        );
+
         let endpoint = raw_data
             .endpoint
             .to_socket_addrs()
@@ -270,22 +407,8 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
                 ConfigError::BadField("endpoint".to_string(), raw_data.endpoint.clone())
             })?;
 
-        let stacks_private_key =
-            StacksPrivateKey::from_hex(&raw_data.stacks_private_key).map_err(|_| {
-                ConfigError::BadField(
-                    "stacks_private_key".to_string(),
-                    raw_data.stacks_private_key.clone(),
-                )
-            })?;
-
-        let ecdsa_private_key =
-            Scalar::try_from(&stacks_private_key.to_bytes()[..32]).map_err(|_| {
-                ConfigError::BadField(
-                    "stacks_private_key".to_string(),
-                    raw_data.stacks_private_key.clone(),
-                )
-            })?;
-        let stacks_public_key = StacksPublicKey::from_private(&stacks_private_key);
+        let (key_provider, ecdsa_private_key) = raw_data.load_key_provider()?;
+        let stacks_public_key = key_provider.public_key();
         let stacks_address = StacksAddress::from_public_keys(
             raw_data.network.to_address_version(),
             &AddressHashMode::SerializeP2PKH,
@@ -315,11 +438,19 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
             None => None,
         };
 
+        let block_policy = raw_data
+            .block_policy
+            .map(BlockPolicy::try_from)
+            .transpose()?
+            .unwrap_or_default();
+
         Ok(Self {
             node_host: raw_data.node_host,
+            backup_node_hosts,
+            node_health_check_interval,
             endpoint,
-            stacks_private_key,
             ecdsa_private_key,
+            key_provider,
             stacks_address,
             network: raw_data.network,
             event_timeout,
@@ -333,6 +464,7 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
             auth_password: raw_data.auth_password,
             db_path,
             metrics_endpoint,
+            block_policy,
         })
     }
 }
@@ -381,13 +513,33 @@ Metrics endpoint: {metrics_endpoint}
             node_host = self.node_host,
             endpoint = self.endpoint,
             stacks_address = self.stacks_address,
-            public_key = StacksPublicKey::from_private(&self.stacks_private_key).to_hex(),
+            public_key = self.key_provider.public_key().to_hex(),
             network = self.network,
             db_path = self.db_path.to_str().unwrap_or_default(),
             tx_fee = tx_fee,
             metrics_endpoint = metrics_endpoint,
         )
     }
+
+    /// A fingerprint of the settings that determine how the signer behaves, as opposed to where
+    /// it runs: its key, network, protocol timeouts, fees and block policy. Two hosts with the
+    /// same fingerprint run the signer the same way.
+    pub fn fingerprint(&self) -> Sha512Trunc256Sum {
+        let settings = format!(
+            "public_key={};network={};dkg_public_timeout={:?};dkg_private_timeout={:?};dkg_end_timeout={:?};nonce_timeout={:?};sign_timeout={:?};tx_fee_ustx={};max_tx_fee_ustx={:?};block_policy={:?}",
+            self.key_provider.public_key().to_hex(),
+            self.network,
+            self.dkg_public_timeout,
+            self.dkg_private_timeout,
+            self.dkg_end_timeout,
+            self.nonce_timeout,
+            self.sign_timeout,
+            self.tx_fee_ustx,
+            self.max_tx_fee_ustx,
+            self.block_policy,
+        );
+        Sha512Trunc256Sum::from_data(settings.as_bytes())
+    }
 }
 
 impl Display for GlobalConfig {
@@ -483,6 +635,7 @@ metrics_endpoint = "{metrics_endpoint}"
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keys::Keystore;
 
     #[test]
     fn build_signer_config_tomls_should_produce_deserializable_strings() {
@@ -625,6 +778,198 @@ mod tests {
         assert_eq!(Some(config.tx_fee_ustx), tx_fee_ustx);
     }
 
+    #[test]
+    fn block_policy_should_deserialize_correctly() {
+        let pk = StacksPrivateKey::from_hex(
+            "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+        )
+        .unwrap();
+        let config_toml = build_signer_config_tomls(
+            &[pk],
+            "localhost",
+            None,
+            &Network::Testnet,
+            "melon",
+            rand::random(),
+            3000,
+            None,
+            None,
+            None,
+        )
+        .remove(0);
+
+        // No policy section means an empty policy
+        let config = GlobalConfig::load_from_str(&config_toml).unwrap();
+        assert_eq!(config.block_policy, BlockPolicy::default());
+
+        let policy_toml = format!(
+            r#"
+{config_toml}
+[block_policy]
+max_block_size_bytes = 1000000
+max_block_cost_pct = 80
+pending_tx_max_age_burn_blocks = 6
+max_tenure_extends = 2
+banned_contract_calls = ["ST000000000000000000002AMW42H.pox-4::stack-stx", "ST000000000000000000002AMW42H.bns"]
+"#
+        );
+        let config = GlobalConfig::load_from_str(&policy_toml).unwrap();
+        assert_eq!(config.block_policy.max_block_size_bytes, Some(1_000_000));
+        assert_eq!(config.block_policy.max_block_cost_pct, Some(80));
+        assert_eq!(config.block_policy.pending_tx_max_age_burn_blocks, Some(6));
+        assert_eq!(config.block_policy.max_tenure_extends, Some(2));
+        assert_eq!(
+            config.block_policy.banned_contract_calls,
+            vec![
+                "ST000000000000000000002AMW42H.pox-4::stack-stx"
+                    .parse()
+                    .unwrap(),
+                "ST000000000000000000002AMW42H.bns".parse().unwrap(),
+            ]
+        );
+
+        for bad_policy in [
+            "max_block_cost_pct = 0",
+            "max_block_cost_pct = 101",
+            "pending_tx_max_age_burn_blocks = 0",
+            r#"banned_contract_calls = ["not-a-contract"]"#,
+        ] {
+            let bad_toml = format!("{config_toml}\n[block_policy]\n{bad_policy}\n");
+            assert!(matches!(
+                GlobalConfig::load_from_str(&bad_toml),
+                Err(ConfigError::BadField(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn backup_node_hosts_should_deserialize_correctly() {
+        let pk = StacksPrivateKey::from_hex(
+            "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+        )
+        .unwrap();
+        let config_toml = build_signer_config_tomls(
+            &[pk],
+            "localhost:20443",
+            None,
+            &Network::Testnet,
+            "melon",
+            rand::random(),
+            3000,
+            None,
+            None,
+            None,
+        )
+        .remove(0);
+
+        let config = GlobalConfig::load_from_str(&config_toml).unwrap();
+        assert!(config.backup_node_hosts.is_empty());
+        assert_eq!(
+            config.node_health_check_interval,
+            Duration::from_millis(NODE_HEALTH_CHECK_INTERVAL_MS)
+        );
+
+        let backup_toml = format!(
+            r#"
+backup_node_hosts = ["localhost:30443", "10.0.0.2:20443"]
+node_health_check_interval_ms = 2000
+{config_toml}
+"#
+        );
+        let config = GlobalConfig::load_from_str(&backup_toml).unwrap();
+        assert_eq!(config.node_host, "localhost:20443");
+        assert_eq!(
+            config.backup_node_hosts,
+            vec!["localhost:30443".to_string(), "10.0.0.2:20443".to_string()]
+        );
+        assert_eq!(
+            config.node_health_check_interval,
+            Duration::from_millis(2000)
+        );
+
+        let bad_toml = format!("backup_node_hosts = [\"not a host\"]\n{config_toml}");
+        assert!(matches!(
+            GlobalConfig::load_from_str(&bad_toml),
+            Err(ConfigError::BadField(..))
+        ));
+    }
+
+    #[test]
+    fn key_sources_should_be_exclusive() {
+        let dir = std::env::temp_dir().join(format!("signer-config-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let keystore_path = dir.join("keystore.json");
+        let password_path = dir.join("password");
+        fs::write(&password_path, "melon\n").unwrap();
+
+        let pk = StacksPrivateKey::from_hex(
+            "eb05c83546fdd2c79f10f5ad5434a90dd28f7e3acb7c092157aa1bc3656b012c01",
+        )
+        .unwrap();
+        Keystore::encrypt(&pk, "melon", 1_000)
+            .unwrap()
+            .save(&keystore_path)
+            .unwrap();
+
+        let config_toml = build_signer_config_tomls(
+            &[pk],
+            "localhost",
+            None,
+            &Network::Testnet,
+            "melon",
+            rand::random(),
+            3000,
+            None,
+            None,
+            None,
+        )
+        .remove(0);
+        let plaintext_config = GlobalConfig::load_from_str(&config_toml).unwrap();
+
+        let keystore_toml = config_toml.replace(
+            &format!("stacks_private_key = \"{}\"", pk.to_hex()),
+            &format!(
+                "keystore_path = \"{}\"\nkeystore_password_file = \"{}\"",
+                keystore_path.display(),
+                password_path.display()
+            ),
+        );
+        let keystore_config = GlobalConfig::load_from_str(&keystore_toml).unwrap();
+        assert_eq!(
+            keystore_config.stacks_address,
+            plaintext_config.stacks_address
+        );
+        assert_eq!(
+            keystore_config.key_provider.public_key(),
+            plaintext_config.key_provider.public_key()
+        );
+
+        // A wrong password is rejected
+        fs::write(&password_path, "lemon").unwrap();
+        assert!(matches!(
+            GlobalConfig::load_from_str(&keystore_toml),
+            Err(ConfigError::KeyError(_))
+        ));
+
+        // Only one key source may be set
+        let both_toml = format!(
+            "{config_toml}\nkeystore_path = \"{}\"\n",
+            keystore_path.display()
+        );
+        assert!(matches!(
+            GlobalConfig::load_from_str(&both_toml),
+            Err(ConfigError::InvalidConfig(_))
+        ));
+        let no_key_toml =
+            config_toml.replace(&format!("stacks_private_key = \"{}\"", pk.to_hex()), "");
+        assert!(matches!(
+            GlobalConfig::load_from_str(&no_key_toml),
+            Err(ConfigError::InvalidConfig(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_config_to_string() {
         let config = GlobalConfig::load_from_file("./src/tests/conf/signer-0.toml").unwrap();
@@ -0,0 +1,314 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use pbkdf2::pbkdf2_hmac;
+use rand_core::{OsRng, RngCore};
+use serde_derive::{Deserialize, Serialize};
+use sha2::Sha256;
+use stacks_common::types::chainstate::{StacksPrivateKey, StacksPublicKey};
+use stacks_common::types::PrivateKey;
+use stacks_common::util::hash::{hex_bytes, to_hex};
+use stacks_common::util::secp256k1::MessageSignature;
+use wsts::curve::scalar::Scalar;
+
+use crate::keys::{InMemoryKeyProvider, KeyError, KeyProvider};
+
+/// The keystore file format version written by this signer
+pub const KEYSTORE_VERSION: u32 = 1;
+/// The default number of PBKDF2 iterations for new keystores
+pub const DEFAULT_KDF_ITERATIONS: u32 = 600_000;
+/// The environment variable the keystore password is read from if no password file is given
+pub const KEYSTORE_PASSWORD_ENV_VAR: &str = "STACKS_SIGNER_KEYSTORE_PASSWORD";
+/// The environment variable a new keystore password is read from if no password file is given
+pub const KEYSTORE_NEW_PASSWORD_ENV_VAR: &str = "STACKS_SIGNER_KEYSTORE_NEW_PASSWORD";
+/// The only supported key derivation function
+const KDF_PBKDF2_HMAC_SHA256: &str = "pbkdf2-hmac-sha256";
+/// The length of the random KDF salt
+const KDF_SALT_LEN: usize = 32;
+
+/// The key derivation parameters of a keystore
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeystoreKdf {
+    /// The key derivation function. Only "pbkdf2-hmac-sha256" is supported.
+    pub algorithm: String,
+    /// The number of iterations
+    pub iterations: u32,
+    /// The hex-encoded salt
+    pub salt: String,
+}
+
+/// A password-encrypted signer key, as stored on disk.
+/// The key is encrypted with AES-256-GCM under a key derived from the password.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Keystore {
+    /// The keystore file format version
+    pub version: u32,
+    /// The hex-encoded public key, so the key can be identified without the password
+    pub public_key: String,
+    /// The key derivation parameters
+    pub kdf: KeystoreKdf,
+    /// The hex-encoded encrypted private key
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    /// Encrypt a private key with the given password
+    pub fn encrypt(
+        private_key: &StacksPrivateKey,
+        password: &str,
+        iterations: u32,
+    ) -> Result<Self, KeyError> {
+        if iterations == 0 {
+            return Err(KeyError::InvalidKeystore(
+                "KDF iterations must be positive".into(),
+            ));
+        }
+        let mut rng = OsRng;
+        let mut salt = [0u8; KDF_SALT_LEN];
+        rng.fill_bytes(&mut salt);
+
+        let encryption_key = derive_encryption_key(password, &salt, iterations);
+        let ciphertext = wsts::util::encrypt(&encryption_key, &private_key.to_bytes(), &mut rng)
+            .map_err(|_| KeyError::InvalidKeystore("Failed to encrypt private key".into()))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            public_key: StacksPublicKey::from_private(private_key).to_hex(),
+            kdf: KeystoreKdf {
+                algorithm: KDF_PBKDF2_HMAC_SHA256.into(),
+                iterations,
+                salt: to_hex(&salt),
+            },
+            ciphertext: to_hex(&ciphertext),
+        })
+    }
+
+    /// Decrypt the private key with the given password
+    pub fn decrypt(&self, password: &str) -> Result<StacksPrivateKey, KeyError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeyError::InvalidKeystore(format!(
+                "Unsupported keystore version {}",
+                self.version
+            )));
+        }
+        if self.kdf.algorithm != KDF_PBKDF2_HMAC_SHA256 {
+            return Err(KeyError::InvalidKeystore(format!(
+                "Unsupported key derivation function {}",
+                self.kdf.algorithm
+            )));
+        }
+        let salt = hex_bytes(&self.kdf.salt)
+            .map_err(|_| KeyError::InvalidKeystore("Malformed KDF salt".into()))?;
+        let ciphertext = hex_bytes(&self.ciphertext)
+            .map_err(|_| KeyError::InvalidKeystore("Malformed ciphertext".into()))?;
+
+        let encryption_key = derive_encryption_key(password, &salt, self.kdf.iterations);
+        let plaintext =
+            wsts::util::decrypt(&encryption_key, &ciphertext).map_err(|_| KeyError::BadPassword)?;
+        let private_key = StacksPrivateKey::from_slice(&plaintext)
+            .map_err(|e| KeyError::InvalidKeystore(e.to_string()))?;
+
+        if StacksPublicKey::from_private(&private_key).to_hex() != self.public_key {
+            return Err(KeyError::InvalidKeystore(
+                "Decrypted key does not match the keystore's public key".into(),
+            ));
+        }
+        Ok(private_key)
+    }
+
+    /// Re-encrypt the keystore under a new password, with a fresh salt
+    pub fn change_password(
+        &self,
+        password: &str,
+        new_password: &str,
+        iterations: u32,
+    ) -> Result<Self, KeyError> {
+        let private_key = self.decrypt(password)?;
+        Self::encrypt(&private_key, new_password, iterations)
+    }
+
+    /// Load a keystore file
+    pub fn load(path: &Path) -> Result<Self, KeyError> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| KeyError::InvalidKeystore(e.to_string()))
+    }
+
+    /// Write the keystore file, readable only by its owner.  The file is replaced atomically, so
+    /// an interrupted password rotation leaves the old keystore intact.
+    pub fn save(&self, path: &Path) -> Result<(), KeyError> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| KeyError::InvalidKeystore(e.to_string()))?;
+        let tmp_path = path.with_extension("tmp");
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// Derive the keystore's AES-256-GCM key from its password with PBKDF2-HMAC-SHA256
+fn derive_encryption_key(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut encryption_key = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut encryption_key);
+    encryption_key
+}
+
+/// Read a password from the given file, or from the environment variable `env_var` if no file is
+/// given.  A trailing newline is ignored.
+pub fn read_keystore_password(
+    password_file: Option<&Path>,
+    env_var: &str,
+) -> Result<String, KeyError> {
+    let password = match password_file {
+        Some(path) => fs::read_to_string(path)?,
+        None => std::env::var(env_var).map_err(|_| {
+            KeyError::KeyUnavailable(format!(
+                "No keystore password file given and {env_var} is not set"
+            ))
+        })?,
+    };
+    Ok(password.trim_end_matches(&['\n', '\r'][..]).to_string())
+}
+
+/// A key provider backed by a password-encrypted keystore file.  The key is decrypted once, when
+/// the provider is opened, and is only held in memory.
+pub struct KeystoreKeyProvider {
+    path: PathBuf,
+    key: InMemoryKeyProvider,
+}
+
+impl KeystoreKeyProvider {
+    /// Open and decrypt a keystore file
+    pub fn open(path: &Path, password: &str) -> Result<Self, KeyError> {
+        let private_key = Keystore::load(path)?.decrypt(password)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            key: InMemoryKeyProvider::new(private_key),
+        })
+    }
+
+    /// Get the decrypted key as a WSTS scalar, for the v1 signer
+    pub fn ecdsa_private_key(&self) -> Result<Scalar, KeyError> {
+        self.key.ecdsa_private_key()
+    }
+}
+
+impl std::fmt::Debug for KeystoreKeyProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeystoreKeyProvider")
+            .field("path", &self.path)
+            .field("public_key", &self.public_key().to_hex())
+            .finish()
+    }
+}
+
+impl KeyProvider for KeystoreKeyProvider {
+    fn public_key(&self) -> StacksPublicKey {
+        self.key.public_key()
+    }
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<MessageSignature, KeyError> {
+        self.key.sign_digest(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keystore_round_trip_and_password_change() {
+        let private_key = StacksPrivateKey::new();
+        let keystore = Keystore::encrypt(&private_key, "hunter2", 1_000).unwrap();
+        assert_eq!(
+            keystore.public_key,
+            StacksPublicKey::from_private(&private_key).to_hex()
+        );
+        assert!(!keystore.ciphertext.contains(&private_key.to_hex()[..64]));
+        assert_eq!(keystore.decrypt("hunter2").unwrap(), private_key);
+        assert!(matches!(
+            keystore.decrypt("hunter3"),
+            Err(KeyError::BadPassword)
+        ));
+
+        let rotated = keystore
+            .change_password("hunter2", "correct horse", 1_000)
+            .unwrap();
+        assert_ne!(rotated.kdf.salt, keystore.kdf.salt);
+        assert_eq!(rotated.public_key, keystore.public_key);
+        assert!(matches!(
+            rotated.decrypt("hunter2"),
+            Err(KeyError::BadPassword)
+        ));
+        assert_eq!(rotated.decrypt("correct horse").unwrap(), private_key);
+        assert!(keystore.change_password("wrong", "new", 1_000).is_err());
+    }
+
+    #[test]
+    fn keystore_file_provider() {
+        let dir = std::env::temp_dir().join(format!("signer-keystore-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("keystore.json");
+
+        let private_key = StacksPrivateKey::new();
+        Keystore::encrypt(&private_key, "hunter2", 1_000)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(KeystoreKeyProvider::open(&path, "hunter3").is_err());
+        let provider = KeystoreKeyProvider::open(&path, "hunter2").unwrap();
+        assert_eq!(
+            provider.public_key(),
+            StacksPublicKey::from_private(&private_key)
+        );
+        let digest = [7u8; 32];
+        assert_eq!(
+            provider.sign_digest(&digest).unwrap(),
+            private_key.sign(&digest).unwrap()
+        );
+        assert!(!format!("{provider:?}").contains(&private_key.to_hex()));
+
+        // A tampered keystore is rejected
+        let mut keystore = Keystore::load(&path).unwrap();
+        keystore.public_key = StacksPublicKey::from_private(&StacksPrivateKey::new()).to_hex();
+        assert!(matches!(
+            keystore.decrypt("hunter2"),
+            Err(KeyError::InvalidKeystore(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
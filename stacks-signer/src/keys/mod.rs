@@ -0,0 +1,195 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+/// The password-encrypted keystore file
+pub mod keystore;
+
+use std::fmt::Debug;
+
+use blockstack_lib::chainstate::stacks::{
+    StacksTransaction, TransactionAuth, TransactionAuthFlags, TransactionSpendingCondition,
+};
+use libstackerdb::StackerDBChunkData;
+use stacks_common::types::chainstate::{StacksPrivateKey, StacksPublicKey};
+use stacks_common::types::PrivateKey;
+use stacks_common::util::hash::Hash160;
+use stacks_common::util::secp256k1::MessageSignature;
+use wsts::curve::scalar::Scalar;
+
+pub use self::keystore::{Keystore, KeystoreKeyProvider};
+
+#[derive(thiserror::Error, Debug)]
+/// An error occurred loading or using the signer's key
+pub enum KeyError {
+    /// Failed to read or write a key file
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The keystore file is malformed
+    #[error("Invalid keystore: {0}")]
+    InvalidKeystore(String),
+    /// The keystore could not be decrypted with the given password
+    #[error("Failed to decrypt keystore: wrong password or corrupt file")]
+    BadPassword,
+    /// The digest or transaction could not be signed
+    #[error("Signing failed: {0}")]
+    SigningFailed(String),
+    /// The key could not be loaded
+    #[error("Key unavailable: {0}")]
+    KeyUnavailable(String),
+}
+
+/// A source of signatures from the signer's Stacks key.
+///
+/// Everything the signer writes on its own behalf -- StackerDB chunks, transactions, and
+/// signer-key signatures for Stacking -- is signed through this trait, so the key itself does not
+/// need to live in the config file.
+pub trait KeyProvider: Debug + Send + Sync {
+    /// The public key of the signer's Stacks key
+    fn public_key(&self) -> StacksPublicKey;
+
+    /// Produce a recoverable secp256k1 signature over a 32-byte digest
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<MessageSignature, KeyError>;
+
+    /// Sign a StackerDB chunk, committing to its slot ID, slot version, and data
+    fn sign_chunk(&self, chunk: &mut StackerDBChunkData) -> Result<(), KeyError> {
+        let digest = chunk.get_slot_metadata().auth_digest();
+        chunk.sig = self.sign_digest(digest.as_bytes())?;
+        Ok(())
+    }
+
+    /// Sign the origin of a standard single-sig transaction owned by this key
+    fn sign_transaction_origin(&self, tx: &mut StacksTransaction) -> Result<(), KeyError> {
+        let mut initial_tx = tx.clone();
+        initial_tx.auth = initial_tx.auth.into_initial_sighash_auth();
+        let initial_sighash = initial_tx.txid();
+
+        let public_key = self.public_key();
+        let TransactionAuth::Standard(TransactionSpendingCondition::Singlesig(ref mut condition)) =
+            tx.auth
+        else {
+            return Err(KeyError::SigningFailed(
+                "Only standard single-sig transactions can be signed".into(),
+            ));
+        };
+        if condition.signer != Hash160::from_node_public_key(&public_key) {
+            return Err(KeyError::SigningFailed(
+                "Transaction origin is not this signer's key".into(),
+            ));
+        }
+        let sighash_presign = TransactionSpendingCondition::make_sighash_presign(
+            &initial_sighash,
+            &TransactionAuthFlags::AuthStandard,
+            condition.tx_fee,
+            condition.nonce,
+        );
+        condition.signature = self.sign_digest(sighash_presign.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// A key provider that holds a plaintext private key, such as one taken from the signer's config
+/// file
+pub struct InMemoryKeyProvider {
+    private_key: StacksPrivateKey,
+}
+
+impl InMemoryKeyProvider {
+    /// Create a new in-memory key provider
+    pub fn new(private_key: StacksPrivateKey) -> Self {
+        Self { private_key }
+    }
+
+    /// Get the key as a WSTS scalar.  The v1 signer's WSTS state machines sign their packets and
+    /// encrypt their persisted state internally, so they need the key itself.
+    pub fn ecdsa_private_key(&self) -> Result<Scalar, KeyError> {
+        Scalar::try_from(&self.private_key.to_bytes()[..32])
+            .map_err(|_| KeyError::KeyUnavailable("Private key is not a valid scalar".into()))
+    }
+}
+
+impl Debug for InMemoryKeyProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemoryKeyProvider")
+            .field("public_key", &self.public_key().to_hex())
+            .finish()
+    }
+}
+
+impl KeyProvider for InMemoryKeyProvider {
+    fn public_key(&self) -> StacksPublicKey {
+        StacksPublicKey::from_private(&self.private_key)
+    }
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<MessageSignature, KeyError> {
+        self.private_key
+            .sign(digest)
+            .map_err(|e| KeyError::SigningFailed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blockstack_lib::chainstate::stacks::{
+        StacksTransactionSigner, TransactionAnchorMode, TransactionContractCall,
+        TransactionPayload, TransactionPostConditionMode, TransactionVersion,
+    };
+    use stacks_common::types::chainstate::StacksAddress;
+
+    use super::*;
+
+    #[test]
+    fn in_memory_provider_signs_chunks_and_transactions() {
+        let private_key = StacksPrivateKey::new();
+        let provider = InMemoryKeyProvider::new(private_key);
+        let address = StacksAddress::p2pkh(false, &provider.public_key());
+
+        let mut chunk = StackerDBChunkData::new(1, 2, vec![1, 2, 3]);
+        provider.sign_chunk(&mut chunk).unwrap();
+        assert!(chunk.verify(&address).unwrap());
+
+        let mut expected_chunk = StackerDBChunkData::new(1, 2, vec![1, 2, 3]);
+        expected_chunk.sign(&private_key).unwrap();
+        assert_eq!(chunk, expected_chunk);
+
+        let payload = TransactionPayload::ContractCall(TransactionContractCall {
+            address,
+            contract_name: "signers-voting".into(),
+            function_name: "vote".into(),
+            function_args: vec![],
+        });
+        let mut tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&private_key).unwrap(),
+            payload,
+        );
+        tx.set_origin_nonce(3);
+        tx.set_tx_fee(100);
+        tx.anchor_mode = TransactionAnchorMode::Any;
+        tx.post_condition_mode = TransactionPostConditionMode::Allow;
+
+        let mut signer = StacksTransactionSigner::new(&tx);
+        signer.sign_origin(&private_key).unwrap();
+        let expected_tx = signer.get_tx().unwrap();
+
+        provider.sign_transaction_origin(&mut tx).unwrap();
+        tx.verify().unwrap();
+        assert_eq!(tx, expected_tx);
+
+        // Another key cannot sign for this origin
+        let other = InMemoryKeyProvider::new(StacksPrivateKey::new());
+        assert!(other.sign_transaction_origin(&mut tx).is_err());
+    }
+}
@@ -26,10 +26,17 @@ pub mod cli;
 pub mod client;
 /// The configuration module for the signer
 pub mod config;
+/// Key providers for the signer's Stacks key
+pub mod keys;
 /// The monitoring server for the signer
 pub mod monitoring;
+/// The configurable block acceptance policy
+pub mod policy;
 /// The primary runloop for the signer
 pub mod runloop;
+/// In-process simulation of a signer set against mock stacks nodes, for tests
+#[cfg(any(test, feature = "testing"))]
+pub mod sim;
 /// The v0 implementation of the signer. This does not include WSTS support
 pub mod v0;
 /// The v1 implementation of the singer. This includes WSTS support
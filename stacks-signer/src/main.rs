@@ -26,23 +26,35 @@ extern crate serde;
 extern crate serde_json;
 extern crate toml;
 
+use std::fs;
 use std::io::{self, Write};
 
-use blockstack_lib::util_lib::signed_structured_data::pox4::make_pox_4_signer_key_signature;
+use blockstack_lib::util_lib::signed_structured_data::pox4::make_pox_4_signer_key_message_hash;
 use clap::Parser;
 use clarity::vm::types::QualifiedContractIdentifier;
 use libsigner::{SignerSession, StackerDBSession};
 use libstackerdb::StackerDBChunkData;
 use slog::slog_debug;
 use stacks_common::debug;
+use stacks_common::types::chainstate::StacksPrivateKey;
+use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::hash::to_hex;
-use stacks_common::util::secp256k1::{MessageSignature, Secp256k1PublicKey};
+use stacks_common::util::secp256k1::MessageSignature;
 use stacks_signer::cli::{
-    Cli, Command, GenerateStackingSignatureArgs, GetChunkArgs, GetLatestChunkArgs, PutChunkArgs,
-    RunSignerArgs, StackerDBArgs,
+    BlockHistoryArgs, Cli, Command, CreateKeystoreArgs, ExportSigningHistoryArgs, ExportStateArgs,
+    GenerateStackingSignatureArgs, GetChunkArgs, GetLatestChunkArgs, ImportSigningHistoryArgs,
+    ImportStateArgs, OutputFormat, PutChunkArgs, RotateKeystoreArgs, RunSignerArgs, StackerDBArgs,
 };
 use stacks_signer::config::GlobalConfig;
+use stacks_signer::keys::keystore::{
+    read_keystore_password, KEYSTORE_NEW_PASSWORD_ENV_VAR, KEYSTORE_PASSWORD_ENV_VAR,
+};
+use stacks_signer::keys::Keystore;
 use stacks_signer::v1;
+use stacks_signer::v1::history::{to_csv, BlockRecord, ParticipationSummary};
+use stacks_signer::v1::migration::{SignedStateBundle, StateBundle};
+use stacks_signer::v1::protection::SigningHistory;
+use stacks_signer::v1::signerdb::SignerDb;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::{fmt, EnvFilter};
 
@@ -115,20 +127,21 @@ fn handle_generate_stacking_signature(
 ) -> MessageSignature {
     let config = GlobalConfig::try_from(&args.config).unwrap();
 
-    let private_key = config.stacks_private_key;
-    let public_key = Secp256k1PublicKey::from_private(&private_key);
+    let public_key = config.key_provider.public_key();
 
-    let signature = make_pox_4_signer_key_signature(
+    let message_hash = make_pox_4_signer_key_message_hash(
         &args.pox_address,
-        &private_key, //
         args.reward_cycle.into(),
         args.method.topic(),
         config.network.to_chain_id(),
         args.period.into(),
         args.max_amount,
         args.auth_id,
-    )
-    .expect("Failed to generate signature");
+    );
+    let signature = config
+        .key_provider
+        .sign_digest(message_hash.as_bytes())
+        .expect("Failed to generate signature");
 
     let output_str = if args.json {
         serde_json::to_string(&serde_json::json!({
@@ -162,6 +175,155 @@ fn handle_check_config(args: RunSignerArgs) {
     println!("Config: {}", config);
 }
 
+fn handle_create_keystore(args: CreateKeystoreArgs) {
+    assert!(
+        !args.keystore.exists(),
+        "Keystore {} already exists",
+        args.keystore.display()
+    );
+    let private_key = match &args.private_key_file {
+        Some(path) => {
+            let private_key_hex =
+                fs::read_to_string(path).expect("Failed to read private key file");
+            StacksPrivateKey::from_hex(private_key_hex.trim()).expect("Invalid private key")
+        }
+        None => StacksPrivateKey::new(),
+    };
+    let password =
+        read_keystore_password(args.password_file.as_deref(), KEYSTORE_PASSWORD_ENV_VAR).unwrap();
+    let keystore = Keystore::encrypt(&private_key, &password, args.kdf_iterations).unwrap();
+    keystore.save(&args.keystore).unwrap();
+    println!(
+        "Created keystore {}\nSigner Public Key: 0x{}",
+        args.keystore.display(),
+        keystore.public_key
+    );
+}
+
+fn handle_rotate_keystore(args: RotateKeystoreArgs) {
+    let keystore = Keystore::load(&args.keystore).unwrap();
+    let password =
+        read_keystore_password(args.password_file.as_deref(), KEYSTORE_PASSWORD_ENV_VAR).unwrap();
+    let new_password = read_keystore_password(
+        args.new_password_file.as_deref(),
+        KEYSTORE_NEW_PASSWORD_ENV_VAR,
+    )
+    .unwrap();
+    let rotated = keystore
+        .change_password(&password, &new_password, args.kdf_iterations)
+        .unwrap();
+    rotated.save(&args.keystore).unwrap();
+    println!(
+        "Re-encrypted keystore {}\nSigner Public Key: 0x{}",
+        args.keystore.display(),
+        rotated.public_key
+    );
+}
+
+fn handle_export_signing_history(args: ExportSigningHistoryArgs) {
+    let config = GlobalConfig::try_from(&args.config).unwrap();
+    let signer_db = SignerDb::new(&config.db_path).expect("Failed to open signer DB");
+    let history = SigningHistory::export(&signer_db, &config.key_provider.public_key())
+        .expect("Failed to read signing history");
+    let json = serde_json::to_string_pretty(&history).expect("Failed to serialize JSON");
+    fs::write(&args.output, json).expect("Failed to write signing history");
+    println!(
+        "Exported {} signed blocks to {}",
+        history.signed_blocks.len(),
+        args.output.display()
+    );
+}
+
+fn handle_import_signing_history(args: ImportSigningHistoryArgs) {
+    let config = GlobalConfig::try_from(&args.config).unwrap();
+    let json = fs::read_to_string(&args.input).expect("Failed to read signing history");
+    let history: SigningHistory =
+        serde_json::from_str(&json).expect("Failed to parse signing history");
+    let signer_db = SignerDb::new(&config.db_path).expect("Failed to open signer DB");
+    let summary = history
+        .import(&signer_db, &config.key_provider.public_key())
+        .unwrap();
+    println!(
+        "Imported {} signed blocks ({} already known, {} conflicting)",
+        summary.imported, summary.already_known, summary.conflicts
+    );
+}
+
+fn handle_block_history(args: BlockHistoryArgs) {
+    let config = GlobalConfig::try_from(&args.config).unwrap();
+    let signer_db = SignerDb::new(&config.db_path).expect("Failed to open signer DB");
+    let records = BlockRecord::load(&signer_db, args.reward_cycle, get_epoch_time_ms())
+        .expect("Failed to read block history");
+    let output = match (args.summary, args.format) {
+        (false, OutputFormat::Json) => {
+            serde_json::to_string_pretty(&records).expect("Failed to serialize JSON") + "\n"
+        }
+        (false, OutputFormat::Csv) => to_csv(&records),
+        (true, OutputFormat::Json) => {
+            let summaries = ParticipationSummary::summarize(&records);
+            serde_json::to_string_pretty(&summaries).expect("Failed to serialize JSON") + "\n"
+        }
+        (true, OutputFormat::Csv) => to_csv(&ParticipationSummary::summarize(&records)),
+    };
+    print!("{output}");
+}
+
+fn handle_export_state(args: ExportStateArgs) {
+    let config = GlobalConfig::try_from(&args.config).unwrap();
+    let signer_db = SignerDb::new(&config.db_path).expect("Failed to open signer DB");
+    let bundle = StateBundle::export(
+        &signer_db,
+        &config.key_provider.public_key(),
+        args.reward_cycle,
+        config.fingerprint(),
+    )
+    .unwrap();
+    let signed_bundle = bundle.sign(config.key_provider.as_ref()).unwrap();
+    let json = serde_json::to_string_pretty(&signed_bundle).expect("Failed to serialize JSON");
+    fs::write(&args.output, json).expect("Failed to write state bundle");
+    println!(
+        "Exported the DKG state for reward cycle {}, {} blocks and {} signed blocks to {}",
+        bundle.reward_cycle,
+        bundle.blocks.len(),
+        bundle.signed_blocks.len(),
+        args.output.display()
+    );
+}
+
+fn handle_import_state(args: ImportStateArgs) {
+    let config = GlobalConfig::try_from(&args.config).unwrap();
+    let json = fs::read_to_string(&args.input).expect("Failed to read state bundle");
+    let signed_bundle: SignedStateBundle =
+        serde_json::from_str(&json).expect("Failed to parse state bundle");
+    let bundle = signed_bundle
+        .open(&config.key_provider.public_key())
+        .unwrap();
+    let mut signer_db = SignerDb::new(&config.db_path).expect("Failed to open signer DB");
+    let summary = bundle
+        .import(
+            &mut signer_db,
+            &config.ecdsa_private_key,
+            args.reward_cycle,
+            config.fingerprint(),
+        )
+        .unwrap();
+    if !summary.config_matches {
+        println!("Warning: this host's configuration differs from the exporting host's (timeouts, fees, network or block policy)");
+    }
+    let signer_state = if summary.signer_state_imported {
+        "Imported"
+    } else {
+        "Already had"
+    };
+    println!(
+        "{signer_state} the DKG state for reward cycle {}. Imported {} blocks ({} already known) and {} signed blocks",
+        bundle.reward_cycle,
+        summary.blocks_imported,
+        summary.blocks_already_known,
+        summary.signed_blocks_imported
+    );
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -192,6 +354,27 @@ fn main() {
         Command::CheckConfig(args) => {
             handle_check_config(args);
         }
+        Command::CreateKeystore(args) => {
+            handle_create_keystore(args);
+        }
+        Command::RotateKeystore(args) => {
+            handle_rotate_keystore(args);
+        }
+        Command::ExportSigningHistory(args) => {
+            handle_export_signing_history(args);
+        }
+        Command::ImportSigningHistory(args) => {
+            handle_import_signing_history(args);
+        }
+        Command::BlockHistory(args) => {
+            handle_block_history(args);
+        }
+        Command::ExportState(args) => {
+            handle_export_state(args);
+        }
+        Command::ImportState(args) => {
+            handle_import_state(args);
+        }
     }
 }
 
@@ -264,7 +447,7 @@ pub mod tests {
         };
 
         let signature = handle_generate_stacking_signature(args.clone(), false);
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.key_provider.public_key();
 
         let valid = call_verify_signer_sig(
             &args.pox_address,
@@ -287,7 +470,7 @@ pub mod tests {
         args.max_amount = 100;
 
         let signature = handle_generate_stacking_signature(args.clone(), false);
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.key_provider.public_key();
 
         let valid = call_verify_signer_sig(
             &args.pox_address,
@@ -320,7 +503,7 @@ pub mod tests {
 
         let signature = handle_generate_stacking_signature(args.clone(), false);
 
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.key_provider.public_key();
 
         let message_hash = make_pox_4_signer_key_message_hash(
             &args.pox_address,
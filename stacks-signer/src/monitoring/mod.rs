@@ -138,6 +138,17 @@ pub fn update_signer_nonce(nonce: u64) {
     prometheus::SIGNER_NONCE.set(nonce as i64);
 }
 
+/// Update the active stacks node metric
+#[allow(unused_variables)]
+pub fn update_active_stacks_node(node_hosts: &[String], active_index: usize) {
+    #[cfg(feature = "monitoring_prom")]
+    for (index, node_host) in node_hosts.iter().enumerate() {
+        prometheus::ACTIVE_STACKS_NODE
+            .with_label_values(&[node_host])
+            .set(i64::from(index == active_index));
+    }
+}
+
 /// Start a new RPC call timer.
 /// The `origin` parameter is the base path of the RPC call, e.g. `http://node.com`.
 /// The `origin` parameter is removed from `full_path` when storing in prometheus.
@@ -17,8 +17,8 @@
 use lazy_static::lazy_static;
 use prometheus::{
     gather, histogram_opts, opts, register_histogram_vec, register_int_counter,
-    register_int_counter_vec, register_int_gauge, Encoder, HistogramVec, IntCounter, IntCounterVec,
-    IntGauge, TextEncoder,
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, Encoder, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
 };
 
 lazy_static! {
@@ -88,6 +88,11 @@ lazy_static! {
         "stacks_signer_nonce",
         "The current nonce of the signer"
     )).unwrap();
+    pub static ref ACTIVE_STACKS_NODE: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_signer_active_stacks_node",
+        "Whether the signer is sending its requests to the stacks node at `node_host` (1) or not (0)",
+        &["node_host"]
+    ).unwrap();
 
     pub static ref SIGNER_RPC_CALL_LATENCIES_HISTOGRAM: HistogramVec = register_histogram_vec!(histogram_opts!(
         "stacks_signer_node_rpc_call_latencies_histogram",
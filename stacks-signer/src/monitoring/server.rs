@@ -26,6 +26,7 @@ use tiny_http::{Response as HttpResponse, Server as HttpServer};
 use super::{update_reward_cycle, update_signer_stx_balance};
 use crate::client::{ClientError, StacksClient};
 use crate::config::{GlobalConfig, Network};
+use crate::keys::KeyProvider;
 use crate::monitoring::prometheus::gather_metrics_string;
 use crate::monitoring::{update_signer_nonce, update_stacks_tip_height};
 
@@ -86,7 +87,7 @@ impl MonitoringServer {
         };
         let stacks_client = StacksClient::from(config);
         let http_server = HttpServer::http(endpoint).map_err(|_| MonitoringError::AlreadyBound)?;
-        let public_key = Secp256k1PublicKey::from_private(&config.stacks_private_key);
+        let public_key = config.key_provider.public_key();
         let mut server = MonitoringServer::new(
             http_server,
             endpoint,
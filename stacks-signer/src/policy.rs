@@ -0,0 +1,673 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use blockstack_lib::burnchains::Txid;
+use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
+use blockstack_lib::chainstate::stacks::{
+    StacksTransaction, TenureChangeCause, TransactionPayload,
+};
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
+use clarity::vm::ClarityName;
+use hashbrown::{HashMap, HashSet};
+use libsigner::v1::messages::RejectCode;
+use stacks_common::types::chainstate::ConsensusHash;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+/// A block whose largest cost dimension is at least this percentage of the block limit is
+/// considered full, and is not required to include overdue pending transactions
+const FULL_BLOCK_COST_PCT: u64 = 95;
+/// The maximum number of mempool transactions tracked for the pending transaction rule
+const MAX_PENDING_TRANSACTIONS: usize = 10_000;
+/// The maximum number of overdue transactions a block is required to include. The signer looks
+/// up the account nonce of each one, so this bounds the lookups per block proposal.
+const MAX_OVERDUE_TRANSACTIONS: usize = 25;
+
+/// A contract, or a single function of a contract, that the signer will not sign blocks calling
+#[derive(Debug, Clone, PartialEq)]
+pub struct BannedContractCall {
+    /// The banned contract
+    pub contract_id: QualifiedContractIdentifier,
+    /// The banned function. If None, every function of the contract is banned.
+    pub function_name: Option<ClarityName>,
+}
+
+impl BannedContractCall {
+    /// Check whether the transaction is a call this rule bans
+    pub fn matches(&self, tx: &StacksTransaction) -> bool {
+        let TransactionPayload::ContractCall(contract_call) = &tx.payload else {
+            return false;
+        };
+        if StandardPrincipalData::from(contract_call.address) != self.contract_id.issuer
+            || contract_call.contract_name != self.contract_id.name
+        {
+            return false;
+        }
+        // A ban without a function name covers every function of the contract
+        self.function_name.is_none()
+            || self
+                .function_name
+                .as_ref()
+                .is_some_and(|function_name| *function_name == contract_call.function_name)
+    }
+}
+
+/// Parses `<contract id>` or `<contract id>::<function name>`
+impl FromStr for BannedContractCall {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (contract_id, function_name) = match s.split_once("::") {
+            Some((contract_id, function_name)) => (contract_id, Some(function_name)),
+            None => (s, None),
+        };
+        let contract_id = QualifiedContractIdentifier::parse(contract_id)
+            .map_err(|e| format!("Invalid contract identifier {contract_id}: {e}"))?;
+        let function_name = function_name
+            .map(|name| {
+                ClarityName::try_from(name.to_string())
+                    .map_err(|e| format!("Invalid function name {name}: {e}"))
+            })
+            .transpose()?;
+        Ok(Self {
+            contract_id,
+            function_name,
+        })
+    }
+}
+
+impl Display for BannedContractCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.function_name {
+            Some(function_name) => write!(f, "{}::{function_name}", self.contract_id),
+            None => write!(f, "{}", self.contract_id),
+        }
+    }
+}
+
+/// The rules a signer applies to a block proposal, on top of the stacks node's validation,
+/// before it will sign it. Every rule is optional; the default policy accepts every valid block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockPolicy {
+    /// The maximum size of a block, in bytes
+    pub max_block_size_bytes: Option<u64>,
+    /// The maximum cost of a block, as a percentage of the block limit in its largest dimension
+    pub max_block_cost_pct: Option<u64>,
+    /// If set, a block that is not full must include every transaction that has been in the
+    /// stacks node's mempool for at least this many burn blocks and is ready to be mined
+    pub pending_tx_max_age_burn_blocks: Option<u64>,
+    /// The maximum number of tenure extensions the signer will accept in a single tenure
+    pub max_tenure_extends: Option<u64>,
+    /// Contract calls that a block must not contain
+    pub banned_contract_calls: Vec<BannedContractCall>,
+}
+
+/// A rule of the block policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyRule {
+    /// `max_block_size_bytes`
+    MaxBlockSize,
+    /// `max_block_cost_pct`
+    MaxBlockCost,
+    /// `pending_tx_max_age_burn_blocks`
+    PendingTransactions,
+    /// `max_tenure_extends`
+    TenureExtends,
+    /// `banned_contract_calls`
+    BannedContractCall,
+}
+
+impl PolicyRule {
+    /// The name of the rule, as recorded in the signer DB
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::MaxBlockSize => "max_block_size",
+            Self::MaxBlockCost => "max_block_cost",
+            Self::PendingTransactions => "pending_transactions",
+            Self::TenureExtends => "tenure_extends",
+            Self::BannedContractCall => "banned_contract_call",
+        }
+    }
+}
+
+impl FromStr for PolicyRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "max_block_size" => Ok(Self::MaxBlockSize),
+            "max_block_cost" => Ok(Self::MaxBlockCost),
+            "pending_transactions" => Ok(Self::PendingTransactions),
+            "tenure_extends" => Ok(Self::TenureExtends),
+            "banned_contract_call" => Ok(Self::BannedContractCall),
+            _ => Err(format!("Unknown policy rule: {s}")),
+        }
+    }
+}
+
+impl Display for PolicyRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The rule a block proposal broke, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyViolation {
+    /// The rule that was broken
+    pub rule: PolicyRule,
+    /// A human readable explanation
+    pub reason: String,
+}
+
+impl Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.rule, self.reason)
+    }
+}
+
+impl From<PolicyViolation> for RejectCode {
+    fn from(violation: PolicyViolation) -> Self {
+        RejectCode::PolicyViolation(violation.to_string())
+    }
+}
+
+/// What the signer knows about a block proposal when it applies the policy
+#[derive(Debug)]
+pub struct ProposalFacts<'a> {
+    /// The proposed block
+    pub block: &'a NakamotoBlock,
+    /// The size of the block, as reported by the stacks node's validation
+    pub size: u64,
+    /// The cost of the block, as reported by the stacks node's validation
+    pub cost: &'a ExecutionCost,
+    /// The block limit of the block's epoch, as reported by the stacks node. Only needed if
+    /// `BlockPolicy::requires_block_limit`; without it, the block's cost is taken to be zero.
+    pub block_limit: Option<&'a ExecutionCost>,
+    /// The number of tenure extensions already accepted in the block's tenure
+    pub prior_tenure_extends: u64,
+    /// The pending transactions the block is expected to include
+    pub overdue_transactions: &'a [Txid],
+}
+
+impl BlockPolicy {
+    /// Whether the policy needs to know the stacks node's pending transactions
+    pub fn requires_pending_transactions(&self) -> bool {
+        self.pending_tx_max_age_burn_blocks.is_some()
+    }
+
+    /// Whether the policy needs to know the block limit, to measure how full a block is
+    pub fn requires_block_limit(&self) -> bool {
+        self.max_block_cost_pct.is_some() || self.requires_pending_transactions()
+    }
+
+    /// Apply the policy to a block proposal, returning the first rule it breaks
+    pub fn evaluate(&self, facts: &ProposalFacts) -> Result<(), PolicyViolation> {
+        for tx in facts.block.txs.iter() {
+            if let Some(banned) = self
+                .banned_contract_calls
+                .iter()
+                .find(|banned| banned.matches(tx))
+            {
+                return Err(PolicyViolation {
+                    rule: PolicyRule::BannedContractCall,
+                    reason: format!("transaction {} calls banned {banned}", tx.txid()),
+                });
+            }
+        }
+
+        if let Some(max_block_size_bytes) = self.max_block_size_bytes {
+            if facts.size > max_block_size_bytes {
+                return Err(PolicyViolation {
+                    rule: PolicyRule::MaxBlockSize,
+                    reason: format!(
+                        "block is {} bytes, more than the maximum of {max_block_size_bytes}",
+                        facts.size
+                    ),
+                });
+            }
+        }
+
+        let cost_pct = facts.block_limit.map_or(0, |block_limit| {
+            block_limit.proportion_largest_dimension(facts.cost)
+        });
+        if let Some(max_block_cost_pct) = self.max_block_cost_pct {
+            if cost_pct > max_block_cost_pct {
+                return Err(PolicyViolation {
+                    rule: PolicyRule::MaxBlockCost,
+                    reason: format!(
+                        "block uses {cost_pct}% of the block limit, more than the maximum of {max_block_cost_pct}%"
+                    ),
+                });
+            }
+        }
+
+        let block_tenure_extends = count_tenure_extends(facts.block);
+        if let Some(max_tenure_extends) = self.max_tenure_extends {
+            let tenure_extends = facts
+                .prior_tenure_extends
+                .saturating_add(block_tenure_extends);
+            if block_tenure_extends > 0 && tenure_extends > max_tenure_extends {
+                return Err(PolicyViolation {
+                    rule: PolicyRule::TenureExtends,
+                    reason: format!(
+                        "block would make {tenure_extends} tenure extensions in tenure {}, more than the maximum of {max_tenure_extends}",
+                        facts.block.header.consensus_hash
+                    ),
+                });
+            }
+        }
+
+        if self.requires_pending_transactions() && cost_pct < FULL_BLOCK_COST_PCT {
+            let block_txids: HashSet<_> = facts.block.txs.iter().map(|tx| tx.txid()).collect();
+            let missing: Vec<_> = facts
+                .overdue_transactions
+                .iter()
+                .filter(|txid| !block_txids.contains(*txid))
+                .collect();
+            if !missing.is_empty() {
+                return Err(PolicyViolation {
+                    rule: PolicyRule::PendingTransactions,
+                    reason: format!(
+                        "block is {cost_pct}% full but omits {} overdue pending transactions: {missing:?}",
+                        missing.len()
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Count the tenure extensions in a block
+pub fn count_tenure_extends(block: &NakamotoBlock) -> u64 {
+    block
+        .txs
+        .iter()
+        .filter(|tx| {
+            matches!(
+                &tx.payload,
+                TransactionPayload::TenureChange(payload)
+                    if payload.cause == TenureChangeCause::Extended
+            )
+        })
+        .count() as u64
+}
+
+/// The outcome of applying the block policy to a proposal, as recorded in the signer DB
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyDecision {
+    /// The signer signature hash of the block
+    pub signer_signature_hash: Sha512Trunc256Sum,
+    /// The consensus hash of the block's tenure
+    pub consensus_hash: ConsensusHash,
+    /// The number of tenure extensions in the block
+    pub tenure_extends: u64,
+    /// The broken rule if the block was rejected, or None if it was accepted
+    pub violation: Option<PolicyViolation>,
+    /// When the decision was made, in seconds since the Unix epoch
+    pub decided_at: u64,
+}
+
+impl PolicyDecision {
+    /// Whether the block was accepted by the policy
+    pub fn accepted(&self) -> bool {
+        self.violation.is_none()
+    }
+}
+
+/// A transaction seen in the stacks node's mempool
+#[derive(Debug)]
+struct PendingTransaction {
+    tx: StacksTransaction,
+    /// The burn block height at which the transaction was first seen
+    first_seen: u64,
+    /// The mempool snapshot in which the transaction was last seen
+    last_snapshot: u64,
+}
+
+/// Tracks how long transactions have been waiting in the stacks node's mempool.
+///
+/// The signer only sees the mempool through periodic snapshots taken when it evaluates a block,
+/// so a transaction's age is measured from the first snapshot that contained it.
+#[derive(Debug, Default)]
+pub struct PendingTransactions {
+    transactions: HashMap<Txid, PendingTransaction>,
+    snapshot: u64,
+}
+
+impl PendingTransactions {
+    /// Record a snapshot of the mempool taken at the given burn block height.
+    /// Transactions that have not been seen for `max_age` burn blocks are forgotten.
+    pub fn observe(&mut self, txs: Vec<StacksTransaction>, burn_block_height: u64, max_age: u64) {
+        self.snapshot = self.snapshot.wrapping_add(1);
+        for tx in txs {
+            let txid = tx.txid();
+            if let Some(pending) = self.transactions.get_mut(&txid) {
+                pending.last_snapshot = self.snapshot;
+                continue;
+            }
+            if self.transactions.len() >= MAX_PENDING_TRANSACTIONS {
+                continue;
+            }
+            self.transactions.insert(
+                txid,
+                PendingTransaction {
+                    tx,
+                    first_seen: burn_block_height,
+                    last_snapshot: self.snapshot,
+                },
+            );
+        }
+        let snapshot = self.snapshot;
+        self.transactions.retain(|_, pending| {
+            pending.last_snapshot == snapshot
+                || burn_block_height.saturating_sub(pending.first_seen) <= max_age
+        });
+    }
+
+    /// Get the transactions in the latest snapshot that have been pending for at least `max_age`
+    /// burn blocks, oldest first and at most `MAX_OVERDUE_TRANSACTIONS` of them
+    pub fn overdue(&self, burn_block_height: u64, max_age: u64) -> Vec<&StacksTransaction> {
+        let mut overdue: Vec<_> = self
+            .transactions
+            .iter()
+            .filter(|(_, pending)| {
+                pending.last_snapshot == self.snapshot
+                    && burn_block_height.saturating_sub(pending.first_seen) >= max_age
+            })
+            .collect();
+        overdue.sort_by_key(|(txid, pending)| (pending.first_seen, txid.0));
+        overdue
+            .into_iter()
+            .take(MAX_OVERDUE_TRANSACTIONS)
+            .map(|(_, pending)| &pending.tx)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blockstack_lib::chainstate::nakamoto::NakamotoBlockHeader;
+    use blockstack_lib::chainstate::stacks::{
+        TenureChangePayload, TransactionAuth, TransactionContractCall, TransactionVersion,
+    };
+    use blockstack_lib::core::BLOCK_LIMIT_MAINNET_21;
+    use stacks_common::types::chainstate::{StacksBlockId, StacksPrivateKey};
+    use stacks_common::util::hash::Hash160;
+
+    use super::*;
+
+    fn make_tx(payload: TransactionPayload, nonce: u64) -> StacksTransaction {
+        let private_key = StacksPrivateKey::new();
+        let mut tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            TransactionAuth::from_p2pkh(&private_key).unwrap(),
+            payload,
+        );
+        tx.set_origin_nonce(nonce);
+        tx
+    }
+
+    fn make_contract_call(contract: &str, function_name: &str) -> StacksTransaction {
+        let contract_id = QualifiedContractIdentifier::parse(contract).unwrap();
+        make_tx(
+            TransactionPayload::ContractCall(TransactionContractCall {
+                address: contract_id.issuer.into(),
+                contract_name: contract_id.name,
+                function_name: function_name.into(),
+                function_args: vec![],
+            }),
+            0,
+        )
+    }
+
+    fn make_tenure_extend() -> StacksTransaction {
+        make_tx(
+            TransactionPayload::TenureChange(TenureChangePayload {
+                tenure_consensus_hash: ConsensusHash([0x01; 20]),
+                prev_tenure_consensus_hash: ConsensusHash([0x01; 20]),
+                burn_view_consensus_hash: ConsensusHash([0x01; 20]),
+                previous_tenure_end: StacksBlockId([0x02; 32]),
+                previous_tenure_blocks: 1,
+                cause: TenureChangeCause::Extended,
+                pubkey_hash: Hash160([0x03; 20]),
+            }),
+            0,
+        )
+    }
+
+    fn make_block(txs: Vec<StacksTransaction>) -> NakamotoBlock {
+        NakamotoBlock {
+            header: NakamotoBlockHeader::empty(),
+            txs,
+        }
+    }
+
+    fn evaluate(
+        policy: &BlockPolicy,
+        block: &NakamotoBlock,
+        size: u64,
+        cost: &ExecutionCost,
+        prior_tenure_extends: u64,
+        overdue_transactions: &[Txid],
+    ) -> Result<(), PolicyViolation> {
+        policy.evaluate(&ProposalFacts {
+            block,
+            size,
+            cost,
+            block_limit: Some(&BLOCK_LIMIT_MAINNET_21),
+            prior_tenure_extends,
+            overdue_transactions,
+        })
+    }
+
+    #[test]
+    fn default_policy_accepts_everything() {
+        let block = make_block(vec![
+            make_contract_call("ST000000000000000000002AMW42H.pox-4", "stack-stx"),
+            make_tenure_extend(),
+        ]);
+        let policy = BlockPolicy::default();
+        assert!(!policy.requires_pending_transactions());
+        assert!(evaluate(
+            &policy,
+            &block,
+            u64::MAX,
+            &BLOCK_LIMIT_MAINNET_21,
+            u64::MAX,
+            &[Txid([0x01; 32])]
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn banned_contract_calls() {
+        let contract_ban: BannedContractCall =
+            "ST000000000000000000002AMW42H.pox-4".parse().unwrap();
+        let function_ban: BannedContractCall = "ST000000000000000000002AMW42H.bns::name-register"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            function_ban.to_string(),
+            "ST000000000000000000002AMW42H.bns::name-register"
+        );
+        assert!("not-a-contract".parse::<BannedContractCall>().is_err());
+        assert!("ST000000000000000000002AMW42H.bns::"
+            .parse::<BannedContractCall>()
+            .is_err());
+
+        let policy = BlockPolicy {
+            banned_contract_calls: vec![contract_ban, function_ban],
+            ..Default::default()
+        };
+        let cost = ExecutionCost::zero();
+        for (contract, function_name, banned) in [
+            ("ST000000000000000000002AMW42H.pox-4", "stack-stx", true),
+            ("ST000000000000000000002AMW42H.bns", "name-register", true),
+            ("ST000000000000000000002AMW42H.bns", "name-renewal", false),
+            ("ST000000000000000000002AMW42H.pox-3", "stack-stx", false),
+        ] {
+            let block = make_block(vec![make_contract_call(contract, function_name)]);
+            let result = evaluate(&policy, &block, 0, &cost, 0, &[]);
+            if banned {
+                assert_eq!(result.unwrap_err().rule, PolicyRule::BannedContractCall);
+            } else {
+                assert!(result.is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn block_size_and_cost_limits() {
+        let policy = BlockPolicy {
+            max_block_size_bytes: Some(1_000),
+            max_block_cost_pct: Some(50),
+            ..Default::default()
+        };
+        let block = make_block(vec![]);
+        let mut cost = ExecutionCost::zero();
+        assert!(evaluate(&policy, &block, 1_000, &cost, 0, &[]).is_ok());
+        assert_eq!(
+            evaluate(&policy, &block, 1_001, &cost, 0, &[])
+                .unwrap_err()
+                .rule,
+            PolicyRule::MaxBlockSize
+        );
+
+        cost.runtime = BLOCK_LIMIT_MAINNET_21.runtime / 2;
+        assert!(evaluate(&policy, &block, 0, &cost, 0, &[]).is_ok());
+        cost.runtime = BLOCK_LIMIT_MAINNET_21.runtime;
+        let violation = evaluate(&policy, &block, 0, &cost, 0, &[]).unwrap_err();
+        assert_eq!(violation.rule, PolicyRule::MaxBlockCost);
+        assert_eq!(
+            RejectCode::from(violation.clone()),
+            RejectCode::PolicyViolation(violation.to_string())
+        );
+
+        // The cost is measured against the block limit the stacks node reports
+        let mut block_limit = BLOCK_LIMIT_MAINNET_21.clone();
+        block_limit.runtime *= 2;
+        assert!(policy
+            .evaluate(&ProposalFacts {
+                block: &block,
+                size: 0,
+                cost: &cost,
+                block_limit: Some(&block_limit),
+                prior_tenure_extends: 0,
+                overdue_transactions: &[],
+            })
+            .is_ok());
+    }
+
+    #[test]
+    fn tenure_extend_limit() {
+        let policy = BlockPolicy {
+            max_tenure_extends: Some(1),
+            ..Default::default()
+        };
+        let cost = ExecutionCost::zero();
+        let block = make_block(vec![make_tenure_extend()]);
+        assert_eq!(count_tenure_extends(&block), 1);
+        assert!(evaluate(&policy, &block, 0, &cost, 0, &[]).is_ok());
+        assert_eq!(
+            evaluate(&policy, &block, 0, &cost, 1, &[])
+                .unwrap_err()
+                .rule,
+            PolicyRule::TenureExtends
+        );
+        // Blocks without a tenure extension are not affected by earlier ones
+        assert!(evaluate(&policy, &make_block(vec![]), 0, &cost, 5, &[]).is_ok());
+    }
+
+    #[test]
+    fn overdue_transactions_must_be_included() {
+        let policy = BlockPolicy {
+            pending_tx_max_age_burn_blocks: Some(3),
+            ..Default::default()
+        };
+        assert!(policy.requires_pending_transactions());
+        let tx = make_contract_call("ST000000000000000000002AMW42H.pox-4", "stack-stx");
+        let overdue = [tx.txid()];
+        let mut cost = ExecutionCost::zero();
+
+        assert!(evaluate(
+            &policy,
+            &make_block(vec![tx.clone()]),
+            0,
+            &cost,
+            0,
+            &overdue
+        )
+        .is_ok());
+        assert_eq!(
+            evaluate(&policy, &make_block(vec![]), 0, &cost, 0, &overdue)
+                .unwrap_err()
+                .rule,
+            PolicyRule::PendingTransactions
+        );
+        // A full block may omit them
+        cost.read_count = BLOCK_LIMIT_MAINNET_21.read_count;
+        assert!(evaluate(&policy, &make_block(vec![]), 0, &cost, 0, &overdue).is_ok());
+    }
+
+    #[test]
+    fn pending_transaction_tracking() {
+        let tx_1 = make_contract_call("ST000000000000000000002AMW42H.pox-4", "stack-stx");
+        let tx_2 = make_contract_call("ST000000000000000000002AMW42H.pox-4", "delegate-stx");
+        let mut pending = PendingTransactions::default();
+
+        pending.observe(vec![tx_1.clone()], 100, 3);
+        pending.observe(vec![tx_1.clone(), tx_2.clone()], 102, 3);
+        assert!(pending.overdue(102, 3).is_empty());
+        pending.observe(vec![tx_1.clone(), tx_2.clone()], 103, 3);
+        assert_eq!(pending.overdue(103, 3), vec![&tx_1]);
+
+        // A transaction missing from the latest snapshot is not overdue, but is still tracked
+        pending.observe(vec![tx_2.clone()], 103, 3);
+        assert!(pending.overdue(103, 3).is_empty());
+        pending.observe(vec![tx_1.clone(), tx_2.clone()], 103, 3);
+        assert_eq!(pending.overdue(103, 3), vec![&tx_1]);
+
+        // Once it has been gone for long enough, it is forgotten
+        pending.observe(vec![tx_2.clone()], 105, 3);
+        assert_eq!(pending.overdue(105, 3), vec![&tx_2]);
+        pending.observe(vec![tx_1.clone()], 105, 3);
+        assert!(pending.overdue(105, 3).is_empty());
+    }
+
+    #[test]
+    fn overdue_transactions_are_capped() {
+        let old_tx = make_contract_call("ST000000000000000000002AMW42H.pox-4", "stack-stx");
+        // Each transaction is signed with a new key, so they all differ
+        let new_txs: Vec<_> = (0..MAX_OVERDUE_TRANSACTIONS)
+            .map(|_| make_contract_call("ST000000000000000000002AMW42H.pox-4", "delegate-stx"))
+            .collect();
+        let mut pending = PendingTransactions::default();
+        pending.observe(vec![old_tx.clone()], 100, 3);
+        let mut snapshot = new_txs.clone();
+        snapshot.push(old_tx.clone());
+        pending.observe(snapshot, 101, 3);
+
+        // The oldest transactions are kept
+        let overdue = pending.overdue(110, 3);
+        assert_eq!(overdue.len(), MAX_OVERDUE_TRANSACTIONS);
+        assert_eq!(overdue[0], &old_tx);
+    }
+}
@@ -16,16 +16,17 @@ use std::fmt::Debug;
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 use std::sync::mpsc::Sender;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use blockstack_lib::burnchains::PoxConstants;
 use blockstack_lib::chainstate::stacks::boot::SIGNERS_NAME;
 use blockstack_lib::util_lib::boot::boot_code_id;
 use clarity::codec::StacksMessageCodec;
-use hashbrown::HashMap;
-use libsigner::{BlockProposal, SignerEntries, SignerEvent, SignerRunLoop};
+use hashbrown::{HashMap, HashSet};
+use libsigner::{BlockProposal, SignerEntries, SignerEvent, SignerEventTrait, SignerRunLoop};
 use slog::{slog_debug, slog_error, slog_info, slog_warn};
 use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_common::{debug, error, info, warn};
 use wsts::common::MerkleRoot;
 use wsts::state_machine::OperationResult;
@@ -119,6 +120,75 @@ impl RewardCycleInfo {
     }
 }
 
+/// The number of recent events remembered to recognize duplicates
+const RECENT_EVENTS_CAPACITY: usize = 1024;
+/// How long a received event is remembered to recognize duplicates
+const RECENT_EVENTS_TTL: Duration = Duration::from_secs(60);
+
+/// The events the signer received recently.
+/// When the signer is subscribed to several stacks nodes, each of them delivers its own copy of
+/// most events; these are used to process only the first copy.
+#[derive(Debug, Default)]
+pub struct RecentEvents {
+    /// The digests of the remembered events
+    digests: HashSet<Sha512Trunc256Sum>,
+    /// The remembered events' digests, oldest first, with when they were received
+    received: VecDeque<(Sha512Trunc256Sum, Instant)>,
+}
+
+impl RecentEvents {
+    /// Remember an event received at `now`.
+    /// Returns true if the same event was already received recently.
+    pub fn is_duplicate(&mut self, digest: Sha512Trunc256Sum, now: Instant) -> bool {
+        while let Some(&(oldest, received_at)) = self.received.front() {
+            if self.received.len() < RECENT_EVENTS_CAPACITY
+                && now.saturating_duration_since(received_at) < RECENT_EVENTS_TTL
+            {
+                break;
+            }
+            self.digests.remove(&oldest);
+            self.received.pop_front();
+        }
+        if !self.digests.insert(digest) {
+            return true;
+        }
+        self.received.push_back((digest, now));
+        false
+    }
+}
+
+/// Compute the digest of an event, which is the same for every stacks node's copy of it.
+/// Status checks have no digest, since they are not delivered by the stacks nodes.
+pub fn event_digest<T: SignerEventTrait>(event: &SignerEvent<T>) -> Option<Sha512Trunc256Sum> {
+    let mut bytes = vec![];
+    match event {
+        SignerEvent::MinerMessages(messages, miner_public_key) => {
+            bytes.push(0);
+            bytes.extend(miner_public_key.to_bytes_compressed());
+            for message in messages {
+                bytes.extend(message.serialize_to_vec());
+            }
+        }
+        SignerEvent::SignerMessages(signer_set, messages) => {
+            bytes.push(1);
+            bytes.extend(signer_set.to_be_bytes());
+            for message in messages {
+                bytes.extend(message.serialize_to_vec());
+            }
+        }
+        SignerEvent::BlockValidationResponse(response) => {
+            bytes.push(2);
+            bytes.extend(serde_json::to_vec(response).ok()?);
+        }
+        SignerEvent::NewBurnBlock(burn_block_height) => {
+            bytes.push(3);
+            bytes.extend(burn_block_height.to_be_bytes());
+        }
+        SignerEvent::StatusCheck => return None,
+    }
+    Some(Sha512Trunc256Sum::from_data(&bytes))
+}
+
 /// The runloop for the stacks signer
 pub struct RunLoop<Signer, T>
 where
@@ -138,6 +208,10 @@ where
     pub commands: VecDeque<RunLoopCommand>,
     /// The current reward cycle info. Only None if the runloop is uninitialized
     pub current_reward_cycle_info: Option<RewardCycleInfo>,
+    /// The events received recently, to drop the copies delivered by backup stacks nodes
+    pub recent_events: RecentEvents,
+    /// When the health of the stacks nodes was last checked
+    pub last_node_health_check: Option<Instant>,
     /// Phantom data for the message codec
     _phantom_data: std::marker::PhantomData<T>,
 }
@@ -153,6 +227,8 @@ impl<Signer: SignerTrait<T>, T: StacksMessageCodec + Clone + Send + Debug> RunLo
             state: State::Uninitialized,
             commands: VecDeque::new(),
             current_reward_cycle_info: None,
+            recent_events: RecentEvents::default(),
+            last_node_health_check: None,
             _phantom_data: std::marker::PhantomData,
         }
     }
@@ -236,8 +312,8 @@ impl<Signer: SignerTrait<T>, T: StacksMessageCodec + Clone + Send + Debug> RunLo
             signer_entries,
             signer_slot_ids: signer_slot_ids.into_values().collect(),
             ecdsa_private_key: self.config.ecdsa_private_key,
-            stacks_private_key: self.config.stacks_private_key,
-            node_host: self.config.node_host.to_string(),
+            key_provider: self.config.key_provider.clone(),
+            nodes: self.stacks_client.node_pool().clone(),
             mainnet: self.config.network.is_mainnet(),
             dkg_end_timeout: self.config.dkg_end_timeout,
             dkg_private_timeout: self.config.dkg_private_timeout,
@@ -247,6 +323,7 @@ impl<Signer: SignerTrait<T>, T: StacksMessageCodec + Clone + Send + Debug> RunLo
             tx_fee_ustx: self.config.tx_fee_ustx,
             max_tx_fee_ustx: self.config.max_tx_fee_ustx,
             db_path: self.config.db_path.clone(),
+            block_policy: self.config.block_policy.clone(),
         })
     }
 
@@ -351,6 +428,36 @@ impl<Signer: SignerTrait<T>, T: StacksMessageCodec + Clone + Send + Debug> RunLo
         Ok(())
     }
 
+    /// If it is time to, check the health of the stacks nodes and switch to the preferred
+    /// healthy one
+    fn check_node_health(&mut self) {
+        if !self.stacks_client.node_pool().has_backups() {
+            return;
+        }
+        if let Some(last_node_health_check) = self.last_node_health_check {
+            if last_node_health_check.elapsed() < self.config.node_health_check_interval {
+                return;
+            }
+        }
+        self.stacks_client.check_node_health();
+        self.last_node_health_check = Some(Instant::now());
+    }
+
+    /// Drop an event if another stacks node already delivered it
+    fn drop_duplicate_event(&mut self, event: Option<SignerEvent<T>>) -> Option<SignerEvent<T>> {
+        if !self.stacks_client.node_pool().has_backups() {
+            return event;
+        }
+        let Some(digest) = event.as_ref().and_then(|event| event_digest(event)) else {
+            return event;
+        };
+        if self.recent_events.is_duplicate(digest, Instant::now()) {
+            debug!("Ignoring an event already delivered by another stacks node: {event:?}");
+            return None;
+        }
+        event
+    }
+
     fn cleanup_stale_signers(&mut self, current_reward_cycle: u64) {
         let mut to_delete = Vec::new();
         for (idx, signer) in &mut self.stacks_signers {
@@ -390,6 +497,8 @@ impl<Signer: SignerTrait<T>, T: StacksMessageCodec + Clone + Send + Debug>
         if let Some(cmd) = cmd {
             self.commands.push_back(cmd);
         }
+        self.check_node_health();
+        let event = self.drop_duplicate_event(event);
         if self.state == State::Uninitialized {
             if let Err(e) = self.initialize_runloop() {
                 error!("Failed to initialize signer runloop: {e}.");
@@ -438,11 +547,11 @@ impl<Signer: SignerTrait<T>, T: StacksMessageCodec + Clone + Send + Debug>
 #[cfg(test)]
 mod tests {
     use blockstack_lib::chainstate::stacks::boot::NakamotoSignerEntry;
-    use libsigner::SignerEntries;
+    use libsigner::v1::messages::SignerMessage;
     use rand::{thread_rng, Rng, RngCore};
     use stacks_common::types::chainstate::{StacksPrivateKey, StacksPublicKey};
 
-    use super::RewardCycleInfo;
+    use super::*;
 
     #[test]
     fn parse_nakamoto_signer_entries_test() {
@@ -607,4 +716,45 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn duplicate_events_are_recognized() {
+        let burn_block = SignerEvent::<SignerMessage>::NewBurnBlock(100);
+        let signer_messages = SignerEvent::<SignerMessage>::SignerMessages(
+            1,
+            vec![SignerMessage::Transactions(vec![])],
+        );
+        let burn_block_digest = event_digest(&burn_block).unwrap();
+        let signer_messages_digest = event_digest(&signer_messages).unwrap();
+        assert_ne!(burn_block_digest, signer_messages_digest);
+        assert_eq!(
+            event_digest(&SignerEvent::<SignerMessage>::NewBurnBlock(100)),
+            Some(burn_block_digest)
+        );
+        assert_ne!(
+            event_digest(&SignerEvent::<SignerMessage>::NewBurnBlock(101)),
+            Some(burn_block_digest)
+        );
+        assert_eq!(
+            event_digest(&SignerEvent::<SignerMessage>::StatusCheck),
+            None
+        );
+
+        let start = Instant::now();
+        let mut recent_events = RecentEvents::default();
+        assert!(!recent_events.is_duplicate(burn_block_digest, start));
+        assert!(!recent_events.is_duplicate(signer_messages_digest, start));
+        assert!(recent_events.is_duplicate(burn_block_digest, start + Duration::from_secs(1)));
+        // Events are forgotten after a while
+        assert!(!recent_events.is_duplicate(burn_block_digest, start + RECENT_EVENTS_TTL));
+
+        // Only the most recent events are remembered
+        let mut recent_events = RecentEvents::default();
+        for height in 0..=RECENT_EVENTS_CAPACITY as u64 {
+            let digest = event_digest(&SignerEvent::<SignerMessage>::NewBurnBlock(height)).unwrap();
+            assert!(!recent_events.is_duplicate(digest, start));
+        }
+        let first_digest = event_digest(&SignerEvent::<SignerMessage>::NewBurnBlock(0)).unwrap();
+        assert!(!recent_events.is_duplicate(first_digest, start));
+    }
 }
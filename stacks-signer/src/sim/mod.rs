@@ -0,0 +1,730 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+/// The mock stacks node and the chain state it serves
+mod node;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
+use blockstack_lib::chainstate::stacks::boot::{NakamotoSignerEntry, SIGNERS_NAME};
+use blockstack_lib::net::api::postblock_proposal::{
+    BlockValidateOk, BlockValidateReject, BlockValidateResponse, ValidateRejectCode,
+};
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::types::QualifiedContractIdentifier;
+use libsigner::v1::messages::{BlockResponse, SignerMessage};
+use libsigner::{BlockProposal, SignerEntries, SignerEvent, SignerEventTrait};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use stacks_common::codec::{read_next, StacksMessageCodec};
+use stacks_common::types::chainstate::{StacksAddress, StacksPrivateKey, StacksPublicKey};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+use wsts::curve::point::Point;
+use wsts::state_machine::OperationResult;
+
+pub use self::node::*;
+use crate::client::{SignerSlotID, StacksClient};
+use crate::config::SignerConfig;
+use crate::keys::InMemoryKeyProvider;
+use crate::policy::BlockPolicy;
+use crate::runloop::{RunLoopCommand, SignerCommand};
+use crate::Signer;
+
+/// The configuration of a simulated signer set
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// The number of signers
+    pub num_signers: u32,
+    /// The weight, i.e. the number of WSTS keys, of each signer
+    pub keys_per_signer: u32,
+    /// The reward cycle the signers are registered for
+    pub reward_cycle: u64,
+    /// Whether to simulate mainnet
+    pub mainnet: bool,
+    /// The seed of the signers' keys and of the network faults
+    pub seed: u64,
+    /// The signers' DKG public timeout
+    pub dkg_public_timeout: Option<Duration>,
+    /// The signers' DKG private timeout
+    pub dkg_private_timeout: Option<Duration>,
+    /// The signers' DKG end timeout
+    pub dkg_end_timeout: Option<Duration>,
+    /// The signers' nonce timeout
+    pub nonce_timeout: Option<Duration>,
+    /// The signers' sign timeout
+    pub sign_timeout: Option<Duration>,
+    /// The fee of the signers' transactions
+    pub tx_fee_ustx: u64,
+    /// The signers' block policy
+    pub block_policy: BlockPolicy,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            num_signers: 4,
+            keys_per_signer: 1,
+            reward_cycle: 10,
+            mainnet: false,
+            seed: 0,
+            dkg_public_timeout: None,
+            dkg_private_timeout: None,
+            dkg_end_timeout: None,
+            nonce_timeout: None,
+            sign_timeout: None,
+            tx_fee_ustx: 10_000,
+            block_policy: BlockPolicy::default(),
+        }
+    }
+}
+
+/// A signer of the simulation, with the client of its own mock node
+struct SimulatedSigner<S> {
+    signer: S,
+    stacks_client: StacksClient,
+    res_send: Sender<Vec<OperationResult>>,
+    res_recv: Receiver<Vec<OperationResult>>,
+    results: Vec<OperationResult>,
+}
+
+/// A signer message in flight to a signer
+struct InFlightMessage<T> {
+    /// The step at which the message is delivered
+    due_step: u64,
+    /// The index of the receiving signer
+    recipient: usize,
+    message: T,
+}
+
+/// Rewrites or drops (by returning `None`) the messages a byzantine signer sends
+pub type MessageMutator<T> = Box<dyn FnMut(T) -> Option<T>>;
+
+/// The faults injected into the delivery of signer messages
+struct Faults<T> {
+    /// The probability that a message is lost on its way to each recipient
+    drop_rate: f64,
+    /// The number of steps by which messages to each signer are delayed
+    delays: HashMap<usize, u64>,
+    /// The signers that neither process events nor receive messages
+    offline: HashSet<usize>,
+    /// The mutators of the messages sent by byzantine signers
+    byzantine: HashMap<usize, MessageMutator<T>>,
+}
+
+/// A set of signers run in-process against mock stacks nodes, with deterministic message
+/// delivery and injectable network faults. Only delivery is deterministic: the signers' own
+/// timeouts still run on the wall clock, so a simulation that depends on them is not.
+///
+/// Each signer talks to its own `MockNode`, and all nodes share one `MockChainState`, so that a
+/// chunk written through any node is visible through every node, as with a replicated
+/// StackerDB. The simulation advances in steps. In each step, the StackerDB writes of the
+/// previous step are delivered to the signers as `SignerEvent::SignerMessages` events, the
+/// blocks submitted for validation are answered with `SignerEvent::BlockValidationResponse`
+/// events, and every online signer processes its events and queued commands in index order.
+pub struct Simulation<S: Signer<T>, T: SignerEventTrait> {
+    config: SimulationConfig,
+    signers: Vec<SimulatedSigner<S>>,
+    chain_state: Arc<Mutex<MockChainState>>,
+    /// Kept so that the nodes are stopped when the simulation is dropped
+    _nodes: Vec<MockNode>,
+    faults: Faults<T>,
+    rng: StdRng,
+    in_flight: Vec<InFlightMessage<T>>,
+    pending_events: Vec<SignerEvent<T>>,
+    pending_commands: Vec<RunLoopCommand>,
+    rejected_blocks: HashMap<Sha512Trunc256Sum, ValidateRejectCode>,
+    messages: Vec<(u32, T)>,
+    step: u64,
+}
+
+impl<S: Signer<T>, T: SignerEventTrait> Simulation<S, T> {
+    /// Create the signer set and start a mock node for each signer
+    pub fn new(config: SimulationConfig) -> Self {
+        let private_keys: Vec<StacksPrivateKey> = (0..config.num_signers)
+            .map(|i| {
+                StacksPrivateKey::from_seed(format!("sim-signer-{}-{i}", config.seed).as_bytes())
+            })
+            .collect();
+        let reward_set: Vec<NakamotoSignerEntry> = private_keys
+            .iter()
+            .map(|private_key| {
+                let signing_key = StacksPublicKey::from_private(private_key)
+                    .to_bytes_compressed()
+                    .try_into()
+                    .expect("FATAL: compressed public key is not 33 bytes");
+                NakamotoSignerEntry {
+                    signing_key,
+                    stacked_amt: 0,
+                    weight: config.keys_per_signer,
+                }
+            })
+            .collect();
+        let signer_entries = SignerEntries::parse(config.mainnet, &reward_set)
+            .expect("FATAL: failed to parse the simulated reward set");
+
+        let mut chain_state = MockChainState::new(config.mainnet, config.reward_cycle, reward_set);
+        chain_state.burn_block_height = Self::active_reward_cycle_of(&config)
+            .saturating_mul(MOCK_REWARD_CYCLE_LENGTH)
+            .saturating_add(1);
+        chain_state.epoch_30_start_height = 1;
+        let chain_state = Arc::new(Mutex::new(chain_state));
+
+        let mut nodes = vec![];
+        let mut signers = vec![];
+        let signer_slot_ids: Vec<SignerSlotID> =
+            (0..config.num_signers).map(SignerSlotID).collect();
+        for (i, private_key) in private_keys.into_iter().enumerate() {
+            let node = MockNode::start(i, chain_state.clone())
+                .expect("FATAL: failed to start a mock stacks node");
+            let stacks_client =
+                StacksClient::new(private_key, node.addr(), "sim".to_string(), config.mainnet);
+            let address =
+                StacksAddress::p2pkh(config.mainnet, &StacksPublicKey::from_private(&private_key));
+            let signer_id = *signer_entries
+                .signer_ids
+                .get(&address)
+                .expect("FATAL: simulated signer is not in its reward set");
+            let key_provider = Arc::new(InMemoryKeyProvider::new(private_key));
+            let ecdsa_private_key = key_provider
+                .ecdsa_private_key()
+                .expect("FATAL: in-memory key provider failed");
+            let signer_config = SignerConfig {
+                reward_cycle: config.reward_cycle,
+                signer_id,
+                signer_slot_id: signer_slot_ids[i],
+                key_ids: signer_entries
+                    .signer_key_ids
+                    .get(&signer_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                signer_entries: signer_entries.clone(),
+                signer_slot_ids: signer_slot_ids.clone(),
+                ecdsa_private_key,
+                key_provider,
+                nodes: stacks_client.node_pool().clone(),
+                mainnet: config.mainnet,
+                dkg_public_timeout: config.dkg_public_timeout,
+                dkg_private_timeout: config.dkg_private_timeout,
+                dkg_end_timeout: config.dkg_end_timeout,
+                nonce_timeout: config.nonce_timeout,
+                sign_timeout: config.sign_timeout,
+                tx_fee_ustx: config.tx_fee_ustx,
+                max_tx_fee_ustx: None,
+                db_path: std::env::temp_dir()
+                    .join(format!("sim-signer-{}-{i}.sqlite", rand::random::<u64>())),
+                block_policy: config.block_policy.clone(),
+            };
+            let (res_send, res_recv) = channel();
+            signers.push(SimulatedSigner {
+                signer: S::new(signer_config),
+                stacks_client,
+                res_send,
+                res_recv,
+                results: vec![],
+            });
+            nodes.push(node);
+        }
+
+        let mut sim = Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            signers,
+            chain_state,
+            _nodes: nodes,
+            faults: Faults {
+                drop_rate: 0.0,
+                delays: HashMap::new(),
+                offline: HashSet::new(),
+                byzantine: HashMap::new(),
+            },
+            in_flight: vec![],
+            pending_events: vec![],
+            pending_commands: vec![],
+            rejected_blocks: HashMap::new(),
+            messages: vec![],
+            step: 0,
+        };
+        sim.initialize_signers();
+        sim
+    }
+
+    /// Let every signer read the (still empty) StackerDB once before the first step.
+    ///
+    /// A signer that is not yet initialized replays the DKG messages it finds in the StackerDB.
+    /// Without this, the signers after the coordinator would replay its `DkgBegin` in the first
+    /// step, restart DKG when it is delivered again in the second, and end up with shares that
+    /// do not match the aggregate key the coordinator computed.
+    fn initialize_signers(&mut self) {
+        let current_reward_cycle = self.active_reward_cycle();
+        for sim_signer in self.signers.iter_mut() {
+            sim_signer.signer.process_event(
+                &sim_signer.stacks_client,
+                None,
+                sim_signer.res_send.clone(),
+                current_reward_cycle,
+            );
+        }
+    }
+
+    fn active_reward_cycle_of(config: &SimulationConfig) -> u64 {
+        config.reward_cycle.saturating_add(2)
+    }
+
+    /// The reward cycle the mock chain is in, which is two cycles past the signers' own.
+    ///
+    /// A signer only coordinates DKG and signing rounds while its reward cycle is not the
+    /// current one, and only accepts block validation responses while the current cycle has the
+    /// parity of its own. Running the signers two cycles behind lets them do both.
+    pub fn active_reward_cycle(&self) -> u64 {
+        Self::active_reward_cycle_of(&self.config)
+    }
+
+    /// The number of signers
+    pub fn num_signers(&self) -> usize {
+        self.signers.len()
+    }
+
+    /// The signer with the given index
+    pub fn signer(&self, index: usize) -> &S {
+        &self.signers[index].signer
+    }
+
+    /// The operation results the signer with the given index has produced so far
+    pub fn operation_results(&self, index: usize) -> &[OperationResult] {
+        &self.signers[index].results
+    }
+
+    /// Every signer message written so far, with the slot of its writer, in write order.
+    /// Messages are logged as written, before any fault is applied to their delivery.
+    pub fn messages(&self) -> &[(u32, T)] {
+        &self.messages
+    }
+
+    /// The number of steps run so far
+    pub fn current_step(&self) -> u64 {
+        self.step
+    }
+
+    /// Run a closure with the chain state shared by the mock nodes
+    pub fn with_chain_state<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut MockChainState) -> R,
+    {
+        let mut chain_state = self
+            .chain_state
+            .lock()
+            .expect("FATAL: mock chain state lock poisoned");
+        f(&mut chain_state)
+    }
+
+    /// The aggregate key the signers-voting contract approved for the signers' reward cycle
+    pub fn approved_aggregate_key(&self) -> Option<Point> {
+        let reward_cycle = self.config.reward_cycle;
+        self.with_chain_state(|chain_state| {
+            chain_state
+                .approved_aggregate_keys
+                .get(&reward_cycle)
+                .cloned()
+        })
+    }
+
+    /// Lose each message on its way to each recipient with the given probability
+    pub fn set_drop_rate(&mut self, drop_rate: f64) {
+        self.faults.drop_rate = drop_rate.clamp(0.0, 1.0);
+    }
+
+    /// Delay the messages to a signer by the given number of steps
+    pub fn delay_signer(&mut self, index: usize, steps: u64) {
+        self.faults.delays.insert(index, steps);
+    }
+
+    /// Stop a signer. It processes no events or commands, and the messages due to it while it
+    /// is offline are lost.
+    pub fn take_offline(&mut self, index: usize) {
+        self.faults.offline.insert(index);
+    }
+
+    /// Restart a signer that was taken offline
+    pub fn bring_online(&mut self, index: usize) {
+        self.faults.offline.remove(&index);
+    }
+
+    /// Make a signer byzantine: every message it writes is passed through `mutator` before it is
+    /// delivered to the other signers. The chunk itself stays in the StackerDB as written.
+    pub fn make_byzantine(&mut self, index: usize, mutator: MessageMutator<T>) {
+        self.faults.byzantine.insert(index, mutator);
+    }
+
+    /// Deliver an event to every online signer in the next step
+    pub fn send_event(&mut self, event: SignerEvent<T>) {
+        self.pending_events.push(event);
+    }
+
+    /// Advance the mock chain to the next burn block, and notify the signers
+    pub fn advance_burn_block(&mut self) {
+        let height = self.with_chain_state(|chain_state| {
+            chain_state.burn_block_height = chain_state.burn_block_height.saturating_add(1);
+            chain_state.burn_block_height
+        });
+        self.send_event(SignerEvent::NewBurnBlock(height));
+    }
+
+    /// Ask every signer to sign a block in the next step. The mock nodes validate it, unless it
+    /// was scripted to be rejected with `reject_block()`.
+    pub fn propose_block(&mut self, block: NakamotoBlock) {
+        let burn_height = self.with_chain_state(|chain_state| chain_state.burn_block_height);
+        self.pending_commands.push(RunLoopCommand {
+            reward_cycle: self.config.reward_cycle,
+            command: SignerCommand::Sign {
+                block_proposal: BlockProposal {
+                    block,
+                    burn_height,
+                    reward_cycle: self.config.reward_cycle,
+                },
+                is_taproot: false,
+                merkle_root: None,
+            },
+        });
+    }
+
+    /// Make the mock nodes reject the block with the given signer signature hash when it is
+    /// submitted for validation
+    pub fn reject_block(
+        &mut self,
+        signer_signature_hash: Sha512Trunc256Sum,
+        code: ValidateRejectCode,
+    ) {
+        self.rejected_blocks.insert(signer_signature_hash, code);
+    }
+
+    /// Whether the contract holds the chunk of one of this simulation's signer set StackerDBs
+    fn is_signer_set_contract(&self, contract_id: &QualifiedContractIdentifier) -> bool {
+        let prefix = format!("{SIGNERS_NAME}-{}-", self.config.reward_cycle % 2);
+        contract_id.is_boot() && contract_id.name.as_str().starts_with(&prefix)
+    }
+
+    /// The node's answer to a block submitted for validation
+    fn validate_block(&self, block: &NakamotoBlock) -> BlockValidateResponse {
+        let signer_signature_hash = block.header.signer_signature_hash();
+        match self.rejected_blocks.get(&signer_signature_hash) {
+            Some(reason_code) => BlockValidateResponse::Reject(BlockValidateReject {
+                signer_signature_hash,
+                reason: format!("Simulated rejection: {reason_code:?}"),
+                reason_code: *reason_code,
+            }),
+            None => BlockValidateResponse::Ok(BlockValidateOk {
+                signer_signature_hash,
+                cost: ExecutionCost::zero(),
+                size: block.serialize_to_vec().len() as u64,
+            }),
+        }
+    }
+
+    /// Run one step of the simulation
+    pub fn step(&mut self) {
+        self.step = self.step.saturating_add(1);
+        let (writes, proposals) = self.with_chain_state(|chain_state| {
+            (chain_state.take_writes(), chain_state.take_proposals())
+        });
+
+        // Route the signer messages written in the last step through the faults
+        for (contract_id, chunk) in writes {
+            if !self.is_signer_set_contract(&contract_id) {
+                continue;
+            }
+            let Ok(message) = read_next::<T, _>(&mut &chunk.data[..]) else {
+                continue;
+            };
+            self.messages.push((chunk.slot_id, message.clone()));
+            let writer = usize::try_from(chunk.slot_id).unwrap_or(usize::MAX);
+            let message = match self.faults.byzantine.get_mut(&writer) {
+                Some(mutator) => match mutator(message) {
+                    Some(message) => message,
+                    None => continue,
+                },
+                None => message,
+            };
+            for recipient in 0..self.signers.len() {
+                if self.faults.drop_rate > 0.0 && self.rng.gen_bool(self.faults.drop_rate) {
+                    continue;
+                }
+                let delay = self.faults.delays.get(&recipient).copied().unwrap_or(0);
+                self.in_flight.push(InFlightMessage {
+                    due_step: self.step.saturating_add(delay),
+                    recipient,
+                    message: message.clone(),
+                });
+            }
+        }
+
+        // Each block goes back to the signer that submitted it, through its node's event observer
+        let mut validations: Vec<Vec<BlockValidateResponse>> = vec![vec![]; self.signers.len()];
+        for (node_index, block) in proposals {
+            let response = self.validate_block(&block);
+            if let Some(node_validations) = validations.get_mut(node_index) {
+                node_validations.push(response);
+            }
+        }
+
+        let (due, in_flight): (Vec<_>, Vec<_>) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|message| message.due_step <= self.step);
+        self.in_flight = in_flight;
+        let mut due_messages: Vec<Vec<T>> = vec![vec![]; self.signers.len()];
+        for message in due {
+            due_messages[message.recipient].push(message.message);
+        }
+
+        let events = std::mem::take(&mut self.pending_events);
+        let commands = std::mem::take(&mut self.pending_commands);
+        let current_reward_cycle = self.active_reward_cycle();
+        let signer_set =
+            u32::try_from(self.config.reward_cycle % 2).expect("infallible: parity fits in u32");
+        for (index, (sim_signer, (node_validations, messages))) in self
+            .signers
+            .iter_mut()
+            .zip(validations.into_iter().zip(due_messages.into_iter()))
+            .enumerate()
+        {
+            if self.faults.offline.contains(&index) {
+                continue;
+            }
+            let mut signer_events = events.clone();
+            signer_events.extend(
+                node_validations
+                    .into_iter()
+                    .map(SignerEvent::BlockValidationResponse),
+            );
+            if !messages.is_empty() {
+                signer_events.push(SignerEvent::SignerMessages(signer_set, messages));
+            }
+            if signer_events.is_empty() {
+                sim_signer.signer.process_event(
+                    &sim_signer.stacks_client,
+                    None,
+                    sim_signer.res_send.clone(),
+                    current_reward_cycle,
+                );
+            }
+            for event in signer_events.iter() {
+                sim_signer.signer.process_event(
+                    &sim_signer.stacks_client,
+                    Some(event),
+                    sim_signer.res_send.clone(),
+                    current_reward_cycle,
+                );
+            }
+            if commands.is_empty() {
+                sim_signer.signer.process_command(
+                    &sim_signer.stacks_client,
+                    current_reward_cycle,
+                    None,
+                );
+            }
+            for command in commands.iter() {
+                sim_signer.signer.process_command(
+                    &sim_signer.stacks_client,
+                    current_reward_cycle,
+                    Some(command.clone()),
+                );
+            }
+            let results: Vec<OperationResult> = sim_signer.res_recv.try_iter().flatten().collect();
+            sim_signer.results.extend(results);
+        }
+    }
+
+    /// Run steps until `done` holds, for at most `max_steps` steps.
+    /// Returns whether `done` held.
+    pub fn run_until<F>(&mut self, max_steps: u64, mut done: F) -> bool
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        for _ in 0..max_steps {
+            if done(self) {
+                return true;
+            }
+            self.step();
+        }
+        done(self)
+    }
+}
+
+/// A simulation of v1 signers
+pub type V1Simulation = Simulation<crate::v1::signer::Signer, SignerMessage>;
+
+impl V1Simulation {
+    /// The block responses the signers have written so far, in write order
+    pub fn block_responses(&self) -> Vec<BlockResponse> {
+        self.messages
+            .iter()
+            .filter_map(|(_, message)| match message {
+                SignerMessage::BlockResponse(response) => Some(response.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blockstack_lib::chainstate::nakamoto::{NakamotoBlockHeader, NakamotoBlockVote};
+
+    use super::*;
+
+    fn empty_block() -> NakamotoBlock {
+        NakamotoBlock {
+            header: NakamotoBlockHeader::empty(),
+            txs: vec![],
+        }
+    }
+
+    /// The slots of the signers that have written a block response
+    fn block_responders(sim: &V1Simulation) -> HashSet<u32> {
+        sim.messages()
+            .iter()
+            .filter(|(_, message)| matches!(message, SignerMessage::BlockResponse(_)))
+            .map(|(slot_id, _)| *slot_id)
+            .collect()
+    }
+
+    fn all_signers_have_dkg_results(sim: &V1Simulation) -> bool {
+        (0..sim.num_signers()).all(|i| {
+            sim.operation_results(i)
+                .iter()
+                .any(|result| matches!(result, OperationResult::Dkg(_)))
+        })
+    }
+
+    fn run_dkg(sim: &mut V1Simulation) -> Point {
+        assert!(
+            sim.run_until(100, |sim| {
+                sim.approved_aggregate_key().is_some() && all_signers_have_dkg_results(sim)
+            }),
+            "DKG did not complete"
+        );
+        sim.approved_aggregate_key().unwrap()
+    }
+
+    #[test]
+    fn dkg_approves_the_aggregate_key() {
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        let aggregate_key = run_dkg(&mut sim);
+        for i in 0..sim.num_signers() {
+            let dkg_keys: Vec<&Point> = sim
+                .operation_results(i)
+                .iter()
+                .filter_map(|result| match result {
+                    OperationResult::Dkg(point) => Some(point),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(dkg_keys, vec![&aggregate_key]);
+        }
+    }
+
+    #[test]
+    fn dkg_stalls_without_enough_signers() {
+        // DKG needs 90% of the keys, so every one of the 4 signers
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        sim.take_offline(3);
+        assert!(!sim.run_until(30, |sim| sim.approved_aggregate_key().is_some()));
+    }
+
+    #[test]
+    fn dkg_completes_despite_a_slow_signer() {
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        run_dkg(&mut sim);
+        let prompt_steps = sim.current_step();
+
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        sim.delay_signer(3, 3);
+        run_dkg(&mut sim);
+        assert!(sim.current_step() > prompt_steps);
+    }
+
+    #[test]
+    fn dkg_stalls_when_messages_are_lost() {
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        sim.set_drop_rate(1.0);
+        assert!(!sim.run_until(30, |sim| sim.approved_aggregate_key().is_some()));
+        // The messages were written, but none was delivered
+        assert!(!sim.messages().is_empty());
+        assert!((0..sim.num_signers()).all(|i| sim.operation_results(i).is_empty()));
+    }
+
+    #[test]
+    fn dkg_depends_on_what_a_byzantine_signer_sends() {
+        // A byzantine signer that relays its messages faithfully does not stop DKG
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        sim.make_byzantine(3, Box::new(Some));
+        run_dkg(&mut sim);
+
+        // One that withholds them does, even though its chunks are in the StackerDB
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        sim.make_byzantine(3, Box::new(|_| None));
+        assert!(!sim.run_until(30, |sim| sim.approved_aggregate_key().is_some()));
+        assert!(sim.messages().iter().any(|(slot_id, _)| *slot_id == 3));
+    }
+
+    #[test]
+    fn signers_sign_a_valid_block() {
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        let aggregate_key = run_dkg(&mut sim);
+
+        let block = empty_block();
+        let signer_signature_hash = block.header.signer_signature_hash();
+        sim.propose_block(block);
+        assert!(sim.run_until(30, |sim| !sim.block_responses().is_empty()));
+
+        // The signature is a threshold signature over the signers' vote, under the aggregate key
+        let vote = NakamotoBlockVote {
+            signer_signature_hash,
+            rejected: false,
+        }
+        .serialize_to_vec();
+        for response in sim.block_responses() {
+            let BlockResponse::Accepted((hash, signature)) = response else {
+                panic!("Expected the block to be accepted");
+            };
+            assert_eq!(hash, signer_signature_hash);
+            assert!(signature.0.verify(&aggregate_key, &vote));
+        }
+    }
+
+    #[test]
+    fn signers_reject_an_invalid_block() {
+        let mut sim = V1Simulation::new(SimulationConfig::default());
+        run_dkg(&mut sim);
+
+        let block = empty_block();
+        let signer_signature_hash = block.header.signer_signature_hash();
+        sim.reject_block(signer_signature_hash, ValidateRejectCode::BadTransaction);
+        sim.propose_block(block);
+        // Every signer validates the block and rejects it
+        let num_signers = sim.num_signers();
+        assert!(sim.run_until(20, |sim| block_responders(sim).len() == num_signers));
+        for response in sim.block_responses() {
+            let BlockResponse::Rejected(rejection) = response else {
+                panic!("Expected the block to be rejected");
+            };
+            assert_eq!(rejection.signer_signature_hash, signer_signature_hash);
+        }
+    }
+}
@@ -0,0 +1,832 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use blockstack_lib::chainstate::nakamoto::signer_set::NakamotoSigners;
+use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
+use blockstack_lib::chainstate::stacks::boot::{NakamotoSignerEntry, RewardSet, POX_4_NAME};
+use blockstack_lib::chainstate::stacks::StacksTransaction;
+use blockstack_lib::net::api::callreadonly::{CallReadOnlyRequestBody, CallReadOnlyResponse};
+use blockstack_lib::net::api::getaccount::AccountEntryResponse;
+use blockstack_lib::net::api::getinfo::RPCPeerInfoData;
+use blockstack_lib::net::api::getpoxinfo::{
+    RPCPoxCurrentCycleInfo, RPCPoxEpoch, RPCPoxInfoData, RPCPoxNextCycleInfo,
+};
+use blockstack_lib::net::api::getstackers::GetStackersResponse;
+use blockstack_lib::net::api::postblock_proposal::NakamotoBlockProposal;
+use blockstack_lib::net::api::postfeerate::{RPCFeeEstimate, RPCFeeEstimateResponse};
+use blockstack_lib::net::api::poststackerdbchunk::StackerDBErrorCodes;
+use blockstack_lib::util_lib::boot::boot_code_id;
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, TupleData};
+use clarity::vm::Value as ClarityValue;
+use libsigner::v1::messages::{MessageSlotID, SignerMessage};
+use libstackerdb::{SlotMetadata, StackerDBChunkAckData, StackerDBChunkData};
+use serde::Serialize;
+use slog::{slog_debug, slog_warn};
+use stacks_common::codec::{read_next, StacksMessageCodec};
+use stacks_common::deps_common::httparse;
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, ConsensusHash, StacksAddress, StacksPublicKey,
+};
+use stacks_common::types::{Address, StacksEpochId};
+use stacks_common::util::hash::Sha256Sum;
+use stacks_common::{debug, warn};
+use wsts::curve::point::Point;
+
+/// The length of a reward cycle on the mock chain, in burn blocks
+pub const MOCK_REWARD_CYCLE_LENGTH: u64 = 20;
+/// The length of a prepare phase on the mock chain, in burn blocks
+pub const MOCK_PREPARE_PHASE_LENGTH: u64 = 5;
+/// The fee every transaction is estimated to need, in uSTX
+const MOCK_TX_FEE_USTX: u64 = 1_000;
+
+/// A DKG vote cast with the emulated signers-voting contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockVote {
+    /// The aggregate key voted for
+    pub aggregate_key: Point,
+    /// The weight of the voter
+    pub weight: u128,
+}
+
+/// The chain state shared by the mock stacks nodes of a simulation.
+///
+/// It holds an in-memory replica of every StackerDB, and emulates the read-only functions of
+/// the signers-voting contract that the signer calls. Vote transactions are mined as soon as
+/// they are submitted to the mempool, or written to the signers' transactions StackerDB, if
+/// their nonce is the next one for their account.
+#[derive(Debug)]
+pub struct MockChainState {
+    /// Whether the chain is mainnet
+    pub mainnet: bool,
+    /// The reward cycle the signers are registered for
+    pub reward_cycle: u64,
+    /// The reward set of `reward_cycle`
+    pub reward_set: Vec<NakamotoSignerEntry>,
+    /// The current burn block height
+    pub burn_block_height: u64,
+    /// The burn block height at which epoch 3.0 starts. Epoch 2.5 starts at 0.
+    pub epoch_30_start_height: u64,
+    /// The next nonce of each account
+    pub nonces: HashMap<StacksAddress, u64>,
+    /// The transactions that have been mined, in order
+    pub mined_transactions: Vec<StacksTransaction>,
+    /// The transactions waiting in the mempool for an earlier nonce to be mined
+    pub mempool: Vec<StacksTransaction>,
+    /// The approved aggregate key of each reward cycle
+    pub approved_aggregate_keys: HashMap<u64, Point>,
+    /// The DKG votes of each (reward cycle, round), by voter
+    pub votes: HashMap<(u64, u64), HashMap<StacksAddress, MockVote>>,
+    /// The signer address of each StackerDB slot, in slot order
+    signer_addresses: Vec<StacksAddress>,
+    /// The chunks of each StackerDB, by slot ID
+    stackerdbs: HashMap<QualifiedContractIdentifier, BTreeMap<u32, StackerDBChunkData>>,
+    /// The chunks written since the last call to `take_writes()`
+    writes: Vec<(QualifiedContractIdentifier, StackerDBChunkData)>,
+    /// The blocks submitted for validation since the last call to `take_proposals()`, with
+    /// the index of the node each was submitted to
+    proposals: Vec<(usize, NakamotoBlock)>,
+}
+
+impl MockChainState {
+    /// Create the chain state for the given reward set. Each signer writes to the StackerDB
+    /// slot of its index in the reward set.
+    pub fn new(mainnet: bool, reward_cycle: u64, reward_set: Vec<NakamotoSignerEntry>) -> Self {
+        let signer_addresses = reward_set
+            .iter()
+            .map(|entry| {
+                let public_key = StacksPublicKey::from_slice(&entry.signing_key)
+                    .expect("FATAL: invalid signing key in the reward set");
+                StacksAddress::p2pkh(mainnet, &public_key)
+            })
+            .collect();
+        Self {
+            mainnet,
+            reward_cycle,
+            reward_set,
+            burn_block_height: 0,
+            epoch_30_start_height: 0,
+            nonces: HashMap::new(),
+            mined_transactions: vec![],
+            mempool: vec![],
+            approved_aggregate_keys: HashMap::new(),
+            votes: HashMap::new(),
+            signer_addresses,
+            stackerdbs: HashMap::new(),
+            writes: vec![],
+            proposals: vec![],
+        }
+    }
+
+    /// The signer address of each StackerDB slot
+    pub fn signer_addresses(&self) -> &[StacksAddress] {
+        &self.signer_addresses
+    }
+
+    /// Get the latest chunk in a StackerDB slot
+    pub fn get_chunk(
+        &self,
+        contract_id: &QualifiedContractIdentifier,
+        slot_id: u32,
+    ) -> Option<&StackerDBChunkData> {
+        self.stackerdbs.get(contract_id)?.get(&slot_id)
+    }
+
+    /// Take the chunks written since the last call, in the order they were written
+    pub fn take_writes(&mut self) -> Vec<(QualifiedContractIdentifier, StackerDBChunkData)> {
+        std::mem::take(&mut self.writes)
+    }
+
+    /// Take the blocks submitted for validation since the last call, with the index of the node
+    /// each was submitted to
+    pub fn take_proposals(&mut self) -> Vec<(usize, NakamotoBlock)> {
+        std::mem::take(&mut self.proposals)
+    }
+
+    /// The signers-voting contract's threshold weight: 70% of the total weight, rounded up
+    pub fn threshold_weight(&self) -> u128 {
+        let total_weight: u128 = self
+            .reward_set
+            .iter()
+            .map(|entry| u128::from(entry.weight))
+            .sum();
+        total_weight.saturating_mul(70).saturating_add(99) / 100
+    }
+
+    /// Store a chunk in a StackerDB, as a stacks node would
+    pub fn put_chunk(
+        &mut self,
+        contract_id: QualifiedContractIdentifier,
+        chunk: StackerDBChunkData,
+    ) -> StackerDBChunkAckData {
+        let nack =
+            |code: StackerDBErrorCodes, metadata: Option<SlotMetadata>| StackerDBChunkAckData {
+                accepted: false,
+                reason: Some(code.reason().to_string()),
+                metadata,
+                code: Some(code.code()),
+            };
+        let Some(signer_address) = usize::try_from(chunk.slot_id)
+            .ok()
+            .and_then(|slot_id| self.signer_addresses.get(slot_id))
+        else {
+            return nack(StackerDBErrorCodes::NoSuchSlot, None);
+        };
+        if !chunk.verify(signer_address).unwrap_or(false) {
+            return nack(StackerDBErrorCodes::BadSigner, None);
+        }
+        let slots = self.stackerdbs.entry(contract_id.clone()).or_default();
+        if let Some(stored) = slots.get(&chunk.slot_id) {
+            if stored.slot_version >= chunk.slot_version {
+                return nack(
+                    StackerDBErrorCodes::DataAlreadyExists,
+                    Some(stored.get_slot_metadata()),
+                );
+            }
+        }
+        let metadata = chunk.get_slot_metadata();
+        slots.insert(chunk.slot_id, chunk.clone());
+
+        // miners include the signers' transactions in their blocks
+        let transactions_contract_id =
+            MessageSlotID::Transactions.stacker_db_contract(self.mainnet, self.reward_cycle);
+        if contract_id == transactions_contract_id {
+            if let Ok(SignerMessage::Transactions(txs)) = read_next(&mut &chunk.data[..]) {
+                for tx in txs {
+                    self.mine_transaction(&tx);
+                }
+            }
+        }
+        self.writes.push((contract_id, chunk));
+        StackerDBChunkAckData {
+            accepted: true,
+            reason: None,
+            metadata: Some(metadata),
+            code: None,
+        }
+    }
+
+    /// Add a transaction to the mempool, and mine every transaction whose nonce is now due
+    pub fn submit_transaction(&mut self, tx: StacksTransaction) {
+        self.mempool.push(tx);
+        while let Some(index) = self.mempool.iter().position(|tx| {
+            tx.get_origin_nonce() == self.nonces.get(&tx.origin_address()).copied().unwrap_or(0)
+        }) {
+            let tx = self.mempool.remove(index);
+            self.mine_transaction(&tx);
+        }
+    }
+
+    /// Mine a transaction if its nonce is the next one for its account, applying it to the
+    /// emulated signers-voting contract if it is a DKG vote
+    fn mine_transaction(&mut self, tx: &StacksTransaction) {
+        let origin = tx.origin_address();
+        let nonce = self.nonces.entry(origin).or_insert(0);
+        if tx.get_origin_nonce() != *nonce {
+            debug!(
+                "Mock node: not mining transaction {} with nonce {} (expected {nonce})",
+                tx.txid(),
+                tx.get_origin_nonce()
+            );
+            return;
+        }
+        *nonce = nonce.saturating_add(1);
+        self.mined_transactions.push(tx.clone());
+
+        let Some(params) = NakamotoSigners::parse_vote_for_aggregate_public_key(tx) else {
+            return;
+        };
+        if params.reward_cycle != self.reward_cycle
+            || self
+                .approved_aggregate_keys
+                .contains_key(&params.reward_cycle)
+        {
+            return;
+        }
+        // the contract only counts a vote from the signer at the given index
+        let Some(weight) = usize::try_from(params.signer_index)
+            .ok()
+            .filter(|index| self.signer_addresses.get(*index) == Some(&origin))
+            .and_then(|index| self.reward_set.get(index))
+            .map(|entry| u128::from(entry.weight))
+        else {
+            warn!(
+                "Mock node: ignoring DKG vote from {origin} with signer index {}",
+                params.signer_index
+            );
+            return;
+        };
+        let round_votes = self
+            .votes
+            .entry((params.reward_cycle, params.voting_round))
+            .or_default();
+        if round_votes.contains_key(&origin) {
+            return;
+        }
+        round_votes.insert(
+            origin,
+            MockVote {
+                aggregate_key: params.aggregate_key,
+                weight,
+            },
+        );
+        let key_weight: u128 = round_votes
+            .values()
+            .filter(|vote| vote.aggregate_key == params.aggregate_key)
+            .map(|vote| vote.weight)
+            .sum();
+        if key_weight >= self.threshold_weight() {
+            debug!(
+                "Mock node: approved aggregate key {} for reward cycle {}",
+                params.aggregate_key, params.reward_cycle
+            );
+            self.approved_aggregate_keys
+                .insert(params.reward_cycle, params.aggregate_key);
+        }
+    }
+
+    /// Evaluate a read-only function of the signers-voting or signers contracts
+    fn call_read_only(
+        &self,
+        function_name: &str,
+        args: Vec<ClarityValue>,
+    ) -> Result<ClarityValue, String> {
+        let uint_arg = |i: usize| -> Result<u64, String> {
+            let value = args
+                .get(i)
+                .cloned()
+                .ok_or_else(|| format!("Missing argument {i}"))?
+                .expect_u128()
+                .map_err(|e| e.to_string())?;
+            u64::try_from(value).map_err(|e| e.to_string())
+        };
+        let key_value = |point: &Point| {
+            ClarityValue::buff_from(point.compress().data.to_vec()).map_err(|e| e.to_string())
+        };
+        let some = |value: ClarityValue| ClarityValue::some(value).map_err(|e| e.to_string());
+        let tuple = |data: Vec<(&str, ClarityValue)>| {
+            TupleData::from_data(
+                data.into_iter()
+                    .map(|(name, value)| (name.into(), value))
+                    .collect(),
+            )
+            .map(ClarityValue::Tuple)
+            .map_err(|e| e.to_string())
+        };
+
+        match function_name {
+            "get-approved-aggregate-key" => match self.approved_aggregate_keys.get(&uint_arg(0)?) {
+                Some(point) => some(key_value(point)?),
+                None => Ok(ClarityValue::none()),
+            },
+            "get-vote" => {
+                let voter = args
+                    .get(2)
+                    .cloned()
+                    .ok_or("Missing argument 2")?
+                    .expect_principal()
+                    .map_err(|e| e.to_string())?;
+                let PrincipalData::Standard(voter) = voter else {
+                    return Ok(ClarityValue::none());
+                };
+                let voter = StacksAddress::from(voter);
+                match self
+                    .votes
+                    .get(&(uint_arg(0)?, uint_arg(1)?))
+                    .and_then(|round_votes| round_votes.get(&voter))
+                {
+                    Some(vote) => some(tuple(vec![
+                        ("aggregate-public-key", key_value(&vote.aggregate_key)?),
+                        ("signer-weight", ClarityValue::UInt(vote.weight)),
+                    ])?),
+                    None => Ok(ClarityValue::none()),
+                }
+            }
+            "get-round-info" => match self.votes.get(&(uint_arg(0)?, uint_arg(1)?)) {
+                Some(round_votes) => some(tuple(vec![
+                    ("votes-count", ClarityValue::UInt(round_votes.len() as u128)),
+                    (
+                        "votes-weight",
+                        ClarityValue::UInt(round_votes.values().map(|vote| vote.weight).sum()),
+                    ),
+                ])?),
+                None => Ok(ClarityValue::none()),
+            },
+            "get-threshold-weight" => Ok(ClarityValue::UInt(self.threshold_weight())),
+            "get-last-round" => {
+                let reward_cycle = uint_arg(0)?;
+                let last_round = self
+                    .votes
+                    .keys()
+                    .filter(|(cycle, _)| *cycle == reward_cycle)
+                    .map(|(_, round)| *round)
+                    .max();
+                match last_round {
+                    Some(round) => some(ClarityValue::UInt(round.into())),
+                    None => Ok(ClarityValue::none()),
+                }
+            }
+            "stackerdb-get-signer-slots-page" => {
+                let slots = self
+                    .signer_addresses
+                    .iter()
+                    .map(|address| {
+                        tuple(vec![
+                            ("signer", ClarityValue::Principal((*address).into())),
+                            ("num-slots", ClarityValue::UInt(1)),
+                        ])
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                ClarityValue::cons_list_unsanitized(slots)
+                    .and_then(ClarityValue::okay)
+                    .map_err(|e| e.to_string())
+            }
+            _ => Err(format!("Unknown function {function_name}")),
+        }
+    }
+
+    fn peer_info(&self) -> RPCPeerInfoData {
+        RPCPeerInfoData {
+            peer_version: 0,
+            pox_consensus: ConsensusHash([0u8; 20]),
+            burn_block_height: self.burn_block_height,
+            stable_pox_consensus: ConsensusHash([0u8; 20]),
+            stable_burn_block_height: self.burn_block_height,
+            server_version: "mock stacks node".to_string(),
+            network_id: 0,
+            parent_network_id: 0,
+            stacks_tip_height: self.mined_transactions.len() as u64,
+            stacks_tip: BlockHeaderHash([0u8; 32]),
+            stacks_tip_consensus_hash: ConsensusHash([0u8; 20]),
+            genesis_chainstate_hash: Sha256Sum::zero(),
+            unanchored_tip: None,
+            unanchored_seq: None,
+            exit_at_block_height: None,
+            node_public_key: None,
+            node_public_key_hash: None,
+            affirmations: None,
+            last_pox_anchor: None,
+            stackerdbs: Some(
+                self.stackerdbs
+                    .keys()
+                    .map(|contract_id| contract_id.to_string())
+                    .collect(),
+            ),
+        }
+    }
+
+    fn pox_info(&self) -> RPCPoxInfoData {
+        let reward_cycle = self.burn_block_height / MOCK_REWARD_CYCLE_LENGTH;
+        let next_cycle_start = reward_cycle
+            .saturating_add(1)
+            .saturating_mul(MOCK_REWARD_CYCLE_LENGTH);
+        let next_prepare_start = next_cycle_start.saturating_sub(MOCK_PREPARE_PHASE_LENGTH);
+        let epoch = |epoch_id, start_height, end_height| RPCPoxEpoch {
+            epoch_id,
+            start_height,
+            end_height,
+            block_limit: ExecutionCost::max_value(),
+            network_epoch: 0,
+        };
+        RPCPoxInfoData {
+            contract_id: boot_code_id(POX_4_NAME, self.mainnet).to_string(),
+            pox_activation_threshold_ustx: 0,
+            first_burnchain_block_height: 0,
+            current_burnchain_block_height: self.burn_block_height,
+            prepare_phase_block_length: MOCK_PREPARE_PHASE_LENGTH,
+            reward_phase_block_length: MOCK_REWARD_CYCLE_LENGTH - MOCK_PREPARE_PHASE_LENGTH,
+            reward_slots: 0,
+            rejection_fraction: None,
+            total_liquid_supply_ustx: 0,
+            current_cycle: RPCPoxCurrentCycleInfo {
+                id: reward_cycle,
+                min_threshold_ustx: 0,
+                stacked_ustx: 0,
+                is_pox_active: true,
+            },
+            next_cycle: RPCPoxNextCycleInfo {
+                id: reward_cycle.saturating_add(1),
+                min_threshold_ustx: 0,
+                min_increment_ustx: 0,
+                stacked_ustx: 0,
+                prepare_phase_start_block_height: next_prepare_start,
+                blocks_until_prepare_phase: (next_prepare_start as i64)
+                    .saturating_sub(self.burn_block_height as i64),
+                reward_phase_start_block_height: next_cycle_start,
+                blocks_until_reward_phase: next_cycle_start.saturating_sub(self.burn_block_height),
+                ustx_until_pox_rejection: None,
+            },
+            epochs: vec![
+                epoch(StacksEpochId::Epoch25, 0, self.epoch_30_start_height),
+                epoch(StacksEpochId::Epoch30, self.epoch_30_start_height, u64::MAX),
+            ],
+            min_amount_ustx: 0,
+            prepare_cycle_length: MOCK_PREPARE_PHASE_LENGTH,
+            reward_cycle_id: reward_cycle,
+            reward_cycle_length: MOCK_REWARD_CYCLE_LENGTH,
+            rejection_votes_left_required: None,
+            next_reward_cycle_in: next_cycle_start.saturating_sub(self.burn_block_height),
+            contract_versions: vec![],
+        }
+    }
+
+    /// Serve an RPC request received by the node with the given index
+    fn handle_request(
+        &mut self,
+        node_index: usize,
+        verb: &str,
+        path: &str,
+        body: &[u8],
+    ) -> MockResponse {
+        let path = path.split('?').next().unwrap_or_default();
+        let parts: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        match (verb, parts.as_slice()) {
+            ("GET", ["v2", "info"]) => MockResponse::json(200, &self.peer_info()),
+            ("GET", ["v2", "pox"]) => MockResponse::json(200, &self.pox_info()),
+            ("GET", ["v2", "accounts", address]) => {
+                let Some(address) = StacksAddress::from_string(address) else {
+                    return MockResponse::bad_request("Invalid address");
+                };
+                MockResponse::json(
+                    200,
+                    &AccountEntryResponse {
+                        balance: format!("0x{:032x}", 0),
+                        locked: format!("0x{:032x}", 0),
+                        unlock_height: 0,
+                        nonce: self.nonces.get(&address).copied().unwrap_or(0),
+                        balance_proof: None,
+                        nonce_proof: None,
+                    },
+                )
+            }
+            ("GET", ["v2", "stacker_set", reward_cycle]) => {
+                if reward_cycle.parse::<u64>().ok() != Some(self.reward_cycle) {
+                    return MockResponse::not_found();
+                }
+                let mut stacker_set = RewardSet::empty();
+                stacker_set.signers = Some(self.reward_set.clone());
+                MockResponse::json(200, &GetStackersResponse { stacker_set })
+            }
+            ("POST", ["v2", "fees", "transaction"]) => MockResponse::json(
+                200,
+                &RPCFeeEstimateResponse {
+                    estimated_cost: ExecutionCost::zero(),
+                    estimated_cost_scalar: 1,
+                    estimations: vec![
+                        RPCFeeEstimate {
+                            fee_rate: 1.0,
+                            fee: MOCK_TX_FEE_USTX,
+                        };
+                        3
+                    ],
+                    cost_scalar_change_by_byte: 0.0,
+                },
+            ),
+            ("POST", ["v2", "mempool", "query"]) => {
+                let mut stream = vec![];
+                for tx in self.mempool.iter() {
+                    tx.consensus_serialize(&mut stream)
+                        .expect("FATAL: failed to serialize a transaction to a Vec");
+                }
+                MockResponse::bytes(stream)
+            }
+            ("POST", ["v2", "transactions"]) => {
+                let Ok(tx) = StacksTransaction::consensus_deserialize(&mut &body[..]) else {
+                    return MockResponse::bad_request("Invalid transaction");
+                };
+                let txid = tx.txid();
+                self.submit_transaction(tx);
+                MockResponse::json(200, &txid.to_hex())
+            }
+            ("POST", ["v2", "block_proposal"]) => {
+                let Ok(proposal) = serde_json::from_slice::<NakamotoBlockProposal>(body) else {
+                    return MockResponse::bad_request("Invalid block proposal");
+                };
+                self.proposals.push((node_index, proposal.block));
+                MockResponse::json(
+                    202,
+                    &serde_json::json!({
+                        "result": "Accepted",
+                        "message": "Block proposal is processing, result will be returned via the event observer"
+                    }),
+                )
+            }
+            ("POST", ["v2", "contracts", "call-read", _address, _contract, function_name]) => {
+                let Ok(request) = serde_json::from_slice::<CallReadOnlyRequestBody>(body) else {
+                    return MockResponse::bad_request("Invalid read-only call");
+                };
+                let args: Result<Vec<_>, _> = request
+                    .arguments
+                    .iter()
+                    .map(|hex| ClarityValue::try_deserialize_hex_untyped(hex))
+                    .collect();
+                let Ok(args) = args else {
+                    return MockResponse::bad_request("Invalid read-only call arguments");
+                };
+                let response = match self
+                    .call_read_only(function_name, args)
+                    .and_then(|value| value.serialize_to_hex().map_err(|e| format!("{e:?}")))
+                {
+                    Ok(hex) => CallReadOnlyResponse {
+                        okay: true,
+                        result: Some(format!("0x{hex}")),
+                        cause: None,
+                    },
+                    Err(cause) => CallReadOnlyResponse {
+                        okay: false,
+                        result: None,
+                        cause: Some(cause),
+                    },
+                };
+                MockResponse::json(200, &response)
+            }
+            ("POST", ["v2", "stackerdb", address, contract, "chunks"]) => {
+                let Some(contract_id) = parse_contract_id(address, contract) else {
+                    return MockResponse::not_found();
+                };
+                let Ok(chunk) = serde_json::from_slice::<StackerDBChunkData>(body) else {
+                    return MockResponse::bad_request("Invalid chunk");
+                };
+                MockResponse::json(200, &self.put_chunk(contract_id, chunk))
+            }
+            ("GET", ["v2", "stackerdb", address, contract]) => {
+                let Some(contract_id) = parse_contract_id(address, contract) else {
+                    return MockResponse::not_found();
+                };
+                let metadata: Vec<SlotMetadata> = self
+                    .stackerdbs
+                    .get(&contract_id)
+                    .map(|slots| {
+                        slots
+                            .values()
+                            .map(|chunk| chunk.get_slot_metadata())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                MockResponse::json(200, &metadata)
+            }
+            ("GET", ["v2", "stackerdb", address, contract, slot_id, rest @ ..])
+                if rest.len() <= 1 =>
+            {
+                let chunk = parse_contract_id(address, contract)
+                    .zip(slot_id.parse::<u32>().ok())
+                    .and_then(|(contract_id, slot_id)| self.get_chunk(&contract_id, slot_id));
+                let version = rest.first().map(|version| version.parse::<u32>().ok());
+                match (chunk, version) {
+                    (Some(chunk), None) => MockResponse::bytes(chunk.data.clone()),
+                    (Some(chunk), Some(Some(version))) if chunk.slot_version == version => {
+                        MockResponse::bytes(chunk.data.clone())
+                    }
+                    _ => MockResponse::not_found(),
+                }
+            }
+            _ => {
+                warn!("Mock node: no handler for {verb} {path}");
+                MockResponse::not_found()
+            }
+        }
+    }
+}
+
+/// Parse the contract ID of a StackerDB from an RPC path
+fn parse_contract_id(address: &str, contract: &str) -> Option<QualifiedContractIdentifier> {
+    QualifiedContractIdentifier::parse(&format!("{address}.{contract}")).ok()
+}
+
+/// A response from the mock node
+struct MockResponse {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl MockResponse {
+    fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec(value)
+                .expect("FATAL: failed to serialize infallible structure"),
+        }
+    }
+
+    fn bytes(body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/octet-stream",
+            body,
+        }
+    }
+
+    fn bad_request(reason: &str) -> Self {
+        Self {
+            status: 400,
+            content_type: "text/plain",
+            body: reason.as_bytes().to_vec(),
+        }
+    }
+
+    fn not_found() -> Self {
+        Self {
+            status: 404,
+            content_type: "text/plain",
+            body: b"Not found".to_vec(),
+        }
+    }
+
+    fn write_to<W: Write>(&self, fd: &mut W) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            _ => "Not Found",
+        };
+        write!(
+            fd,
+            "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        )?;
+        fd.write_all(&self.body)?;
+        fd.flush()
+    }
+}
+
+/// A mock stacks node, which serves the RPC endpoints the signer uses from a chain state it
+/// shares with the other mock nodes of the simulation
+#[derive(Debug)]
+pub struct MockNode {
+    /// The address the node listens on
+    addr: SocketAddr,
+    /// Set to stop the node
+    stopped: Arc<AtomicBool>,
+    /// The thread serving requests
+    server_thread: Option<JoinHandle<()>>,
+}
+
+impl MockNode {
+    /// Start a mock node on a random local port. `node_index` identifies the node in the chain
+    /// state's list of submitted block proposals.
+    pub fn start(node_index: usize, chain_state: Arc<Mutex<MockChainState>>) -> io::Result<Self> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        let addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let server_stopped = stopped.clone();
+        let server_thread = std::thread::Builder::new()
+            .name(format!("mock-stacks-node-{node_index}"))
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    if server_stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let result = stream.and_then(|stream| {
+                        Self::serve_connection(node_index, &chain_state, stream)
+                    });
+                    if let Err(e) = result {
+                        warn!("Mock node {node_index}: failed to serve a request: {e}");
+                    }
+                }
+            })?;
+        Ok(Self {
+            addr,
+            stopped,
+            server_thread: Some(server_thread),
+        })
+    }
+
+    /// The address the node listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Read one HTTP request from the stream, and answer it
+    fn serve_connection(
+        node_index: usize,
+        chain_state: &Mutex<MockChainState>,
+        mut stream: TcpStream,
+    ) -> io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let mut buf = vec![];
+        let mut read_buf = [0u8; 4096];
+        let (verb, path, body_offset, content_length) = loop {
+            let num_read = stream.read(&mut read_buf)?;
+            if num_read == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            buf.extend_from_slice(&read_buf[..num_read]);
+            let mut headers = [httparse::EMPTY_HEADER; 32];
+            let mut request = httparse::Request::new(&mut headers);
+            let status = request.parse(&buf).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid request: {e:?}"),
+                )
+            })?;
+            if let httparse::Status::Complete(body_offset) = status {
+                let content_length = request
+                    .headers
+                    .iter()
+                    .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|header| std::str::from_utf8(header.value).ok())
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                break (
+                    request.method.unwrap_or_default().to_string(),
+                    request.path.unwrap_or_default().to_string(),
+                    body_offset,
+                    content_length,
+                );
+            }
+        };
+        let body_end = body_offset.saturating_add(content_length);
+        while buf.len() < body_end {
+            let num_read = stream.read(&mut read_buf)?;
+            if num_read == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            buf.extend_from_slice(&read_buf[..num_read]);
+        }
+
+        let response = chain_state
+            .lock()
+            .expect("FATAL: mock chain state lock poisoned")
+            .handle_request(node_index, &verb, &path, &buf[body_offset..body_end]);
+        debug!(
+            "Mock node {node_index}: {verb} {path} -> {}",
+            response.status
+        );
+        response.write_to(&mut stream)
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wake the server thread up so it sees that it has been stopped
+        let _ = TcpStream::connect(self.addr);
+        if let Some(server_thread) = self.server_thread.take() {
+            let _ = server_thread.join();
+        }
+    }
+}
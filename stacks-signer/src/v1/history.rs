@@ -0,0 +1,416 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use blockstack_lib::util_lib::db::Error as DBError;
+use hashbrown::HashSet;
+use serde_derive::Serialize;
+use stacks_common::types::chainstate::{ConsensusHash, StacksBlockId};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+use crate::v1::signer::BlockInfo;
+use crate::v1::signerdb::SignerDb;
+
+/// How long the signer waits for the stacks node to validate a block before the block counts as
+/// timed out, in milliseconds
+pub const BLOCK_VALIDATION_TIMEOUT_MS: u128 = 60_000;
+
+/// The signer's decision on a block proposal
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockDecision {
+    /// The block was validated, and the signer would sign it
+    Accepted,
+    /// The block was rejected by the stacks node or by the signer
+    Rejected,
+    /// The stacks node never validated the block
+    TimedOut,
+    /// The block is still waiting for validation
+    Pending,
+}
+
+impl BlockDecision {
+    /// Determine the signer's decision on a block, as of `now_ms`
+    pub fn of(block_info: &BlockInfo, now_ms: u128) -> Self {
+        match block_info.is_valid() {
+            Some(true) => Self::Accepted,
+            Some(false) => Self::Rejected,
+            None => match block_info.proposed_at_ms {
+                Some(proposed_at_ms)
+                    if now_ms.saturating_sub(proposed_at_ms) < BLOCK_VALIDATION_TIMEOUT_MS =>
+                {
+                    Self::Pending
+                }
+                _ => Self::TimedOut,
+            },
+        }
+    }
+
+    /// Get the decision's name
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+            Self::TimedOut => "timed_out",
+            Self::Pending => "pending",
+        }
+    }
+}
+
+impl Display for BlockDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A record that can be written as a CSV row
+pub trait CsvRecord {
+    /// The column names
+    const HEADER: &'static [&'static str];
+
+    /// The column values, in the order of `HEADER`
+    fn csv_fields(&self) -> Vec<String>;
+}
+
+/// Write the records as CSV, with a header row
+pub fn to_csv<T: CsvRecord>(records: &[T]) -> String {
+    let mut csv = T::HEADER.join(",");
+    csv.push('\n');
+    for record in records {
+        let fields: Vec<_> = record
+            .csv_fields()
+            .iter()
+            .map(|field| escape_csv_field(field))
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains a separator, a quote or a line break
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A block the signer has seen, and what it decided about it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BlockRecord {
+    /// The reward cycle the block belongs to
+    pub reward_cycle: u64,
+    /// The burn block height at which the block was proposed
+    pub burn_block_height: u64,
+    /// The block's signer signature hash
+    pub signer_signature_hash: Sha512Trunc256Sum,
+    /// The block's ID
+    pub block_id: StacksBlockId,
+    /// The consensus hash of the block's tenure
+    pub consensus_hash: ConsensusHash,
+    /// The block's height
+    pub block_height: u64,
+    /// The signer's decision on the block
+    pub decision: BlockDecision,
+    /// Why the block was rejected, if it was
+    pub rejection_reason: Option<String>,
+    /// How long the stacks node took to validate the block, in milliseconds
+    pub validation_latency_ms: Option<u128>,
+    /// Whether the signer issued a signature share for the block
+    pub signed: bool,
+}
+
+impl BlockRecord {
+    /// Describe a block the signer has seen, as of `now_ms`
+    pub fn new(block_info: &BlockInfo, signed: bool, now_ms: u128) -> Self {
+        Self {
+            reward_cycle: block_info.reward_cycle,
+            burn_block_height: block_info.burn_block_height,
+            signer_signature_hash: block_info.signer_signature_hash(),
+            block_id: block_info.block.block_id(),
+            consensus_hash: block_info.block.header.consensus_hash,
+            block_height: block_info.block.header.chain_length,
+            decision: BlockDecision::of(block_info, now_ms),
+            rejection_reason: block_info.rejection_reason.clone(),
+            validation_latency_ms: block_info.validation_latency_ms(),
+            signed,
+        }
+    }
+
+    /// Load the blocks the signer has seen in the given reward cycle, or in every reward cycle
+    pub fn load(
+        signer_db: &SignerDb,
+        reward_cycle: Option<u64>,
+        now_ms: u128,
+    ) -> Result<Vec<Self>, DBError> {
+        let signed_blocks: HashSet<_> = signer_db
+            .get_signed_blocks()?
+            .into_iter()
+            .map(|signed_block| {
+                (
+                    signed_block.reward_cycle,
+                    signed_block.signer_signature_hash,
+                )
+            })
+            .collect();
+        Ok(signer_db
+            .get_blocks(reward_cycle)?
+            .iter()
+            .map(|block_info| {
+                let signed = signed_blocks
+                    .contains(&(block_info.reward_cycle, block_info.signer_signature_hash()));
+                Self::new(block_info, signed, now_ms)
+            })
+            .collect())
+    }
+}
+
+impl CsvRecord for BlockRecord {
+    const HEADER: &'static [&'static str] = &[
+        "reward_cycle",
+        "burn_block_height",
+        "signer_signature_hash",
+        "block_id",
+        "consensus_hash",
+        "block_height",
+        "decision",
+        "rejection_reason",
+        "validation_latency_ms",
+        "signed",
+    ];
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.reward_cycle.to_string(),
+            self.burn_block_height.to_string(),
+            self.signer_signature_hash.to_string(),
+            self.block_id.to_string(),
+            self.consensus_hash.to_string(),
+            self.block_height.to_string(),
+            self.decision.to_string(),
+            self.rejection_reason.clone().unwrap_or_default(),
+            self.validation_latency_ms
+                .map(|latency| latency.to_string())
+                .unwrap_or_default(),
+            self.signed.to_string(),
+        ]
+    }
+}
+
+/// How the signer participated in a reward cycle
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ParticipationSummary {
+    /// The reward cycle
+    pub reward_cycle: u64,
+    /// The number of blocks the signer saw
+    pub blocks: u64,
+    /// The number of blocks the signer accepted
+    pub accepted: u64,
+    /// The number of blocks the signer rejected
+    pub rejected: u64,
+    /// The number of blocks that timed out waiting for validation
+    pub timed_out: u64,
+    /// The number of blocks still waiting for validation
+    pub pending: u64,
+    /// The number of blocks the signer issued a signature share for
+    pub signed: u64,
+    /// The fraction of the blocks the signer reached a decision on, out of those that are not
+    /// pending. None if every block is pending.
+    pub participation_rate: Option<f64>,
+    /// The mean time the stacks node took to validate a block, in milliseconds
+    pub mean_validation_latency_ms: Option<u128>,
+}
+
+impl ParticipationSummary {
+    /// Summarize the signer's participation in each reward cycle of the given blocks
+    pub fn summarize(records: &[BlockRecord]) -> Vec<Self> {
+        let mut summaries: BTreeMap<u64, (Self, Vec<u128>)> = BTreeMap::new();
+        for record in records {
+            let (summary, latencies) = summaries.entry(record.reward_cycle).or_insert_with(|| {
+                (
+                    Self {
+                        reward_cycle: record.reward_cycle,
+                        ..Self::default()
+                    },
+                    vec![],
+                )
+            });
+            summary.blocks += 1;
+            match record.decision {
+                BlockDecision::Accepted => summary.accepted += 1,
+                BlockDecision::Rejected => summary.rejected += 1,
+                BlockDecision::TimedOut => summary.timed_out += 1,
+                BlockDecision::Pending => summary.pending += 1,
+            }
+            if record.signed {
+                summary.signed += 1;
+            }
+            latencies.extend(record.validation_latency_ms);
+        }
+        summaries
+            .into_values()
+            .map(|(mut summary, latencies)| {
+                let decided = summary.accepted + summary.rejected;
+                let expected = decided + summary.timed_out;
+                if expected > 0 {
+                    summary.participation_rate = Some(decided as f64 / expected as f64);
+                }
+                if !latencies.is_empty() {
+                    summary.mean_validation_latency_ms =
+                        Some(latencies.iter().sum::<u128>() / latencies.len() as u128);
+                }
+                summary
+            })
+            .collect()
+    }
+}
+
+impl CsvRecord for ParticipationSummary {
+    const HEADER: &'static [&'static str] = &[
+        "reward_cycle",
+        "blocks",
+        "accepted",
+        "rejected",
+        "timed_out",
+        "pending",
+        "signed",
+        "participation_rate",
+        "mean_validation_latency_ms",
+    ];
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.reward_cycle.to_string(),
+            self.blocks.to_string(),
+            self.accepted.to_string(),
+            self.rejected.to_string(),
+            self.timed_out.to_string(),
+            self.pending.to_string(),
+            self.signed.to_string(),
+            self.participation_rate
+                .map(|rate| format!("{rate:.4}"))
+                .unwrap_or_default(),
+            self.mean_validation_latency_ms
+                .map(|latency| latency.to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blockstack_lib::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockHeader};
+    use libsigner::BlockProposal;
+
+    use super::*;
+
+    fn block_info(reward_cycle: u64, burn_height: u64, proposed_at_ms: u128) -> BlockInfo {
+        let mut header = NakamotoBlockHeader::empty();
+        header.chain_length = burn_height;
+        let mut block_info = BlockInfo::from(BlockProposal {
+            block: NakamotoBlock {
+                header,
+                txs: vec![],
+            },
+            burn_height,
+            reward_cycle,
+        });
+        block_info.proposed_at_ms = Some(proposed_at_ms);
+        block_info
+    }
+
+    #[test]
+    fn block_decisions() {
+        let now_ms = 1_000_000;
+        let pending = block_info(1, 100, now_ms - 1_000);
+        assert_eq!(BlockDecision::of(&pending, now_ms), BlockDecision::Pending);
+        assert_eq!(
+            BlockDecision::of(&pending, now_ms + BLOCK_VALIDATION_TIMEOUT_MS),
+            BlockDecision::TimedOut
+        );
+
+        // Blocks recorded before proposal times were kept have timed out if they were never
+        // validated
+        let mut unknown = block_info(1, 100, now_ms);
+        unknown.proposed_at_ms = None;
+        assert_eq!(BlockDecision::of(&unknown, now_ms), BlockDecision::TimedOut);
+    }
+
+    #[test]
+    fn participation_summary() {
+        let now_ms = 1_000_000;
+        let records = vec![
+            BlockRecord {
+                decision: BlockDecision::Accepted,
+                validation_latency_ms: Some(100),
+                signed: true,
+                ..BlockRecord::new(&block_info(1, 100, 0), false, now_ms)
+            },
+            BlockRecord {
+                decision: BlockDecision::Rejected,
+                rejection_reason: Some("too big, sorry".into()),
+                validation_latency_ms: Some(300),
+                ..BlockRecord::new(&block_info(1, 101, 0), false, now_ms)
+            },
+            BlockRecord::new(&block_info(1, 102, 0), false, now_ms),
+            BlockRecord::new(&block_info(2, 103, now_ms), false, now_ms),
+        ];
+        assert_eq!(records[2].decision, BlockDecision::TimedOut);
+        assert_eq!(records[3].decision, BlockDecision::Pending);
+
+        let summaries = ParticipationSummary::summarize(&records);
+        assert_eq!(
+            summaries,
+            vec![
+                ParticipationSummary {
+                    reward_cycle: 1,
+                    blocks: 3,
+                    accepted: 1,
+                    rejected: 1,
+                    timed_out: 1,
+                    pending: 0,
+                    signed: 1,
+                    participation_rate: Some(2.0 / 3.0),
+                    mean_validation_latency_ms: Some(200),
+                },
+                ParticipationSummary {
+                    reward_cycle: 2,
+                    blocks: 1,
+                    pending: 1,
+                    ..ParticipationSummary::default()
+                },
+            ]
+        );
+
+        let csv = to_csv(&summaries);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some(ParticipationSummary::HEADER.join(",").as_str())
+        );
+        assert_eq!(lines.next(), Some("1,3,1,1,1,0,1,0.6667,200"));
+        assert_eq!(lines.next(), Some("2,1,0,0,0,1,0,,"));
+        assert_eq!(lines.next(), None);
+
+        // Rejection reasons are quoted
+        let csv = to_csv(&records[1..2]);
+        assert!(csv.contains(",rejected,\"too big, sorry\",300,false\n"));
+    }
+}
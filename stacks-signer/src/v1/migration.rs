@@ -0,0 +1,482 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use blockstack_lib::util_lib::db::Error as DBError;
+use serde_derive::{Deserialize, Serialize};
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::get_epoch_time_secs;
+use stacks_common::util::hash::{hex_bytes, to_hex, Sha512Trunc256Sum};
+use stacks_common::util::secp256k1::MessageSignature;
+use wsts::curve::scalar::Scalar;
+
+use crate::keys::{KeyError, KeyProvider};
+use crate::v1::protection::SignedBlock;
+use crate::v1::signer::{decrypt, BlockInfo};
+use crate::v1::signerdb::SignerDb;
+
+/// The state bundle format version written by this signer
+pub const STATE_BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Domain separator of the digest a state bundle's signature commits to
+const STATE_BUNDLE_DIGEST_PREFIX: &[u8] = b"SIGNER_STATE_BUNDLE/";
+
+#[derive(thiserror::Error, Debug)]
+/// An error occurred exporting or importing a signer's state
+pub enum MigrationError {
+    /// The signer DB could not be read or written
+    #[error("Signer DB error: {0}")]
+    DBError(#[from] DBError),
+    /// The bundle could not be signed
+    #[error("Failed to sign the state bundle: {0}")]
+    KeyError(#[from] KeyError),
+    /// The bundle could not be encoded or decoded
+    #[error("Malformed state bundle: {0}")]
+    Malformed(String),
+    /// The bundle has an unsupported format version
+    #[error("Unsupported state bundle format version {0}")]
+    UnsupportedVersion(u32),
+    /// The bundle's signature is not valid
+    #[error("Invalid state bundle signature: {0}")]
+    BadSignature(String),
+    /// The bundle belongs to another signer
+    #[error("State bundle belongs to signer {found}, not {expected}")]
+    SignerMismatch {
+        /// The public key of this signer
+        expected: String,
+        /// The public key the bundle was signed with
+        found: String,
+    },
+    /// The bundle holds the state of another reward cycle
+    #[error("State bundle is for reward cycle {found}, not {expected}")]
+    RewardCycleMismatch {
+        /// The reward cycle being migrated
+        expected: u64,
+        /// The reward cycle in the bundle
+        found: u64,
+    },
+    /// The signer DB has no DKG state for the reward cycle
+    #[error("No DKG state for reward cycle {0} in the signer DB")]
+    NoSignerState(u64),
+    /// The bundle's DKG state cannot be decrypted with this signer's key
+    #[error("DKG state in the bundle cannot be decrypted with this signer's key")]
+    UndecryptableState,
+    /// The signer DB already holds different DKG state for the reward cycle
+    #[error("Signer DB already has different DKG state for reward cycle {0}")]
+    ConflictingSignerState(u64),
+}
+
+/// A snapshot of a signer's state for one reward cycle, used to move the signer to another host
+/// without missing DKG rounds
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct StateBundle {
+    /// The hex-encoded public key of the signer the state belongs to
+    pub signer_public_key: String,
+    /// The reward cycle of the DKG state
+    pub reward_cycle: u64,
+    /// The fingerprint of the exporting host's configuration
+    pub config_fingerprint: Sha512Trunc256Sum,
+    /// When the bundle was exported, in seconds since the Unix epoch
+    pub exported_at: u64,
+    /// The hex-encoded DKG state, as encrypted by the signer with its own key
+    pub encrypted_signer_state: String,
+    /// Every block the signer has seen, with its decision on each
+    pub blocks: Vec<BlockInfo>,
+    /// Every block the signer has signed
+    pub signed_blocks: Vec<SignedBlock>,
+}
+
+/// A state bundle, signed with the signer's key.
+///
+/// The signature covers the exact bytes of the JSON-encoded bundle, so the bundle is carried as
+/// a string rather than re-encoded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignedStateBundle {
+    /// The state bundle format version
+    pub format_version: u32,
+    /// The JSON-encoded `StateBundle`
+    pub bundle: String,
+    /// The hex-encoded recoverable signature over the digest of the format version and bundle
+    pub signature: String,
+}
+
+/// The result of importing a state bundle
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateImportSummary {
+    /// Whether the DKG state was added to the signer DB, rather than already there
+    pub signer_state_imported: bool,
+    /// The number of blocks added to the signer DB
+    pub blocks_imported: usize,
+    /// The number of blocks the signer DB already had
+    pub blocks_already_known: usize,
+    /// The number of signed blocks added to the signer DB
+    pub signed_blocks_imported: usize,
+    /// Whether the bundle was exported with the same configuration fingerprint as this host's
+    pub config_matches: bool,
+}
+
+impl StateBundle {
+    /// Export the signer's state for the given reward cycle from the signer DB
+    pub fn export(
+        signer_db: &SignerDb,
+        signer_public_key: &StacksPublicKey,
+        reward_cycle: u64,
+        config_fingerprint: Sha512Trunc256Sum,
+    ) -> Result<Self, MigrationError> {
+        let encrypted_signer_state = signer_db
+            .get_encrypted_signer_state(reward_cycle)?
+            .ok_or(MigrationError::NoSignerState(reward_cycle))?;
+        Ok(Self {
+            signer_public_key: signer_public_key.to_hex(),
+            reward_cycle,
+            config_fingerprint,
+            exported_at: get_epoch_time_secs(),
+            encrypted_signer_state: to_hex(&encrypted_signer_state),
+            blocks: signer_db.get_blocks(None)?,
+            signed_blocks: signer_db.get_signed_blocks()?,
+        })
+    }
+
+    /// Sign the bundle with the signer's key
+    pub fn sign(
+        &self,
+        key_provider: &dyn KeyProvider,
+    ) -> Result<SignedStateBundle, MigrationError> {
+        let bundle =
+            serde_json::to_string(self).map_err(|e| MigrationError::Malformed(e.to_string()))?;
+        let digest = SignedStateBundle::digest(STATE_BUNDLE_FORMAT_VERSION, &bundle);
+        let signature = key_provider.sign_digest(digest.as_bytes())?;
+        Ok(SignedStateBundle {
+            format_version: STATE_BUNDLE_FORMAT_VERSION,
+            bundle,
+            signature: signature.to_hex(),
+        })
+    }
+
+    /// Import the bundle into the signer DB of the signer with the given key, after checking that
+    /// it holds the state of `reward_cycle` and that the key can decrypt it.
+    /// The signer's DKG state is only written if the signer DB has none for the reward cycle,
+    /// since a signer that already took part in a DKG round on this host must keep its shares.
+    /// The import happens in one transaction, so a failed import leaves the signer DB unchanged.
+    pub fn import(
+        &self,
+        signer_db: &mut SignerDb,
+        ecdsa_private_key: &Scalar,
+        reward_cycle: u64,
+        config_fingerprint: Sha512Trunc256Sum,
+    ) -> Result<StateImportSummary, MigrationError> {
+        if self.reward_cycle != reward_cycle {
+            return Err(MigrationError::RewardCycleMismatch {
+                expected: reward_cycle,
+                found: self.reward_cycle,
+            });
+        }
+        let encrypted_signer_state = hex_bytes(&self.encrypted_signer_state)
+            .map_err(|e| MigrationError::Malformed(e.to_string()))?;
+        decrypt(ecdsa_private_key, &encrypted_signer_state)
+            .map_err(|_| MigrationError::UndecryptableState)?;
+
+        let mut summary = StateImportSummary {
+            config_matches: self.config_fingerprint == config_fingerprint,
+            ..StateImportSummary::default()
+        };
+        signer_db.in_transaction(|signer_db| {
+            match signer_db.get_encrypted_signer_state(reward_cycle)? {
+                Some(existing) if existing == encrypted_signer_state => {}
+                Some(_) => return Err(MigrationError::ConflictingSignerState(reward_cycle)),
+                None => {
+                    signer_db
+                        .insert_encrypted_signer_state(reward_cycle, &encrypted_signer_state)?;
+                    summary.signer_state_imported = true;
+                }
+            }
+            for block_info in self.blocks.iter() {
+                if signer_db
+                    .block_lookup(block_info.reward_cycle, &block_info.signer_signature_hash())?
+                    .is_some()
+                {
+                    summary.blocks_already_known += 1;
+                } else {
+                    signer_db.insert_block(block_info)?;
+                    summary.blocks_imported += 1;
+                }
+            }
+            for signed_block in self.signed_blocks.iter() {
+                if signer_db.insert_signed_block(signed_block)? {
+                    summary.signed_blocks_imported += 1;
+                }
+            }
+            Ok(summary)
+        })
+    }
+}
+
+impl SignedStateBundle {
+    /// The digest the signature commits to
+    fn digest(format_version: u32, bundle: &str) -> Sha512Trunc256Sum {
+        let mut data = STATE_BUNDLE_DIGEST_PREFIX.to_vec();
+        data.extend_from_slice(&format_version.to_be_bytes());
+        data.extend_from_slice(bundle.as_bytes());
+        Sha512Trunc256Sum::from_data(&data)
+    }
+
+    /// Check that the bundle was signed by the signer with the given key, and decode it
+    pub fn open(&self, signer_public_key: &StacksPublicKey) -> Result<StateBundle, MigrationError> {
+        if self.format_version != STATE_BUNDLE_FORMAT_VERSION {
+            return Err(MigrationError::UnsupportedVersion(self.format_version));
+        }
+        let signature = MessageSignature::from_hex(&self.signature)
+            .map_err(|e| MigrationError::BadSignature(e.to_string()))?;
+        let digest = Self::digest(self.format_version, &self.bundle);
+        let signed_by = StacksPublicKey::recover_to_pubkey(digest.as_bytes(), &signature)
+            .map_err(|e| MigrationError::BadSignature(e.to_string()))?;
+        if signed_by.to_bytes_compressed() != signer_public_key.to_bytes_compressed() {
+            return Err(MigrationError::SignerMismatch {
+                expected: signer_public_key.to_hex(),
+                found: signed_by.to_hex(),
+            });
+        }
+        let bundle: StateBundle = serde_json::from_str(&self.bundle)
+            .map_err(|e| MigrationError::Malformed(e.to_string()))?;
+        if bundle.signer_public_key != signer_public_key.to_hex() {
+            return Err(MigrationError::SignerMismatch {
+                expected: signer_public_key.to_hex(),
+                found: bundle.signer_public_key,
+            });
+        }
+        Ok(bundle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blockstack_lib::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockHeader};
+    use libsigner::BlockProposal;
+    use rand_core::OsRng;
+    use stacks_common::types::chainstate::{StacksBlockId, StacksPrivateKey};
+
+    use super::*;
+    use crate::keys::InMemoryKeyProvider;
+    use crate::v1::signer::encrypt;
+
+    const REWARD_CYCLE: u64 = 10;
+
+    fn block_info(burn_height: u64) -> BlockInfo {
+        let mut header = NakamotoBlockHeader::empty();
+        header.chain_length = burn_height;
+        BlockInfo::from(BlockProposal {
+            block: NakamotoBlock {
+                header,
+                txs: vec![],
+            },
+            burn_height,
+            reward_cycle: REWARD_CYCLE,
+        })
+    }
+
+    fn signed_block(burn_block_height: u64) -> SignedBlock {
+        SignedBlock {
+            reward_cycle: REWARD_CYCLE,
+            burn_block_height,
+            parent_block_id: StacksBlockId([1; 32]),
+            signer_signature_hash: Sha512Trunc256Sum([burn_block_height as u8; 32]),
+            signed_at: 1_700_000_000,
+        }
+    }
+
+    /// A signer DB with DKG state for `REWARD_CYCLE`, two blocks and a signed block
+    fn old_signer_db(key_provider: &InMemoryKeyProvider) -> SignerDb {
+        let mut signer_db = SignerDb::new(":memory:").unwrap();
+        let ecdsa_private_key = key_provider.ecdsa_private_key().unwrap();
+        let encrypted_state = encrypt(&ecdsa_private_key, b"dkg state", &mut OsRng).unwrap();
+        signer_db
+            .insert_encrypted_signer_state(REWARD_CYCLE, &encrypted_state)
+            .unwrap();
+        signer_db.insert_block(&block_info(100)).unwrap();
+        signer_db.insert_block(&block_info(101)).unwrap();
+        signer_db.insert_signed_block(&signed_block(100)).unwrap();
+        signer_db
+    }
+
+    #[test]
+    fn export_and_import_state() {
+        let key_provider = InMemoryKeyProvider::new(StacksPrivateKey::new());
+        let public_key = key_provider.public_key();
+        let ecdsa_private_key = key_provider.ecdsa_private_key().unwrap();
+        let fingerprint = Sha512Trunc256Sum([7; 32]);
+        let old_db = old_signer_db(&key_provider);
+
+        let signed = StateBundle::export(&old_db, &public_key, REWARD_CYCLE, fingerprint)
+            .unwrap()
+            .sign(&key_provider)
+            .unwrap();
+        let json = serde_json::to_string(&signed).unwrap();
+        let signed: SignedStateBundle = serde_json::from_str(&json).unwrap();
+        let bundle = signed.open(&public_key).unwrap();
+        assert_eq!(bundle.blocks.len(), 2);
+        assert_eq!(bundle.signed_blocks.len(), 1);
+
+        // The new host has already seen one of the blocks
+        let mut new_db = SignerDb::new(":memory:").unwrap();
+        new_db.insert_block(&block_info(101)).unwrap();
+        let summary = bundle
+            .import(&mut new_db, &ecdsa_private_key, REWARD_CYCLE, fingerprint)
+            .unwrap();
+        assert_eq!(
+            summary,
+            StateImportSummary {
+                signer_state_imported: true,
+                blocks_imported: 1,
+                blocks_already_known: 1,
+                signed_blocks_imported: 1,
+                config_matches: true,
+            }
+        );
+        assert_eq!(
+            new_db.get_encrypted_signer_state(REWARD_CYCLE).unwrap(),
+            old_db.get_encrypted_signer_state(REWARD_CYCLE).unwrap()
+        );
+        assert_eq!(new_db.get_signed_blocks().unwrap(), vec![signed_block(100)]);
+
+        // Importing again changes nothing
+        let summary = bundle
+            .import(
+                &mut new_db,
+                &ecdsa_private_key,
+                REWARD_CYCLE,
+                Sha512Trunc256Sum([8; 32]),
+            )
+            .unwrap();
+        assert_eq!(
+            summary,
+            StateImportSummary {
+                signer_state_imported: false,
+                blocks_imported: 0,
+                blocks_already_known: 2,
+                signed_blocks_imported: 0,
+                config_matches: false,
+            }
+        );
+    }
+
+    #[test]
+    fn import_checks_the_bundle() {
+        let key_provider = InMemoryKeyProvider::new(StacksPrivateKey::new());
+        let public_key = key_provider.public_key();
+        let ecdsa_private_key = key_provider.ecdsa_private_key().unwrap();
+        let other_key_provider = InMemoryKeyProvider::new(StacksPrivateKey::new());
+        let fingerprint = Sha512Trunc256Sum([7; 32]);
+        let old_db = old_signer_db(&key_provider);
+        let bundle = StateBundle::export(&old_db, &public_key, REWARD_CYCLE, fingerprint).unwrap();
+        let signed = bundle.sign(&key_provider).unwrap();
+
+        // Another signer's bundle, or a bundle signed by another key, is refused
+        assert!(matches!(
+            signed.open(&other_key_provider.public_key()),
+            Err(MigrationError::SignerMismatch { .. })
+        ));
+        let forged = bundle.sign(&other_key_provider).unwrap();
+        assert!(matches!(
+            forged.open(&public_key),
+            Err(MigrationError::SignerMismatch { .. })
+        ));
+
+        // Tampering with the bundle invalidates the signature
+        let mut tampered = signed.clone();
+        tampered.bundle = tampered.bundle.replace(
+            &format!("\"reward_cycle\":{REWARD_CYCLE}"),
+            &format!("\"reward_cycle\":{}", REWARD_CYCLE + 1),
+        );
+        assert_ne!(tampered.bundle, signed.bundle);
+        assert!(tampered.open(&public_key).is_err());
+
+        let mut future = signed.clone();
+        future.format_version += 1;
+        assert!(matches!(
+            future.open(&public_key),
+            Err(MigrationError::UnsupportedVersion(_))
+        ));
+
+        // The bundle must be for the expected reward cycle, and decrypt with the signer's key
+        let mut new_db = SignerDb::new(":memory:").unwrap();
+        assert!(matches!(
+            bundle.import(
+                &mut new_db,
+                &ecdsa_private_key,
+                REWARD_CYCLE + 1,
+                fingerprint
+            ),
+            Err(MigrationError::RewardCycleMismatch { .. })
+        ));
+        assert!(matches!(
+            bundle.import(
+                &mut new_db,
+                &other_key_provider.ecdsa_private_key().unwrap(),
+                REWARD_CYCLE,
+                fingerprint
+            ),
+            Err(MigrationError::UndecryptableState)
+        ));
+
+        // DKG state the new host already has is never overwritten
+        let other_state = encrypt(&ecdsa_private_key, b"other dkg state", &mut OsRng).unwrap();
+        new_db
+            .insert_encrypted_signer_state(REWARD_CYCLE, &other_state)
+            .unwrap();
+        assert!(matches!(
+            bundle.import(&mut new_db, &ecdsa_private_key, REWARD_CYCLE, fingerprint),
+            Err(MigrationError::ConflictingSignerState(_))
+        ));
+        assert!(new_db.get_blocks(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn failed_import_changes_nothing() {
+        let key_provider = InMemoryKeyProvider::new(StacksPrivateKey::new());
+        let public_key = key_provider.public_key();
+        let ecdsa_private_key = key_provider.ecdsa_private_key().unwrap();
+        let fingerprint = Sha512Trunc256Sum([7; 32]);
+        let old_db = old_signer_db(&key_provider);
+        let mut bundle =
+            StateBundle::export(&old_db, &public_key, REWARD_CYCLE, fingerprint).unwrap();
+
+        // The last signed block cannot be stored, so the import fails after the DKG state and
+        // the blocks were written
+        let mut unstorable = signed_block(102);
+        unstorable.signed_at = u64::MAX;
+        bundle.signed_blocks.push(unstorable);
+
+        let mut new_db = SignerDb::new(":memory:").unwrap();
+        assert!(matches!(
+            bundle.import(&mut new_db, &ecdsa_private_key, REWARD_CYCLE, fingerprint),
+            Err(MigrationError::DBError(_))
+        ));
+        assert!(new_db
+            .get_encrypted_signer_state(REWARD_CYCLE)
+            .unwrap()
+            .is_none());
+        assert!(new_db.get_blocks(None).unwrap().is_empty());
+        assert!(new_db.get_signed_blocks().unwrap().is_empty());
+
+        // The DB is still usable, and a good bundle imports cleanly
+        bundle.signed_blocks.pop();
+        let summary = bundle
+            .import(&mut new_db, &ecdsa_private_key, REWARD_CYCLE, fingerprint)
+            .unwrap();
+        assert!(summary.signer_state_imported);
+        assert_eq!(summary.blocks_imported, 2);
+        assert_eq!(summary.signed_blocks_imported, 1);
+    }
+}
@@ -16,6 +16,12 @@
 
 /// The coordinator selector for the signer
 pub mod coordinator;
+/// The signer's block history, for auditing its decisions
+pub mod history;
+/// Moving the signer's state to another host
+pub mod migration;
+/// Double-signing protection for the signer
+pub mod protection;
 /// The signer module for processing events
 pub mod signer;
 /// The state module for the signer
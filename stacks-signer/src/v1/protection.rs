@@ -0,0 +1,270 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use blockstack_lib::util_lib::db::Error as DBError;
+use serde_derive::{Deserialize, Serialize};
+use stacks_common::types::chainstate::{StacksBlockId, StacksPublicKey};
+use stacks_common::util::get_epoch_time_secs;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+use crate::v1::signer::BlockInfo;
+use crate::v1::signerdb::SignerDb;
+
+/// The signing history interchange format version written by this signer
+pub const INTERCHANGE_FORMAT_VERSION: u32 = 1;
+
+/// A block this signer issued a signature share for.
+///
+/// Two blocks in the same reward cycle, at the same burn block height and with the same parent
+/// conflict: a signer that has signed one of them must never sign the other.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignedBlock {
+    /// The reward cycle the block belongs to
+    pub reward_cycle: u64,
+    /// The burn block height at which the block was proposed
+    pub burn_block_height: u64,
+    /// The block's parent
+    pub parent_block_id: StacksBlockId,
+    /// The block's signer signature hash
+    pub signer_signature_hash: Sha512Trunc256Sum,
+    /// When the signature share was issued, in seconds since the Unix epoch
+    pub signed_at: u64,
+}
+
+impl SignedBlock {
+    /// Describe a signature share for the given block, issued now
+    pub fn new(block_info: &BlockInfo) -> Self {
+        Self {
+            reward_cycle: block_info.reward_cycle,
+            burn_block_height: block_info.burn_block_height,
+            parent_block_id: block_info.block.header.parent_block_id,
+            signer_signature_hash: block_info.signer_signature_hash(),
+            signed_at: get_epoch_time_secs(),
+        }
+    }
+
+    /// Whether the two signed blocks cannot both be signed
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        self.reward_cycle == other.reward_cycle
+            && self.burn_block_height == other.burn_block_height
+            && self.parent_block_id == other.parent_block_id
+            && self.signer_signature_hash != other.signer_signature_hash
+    }
+}
+
+/// The outcome of checking a block against the signing history
+#[derive(Debug, Clone, PartialEq)]
+pub enum SigningCheck {
+    /// The block does not conflict with any signed block, and has been recorded as signed
+    Allowed,
+    /// The block conflicts with a block that was already signed
+    Conflict(SignedBlock),
+}
+
+#[derive(thiserror::Error, Debug)]
+/// An error occurred importing a signing history
+pub enum InterchangeError {
+    /// The signer DB could not be read or written
+    #[error("Signer DB error: {0}")]
+    DBError(#[from] DBError),
+    /// The interchange file has an unsupported format version
+    #[error("Unsupported interchange format version {0}")]
+    UnsupportedVersion(u32),
+    /// The interchange file belongs to another signer
+    #[error("Signing history belongs to signer {found}, not {expected}")]
+    SignerMismatch {
+        /// The public key of this signer
+        expected: String,
+        /// The public key in the interchange file
+        found: String,
+    },
+}
+
+/// The metadata of an exported signing history
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InterchangeMetadata {
+    /// The interchange format version
+    pub interchange_format_version: u32,
+    /// The hex-encoded public key of the signer the history belongs to
+    pub signer_public_key: String,
+    /// When the history was exported, in seconds since the Unix epoch
+    pub exported_at: u64,
+}
+
+/// A signer's signing history in a portable JSON format, used to carry its double-signing
+/// protection over to a new host
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SigningHistory {
+    /// The metadata
+    pub metadata: InterchangeMetadata,
+    /// Every block the signer has signed
+    pub signed_blocks: Vec<SignedBlock>,
+}
+
+/// The result of importing a signing history
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    /// The number of signed blocks added to the signer DB
+    pub imported: usize,
+    /// The number of signed blocks the signer DB already had
+    pub already_known: usize,
+    /// The number of imported signed blocks that conflict with another signed block.
+    /// They are imported anyway, so that neither block can be signed again.
+    pub conflicts: usize,
+}
+
+impl SigningHistory {
+    /// Export the signing history in the signer DB
+    pub fn export(
+        signer_db: &SignerDb,
+        signer_public_key: &StacksPublicKey,
+    ) -> Result<Self, DBError> {
+        Ok(Self {
+            metadata: InterchangeMetadata {
+                interchange_format_version: INTERCHANGE_FORMAT_VERSION,
+                signer_public_key: signer_public_key.to_hex(),
+                exported_at: get_epoch_time_secs(),
+            },
+            signed_blocks: signer_db.get_signed_blocks()?,
+        })
+    }
+
+    /// Merge the signing history into the signer DB
+    pub fn import(
+        &self,
+        signer_db: &SignerDb,
+        signer_public_key: &StacksPublicKey,
+    ) -> Result<ImportSummary, InterchangeError> {
+        if self.metadata.interchange_format_version != INTERCHANGE_FORMAT_VERSION {
+            return Err(InterchangeError::UnsupportedVersion(
+                self.metadata.interchange_format_version,
+            ));
+        }
+        if self.metadata.signer_public_key != signer_public_key.to_hex() {
+            return Err(InterchangeError::SignerMismatch {
+                expected: signer_public_key.to_hex(),
+                found: self.metadata.signer_public_key.clone(),
+            });
+        }
+        let mut summary = ImportSummary::default();
+        for signed_block in self.signed_blocks.iter() {
+            if signer_db
+                .get_conflicting_signed_block(signed_block)?
+                .is_some()
+            {
+                summary.conflicts += 1;
+            }
+            if signer_db.insert_signed_block(signed_block)? {
+                summary.imported += 1;
+            } else {
+                summary.already_known += 1;
+            }
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::types::chainstate::StacksPrivateKey;
+
+    use super::*;
+
+    fn signed_block(burn_block_height: u64, parent: u8, hash: u8) -> SignedBlock {
+        SignedBlock {
+            reward_cycle: 10,
+            burn_block_height,
+            parent_block_id: StacksBlockId([parent; 32]),
+            signer_signature_hash: Sha512Trunc256Sum([hash; 32]),
+            signed_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn conflicting_blocks() {
+        let block = signed_block(100, 1, 1);
+        assert!(!block.conflicts_with(&block));
+        assert!(block.conflicts_with(&signed_block(100, 1, 2)));
+        assert!(!block.conflicts_with(&signed_block(101, 1, 2)));
+        assert!(!block.conflicts_with(&signed_block(100, 2, 2)));
+        let mut other_cycle = signed_block(100, 1, 2);
+        other_cycle.reward_cycle += 1;
+        assert!(!block.conflicts_with(&other_cycle));
+    }
+
+    #[test]
+    fn export_and_import_history() {
+        let public_key = StacksPublicKey::from_private(&StacksPrivateKey::new());
+        let mut old_db = SignerDb::new(":memory:").unwrap();
+        for block in [signed_block(100, 1, 1), signed_block(101, 2, 2)] {
+            assert_eq!(
+                old_db.check_and_record_signed_block(&block).unwrap(),
+                SigningCheck::Allowed
+            );
+        }
+        let history = SigningHistory::export(&old_db, &public_key).unwrap();
+        let json = serde_json::to_string(&history).unwrap();
+        let history: SigningHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(history.signed_blocks.len(), 2);
+
+        // The new host already signed a conflicting block
+        let mut new_db = SignerDb::new(":memory:").unwrap();
+        new_db
+            .check_and_record_signed_block(&signed_block(101, 2, 3))
+            .unwrap();
+        let summary = history.import(&new_db, &public_key).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 2,
+                already_known: 0,
+                conflicts: 1,
+            }
+        );
+        let summary = history.import(&new_db, &public_key).unwrap();
+        assert_eq!(summary.imported, 0);
+        assert_eq!(summary.already_known, 2);
+
+        // Neither conflicting block can be signed again, nor can a new conflicting one
+        for hash in [2, 3, 4] {
+            assert!(matches!(
+                new_db
+                    .check_and_record_signed_block(&signed_block(101, 2, hash))
+                    .unwrap(),
+                SigningCheck::Conflict(_)
+            ));
+        }
+        assert!(matches!(
+            new_db
+                .check_and_record_signed_block(&signed_block(100, 1, 5))
+                .unwrap(),
+            SigningCheck::Conflict(_)
+        ));
+
+        // Another signer's history is refused
+        let other_key = StacksPublicKey::from_private(&StacksPrivateKey::new());
+        assert!(matches!(
+            history.import(&new_db, &other_key),
+            Err(InterchangeError::SignerMismatch { .. })
+        ));
+        let mut future_history = history.clone();
+        future_history.metadata.interchange_format_version += 1;
+        assert!(matches!(
+            future_history.import(&new_db, &public_key),
+            Err(InterchangeError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -19,12 +19,13 @@ use std::path::PathBuf;
 use std::sync::mpsc::Sender;
 use std::time::Instant;
 
+use blockstack_lib::burnchains::Txid;
 use blockstack_lib::chainstate::burn::ConsensusHashExtensions;
 use blockstack_lib::chainstate::nakamoto::signer_set::NakamotoSigners;
 use blockstack_lib::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockVote};
 use blockstack_lib::chainstate::stacks::boot::SIGNERS_VOTING_FUNCTION_NAME;
 use blockstack_lib::chainstate::stacks::StacksTransaction;
-use blockstack_lib::net::api::postblock_proposal::BlockValidateResponse;
+use blockstack_lib::net::api::postblock_proposal::{BlockValidateOk, BlockValidateResponse};
 use blockstack_lib::util_lib::db::Error as DBError;
 use hashbrown::HashSet;
 use libsigner::v1::messages::{
@@ -38,6 +39,7 @@ use stacks_common::codec::{read_next, StacksMessageCodec};
 use stacks_common::types::chainstate::{ConsensusHash, StacksAddress};
 use stacks_common::types::StacksEpochId;
 use stacks_common::util::hash::Sha512Trunc256Sum;
+use stacks_common::util::{get_epoch_time_ms, get_epoch_time_secs};
 use stacks_common::{debug, error, info, warn};
 use wsts::common::Signature;
 use wsts::curve::keys::PublicKey;
@@ -55,8 +57,12 @@ use wsts::v2;
 
 use crate::client::{ClientError, SignerSlotID, StackerDB, StacksClient};
 use crate::config::SignerConfig;
+use crate::policy::{
+    count_tenure_extends, BlockPolicy, PendingTransactions, PolicyDecision, ProposalFacts,
+};
 use crate::runloop::{RunLoopCommand, SignerCommand};
 use crate::v1::coordinator::CoordinatorSelector;
+use crate::v1::protection::{SignedBlock, SigningCheck};
 use crate::v1::signerdb::SignerDb;
 use crate::Signer as SignerTrait;
 
@@ -77,6 +83,13 @@ pub struct BlockInfo {
     nonce_request: Option<NonceRequest>,
     /// Whether this block is already being signed over
     pub signed_over: bool,
+    /// When the block proposal was received, in milliseconds since the Unix epoch
+    pub proposed_at_ms: Option<u128>,
+    /// When the stacks node's validation response for the block was received, in milliseconds
+    /// since the Unix epoch
+    pub validated_at_ms: Option<u128>,
+    /// Why the block was rejected, if it was
+    pub rejection_reason: Option<String>,
 }
 
 impl From<BlockProposal> for BlockInfo {
@@ -89,6 +102,9 @@ impl From<BlockProposal> for BlockInfo {
             valid: None,
             nonce_request: None,
             signed_over: false,
+            proposed_at_ms: Some(get_epoch_time_ms()),
+            validated_at_ms: None,
+            rejection_reason: None,
         }
     }
 }
@@ -105,6 +121,17 @@ impl BlockInfo {
     pub fn signer_signature_hash(&self) -> Sha512Trunc256Sum {
         self.block.header.signer_signature_hash()
     }
+
+    /// Whether the stacks node has validated the block, and if so, whether the signer found it
+    /// valid
+    pub fn is_valid(&self) -> Option<bool> {
+        self.valid
+    }
+
+    /// How long the stacks node took to validate the block, in milliseconds
+    pub fn validation_latency_ms(&self) -> Option<u128> {
+        self.validated_at_ms?.checked_sub(self.proposed_at_ms?)
+    }
 }
 
 /// The specific operations that a signer can perform
@@ -169,6 +196,10 @@ pub struct Signer {
     pub db_path: PathBuf,
     /// SignerDB for state management
     pub signer_db: SignerDb,
+    /// The policy applied to block proposals before signing them
+    pub block_policy: BlockPolicy,
+    /// The transactions seen pending in the stacks node's mempool
+    pub pending_transactions: PendingTransactions,
 }
 
 impl std::fmt::Display for Signer {
@@ -515,6 +546,8 @@ impl From<SignerConfig> for Signer {
             miner_key: None,
             db_path: signer_config.db_path,
             signer_db,
+            block_policy: signer_config.block_policy,
+            pending_transactions: PendingTransactions::default(),
         }
     }
 }
@@ -667,8 +700,24 @@ impl Signer {
                         return;
                     }
                 };
-                let is_valid = self.verify_block_transactions(stacks_client, &block_info.block);
-                block_info.valid = Some(is_valid);
+                block_info.validated_at_ms = Some(get_epoch_time_ms());
+                let result = self
+                    .verify_block_transactions(stacks_client, &block_info.block)
+                    .and_then(|()| {
+                        self.check_block_policy(stacks_client, &block_info, block_validate_ok)
+                    });
+                if let Err(reject_code) = result.as_ref() {
+                    block_info.rejection_reason = Some(reject_code.to_string());
+                    let block_rejection =
+                        BlockRejection::new(signer_signature_hash, reject_code.clone());
+                    if let Err(e) = self
+                        .stackerdb
+                        .send_message_with_retry(block_rejection.into())
+                    {
+                        warn!("{self}: Failed to send block rejection to stacker-db: {e:?}",);
+                    }
+                }
+                block_info.valid = Some(result.is_ok());
                 self.signer_db
                     .insert_block(&block_info)
                     .unwrap_or_else(|_| panic!("{self}: Failed to insert block in DB"));
@@ -698,6 +747,11 @@ impl Signer {
                     }
                 };
                 block_info.valid = Some(false);
+                block_info.validated_at_ms = Some(get_epoch_time_ms());
+                block_info.rejection_reason = Some(format!(
+                    "The stacks node rejected the block: {}",
+                    block_validate_reject.reason
+                ));
                 // Submit a rejection response to the .signers contract for miners
                 // to observe so they know to send another block and to prove signers are doing work);
                 warn!("{self}: Broadcasting a block rejection due to stacks node validation failure...");
@@ -851,7 +905,7 @@ impl Signer {
     /// Validate a signature share request, updating its message where appropriate.
     /// If the request is for a block it has already agreed to sign, it will overwrite the message with the agreed upon value
     /// Returns whether the request is valid or not.
-    fn validate_signature_share_request(&self, request: &mut SignatureShareRequest) -> bool {
+    fn validate_signature_share_request(&mut self, request: &mut SignatureShareRequest) -> bool {
         let Some(block_vote): Option<NakamotoBlockVote> = read_next(&mut &request.message[..]).ok()
         else {
             // We currently reject anything that is not a block vote
@@ -861,42 +915,55 @@ impl Signer {
             return false;
         };
 
-        match self
+        let Some(block_info) = self
             .signer_db
             .block_lookup(self.reward_cycle, &block_vote.signer_signature_hash)
             .unwrap_or_else(|_| panic!("{self}: Failed to connect to DB"))
-            .map(|b| b.vote)
-        {
-            Some(Some(vote)) => {
-                // Overwrite with our agreed upon value in case another message won majority or the coordinator is trying to cheat...
-                debug!(
-                    "{self}: Set vote (rejected = {}) to {vote:?}", block_vote.rejected;
-                    "requested_sighash" => %block_vote.signer_signature_hash,
-                );
-                request.message = vote.serialize_to_vec();
-                true
-            }
-            Some(None) => {
-                // We never agreed to sign this block. Reject it.
-                // This can happen if the coordinator received enough votes to sign yes
-                // or no on a block before we received validation from the stacks node.
-                debug!(
-                    "{self}: Received a signature share request for a block we never agreed to sign. Ignore it.";
-                    "requested_sighash" => %block_vote.signer_signature_hash,
-                );
-                false
-            }
-            None => {
-                // We will only sign across block hashes or block hashes + b'n' byte for
-                // blocks we have seen a Nonce Request for (and subsequent validation)
-                // We are missing the context here necessary to make a decision. Reject the block
-                debug!(
-                    "{self}: Received a signature share request from an unknown block. Reject it.";
+        else {
+            // We will only sign across block hashes or block hashes + b'n' byte for
+            // blocks we have seen a Nonce Request for (and subsequent validation)
+            // We are missing the context here necessary to make a decision. Reject the block
+            debug!(
+                "{self}: Received a signature share request from an unknown block. Reject it.";
+                "requested_sighash" => %block_vote.signer_signature_hash,
+            );
+            return false;
+        };
+        let Some(vote) = block_info.vote.as_ref() else {
+            // We never agreed to sign this block. Reject it.
+            // This can happen if the coordinator received enough votes to sign yes
+            // or no on a block before we received validation from the stacks node.
+            debug!(
+                "{self}: Received a signature share request for a block we never agreed to sign. Ignore it.";
+                "requested_sighash" => %block_vote.signer_signature_hash,
+            );
+            return false;
+        };
+        if !vote.rejected {
+            // We are about to sign the block itself. Never sign a block that conflicts with one we already signed.
+            let signed_block = SignedBlock::new(&block_info);
+            if let SigningCheck::Conflict(conflict) = self
+                .signer_db
+                .check_and_record_signed_block(&signed_block)
+                .unwrap_or_else(|_| panic!("{self}: Failed to connect to DB"))
+            {
+                error!(
+                    "{self}: Refusing to sign a block that conflicts with a block we already signed";
                     "requested_sighash" => %block_vote.signer_signature_hash,
+                    "signed_sighash" => %conflict.signer_signature_hash,
+                    "burn_block_height" => conflict.burn_block_height,
+                    "parent_block_id" => %conflict.parent_block_id,
                 );
-                false
+                return false;
             }
         }
+        // Overwrite with our agreed upon value in case another message won majority or the coordinator is trying to cheat...
+        debug!(
+            "{self}: Set vote (rejected = {}) to {vote:?}", block_vote.rejected;
+            "requested_sighash" => %block_vote.signer_signature_hash,
+        );
+        request.message = vote.serialize_to_vec();
+        true
     }
 
     /// Validate a nonce request, updating its message appropriately.
@@ -954,12 +1021,13 @@ impl Signer {
         Some(block_info)
     }
 
-    /// Verify the transactions in a block are as expected
+    /// Verify the transactions in a block are as expected, returning why the block must be
+    /// rejected if they are not
     fn verify_block_transactions(
         &mut self,
         stacks_client: &StacksClient,
         block: &NakamotoBlock,
-    ) -> bool {
+    ) -> Result<(), RejectCode> {
         let next_reward_cycle = self.reward_cycle.wrapping_add(1);
         let approved_aggregate_public_key = stacks_client
             .get_approved_aggregate_key(next_reward_cycle)
@@ -968,7 +1036,7 @@ impl Signer {
             // We do not enforce a block contain any transactions except the aggregate votes when it is NOT already set for the upcoming signers' reward cycle
             // Otherwise it is a waste of block space and time to enforce as the desired outcome has been reached.
             debug!("{self}: Already have an aggregate key for the next signer set's reward cycle ({}). Skipping transaction verification...", next_reward_cycle);
-            return true;
+            return Ok(());
         }
         if let Ok(expected_transactions) = self.get_expected_transactions(stacks_client) {
             //It might be worth building a hashset of the blocks' txids and checking that against the expected transaction's txid.
@@ -986,40 +1054,112 @@ impl Signer {
                     }
                 })
                 .collect::<Vec<_>>();
-            let is_valid = missing_transactions.is_empty();
-            if !is_valid {
+            if !missing_transactions.is_empty() {
                 debug!("{self}: Broadcasting a block rejection due to missing expected transactions...");
-                let block_rejection = BlockRejection::new(
-                    block.header.signer_signature_hash(),
-                    RejectCode::MissingTransactions(missing_transactions),
-                );
-                // Submit signature result to miners to observe
-                if let Err(e) = self
-                    .stackerdb
-                    .send_message_with_retry(block_rejection.into())
-                {
-                    warn!("{self}: Failed to send block rejection to stacker-db: {e:?}",);
-                }
+                return Err(RejectCode::MissingTransactions(missing_transactions));
             }
-            is_valid
+            Ok(())
         } else {
             // Failed to connect to the stacks node to get transactions. Cannot validate the block. Reject it.
             debug!("{self}: Broadcasting a block rejection due to signer connectivity issues...",);
-            let block_rejection = BlockRejection::new(
-                block.header.signer_signature_hash(),
-                RejectCode::ConnectivityIssues,
-            );
-            // Submit signature result to miners to observe
-            if let Err(e) = self
-                .stackerdb
-                .send_message_with_retry(block_rejection.into())
-            {
-                warn!("{self}: Failed to send block submission to stacker-db: {e:?}",);
-            }
-            false
+            Err(RejectCode::ConnectivityIssues)
         }
     }
 
+    /// Apply the block policy to a block the stacks node has validated, recording the decision in
+    /// the signer DB and returning why the block must be rejected if it breaks the policy
+    fn check_block_policy(
+        &mut self,
+        stacks_client: &StacksClient,
+        block_info: &BlockInfo,
+        block_validate_ok: &BlockValidateOk,
+    ) -> Result<(), RejectCode> {
+        let block = &block_info.block;
+        let signer_signature_hash = block.header.signer_signature_hash();
+        let overdue_transactions = match self
+            .get_overdue_transactions(stacks_client, block_info.burn_block_height)
+        {
+            Ok(overdue_transactions) => overdue_transactions,
+            Err(e) => {
+                // Cannot apply the policy without the node's mempool. Reject the block.
+                warn!("{self}: Failed to get pending transactions: {e:?}. Broadcasting a block rejection due to signer connectivity issues...");
+                return Err(RejectCode::ConnectivityIssues);
+            }
+        };
+        let block_limit = if self.block_policy.requires_block_limit() {
+            match stacks_client.get_block_limit(block_info.burn_block_height) {
+                Ok(block_limit) => Some(block_limit),
+                Err(e) => {
+                    warn!("{self}: Failed to get the block limit: {e:?}. Broadcasting a block rejection due to signer connectivity issues...");
+                    return Err(RejectCode::ConnectivityIssues);
+                }
+            }
+        } else {
+            None
+        };
+        let prior_tenure_extends = self
+            .signer_db
+            .get_accepted_tenure_extends(&block.header.consensus_hash)
+            .unwrap_or_else(|_| panic!("{self}: Failed to read tenure extensions from DB"));
+
+        let result = self.block_policy.evaluate(&ProposalFacts {
+            block,
+            size: block_validate_ok.size,
+            cost: &block_validate_ok.cost,
+            block_limit: block_limit.as_ref(),
+            prior_tenure_extends,
+            overdue_transactions: &overdue_transactions,
+        });
+        let decision = PolicyDecision {
+            signer_signature_hash,
+            consensus_hash: block.header.consensus_hash,
+            tenure_extends: count_tenure_extends(block),
+            violation: result.clone().err(),
+            decided_at: get_epoch_time_secs(),
+        };
+        self.signer_db
+            .insert_policy_decision(self.reward_cycle, &decision)
+            .unwrap_or_else(|_| panic!("{self}: Failed to insert policy decision in DB"));
+
+        let Err(violation) = result else {
+            return Ok(());
+        };
+        warn!("{self}: Broadcasting a block rejection due to a block policy violation";
+            "signer_sighash" => %signer_signature_hash,
+            "rule" => %violation.rule,
+            "reason" => &violation.reason,
+        );
+        Err(violation.into())
+    }
+
+    /// Get the transactions the block policy requires the next block to include: those that have
+    /// been pending in the stacks node's mempool for long enough and can be mined now
+    fn get_overdue_transactions(
+        &mut self,
+        stacks_client: &StacksClient,
+        burn_block_height: u64,
+    ) -> Result<Vec<Txid>, ClientError> {
+        let Some(max_age) = self.block_policy.pending_tx_max_age_burn_blocks else {
+            return Ok(vec![]);
+        };
+        let mempool_transactions = stacks_client.get_mempool_transactions()?;
+        self.pending_transactions
+            .observe(mempool_transactions, burn_block_height, max_age);
+
+        let overdue = self
+            .pending_transactions
+            .overdue(burn_block_height, max_age);
+        let origins: HashSet<_> = overdue.iter().map(|tx| tx.origin_address()).collect();
+        let origins: Vec<_> = origins.into_iter().collect();
+        let account_nonces = self.get_account_nonces(stacks_client, &origins);
+        // A transaction whose nonce is ahead of its account's cannot be mined yet
+        Ok(overdue
+            .into_iter()
+            .filter(|tx| account_nonces.get(&tx.origin_address()) == Some(&tx.get_origin_nonce()))
+            .map(|tx| tx.txid())
+            .collect())
+    }
+
     /// Get transactions from stackerdb for the given addresses and account nonces, filtering out any malformed transactions
     fn get_signer_transactions(
         &mut self,
@@ -1704,7 +1844,8 @@ impl SignerStateStorage for &SignerDb {
     }
 }
 
-fn encrypt(
+/// Encrypt signer state with a key derived from the signer's private key
+pub(crate) fn encrypt(
     private_key: &Scalar,
     msg: &[u8],
     rng: &mut impl rand_core::CryptoRngCore,
@@ -1713,7 +1854,11 @@ fn encrypt(
         .map_err(|_| EncryptionError::Encrypt)
 }
 
-fn decrypt(private_key: &Scalar, encrypted_msg: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+/// Decrypt signer state encrypted with `encrypt()`
+pub(crate) fn decrypt(
+    private_key: &Scalar,
+    encrypted_msg: &[u8],
+) -> Result<Vec<u8>, EncryptionError> {
     wsts::util::decrypt(derive_encryption_key(private_key).as_bytes(), encrypted_msg)
         .map_err(|_| EncryptionError::Decrypt)
 }
@@ -17,13 +17,17 @@
 use std::path::Path;
 
 use blockstack_lib::util_lib::db::{
-    query_row, sqlite_open, table_exists, u64_to_sql, Error as DBError,
+    query_row, query_rows, sqlite_open, table_exists, tx_begin_immediate, u64_to_sql,
+    Error as DBError, FromColumn, FromRow,
 };
-use rusqlite::{params, Connection, Error as SqliteError, OpenFlags, NO_PARAMS};
+use rusqlite::{params, Connection, Error as SqliteError, OpenFlags, Row, NO_PARAMS};
 use slog::slog_debug;
 use stacks_common::debug;
+use stacks_common::types::chainstate::{ConsensusHash, StacksBlockId};
 use stacks_common::util::hash::Sha512Trunc256Sum;
 
+use crate::policy::{PolicyDecision, PolicyViolation};
+use crate::v1::protection::{SignedBlock, SigningCheck};
 use crate::v1::signer::BlockInfo;
 
 /// This struct manages a SQLite database connection
@@ -49,6 +53,33 @@ CREATE TABLE IF NOT EXISTS signer_states (
     encrypted_state BLOB NOT NULL
 )";
 
+const CREATE_BLOCK_POLICY_DECISIONS_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS block_policy_decisions (
+    reward_cycle INTEGER NOT NULL,
+    signer_signature_hash TEXT NOT NULL,
+    consensus_hash TEXT NOT NULL,
+    tenure_extends INTEGER NOT NULL,
+    accepted INTEGER NOT NULL,
+    violated_rule TEXT,
+    reason TEXT,
+    decided_at INTEGER NOT NULL,
+    PRIMARY KEY (reward_cycle, signer_signature_hash)
+)";
+
+const CREATE_BLOCK_POLICY_DECISIONS_INDEX: &str = "
+CREATE INDEX IF NOT EXISTS block_policy_decisions_by_tenure
+    ON block_policy_decisions (consensus_hash, accepted)";
+
+const CREATE_SIGNED_BLOCKS_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS signed_blocks (
+    reward_cycle INTEGER NOT NULL,
+    burn_block_height INTEGER NOT NULL,
+    parent_block_id TEXT NOT NULL,
+    signer_signature_hash TEXT NOT NULL,
+    signed_at INTEGER NOT NULL,
+    PRIMARY KEY (reward_cycle, burn_block_height, parent_block_id, signer_signature_hash)
+)";
+
 impl SignerDb {
     /// Create a new `SignerState` instance.
     /// This will create a new SQLite database at the given path
@@ -72,6 +103,17 @@ impl SignerDb {
             self.db.execute(CREATE_SIGNER_STATE_TABLE, NO_PARAMS)?;
         }
 
+        if !table_exists(&self.db, "block_policy_decisions")? {
+            self.db
+                .execute(CREATE_BLOCK_POLICY_DECISIONS_TABLE, NO_PARAMS)?;
+            self.db
+                .execute(CREATE_BLOCK_POLICY_DECISIONS_INDEX, NO_PARAMS)?;
+        }
+
+        if !table_exists(&self.db, "signed_blocks")? {
+            self.db.execute(CREATE_SIGNED_BLOCKS_TABLE, NO_PARAMS)?;
+        }
+
         Ok(())
     }
 
@@ -83,6 +125,30 @@ impl SignerDb {
         )
     }
 
+    /// Run `f` against the database in one immediate transaction, which is committed if `f`
+    /// succeeds and rolled back if it fails. `f` must not start a transaction of its own.
+    pub fn in_transaction<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        E: From<DBError>,
+    {
+        self.db
+            .execute_batch("BEGIN IMMEDIATE")
+            .map_err(DBError::from)?;
+        match f(self) {
+            Ok(result) => {
+                self.db.execute_batch("COMMIT").map_err(DBError::from)?;
+                Ok(result)
+            }
+            Err(e) => {
+                self.db.execute_batch("ROLLBACK").map_err(DBError::from)?;
+                Err(e)
+            }
+        }
+    }
+
     /// Get the signer state for the provided reward cycle if it exists in the database
     pub fn get_encrypted_signer_state(
         &self,
@@ -124,6 +190,27 @@ impl SignerDb {
         try_deserialize(result)
     }
 
+    /// Get every block in the database from the given reward cycle, or from every reward cycle,
+    /// ordered by reward cycle and burn block height
+    pub fn get_blocks(&self, reward_cycle: Option<u64>) -> Result<Vec<BlockInfo>, DBError> {
+        let block_jsons: Vec<String> = match reward_cycle {
+            Some(reward_cycle) => query_rows(
+                &self.db,
+                "SELECT block_info FROM blocks WHERE reward_cycle = ? ORDER BY burn_block_height, signer_signature_hash",
+                params![u64_to_sql(reward_cycle)?],
+            )?,
+            None => query_rows(
+                &self.db,
+                "SELECT block_info FROM blocks ORDER BY reward_cycle, burn_block_height, signer_signature_hash",
+                NO_PARAMS,
+            )?,
+        };
+        block_jsons
+            .iter()
+            .map(|block_json| serde_json::from_str(block_json).map_err(DBError::SerializationError))
+            .collect()
+    }
+
     /// Insert a block into the database.
     /// `hash` is the `signer_signature_hash` of the block.
     pub fn insert_block(&mut self, block_info: &BlockInfo) -> Result<(), DBError> {
@@ -153,6 +240,172 @@ impl SignerDb {
 
         Ok(())
     }
+
+    /// Record the block policy's decision on a block
+    pub fn insert_policy_decision(
+        &self,
+        reward_cycle: u64,
+        decision: &PolicyDecision,
+    ) -> Result<(), DBError> {
+        let (violated_rule, reason) = match &decision.violation {
+            Some(violation) => (
+                Some(violation.rule.as_str()),
+                Some(violation.reason.as_str()),
+            ),
+            None => (None, None),
+        };
+        self.db.execute(
+            "INSERT OR REPLACE INTO block_policy_decisions (reward_cycle, signer_signature_hash, consensus_hash, tenure_extends, accepted, violated_rule, reason, decided_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                u64_to_sql(reward_cycle)?,
+                decision.signer_signature_hash.to_string(),
+                decision.consensus_hash.to_hex(),
+                u64_to_sql(decision.tenure_extends)?,
+                decision.accepted(),
+                violated_rule,
+                reason,
+                u64_to_sql(decision.decided_at)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Fetch the block policy's decision on a block, if it made one
+    pub fn get_policy_decision(
+        &self,
+        reward_cycle: u64,
+        hash: &Sha512Trunc256Sum,
+    ) -> Result<Option<PolicyDecision>, DBError> {
+        let mut stmt = self.db.prepare(
+            "SELECT consensus_hash, tenure_extends, violated_rule, reason, decided_at FROM block_policy_decisions WHERE reward_cycle = ? AND signer_signature_hash = ?",
+        )?;
+        let mut rows = stmt.query(params![u64_to_sql(reward_cycle)?, hash.to_string()])?;
+        let Some(row) = rows.next()? else {
+            return Ok(None);
+        };
+        let consensus_hash: String = row.get(0)?;
+        let consensus_hash =
+            ConsensusHash::from_hex(&consensus_hash).map_err(|_| DBError::ParseError)?;
+        let tenure_extends: i64 = row.get(1)?;
+        let violated_rule: Option<String> = row.get(2)?;
+        let reason: Option<String> = row.get(3)?;
+        let decided_at: i64 = row.get(4)?;
+        let violation = violated_rule
+            .map(|rule| -> Result<_, DBError> {
+                Ok(PolicyViolation {
+                    rule: rule.parse().map_err(|_| DBError::ParseError)?,
+                    reason: reason.unwrap_or_default(),
+                })
+            })
+            .transpose()?;
+        Ok(Some(PolicyDecision {
+            signer_signature_hash: *hash,
+            consensus_hash,
+            tenure_extends: u64::try_from(tenure_extends).map_err(|_| DBError::ParseError)?,
+            violation,
+            decided_at: u64::try_from(decided_at).map_err(|_| DBError::ParseError)?,
+        }))
+    }
+
+    /// Count the tenure extensions in the blocks the policy accepted in the given tenure
+    pub fn get_accepted_tenure_extends(
+        &self,
+        consensus_hash: &ConsensusHash,
+    ) -> Result<u64, DBError> {
+        let count: Option<u64> = query_row(
+            &self.db,
+            "SELECT COALESCE(SUM(tenure_extends), 0) FROM block_policy_decisions WHERE consensus_hash = ? AND accepted = 1",
+            [consensus_hash.to_hex()],
+        )?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// Check that signing the given block does not conflict with a block this signer already
+    /// signed, and if so, record it as signed. The check and the write happen in one immediate
+    /// transaction, so signer processes sharing this DB cannot both pass the check.
+    pub fn check_and_record_signed_block(
+        &mut self,
+        signed_block: &SignedBlock,
+    ) -> Result<SigningCheck, DBError> {
+        let tx = tx_begin_immediate(&mut self.db)?;
+        if let Some(conflict) = Self::find_conflicting_signed_block(&tx, signed_block)? {
+            return Ok(SigningCheck::Conflict(conflict));
+        }
+        Self::insert_signed_block_in(&tx, signed_block)?;
+        tx.commit()?;
+        Ok(SigningCheck::Allowed)
+    }
+
+    /// Get a signed block that conflicts with the given one, if there is one
+    pub fn get_conflicting_signed_block(
+        &self,
+        signed_block: &SignedBlock,
+    ) -> Result<Option<SignedBlock>, DBError> {
+        Self::find_conflicting_signed_block(&self.db, signed_block)
+    }
+
+    /// Record a block as signed, unless it already is. Returns whether it was inserted.
+    pub fn insert_signed_block(&self, signed_block: &SignedBlock) -> Result<bool, DBError> {
+        Self::insert_signed_block_in(&self.db, signed_block)
+    }
+
+    /// Get every block this signer has signed, oldest first
+    pub fn get_signed_blocks(&self) -> Result<Vec<SignedBlock>, DBError> {
+        query_rows(
+            &self.db,
+            "SELECT * FROM signed_blocks ORDER BY reward_cycle, burn_block_height, signed_at",
+            NO_PARAMS,
+        )
+    }
+
+    fn find_conflicting_signed_block(
+        conn: &Connection,
+        signed_block: &SignedBlock,
+    ) -> Result<Option<SignedBlock>, DBError> {
+        query_row(
+            conn,
+            "SELECT * FROM signed_blocks WHERE reward_cycle = ?1 AND burn_block_height = ?2 AND parent_block_id = ?3 AND signer_signature_hash != ?4 LIMIT 1",
+            params![
+                u64_to_sql(signed_block.reward_cycle)?,
+                u64_to_sql(signed_block.burn_block_height)?,
+                signed_block.parent_block_id.to_hex(),
+                signed_block.signer_signature_hash.to_string(),
+            ],
+        )
+    }
+
+    fn insert_signed_block_in(
+        conn: &Connection,
+        signed_block: &SignedBlock,
+    ) -> Result<bool, DBError> {
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO signed_blocks (reward_cycle, burn_block_height, parent_block_id, signer_signature_hash, signed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                u64_to_sql(signed_block.reward_cycle)?,
+                u64_to_sql(signed_block.burn_block_height)?,
+                signed_block.parent_block_id.to_hex(),
+                signed_block.signer_signature_hash.to_string(),
+                u64_to_sql(signed_block.signed_at)?,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+}
+
+impl FromRow<SignedBlock> for SignedBlock {
+    fn from_row(row: &Row) -> Result<SignedBlock, DBError> {
+        let parent_block_id: String = row.get("parent_block_id")?;
+        let signer_signature_hash: String = row.get("signer_signature_hash")?;
+        Ok(SignedBlock {
+            reward_cycle: u64::from_column(row, "reward_cycle")?,
+            burn_block_height: u64::from_column(row, "burn_block_height")?,
+            parent_block_id: StacksBlockId::from_hex(&parent_block_id)
+                .map_err(|_| DBError::ParseError)?,
+            signer_signature_hash: Sha512Trunc256Sum::from_hex(&signer_signature_hash)
+                .map_err(|_| DBError::ParseError)?,
+            signed_at: u64::from_column(row, "signed_at")?,
+        })
+    }
 }
 
 fn try_deserialize<T>(s: Option<String>) -> Result<Option<T>, DBError>
@@ -166,6 +419,7 @@ where
 }
 
 #[cfg(test)]
+/// Create a fresh signer db at the given path, removing any existing file
 pub fn test_signer_db(db_path: &str) -> SignerDb {
     use std::fs;
 
@@ -186,6 +440,7 @@ mod tests {
     use libsigner::BlockProposal;
 
     use super::*;
+    use crate::policy::PolicyRule;
 
     fn _wipe_db(db_path: &PathBuf) {
         if fs::metadata(db_path).is_ok() {
@@ -223,9 +478,9 @@ mod tests {
 
     fn test_basic_signer_db_with_path(db_path: impl AsRef<Path>) {
         let mut db = SignerDb::new(db_path).expect("Failed to create signer db");
-        let (block_info, block_proposal) = create_block();
-        let reward_cycle = block_info.reward_cycle;
-        db.insert_block(&block_info)
+        let (inserted_block_info, block_proposal) = create_block();
+        let reward_cycle = inserted_block_info.reward_cycle;
+        db.insert_block(&inserted_block_info)
             .expect("Unable to insert block into db");
         let block_info = db
             .block_lookup(
@@ -235,7 +490,7 @@ mod tests {
             .unwrap()
             .expect("Unable to get block from db");
 
-        assert_eq!(BlockInfo::from(block_proposal.clone()), block_info);
+        assert_eq!(inserted_block_info, block_info);
 
         // Test looking up a block from a different reward cycle
         let block_info = db
@@ -262,9 +517,9 @@ mod tests {
     fn test_update_block() {
         let db_path = tmp_db_path();
         let mut db = SignerDb::new(db_path).expect("Failed to create signer db");
-        let (block_info, block_proposal) = create_block();
-        let reward_cycle = block_info.reward_cycle;
-        db.insert_block(&block_info)
+        let (inserted_block_info, block_proposal) = create_block();
+        let reward_cycle = inserted_block_info.reward_cycle;
+        db.insert_block(&inserted_block_info)
             .expect("Unable to insert block into db");
 
         let block_info = db
@@ -275,7 +530,7 @@ mod tests {
             .unwrap()
             .expect("Unable to get block from db");
 
-        assert_eq!(BlockInfo::from(block_proposal.clone()), block_info);
+        assert_eq!(inserted_block_info, block_info);
 
         let old_block_info = block_info;
         let old_block_proposal = block_proposal;
@@ -308,6 +563,80 @@ mod tests {
         assert_eq!(block_info.vote, Some(vote));
     }
 
+    #[test]
+    fn test_get_blocks() {
+        let mut db = SignerDb::new(":memory:").expect("Failed to create signer db");
+        let mut block_infos = vec![];
+        for (reward_cycle, burn_height, chain_length) in [(2, 20, 3), (1, 11, 2), (1, 10, 1)] {
+            let (block_info, _) = create_block_override(|b| {
+                b.reward_cycle = reward_cycle;
+                b.burn_height = burn_height;
+                b.block.header.chain_length = chain_length;
+            });
+            db.insert_block(&block_info)
+                .expect("Unable to insert block into db");
+            block_infos.push(block_info);
+        }
+        block_infos.reverse();
+
+        assert_eq!(db.get_blocks(None).unwrap(), block_infos);
+        assert_eq!(db.get_blocks(Some(1)).unwrap(), block_infos[..2]);
+        assert_eq!(db.get_blocks(Some(2)).unwrap(), block_infos[2..]);
+        assert!(db.get_blocks(Some(3)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_policy_decisions() {
+        let db_path = tmp_db_path();
+        let db = SignerDb::new(db_path).expect("Failed to create signer db");
+        let tenure = ConsensusHash([0x01; 20]);
+        let accepted = PolicyDecision {
+            signer_signature_hash: Sha512Trunc256Sum([0x01; 32]),
+            consensus_hash: tenure,
+            tenure_extends: 1,
+            violation: None,
+            decided_at: 1_700_000_000,
+        };
+        let rejected = PolicyDecision {
+            signer_signature_hash: Sha512Trunc256Sum([0x02; 32]),
+            consensus_hash: tenure,
+            tenure_extends: 1,
+            violation: Some(PolicyViolation {
+                rule: PolicyRule::TenureExtends,
+                reason: "too many tenure extensions".into(),
+            }),
+            decided_at: 1_700_000_001,
+        };
+        assert_eq!(db.get_accepted_tenure_extends(&tenure).unwrap(), 0);
+        db.insert_policy_decision(10, &accepted)
+            .expect("Failed to insert policy decision");
+        db.insert_policy_decision(10, &rejected)
+            .expect("Failed to insert policy decision");
+
+        assert_eq!(
+            db.get_policy_decision(10, &accepted.signer_signature_hash)
+                .unwrap(),
+            Some(accepted.clone())
+        );
+        assert_eq!(
+            db.get_policy_decision(10, &rejected.signer_signature_hash)
+                .unwrap(),
+            Some(rejected)
+        );
+        assert!(db
+            .get_policy_decision(11, &accepted.signer_signature_hash)
+            .unwrap()
+            .is_none());
+
+        // Only accepted blocks count towards the tenure's extensions
+        assert_eq!(db.get_accepted_tenure_extends(&tenure).unwrap(), 1);
+        assert_eq!(
+            db.get_accepted_tenure_extends(&ConsensusHash([0x02; 20]))
+                .unwrap(),
+            0
+        );
+    }
+
     #[test]
     fn test_write_signer_state() {
         let db_path = tmp_db_path();
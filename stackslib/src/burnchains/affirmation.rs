@@ -1239,3 +1239,210 @@ pub fn update_pox_affirmation_maps<B: BurnchainHeaderReader>(
 
     Ok(())
 }
+
+/// A prepare-phase block-commit, and how it voted on its reward cycle's anchor block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrepareCommitAffirmation {
+    pub txid: Txid,
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub block_height: u64,
+    pub vtxindex: u32,
+    pub apparent_sender: String,
+    pub burn_fee: u64,
+    /// Whether this block-commit descends from the reward cycle's anchor block
+    pub confirms_anchor_block: bool,
+    /// This block-commit's affirmation map, if it has one (orphaned and late block-commits do
+    /// not)
+    pub affirmation_map: Option<AffirmationMap>,
+}
+
+/// How a reward cycle's anchor block was elected, and how the burnchain affirmed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardCycleAffirmation {
+    pub reward_cycle: u64,
+    /// The block-commit on the canonical burnchain fork that was elected as this reward cycle's
+    /// anchor block, if any
+    pub anchor_block_txid: Option<Txid>,
+    pub anchor_block_burn_header_hash: Option<BurnchainHeaderHash>,
+    pub anchor_block_height: Option<u64>,
+    pub anchor_stacks_block_hash: Option<BlockHeaderHash>,
+    /// The anchor block-commit's affirmation map, and its weight
+    pub anchor_affirmation_map: Option<AffirmationMap>,
+    pub anchor_affirmation_weight: Option<u64>,
+    /// Number of prepare-phase blocks with a block-commit that descends from the anchor block
+    pub confirmations: u64,
+    /// BTC burnt by the prepare-phase block-commits that descend from the anchor block
+    pub confirming_burn: u64,
+    /// All block-commits in the prepare phase that elected this reward cycle's anchor block
+    pub prepare_phase_commits: Vec<PrepareCommitAffirmation>,
+    /// The affirmation map set by the operator for this reward cycle, if any
+    pub override_affirmation_map: Option<AffirmationMap>,
+}
+
+/// Describe how the anchor block for `reward_cycle` was elected and affirmed, on the burnchain
+/// fork that the sortition DB considers canonical.
+pub fn get_reward_cycle_affirmation(
+    burnchain_db: &BurnchainDB,
+    sortdb: &SortitionDB,
+    burnchain: &Burnchain,
+    reward_cycle: u64,
+) -> Result<RewardCycleAffirmation, Error> {
+    let conn = burnchain_db.conn();
+    let sort_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+
+    // the anchor block for this reward cycle is elected in the prepare phase that ends at its
+    // first block
+    let prepare_end = burnchain
+        .reward_cycle_to_block_height(reward_cycle)
+        .min(sort_tip.block_height + 1);
+    let prepare_start = burnchain
+        .reward_cycle_to_block_height(reward_cycle)
+        .saturating_sub(burnchain.pox_constants.prepare_length.into());
+
+    let canonical_hash_at = |height: u64| -> Result<Option<BurnchainHeaderHash>, DBError> {
+        Ok(SortitionDB::get_ancestor_snapshot(
+            &sortdb.index_conn(),
+            height,
+            &sort_tip.sortition_id,
+        )?
+        .map(|sn| sn.burn_header_hash))
+    };
+
+    let mut anchor = None;
+    for metadata in BurnchainDB::get_anchor_block_commit_metadatas(conn, reward_cycle)? {
+        if canonical_hash_at(metadata.block_height)?.as_ref() == Some(&metadata.burn_block_hash) {
+            let commit =
+                BurnchainDB::get_block_commit(conn, &metadata.burn_block_hash, &metadata.txid)?
+                    .expect("BUG: no block-commit for block-commit metadata");
+            anchor = Some((commit, metadata));
+            break;
+        }
+    }
+
+    let mut prepare_phase_commits = vec![];
+    let mut confirmations = 0;
+    let mut confirming_burn = 0u64;
+    for height in prepare_start..prepare_end {
+        let Some(burn_header_hash) = canonical_hash_at(height)? else {
+            continue;
+        };
+        let block = BurnchainDB::get_burnchain_block(conn, &burn_header_hash)?;
+        let mut confirmed_here = false;
+        for op in block.ops.into_iter() {
+            let BlockstackOperationType::LeaderBlockCommit(commit) = op else {
+                continue;
+            };
+            let metadata =
+                BurnchainDB::get_commit_metadata(conn, &commit.burn_header_hash, &commit.txid)?;
+            let confirms_anchor_block = anchor.is_some()
+                && metadata
+                    .as_ref()
+                    .map(|md| md.anchor_block_descendant == Some(reward_cycle))
+                    .unwrap_or(false);
+            let affirmation_map = match metadata.as_ref() {
+                Some(md) => BurnchainDB::get_affirmation_map(conn, md.affirmation_id)?,
+                None => None,
+            };
+            if confirms_anchor_block {
+                confirmed_here = true;
+                confirming_burn = confirming_burn.saturating_add(commit.burn_fee);
+            }
+            prepare_phase_commits.push(PrepareCommitAffirmation {
+                txid: commit.txid,
+                burn_header_hash: commit.burn_header_hash,
+                block_height: commit.block_height,
+                vtxindex: commit.vtxindex,
+                apparent_sender: commit.apparent_sender.to_string(),
+                burn_fee: commit.burn_fee,
+                confirms_anchor_block,
+                affirmation_map,
+            });
+        }
+        if confirmed_here {
+            confirmations += 1;
+        }
+    }
+
+    let (anchor_affirmation_map, anchor_affirmation_weight) = match anchor.as_ref() {
+        Some((_, metadata)) => (
+            BurnchainDB::get_affirmation_map(conn, metadata.affirmation_id)?,
+            BurnchainDB::get_affirmation_weight(conn, metadata.affirmation_id)?,
+        ),
+        None => (None, None),
+    };
+
+    Ok(RewardCycleAffirmation {
+        reward_cycle,
+        anchor_block_txid: anchor.as_ref().map(|(commit, _)| commit.txid.clone()),
+        anchor_block_burn_header_hash: anchor
+            .as_ref()
+            .map(|(commit, _)| commit.burn_header_hash.clone()),
+        anchor_block_height: anchor.as_ref().map(|(commit, _)| commit.block_height),
+        anchor_stacks_block_hash: anchor
+            .as_ref()
+            .map(|(commit, _)| commit.block_header_hash.clone()),
+        anchor_affirmation_map,
+        anchor_affirmation_weight,
+        confirmations,
+        confirming_burn,
+        prepare_phase_commits,
+        override_affirmation_map: BurnchainDB::get_override_affirmation_map(conn, reward_cycle)?,
+    })
+}
+
+/// Check that `affirmation_map` can be used as the affirmation override for `reward_cycle`
+/// (i.e. as a `burnchain.affirmation_overrides` entry) before it is applied on the node's next
+/// restart.  Returns warnings about entries that are allowed but look wrong, or an error if the
+/// override must not be used.
+pub fn check_affirmation_override(
+    burnchain_db: &BurnchainDB,
+    reward_cycle: u64,
+    affirmation_map: &AffirmationMap,
+) -> Result<Vec<String>, String> {
+    let conn = burnchain_db.conn();
+    if reward_cycle == 0 {
+        return Err("There is no affirmation map for reward cycle 0".into());
+    }
+    if (affirmation_map.len() as u64) + 1 != reward_cycle {
+        return Err(format!(
+            "The override for reward cycle {} must have {} entries, but it has {}",
+            reward_cycle,
+            reward_cycle - 1,
+            affirmation_map.len()
+        ));
+    }
+
+    let mut warnings = vec![];
+    for (i, entry) in affirmation_map.as_slice().iter().enumerate() {
+        let rc = (i as u64) + 1;
+        let has_anchor_block = BurnchainDB::has_anchor_block(conn, rc).map_err(|e| {
+            format!(
+                "Failed to query anchor block for reward cycle {}: {:?}",
+                rc, &e
+            )
+        })?;
+        match entry {
+            AffirmationMapEntry::PoxAnchorBlockPresent if !has_anchor_block => {
+                return Err(format!(
+                    "Reward cycle {} is affirmed to have an anchor block, but no anchor block was elected for it",
+                    rc
+                ));
+            }
+            AffirmationMapEntry::PoxAnchorBlockAbsent if !has_anchor_block => {
+                warnings.push(format!(
+                    "Reward cycle {} is affirmed to have a missing anchor block, but no anchor block was elected for it",
+                    rc
+                ));
+            }
+            AffirmationMapEntry::Nothing if has_anchor_block => {
+                warnings.push(format!(
+                    "Reward cycle {} is affirmed to have no anchor block, but an anchor block was elected for it",
+                    rc
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(warnings)
+}
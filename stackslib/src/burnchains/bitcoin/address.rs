@@ -461,6 +461,24 @@ impl BitcoinAddress {
         )))
     }
 
+    /// Make a segwit p2tr bitcoin address from a Taproot output key
+    pub fn from_bytes_segwit_p2tr(
+        network_id: BitcoinNetworkType,
+        bytes: &[u8],
+    ) -> Result<BitcoinAddress, btc_error> {
+        if bytes.len() != 32 {
+            return Err(btc_error::InvalidByteSequence);
+        }
+
+        let mut my_bytes = [0; 32];
+        my_bytes.copy_from_slice(bytes);
+
+        let mainnet = network_id == BitcoinNetworkType::Mainnet;
+        Ok(BitcoinAddress::Segwit(SegwitBitcoinAddress::P2TR(
+            mainnet, my_bytes,
+        )))
+    }
+
     /// Instantiate an address from a scriptpubkey
     /// If we don't recognize it, then return None.
     /// WARNING: cannot differentiate between p2sh and segwit-p2sh
@@ -1105,4 +1123,30 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_from_bytes_segwit_p2tr() {
+        // BIP-86 test vector: m/86'/0'/0'/0/0
+        let output_key =
+            hex_bytes("a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c").unwrap();
+        let addr = BitcoinAddress::from_bytes_segwit_p2tr(BitcoinNetworkType::Mainnet, &output_key)
+            .unwrap();
+        assert_eq!(
+            addr.to_string(),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+        assert!(addr.is_segwit_p2tr());
+        assert_eq!(
+            BitcoinAddress::from_string(
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+            ),
+            Some(addr)
+        );
+
+        assert!(BitcoinAddress::from_bytes_segwit_p2tr(
+            BitcoinNetworkType::Mainnet,
+            &output_key[1..]
+        )
+        .is_err());
+    }
 }
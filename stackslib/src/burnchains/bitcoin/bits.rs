@@ -38,6 +38,9 @@ use crate::chainstate::stacks::{
     C32_ADDRESS_VERSION_TESTNET_MULTISIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
 
+/// First byte of a BIP-341 witness annex
+const TAPROOT_ANNEX_TAG: u8 = 0x50;
+
 /// Parse a script into its structured constituant opcodes and data and collect them
 pub fn parse_script<'a>(script: &'a Script) -> Vec<Instruction<'a>> {
     // we will have to accept non-minimial pushdata since there's at least one OP_RETURN
@@ -502,6 +505,25 @@ impl BitcoinTxInputRaw {
             tx_ref: input_txid,
         }
     }
+
+    /// Is this input a Taproot key-path spend?  Per BIP-341, such an input has an empty
+    /// scriptSig, and its witness is a single 64- or 65-byte Schnorr signature (optionally
+    /// followed by an annex, which starts with 0x50).
+    /// Note that the spender's public key is not recoverable from a key-path spend; it is the
+    /// witness program of the spent output.
+    pub fn is_p2tr_key_spend(&self) -> bool {
+        if !self.scriptSig.is_empty() {
+            return false;
+        }
+        let sig = match self.witness.as_slice() {
+            [sig] => sig,
+            [sig, annex] if annex.first() == Some(&TAPROOT_ANNEX_TAG) => sig,
+            _ => {
+                return false;
+            }
+        };
+        sig.len() == 64 || sig.len() == 65
+    }
 }
 
 /// All of the parsing code in this implementation is for use in Stacks 2.05 or earlier.
@@ -547,6 +569,16 @@ impl BitcoinTxInput {
             BitcoinTxInput::Raw(ref inp) => &inp.tx_ref,
         }
     }
+
+    /// Is this input a Taproot key-path spend?
+    /// Structured inputs never are, since they are only produced for scriptSigs and witnesses
+    /// from which we can extract public keys.
+    pub fn is_p2tr_key_spend(&self) -> bool {
+        match *self {
+            BitcoinTxInput::Structured(..) => false,
+            BitcoinTxInput::Raw(ref inp) => inp.is_p2tr_key_spend(),
+        }
+    }
 }
 
 fn to_txid(txin: &BtcTxIn) -> (Txid, u32) {
@@ -1116,6 +1148,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_input_p2tr_key_spend() {
+        let sig = "a60c383f71bac0ec919b1d7dbc3eb72dd56e7aa99583615564f9f99b8ae4e837b758773a5b2e4c51348854c8389f008e05029db7f464a5ff2e01d5e6e626174a";
+        let sig_with_hashtype = format!("{}01", sig);
+
+        // SIGHASH_DEFAULT and explicit sighash types
+        assert!(BitcoinTxInputRaw::from_hex_parts("", &[sig]).is_p2tr_key_spend());
+        assert!(BitcoinTxInputRaw::from_hex_parts("", &[&sig_with_hashtype]).is_p2tr_key_spend());
+
+        // with an annex
+        assert!(BitcoinTxInputRaw::from_hex_parts("", &[sig, "50aabb"]).is_p2tr_key_spend());
+        assert!(!BitcoinTxInputRaw::from_hex_parts("", &[sig, "51aabb"]).is_p2tr_key_spend());
+
+        // not a key-path spend
+        assert!(!BitcoinTxInputRaw::from_hex_parts("", &[]).is_p2tr_key_spend());
+        assert!(!BitcoinTxInputRaw::from_hex_parts("", &[&sig[2..]]).is_p2tr_key_spend());
+        assert!(!BitcoinTxInputRaw::from_hex_parts("00", &[sig]).is_p2tr_key_spend());
+
+        // p2wpkh
+        assert!(!BitcoinTxInputRaw::from_hex_parts(
+            "",
+            &[
+                "3044022012e0c8d9ee2aa53cb14e3f6ac1e66b05d84ab2ee2a4193df4d0e4ce3bc18cd5b02201c2ec3a1d2b2f1bcd5f2bb4eb4f4be5458f6ad1e5fd2e1c7a5c1fa53f2f6a64e01",
+                "0355bf7c3b7c46a5d2f8ba2ff07fa6d1b7f1a4fb0b3f6b0bcbb3bd8a6e0e3ad1a2",
+            ]
+        )
+        .is_p2tr_key_spend());
+    }
+
     /// Make sure we can decode taproot scripts with the current vendored version of bitcoin-rs
     #[test]
     fn test_input_taproot() {
@@ -1280,6 +1341,13 @@ mod tests {
                     to_txid(&txin),
                 );
                 assert_eq!(raw_in, inputs[i]);
+
+                // key-path spends are exactly the single-signature witnesses in these fixtures
+                assert_eq!(raw_in.is_p2tr_key_spend(), inputs[i].witness.len() == 1);
+                assert_eq!(
+                    BitcoinTxInput::from_bitcoin_txin_raw(&txin).is_p2tr_key_spend(),
+                    inputs[i].witness.len() == 1
+                );
             }
 
             let mut j = 0;
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
 use std::ops::Deref;
 
 use stacks_common::deps_common::bitcoin::blockdata::block::{Block, LoneBlockHeader};
@@ -24,13 +25,14 @@ use stacks_common::deps_common::bitcoin::network::message as btc_message;
 use stacks_common::deps_common::bitcoin::network::serialize::BitcoinHash;
 use stacks_common::deps_common::bitcoin::util::hash::bitcoin_merkle_root;
 use stacks_common::types::chainstate::BurnchainHeaderHash;
-use stacks_common::util::hash::to_hex;
+use stacks_common::util::hash::{to_hex, Hash160};
 use stacks_common::util::log;
 
 use crate::burnchains::bitcoin::address::BitcoinAddress;
 use crate::burnchains::bitcoin::indexer::BitcoinIndexer;
 use crate::burnchains::bitcoin::keys::BitcoinPublicKey;
 use crate::burnchains::bitcoin::messages::BitcoinMessageHandler;
+use crate::burnchains::bitcoin::wallet::BitcoinWalletActivity;
 use crate::burnchains::bitcoin::{
     bits, BitcoinBlock, BitcoinInputType, BitcoinNetworkType, BitcoinTransaction, BitcoinTxInput,
     BitcoinTxOutput, Error as btc_error, PeerMessage,
@@ -99,6 +101,7 @@ pub struct BitcoinBlockDownloader {
 pub struct BitcoinBlockParser {
     network_id: BitcoinNetworkType,
     magic_bytes: MagicBytes,
+    watched_pubkey_hashes: HashSet<Hash160>,
 }
 
 impl BitcoinBlockDownloader {
@@ -233,9 +236,16 @@ impl BitcoinBlockParser {
         BitcoinBlockParser {
             network_id: network_id,
             magic_bytes: magic_bytes.clone(),
+            watched_pubkey_hashes: HashSet::new(),
         }
     }
 
+    /// Also report the outputs received and spent by these public key hashes in each parsed block
+    pub fn with_watched_pubkey_hashes(mut self, pubkey_hashes: &[Hash160]) -> BitcoinBlockParser {
+        self.watched_pubkey_hashes = pubkey_hashes.iter().cloned().collect();
+        self
+    }
+
     /// Allow raw inputs?
     fn allow_raw_inputs(epoch_id: StacksEpochId) -> bool {
         epoch_id >= StacksEpochId::Epoch21
@@ -483,6 +493,7 @@ impl BitcoinBlockParser {
             parent_block_hash: BurnchainHeaderHash::from_bitcoin_hash(&block.header.prev_blockhash),
             txs: accepted_txs,
             timestamp: block.header.time as u64,
+            wallet_activity: BitcoinWalletActivity::scan_block(block, &self.watched_pubkey_hashes),
         }
     }
 
@@ -555,6 +566,7 @@ mod tests {
     use super::BitcoinBlockParser;
     use crate::burnchains::bitcoin::address::{BitcoinAddress, LegacyBitcoinAddressType};
     use crate::burnchains::bitcoin::keys::BitcoinPublicKey;
+    use crate::burnchains::bitcoin::wallet::BitcoinWalletActivity;
     use crate::burnchains::bitcoin::{
         BitcoinBlock, BitcoinInputType, BitcoinNetworkType, BitcoinTransaction, BitcoinTxInput,
         BitcoinTxInputRaw, BitcoinTxInputStructured, BitcoinTxOutput,
@@ -1064,6 +1076,7 @@ mod tests {
                         }
                     ],
                     timestamp: 1543267060,
+                    wallet_activity: BitcoinWalletActivity::default(),
                 })
             },
             BlockFixture {
@@ -1076,6 +1089,7 @@ mod tests {
                     block_hash: to_block_hash(&hex_bytes("4f3757bc236e58b87d6208aa795115002b739bf39268cf69640f0b092e8cdafe").unwrap()),
                     parent_block_hash: to_block_hash(&hex_bytes("25af4b7151b77f6f8235bda83a8062fba621591beef57e18f4697c8b88a298ad").unwrap()),
                     timestamp: 1543272755,
+                    wallet_activity: BitcoinWalletActivity::default(),
                     txs: vec![
                         BitcoinTransaction {
                             data_amt: 0,
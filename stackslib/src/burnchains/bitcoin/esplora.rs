@@ -0,0 +1,1312 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Burnchain backend for an Esplora-compatible REST API.
+//!
+//! `EsploraIndexer` is a `BurnchainIndexer` that fetches headers and blocks over HTTP from an
+//! Esplora server (i.e. the API served by blockstream.info and mempool.space, or a self-hosted
+//! electrs) instead of from a bitcoind peer.  Headers are stored in the same SPV headers DB as
+//! the `BitcoinIndexer` uses, so anything that reads headers through a `BitcoinIndexer` keeps
+//! working.
+//!
+//! Headers from the server are checked the same way as headers from a bitcoind peer: each must
+//! meet its own `bits`, follow the difficulty adjustment rules, and match any checkpoints, and
+//! the server's chain only replaces ours if it has more cumulative work.
+//!
+//! Only `http://` URLs are supported, since there is no TLS client here.  Point the indexer at an
+//! Esplora server you run yourself, or reach one over a trusted network (e.g. an SSH tunnel or a
+//! VPN).  A public `https://` endpoint cannot be used directly.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{cmp, io};
+
+use stacks_common::deps_common::bitcoin::blockdata::block::{Block, BlockHeader, LoneBlockHeader};
+use stacks_common::deps_common::bitcoin::network::encodable::VarInt;
+use stacks_common::deps_common::bitcoin::network::message::NetworkMessage;
+use stacks_common::deps_common::bitcoin::network::serialize::{deserialize, BitcoinHash};
+use stacks_common::deps_common::bitcoin::util::hash::Sha256dHash;
+use stacks_common::deps_common::httparse;
+use stacks_common::types::chainstate::BurnchainHeaderHash;
+use stacks_common::util::chunked_encoding::HttpChunkedTransferReader;
+use stacks_common::util::hash::{to_hex, Hash160};
+use stacks_common::util::uint::Uint256;
+use url::Url;
+
+use crate::burnchains::bitcoin::blocks::{BitcoinBlockIPC, BitcoinBlockParser, BitcoinHeaderIPC};
+use crate::burnchains::bitcoin::spv::{SpvClient, BLOCK_DIFFICULTY_CHUNK_SIZE};
+use crate::burnchains::bitcoin::{BitcoinNetworkType, Error as btc_error};
+use crate::burnchains::db::BurnchainHeaderReader;
+use crate::burnchains::indexer::{
+    BurnHeaderIPC, BurnchainBlockDownloader, BurnchainBlockParser, BurnchainIndexer,
+};
+use crate::burnchains::{
+    BurnchainBlock, BurnchainBlockHeader, Error as burnchain_error, MagicBytes, Txid,
+};
+use crate::core::{StacksEpoch, StacksEpochExtension, StacksEpochId};
+use crate::util_lib::db::Error as DBError;
+
+/// Number of block summaries returned by `GET /blocks/:start_height`
+pub const ESPLORA_BLOCKS_PER_REQUEST: u64 = 10;
+
+/// Largest response body we will read from the server.  Blocks are at most 4MB.
+const MAX_ESPLORA_RESPONSE_LEN: u64 = 16 * 1024 * 1024;
+
+const MAX_HTTP_HEADERS: usize = 32;
+
+/// Block summary, as returned by `GET /blocks/:start_height`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct EsploraBlockSummary {
+    id: String,
+    height: u64,
+    version: u32,
+    timestamp: u32,
+    bits: u32,
+    nonce: u32,
+    merkle_root: String,
+    previousblockhash: Option<String>,
+}
+
+impl EsploraBlockSummary {
+    /// Rebuild the block header, and check that it hashes to the reported block ID
+    fn to_header(&self) -> Result<BlockHeader, btc_error> {
+        let prev_blockhash = match self.previousblockhash.as_ref() {
+            Some(hash) => Sha256dHash::from_hex(hash).map_err(btc_error::HashError)?,
+            None => Sha256dHash::default(),
+        };
+        let header = BlockHeader {
+            version: self.version,
+            prev_blockhash,
+            merkle_root: Sha256dHash::from_hex(&self.merkle_root).map_err(btc_error::HashError)?,
+            time: self.timestamp,
+            bits: self.bits,
+            nonce: self.nonce,
+        };
+        let block_hash = Sha256dHash::from_hex(&self.id).map_err(btc_error::HashError)?;
+        if header.bitcoin_hash() != block_hash {
+            warn!(
+                "Esplora block summary for {} at height {} hashes to {}",
+                &self.id,
+                self.height,
+                &header.bitcoin_hash()
+            );
+            return Err(btc_error::InvalidReply);
+        }
+        if header.spv_validate(&header.target()).is_err() {
+            warn!(
+                "Esplora block {} at height {} has invalid proof-of-work",
+                &self.id, self.height
+            );
+            return Err(btc_error::InvalidPoW);
+        }
+        Ok(header)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EsploraTxStatus {
+    confirmed: bool,
+    block_height: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EsploraUtxoEntry {
+    txid: String,
+    vout: u32,
+    value: u64,
+    status: EsploraTxStatus,
+}
+
+/// An unspent output, as reported by `GET /address/:address/utxo`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EsploraUtxo {
+    /// Transaction ID, in the byte order used by `Transaction::txid()`
+    pub txid: Sha256dHash,
+    pub vout: u32,
+    pub value: u64,
+    /// Height of the block that confirmed the output, if it is confirmed
+    pub block_height: Option<u64>,
+}
+
+/// Minimal blocking HTTP/1.1 client for an Esplora server
+#[derive(Debug, Clone)]
+pub struct EsploraClient {
+    host: String,
+    port: u16,
+    path_prefix: String,
+    timeout: Duration,
+}
+
+impl EsploraClient {
+    /// Make a client for the Esplora API rooted at `url` (e.g. `http://localhost:3000/api`).
+    /// Only `http://` URLs are accepted, so the server should be local or reached over a trusted
+    /// network.
+    pub fn new(url: &str, timeout_secs: u64) -> Result<EsploraClient, btc_error> {
+        let parsed = Url::parse(url)
+            .map_err(|e| btc_error::ConfigError(format!("Invalid Esplora URL {}: {}", url, e)))?;
+        if parsed.scheme() != "http" {
+            return Err(btc_error::ConfigError(format!(
+                "Unsupported Esplora URL {}: only http:// is supported, so use a local or otherwise trusted server",
+                url
+            )));
+        }
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| btc_error::ConfigError(format!("No host in Esplora URL {}", url)))?
+            .to_string();
+        let port = parsed.port_or_known_default().unwrap_or(80);
+        Ok(EsploraClient {
+            host,
+            port,
+            path_prefix: parsed.path().trim_end_matches('/').to_string(),
+            timeout: Duration::from_secs(cmp::max(timeout_secs, 1)),
+        })
+    }
+
+    fn io_error(e: io::Error) -> btc_error {
+        match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => btc_error::TimedOut,
+            io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted => btc_error::ConnectionError,
+            _ => btc_error::Io(e),
+        }
+    }
+
+    /// Send a request and return the response body.  Non-200 replies are reported as
+    /// `btc_error::HttpError`.
+    fn request(
+        &self,
+        verb: &str,
+        path: &str,
+        content_type: Option<&str>,
+        payload: &[u8],
+    ) -> Result<Vec<u8>, btc_error> {
+        let full_path = format!("{}{}", &self.path_prefix, path);
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(EsploraClient::io_error)?
+            .next()
+            .ok_or(btc_error::ConnectionError)?;
+
+        let mut sock =
+            TcpStream::connect_timeout(&addr, self.timeout).map_err(EsploraClient::io_error)?;
+        sock.set_read_timeout(Some(self.timeout))
+            .map_err(EsploraClient::io_error)?;
+        sock.set_write_timeout(Some(self.timeout))
+            .map_err(EsploraClient::io_error)?;
+
+        let content_type_hdr = content_type
+            .map(|ct| format!("Content-Type: {}\r\n", ct))
+            .unwrap_or_default();
+        let req_txt = format!(
+            "{} {} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\n{}Content-Length: {}\r\nUser-Agent: stacks/esplora\r\nAccept: */*\r\n\r\n",
+            verb, &full_path, &self.host, self.port, content_type_hdr, payload.len()
+        );
+        debug!("Esplora request: {} {}", verb, &full_path);
+
+        sock.write_all(req_txt.as_bytes())
+            .map_err(EsploraClient::io_error)?;
+        sock.write_all(payload).map_err(EsploraClient::io_error)?;
+
+        let mut buf = vec![];
+        sock.take(MAX_ESPLORA_RESPONSE_LEN)
+            .read_to_end(&mut buf)
+            .map_err(EsploraClient::io_error)?;
+
+        let mut headers_buf = [httparse::EMPTY_HEADER; MAX_HTTP_HEADERS];
+        let mut resp = httparse::Response::new(&mut headers_buf);
+        let body_offset = match resp.parse(&buf) {
+            Ok(httparse::Status::Complete(body_offset)) => body_offset,
+            _ => {
+                warn!(
+                    "Failed to decode Esplora response to {} {}",
+                    verb, &full_path
+                );
+                return Err(btc_error::InvalidReply);
+            }
+        };
+        let code = resp.code.ok_or(btc_error::InvalidReply)?;
+        let chunked = resp.headers.iter().any(|hdr| {
+            hdr.name.eq_ignore_ascii_case("transfer-encoding")
+                && hdr.value.eq_ignore_ascii_case(b"chunked")
+        });
+
+        let mut body_bytes = &buf[body_offset..];
+        let body = if chunked {
+            let mut fd =
+                HttpChunkedTransferReader::from_reader(&mut body_bytes, MAX_ESPLORA_RESPONSE_LEN);
+            let mut decoded = vec![];
+            fd.read_to_end(&mut decoded)
+                .map_err(EsploraClient::io_error)?;
+            decoded
+        } else {
+            body_bytes.to_vec()
+        };
+
+        if code != 200 {
+            let reason = String::from_utf8_lossy(&body).to_string();
+            debug!(
+                "Esplora replied {} to {} {}: {}",
+                code, verb, &full_path, &reason
+            );
+            return Err(btc_error::HttpError(code, reason));
+        }
+        Ok(body)
+    }
+
+    fn get(&self, path: &str) -> Result<Vec<u8>, btc_error> {
+        self.request("GET", path, None, &[])
+    }
+
+    fn get_text(&self, path: &str) -> Result<String, btc_error> {
+        let body = self.get(path)?;
+        String::from_utf8(body)
+            .map(|text| text.trim().to_string())
+            .map_err(|_| btc_error::InvalidReply)
+    }
+
+    fn get_json<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, btc_error> {
+        let body = self.get(path)?;
+        serde_json::from_slice(&body).map_err(|e| {
+            warn!("Failed to decode Esplora JSON from {}: {:?}", path, &e);
+            btc_error::InvalidReply
+        })
+    }
+
+    /// Height of the server's chain tip
+    pub fn get_tip_height(&self) -> Result<u64, btc_error> {
+        self.get_text("/blocks/tip/height")?
+            .parse()
+            .map_err(|_| btc_error::InvalidReply)
+    }
+
+    /// Hash of the block at `height` on the server's best chain
+    pub fn get_block_hash(&self, height: u64) -> Result<Sha256dHash, btc_error> {
+        let hash = self.get_text(&format!("/block-height/{}", height))?;
+        Sha256dHash::from_hex(&hash).map_err(btc_error::HashError)
+    }
+
+    /// Headers of the blocks at heights `start_height` through `end_height` (inclusive), in
+    /// ascending order.  Errors if the server does not have all of them.
+    pub fn get_block_headers(
+        &self,
+        start_height: u64,
+        end_height: u64,
+    ) -> Result<Vec<LoneBlockHeader>, btc_error> {
+        let mut headers = vec![];
+        let mut next_height = start_height;
+        while next_height <= end_height {
+            // the server returns up to ESPLORA_BLOCKS_PER_REQUEST blocks, descending from the
+            // given height
+            let batch_top = cmp::min(next_height + ESPLORA_BLOCKS_PER_REQUEST - 1, end_height);
+            let mut summaries: Vec<EsploraBlockSummary> =
+                self.get_json(&format!("/blocks/{}", batch_top))?;
+            summaries
+                .retain(|summary| summary.height >= next_height && summary.height <= batch_top);
+            summaries.sort_by_key(|summary| summary.height);
+
+            for summary in summaries.iter() {
+                if summary.height != next_height {
+                    warn!(
+                        "Esplora server did not return block {} (got {})",
+                        next_height, summary.height
+                    );
+                    return Err(btc_error::MissingHeader);
+                }
+                headers.push(LoneBlockHeader {
+                    header: summary.to_header()?,
+                    tx_count: VarInt(0),
+                });
+                next_height += 1;
+            }
+            if next_height <= batch_top {
+                warn!("Esplora server did not return block {}", next_height);
+                return Err(btc_error::MissingHeader);
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Download a whole block
+    pub fn get_block(&self, block_hash: &Sha256dHash) -> Result<Block, btc_error> {
+        let bytes = self.get(&format!("/block/{}/raw", block_hash.be_hex_string()))?;
+        deserialize(&bytes).map_err(btc_error::SerializationError)
+    }
+
+    /// List the unspent outputs of an address
+    pub fn get_address_utxos(&self, address: &str) -> Result<Vec<EsploraUtxo>, btc_error> {
+        let entries: Vec<EsploraUtxoEntry> =
+            self.get_json(&format!("/address/{}/utxo", address))?;
+        let mut utxos = Vec::with_capacity(entries.len());
+        for entry in entries.into_iter() {
+            let txid = Sha256dHash::from_hex(&entry.txid).map_err(btc_error::HashError)?;
+            utxos.push(EsploraUtxo {
+                txid,
+                vout: entry.vout,
+                value: entry.value,
+                block_height: if entry.status.confirmed {
+                    entry.status.block_height
+                } else {
+                    None
+                },
+            });
+        }
+        Ok(utxos)
+    }
+
+    /// Has a transaction been mined?
+    pub fn is_transaction_confirmed(&self, txid: &Txid) -> Result<bool, btc_error> {
+        let status: EsploraTxStatus = self.get_json(&format!("/tx/{}/status", txid))?;
+        Ok(status.confirmed)
+    }
+
+    /// Broadcast a serialized transaction.  Returns the txid the server reports.
+    pub fn broadcast_transaction(&self, tx_bytes: &[u8]) -> Result<String, btc_error> {
+        let body = self.request(
+            "POST",
+            "/tx",
+            Some("text/plain"),
+            to_hex(tx_bytes).as_bytes(),
+        )?;
+        String::from_utf8(body)
+            .map(|txid| txid.trim().to_string())
+            .map_err(|_| btc_error::InvalidReply)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EsploraIndexerConfig {
+    /// Root of the Esplora API, e.g. `http://localhost:3000/api`
+    pub url: String,
+    /// Request timeout, in seconds
+    pub timeout: u64,
+    pub spv_headers_path: String,
+    pub first_block: u64,
+    pub magic_bytes: MagicBytes,
+    pub epochs: Option<Vec<StacksEpoch>>,
+    /// public key hashes whose outputs and spends are reported in each parsed block
+    pub watched_pubkey_hashes: Vec<Hash160>,
+    /// (height, hash) checkpoints that headers must match, besides the built-in ones
+    pub header_checkpoints: Vec<(u64, BurnchainHeaderHash)>,
+}
+
+pub struct EsploraIndexer {
+    pub config: EsploraIndexerConfig,
+    pub network_id: BitcoinNetworkType,
+    pub should_keep_running: Option<Arc<AtomicBool>>,
+    client: EsploraClient,
+}
+
+pub struct EsploraBlockDownloader {
+    client: EsploraClient,
+}
+
+/// Parses blocks fetched by an `EsploraBlockDownloader`, which are the same as those fetched
+/// from a bitcoind peer.
+pub struct EsploraBlockParser {
+    parser: BitcoinBlockParser,
+}
+
+fn to_burnchain_error(e: btc_error) -> burnchain_error {
+    match e {
+        btc_error::TimedOut | btc_error::ConnectionError => burnchain_error::TrySyncAgain,
+        x => burnchain_error::Bitcoin(x),
+    }
+}
+
+impl EsploraIndexer {
+    pub fn new(
+        config: EsploraIndexerConfig,
+        network_id: BitcoinNetworkType,
+        should_keep_running: Option<Arc<AtomicBool>>,
+    ) -> Result<EsploraIndexer, btc_error> {
+        let client = EsploraClient::new(&config.url, config.timeout)?;
+        Ok(EsploraIndexer {
+            config,
+            network_id,
+            should_keep_running,
+            client,
+        })
+    }
+
+    pub fn client(&self) -> &EsploraClient {
+        &self.client
+    }
+
+    fn dup(&self) -> EsploraIndexer {
+        EsploraIndexer {
+            config: self.config.clone(),
+            network_id: self.network_id,
+            should_keep_running: self.should_keep_running.clone(),
+            client: self.client.clone(),
+        }
+    }
+
+    fn open_spv_client(&self, readwrite: bool) -> Result<SpvClient, btc_error> {
+        let mut spv_client = SpvClient::new(
+            &self.config.spv_headers_path,
+            0,
+            None,
+            self.network_id,
+            readwrite,
+            false,
+        )?;
+        spv_client.add_checkpoints(&self.config.header_checkpoints);
+        Ok(spv_client)
+    }
+
+    fn should_keep_running(&self) -> bool {
+        self.should_keep_running
+            .as_ref()
+            .map(|flag| flag.load(Ordering::SeqCst))
+            .unwrap_or(true)
+    }
+
+    /// Check the difficulty and timestamps of the headers just stored at heights
+    /// `start_height` through `end_height`, and that they did not lower the total chain work
+    /// from `total_work_before`.  Returns the new total chain work.
+    fn validate_new_headers(
+        spv_client: &mut SpvClient,
+        start_height: u64,
+        end_height: u64,
+        total_work_before: Uint256,
+    ) -> Result<Uint256, btc_error> {
+        // the first difficulty interval has no parent interval to retarget from, so (as with
+        // headers from a bitcoind peer) it is not checked
+        let interval_start = cmp::max(start_height / BLOCK_DIFFICULTY_CHUNK_SIZE, 1);
+        let interval_end = end_height / BLOCK_DIFFICULTY_CHUNK_SIZE + 1;
+        if interval_start < interval_end {
+            spv_client.validate_header_work(interval_start, interval_end)?;
+        }
+
+        let total_work_after = spv_client.update_chain_work()?;
+        if total_work_after < total_work_before {
+            error!(
+                "New headers represent less work than the old headers ({} < {})",
+                total_work_before, total_work_after
+            );
+            return Err(btc_error::InvalidChainWork);
+        }
+        Ok(total_work_after)
+    }
+
+    /// Fetch and store headers until our highest header is at `target_height`.  Headers are
+    /// fetched and validated in batches that end at a difficulty interval boundary, so each
+    /// interval is only validated once.  A batch that fails validation is dropped, and the error
+    /// returned.
+    /// Returns the height of the highest header stored.
+    fn sync_headers_to(&mut self, target_height: u64) -> Result<u64, burnchain_error> {
+        let mut spv_client = self.open_spv_client(true)?;
+        let mut height = spv_client.get_highest_header_height()?;
+        let start_height = height;
+        let mut total_work = spv_client.update_chain_work()?;
+
+        while height < target_height {
+            if !self.should_keep_running() {
+                return Err(burnchain_error::CoordinatorClosed);
+            }
+            let interval_end =
+                ((height + 1) / BLOCK_DIFFICULTY_CHUNK_SIZE + 1) * BLOCK_DIFFICULTY_CHUNK_SIZE - 1;
+            let batch_end = cmp::min(interval_end, target_height);
+            let headers = self
+                .client
+                .get_block_headers(height + 1, batch_end)
+                .map_err(to_burnchain_error)?;
+            spv_client.insert_block_headers_after(height, headers)?;
+            total_work = match EsploraIndexer::validate_new_headers(
+                &mut spv_client,
+                height + 1,
+                batch_end,
+                total_work,
+            ) {
+                Ok(total_work) => total_work,
+                Err(e) => {
+                    warn!(
+                        "Rejecting headers {}-{} from Esplora server {}: {:?}",
+                        height + 1,
+                        batch_end,
+                        &self.config.url,
+                        &e
+                    );
+                    spv_client.drop_headers(height)?;
+                    return Err(burnchain_error::Bitcoin(e));
+                }
+            };
+            height = batch_end;
+        }
+
+        if height > start_height {
+            debug!(
+                "Synced headers {}-{} from Esplora server {}",
+                start_height + 1,
+                height,
+                &self.config.url
+            );
+        }
+        Ok(height)
+    }
+
+    /// Does the server's chain from `fork_height + 1` up to `remote_height` have more work than
+    /// `local_work`?  `fork_hash` is the hash of the header at `fork_height`, which the server's
+    /// chain must build on.  Stops fetching headers as soon as the answer is known.
+    fn remote_chain_has_more_work(
+        &self,
+        fork_height: u64,
+        fork_hash: Sha256dHash,
+        remote_height: u64,
+        local_work: Uint256,
+    ) -> Result<bool, burnchain_error> {
+        let mut remote_work = Uint256::from_u64(0);
+        let mut parent_hash = fork_hash;
+        let mut height = fork_height;
+        while height < remote_height {
+            if !self.should_keep_running() {
+                return Err(burnchain_error::CoordinatorClosed);
+            }
+            let batch_end = cmp::min(height + ESPLORA_BLOCKS_PER_REQUEST, remote_height);
+            let headers = self
+                .client
+                .get_block_headers(height + 1, batch_end)
+                .map_err(to_burnchain_error)?;
+            for (i, header) in headers.iter().enumerate() {
+                if header.header.prev_blockhash != parent_hash {
+                    warn!(
+                        "Esplora server's header at height {} does not build on {}",
+                        height + 1 + (i as u64),
+                        &parent_hash
+                    );
+                    return Err(burnchain_error::Bitcoin(btc_error::NoncontiguousHeader));
+                }
+                parent_hash = header.header.bitcoin_hash();
+                remote_work = remote_work + header.header.work();
+            }
+            if remote_work > local_work {
+                return Ok(true);
+            }
+            height = batch_end;
+        }
+        Ok(false)
+    }
+}
+
+impl BurnchainIndexer for EsploraIndexer {
+    type P = EsploraBlockParser;
+
+    /// Make sure the Esplora server is reachable
+    fn connect(&mut self) -> Result<(), burnchain_error> {
+        self.client
+            .get_tip_height()
+            .map(|_| ())
+            .map_err(to_burnchain_error)
+    }
+
+    fn get_headers_path(&self) -> String {
+        self.config.spv_headers_path.clone()
+    }
+
+    fn get_headers_height(&self) -> Result<u64, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        spv_client
+            .get_headers_height()
+            .map_err(burnchain_error::Bitcoin)
+    }
+
+    fn get_highest_header_height(&self) -> Result<u64, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        spv_client
+            .get_highest_header_height()
+            .map_err(burnchain_error::Bitcoin)
+    }
+
+    fn get_first_block_height(&self) -> u64 {
+        self.config.first_block
+    }
+
+    fn get_first_block_header_hash(&self) -> Result<BurnchainHeaderHash, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        let first_header = spv_client
+            .read_block_header(self.config.first_block)?
+            .expect("BUG: no first block header hash");
+        Ok(BurnchainHeaderHash::from_bitcoin_hash(
+            &first_header.header.bitcoin_hash(),
+        ))
+    }
+
+    fn get_first_block_header_timestamp(&self) -> Result<u64, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        let first_header = spv_client
+            .read_block_header(self.config.first_block)?
+            .expect("BUG: no first block header timestamp");
+        Ok(first_header.header.time as u64)
+    }
+
+    fn get_stacks_epochs(&self) -> Vec<StacksEpoch> {
+        StacksEpoch::get_epochs(self.network_id, self.config.epochs.as_ref())
+    }
+
+    fn read_headers(
+        &self,
+        start_block: u64,
+        end_block: u64,
+    ) -> Result<Vec<BitcoinHeaderIPC>, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        let headers = spv_client.read_block_headers(start_block, end_block)?;
+        Ok(headers
+            .into_iter()
+            .enumerate()
+            .map(|(i, block_header)| BitcoinHeaderIPC {
+                block_header,
+                block_height: (i as u64) + start_block,
+            })
+            .collect())
+    }
+
+    /// Find the highest header we share with the server's best chain, and drop the headers
+    /// above it if the server's chain has more work above it than ours does.  Otherwise, keep
+    /// our chain and report no reorg.
+    fn find_chain_reorg(&mut self) -> Result<u64, burnchain_error> {
+        let mut spv_client = self.open_spv_client(true)?;
+        let local_height = spv_client.get_highest_header_height()?;
+        let remote_height = self.client.get_tip_height().map_err(to_burnchain_error)?;
+
+        let mut height = cmp::min(local_height, remote_height);
+        while height > 0 {
+            let local_header = spv_client
+                .read_block_header(height)?
+                .ok_or(burnchain_error::Bitcoin(btc_error::MissingHeader))?;
+            let remote_hash = self
+                .client
+                .get_block_hash(height)
+                .map_err(to_burnchain_error)?;
+            if local_header.header.bitcoin_hash() == remote_hash {
+                break;
+            }
+            height -= 1;
+        }
+
+        if height < local_height {
+            let fork_header = spv_client
+                .read_block_header(height)?
+                .ok_or(burnchain_error::Bitcoin(btc_error::MissingHeader))?;
+            let local_headers = spv_client.read_block_headers(height + 1, local_height + 1)?;
+            let local_work = local_headers
+                .iter()
+                .fold(Uint256::from_u64(0), |work, header| {
+                    work + header.header.work()
+                });
+            if !self.remote_chain_has_more_work(
+                height,
+                fork_header.header.bitcoin_hash(),
+                remote_height,
+                local_work,
+            )? {
+                warn!(
+                    "Ignoring Esplora server's chain, which diverges from ours above height {} but does not have more work (ours is at {}, server's at {})",
+                    height, local_height, remote_height
+                );
+                return Ok(local_height);
+            }
+            warn!(
+                "Esplora server's chain diverges from ours above height {} (ours is at {}, server's at {})",
+                height, local_height, remote_height
+            );
+            spv_client.drop_headers(height)?;
+        }
+        Ok(height)
+    }
+
+    /// Download and store all headers up to `end_height` (inclusive), or up to the server's chain
+    /// tip if not given.  Returns the height of the last header fetched.
+    fn sync_headers(
+        &mut self,
+        start_height: u64,
+        end_height: Option<u64>,
+    ) -> Result<u64, burnchain_error> {
+        if end_height.is_some() && end_height <= Some(start_height) {
+            return Ok(end_height.unwrap());
+        }
+
+        let remote_height = self.client.get_tip_height().map_err(to_burnchain_error)?;
+        let target_height = end_height
+            .map(|height| cmp::min(height, remote_height))
+            .unwrap_or(remote_height);
+        self.sync_headers_to(target_height)
+    }
+
+    fn drop_headers(&mut self, new_height: u64) -> Result<(), burnchain_error> {
+        let mut spv_client = self.open_spv_client(true)?;
+        spv_client
+            .drop_headers(new_height)
+            .map_err(burnchain_error::Bitcoin)
+    }
+
+    fn downloader(&self) -> EsploraBlockDownloader {
+        EsploraBlockDownloader {
+            client: self.client.clone(),
+        }
+    }
+
+    fn parser(&self) -> EsploraBlockParser {
+        EsploraBlockParser {
+            parser: BitcoinBlockParser::new(self.network_id, self.config.magic_bytes)
+                .with_watched_pubkey_hashes(&self.config.watched_pubkey_hashes),
+        }
+    }
+
+    fn reader(&self) -> EsploraIndexer {
+        self.dup()
+    }
+}
+
+impl BurnchainHeaderReader for EsploraIndexer {
+    fn read_burnchain_headers(
+        &self,
+        start_height: u64,
+        end_height: u64,
+    ) -> Result<Vec<BurnchainBlockHeader>, DBError> {
+        let hdrs = self
+            .read_headers(start_height, end_height)
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))?;
+
+        Ok(hdrs
+            .into_iter()
+            .map(|hdr| BurnchainBlockHeader {
+                block_height: hdr.block_height,
+                block_hash: BurnchainHeaderHash::from_bitcoin_hash(&Sha256dHash(hdr.header_hash())),
+                parent_block_hash: BurnchainHeaderHash::from_bitcoin_hash(
+                    &hdr.block_header.header.prev_blockhash,
+                ),
+                num_txs: hdr.block_header.tx_count.0,
+                timestamp: hdr.block_header.header.time as u64,
+            })
+            .collect())
+    }
+
+    fn get_burnchain_headers_height(&self) -> Result<u64, DBError> {
+        self.get_headers_height()
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))
+    }
+
+    fn find_burnchain_header_height(
+        &self,
+        burn_header_hash: &BurnchainHeaderHash,
+    ) -> Result<Option<u64>, DBError> {
+        let spv_client = self
+            .open_spv_client(false)
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))?;
+        spv_client
+            .find_block_header_height(burn_header_hash)
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))
+    }
+}
+
+impl BurnchainBlockDownloader for EsploraBlockDownloader {
+    type H = BitcoinHeaderIPC;
+    type B = BitcoinBlockIPC;
+
+    fn download(&mut self, header: &BitcoinHeaderIPC) -> Result<BitcoinBlockIPC, burnchain_error> {
+        let block_hash = header.block_header.header.bitcoin_hash();
+        let block = self.client.get_block(&block_hash).map_err(|e| match e {
+            btc_error::TimedOut | btc_error::ConnectionError => burnchain_error::TrySyncAgain,
+            x => burnchain_error::DownloadError(x),
+        })?;
+        if !BitcoinBlockParser::check_block(&block, &header.block_header) {
+            warn!(
+                "Esplora server returned block {} for {}",
+                &block.bitcoin_hash(),
+                &block_hash
+            );
+            return Err(burnchain_error::DownloadError(btc_error::InvalidReply));
+        }
+        debug!("Got block {}: {}", header.block_height, &block_hash);
+        Ok(BitcoinBlockIPC {
+            header_data: header.clone(),
+            block_message: NetworkMessage::Block(block),
+        })
+    }
+}
+
+impl BurnchainBlockParser for EsploraBlockParser {
+    type D = EsploraBlockDownloader;
+
+    fn parse(
+        &mut self,
+        ipc_block: &BitcoinBlockIPC,
+        epoch_id: StacksEpochId,
+    ) -> Result<BurnchainBlock, burnchain_error> {
+        BurnchainBlockParser::parse(&mut self.parser, ipc_block, epoch_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+    use std::{fs, thread};
+
+    use serde_json::json;
+    use stacks_common::deps_common::bitcoin::blockdata::constants::genesis_block;
+    use stacks_common::deps_common::bitcoin::blockdata::script::Script;
+    use stacks_common::deps_common::bitcoin::blockdata::transaction::{
+        OutPoint, Transaction, TxIn, TxOut,
+    };
+    use stacks_common::deps_common::bitcoin::network::constants::Network;
+    use stacks_common::deps_common::bitcoin::network::serialize::serialize;
+    use stacks_common::deps_common::bitcoin::util::hash::bitcoin_merkle_root;
+    use stacks_common::util::hash::hex_bytes;
+
+    use super::*;
+    use crate::burnchains::BLOCKSTACK_MAGIC_MAINNET;
+
+    /// In-memory chain served by `MockEsplora`
+    #[derive(Default)]
+    struct MockChain {
+        blocks: Vec<Block>,
+        utxos: HashMap<String, String>,
+        broadcast: Vec<String>,
+    }
+
+    /// Just enough of an Esplora server to test against
+    struct MockEsplora {
+        url: String,
+        chain: Arc<Mutex<MockChain>>,
+    }
+
+    impl MockChain {
+        fn summary_json(&self, height: usize) -> serde_json::Value {
+            let block = &self.blocks[height];
+            json!({
+                "id": block.bitcoin_hash().be_hex_string(),
+                "height": height,
+                "version": block.header.version,
+                "timestamp": block.header.time,
+                "bits": block.header.bits,
+                "nonce": block.header.nonce,
+                "merkle_root": block.header.merkle_root.be_hex_string(),
+                "previousblockhash": if height > 0 {
+                    serde_json::Value::String(block.header.prev_blockhash.be_hex_string())
+                } else {
+                    serde_json::Value::Null
+                },
+                "tx_count": block.txdata.len(),
+            })
+        }
+
+        fn find_block(&self, hash: &str) -> Option<&Block> {
+            self.blocks
+                .iter()
+                .find(|block| block.bitcoin_hash().be_hex_string() == hash)
+        }
+
+        fn handle(&mut self, verb: &str, path: &str, body: &[u8]) -> (u16, Vec<u8>) {
+            let parts: Vec<&str> = path.trim_start_matches("/api/").split('/').collect();
+            let tip = self.blocks.len() - 1;
+            match (verb, &parts[..]) {
+                ("GET", ["blocks", "tip", "height"]) => (200, tip.to_string().into_bytes()),
+                ("GET", ["block-height", height]) => match height.parse::<usize>() {
+                    Ok(height) if height <= tip => (
+                        200,
+                        self.blocks[height]
+                            .bitcoin_hash()
+                            .be_hex_string()
+                            .into_bytes(),
+                    ),
+                    _ => (404, b"Block not found".to_vec()),
+                },
+                ("GET", ["blocks", height]) => match height.parse::<usize>() {
+                    Ok(height) if height <= tip => {
+                        let summaries: Vec<_> = (0..=height)
+                            .rev()
+                            .take(ESPLORA_BLOCKS_PER_REQUEST as usize)
+                            .map(|h| self.summary_json(h))
+                            .collect();
+                        (200, serde_json::to_vec(&summaries).unwrap())
+                    }
+                    _ => (404, b"Block not found".to_vec()),
+                },
+                ("GET", ["block", hash, "raw"]) => match self.find_block(hash) {
+                    Some(block) => (200, serialize(block).unwrap()),
+                    None => (404, b"Block not found".to_vec()),
+                },
+                ("GET", ["address", address, "utxo"]) => (
+                    200,
+                    self.utxos
+                        .get(*address)
+                        .cloned()
+                        .unwrap_or("[]".to_string())
+                        .into_bytes(),
+                ),
+                ("GET", ["tx", _txid, "status"]) => {
+                    (200, b"{\"confirmed\":true,\"block_height\":3}".to_vec())
+                }
+                ("POST", ["tx"]) => {
+                    let tx_hex = String::from_utf8(body.to_vec()).unwrap();
+                    let tx: Transaction = match hex_bytes(&tx_hex)
+                        .ok()
+                        .and_then(|bytes| deserialize(&bytes).ok())
+                    {
+                        Some(tx) => tx,
+                        None => return (400, b"TX decode failed".to_vec()),
+                    };
+                    self.broadcast.push(tx_hex);
+                    (200, tx.txid().be_hex_string().into_bytes())
+                }
+                _ => (404, b"Not found".to_vec()),
+            }
+        }
+    }
+
+    impl MockEsplora {
+        fn start(chain: MockChain) -> MockEsplora {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/api", listener.local_addr().unwrap());
+            let chain = Arc::new(Mutex::new(chain));
+            let server_chain = chain.clone();
+            thread::spawn(move || {
+                for sock in listener.incoming() {
+                    let Ok(mut sock) = sock else {
+                        continue;
+                    };
+                    let mut buf = vec![];
+                    let mut chunk = [0u8; 4096];
+                    let (verb, path, body) = loop {
+                        let nr = sock.read(&mut chunk).unwrap();
+                        buf.extend_from_slice(&chunk[..nr]);
+                        let mut headers = [httparse::EMPTY_HEADER; MAX_HTTP_HEADERS];
+                        let mut req = httparse::Request::new(&mut headers);
+                        if let Ok(httparse::Status::Complete(body_offset)) = req.parse(&buf) {
+                            let content_length: usize = req
+                                .headers
+                                .iter()
+                                .find(|hdr| hdr.name.eq_ignore_ascii_case("content-length"))
+                                .map(|hdr| std::str::from_utf8(hdr.value).unwrap().parse().unwrap())
+                                .unwrap_or(0);
+                            if buf.len() >= body_offset + content_length {
+                                break (
+                                    req.method.unwrap().to_string(),
+                                    req.path.unwrap().to_string(),
+                                    buf[body_offset..body_offset + content_length].to_vec(),
+                                );
+                            }
+                        }
+                    };
+                    let (code, body) = server_chain.lock().unwrap().handle(&verb, &path, &body);
+                    let resp = format!(
+                        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        code,
+                        body.len()
+                    );
+                    let _ = sock.write_all(resp.as_bytes());
+                    let _ = sock.write_all(&body);
+                }
+            });
+            MockEsplora { url, chain }
+        }
+    }
+
+    /// Mine a regtest block on top of `parent`.  `tag` makes forks distinct.
+    fn make_block(parent: &Block, height: u64, tag: u8) -> Block {
+        let coinbase = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Script::from(vec![0x08, (height & 0xff) as u8, tag]),
+                sequence: 0xffffffff,
+                witness: vec![],
+            }],
+            output: vec![TxOut {
+                value: 5_000_000_000,
+                script_pubkey: Script::from(vec![0x51]),
+            }],
+        };
+        let mut header = BlockHeader {
+            version: 0x20000000,
+            prev_blockhash: parent.bitcoin_hash(),
+            merkle_root: bitcoin_merkle_root(vec![coinbase.txid()]),
+            time: parent.header.time + 600,
+            bits: parent.header.bits,
+            nonce: 0,
+        };
+        while header.spv_validate(&header.target()).is_err() {
+            header.nonce += 1;
+        }
+        Block {
+            header,
+            txdata: vec![coinbase],
+        }
+    }
+
+    fn make_chain(genesis: &Block, len: u64, fork_height: u64, tag: u8) -> Vec<Block> {
+        let mut blocks = vec![genesis.clone()];
+        for height in 1..=len {
+            let block_tag = if height > fork_height { tag } else { 0 };
+            let block = make_block(&blocks[(height - 1) as usize], height, block_tag);
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn make_indexer(test_name: &str, url: &str) -> EsploraIndexer {
+        let working_dir = format!("/tmp/stacks-esplora-tests-{}", test_name);
+        if fs::metadata(&working_dir).is_ok() {
+            fs::remove_dir_all(&working_dir).unwrap();
+        }
+        fs::create_dir_all(&working_dir).unwrap();
+        let headers_path = format!("{}/headers.sqlite", &working_dir);
+        SpvClient::new(
+            &headers_path,
+            0,
+            None,
+            BitcoinNetworkType::Regtest,
+            true,
+            false,
+        )
+        .unwrap();
+
+        EsploraIndexer::new(
+            EsploraIndexerConfig {
+                url: url.to_string(),
+                timeout: 5,
+                spv_headers_path: headers_path,
+                first_block: 0,
+                magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
+                epochs: None,
+                watched_pubkey_hashes: vec![],
+                header_checkpoints: vec![],
+            },
+            BitcoinNetworkType::Regtest,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_esplora_client_rejects_bad_urls() {
+        assert!(EsploraClient::new("https://blockstream.info/api", 30).is_err());
+        assert!(EsploraClient::new("not a url", 30).is_err());
+
+        let client = EsploraClient::new("http://localhost:3000/api/", 30).unwrap();
+        assert_eq!(client.host, "localhost");
+        assert_eq!(client.port, 3000);
+        assert_eq!(client.path_prefix, "/api");
+
+        let client = EsploraClient::new("http://esplora.local", 30).unwrap();
+        assert_eq!(client.port, 80);
+        assert_eq!(client.path_prefix, "");
+    }
+
+    #[test]
+    fn test_esplora_sync_headers_and_download() {
+        let genesis = genesis_block(Network::Regtest);
+        let blocks = make_chain(&genesis, 25, 0, 0);
+        let server = MockEsplora::start(MockChain {
+            blocks: blocks.clone(),
+            ..MockChain::default()
+        });
+        let mut indexer = make_indexer("sync_headers_and_download", &server.url);
+
+        indexer.connect().unwrap();
+        assert_eq!(indexer.sync_headers(0, Some(12)).unwrap(), 12);
+        assert_eq!(indexer.get_highest_header_height().unwrap(), 12);
+        assert_eq!(indexer.sync_headers(12, None).unwrap(), 25);
+        assert_eq!(indexer.get_highest_header_height().unwrap(), 25);
+
+        let headers = indexer.read_headers(1, 26).unwrap();
+        assert_eq!(headers.len(), 25);
+        for (i, header) in headers.iter().enumerate() {
+            assert_eq!(header.block_height, (i + 1) as u64);
+            assert_eq!(header.header_hash(), blocks[i + 1].bitcoin_hash().0);
+        }
+
+        // the headers are also visible to anything reading the headers DB
+        let burn_header = indexer.read_burnchain_headers(7, 8).unwrap().pop().unwrap();
+        assert_eq!(
+            burn_header.block_hash,
+            BurnchainHeaderHash::from_bitcoin_hash(&blocks[7].bitcoin_hash())
+        );
+        assert_eq!(
+            indexer
+                .find_burnchain_header_height(&burn_header.block_hash)
+                .unwrap(),
+            Some(7)
+        );
+
+        let mut downloader = indexer.downloader();
+        let mut parser = indexer.parser();
+        let ipc_block = downloader.download(&headers[9]).unwrap();
+        assert_eq!(
+            ipc_block.block_message,
+            NetworkMessage::Block(blocks[10].clone())
+        );
+        let burn_block = parser.parse(&ipc_block, StacksEpochId::Epoch21).unwrap();
+        assert_eq!(burn_block.block_height(), 10);
+        assert_eq!(
+            burn_block.block_hash(),
+            BurnchainHeaderHash::from_bitcoin_hash(&blocks[10].bitcoin_hash())
+        );
+
+        // a header the server does not know about cannot be downloaded
+        let mut bad_header = headers[9].clone();
+        bad_header.block_header.header.nonce += 1;
+        assert!(downloader.download(&bad_header).is_err());
+    }
+
+    #[test]
+    fn test_esplora_find_chain_reorg() {
+        let genesis = genesis_block(Network::Regtest);
+        let blocks = make_chain(&genesis, 20, 0, 0);
+        let server = MockEsplora::start(MockChain {
+            blocks: blocks.clone(),
+            ..MockChain::default()
+        });
+        let mut indexer = make_indexer("find_chain_reorg", &server.url);
+
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), 20);
+        assert_eq!(indexer.find_chain_reorg().unwrap(), 20);
+
+        // server switches to a longer fork that diverges above height 14
+        let fork = make_chain(&genesis, 23, 14, 1);
+        assert_eq!(fork[14].bitcoin_hash(), blocks[14].bitcoin_hash());
+        assert_ne!(fork[15].bitcoin_hash(), blocks[15].bitcoin_hash());
+        server.chain.lock().unwrap().blocks = fork.clone();
+
+        assert_eq!(indexer.find_chain_reorg().unwrap(), 14);
+        assert_eq!(indexer.get_highest_header_height().unwrap(), 14);
+        assert_eq!(indexer.sync_headers(14, None).unwrap(), 23);
+        let tip = indexer.read_headers(23, 24).unwrap().pop().unwrap();
+        assert_eq!(tip.header_hash(), fork[23].bitcoin_hash().0);
+
+        // server switches to forks with less work, and then the same work, as ours above
+        // height 10.  We keep our chain in both cases.
+        for (fork_len, tag) in [(18, 2), (23, 3)] {
+            let weaker_fork = make_chain(&genesis, fork_len, 10, tag);
+            server.chain.lock().unwrap().blocks = weaker_fork;
+
+            assert_eq!(indexer.find_chain_reorg().unwrap(), 23);
+            assert_eq!(indexer.get_highest_header_height().unwrap(), 23);
+            let tip = indexer.read_headers(23, 24).unwrap().pop().unwrap();
+            assert_eq!(tip.header_hash(), fork[23].bitcoin_hash().0);
+        }
+    }
+
+    #[test]
+    fn test_esplora_rejects_headers_with_bad_difficulty() {
+        let genesis = genesis_block(Network::Regtest);
+        let mut blocks = make_chain(&genesis, 2019, 0, 0);
+
+        // regtest never retargets, so a block in the second difficulty interval with different
+        // bits is invalid, even if it meets its own target
+        let mut bad_block = make_block(&blocks[2019], 2020, 0);
+        bad_block.header.bits = 0x207ffffe;
+        while bad_block
+            .header
+            .spv_validate(&bad_block.header.target())
+            .is_err()
+        {
+            bad_block.header.nonce += 1;
+        }
+        blocks.push(bad_block);
+        for height in 2021..=2030 {
+            let block = make_block(&blocks[(height - 1) as usize], height, 0);
+            blocks.push(block);
+        }
+
+        let server = MockEsplora::start(MockChain {
+            blocks: blocks.clone(),
+            ..MockChain::default()
+        });
+        let mut indexer = make_indexer("rejects_headers_with_bad_difficulty", &server.url);
+
+        // the first interval is kept, but the batch with the bad header is rejected
+        assert!(matches!(
+            indexer.sync_headers(0, None),
+            Err(burnchain_error::Bitcoin(btc_error::InvalidPoW))
+        ));
+        assert_eq!(indexer.get_highest_header_height().unwrap(), 2015);
+        let tip = indexer.read_headers(2015, 2016).unwrap().pop().unwrap();
+        assert_eq!(tip.header_hash(), blocks[2015].bitcoin_hash().0);
+    }
+
+    #[test]
+    fn test_esplora_utxos_and_broadcast() {
+        let genesis = genesis_block(Network::Regtest);
+        let mut chain = MockChain {
+            blocks: make_chain(&genesis, 3, 0, 0),
+            ..MockChain::default()
+        };
+        let funding_txid = chain.blocks[2].txdata[0].txid();
+        chain.utxos.insert(
+            "mxVFsFW5N4mu1HPkxPttorvocvzeZ7KZyk".to_string(),
+            format!(
+                r#"[{{"txid":"{}","vout":0,"value":5000000000,"status":{{"confirmed":true,"block_height":2,"block_hash":"00"}}}},
+                   {{"txid":"{}","vout":1,"value":1000,"status":{{"confirmed":false}}}}]"#,
+                funding_txid.be_hex_string(),
+                funding_txid.be_hex_string()
+            ),
+        );
+        let server = MockEsplora::start(chain);
+        let client = EsploraClient::new(&server.url, 5).unwrap();
+
+        let utxos = client
+            .get_address_utxos("mxVFsFW5N4mu1HPkxPttorvocvzeZ7KZyk")
+            .unwrap();
+        assert_eq!(
+            utxos,
+            vec![
+                EsploraUtxo {
+                    txid: funding_txid,
+                    vout: 0,
+                    value: 5_000_000_000,
+                    block_height: Some(2),
+                },
+                EsploraUtxo {
+                    txid: funding_txid,
+                    vout: 1,
+                    value: 1000,
+                    block_height: None,
+                }
+            ]
+        );
+        assert!(client
+            .get_address_utxos("mfWxJ45yp2SFn7UciZyNpvDKrzbhyfKrY8")
+            .unwrap()
+            .is_empty());
+
+        let tx = server.chain.lock().unwrap().blocks[3].txdata[0].clone();
+        let tx_bytes = serialize(&tx).unwrap();
+        assert_eq!(
+            client.broadcast_transaction(&tx_bytes).unwrap(),
+            tx.txid().be_hex_string()
+        );
+        assert_eq!(
+            server.chain.lock().unwrap().broadcast,
+            vec![to_hex(&tx_bytes)]
+        );
+
+        match client.broadcast_transaction(&[0x00, 0x01]) {
+            Err(btc_error::HttpError(400, reason)) => assert_eq!(reason, "TX decode failed"),
+            x => panic!("Unexpected broadcast result {:?}", &x),
+        }
+
+        let txid = Txid::from_vec_be(&tx.txid().as_bytes().to_vec()).unwrap();
+        assert!(client.is_transaction_confirmed(&txid).unwrap());
+    }
+}
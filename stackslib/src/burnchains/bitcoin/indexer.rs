@@ -31,6 +31,7 @@ use stacks_common::deps_common::bitcoin::network::serialize::{
 };
 use stacks_common::deps_common::bitcoin::util::hash::Sha256dHash;
 use stacks_common::types::chainstate::BurnchainHeaderHash;
+use stacks_common::util::hash::Hash160;
 use stacks_common::util::{get_epoch_time_secs, log};
 
 use crate::burnchains::bitcoin::blocks::{
@@ -48,6 +49,7 @@ use crate::core::{
     StacksEpoch, StacksEpochExtension, STACKS_EPOCHS_MAINNET, STACKS_EPOCHS_REGTEST,
     STACKS_EPOCHS_TESTNET,
 };
+use crate::monitoring;
 use crate::util_lib::db::Error as DBError;
 
 pub const USER_AGENT: &'static str = "Stacks/2.1";
@@ -67,6 +69,10 @@ const REORG_BATCH_SIZE: u64 = 16;
 #[cfg(test)]
 const REORG_BATCH_SIZE: u64 = 2;
 
+/// How many blocks one of the `extra_peers` may be behind our headers before we raise a
+/// `BitcoinPeerTipAlert`
+pub const PEER_TIP_LAG_TOLERANCE: u64 = 2;
+
 pub fn network_id_to_bytes(network_id: BitcoinNetworkType) -> u32 {
     match network_id {
         BitcoinNetworkType::Mainnet => BITCOIN_MAINNET,
@@ -113,6 +119,30 @@ pub struct BitcoinIndexerConfig {
     pub first_block: u64,
     pub magic_bytes: MagicBytes,
     pub epochs: Option<Vec<StacksEpoch>>,
+    /// public key hashes whose outputs and spends are reported in each parsed block
+    pub watched_pubkey_hashes: Vec<Hash160>,
+    /// header checkpoints to enforce in addition to the built-in ones for this network
+    pub header_checkpoints: Vec<(u64, BurnchainHeaderHash)>,
+    /// other bitcoin peers (host, port) whose headers are compared against ours on each reorg
+    /// check.  If one has a chain with more work, we switch to it.
+    pub extra_peers: Vec<(String, u16)>,
+}
+
+/// Raised when one of the `extra_peers` disagrees with our headers about the bitcoin chain tip,
+/// i.e. when it is on a different fork (whichever of us has more work), or lags too far behind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BitcoinPeerTipAlert {
+    /// `host:port` of the peer
+    pub peer: String,
+    /// chain height the peer advertised
+    pub peer_height: u64,
+    /// our highest header, after checking this peer's headers
+    pub local_height: u64,
+    pub local_tip: BurnchainHeaderHash,
+    /// height of the highest header the peer's chain and ours have in common
+    pub common_ancestor_height: u64,
+    /// whether we switched to the peer's chain because it had more work
+    pub switched_to_peer: bool,
 }
 
 #[derive(Debug)]
@@ -126,6 +156,8 @@ pub struct BitcoinIndexerRuntime {
     pub last_getdata_send_time: u64,
     pub last_getheaders_send_time: u64,
     pub timeout: u64,
+    /// alerts raised while checking `extra_peers`, not yet consumed by `take_peer_tip_alerts()`
+    pub peer_tip_alerts: Vec<BitcoinPeerTipAlert>,
 }
 
 pub struct BitcoinIndexer {
@@ -148,6 +180,9 @@ impl BitcoinIndexerConfig {
             first_block,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
+            watched_pubkey_hashes: vec![],
+            header_checkpoints: vec![],
+            extra_peers: vec![],
         }
     }
 
@@ -164,6 +199,9 @@ impl BitcoinIndexerConfig {
             first_block: 0,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
+            watched_pubkey_hashes: vec![],
+            header_checkpoints: vec![],
+            extra_peers: vec![],
         }
     }
 
@@ -181,6 +219,9 @@ impl BitcoinIndexerConfig {
             first_block: 0,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
+            watched_pubkey_hashes: vec![],
+            header_checkpoints: vec![],
+            extra_peers: vec![],
         }
     }
 }
@@ -198,6 +239,7 @@ impl BitcoinIndexerRuntime {
             last_getdata_send_time: 0,
             last_getheaders_send_time: 0,
             timeout: 300,
+            peer_tip_alerts: vec![],
         }
     }
 }
@@ -448,6 +490,7 @@ impl BitcoinIndexer {
             true,
             false,
         )?;
+        spv_client.add_checkpoints(&self.config.header_checkpoints);
         if let Some(last_block) = last_block.as_ref() {
             // do we need to do anything?
             let cur_height = spv_client.get_headers_height()?;
@@ -519,6 +562,7 @@ impl BitcoinIndexer {
             Some(start_block + REORG_BATCH_SIZE),
             self.runtime.network_id,
         )?;
+        reorg_spv_client.add_checkpoints(&self.config.header_checkpoints);
 
         if start_block > 0 {
             if start_block > BLOCK_DIFFICULTY_CHUNK_SIZE {
@@ -817,6 +861,7 @@ impl BitcoinIndexer {
                     true,
                     false,
                 )?;
+                orig_spv_client.add_checkpoints(&self.config.header_checkpoints);
 
                 // copy over new headers
                 if new_tip > 0 {
@@ -902,6 +947,175 @@ impl BitcoinIndexer {
         }
     }
 
+    /// Make an indexer that talks to one of our `extra_peers` instead of our primary peer, but
+    /// uses our headers DB
+    fn peer_indexer(&self, peer_host: &str, peer_port: u16) -> BitcoinIndexer {
+        let mut config = self.config.clone();
+        config.peer_host = peer_host.to_string();
+        config.peer_port = peer_port;
+        config.extra_peers = vec![];
+        BitcoinIndexer {
+            config,
+            runtime: BitcoinIndexerRuntime::new(self.runtime.network_id),
+            should_keep_running: self.should_keep_running.clone(),
+        }
+    }
+
+    /// Get the hash of our header at `height`, if we have one
+    fn read_local_header_hash(
+        &self,
+        height: u64,
+    ) -> Result<Option<BurnchainHeaderHash>, btc_error> {
+        let spv_client = SpvClient::new(
+            &self.config.spv_headers_path,
+            0,
+            None,
+            self.runtime.network_id,
+            false,
+            false,
+        )?;
+        Ok(spv_client
+            .read_block_header(height)?
+            .map(|header| BurnchainHeaderHash::from_bitcoin_hash(&header.header.bitcoin_hash())))
+    }
+
+    /// Get the height and hash of our highest header
+    fn read_local_tip(&self) -> Result<(u64, BurnchainHeaderHash), btc_error> {
+        let height = SpvClient::new(
+            &self.config.spv_headers_path,
+            0,
+            None,
+            self.runtime.network_id,
+            false,
+            false,
+        )?
+        .get_highest_header_height()?;
+        let hash = self
+            .read_local_header_hash(height)?
+            .ok_or(btc_error::MissingHeader)?;
+        Ok((height, hash))
+    }
+
+    /// Find the highest header that a peer's headers (as loaded into `reorg_headers_path` by
+    /// `find_bitcoin_reorg()`) have in common with ours
+    fn find_common_ancestor(&self, reorg_headers_path: &str) -> Result<u64, btc_error> {
+        let canonical_spv_client = SpvClient::new(
+            &self.config.spv_headers_path,
+            0,
+            None,
+            self.runtime.network_id,
+            false,
+            false,
+        )?;
+        let reorg_spv_client = SpvClient::new(
+            reorg_headers_path,
+            0,
+            None,
+            self.runtime.network_id,
+            false,
+            false,
+        )?;
+        let mut height = cmp::min(
+            canonical_spv_client.get_highest_header_height()?,
+            reorg_spv_client.get_highest_header_height()?,
+        );
+        while height > 0 {
+            let header = canonical_spv_client.read_block_header(height)?;
+            if header.is_some() && header == reorg_spv_client.read_block_header(height)? {
+                break;
+            }
+            height -= 1;
+        }
+        Ok(height)
+    }
+
+    /// Check our headers against those of one of the `extra_peers`:
+    /// * if the peer's chain has more work than ours, switch to it just as if our primary peer
+    /// had reorged, and fetch the rest of its headers.
+    /// * if the peer is on a different fork, or lags more than `PEER_TIP_LAG_TOLERANCE` blocks
+    /// behind us, raise a `BitcoinPeerTipAlert`.
+    /// The peer is only tried once, so an unreachable peer cannot stall us.
+    /// Returns the height of the highest header that this did not change (i.e. the reorg height).
+    fn check_peer_headers(
+        &mut self,
+        peer_index: usize,
+        peer_host: &str,
+        peer_port: u16,
+    ) -> Result<u64, btc_error> {
+        let peer = format!("{}:{}", peer_host, peer_port);
+        let mut peer_indexer = self.peer_indexer(peer_host, peer_port);
+        peer_indexer.reconnect_peer()?;
+        let peer_height = peer_indexer.peer_handshake()?;
+        monitoring::update_btc_peer_height(&peer, peer_height);
+
+        let (local_height_before, local_tip_before) = self.read_local_tip()?;
+        let headers_path = self.config.spv_headers_path.clone();
+        let reorg_path = format!("{}.reorg.{}", &headers_path, peer_index);
+        let reorg_height = peer_indexer.find_bitcoin_reorg(
+            &headers_path,
+            &reorg_path,
+            |ref mut indexer, ref mut spv_client, start_block, end_block_opt| {
+                spv_client.set_scan_range(start_block, end_block_opt);
+                spv_client.run(indexer)
+            },
+        )?;
+        let mut common_ancestor_height = self.find_common_ancestor(&reorg_path)?;
+
+        let switched_to_peer =
+            self.read_local_header_hash(local_height_before)? != Some(local_tip_before);
+        if switched_to_peer {
+            info!(
+                "Switched to the Bitcoin chain of peer {} with more work, from height {}",
+                &peer, reorg_height
+            );
+        }
+
+        let (mut local_height, mut local_tip) = self.read_local_tip()?;
+        if peer_height > local_height && common_ancestor_height == local_height {
+            // the peer extends our chain, so don't wait for our primary peer to catch up
+            match peer_indexer.sync_last_headers(local_height, None) {
+                Ok(_) => {
+                    (local_height, local_tip) = self.read_local_tip()?;
+                    common_ancestor_height = local_height;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to sync Bitcoin headers from peer {}: {:?}",
+                        &peer, &e
+                    );
+                }
+            }
+        }
+
+        let forked = common_ancestor_height < cmp::min(local_height, peer_height);
+        let lagging = peer_height + PEER_TIP_LAG_TOLERANCE < local_height;
+        if switched_to_peer || forked || lagging {
+            warn!("Bitcoin peer disagrees with our headers about the chain tip";
+                  "peer" => %peer,
+                  "peer_height" => peer_height,
+                  "local_height" => local_height,
+                  "local_tip" => %local_tip,
+                  "common_ancestor_height" => common_ancestor_height,
+                  "switched_to_peer" => switched_to_peer);
+            monitoring::increment_btc_peer_tip_alerts_counter(&peer);
+            self.runtime.peer_tip_alerts.push(BitcoinPeerTipAlert {
+                peer,
+                peer_height,
+                local_height,
+                local_tip,
+                common_ancestor_height,
+                switched_to_peer,
+            });
+        }
+
+        Ok(reorg_height)
+    }
+
+    /// Take the alerts raised while checking `extra_peers` since the last call
+    pub fn take_peer_tip_alerts(&mut self) -> Vec<BitcoinPeerTipAlert> {
+        std::mem::take(&mut self.runtime.peer_tip_alerts)
+    }
+
     /// Verify that the last block header we have is within 2 hours of now.
     /// Return burnchain_error::TrySyncAgain if not, and delete the offending header
     pub fn check_chain_tip_timestamp(&mut self) -> Result<(), burnchain_error> {
@@ -930,6 +1144,26 @@ impl BitcoinIndexer {
         self.drop_headers(highest_header_height.saturating_sub(1))?;
         return Err(burnchain_error::TrySyncAgain);
     }
+
+    /// Check the headers of each of the `extra_peers`, lowering `reorg_height` to the lowest
+    /// height a peer made us reorg from.  A peer that cannot be reached or times out is skipped,
+    /// so one dead peer does not stall the sync from our primary peer.
+    fn check_extra_peers(&mut self, mut reorg_height: u64) -> u64 {
+        for (i, (peer_host, peer_port)) in self.config.extra_peers.clone().iter().enumerate() {
+            match self.check_peer_headers(i, peer_host, *peer_port) {
+                Ok(peer_reorg_height) => {
+                    reorg_height = cmp::min(reorg_height, peer_reorg_height);
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to check Bitcoin headers of peer {}:{}: {:?}",
+                        peer_host, peer_port, &e
+                    );
+                }
+            }
+        }
+        reorg_height
+    }
 }
 
 impl Drop for BitcoinIndexer {
@@ -1075,21 +1309,25 @@ impl BurnchainIndexer for BitcoinIndexer {
 
     /// Identify underlying reorgs and return the block height of the highest block in common
     /// between the remote node and our block headers.
+    /// Then check the headers of each of the `extra_peers`, and switch to any chain with more
+    /// work that they have.
     fn find_chain_reorg(&mut self) -> Result<u64, burnchain_error> {
         let headers_path = self.config.spv_headers_path.clone();
         let reorg_path = format!("{}.reorg", &self.config.spv_headers_path);
-        self.find_bitcoin_reorg(
-            &headers_path,
-            &reorg_path,
-            |ref mut indexer, ref mut spv_client, start_block, end_block_opt| {
-                spv_client.set_scan_range(start_block, end_block_opt);
-                spv_client.run(indexer)
-            },
-        )
-        .map_err(|e| match e {
-            btc_error::TimedOut => burnchain_error::TrySyncAgain,
-            x => burnchain_error::Bitcoin(x),
-        })
+        let reorg_height = self
+            .find_bitcoin_reorg(
+                &headers_path,
+                &reorg_path,
+                |ref mut indexer, ref mut spv_client, start_block, end_block_opt| {
+                    spv_client.set_scan_range(start_block, end_block_opt);
+                    spv_client.run(indexer)
+                },
+            )
+            .map_err(|e| match e {
+                btc_error::TimedOut => burnchain_error::TrySyncAgain,
+                x => burnchain_error::Bitcoin(x),
+            })?;
+        Ok(self.check_extra_peers(reorg_height))
     }
 
     /// Download and store all headers between two block heights
@@ -1104,12 +1342,20 @@ impl BurnchainIndexer for BitcoinIndexer {
             return Ok(end_height.unwrap());
         }
 
-        let new_height = self
-            .sync_last_headers(start_height, end_height)
-            .map_err(|e| match e {
-                btc_error::TimedOut => burnchain_error::TrySyncAgain,
-                x => burnchain_error::Bitcoin(x),
-            })?;
+        let new_height = match self.sync_last_headers(start_height, end_height) {
+            Ok(height) => height,
+            Err(btc_error::NoncontiguousHeader) if !self.config.extra_peers.is_empty() => {
+                // our primary peer is on a different chain than the one we took from
+                // `extra_peers` in `find_chain_reorg()`
+                warn!(
+                    "Headers from Bitcoin peer {}:{} do not connect to ours; keeping headers from other peers",
+                    &self.config.peer_host, self.config.peer_port
+                );
+                self.get_highest_header_height()?
+            }
+            Err(btc_error::TimedOut) => return Err(burnchain_error::TrySyncAgain),
+            Err(e) => return Err(burnchain_error::Bitcoin(e)),
+        };
 
         // make sure the headers are up-to-date if we have no target height
         if end_height.is_none() {
@@ -1140,6 +1386,7 @@ impl BurnchainIndexer for BitcoinIndexer {
 
     fn parser(&self) -> BitcoinBlockParser {
         BitcoinBlockParser::new(self.runtime.network_id, self.config.magic_bytes)
+            .with_watched_pubkey_hashes(&self.config.watched_pubkey_hashes)
     }
 
     fn reader(&self) -> BitcoinIndexer {
@@ -1614,6 +1861,9 @@ mod test {
             first_block: 0,
             magic_bytes: MagicBytes([105, 100]),
             epochs: None,
+            watched_pubkey_hashes: vec![],
+            header_checkpoints: vec![],
+            extra_peers: vec![],
         };
 
         if fs::metadata(&indexer_conf.spv_headers_path).is_ok() {
@@ -3507,6 +3757,42 @@ mod test {
         assert_eq!(spv_client.get_highest_header_height().unwrap(), 1);
     }
 
+    #[test]
+    fn test_dead_extra_peers_are_skipped() {
+        let db_path = "/tmp/test_dead_extra_peers_are_skipped.dat".to_string();
+        if fs::metadata(&db_path).is_ok() {
+            fs::remove_file(&db_path).unwrap();
+        }
+
+        // nothing listens on this port
+        let closed_port = net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        // this peer hangs up before the handshake
+        let hangup_listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let hangup_port = hangup_listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (sock, _) = hangup_listener.accept().unwrap();
+            drop(sock);
+        });
+
+        let mut config = BitcoinIndexerConfig::test_default(db_path.clone());
+        config.extra_peers = vec![
+            ("127.0.0.1".to_string(), closed_port),
+            ("127.0.0.1".to_string(), hangup_port),
+        ];
+        let mut indexer = BitcoinIndexer::new(
+            config,
+            BitcoinIndexerRuntime::new(BitcoinNetworkType::Regtest),
+            None,
+        );
+
+        assert_eq!(indexer.check_extra_peers(123), 123);
+        assert!(indexer.take_peer_tip_alerts().is_empty());
+    }
+
     /// This test ensures that setting `should_keep_running` to false halts the handshake function.
     #[test]
     fn test_should_keep_running_halts_handshake() {
@@ -26,6 +26,7 @@ use stacks_common::util::HexError as btc_hex_error;
 
 use crate::burnchains::bitcoin::address::BitcoinAddress;
 use crate::burnchains::bitcoin::keys::BitcoinPublicKey;
+use crate::burnchains::bitcoin::wallet::BitcoinWalletActivity;
 use crate::burnchains::Txid;
 use crate::chainstate::burn::operations::BlockstackOperationType;
 use crate::deps;
@@ -34,11 +35,15 @@ use crate::util_lib::db::Error as db_error;
 pub mod address;
 pub mod bits;
 pub mod blocks;
+pub mod esplora;
 pub mod indexer;
 pub mod keys;
 pub mod messages;
 pub mod network;
+pub mod psbt;
+pub mod simulator;
 pub mod spv;
+pub mod wallet;
 
 pub type PeerMessage = stacks_common::deps_common::bitcoin::network::message::NetworkMessage;
 
@@ -79,6 +84,8 @@ pub enum Error {
     InvalidPoW,
     /// Chainwork would decrease by including a given header
     InvalidChainWork,
+    /// A header does not match the header checkpoint at this height
+    CheckpointMismatch(u64),
     /// Wrong number of bytes for constructing an address
     InvalidByteSequence,
     /// Configuration error
@@ -87,6 +94,8 @@ pub enum Error {
     BlockchainHeight,
     /// Request timed out
     TimedOut,
+    /// HTTP server replied with a non-200 status code and reason
+    HttpError(u16, String),
 }
 
 impl fmt::Display for Error {
@@ -108,10 +117,14 @@ impl fmt::Display for Error {
             Error::MissingHeader => write!(f, "Missing header"),
             Error::InvalidPoW => write!(f, "Invalid proof of work"),
             Error::InvalidChainWork => write!(f, "Chain difficulty cannot decrease"),
+            Error::CheckpointMismatch(height) => {
+                write!(f, "Header does not match checkpoint at height {}", height)
+            }
             Error::InvalidByteSequence => write!(f, "Invalid sequence of bytes"),
             Error::ConfigError(ref e_str) => fmt::Display::fmt(e_str, f),
             Error::BlockchainHeight => write!(f, "Value is beyond the end of the blockchain"),
             Error::TimedOut => write!(f, "Request timed out"),
+            Error::HttpError(code, ref reason) => write!(f, "HTTP error {}: {}", code, reason),
         }
     }
 }
@@ -135,10 +148,12 @@ impl error::Error for Error {
             Error::MissingHeader => None,
             Error::InvalidPoW => None,
             Error::InvalidChainWork => None,
+            Error::CheckpointMismatch(_) => None,
             Error::InvalidByteSequence => None,
             Error::ConfigError(ref _e_str) => None,
             Error::BlockchainHeight => None,
             Error::TimedOut => None,
+            Error::HttpError(..) => None,
         }
     }
 }
@@ -225,6 +240,9 @@ pub struct BitcoinBlock {
     pub parent_block_hash: BurnchainHeaderHash,
     pub txs: Vec<BitcoinTransaction>,
     pub timestamp: u64,
+    /// outputs received and spent by the indexer's watched keys, if any
+    #[serde(default)]
+    pub wallet_activity: BitcoinWalletActivity,
 }
 
 impl BitcoinBlock {
@@ -241,6 +259,7 @@ impl BitcoinBlock {
             parent_block_hash: parent.clone(),
             txs: txs,
             timestamp: timestamp,
+            wallet_activity: BitcoinWalletActivity::default(),
         }
     }
 }
@@ -0,0 +1,1794 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Partially-signed Bitcoin transactions (BIP-174) for the burnchain operations that a Stacks
+//! account can send without a miner: `PreStxOp`, followed by one of `StackStxOp`,
+//! `TransferStxOp`, `DelegateStxOp` or `VoteForAggregateKeyOp`.
+//!
+//! The node normally builds and signs these with a hot key.  Here, they are instead built as
+//! unsigned PSBTs from a caller-supplied set of funding UTXOs, so that they can be signed by an
+//! external (hardware or multisig) wallet.  A signed PSBT can be finalized, extracted, and parsed
+//! back with the same code the burnchain indexer uses, so the caller can check that the wallet
+//! produced the intended operation before broadcasting it.
+//!
+//! Only the parts of BIP-174 that these transactions need are interpreted: the unsigned
+//! transaction, the spent outputs, partial signatures, redeem and witness scripts, and the final
+//! scriptSig and witness.  All other key-value pairs (such as BIP-32 derivation paths) are kept
+//! as-is, so that they survive a round-trip through this module.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::Cursor;
+use std::{error, fmt};
+
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::deps_common::bitcoin::blockdata::opcodes;
+use stacks_common::deps_common::bitcoin::blockdata::script::{Builder, Script};
+use stacks_common::deps_common::bitcoin::blockdata::transaction::{
+    OutPoint, Transaction, TxIn, TxOut,
+};
+use stacks_common::deps_common::bitcoin::network::encodable::{
+    ConsensusDecodable, ConsensusEncodable,
+};
+use stacks_common::deps_common::bitcoin::network::serialize::{
+    deserialize, serialize, RawDecoder, RawEncoder,
+};
+use stacks_common::deps_common::bitcoin::util::hash::Sha256dHash;
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksAddress};
+use stacks_common::types::StacksEpochId;
+
+use crate::burnchains::bitcoin::address::to_b58_version_byte;
+use crate::burnchains::bitcoin::blocks::BitcoinBlockParser;
+use crate::burnchains::{BurnchainTransaction, MagicBytes, Txid};
+use crate::chainstate::burn::operations::{
+    BlockstackOperationType, DelegateStxOp, PreStxOp, StackStxOp, TransferStxOp,
+    VoteForAggregateKeyOp,
+};
+use crate::chainstate::stacks::address::PoxAddress;
+
+/// Value of the outputs that a burnchain op pays to a Stacks-relevant address
+pub const BURN_OP_DUST_OUTPUT_VALUE: u64 = 5500;
+
+/// nSequence for PSBT inputs (signals RBF, as the node's own burnchain ops do)
+const PSBT_INPUT_SEQUENCE: u32 = 0xFFFFFFFD;
+
+const PSBT_MAGIC: &'static [u8] = b"psbt\xff";
+
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+
+const PSBT_IN_NON_WITNESS_UTXO: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_REDEEM_SCRIPT: u8 = 0x04;
+const PSBT_IN_WITNESS_SCRIPT: u8 = 0x05;
+const PSBT_IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const PSBT_IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+
+const PSBT_OUT_REDEEM_SCRIPT: u8 = 0x00;
+const PSBT_OUT_WITNESS_SCRIPT: u8 = 0x01;
+
+/// Largest signature (DER plus sighash byte) assumed when estimating transaction sizes
+const MAX_SIG_LEN: u64 = 73;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The data does not start with the PSBT magic bytes
+    InvalidMagic,
+    /// The data ended in the middle of a key-value map
+    Truncated,
+    /// A key appears twice in the same map
+    DuplicateKey(Vec<u8>),
+    /// A known key has an invalid key or value encoding
+    InvalidValue(String),
+    /// The global map has no unsigned transaction
+    MissingUnsignedTx,
+    /// PSBT version is not supported (only version 0 is)
+    UnsupportedVersion(u32),
+    /// Base64 decoding failed
+    Base64(String),
+    /// The PSBT does not say what output input `i` spends
+    MissingSpentOutput(usize),
+    /// Input `i` cannot be finalized with the information in the PSBT
+    CannotFinalize(usize, String),
+    /// Input `i` has not been finalized
+    NotFinalized(usize),
+    /// Input `i` spends a script type this module does not know how to size or finalize
+    UnsupportedScript(usize),
+    /// Two PSBTs that were expected to be for the same transaction are not
+    UnsignedTxMismatch,
+    /// No funding UTXOs were given
+    NoFunding,
+    /// The funding UTXOs do not cover the outputs and the fee
+    InsufficientFunds { needed: u64, available: u64 },
+    /// The operation cannot be sent as a PSBT
+    UnsupportedOperation(String),
+    /// The signed transaction is not the expected burnchain operation
+    InvalidOperation(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidMagic => write!(f, "Invalid PSBT magic bytes"),
+            Error::Truncated => write!(f, "PSBT is truncated"),
+            Error::DuplicateKey(ref key) => write!(f, "Duplicate PSBT key {:?}", key),
+            Error::InvalidValue(ref msg) => write!(f, "Invalid PSBT value: {}", msg),
+            Error::MissingUnsignedTx => write!(f, "PSBT has no unsigned transaction"),
+            Error::UnsupportedVersion(v) => write!(f, "Unsupported PSBT version {}", v),
+            Error::Base64(ref msg) => write!(f, "Invalid base64: {}", msg),
+            Error::MissingSpentOutput(i) => write!(f, "Input {} has no spent output", i),
+            Error::CannotFinalize(i, ref msg) => {
+                write!(f, "Cannot finalize input {}: {}", i, msg)
+            }
+            Error::NotFinalized(i) => write!(f, "Input {} is not finalized", i),
+            Error::UnsupportedScript(i) => write!(f, "Input {} spends an unsupported script", i),
+            Error::UnsignedTxMismatch => write!(f, "PSBTs are for different transactions"),
+            Error::NoFunding => write!(f, "No funding UTXOs given"),
+            Error::InsufficientFunds { needed, available } => write!(
+                f,
+                "Insufficient funds: need {} sats, have {} sats",
+                needed, available
+            ),
+            Error::UnsupportedOperation(ref msg) => write!(f, "Unsupported operation: {}", msg),
+            Error::InvalidOperation(ref msg) => write!(f, "Invalid operation: {}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
+    }
+}
+
+/// Per-input PSBT fields
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PsbtInput {
+    /// The full transaction that created the spent output (required by legacy inputs on most
+    /// hardware wallets)
+    pub non_witness_utxo: Option<Transaction>,
+    /// The spent output
+    pub witness_utxo: Option<TxOut>,
+    /// Signatures (DER plus sighash byte), by public key
+    pub partial_sigs: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub sighash_type: Option<u32>,
+    pub redeem_script: Option<Script>,
+    pub witness_script: Option<Script>,
+    pub final_script_sig: Option<Script>,
+    pub final_script_witness: Option<Vec<Vec<u8>>>,
+    /// All other key-value pairs, by key
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+/// Per-output PSBT fields
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PsbtOutput {
+    pub redeem_script: Option<Script>,
+    pub witness_script: Option<Script>,
+    /// All other key-value pairs, by key
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+/// A version-0 BIP-174 partially-signed Bitcoin transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Psbt {
+    /// The transaction being signed, with empty scriptSigs and witnesses
+    pub unsigned_tx: Transaction,
+    /// Global key-value pairs other than the unsigned transaction
+    pub unknown: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub inputs: Vec<PsbtInput>,
+    pub outputs: Vec<PsbtOutput>,
+}
+
+fn write_compact_size(buf: &mut Vec<u8>, n: u64) {
+    if n < 0xfd {
+        buf.push(n as u8);
+    } else if n <= 0xffff {
+        buf.push(0xfd);
+        buf.extend_from_slice(&(n as u16).to_le_bytes());
+    } else if n <= 0xffff_ffff {
+        buf.push(0xfe);
+        buf.extend_from_slice(&(n as u32).to_le_bytes());
+    } else {
+        buf.push(0xff);
+        buf.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+fn compact_size_len(n: u64) -> u64 {
+    if n < 0xfd {
+        1
+    } else if n <= 0xffff {
+        3
+    } else if n <= 0xffff_ffff {
+        5
+    } else {
+        9
+    }
+}
+
+fn read_bytes<'a>(cursor: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if cursor.len() < len {
+        return Err(Error::Truncated);
+    }
+    let (bytes, rest) = cursor.split_at(len);
+    *cursor = rest;
+    Ok(bytes)
+}
+
+fn read_compact_size(cursor: &mut &[u8]) -> Result<u64, Error> {
+    let first = read_bytes(cursor, 1)?[0];
+    let n = match first {
+        0xfd => {
+            let mut b = [0u8; 2];
+            b.copy_from_slice(read_bytes(cursor, 2)?);
+            u16::from_le_bytes(b) as u64
+        }
+        0xfe => {
+            let mut b = [0u8; 4];
+            b.copy_from_slice(read_bytes(cursor, 4)?);
+            u32::from_le_bytes(b) as u64
+        }
+        0xff => {
+            let mut b = [0u8; 8];
+            b.copy_from_slice(read_bytes(cursor, 8)?);
+            u64::from_le_bytes(b)
+        }
+        n => n as u64,
+    };
+    Ok(n)
+}
+
+fn write_pair(buf: &mut Vec<u8>, key: &[u8], value: &[u8]) {
+    write_compact_size(buf, key.len() as u64);
+    buf.extend_from_slice(key);
+    write_compact_size(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+/// Read one key-value map, up to and including its 0x00 separator
+fn read_map(cursor: &mut &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error> {
+    let mut pairs = vec![];
+    let mut seen = HashSet::new();
+    loop {
+        let key_len = read_compact_size(cursor)?;
+        if key_len == 0 {
+            return Ok(pairs);
+        }
+        let key = read_bytes(cursor, key_len as usize)?.to_vec();
+        let value_len = read_compact_size(cursor)?;
+        let value = read_bytes(cursor, value_len as usize)?.to_vec();
+        if !seen.insert(key.clone()) {
+            return Err(Error::DuplicateKey(key));
+        }
+        pairs.push((key, value));
+    }
+}
+
+fn btc_serialize<T>(data: &T) -> Vec<u8>
+where
+    T: ?Sized + ConsensusEncodable<RawEncoder<Cursor<Vec<u8>>>>,
+{
+    serialize(data).expect("BUG: failed to serialize to a Vec")
+}
+
+fn decode_value<T>(what: &str, value: &[u8]) -> Result<T, Error>
+where
+    T: for<'a> ConsensusDecodable<RawDecoder<Cursor<&'a [u8]>>>,
+{
+    deserialize(value).map_err(|e| Error::InvalidValue(format!("{}: {:?}", what, e)))
+}
+
+/// Convert a burnchain txid into the hash stored in a Bitcoin outpoint
+fn txid_to_outpoint_hash(txid: &Txid) -> Sha256dHash {
+    let mut bytes = txid.0.clone();
+    bytes.reverse();
+    Sha256dHash(bytes)
+}
+
+/// Convert the hash in a Bitcoin outpoint (or a tx's `txid()`) into a burnchain txid
+fn outpoint_hash_to_txid(hash: &Sha256dHash) -> Txid {
+    Txid::from_vec_be(&hash.as_bytes().to_vec()).expect("BUG: Sha256dHash is not 32 bytes")
+}
+
+/// If `script` is `OP_m <pubkey>... OP_n OP_CHECKMULTISIG`, return `m` and the public keys
+fn parse_multisig(script: &Script) -> Option<(usize, Vec<Vec<u8>>)> {
+    let bytes = script.as_bytes();
+    if bytes.len() < 3 || *bytes.last()? != opcodes::All::OP_CHECKMULTISIG as u8 {
+        return None;
+    }
+    let small_int = |op: u8| -> Option<usize> {
+        if op >= opcodes::All::OP_PUSHNUM_1 as u8 && op <= opcodes::All::OP_PUSHNUM_16 as u8 {
+            Some((op - opcodes::All::OP_PUSHNUM_1 as u8 + 1) as usize)
+        } else {
+            None
+        }
+    };
+    let m = small_int(bytes[0])?;
+    let n = small_int(bytes[bytes.len() - 2])?;
+    let mut pubkeys = vec![];
+    let mut i = 1;
+    while i < bytes.len() - 2 {
+        let len = bytes[i] as usize;
+        if len != 33 && len != 65 {
+            return None;
+        }
+        pubkeys.push(bytes.get(i + 1..i + 1 + len)?.to_vec());
+        i += 1 + len;
+    }
+    if i != bytes.len() - 2 || pubkeys.len() != n || m > n {
+        return None;
+    }
+    Some((m, pubkeys))
+}
+
+/// Size of pushing `len` bytes in a script
+fn push_len(len: u64) -> u64 {
+    if len < 0x4c {
+        1 + len
+    } else if len <= 0xff {
+        2 + len
+    } else {
+        3 + len
+    }
+}
+
+/// Size of a witness stack satisfying a multisig witness script
+fn multisig_witness_len(witness_script: &Script) -> Option<u64> {
+    let (m, _) = parse_multisig(witness_script)?;
+    let ws_len = witness_script.len() as u64;
+    // item count, empty dummy item, signatures, witness script
+    Some(1 + 1 + (m as u64) * (1 + MAX_SIG_LEN) + compact_size_len(ws_len) + ws_len)
+}
+
+impl PsbtInput {
+    /// Has this input been finalized?
+    pub fn is_finalized(&self) -> bool {
+        self.final_script_sig.is_some() || self.final_script_witness.is_some()
+    }
+
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        if let Some(ref tx) = self.non_witness_utxo {
+            write_pair(buf, &[PSBT_IN_NON_WITNESS_UTXO], &btc_serialize(tx));
+        }
+        if let Some(ref out) = self.witness_utxo {
+            write_pair(buf, &[PSBT_IN_WITNESS_UTXO], &btc_serialize(out));
+        }
+        for (pubkey, sig) in self.partial_sigs.iter() {
+            let mut key = vec![PSBT_IN_PARTIAL_SIG];
+            key.extend_from_slice(pubkey);
+            write_pair(buf, &key, sig);
+        }
+        if let Some(sighash) = self.sighash_type {
+            write_pair(buf, &[PSBT_IN_SIGHASH_TYPE], &sighash.to_le_bytes());
+        }
+        if let Some(ref script) = self.redeem_script {
+            write_pair(buf, &[PSBT_IN_REDEEM_SCRIPT], script.as_bytes());
+        }
+        if let Some(ref script) = self.witness_script {
+            write_pair(buf, &[PSBT_IN_WITNESS_SCRIPT], script.as_bytes());
+        }
+        if let Some(ref script) = self.final_script_sig {
+            write_pair(buf, &[PSBT_IN_FINAL_SCRIPTSIG], script.as_bytes());
+        }
+        if let Some(ref witness) = self.final_script_witness {
+            write_pair(buf, &[PSBT_IN_FINAL_SCRIPTWITNESS], &btc_serialize(witness));
+        }
+        for (key, value) in self.unknown.iter() {
+            write_pair(buf, key, value);
+        }
+        buf.push(0x00);
+    }
+
+    fn from_pairs(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> Result<PsbtInput, Error> {
+        let mut input = PsbtInput::default();
+        for (key, value) in pairs.into_iter() {
+            let is_bare = key.len() == 1;
+            match key[0] {
+                PSBT_IN_NON_WITNESS_UTXO if is_bare => {
+                    input.non_witness_utxo = Some(decode_value("non-witness UTXO", &value)?);
+                }
+                PSBT_IN_WITNESS_UTXO if is_bare => {
+                    input.witness_utxo = Some(decode_value("witness UTXO", &value)?);
+                }
+                PSBT_IN_PARTIAL_SIG => {
+                    let pubkey = key[1..].to_vec();
+                    if pubkey.len() != 33 && pubkey.len() != 65 {
+                        return Err(Error::InvalidValue(
+                            "partial signature public key".to_string(),
+                        ));
+                    }
+                    input.partial_sigs.insert(pubkey, value);
+                }
+                PSBT_IN_SIGHASH_TYPE if is_bare => {
+                    if value.len() != 4 {
+                        return Err(Error::InvalidValue("sighash type".to_string()));
+                    }
+                    let mut b = [0u8; 4];
+                    b.copy_from_slice(&value);
+                    input.sighash_type = Some(u32::from_le_bytes(b));
+                }
+                PSBT_IN_REDEEM_SCRIPT if is_bare => {
+                    input.redeem_script = Some(Script::from(value));
+                }
+                PSBT_IN_WITNESS_SCRIPT if is_bare => {
+                    input.witness_script = Some(Script::from(value));
+                }
+                PSBT_IN_FINAL_SCRIPTSIG if is_bare => {
+                    input.final_script_sig = Some(Script::from(value));
+                }
+                PSBT_IN_FINAL_SCRIPTWITNESS if is_bare => {
+                    input.final_script_witness = Some(decode_value("final witness", &value)?);
+                }
+                _ => {
+                    input.unknown.insert(key, value);
+                }
+            }
+        }
+        Ok(input)
+    }
+
+    /// Fill in anything `other` has that this input does not
+    fn combine(&mut self, other: &PsbtInput) {
+        if self.non_witness_utxo.is_none() {
+            self.non_witness_utxo = other.non_witness_utxo.clone();
+        }
+        if self.witness_utxo.is_none() {
+            self.witness_utxo = other.witness_utxo.clone();
+        }
+        for (pubkey, sig) in other.partial_sigs.iter() {
+            self.partial_sigs
+                .entry(pubkey.clone())
+                .or_insert_with(|| sig.clone());
+        }
+        if self.sighash_type.is_none() {
+            self.sighash_type = other.sighash_type;
+        }
+        if self.redeem_script.is_none() {
+            self.redeem_script = other.redeem_script.clone();
+        }
+        if self.witness_script.is_none() {
+            self.witness_script = other.witness_script.clone();
+        }
+        if !self.is_finalized() && other.is_finalized() {
+            self.final_script_sig = other.final_script_sig.clone();
+            self.final_script_witness = other.final_script_witness.clone();
+        }
+        for (key, value) in other.unknown.iter() {
+            self.unknown
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Get the single (public key, signature) pair for a single-sig input
+    fn single_sig(&self, index: usize) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        if self.partial_sigs.len() != 1 {
+            return Err(Error::CannotFinalize(
+                index,
+                format!("expected 1 signature, got {}", self.partial_sigs.len()),
+            ));
+        }
+        let (pubkey, sig) = self.partial_sigs.iter().next().expect("BUG: no signature");
+        Ok((pubkey.clone(), sig.clone()))
+    }
+
+    /// Get the signatures for a multisig script, in the order of its public keys
+    fn multisig_sigs(&self, index: usize, script: &Script) -> Result<Vec<Vec<u8>>, Error> {
+        let (m, pubkeys) = parse_multisig(script)
+            .ok_or_else(|| Error::CannotFinalize(index, "not a multisig script".to_string()))?;
+        let sigs: Vec<_> = pubkeys
+            .iter()
+            .filter_map(|pubkey| self.partial_sigs.get(pubkey).cloned())
+            .take(m)
+            .collect();
+        if sigs.len() < m {
+            return Err(Error::CannotFinalize(
+                index,
+                format!("need {} signatures, got {}", m, sigs.len()),
+            ));
+        }
+        Ok(sigs)
+    }
+
+    fn multisig_witness(
+        &self,
+        index: usize,
+        witness_script: &Script,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let mut witness = vec![vec![]];
+        witness.extend(self.multisig_sigs(index, witness_script)?);
+        witness.push(witness_script.to_bytes());
+        Ok(witness)
+    }
+
+    fn require_witness_script(&self, index: usize, script_hash: &Script) -> Result<Script, Error> {
+        let witness_script = self
+            .witness_script
+            .clone()
+            .ok_or_else(|| Error::CannotFinalize(index, "no witness script".to_string()))?;
+        if witness_script.to_v0_p2wsh() != *script_hash {
+            return Err(Error::CannotFinalize(
+                index,
+                "witness script does not match the spent output".to_string(),
+            ));
+        }
+        Ok(witness_script)
+    }
+
+    /// Build the final scriptSig and witness for this input from its signatures, and clear the
+    /// fields that are no longer needed.  Does nothing if the input is already finalized.
+    fn finalize(&mut self, index: usize, spent: &TxOut) -> Result<(), Error> {
+        if self.is_finalized() {
+            return Ok(());
+        }
+        let script_pubkey = &spent.script_pubkey;
+        let (script_sig, witness) = if script_pubkey.is_p2pkh() {
+            let (pubkey, sig) = self.single_sig(index)?;
+            let script_sig = Builder::new()
+                .push_slice(&sig)
+                .push_slice(&pubkey)
+                .into_script();
+            (script_sig, vec![])
+        } else if script_pubkey.is_v0_p2wpkh() {
+            let (pubkey, sig) = self.single_sig(index)?;
+            (Script::new(), vec![sig, pubkey])
+        } else if script_pubkey.is_v0_p2wsh() {
+            let witness_script = self.require_witness_script(index, script_pubkey)?;
+            (
+                Script::new(),
+                self.multisig_witness(index, &witness_script)?,
+            )
+        } else if script_pubkey.is_p2sh() {
+            let redeem_script = self
+                .redeem_script
+                .clone()
+                .ok_or_else(|| Error::CannotFinalize(index, "no redeem script".to_string()))?;
+            if redeem_script.to_p2sh() != *script_pubkey {
+                return Err(Error::CannotFinalize(
+                    index,
+                    "redeem script does not match the spent output".to_string(),
+                ));
+            }
+            let redeem_push = Builder::new()
+                .push_slice(redeem_script.as_bytes())
+                .into_script();
+            if redeem_script.is_v0_p2wpkh() {
+                let (pubkey, sig) = self.single_sig(index)?;
+                (redeem_push, vec![sig, pubkey])
+            } else if redeem_script.is_v0_p2wsh() {
+                let witness_script = self.require_witness_script(index, &redeem_script)?;
+                (redeem_push, self.multisig_witness(index, &witness_script)?)
+            } else {
+                let mut builder = Builder::new().push_opcode(opcodes::All::OP_PUSHBYTES_0);
+                for sig in self.multisig_sigs(index, &redeem_script)?.iter() {
+                    builder = builder.push_slice(sig);
+                }
+                (
+                    builder.push_slice(redeem_script.as_bytes()).into_script(),
+                    vec![],
+                )
+            }
+        } else {
+            return Err(Error::UnsupportedScript(index));
+        };
+
+        self.final_script_sig = if script_sig.is_empty() {
+            None
+        } else {
+            Some(script_sig)
+        };
+        self.final_script_witness = if witness.is_empty() {
+            None
+        } else {
+            Some(witness)
+        };
+        self.partial_sigs.clear();
+        self.sighash_type = None;
+        self.redeem_script = None;
+        self.witness_script = None;
+        Ok(())
+    }
+}
+
+impl PsbtOutput {
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        if let Some(ref script) = self.redeem_script {
+            write_pair(buf, &[PSBT_OUT_REDEEM_SCRIPT], script.as_bytes());
+        }
+        if let Some(ref script) = self.witness_script {
+            write_pair(buf, &[PSBT_OUT_WITNESS_SCRIPT], script.as_bytes());
+        }
+        for (key, value) in self.unknown.iter() {
+            write_pair(buf, key, value);
+        }
+        buf.push(0x00);
+    }
+
+    fn from_pairs(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> PsbtOutput {
+        let mut output = PsbtOutput::default();
+        for (key, value) in pairs.into_iter() {
+            match key[0] {
+                PSBT_OUT_REDEEM_SCRIPT if key.len() == 1 => {
+                    output.redeem_script = Some(Script::from(value));
+                }
+                PSBT_OUT_WITNESS_SCRIPT if key.len() == 1 => {
+                    output.witness_script = Some(Script::from(value));
+                }
+                _ => {
+                    output.unknown.insert(key, value);
+                }
+            }
+        }
+        output
+    }
+}
+
+impl Psbt {
+    /// Make an empty PSBT for a transaction.  Any scriptSigs and witnesses are dropped.
+    pub fn new(mut unsigned_tx: Transaction) -> Psbt {
+        for input in unsigned_tx.input.iter_mut() {
+            input.script_sig = Script::new();
+            input.witness.clear();
+        }
+        Psbt {
+            inputs: vec![PsbtInput::default(); unsigned_tx.input.len()],
+            outputs: vec![PsbtOutput::default(); unsigned_tx.output.len()],
+            unsigned_tx,
+            unknown: BTreeMap::new(),
+        }
+    }
+
+    /// The txid of the transaction, which will only change when signed if one of its inputs is
+    /// not a segwit input
+    pub fn unsigned_txid(&self) -> Txid {
+        outpoint_hash_to_txid(&self.unsigned_tx.txid())
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = PSBT_MAGIC.to_vec();
+        write_pair(
+            &mut buf,
+            &[PSBT_GLOBAL_UNSIGNED_TX],
+            &btc_serialize(&self.unsigned_tx),
+        );
+        for (key, value) in self.unknown.iter() {
+            write_pair(&mut buf, key, value);
+        }
+        buf.push(0x00);
+        for input in self.inputs.iter() {
+            input.serialize(&mut buf);
+        }
+        for output in self.outputs.iter() {
+            output.serialize(&mut buf);
+        }
+        buf
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Psbt, Error> {
+        let mut cursor = bytes;
+        if read_bytes(&mut cursor, PSBT_MAGIC.len()).map_err(|_| Error::InvalidMagic)? != PSBT_MAGIC
+        {
+            return Err(Error::InvalidMagic);
+        }
+
+        let mut unsigned_tx: Option<Transaction> = None;
+        let mut unknown = BTreeMap::new();
+        for (key, value) in read_map(&mut cursor)?.into_iter() {
+            if key == [PSBT_GLOBAL_UNSIGNED_TX] {
+                unsigned_tx = Some(decode_value("unsigned tx", &value)?);
+                continue;
+            }
+            if key == [PSBT_GLOBAL_VERSION] {
+                if value.len() != 4 {
+                    return Err(Error::InvalidValue("version".to_string()));
+                }
+                let mut b = [0u8; 4];
+                b.copy_from_slice(&value);
+                let version = u32::from_le_bytes(b);
+                if version != 0 {
+                    return Err(Error::UnsupportedVersion(version));
+                }
+            }
+            unknown.insert(key, value);
+        }
+        let unsigned_tx = unsigned_tx.ok_or(Error::MissingUnsignedTx)?;
+        if unsigned_tx
+            .input
+            .iter()
+            .any(|input| !input.script_sig.is_empty() || !input.witness.is_empty())
+        {
+            return Err(Error::InvalidValue(
+                "unsigned tx has scriptSigs or witnesses".to_string(),
+            ));
+        }
+
+        let mut inputs = vec![];
+        for (i, txin) in unsigned_tx.input.iter().enumerate() {
+            let input = PsbtInput::from_pairs(read_map(&mut cursor)?)?;
+            if let Some(ref prev_tx) = input.non_witness_utxo {
+                if prev_tx.txid() != txin.previous_output.txid {
+                    return Err(Error::InvalidValue(format!(
+                        "non-witness UTXO of input {} is not the spent transaction",
+                        i
+                    )));
+                }
+            }
+            inputs.push(input);
+        }
+        let mut outputs = vec![];
+        for _ in unsigned_tx.output.iter() {
+            outputs.push(PsbtOutput::from_pairs(read_map(&mut cursor)?));
+        }
+        if !cursor.is_empty() {
+            return Err(Error::InvalidValue("trailing data".to_string()));
+        }
+
+        Ok(Psbt {
+            unsigned_tx,
+            unknown,
+            inputs,
+            outputs,
+        })
+    }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.serialize())
+    }
+
+    pub fn from_base64(s: &str) -> Result<Psbt, Error> {
+        let bytes = base64::decode(s.trim()).map_err(|e| Error::Base64(format!("{:?}", &e)))?;
+        Psbt::deserialize(&bytes)
+    }
+
+    /// Merge the signatures and other fields from another PSBT for the same transaction (e.g.
+    /// one signed by a different multisig cosigner)
+    pub fn combine(&mut self, other: &Psbt) -> Result<(), Error> {
+        if self.unsigned_tx != other.unsigned_tx {
+            return Err(Error::UnsignedTxMismatch);
+        }
+        for (input, other_input) in self.inputs.iter_mut().zip(other.inputs.iter()) {
+            input.combine(other_input);
+        }
+        for (output, other_output) in self.outputs.iter_mut().zip(other.outputs.iter()) {
+            for (key, value) in other_output.unknown.iter() {
+                output
+                    .unknown
+                    .entry(key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+        for (key, value) in other.unknown.iter() {
+            self.unknown
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        Ok(())
+    }
+
+    /// Get the output that input `index` spends
+    pub fn spent_output(&self, index: usize) -> Result<TxOut, Error> {
+        let input = self
+            .inputs
+            .get(index)
+            .ok_or(Error::MissingSpentOutput(index))?;
+        if let Some(ref out) = input.witness_utxo {
+            return Ok(out.clone());
+        }
+        let vout = self.unsigned_tx.input[index].previous_output.vout as usize;
+        input
+            .non_witness_utxo
+            .as_ref()
+            .and_then(|prev_tx| prev_tx.output.get(vout).cloned())
+            .ok_or(Error::MissingSpentOutput(index))
+    }
+
+    /// Finalize every input that has enough signatures.  Supports p2pkh, p2wpkh, p2sh-p2wpkh,
+    /// and m-of-n multisig in p2sh, p2wsh and p2sh-p2wsh.  Inputs that a wallet has already
+    /// finalized are left alone.
+    pub fn finalize(&mut self) -> Result<(), Error> {
+        for i in 0..self.inputs.len() {
+            if self.inputs[i].is_finalized() {
+                continue;
+            }
+            let spent = self.spent_output(i)?;
+            self.inputs[i].finalize(i, &spent)?;
+        }
+        Ok(())
+    }
+
+    /// Get the signed transaction out of a finalized PSBT
+    pub fn extract_tx(&self) -> Result<Transaction, Error> {
+        let mut tx = self.unsigned_tx.clone();
+        for (i, (txin, input)) in tx.input.iter_mut().zip(self.inputs.iter()).enumerate() {
+            if !input.is_finalized() {
+                return Err(Error::NotFinalized(i));
+            }
+            txin.script_sig = input.final_script_sig.clone().unwrap_or_else(Script::new);
+            txin.witness = input.final_script_witness.clone().unwrap_or_default();
+        }
+        Ok(tx)
+    }
+}
+
+/// A UTXO that funds a burnchain op PSBT
+#[derive(Debug, Clone, PartialEq)]
+pub struct PsbtFundingUtxo {
+    pub txid: Txid,
+    pub vout: u32,
+    pub amount: u64,
+    pub script_pubkey: Script,
+    /// The transaction that created this UTXO.  Hardware wallets need this to sign legacy
+    /// inputs.
+    pub prev_tx: Option<Transaction>,
+    /// Required for p2sh outputs
+    pub redeem_script: Option<Script>,
+    /// Required for p2wsh and p2sh-p2wsh outputs
+    pub witness_script: Option<Script>,
+}
+
+impl PsbtFundingUtxo {
+    fn to_txin(&self) -> TxIn {
+        TxIn {
+            previous_output: OutPoint {
+                txid: txid_to_outpoint_hash(&self.txid),
+                vout: self.vout,
+            },
+            script_sig: Script::new(),
+            sequence: PSBT_INPUT_SEQUENCE,
+            witness: vec![],
+        }
+    }
+
+    fn to_psbt_input(&self) -> PsbtInput {
+        PsbtInput {
+            non_witness_utxo: self.prev_tx.clone(),
+            witness_utxo: Some(TxOut {
+                value: self.amount,
+                script_pubkey: self.script_pubkey.clone(),
+            }),
+            redeem_script: self.redeem_script.clone(),
+            witness_script: self.witness_script.clone(),
+            ..PsbtInput::default()
+        }
+    }
+
+    /// Upper bound on the (scriptSig, witness) bytes needed to spend this UTXO, including the
+    /// growth of the scriptSig length prefix
+    fn satisfaction_len(&self, index: usize) -> Result<(u64, u64), Error> {
+        // signature plus compressed public key
+        let sig_and_pubkey = (1 + MAX_SIG_LEN) + (1 + 33);
+        let script_pubkey = &self.script_pubkey;
+        let witness_script_len = || {
+            self.witness_script
+                .as_ref()
+                .and_then(multisig_witness_len)
+                .ok_or(Error::UnsupportedScript(index))
+        };
+        let lens = if script_pubkey.is_p2pkh() {
+            (sig_and_pubkey, 0)
+        } else if script_pubkey.is_v0_p2wpkh() {
+            (0, 1 + sig_and_pubkey)
+        } else if script_pubkey.is_v0_p2wsh() {
+            (0, witness_script_len()?)
+        } else if script_pubkey.is_p2sh() {
+            let redeem_script = self
+                .redeem_script
+                .as_ref()
+                .ok_or(Error::UnsupportedScript(index))?;
+            let redeem_push = push_len(redeem_script.len() as u64);
+            if redeem_script.is_v0_p2wpkh() {
+                (redeem_push, 1 + sig_and_pubkey)
+            } else if redeem_script.is_v0_p2wsh() {
+                (redeem_push, witness_script_len()?)
+            } else {
+                let (m, _) =
+                    parse_multisig(redeem_script).ok_or(Error::UnsupportedScript(index))?;
+                let script_sig_len = 1 + (m as u64) * (1 + MAX_SIG_LEN) + redeem_push;
+                (script_sig_len + compact_size_len(script_sig_len) - 1, 0)
+            }
+        } else if script_pubkey.as_bytes().len() == 34 && script_pubkey.as_bytes()[0] == 0x51 {
+            // p2tr key-path spend: item count plus a 64-byte signature
+            (0, 1 + 1 + 64)
+        } else {
+            return Err(Error::UnsupportedScript(index));
+        };
+        Ok(lens)
+    }
+}
+
+/// Estimate the virtual size of `tx` once the inputs, which spend `spent`, are signed
+fn estimate_vsize(tx: &Transaction, spent: &[PsbtFundingUtxo]) -> Result<u64, Error> {
+    let mut base_len = btc_serialize(tx).len() as u64;
+    let mut witness_len = 0;
+    for (i, utxo) in spent.iter().enumerate() {
+        let (script_sig_len, input_witness_len) = utxo.satisfaction_len(i)?;
+        base_len += script_sig_len;
+        witness_len += input_witness_len;
+    }
+    let mut weight = base_len * 4;
+    if witness_len > 0 {
+        // segwit marker and flag, plus an empty witness for each non-segwit input
+        weight += 2 + witness_len + spent.len() as u64;
+    }
+    Ok((weight + 3) / 4)
+}
+
+fn standard_tx_out(addr: &StacksAddress, value: u64) -> Result<TxOut, Error> {
+    if to_b58_version_byte(addr.version).is_none() {
+        return Err(Error::UnsupportedOperation(format!(
+            "address version {} has no Bitcoin equivalent",
+            addr.version
+        )));
+    }
+    Ok(PoxAddress::Standard(addr.clone(), None).to_bitcoin_tx_out(value))
+}
+
+fn pox_tx_out(addr: &PoxAddress, value: u64) -> Result<TxOut, Error> {
+    match addr {
+        PoxAddress::Standard(ref stacks_addr, _) => standard_tx_out(stacks_addr, value),
+        _ => Ok(addr.to_bitcoin_tx_out(value)),
+    }
+}
+
+/// Get the Stacks account that a burnchain op is sent from.  For a `PreStxOp`, this is the
+/// account that its second output funds.
+pub fn burn_op_sender(op: &BlockstackOperationType) -> Result<StacksAddress, Error> {
+    match op {
+        BlockstackOperationType::PreStx(ref op) => Ok(op.output.clone()),
+        BlockstackOperationType::StackStx(ref op) => Ok(op.sender.clone()),
+        BlockstackOperationType::TransferStx(ref op) => Ok(op.sender.clone()),
+        BlockstackOperationType::DelegateStx(ref op) => Ok(op.sender.clone()),
+        BlockstackOperationType::VoteForAggregateKey(ref op) => Ok(op.sender.clone()),
+        _ => Err(Error::UnsupportedOperation(format!("{:?}", op.opcode()))),
+    }
+}
+
+/// Builds the unsigned PSBTs for a `PreStxOp` and the burnchain op that it enables.
+pub struct BurnOpPsbtBuilder {
+    magic_bytes: MagicBytes,
+    /// sats/vbyte
+    fee_rate: u64,
+    /// Where any leftover funds are sent
+    change_script: Script,
+}
+
+impl BurnOpPsbtBuilder {
+    pub fn new(magic_bytes: MagicBytes, fee_rate: u64, change_script: Script) -> BurnOpPsbtBuilder {
+        BurnOpPsbtBuilder {
+            magic_bytes,
+            fee_rate,
+            change_script,
+        }
+    }
+
+    /// Get the outputs of the transaction for `op`, per its wire format: the OP_RETURN output
+    /// with the op's data, followed by the outputs that the op's parser reads
+    fn op_outputs(
+        &self,
+        op: &BlockstackOperationType,
+        pre_stx_amount: u64,
+    ) -> Result<Vec<TxOut>, Error> {
+        let mut op_bytes = self.magic_bytes.as_bytes().to_vec();
+        let serialized = match op {
+            BlockstackOperationType::PreStx(ref op) => op.consensus_serialize(&mut op_bytes),
+            BlockstackOperationType::StackStx(ref op) => op.consensus_serialize(&mut op_bytes),
+            BlockstackOperationType::TransferStx(ref op) => op.consensus_serialize(&mut op_bytes),
+            BlockstackOperationType::DelegateStx(ref op) => op.consensus_serialize(&mut op_bytes),
+            BlockstackOperationType::VoteForAggregateKey(ref op) => {
+                op.consensus_serialize(&mut op_bytes)
+            }
+            _ => return Err(Error::UnsupportedOperation(format!("{:?}", op.opcode()))),
+        };
+        serialized.map_err(|e| Error::UnsupportedOperation(format!("{:?}", &e)))?;
+        if op_bytes.len() > 80 {
+            return Err(Error::UnsupportedOperation(format!(
+                "op data is {} bytes, but OP_RETURN allows 80",
+                op_bytes.len()
+            )));
+        }
+
+        let mut outputs = vec![TxOut {
+            value: 0,
+            script_pubkey: Builder::new()
+                .push_opcode(opcodes::All::OP_RETURN)
+                .push_slice(&op_bytes)
+                .into_script(),
+        }];
+        match op {
+            BlockstackOperationType::PreStx(ref op) => {
+                outputs.push(standard_tx_out(&op.output, pre_stx_amount)?);
+            }
+            BlockstackOperationType::StackStx(ref op) => {
+                outputs.push(pox_tx_out(&op.reward_addr, BURN_OP_DUST_OUTPUT_VALUE)?);
+            }
+            BlockstackOperationType::TransferStx(ref op) => {
+                outputs.push(standard_tx_out(&op.recipient, BURN_OP_DUST_OUTPUT_VALUE)?);
+            }
+            BlockstackOperationType::DelegateStx(ref op) => {
+                outputs.push(standard_tx_out(&op.delegate_to, BURN_OP_DUST_OUTPUT_VALUE)?);
+                if let Some((index, ref reward_addr)) = op.reward_addr {
+                    // the index counts the outputs after the OP_RETURN, and the first of
+                    // those is the delegate
+                    if index != 1 {
+                        return Err(Error::UnsupportedOperation(format!(
+                            "reward address output index must be 1, not {}",
+                            index
+                        )));
+                    }
+                    outputs.push(pox_tx_out(reward_addr, BURN_OP_DUST_OUTPUT_VALUE)?);
+                }
+            }
+            _ => {}
+        }
+        Ok(outputs)
+    }
+
+    /// Build a transaction that spends `funding` to `outputs`, plus change if there is enough
+    /// left over after the fee
+    fn build_psbt(&self, outputs: Vec<TxOut>, funding: &[PsbtFundingUtxo]) -> Result<Psbt, Error> {
+        if funding.is_empty() {
+            return Err(Error::NoFunding);
+        }
+        let available: u64 = funding.iter().map(|utxo| utxo.amount).sum();
+        let spent: u64 = outputs.iter().map(|out| out.value).sum();
+
+        let mut tx = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: funding.iter().map(|utxo| utxo.to_txin()).collect(),
+            output: outputs,
+        };
+        let fee_without_change = estimate_vsize(&tx, funding)? * self.fee_rate;
+        let needed = spent + fee_without_change;
+        if available < needed {
+            return Err(Error::InsufficientFunds { needed, available });
+        }
+
+        tx.output.push(TxOut {
+            value: 0,
+            script_pubkey: self.change_script.clone(),
+        });
+        let fee_with_change = estimate_vsize(&tx, funding)? * self.fee_rate;
+        let change = available.saturating_sub(spent + fee_with_change);
+        if change >= BURN_OP_DUST_OUTPUT_VALUE {
+            tx.output.last_mut().expect("BUG: no change output").value = change;
+        } else {
+            tx.output.pop();
+        }
+
+        let mut psbt = Psbt::new(tx);
+        psbt.inputs = funding.iter().map(|utxo| utxo.to_psbt_input()).collect();
+        Ok(psbt)
+    }
+
+    /// How much the `PreStxOp` must send to the sender for it to be able to pay for `op`, which
+    /// spends `sender_utxo` (whose txid, vout and amount are ignored)
+    pub fn op_funding_amount(
+        &self,
+        op: &BlockstackOperationType,
+        sender_utxo: &PsbtFundingUtxo,
+    ) -> Result<u64, Error> {
+        let outputs = self.op_outputs(op, 0)?;
+        let spent: u64 = outputs.iter().map(|out| out.value).sum();
+        let tx = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![sender_utxo.to_txin()],
+            output: outputs,
+        };
+        let fee = estimate_vsize(&tx, &[sender_utxo.clone()])? * self.fee_rate;
+        Ok(spent + fee)
+    }
+
+    /// Build the `PreStxOp` transaction, which sends `amount` to `sender`
+    pub fn build_pre_stx(
+        &self,
+        sender: &StacksAddress,
+        amount: u64,
+        funding: &[PsbtFundingUtxo],
+    ) -> Result<Psbt, Error> {
+        let pre_stx = BlockstackOperationType::PreStx(PreStxOp {
+            output: sender.clone(),
+            txid: Txid([0u8; 32]),
+            vtxindex: 0,
+            block_height: 0,
+            burn_header_hash: BurnchainHeaderHash::zero(),
+        });
+        self.build_psbt(self.op_outputs(&pre_stx, amount)?, funding)
+    }
+
+    /// Build the transaction for `op`, spending `sender_utxo`, which must be the second output
+    /// of the sender's `PreStxOp` transaction
+    pub fn build_op(
+        &self,
+        op: &BlockstackOperationType,
+        sender_utxo: &PsbtFundingUtxo,
+    ) -> Result<Psbt, Error> {
+        if let BlockstackOperationType::PreStx(_) = op {
+            return Err(Error::UnsupportedOperation(
+                "use build_pre_stx() for PreStxOp".to_string(),
+            ));
+        }
+        if sender_utxo.vout != 1 {
+            return Err(Error::UnsupportedOperation(
+                "the sender UTXO must be output 1 of a PreStxOp".to_string(),
+            ));
+        }
+        self.build_psbt(self.op_outputs(op, 0)?, &[sender_utxo.clone()])
+    }
+
+    /// Build the `PreStxOp` transaction, funded by `funding`, and the transaction for `op`,
+    /// which spends the `PreStxOp`'s output to the sender.  If the sender is a p2sh address,
+    /// its redeem script (and witness script, if p2sh-p2wsh) must be given.
+    ///
+    /// The `op` transaction refers to the `PreStxOp` transaction by its unsigned txid.  If any
+    /// funding UTXO is not a segwit output, signing will change that txid, and the `op`
+    /// transaction must be rebuilt with `build_op()` once the `PreStxOp` is signed.
+    pub fn build_pre_stx_and_op(
+        &self,
+        op: &BlockstackOperationType,
+        funding: &[PsbtFundingUtxo],
+        sender_redeem_script: Option<Script>,
+        sender_witness_script: Option<Script>,
+    ) -> Result<(Psbt, Psbt), Error> {
+        let sender = burn_op_sender(op)?;
+        let mut sender_utxo = PsbtFundingUtxo {
+            txid: Txid([0u8; 32]),
+            vout: 1,
+            amount: 0,
+            script_pubkey: standard_tx_out(&sender, 0)?.script_pubkey,
+            prev_tx: None,
+            redeem_script: sender_redeem_script,
+            witness_script: sender_witness_script,
+        };
+        let amount = self.op_funding_amount(op, &sender_utxo)?;
+        let pre_stx = self.build_pre_stx(&sender, amount, funding)?;
+
+        sender_utxo.txid = pre_stx.unsigned_txid();
+        sender_utxo.amount = amount;
+        let op_psbt = self.build_op(op, &sender_utxo)?;
+        Ok((pre_stx, op_psbt))
+    }
+}
+
+/// Set the fields that depend on where `op` was mined
+fn set_op_location(
+    op: &mut BlockstackOperationType,
+    txid: &Txid,
+    vtxindex: u32,
+    block_height: u64,
+    burn_header_hash: &BurnchainHeaderHash,
+) {
+    macro_rules! set_location {
+        ($op:expr) => {{
+            $op.txid = txid.clone();
+            $op.vtxindex = vtxindex;
+            $op.block_height = block_height;
+            $op.burn_header_hash = burn_header_hash.clone();
+        }};
+    }
+    match op {
+        BlockstackOperationType::LeaderKeyRegister(ref mut op) => set_location!(op),
+        BlockstackOperationType::LeaderBlockCommit(ref mut op) => set_location!(op),
+        BlockstackOperationType::PreStx(ref mut op) => set_location!(op),
+        BlockstackOperationType::StackStx(ref mut op) => set_location!(op),
+        BlockstackOperationType::TransferStx(ref mut op) => set_location!(op),
+        BlockstackOperationType::DelegateStx(ref mut op) => set_location!(op),
+        BlockstackOperationType::VoteForAggregateKey(ref mut op) => set_location!(op),
+    }
+}
+
+/// Put PoX addresses in `op` in the form the op parsers produce
+fn coerce_op_addresses(op: &mut BlockstackOperationType) {
+    match op {
+        BlockstackOperationType::StackStx(ref mut op) => {
+            op.reward_addr = op.reward_addr.clone().coerce_hash_mode();
+        }
+        BlockstackOperationType::DelegateStx(ref mut op) => {
+            if let Some((_, ref mut reward_addr)) = op.reward_addr {
+                *reward_addr = reward_addr.clone().coerce_hash_mode();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Finalize and extract a signed PSBT, and check that it encodes `expected` as the burnchain
+/// indexer would parse it in `epoch_id`.  Only the op's fields are compared; its txid and
+/// location are ignored.  For ops other than `PreStxOp`, the transaction must spend output 1
+/// of `pre_stx_txid`, if given.
+///
+/// Signatures are not verified -- test the transaction against a Bitcoin node (e.g. with
+/// `testmempoolaccept`) before broadcasting it.
+///
+/// Returns the signed transaction and the op as parsed from it.
+pub fn validate_signed_psbt(
+    signed: &Psbt,
+    expected: &BlockstackOperationType,
+    parser: &BitcoinBlockParser,
+    epoch_id: StacksEpochId,
+    pre_stx_txid: Option<&Txid>,
+) -> Result<(Transaction, BlockstackOperationType), Error> {
+    let mut psbt = signed.clone();
+    psbt.finalize()?;
+    let tx = psbt.extract_tx()?;
+
+    let btc_tx = parser.parse_tx(&tx, 0, epoch_id).ok_or_else(|| {
+        Error::InvalidOperation("transaction is not a burnchain operation".to_string())
+    })?;
+    let burn_tx = BurnchainTransaction::Bitcoin(btc_tx);
+    let block_hash = BurnchainHeaderHash::zero();
+    let sender = burn_op_sender(expected)?;
+    let op_err = |e| Error::InvalidOperation(format!("{}", &e));
+
+    let sender_txid = match expected {
+        BlockstackOperationType::PreStx(_) => None,
+        BlockstackOperationType::StackStx(_) => Some(StackStxOp::get_sender_txid(&burn_tx)),
+        BlockstackOperationType::TransferStx(_) => Some(TransferStxOp::get_sender_txid(&burn_tx)),
+        BlockstackOperationType::DelegateStx(_) => Some(DelegateStxOp::get_sender_txid(&burn_tx)),
+        BlockstackOperationType::VoteForAggregateKey(_) => {
+            Some(VoteForAggregateKeyOp::get_sender_txid(&burn_tx))
+        }
+        _ => {
+            return Err(Error::UnsupportedOperation(format!(
+                "{:?}",
+                expected.opcode()
+            )))
+        }
+    };
+    if let Some(sender_txid) = sender_txid {
+        let sender_txid = sender_txid.map_err(op_err)?;
+        if let Some(pre_stx_txid) = pre_stx_txid {
+            if sender_txid != pre_stx_txid {
+                return Err(Error::InvalidOperation(format!(
+                    "transaction spends {}, not PreStxOp {}",
+                    sender_txid, pre_stx_txid
+                )));
+            }
+        }
+    }
+
+    let parsed = match expected {
+        BlockstackOperationType::PreStx(_) => {
+            PreStxOp::parse_from_tx(0, &block_hash, epoch_id, &burn_tx, u64::MAX)
+                .map(BlockstackOperationType::PreStx)
+        }
+        BlockstackOperationType::StackStx(_) => {
+            StackStxOp::parse_from_tx(0, &block_hash, epoch_id, &burn_tx, &sender, u64::MAX)
+                .and_then(|op| op.check().map(|_| op))
+                .map(BlockstackOperationType::StackStx)
+        }
+        BlockstackOperationType::TransferStx(_) => {
+            TransferStxOp::parse_from_tx(0, &block_hash, &burn_tx, &sender)
+                .and_then(|op| op.check().map(|_| op))
+                .map(BlockstackOperationType::TransferStx)
+        }
+        BlockstackOperationType::DelegateStx(_) => {
+            DelegateStxOp::parse_from_tx(0, &block_hash, &burn_tx, &sender)
+                .and_then(|op| op.check().map(|_| op))
+                .map(BlockstackOperationType::DelegateStx)
+        }
+        BlockstackOperationType::VoteForAggregateKey(_) => {
+            VoteForAggregateKeyOp::parse_from_tx(0, &block_hash, &burn_tx, &sender)
+                .and_then(|op| op.check().map(|_| op))
+                .map(BlockstackOperationType::VoteForAggregateKey)
+        }
+        _ => unreachable!("BUG: unsupported op was not rejected"),
+    }
+    .map_err(op_err)?;
+
+    let mut expected = expected.clone();
+    coerce_op_addresses(&mut expected);
+    set_op_location(&mut expected, &parsed.txid(), 0, 0, &block_hash);
+    if expected != parsed {
+        return Err(Error::InvalidOperation(format!(
+            "expected {}, but transaction encodes {}",
+            expected.blockstack_op_to_json(),
+            parsed.blockstack_op_to_json()
+        )));
+    }
+    Ok((tx, parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::address::{AddressHashMode, C32_ADDRESS_VERSION_TESTNET_SINGLESIG};
+    use stacks_common::deps_common::bitcoin::blockdata::transaction::SigHashType;
+    use stacks_common::types::PrivateKey;
+    use stacks_common::util::hash::{hex_bytes, Hash160};
+    use stacks_common::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
+
+    use super::*;
+    use crate::burnchains::bitcoin::BitcoinNetworkType;
+
+    fn sign(privk: &Secp256k1PrivateKey, sighash: &Sha256dHash) -> Vec<u8> {
+        let mut sig = privk
+            .sign(sighash.as_bytes())
+            .unwrap()
+            .to_secp256k1_recoverable()
+            .unwrap()
+            .to_standard()
+            .serialize_der()
+            .to_vec();
+        sig.push(SigHashType::All as u8);
+        sig
+    }
+
+    fn p2pkh_sender(privk: &Secp256k1PrivateKey) -> StacksAddress {
+        StacksAddress::from_public_keys(
+            C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            &AddressHashMode::SerializeP2PKH,
+            1,
+            &vec![Secp256k1PublicKey::from_private(privk)],
+        )
+        .unwrap()
+    }
+
+    /// Sign every input of `psbt` that spends a p2pkh or p2wpkh output
+    fn sign_single_sig(psbt: &mut Psbt, privk: &Secp256k1PrivateKey) {
+        let pubkey = Secp256k1PublicKey::from_private(privk).to_bytes_compressed();
+        let p2pkh_script = Builder::new()
+            .push_opcode(opcodes::All::OP_DUP)
+            .push_opcode(opcodes::All::OP_HASH160)
+            .push_slice(Hash160::from_data(&pubkey).as_bytes())
+            .push_opcode(opcodes::All::OP_EQUALVERIFY)
+            .push_opcode(opcodes::All::OP_CHECKSIG)
+            .into_script();
+        for i in 0..psbt.inputs.len() {
+            let spent = psbt.spent_output(i).unwrap();
+            let sighash = if spent.script_pubkey.is_v0_p2wpkh() {
+                psbt.unsigned_tx.segwit_signature_hash(
+                    i,
+                    &p2pkh_script,
+                    spent.value,
+                    SigHashType::All as u32,
+                )
+            } else {
+                psbt.unsigned_tx
+                    .signature_hash(i, &spent.script_pubkey, SigHashType::All as u32)
+            };
+            psbt.inputs[i]
+                .partial_sigs
+                .insert(pubkey.clone(), sign(privk, &sighash));
+        }
+    }
+
+    fn funding_utxo(script_pubkey: Script, amount: u64) -> PsbtFundingUtxo {
+        PsbtFundingUtxo {
+            txid: Txid([0x11; 32]),
+            vout: 3,
+            amount,
+            script_pubkey,
+            prev_tx: None,
+            redeem_script: None,
+            witness_script: None,
+        }
+    }
+
+    fn stack_stx_op(sender: &StacksAddress) -> BlockstackOperationType {
+        BlockstackOperationType::StackStx(StackStxOp {
+            sender: sender.clone(),
+            reward_addr: PoxAddress::Standard(sender.clone(), None),
+            stacked_ustx: 1_000_000_000,
+            num_cycles: 6,
+            signer_key: None,
+            max_amount: None,
+            auth_id: None,
+            txid: Txid([0u8; 32]),
+            vtxindex: 0,
+            block_height: 0,
+            burn_header_hash: BurnchainHeaderHash::zero(),
+        })
+    }
+
+    fn parser() -> BitcoinBlockParser {
+        BitcoinBlockParser::new(BitcoinNetworkType::Testnet, MagicBytes([105, 100]))
+    }
+
+    #[test]
+    fn test_psbt_codec() {
+        let privk = Secp256k1PrivateKey::new();
+        let sender = p2pkh_sender(&privk);
+        let funding_script = standard_tx_out(&sender, 0).unwrap().script_pubkey;
+        let builder = BurnOpPsbtBuilder::new(MagicBytes([105, 100]), 10, funding_script.clone());
+        let mut psbt = builder
+            .build_pre_stx(&sender, 20_000, &[funding_utxo(funding_script, 100_000)])
+            .unwrap();
+        psbt.unknown.insert(vec![0x01, 0xaa], vec![0xbb]);
+        psbt.inputs[0]
+            .unknown
+            .insert(vec![0x06, 0x02, 0x03], vec![1, 2, 3, 4]);
+        psbt.outputs[1].unknown.insert(vec![0x02, 0x04], vec![5, 6]);
+
+        let bytes = psbt.serialize();
+        assert_eq!(&bytes[0..5], b"psbt\xff");
+        assert_eq!(Psbt::deserialize(&bytes).unwrap(), psbt);
+        assert_eq!(Psbt::from_base64(&psbt.to_base64()).unwrap(), psbt);
+
+        // no magic
+        assert_eq!(Psbt::deserialize(&bytes[1..]), Err(Error::InvalidMagic));
+        // truncated
+        assert_eq!(
+            Psbt::deserialize(&bytes[0..bytes.len() - 1]),
+            Err(Error::Truncated)
+        );
+        // trailing data
+        let mut trailing = bytes.clone();
+        trailing.push(0x00);
+        assert!(Psbt::deserialize(&trailing).is_err());
+        // duplicate key
+        let mut dup = PSBT_MAGIC.to_vec();
+        write_pair(&mut dup, &[0x00], &btc_serialize(&psbt.unsigned_tx));
+        write_pair(&mut dup, &[0x00], &btc_serialize(&psbt.unsigned_tx));
+        assert_eq!(
+            Psbt::deserialize(&dup),
+            Err(Error::DuplicateKey(vec![0x00]))
+        );
+    }
+
+    #[test]
+    fn test_pre_stx_and_stack_stx_p2pkh() {
+        let privk = Secp256k1PrivateKey::new();
+        let sender = p2pkh_sender(&privk);
+        let sender_script = standard_tx_out(&sender, 0).unwrap().script_pubkey;
+        let builder = BurnOpPsbtBuilder::new(MagicBytes([105, 100]), 10, sender_script.clone());
+        let op = stack_stx_op(&sender);
+
+        let (mut pre_stx, mut op_psbt) = builder
+            .build_pre_stx_and_op(
+                &op,
+                &[funding_utxo(sender_script.clone(), 100_000)],
+                None,
+                None,
+            )
+            .unwrap();
+
+        // pre-stx: OP_RETURN, sender, change
+        assert_eq!(pre_stx.unsigned_tx.output.len(), 3);
+        assert_eq!(pre_stx.unsigned_tx.output[1].script_pubkey, sender_script);
+        let sender_amount = pre_stx.unsigned_tx.output[1].value;
+        assert_eq!(
+            sender_amount,
+            builder
+                .op_funding_amount(&op, &funding_utxo(sender_script.clone(), 0))
+                .unwrap()
+        );
+
+        // op: spends the sender output, pays OP_RETURN and the reward address, and has no change
+        assert_eq!(op_psbt.unsigned_tx.input.len(), 1);
+        assert_eq!(
+            op_psbt.unsigned_tx.input[0].previous_output,
+            OutPoint {
+                txid: pre_stx.unsigned_tx.txid(),
+                vout: 1
+            }
+        );
+        assert_eq!(op_psbt.unsigned_tx.output.len(), 2);
+        assert_eq!(
+            op_psbt.unsigned_tx.output[1].value,
+            BURN_OP_DUST_OUTPUT_VALUE
+        );
+
+        // unsigned PSBTs can't be validated
+        assert_eq!(
+            validate_signed_psbt(&pre_stx, &op, &parser(), StacksEpochId::Epoch25, None),
+            Err(Error::CannotFinalize(
+                0,
+                "expected 1 signature, got 0".to_string()
+            ))
+        );
+
+        sign_single_sig(&mut pre_stx, &privk);
+        let pre_stx_op = BlockstackOperationType::PreStx(PreStxOp {
+            output: sender.clone(),
+            txid: Txid([0u8; 32]),
+            vtxindex: 0,
+            block_height: 0,
+            burn_header_hash: BurnchainHeaderHash::zero(),
+        });
+        let (pre_stx_tx, parsed) = validate_signed_psbt(
+            &pre_stx,
+            &pre_stx_op,
+            &parser(),
+            StacksEpochId::Epoch25,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            pre_stx_tx.input[0].script_sig.len(),
+            2 + 33 + {
+                let sig_len = pre_stx.inputs[0]
+                    .partial_sigs
+                    .values()
+                    .next()
+                    .unwrap()
+                    .len();
+                sig_len
+            }
+        );
+        assert_eq!(parsed.txid(), outpoint_hash_to_txid(&pre_stx_tx.txid()));
+
+        // the funding input is legacy, so the op must be rebuilt with the signed PreStx's txid
+        let sender_utxo = PsbtFundingUtxo {
+            txid: outpoint_hash_to_txid(&pre_stx_tx.txid()),
+            vout: 1,
+            amount: sender_amount,
+            script_pubkey: sender_script.clone(),
+            prev_tx: Some(pre_stx_tx.clone()),
+            redeem_script: None,
+            witness_script: None,
+        };
+        op_psbt = builder.build_op(&op, &sender_utxo).unwrap();
+        assert_eq!(op_psbt.unsigned_tx.output.len(), 2);
+        let op_psbt = Psbt::from_base64(&op_psbt.to_base64()).unwrap();
+        assert_eq!(
+            op_psbt.inputs[0].non_witness_utxo.as_ref(),
+            Some(&pre_stx_tx)
+        );
+
+        let mut signed = op_psbt.clone();
+        sign_single_sig(&mut signed, &privk);
+        let pre_stx_txid = outpoint_hash_to_txid(&pre_stx_tx.txid());
+        let (_, parsed) = validate_signed_psbt(
+            &signed,
+            &op,
+            &parser(),
+            StacksEpochId::Epoch25,
+            Some(&pre_stx_txid),
+        )
+        .unwrap();
+        match parsed {
+            BlockstackOperationType::StackStx(ref parsed) => {
+                assert_eq!(parsed.stacked_ustx, 1_000_000_000);
+                assert_eq!(parsed.num_cycles, 6);
+                assert_eq!(parsed.sender, sender);
+            }
+            _ => panic!("not a StackStxOp"),
+        }
+
+        // must spend the given PreStx
+        assert!(matches!(
+            validate_signed_psbt(
+                &signed,
+                &op,
+                &parser(),
+                StacksEpochId::Epoch25,
+                Some(&Txid([0x22; 32])),
+            ),
+            Err(Error::InvalidOperation(_))
+        ));
+
+        // a different op does not validate
+        let mut other_op = op.clone();
+        if let BlockstackOperationType::StackStx(ref mut other_op) = other_op {
+            other_op.num_cycles = 12;
+        }
+        assert!(matches!(
+            validate_signed_psbt(&signed, &other_op, &parser(), StacksEpochId::Epoch25, None),
+            Err(Error::InvalidOperation(_))
+        ));
+    }
+
+    #[test]
+    fn test_transfer_and_delegate_outputs() {
+        let privk = Secp256k1PrivateKey::new();
+        let sender = p2pkh_sender(&privk);
+        let recipient = p2pkh_sender(&Secp256k1PrivateKey::new());
+        let sender_script = standard_tx_out(&sender, 0).unwrap().script_pubkey;
+        let builder = BurnOpPsbtBuilder::new(MagicBytes([105, 100]), 5, sender_script.clone());
+        let sender_utxo = PsbtFundingUtxo {
+            vout: 1,
+            ..funding_utxo(sender_script.clone(), 50_000)
+        };
+
+        let transfer = BlockstackOperationType::TransferStx(TransferStxOp {
+            sender: sender.clone(),
+            recipient: recipient.clone(),
+            transfered_ustx: 123,
+            memo: vec![1, 2, 3],
+            txid: Txid([0u8; 32]),
+            vtxindex: 0,
+            block_height: 0,
+            burn_header_hash: BurnchainHeaderHash::zero(),
+        });
+        let mut psbt = builder.build_op(&transfer, &sender_utxo).unwrap();
+        assert_eq!(
+            psbt.unsigned_tx.output[1],
+            standard_tx_out(&recipient, BURN_OP_DUST_OUTPUT_VALUE).unwrap()
+        );
+        // leftover goes to change
+        assert_eq!(psbt.unsigned_tx.output.len(), 3);
+        sign_single_sig(&mut psbt, &privk);
+        validate_signed_psbt(&psbt, &transfer, &parser(), StacksEpochId::Epoch25, None).unwrap();
+
+        let delegate = BlockstackOperationType::DelegateStx(DelegateStxOp {
+            sender: sender.clone(),
+            delegate_to: recipient.clone(),
+            reward_addr: Some((1, PoxAddress::Standard(sender.clone(), None))),
+            delegated_ustx: 456,
+            until_burn_height: Some(1000),
+            txid: Txid([0u8; 32]),
+            vtxindex: 0,
+            block_height: 0,
+            burn_header_hash: BurnchainHeaderHash::zero(),
+        });
+        let mut psbt = builder.build_op(&delegate, &sender_utxo).unwrap();
+        assert_eq!(psbt.unsigned_tx.output[2].script_pubkey, sender_script);
+        sign_single_sig(&mut psbt, &privk);
+        validate_signed_psbt(&psbt, &delegate, &parser(), StacksEpochId::Epoch25, None).unwrap();
+
+        let mut bad_delegate = delegate.clone();
+        if let BlockstackOperationType::DelegateStx(ref mut op) = bad_delegate {
+            op.reward_addr = Some((2, PoxAddress::Standard(sender.clone(), None)));
+        }
+        assert!(matches!(
+            builder.build_op(&bad_delegate, &sender_utxo),
+            Err(Error::UnsupportedOperation(_))
+        ));
+
+        // the op must spend output 1
+        assert!(matches!(
+            builder.build_op(
+                &transfer,
+                &PsbtFundingUtxo {
+                    vout: 0,
+                    ..sender_utxo.clone()
+                }
+            ),
+            Err(Error::UnsupportedOperation(_))
+        ));
+    }
+
+    #[test]
+    fn test_p2wsh_multisig_funding() {
+        let privks: Vec<_> = (0..3).map(|_| Secp256k1PrivateKey::new()).collect();
+        let pubkeys: Vec<_> = privks
+            .iter()
+            .map(|privk| Secp256k1PublicKey::from_private(privk).to_bytes_compressed())
+            .collect();
+        let mut builder = Builder::new().push_int(2);
+        for pubkey in pubkeys.iter() {
+            builder = builder.push_slice(pubkey);
+        }
+        let witness_script = builder
+            .push_int(3)
+            .push_opcode(opcodes::All::OP_CHECKMULTISIG)
+            .into_script();
+        assert_eq!(parse_multisig(&witness_script), Some((2, pubkeys.clone())));
+        let funding_script = witness_script.to_v0_p2wsh();
+
+        let sender_privk = Secp256k1PrivateKey::new();
+        let sender = p2pkh_sender(&sender_privk);
+        let op = stack_stx_op(&sender);
+        let psbt_builder =
+            BurnOpPsbtBuilder::new(MagicBytes([105, 100]), 10, funding_script.clone());
+        let utxo = PsbtFundingUtxo {
+            witness_script: Some(witness_script.clone()),
+            ..funding_utxo(funding_script.clone(), 100_000)
+        };
+
+        let (pre_stx, op_psbt) = psbt_builder
+            .build_pre_stx_and_op(&op, &[utxo.clone()], None, None)
+            .unwrap();
+
+        // segwit funding, so the op already spends the final PreStx txid
+        assert_eq!(
+            op_psbt.unsigned_tx.input[0].previous_output.txid,
+            pre_stx.unsigned_tx.txid()
+        );
+
+        // each cosigner signs their own copy
+        let mut signed = vec![];
+        for privk in privks[1..].iter() {
+            let mut copy = Psbt::from_base64(&pre_stx.to_base64()).unwrap();
+            let sighash = copy.unsigned_tx.segwit_signature_hash(
+                0,
+                &witness_script,
+                utxo.amount,
+                SigHashType::All as u32,
+            );
+            copy.inputs[0].partial_sigs.insert(
+                Secp256k1PublicKey::from_private(privk).to_bytes_compressed(),
+                sign(privk, &sighash),
+            );
+            signed.push(copy);
+        }
+
+        // one signature is not enough
+        let mut combined = signed[0].clone();
+        assert!(matches!(
+            combined.clone().finalize(),
+            Err(Error::CannotFinalize(0, _))
+        ));
+        combined.combine(&signed[1]).unwrap();
+        assert_eq!(combined.inputs[0].partial_sigs.len(), 2);
+        assert_eq!(combined.combine(&op_psbt), Err(Error::UnsignedTxMismatch));
+
+        let pre_stx_op = BlockstackOperationType::PreStx(PreStxOp {
+            output: sender.clone(),
+            txid: Txid([0u8; 32]),
+            vtxindex: 0,
+            block_height: 0,
+            burn_header_hash: BurnchainHeaderHash::zero(),
+        });
+        let (tx, _) = validate_signed_psbt(
+            &combined,
+            &pre_stx_op,
+            &parser(),
+            StacksEpochId::Epoch25,
+            None,
+        )
+        .unwrap();
+        assert!(tx.input[0].script_sig.is_empty());
+        let witness = &tx.input[0].witness;
+        assert_eq!(witness.len(), 4);
+        assert!(witness[0].is_empty());
+        assert_eq!(witness[1], combined.inputs[0].partial_sigs[&pubkeys[1]]);
+        assert_eq!(witness[3], witness_script.to_bytes());
+        assert_eq!(outpoint_hash_to_txid(&tx.txid()), pre_stx.unsigned_txid());
+
+        // the size estimate covers the real transaction
+        let signed_vsize = {
+            let base = btc_serialize(&Transaction {
+                input: tx
+                    .input
+                    .iter()
+                    .map(|input| TxIn {
+                        witness: vec![],
+                        ..input.clone()
+                    })
+                    .collect(),
+                ..tx.clone()
+            })
+            .len() as u64;
+            let total = btc_serialize(&tx).len() as u64;
+            (base * 3 + total + 3) / 4
+        };
+        let estimate = estimate_vsize(&pre_stx.unsigned_tx, &[utxo]).unwrap();
+        assert!(estimate >= signed_vsize);
+        assert!(estimate <= signed_vsize + 2);
+    }
+
+    #[test]
+    fn test_insufficient_funds() {
+        let sender = p2pkh_sender(&Secp256k1PrivateKey::new());
+        let sender_script = standard_tx_out(&sender, 0).unwrap().script_pubkey;
+        let builder = BurnOpPsbtBuilder::new(MagicBytes([105, 100]), 10, sender_script.clone());
+        let op = stack_stx_op(&sender);
+        assert_eq!(
+            builder.build_pre_stx_and_op(&op, &[], None, None),
+            Err(Error::NoFunding)
+        );
+        assert!(matches!(
+            builder.build_pre_stx_and_op(
+                &op,
+                &[funding_utxo(sender_script.clone(), 1000)],
+                None,
+                None
+            ),
+            Err(Error::InsufficientFunds { .. })
+        ));
+
+        // a p2sh sender needs its redeem script for the fee estimate
+        let p2sh_script =
+            Script::from(hex_bytes("a914000102030405060708090a0b0c0d0e0f1011121388").unwrap());
+        assert_eq!(
+            funding_utxo(p2sh_script, 1000).satisfaction_len(0),
+            Err(Error::UnsupportedScript(0))
+        );
+    }
+}
@@ -0,0 +1,1068 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Scriptable, in-process regtest burnchain for tests.
+//!
+//! `SimulatedBurnchain` holds a tree of real (regtest-difficulty) bitcoin blocks and a mempool.
+//! Unlike bitcoind, it never picks the best chain by itself: the test decides which chain tip
+//! indexers see, so forks can be built at any height and delivered in any order.  Individual
+//! blocks can also be withheld, in which case indexers only see the chain up to the withheld
+//! block's parent until the block is released.
+//!
+//! `SimulatedIndexer` is a `BurnchainIndexer` over a `SimulatedBurnchain`.  As with the
+//! `EsploraIndexer`, headers are stored in the same SPV headers DB that the `BitcoinIndexer`
+//! uses, and blocks are parsed by the `BitcoinBlockParser`, so everything downstream of the
+//! indexer (the burnchain DB, the chains coordinator, affirmation maps) runs unmodified.
+//!
+//! Transactions are not validated: anything submitted to the mempool is mined as-is.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{cmp, fmt};
+
+use stacks_common::deps_common::bitcoin::blockdata::block::{Block, BlockHeader, LoneBlockHeader};
+use stacks_common::deps_common::bitcoin::blockdata::constants::genesis_block;
+use stacks_common::deps_common::bitcoin::blockdata::script::Script;
+use stacks_common::deps_common::bitcoin::blockdata::transaction::{
+    OutPoint, Transaction, TxIn, TxOut,
+};
+use stacks_common::deps_common::bitcoin::network::constants::Network;
+use stacks_common::deps_common::bitcoin::network::encodable::VarInt;
+use stacks_common::deps_common::bitcoin::network::message::NetworkMessage;
+use stacks_common::deps_common::bitcoin::network::serialize::{deserialize, BitcoinHash};
+use stacks_common::deps_common::bitcoin::util::hash::{bitcoin_merkle_root, Sha256dHash};
+use stacks_common::types::chainstate::BurnchainHeaderHash;
+use stacks_common::util::get_epoch_time_secs;
+use stacks_common::util::hash::Hash160;
+
+use crate::burnchains::bitcoin::blocks::{BitcoinBlockIPC, BitcoinBlockParser, BitcoinHeaderIPC};
+use crate::burnchains::bitcoin::esplora::EsploraUtxo;
+use crate::burnchains::bitcoin::spv::SpvClient;
+use crate::burnchains::bitcoin::{BitcoinNetworkType, Error as btc_error};
+use crate::burnchains::db::BurnchainHeaderReader;
+use crate::burnchains::indexer::{
+    BurnHeaderIPC, BurnchainBlockDownloader, BurnchainBlockParser, BurnchainIndexer,
+};
+use crate::burnchains::{
+    BurnchainBlock, BurnchainBlockHeader, Error as burnchain_error, MagicBytes, Txid,
+};
+use crate::core::{StacksEpoch, StacksEpochExtension, StacksEpochId};
+use crate::util_lib::db::Error as DBError;
+
+/// Coinbase output value of every simulated block
+pub const SIMULATED_BLOCK_REWARD: u64 = 5_000_000_000;
+
+fn to_txid(tx: &Transaction) -> Txid {
+    Txid::from_vec_be(&tx.txid().as_bytes().to_vec()).expect("BUG: txid is not 32 bytes")
+}
+
+struct SimulatedBlock {
+    block: Block,
+    height: u64,
+}
+
+struct SimulatedChainState {
+    blocks: HashMap<BurnchainHeaderHash, SimulatedBlock>,
+    genesis: BurnchainHeaderHash,
+    /// Tip of the chain indexers are told about (less any withheld blocks)
+    chain_tip: BurnchainHeaderHash,
+    withheld: HashSet<BurnchainHeaderHash>,
+    mempool: Vec<Transaction>,
+    /// Number of blocks mined so far.  Goes into each coinbase, so that blocks mined on the same
+    /// parent with the same transactions still get distinct hashes.
+    num_mined: u64,
+}
+
+impl SimulatedChainState {
+    fn get(&self, block_hash: &BurnchainHeaderHash) -> Result<&SimulatedBlock, btc_error> {
+        self.blocks.get(block_hash).ok_or_else(|| {
+            warn!("No such simulated block {}", block_hash);
+            btc_error::MissingHeader
+        })
+    }
+
+    fn parent_of(&self, block_hash: &BurnchainHeaderHash) -> Option<BurnchainHeaderHash> {
+        let block = self.blocks.get(block_hash)?;
+        if block.height == 0 {
+            return None;
+        }
+        Some(BurnchainHeaderHash::from_bitcoin_hash(
+            &block.block.header.prev_blockhash,
+        ))
+    }
+
+    /// Hashes of the blocks from genesis up to and including `tip`
+    fn ancestry(&self, tip: &BurnchainHeaderHash) -> Vec<BurnchainHeaderHash> {
+        let mut chain = vec![tip.clone()];
+        let mut cursor = tip.clone();
+        while let Some(parent) = self.parent_of(&cursor) {
+            chain.push(parent.clone());
+            cursor = parent;
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// The chain indexers can see: the selected chain, up to the parent of its lowest withheld
+    /// block.
+    fn visible_chain(&self) -> Vec<BurnchainHeaderHash> {
+        let mut chain = self.ancestry(&self.chain_tip);
+        if let Some(first_withheld) = chain.iter().position(|bhh| self.withheld.contains(bhh)) {
+            chain.truncate(first_withheld);
+        }
+        chain
+    }
+
+    fn mine(
+        &mut self,
+        parent: &BurnchainHeaderHash,
+        txs: Vec<Transaction>,
+        coinbase_script: &Script,
+    ) -> Result<BurnchainHeaderHash, btc_error> {
+        let (parent_header, height) = {
+            let parent = self.get(parent)?;
+            (parent.block.header.clone(), parent.height + 1)
+        };
+        self.num_mined += 1;
+
+        let mut coinbase_sig = vec![0x08];
+        coinbase_sig.extend_from_slice(&height.to_le_bytes());
+        coinbase_sig.extend_from_slice(&self.num_mined.to_le_bytes());
+        let coinbase = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Script::from(coinbase_sig),
+                sequence: 0xffffffff,
+                witness: vec![],
+            }],
+            output: vec![TxOut {
+                value: SIMULATED_BLOCK_REWARD,
+                script_pubkey: coinbase_script.clone(),
+            }],
+        };
+
+        let mut txdata = vec![coinbase];
+        txdata.extend(txs);
+
+        let time = cmp::max(
+            parent_header.time + 1,
+            u32::try_from(get_epoch_time_secs()).unwrap_or(u32::MAX),
+        );
+        let mut header = BlockHeader {
+            version: 0x20000000,
+            prev_blockhash: parent_header.bitcoin_hash(),
+            merkle_root: bitcoin_merkle_root(txdata.iter().map(|tx| tx.txid()).collect()),
+            time,
+            bits: parent_header.bits,
+            nonce: 0,
+        };
+        while header.spv_validate(&header.target()).is_err() {
+            header.nonce += 1;
+        }
+
+        let block_hash = BurnchainHeaderHash::from_bitcoin_hash(&header.bitcoin_hash());
+        debug!(
+            "Simulated burnchain: mined block {} at height {} on {} with {} tx(s)",
+            &block_hash,
+            height,
+            BurnchainHeaderHash::from_bitcoin_hash(&parent_header.bitcoin_hash()),
+            txdata.len()
+        );
+        self.blocks.insert(
+            block_hash.clone(),
+            SimulatedBlock {
+                block: Block { header, txdata },
+                height,
+            },
+        );
+        Ok(block_hash)
+    }
+}
+
+/// Handle to a simulated burnchain.  Clones share the same chain.
+#[derive(Clone)]
+pub struct SimulatedBurnchain {
+    state: Arc<Mutex<SimulatedChainState>>,
+}
+
+impl fmt::Debug for SimulatedBurnchain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self
+            .state
+            .lock()
+            .expect("FATAL: simulated burnchain lock poisoned");
+        write!(
+            f,
+            "SimulatedBurnchain(tip={}, blocks={}, mempool={})",
+            &state.chain_tip,
+            state.blocks.len(),
+            state.mempool.len()
+        )
+    }
+}
+
+impl SimulatedBurnchain {
+    /// Make a new simulated chain, starting from the regtest genesis block (which is what the SPV
+    /// headers DB is initialized with)
+    pub fn new() -> SimulatedBurnchain {
+        let genesis = genesis_block(Network::Regtest);
+        let genesis_hash = BurnchainHeaderHash::from_bitcoin_hash(&genesis.bitcoin_hash());
+        let mut blocks = HashMap::new();
+        blocks.insert(
+            genesis_hash.clone(),
+            SimulatedBlock {
+                block: genesis,
+                height: 0,
+            },
+        );
+        SimulatedBurnchain {
+            state: Arc::new(Mutex::new(SimulatedChainState {
+                blocks,
+                genesis: genesis_hash.clone(),
+                chain_tip: genesis_hash,
+                withheld: HashSet::new(),
+                mempool: vec![],
+                num_mined: 0,
+            })),
+        }
+    }
+
+    fn with_state<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut SimulatedChainState) -> R,
+    {
+        let mut state = self
+            .state
+            .lock()
+            .expect("FATAL: simulated burnchain lock poisoned");
+        f(&mut state)
+    }
+
+    pub fn genesis_hash(&self) -> BurnchainHeaderHash {
+        self.with_state(|state| state.genesis.clone())
+    }
+
+    /// The selected chain tip, including any withheld blocks
+    pub fn chain_tip(&self) -> BurnchainHeaderHash {
+        self.with_state(|state| state.chain_tip.clone())
+    }
+
+    /// Hash and height of the highest block indexers can see
+    pub fn visible_tip(&self) -> (BurnchainHeaderHash, u64) {
+        self.with_state(|state| {
+            let chain = state.visible_chain();
+            let tip = chain
+                .last()
+                .expect("BUG: genesis is never withheld")
+                .clone();
+            (tip, (chain.len() as u64) - 1)
+        })
+    }
+
+    /// Hash of the block at `height` on the chain indexers can see
+    pub fn get_block_hash(&self, height: u64) -> Option<BurnchainHeaderHash> {
+        self.with_state(|state| state.visible_chain().get(height as usize).cloned())
+    }
+
+    /// Height of any block the simulator has mined, whether or not it is visible
+    pub fn get_block_height(&self, block_hash: &BurnchainHeaderHash) -> Option<u64> {
+        self.with_state(|state| state.blocks.get(block_hash).map(|block| block.height))
+    }
+
+    /// Any block the simulator has mined, whether or not it is visible
+    pub fn get_block(&self, block_hash: &BurnchainHeaderHash) -> Option<Block> {
+        self.with_state(|state| {
+            state
+                .blocks
+                .get(block_hash)
+                .map(|block| block.block.clone())
+        })
+    }
+
+    /// Headers at heights `start_height` through `end_height` (inclusive) on the chain indexers
+    /// can see.  Truncated at the visible tip.
+    pub fn get_headers(&self, start_height: u64, end_height: u64) -> Vec<LoneBlockHeader> {
+        self.with_state(|state| {
+            state
+                .visible_chain()
+                .iter()
+                .skip(start_height as usize)
+                .take((end_height + 1).saturating_sub(start_height) as usize)
+                .map(|bhh| LoneBlockHeader {
+                    header: state.blocks[bhh].block.header.clone(),
+                    tx_count: VarInt(0),
+                })
+                .collect()
+        })
+    }
+
+    /// Add a transaction to the mempool.  It will be mined into the next block built by
+    /// `mine_blocks()`.
+    pub fn submit_transaction(&self, tx: Transaction) -> Txid {
+        let txid = to_txid(&tx);
+        self.with_state(|state| {
+            if !state
+                .mempool
+                .iter()
+                .any(|mempool_tx| to_txid(mempool_tx) == txid)
+            {
+                state.mempool.push(tx);
+            }
+        });
+        debug!("Simulated burnchain: accepted transaction {}", &txid);
+        txid
+    }
+
+    /// Add a serialized transaction to the mempool
+    pub fn submit_raw_transaction(&self, tx_bytes: &[u8]) -> Result<Txid, btc_error> {
+        let tx: Transaction = deserialize(tx_bytes).map_err(btc_error::SerializationError)?;
+        Ok(self.submit_transaction(tx))
+    }
+
+    pub fn mempool(&self) -> Vec<Transaction> {
+        self.with_state(|state| state.mempool.clone())
+    }
+
+    /// Remove and return all mempool transactions, e.g. to mine them into a fork with
+    /// `mine_block_on()`
+    pub fn take_mempool(&self) -> Vec<Transaction> {
+        self.with_state(|state| std::mem::take(&mut state.mempool))
+    }
+
+    /// Drop a transaction from the mempool.  Returns whether or not it was there.
+    pub fn drop_transaction(&self, txid: &Txid) -> bool {
+        self.with_state(|state| {
+            let len = state.mempool.len();
+            state.mempool.retain(|tx| to_txid(tx) != *txid);
+            state.mempool.len() < len
+        })
+    }
+
+    /// Find a transaction in the mempool or in any block
+    pub fn get_transaction(&self, txid: &Txid) -> Option<Transaction> {
+        self.with_state(|state| {
+            state
+                .mempool
+                .iter()
+                .chain(
+                    state
+                        .blocks
+                        .values()
+                        .flat_map(|block| block.block.txdata.iter()),
+                )
+                .find(|tx| to_txid(tx) == *txid)
+                .cloned()
+        })
+    }
+
+    /// Is the transaction in a block on the chain indexers can see?
+    pub fn is_transaction_confirmed(&self, txid: &Txid) -> bool {
+        self.with_state(|state| {
+            state.visible_chain().iter().any(|bhh| {
+                state.blocks[bhh]
+                    .block
+                    .txdata
+                    .iter()
+                    .any(|tx| to_txid(tx) == *txid)
+            })
+        })
+    }
+
+    /// Mine `num_blocks` blocks on top of the selected chain tip, paying the coinbase to
+    /// `coinbase_script`.  The first block includes the whole mempool.  The selected chain tip
+    /// moves to the last block mined.  Returns the new blocks' hashes.
+    pub fn mine_blocks(
+        &self,
+        num_blocks: u64,
+        coinbase_script: &Script,
+    ) -> Vec<BurnchainHeaderHash> {
+        self.with_state(|state| {
+            let mut mined = vec![];
+            for _ in 0..num_blocks {
+                let txs = std::mem::take(&mut state.mempool);
+                let parent = state.chain_tip.clone();
+                let block_hash = state
+                    .mine(&parent, txs, coinbase_script)
+                    .expect("BUG: selected chain tip is not a known block");
+                state.chain_tip = block_hash.clone();
+                mined.push(block_hash);
+            }
+            mined
+        })
+    }
+
+    /// Mine a block with the given transactions on top of any known block.  This does *not*
+    /// change the selected chain tip, so a fork stays invisible to indexers until
+    /// `set_chain_tip()` selects it.
+    pub fn mine_block_on(
+        &self,
+        parent: &BurnchainHeaderHash,
+        txs: Vec<Transaction>,
+        coinbase_script: &Script,
+    ) -> Result<BurnchainHeaderHash, btc_error> {
+        self.with_state(|state| state.mine(parent, txs, coinbase_script))
+    }
+
+    /// Select the chain ending at `block_hash` as the one indexers see.  This is how forks are
+    /// delivered: the simulator does not compare chain work, so the new chain may well be
+    /// shorter than the old one.
+    pub fn set_chain_tip(&self, block_hash: &BurnchainHeaderHash) -> Result<(), btc_error> {
+        self.with_state(|state| {
+            let height = state.get(block_hash)?.height;
+            info!(
+                "Simulated burnchain: select chain tip {} at height {} (was {})",
+                block_hash, height, &state.chain_tip
+            );
+            state.chain_tip = block_hash.clone();
+            Ok(())
+        })
+    }
+
+    /// Like bitcoind's `invalidateblock`: if `block_hash` is on the selected chain, select its
+    /// parent instead.
+    pub fn invalidate_block(&self, block_hash: &BurnchainHeaderHash) -> Result<(), btc_error> {
+        let parent = self.with_state(|state| -> Result<_, btc_error> {
+            state.get(block_hash)?;
+            if !state.ancestry(&state.chain_tip).contains(block_hash) {
+                return Ok(None);
+            }
+            Ok(state.parent_of(block_hash))
+        })?;
+        match parent {
+            Some(parent) => self.set_chain_tip(&parent),
+            None => Ok(()),
+        }
+    }
+
+    /// Hide a block (and therefore its descendants) from indexers, as if it had not propagated
+    /// yet
+    pub fn withhold_block(&self, block_hash: &BurnchainHeaderHash) -> Result<(), btc_error> {
+        self.with_state(|state| {
+            if state.get(block_hash)?.height == 0 {
+                warn!("Cannot withhold the genesis block");
+                return Err(btc_error::BlockchainHeight);
+            }
+            state.withheld.insert(block_hash.clone());
+            Ok(())
+        })
+    }
+
+    /// Stop withholding a block
+    pub fn release_block(&self, block_hash: &BurnchainHeaderHash) {
+        self.with_state(|state| state.withheld.remove(block_hash));
+    }
+
+    /// Unspent outputs paying to `script_pubkey`, on the chain indexers can see plus the
+    /// mempool.  Reported the same way an Esplora server would, i.e. mempool outputs have no
+    /// block height.
+    pub fn get_utxos(&self, script_pubkey: &Script) -> Vec<EsploraUtxo> {
+        self.with_state(|state| {
+            let confirmed = state.visible_chain().into_iter().flat_map(|bhh| {
+                let block = &state.blocks[&bhh];
+                block
+                    .block
+                    .txdata
+                    .iter()
+                    .map(move |tx| (tx, Some(block.height)))
+            });
+            let unconfirmed = state.mempool.iter().map(|tx| (tx, None));
+
+            let mut utxos: Vec<EsploraUtxo> = vec![];
+            for (tx, block_height) in confirmed.chain(unconfirmed) {
+                for input in tx.input.iter() {
+                    utxos.retain(|utxo| {
+                        utxo.txid != input.previous_output.txid
+                            || utxo.vout != input.previous_output.vout
+                    });
+                }
+                let txid = tx.txid();
+                for (vout, output) in tx.output.iter().enumerate() {
+                    if output.script_pubkey != *script_pubkey {
+                        continue;
+                    }
+                    utxos.push(EsploraUtxo {
+                        txid,
+                        vout: vout as u32,
+                        value: output.value,
+                        block_height,
+                    });
+                }
+            }
+            utxos
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedIndexerConfig {
+    pub spv_headers_path: String,
+    pub first_block: u64,
+    pub magic_bytes: MagicBytes,
+    pub epochs: Option<Vec<StacksEpoch>>,
+    /// public key hashes whose outputs and spends are reported in each parsed block
+    pub watched_pubkey_hashes: Vec<Hash160>,
+}
+
+pub struct SimulatedIndexer {
+    pub config: SimulatedIndexerConfig,
+    pub should_keep_running: Option<Arc<AtomicBool>>,
+    chain: SimulatedBurnchain,
+}
+
+pub struct SimulatedBlockDownloader {
+    chain: SimulatedBurnchain,
+}
+
+pub struct SimulatedBlockParser {
+    parser: BitcoinBlockParser,
+}
+
+impl SimulatedIndexer {
+    pub fn new(
+        config: SimulatedIndexerConfig,
+        chain: SimulatedBurnchain,
+        should_keep_running: Option<Arc<AtomicBool>>,
+    ) -> SimulatedIndexer {
+        SimulatedIndexer {
+            config,
+            should_keep_running,
+            chain,
+        }
+    }
+
+    pub fn chain(&self) -> &SimulatedBurnchain {
+        &self.chain
+    }
+
+    fn dup(&self) -> SimulatedIndexer {
+        SimulatedIndexer {
+            config: self.config.clone(),
+            should_keep_running: self.should_keep_running.clone(),
+            chain: self.chain.clone(),
+        }
+    }
+
+    fn open_spv_client(&self, readwrite: bool) -> Result<SpvClient, btc_error> {
+        SpvClient::new(
+            &self.config.spv_headers_path,
+            0,
+            None,
+            BitcoinNetworkType::Regtest,
+            readwrite,
+            false,
+        )
+    }
+
+    fn should_keep_running(&self) -> bool {
+        self.should_keep_running
+            .as_ref()
+            .map(|flag| flag.load(Ordering::SeqCst))
+            .unwrap_or(true)
+    }
+}
+
+impl BurnchainIndexer for SimulatedIndexer {
+    type P = SimulatedBlockParser;
+
+    fn connect(&mut self) -> Result<(), burnchain_error> {
+        Ok(())
+    }
+
+    fn get_headers_path(&self) -> String {
+        self.config.spv_headers_path.clone()
+    }
+
+    fn get_headers_height(&self) -> Result<u64, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        spv_client
+            .get_headers_height()
+            .map_err(burnchain_error::Bitcoin)
+    }
+
+    fn get_highest_header_height(&self) -> Result<u64, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        spv_client
+            .get_highest_header_height()
+            .map_err(burnchain_error::Bitcoin)
+    }
+
+    fn get_first_block_height(&self) -> u64 {
+        self.config.first_block
+    }
+
+    fn get_first_block_header_hash(&self) -> Result<BurnchainHeaderHash, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        let first_header = spv_client
+            .read_block_header(self.config.first_block)?
+            .expect("BUG: no first block header hash");
+        Ok(BurnchainHeaderHash::from_bitcoin_hash(
+            &first_header.header.bitcoin_hash(),
+        ))
+    }
+
+    fn get_first_block_header_timestamp(&self) -> Result<u64, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        let first_header = spv_client
+            .read_block_header(self.config.first_block)?
+            .expect("BUG: no first block header timestamp");
+        Ok(first_header.header.time as u64)
+    }
+
+    fn get_stacks_epochs(&self) -> Vec<StacksEpoch> {
+        StacksEpoch::get_epochs(BitcoinNetworkType::Regtest, self.config.epochs.as_ref())
+    }
+
+    fn read_headers(
+        &self,
+        start_block: u64,
+        end_block: u64,
+    ) -> Result<Vec<BitcoinHeaderIPC>, burnchain_error> {
+        let spv_client = self.open_spv_client(false)?;
+        let headers = spv_client.read_block_headers(start_block, end_block)?;
+        Ok(headers
+            .into_iter()
+            .enumerate()
+            .map(|(i, block_header)| BitcoinHeaderIPC {
+                block_header,
+                block_height: (i as u64) + start_block,
+            })
+            .collect())
+    }
+
+    /// Find the highest header we share with the simulator's visible chain, and drop the
+    /// headers above it.
+    fn find_chain_reorg(&mut self) -> Result<u64, burnchain_error> {
+        let mut spv_client = self.open_spv_client(true)?;
+        let local_height = spv_client.get_highest_header_height()?;
+        let (_, remote_height) = self.chain.visible_tip();
+
+        let mut height = cmp::min(local_height, remote_height);
+        while height > 0 {
+            let local_header = spv_client
+                .read_block_header(height)?
+                .ok_or(burnchain_error::Bitcoin(btc_error::MissingHeader))?;
+            let local_hash =
+                BurnchainHeaderHash::from_bitcoin_hash(&local_header.header.bitcoin_hash());
+            if self.chain.get_block_hash(height) == Some(local_hash) {
+                break;
+            }
+            height -= 1;
+        }
+
+        if height < local_height {
+            info!(
+                "Simulated burnchain diverges from our headers above height {} (ours is at {}, simulator's at {})",
+                height, local_height, remote_height
+            );
+            spv_client.drop_headers(height)?;
+        }
+        Ok(height)
+    }
+
+    /// Store all visible headers up to `end_height` (inclusive), or up to the visible tip if not
+    /// given.  Returns the height of the last header stored.
+    fn sync_headers(
+        &mut self,
+        start_height: u64,
+        end_height: Option<u64>,
+    ) -> Result<u64, burnchain_error> {
+        if end_height.is_some() && end_height <= Some(start_height) {
+            return Ok(end_height.unwrap());
+        }
+        if !self.should_keep_running() {
+            return Err(burnchain_error::CoordinatorClosed);
+        }
+
+        let (_, remote_height) = self.chain.visible_tip();
+        let target_height = end_height
+            .map(|height| cmp::min(height, remote_height))
+            .unwrap_or(remote_height);
+
+        let mut spv_client = self.open_spv_client(true)?;
+        let height = spv_client.get_highest_header_height()?;
+        if height < target_height {
+            let headers = self.chain.get_headers(height + 1, target_height);
+            spv_client.insert_block_headers_after(height, headers)?;
+            spv_client.update_chain_work()?;
+            debug!(
+                "Synced headers {}-{} from simulated burnchain",
+                height + 1,
+                target_height
+            );
+        }
+        Ok(cmp::max(height, target_height))
+    }
+
+    fn drop_headers(&mut self, new_height: u64) -> Result<(), burnchain_error> {
+        let mut spv_client = self.open_spv_client(true)?;
+        spv_client
+            .drop_headers(new_height)
+            .map_err(burnchain_error::Bitcoin)
+    }
+
+    fn downloader(&self) -> SimulatedBlockDownloader {
+        SimulatedBlockDownloader {
+            chain: self.chain.clone(),
+        }
+    }
+
+    fn parser(&self) -> SimulatedBlockParser {
+        SimulatedBlockParser {
+            parser: BitcoinBlockParser::new(BitcoinNetworkType::Regtest, self.config.magic_bytes)
+                .with_watched_pubkey_hashes(&self.config.watched_pubkey_hashes),
+        }
+    }
+
+    fn reader(&self) -> SimulatedIndexer {
+        self.dup()
+    }
+}
+
+impl BurnchainHeaderReader for SimulatedIndexer {
+    fn read_burnchain_headers(
+        &self,
+        start_height: u64,
+        end_height: u64,
+    ) -> Result<Vec<BurnchainBlockHeader>, DBError> {
+        let hdrs = self
+            .read_headers(start_height, end_height)
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))?;
+
+        Ok(hdrs
+            .into_iter()
+            .map(|hdr| BurnchainBlockHeader {
+                block_height: hdr.block_height,
+                block_hash: BurnchainHeaderHash::from_bitcoin_hash(&Sha256dHash(hdr.header_hash())),
+                parent_block_hash: BurnchainHeaderHash::from_bitcoin_hash(
+                    &hdr.block_header.header.prev_blockhash,
+                ),
+                num_txs: hdr.block_header.tx_count.0,
+                timestamp: hdr.block_header.header.time as u64,
+            })
+            .collect())
+    }
+
+    fn get_burnchain_headers_height(&self) -> Result<u64, DBError> {
+        self.get_headers_height()
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))
+    }
+
+    fn find_burnchain_header_height(
+        &self,
+        burn_header_hash: &BurnchainHeaderHash,
+    ) -> Result<Option<u64>, DBError> {
+        let spv_client = self
+            .open_spv_client(false)
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))?;
+        spv_client
+            .find_block_header_height(burn_header_hash)
+            .map_err(|e| DBError::Other(format!("Burnchain error: {:?}", &e)))
+    }
+}
+
+impl BurnchainBlockDownloader for SimulatedBlockDownloader {
+    type H = BitcoinHeaderIPC;
+    type B = BitcoinBlockIPC;
+
+    fn download(&mut self, header: &BitcoinHeaderIPC) -> Result<BitcoinBlockIPC, burnchain_error> {
+        let block_hash =
+            BurnchainHeaderHash::from_bitcoin_hash(&header.block_header.header.bitcoin_hash());
+        let block = self
+            .chain
+            .get_block(&block_hash)
+            .ok_or(burnchain_error::DownloadError(btc_error::MissingHeader))?;
+        Ok(BitcoinBlockIPC {
+            header_data: header.clone(),
+            block_message: NetworkMessage::Block(block),
+        })
+    }
+}
+
+impl BurnchainBlockParser for SimulatedBlockParser {
+    type D = SimulatedBlockDownloader;
+
+    fn parse(
+        &mut self,
+        ipc_block: &BitcoinBlockIPC,
+        epoch_id: StacksEpochId,
+    ) -> Result<BurnchainBlock, burnchain_error> {
+        BurnchainBlockParser::parse(&mut self.parser, ipc_block, epoch_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use stacks_common::deps_common::bitcoin::blockdata::opcodes;
+    use stacks_common::deps_common::bitcoin::blockdata::script::Builder;
+
+    use super::*;
+    use crate::burnchains::BLOCKSTACK_MAGIC_MAINNET;
+
+    fn make_indexer(test_name: &str, chain: &SimulatedBurnchain) -> SimulatedIndexer {
+        let working_dir = format!("/tmp/stacks-simulated-burnchain-tests-{}", test_name);
+        if fs::metadata(&working_dir).is_ok() {
+            fs::remove_dir_all(&working_dir).unwrap();
+        }
+        fs::create_dir_all(&working_dir).unwrap();
+        let headers_path = format!("{}/headers.sqlite", &working_dir);
+        SpvClient::new(
+            &headers_path,
+            0,
+            None,
+            BitcoinNetworkType::Regtest,
+            true,
+            false,
+        )
+        .unwrap();
+
+        SimulatedIndexer::new(
+            SimulatedIndexerConfig {
+                spv_headers_path: headers_path,
+                first_block: 0,
+                magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
+                epochs: None,
+                watched_pubkey_hashes: vec![],
+            },
+            chain.clone(),
+            None,
+        )
+    }
+
+    fn miner_script(tag: u8) -> Script {
+        Builder::new()
+            .push_opcode(opcodes::All::OP_DUP)
+            .push_opcode(opcodes::All::OP_HASH160)
+            .push_slice(&[tag; 20])
+            .push_opcode(opcodes::All::OP_EQUALVERIFY)
+            .push_opcode(opcodes::All::OP_CHECKSIG)
+            .into_script()
+    }
+
+    fn spend(txid: Sha256dHash, vout: u32, outputs: Vec<TxOut>) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint { txid, vout },
+                script_sig: Script::from(vec![0x51]),
+                sequence: 0xfffffffd,
+                witness: vec![],
+            }],
+            output: outputs,
+        }
+    }
+
+    #[test]
+    fn test_simulated_forks_and_withholding() {
+        let chain = SimulatedBurnchain::new();
+        let script = miner_script(1);
+        let main = chain.mine_blocks(5, &script);
+        assert_eq!(chain.visible_tip(), (main[4].clone(), 5));
+
+        // a longer fork off of height 2 stays invisible until selected
+        let mut fork = vec![];
+        let mut parent = main[1].clone();
+        for _ in 0..4 {
+            parent = chain.mine_block_on(&parent, vec![], &script).unwrap();
+            fork.push(parent.clone());
+        }
+        assert_eq!(chain.visible_tip(), (main[4].clone(), 5));
+        assert_eq!(chain.get_block_height(&fork[3]), Some(6));
+
+        chain.set_chain_tip(&fork[3]).unwrap();
+        assert_eq!(chain.visible_tip(), (fork[3].clone(), 6));
+        assert_eq!(chain.get_block_hash(2), Some(main[1].clone()));
+        assert_eq!(chain.get_block_hash(3), Some(fork[0].clone()));
+
+        // the shorter chain can be delivered again afterwards
+        chain.set_chain_tip(&main[4]).unwrap();
+        assert_eq!(chain.visible_tip(), (main[4].clone(), 5));
+        assert_eq!(chain.get_block_hash(3), Some(main[2].clone()));
+
+        // withholding a block hides it and its descendants
+        chain.withhold_block(&main[3]).unwrap();
+        assert_eq!(chain.visible_tip(), (main[2].clone(), 3));
+        assert_eq!(chain.get_block_hash(4), None);
+        assert_eq!(chain.get_headers(1, 10).len(), 3);
+        chain.release_block(&main[3]);
+        assert_eq!(chain.visible_tip(), (main[4].clone(), 5));
+
+        // invalidating a block selects its parent
+        chain.invalidate_block(&main[4]).unwrap();
+        assert_eq!(chain.visible_tip(), (main[3].clone(), 4));
+
+        assert!(chain.withhold_block(&chain.genesis_hash()).is_err());
+        assert!(chain
+            .set_chain_tip(&BurnchainHeaderHash([0xff; 32]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_simulated_mempool_and_utxos() {
+        let chain = SimulatedBurnchain::new();
+        let script = miner_script(1);
+        let other_script = miner_script(2);
+        chain.mine_blocks(2, &script);
+
+        let utxos = chain.get_utxos(&script);
+        assert_eq!(utxos.len(), 2);
+        assert!(utxos
+            .iter()
+            .all(|utxo| utxo.value == SIMULATED_BLOCK_REWARD && utxo.block_height.is_some()));
+
+        // spend one coinbase, with change
+        let tx = spend(
+            utxos[0].txid,
+            utxos[0].vout,
+            vec![
+                TxOut {
+                    value: 1000,
+                    script_pubkey: other_script.clone(),
+                },
+                TxOut {
+                    value: SIMULATED_BLOCK_REWARD - 2000,
+                    script_pubkey: script.clone(),
+                },
+            ],
+        );
+        let txid = chain.submit_transaction(tx.clone());
+        assert_eq!(chain.submit_transaction(tx.clone()), txid);
+        assert_eq!(chain.mempool().len(), 1);
+        assert!(!chain.is_transaction_confirmed(&txid));
+
+        let utxos = chain.get_utxos(&script);
+        assert_eq!(utxos.len(), 2);
+        assert_eq!(
+            utxos
+                .iter()
+                .filter(|utxo| utxo.block_height.is_none())
+                .count(),
+            1
+        );
+        assert_eq!(chain.get_utxos(&other_script)[0].value, 1000);
+
+        // dropped transactions are never mined
+        assert!(chain.drop_transaction(&txid));
+        assert!(!chain.drop_transaction(&txid));
+        chain.mine_blocks(1, &script);
+        assert!(!chain.is_transaction_confirmed(&txid));
+        assert_eq!(chain.get_utxos(&script).len(), 3);
+
+        // mine it into a fork, and only see it confirmed once the fork is selected
+        chain.submit_transaction(tx);
+        let (tip, _) = chain.visible_tip();
+        let txs = chain.take_mempool();
+        assert!(chain.mempool().is_empty());
+        let parent = chain.get_block_hash(2).unwrap();
+        let fork_tip = chain.mine_block_on(&parent, txs, &other_script).unwrap();
+        assert!(!chain.is_transaction_confirmed(&txid));
+        assert!(chain.get_transaction(&txid).is_some());
+
+        chain.set_chain_tip(&fork_tip).unwrap();
+        assert!(chain.is_transaction_confirmed(&txid));
+        assert_eq!(chain.get_utxos(&other_script).len(), 2);
+        chain.set_chain_tip(&tip).unwrap();
+        assert!(!chain.is_transaction_confirmed(&txid));
+    }
+
+    #[test]
+    fn test_simulated_indexer_sync_and_reorg() {
+        let chain = SimulatedBurnchain::new();
+        let script = miner_script(1);
+        let mut indexer = make_indexer("sync_and_reorg", &chain);
+
+        let main = chain.mine_blocks(6, &script);
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), 6);
+        assert_eq!(indexer.find_chain_reorg().unwrap(), 6);
+        let headers = indexer.read_burnchain_headers(1, 7).unwrap();
+        assert_eq!(
+            headers
+                .iter()
+                .map(|hdr| hdr.block_hash.clone())
+                .collect::<Vec<_>>(),
+            main
+        );
+
+        // reorg to a shorter fork off of height 3
+        let fork_tip = chain.mine_block_on(&main[2], vec![], &script).unwrap();
+        chain.set_chain_tip(&fork_tip).unwrap();
+        assert_eq!(indexer.find_chain_reorg().unwrap(), 3);
+        assert_eq!(indexer.get_highest_header_height().unwrap(), 3);
+        assert_eq!(indexer.sync_headers(3, None).unwrap(), 4);
+        assert_eq!(
+            indexer.find_burnchain_header_height(&fork_tip).unwrap(),
+            Some(4)
+        );
+
+        // withheld blocks aren't synced
+        let next = chain.mine_blocks(2, &script);
+        chain.withhold_block(&next[0]).unwrap();
+        assert_eq!(indexer.sync_headers(4, None).unwrap(), 4);
+        chain.release_block(&next[0]);
+        assert_eq!(indexer.sync_headers(4, None).unwrap(), 6);
+        assert_eq!(indexer.find_chain_reorg().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_simulated_indexer_download_and_parse() {
+        let chain = SimulatedBurnchain::new();
+        let script = miner_script(1);
+        let mut indexer = make_indexer("download_and_parse", &chain);
+        chain.mine_blocks(1, &script);
+        let coinbase = chain.get_utxos(&script)[0].clone();
+
+        let mut payload = BLOCKSTACK_MAGIC_MAINNET.as_bytes().to_vec();
+        payload.push(b'^');
+        payload.extend_from_slice(&[0x11; 40]);
+        let op_tx = spend(
+            coinbase.txid,
+            coinbase.vout,
+            vec![
+                TxOut {
+                    value: 0,
+                    script_pubkey: Builder::new()
+                        .push_opcode(opcodes::All::OP_RETURN)
+                        .push_slice(&payload)
+                        .into_script(),
+                },
+                TxOut {
+                    value: SIMULATED_BLOCK_REWARD - 1000,
+                    script_pubkey: script.clone(),
+                },
+            ],
+        );
+        let txid = chain.submit_transaction(op_tx);
+        chain.mine_blocks(1, &script);
+
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), 2);
+        let headers = indexer.read_headers(2, 3).unwrap();
+        let ipc_block = indexer.downloader().download(&headers[0]).unwrap();
+        let block = indexer
+            .parser()
+            .parse(&ipc_block, StacksEpochId::Epoch21)
+            .unwrap();
+        let BurnchainBlock::Bitcoin(block) = block;
+        assert_eq!(block.block_height, 2);
+        assert_eq!(block.txs.len(), 1);
+        assert_eq!(block.txs[0].txid, txid);
+        assert_eq!(block.txs[0].opcode, b'^');
+        assert_eq!(block.txs[0].data, vec![0x11; 40]);
+    }
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::ops::Deref;
 use std::{cmp, fs};
@@ -56,6 +56,71 @@ pub const BITCOIN_GENESIS_BLOCK_HASH_TESTNET: &'static str =
 pub const BITCOIN_GENESIS_BLOCK_HASH_REGTEST: &'static str =
     "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
 
+/// Mainnet header checkpoints, as Bitcoin Core shipped them before it dropped checkpoints.  A
+/// header stored at one of these heights must have the given hash, no matter how much work its
+/// chain claims to have.  They end at height 295000, so they only pin the early chain; headers
+/// above that are checked against `header_checkpoints` from the node's config, if any.
+pub const BITCOIN_HEADER_CHECKPOINTS_MAINNET: &[(u64, &'static str)] = &[
+    (
+        11111,
+        "0000000069e244f73d78e8fd29ba2fd2ed618bd6fa2ee92559f542fdb26e7c1d",
+    ),
+    (
+        33333,
+        "000000002dd5588a74784eaa7ab0507a18ad16a236e7b1ce69f00d7ddfb5d0a6",
+    ),
+    (
+        74000,
+        "0000000000573993a3c9e41ce34471c079dcf5f52a0e824a81e7f953b8661a20",
+    ),
+    (
+        105000,
+        "00000000000291ce28027faea320c8d2b054b2e0fe44a773f3eefb151d6bdc97",
+    ),
+    (
+        134444,
+        "00000000000005b12ffd4cd315cd34ffd4a594f430ac814c91184a0d42d2b0fe",
+    ),
+    (
+        168000,
+        "000000000000099e61ea72015e79632f216fe6cb33d7899acb35b75c8303b763",
+    ),
+    (
+        193000,
+        "000000000000059f452a5f7340de6682a977387c17010ff6e6c3bd83ca8b1317",
+    ),
+    (
+        210000,
+        "000000000000048b95347e83192f69cf0366076336c639f9b7228e9ba171342e",
+    ),
+    (
+        216116,
+        "00000000000001b4f4b433e81ee46494af945cf96014816a4e2370f11b23df4e",
+    ),
+    (
+        225430,
+        "00000000000001c108384350f74090433e7fcf79a606b8e797f065b130575932",
+    ),
+    (
+        250000,
+        "000000000000003887df1f29024b06fc2200b55f8af8f35453d7be294df2d214",
+    ),
+    (
+        279000,
+        "0000000000000001ae8c72a0b0c301f67e3afca10e819efa9041e458e9bd7e40",
+    ),
+    (
+        295000,
+        "00000000000000004d9b4ef50f0f9d686fd69db2e03af35a100370c64632a983",
+    ),
+];
+
+/// Testnet header checkpoints
+pub const BITCOIN_HEADER_CHECKPOINTS_TESTNET: &[(u64, &'static str)] = &[(
+    546,
+    "000000002a936ca763904c3c35fce2f3556c559c0214345d31b1bcebf76acb70",
+)];
+
 pub const BLOCK_DIFFICULTY_CHUNK_SIZE: u64 = 2016;
 const BLOCK_DIFFICULTY_INTERVAL: u32 = 14 * 24 * 60 * 60; // two weeks, in seconds
 
@@ -122,6 +187,28 @@ pub struct SpvClient {
     reverse_order: bool,
     headers_db: DBConn,
     check_txcount: bool,
+    /// block height --> the only acceptable header hash at that height
+    checkpoints: HashMap<u64, BurnchainHeaderHash>,
+}
+
+/// Get the built-in header checkpoints for a network
+pub fn get_bitcoin_header_checkpoints(
+    network_id: BitcoinNetworkType,
+) -> Vec<(u64, BurnchainHeaderHash)> {
+    let checkpoints = match network_id {
+        BitcoinNetworkType::Mainnet => BITCOIN_HEADER_CHECKPOINTS_MAINNET,
+        BitcoinNetworkType::Testnet => BITCOIN_HEADER_CHECKPOINTS_TESTNET,
+        BitcoinNetworkType::Regtest => &[],
+    };
+    checkpoints
+        .iter()
+        .map(|(height, hash_str)| {
+            (
+                *height,
+                BurnchainHeaderHash::from_hex(hash_str).expect("BUG: invalid checkpoint hash"),
+            )
+        })
+        .collect()
 }
 
 impl FromColumn<Sha256dHash> for Sha256dHash {
@@ -171,6 +258,9 @@ impl SpvClient {
             reverse_order: reverse_order,
             headers_db: conn,
             check_txcount: true,
+            checkpoints: get_bitcoin_header_checkpoints(network_id)
+                .into_iter()
+                .collect(),
         };
 
         let empty = client.is_empty()?;
@@ -201,6 +291,9 @@ impl SpvClient {
             reverse_order: reverse_order,
             headers_db: conn,
             check_txcount: true,
+            checkpoints: get_bitcoin_header_checkpoints(network_id)
+                .into_iter()
+                .collect(),
         };
 
         if readwrite {
@@ -215,6 +308,14 @@ impl SpvClient {
         self.check_txcount = false;
     }
 
+    /// Require the headers at the given heights to have the given hashes, in addition to the
+    /// built-in checkpoints for this network.  Later checkpoints at the same height win.
+    pub fn add_checkpoints(&mut self, checkpoints: &[(u64, BurnchainHeaderHash)]) {
+        for (height, hash) in checkpoints.iter() {
+            self.checkpoints.insert(*height, hash.clone());
+        }
+    }
+
     pub fn conn(&self) -> &DBConn {
         &self.headers_db
     }
@@ -562,9 +663,33 @@ impl SpvClient {
         return Ok(());
     }
 
+    /// Verify that none of the given headers, the first of which would be stored at
+    /// `first_height`, conflicts with a checkpoint.
+    fn validate_header_checkpoints(
+        &self,
+        first_height: u64,
+        headers: &[LoneBlockHeader],
+    ) -> Result<(), btc_error> {
+        for (i, header) in headers.iter().enumerate() {
+            let height = first_height + (i as u64);
+            let Some(checkpoint) = self.checkpoints.get(&height) else {
+                continue;
+            };
+            let header_hash = BurnchainHeaderHash::from_bitcoin_hash(&header.header.bitcoin_hash());
+            if header_hash != *checkpoint {
+                error!(
+                    "Header {} at height {} does not match checkpoint {}",
+                    &header_hash, height, checkpoint
+                );
+                return Err(btc_error::CheckpointMismatch(height));
+            }
+        }
+        Ok(())
+    }
+
     /// Verify that the given headers have the correct amount of work to be appended to our
     /// local header chain.  Checks the difficulty between [interval, interval+1]
-    fn validate_header_work(
+    pub fn validate_header_work(
         &self,
         interval_start: u64,
         interval_end: u64,
@@ -961,6 +1086,7 @@ impl SpvClient {
                 error!("Received invalid headers: {:?}", &e);
                 e
             })?;
+        self.validate_header_checkpoints(start_height + 1, &block_headers)?;
 
         let parent_header = match self.read_block_header(start_height)? {
             Some(header) => header,
@@ -1015,6 +1141,7 @@ impl SpvClient {
                 error!("Received invalid headers: {:?}", &e);
                 e
             })?;
+        self.validate_header_checkpoints(start_height + 1, &block_headers)?;
 
         match self.read_block_header(end_height)? {
             Some(child_header) => {
@@ -1540,6 +1667,83 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_spv_store_headers_checkpoints() {
+        if fs::metadata("/tmp/test-spv-store_headers_checkpoints.dat").is_ok() {
+            fs::remove_file("/tmp/test-spv-store_headers_checkpoints.dat").unwrap();
+        }
+        let genesis_regtest_header = get_genesis_regtest_header();
+        let headers = vec![
+            LoneBlockHeader {
+                header: BlockHeader {
+                    bits: 545259519,
+                    merkle_root: Sha256dHash::from_hex(
+                        "20bee96458517fc5082a9720ce6207b5742f2b18e4e0a7e7373342725d80f88c",
+                    )
+                    .unwrap(),
+                    nonce: 2,
+                    prev_blockhash: Sha256dHash::from_hex(
+                        "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+                    )
+                    .unwrap(),
+                    time: 1587626881,
+                    version: 0x20000000,
+                },
+                tx_count: VarInt(0),
+            },
+            LoneBlockHeader {
+                header: BlockHeader {
+                    bits: 545259519,
+                    merkle_root: Sha256dHash::from_hex(
+                        "39d1a6f1ee7a5903797f92ec89e4c58549013f38114186fc2eb6e5218cb2d0ac",
+                    )
+                    .unwrap(),
+                    nonce: 1,
+                    prev_blockhash: Sha256dHash::from_hex(
+                        "606d31daaaa5919f3720d8440dd99d31f2a4e4189c65879f19ae43268425e74b",
+                    )
+                    .unwrap(),
+                    time: 1587626882,
+                    version: 0x20000000,
+                },
+                tx_count: VarInt(0),
+            },
+        ];
+
+        let mut spv_client = SpvClient::new(
+            "/tmp/test-spv-store_headers_checkpoints.dat",
+            0,
+            None,
+            BitcoinNetworkType::Regtest,
+            true,
+            false,
+        )
+        .unwrap();
+
+        // a checkpoint that the second header does not match
+        spv_client.add_checkpoints(&[(2, BurnchainHeaderHash([0x11; 32]))]);
+        if let Err(btc_error::CheckpointMismatch(2)) =
+            spv_client.insert_block_headers_after(0, headers.clone())
+        {
+        } else {
+            assert!(false);
+        }
+        assert_eq!(
+            spv_client.read_block_headers(0, 10).unwrap(),
+            vec![genesis_regtest_header.clone()]
+        );
+
+        // a later checkpoint at the same height replaces it
+        spv_client.add_checkpoints(&[(
+            2,
+            BurnchainHeaderHash::from_bitcoin_hash(&headers[1].header.bitcoin_hash()),
+        )]);
+        spv_client
+            .insert_block_headers_after(0, headers.clone())
+            .unwrap();
+        assert_eq!(spv_client.read_block_headers(1, 10).unwrap(), headers);
+    }
+
     #[test]
     fn test_spv_store_headers_before() {
         if fs::metadata("/tmp/test-spv-store_headers_before.dat").is_ok() {
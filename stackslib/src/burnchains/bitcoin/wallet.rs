@@ -0,0 +1,1380 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A minimal UTXO wallet for the keys a node burns with (i.e. a miner's), built from the blocks
+//! that the burnchain indexer downloads anyway, so that the node does not need bitcoind's wallet.
+//!
+//! The block parser reports, for each block, the outputs that pay to a watched p2pkh or p2wpkh
+//! script and the inputs that reveal a watched public key.  These are stored in the burnchain DB
+//! keyed by the block that contains them, so the unspent set can be computed for whichever
+//! burnchain fork is canonical.  Transactions the node broadcasts are recorded as pending until
+//! they are mined, so their inputs are not spent twice and their change can be chained.
+//!
+//! Once a block is `WALLET_FINALITY_DEPTH` blocks below the canonical tip, its activity is treated
+//! as final: activity from other forks at that height is deleted, as are outputs whose spends are
+//! final, along with those spends.  The wallet tables therefore only hold the unspent outputs
+//! plus the last `WALLET_FINALITY_DEPTH` blocks' worth of activity.
+
+use std::collections::HashSet;
+
+use rusqlite::types::ToSql;
+use rusqlite::{Row, NO_PARAMS};
+use stacks_common::deps_common::bitcoin::blockdata::block::Block;
+use stacks_common::deps_common::bitcoin::blockdata::script::{Instruction, Script};
+use stacks_common::deps_common::bitcoin::blockdata::transaction::{Transaction, TxIn};
+use stacks_common::deps_common::bitcoin::network::message as btc_message;
+use stacks_common::deps_common::bitcoin::network::serialize::BitcoinHash;
+use stacks_common::types::chainstate::BurnchainHeaderHash;
+use stacks_common::util::hash::{hex_bytes, to_hex, Hash160};
+
+use crate::burnchains::bitcoin::bits::parse_script;
+use crate::burnchains::bitcoin::indexer::BitcoinIndexer;
+use crate::burnchains::bitcoin::keys::BitcoinPublicKey;
+use crate::burnchains::db::{BurnchainDB, BurnchainDBTransaction};
+use crate::burnchains::indexer::{BurnchainBlockDownloader, BurnchainIndexer};
+use crate::burnchains::{BurnchainBlockHeader, Error as BurnchainError, PublicKey, Txid};
+use crate::util_lib::db::{
+    query_row, query_rows, u64_to_sql, DBConn, Error as DBError, FromColumn, FromRow,
+};
+
+/// Number of burnchain blocks a broadcast transaction is considered pending for.  After this, if
+/// it still has not been mined, its inputs become spendable again and its outputs are forgotten.
+pub const WALLET_PENDING_TX_LIFETIME: u64 = 12;
+
+/// Number of blocks below the canonical burnchain tip after which a block's wallet activity is
+/// final, and no longer subject to reorgs.
+pub const WALLET_FINALITY_DEPTH: u64 = 144;
+
+const BITCOIN_WALLET_SCHEMA: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS wallet_outputs (
+        -- 32-byte ID of the transaction that created this output
+        txid TEXT NOT NULL,
+        -- index of this output in the transaction
+        vout INTEGER NOT NULL,
+        -- burnchain block that contains the transaction
+        block_hash TEXT NOT NULL,
+        block_height INTEGER NOT NULL,
+        -- value in satoshis
+        amount INTEGER NOT NULL,
+        -- hex-encoded scriptPubKey
+        script_pubkey TEXT NOT NULL,
+
+        -- the same transaction can be mined in more than one fork
+        PRIMARY KEY(txid,vout,block_hash)
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS wallet_spends (
+        -- the spent output
+        txid TEXT NOT NULL,
+        vout INTEGER NOT NULL,
+        -- 32-byte ID of the spending transaction
+        spending_txid TEXT NOT NULL,
+        -- burnchain block that contains the spending transaction
+        block_hash TEXT NOT NULL,
+        block_height INTEGER NOT NULL,
+
+        PRIMARY KEY(txid,vout,block_hash)
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS wallet_pending_spends (
+        -- the spent output
+        txid TEXT NOT NULL,
+        vout INTEGER NOT NULL,
+        -- 32-byte ID of the broadcast (but not yet mined) spending transaction
+        spending_txid TEXT NOT NULL,
+        -- burnchain height at which it was broadcast
+        broadcast_height INTEGER NOT NULL,
+
+        PRIMARY KEY(txid,vout,spending_txid)
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS wallet_pending_outputs (
+        -- 32-byte ID of the broadcast (but not yet mined) transaction
+        txid TEXT NOT NULL,
+        vout INTEGER NOT NULL,
+        amount INTEGER NOT NULL,
+        script_pubkey TEXT NOT NULL,
+        broadcast_height INTEGER NOT NULL,
+
+        PRIMARY KEY(txid,vout)
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS wallet_rescan (
+        -- lowest burnchain height from which blocks have been rescanned for wallet activity
+        start_height INTEGER NOT NULL
+    );"#,
+    "CREATE INDEX IF NOT EXISTS index_wallet_outputs_block_height ON wallet_outputs(block_height);",
+    "CREATE INDEX IF NOT EXISTS index_wallet_outputs_script_pubkey ON wallet_outputs(script_pubkey);",
+    "CREATE INDEX IF NOT EXISTS index_wallet_spends_block_height ON wallet_spends(block_height);",
+    "CREATE INDEX IF NOT EXISTS index_wallet_spends_spending_txid ON wallet_spends(spending_txid);",
+    "CREATE INDEX IF NOT EXISTS index_wallet_pending_spends_spending_txid ON wallet_pending_spends(spending_txid);",
+];
+
+/// An output that pays to a watched key
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct WalletTxOutput {
+    pub txid: Txid,
+    pub vout: u32,
+    /// value in satoshis
+    pub amount: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+/// An input that spends with a watched key
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct WalletTxSpend {
+    /// the spent output
+    pub txid: Txid,
+    pub vout: u32,
+    pub spending_txid: Txid,
+}
+
+/// Everything a block (or a single transaction) does to the watched keys' outputs
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct BitcoinWalletActivity {
+    pub received: Vec<WalletTxOutput>,
+    pub spent: Vec<WalletTxSpend>,
+}
+
+/// An unspent output on the canonical burnchain fork, or the output of a pending transaction
+#[derive(Debug, PartialEq, Clone)]
+pub struct WalletUtxo {
+    pub txid: Txid,
+    pub vout: u32,
+    pub amount: u64,
+    pub script_pubkey: Vec<u8>,
+    /// 0 if this is the output of a transaction which has not been mined yet
+    pub confirmations: u32,
+}
+
+struct ConfirmedWalletOutput {
+    output: WalletTxOutput,
+    block_hash: BurnchainHeaderHash,
+    block_height: u64,
+}
+
+impl FromRow<ConfirmedWalletOutput> for ConfirmedWalletOutput {
+    fn from_row<'a>(row: &'a Row) -> Result<ConfirmedWalletOutput, DBError> {
+        Ok(ConfirmedWalletOutput {
+            output: WalletTxOutput::from_row(row)?,
+            block_hash: BurnchainHeaderHash::from_column(row, "block_hash")?,
+            block_height: u64::from_column(row, "block_height")?,
+        })
+    }
+}
+
+impl FromRow<WalletTxOutput> for WalletTxOutput {
+    fn from_row<'a>(row: &'a Row) -> Result<WalletTxOutput, DBError> {
+        let txid = Txid::from_column(row, "txid")?;
+        let vout: u32 = row.get_unwrap("vout");
+        let amount = u64::from_column(row, "amount")?;
+        let script_pubkey_hex: String = row.get_unwrap("script_pubkey");
+        let script_pubkey = hex_bytes(&script_pubkey_hex).map_err(|_| DBError::ParseError)?;
+        Ok(WalletTxOutput {
+            txid,
+            vout,
+            amount,
+            script_pubkey,
+        })
+    }
+}
+
+struct ConfirmedWalletSpend {
+    spend: WalletTxSpend,
+    block_hash: BurnchainHeaderHash,
+    block_height: u64,
+}
+
+impl FromRow<ConfirmedWalletSpend> for ConfirmedWalletSpend {
+    fn from_row<'a>(row: &'a Row) -> Result<ConfirmedWalletSpend, DBError> {
+        Ok(ConfirmedWalletSpend {
+            spend: WalletTxSpend::from_row(row)?,
+            block_hash: BurnchainHeaderHash::from_column(row, "block_hash")?,
+            block_height: u64::from_column(row, "block_height")?,
+        })
+    }
+}
+
+impl FromRow<WalletTxSpend> for WalletTxSpend {
+    fn from_row<'a>(row: &'a Row) -> Result<WalletTxSpend, DBError> {
+        Ok(WalletTxSpend {
+            txid: Txid::from_column(row, "txid")?,
+            vout: row.get_unwrap("vout"),
+            spending_txid: Txid::from_column(row, "spending_txid")?,
+        })
+    }
+}
+
+/// Get the hashes of both the compressed and uncompressed encodings of a public key.  These are
+/// what the wallet watches for, since the same key can be used in either form.
+pub fn wallet_pubkey_hashes(pubkey: &BitcoinPublicKey) -> Vec<Hash160> {
+    let mut pubkey = pubkey.clone();
+    let mut hashes = vec![];
+    for compressed in [true, false] {
+        pubkey.set_compressed(compressed);
+        hashes.push(Hash160::from_data(&pubkey.to_bytes()));
+    }
+    hashes
+}
+
+/// Get the public key hash a scriptPubKey pays to, if it is p2pkh or p2wpkh
+fn script_pubkey_hash(script: &Script) -> Option<Hash160> {
+    let bytes = script.as_bytes();
+    if script.is_p2pkh() {
+        Hash160::from_bytes(&bytes[3..23])
+    } else if script.is_v0_p2wpkh() {
+        Hash160::from_bytes(&bytes[2..22])
+    } else {
+        None
+    }
+}
+
+/// Get the public key an input reveals, if it is a p2pkh or p2wpkh spend
+fn input_pubkey(txin: &TxIn) -> Option<Vec<u8>> {
+    let pubkey = if txin.script_sig.len() == 0 {
+        // p2wpkh: <sig> <pubkey>
+        if txin.witness.len() != 2 {
+            return None;
+        }
+        txin.witness[1].clone()
+    } else {
+        // p2pkh: <sig> <pubkey>
+        match parse_script(&txin.script_sig).as_slice() {
+            [Instruction::PushBytes(_), Instruction::PushBytes(pubkey)] => pubkey.to_vec(),
+            _ => return None,
+        }
+    };
+    if pubkey.len() == 33 || pubkey.len() == 65 {
+        Some(pubkey)
+    } else {
+        None
+    }
+}
+
+impl BitcoinWalletActivity {
+    pub fn is_empty(&self) -> bool {
+        self.received.is_empty() && self.spent.is_empty()
+    }
+
+    /// Find the outputs and spends of the watched keys in a single transaction
+    pub fn scan_tx(&mut self, tx: &Transaction, watched: &HashSet<Hash160>) {
+        if watched.is_empty() {
+            return;
+        }
+        let txid =
+            Txid::from_vec_be(&tx.txid().as_bytes().to_vec()).expect("FATAL: txid is not 32 bytes");
+
+        for txin in tx.input.iter() {
+            let Some(pubkey) = input_pubkey(txin) else {
+                continue;
+            };
+            if !watched.contains(&Hash160::from_data(&pubkey)) {
+                continue;
+            }
+            let mut spent_txid = txin.previous_output.txid.0.clone();
+            spent_txid.reverse();
+            self.spent.push(WalletTxSpend {
+                txid: Txid(spent_txid),
+                vout: txin.previous_output.vout,
+                spending_txid: txid.clone(),
+            });
+        }
+
+        for (vout, txout) in tx.output.iter().enumerate() {
+            let Some(pubkey_hash) = script_pubkey_hash(&txout.script_pubkey) else {
+                continue;
+            };
+            if !watched.contains(&pubkey_hash) {
+                continue;
+            }
+            self.received.push(WalletTxOutput {
+                txid: txid.clone(),
+                vout: vout as u32,
+                amount: txout.value,
+                script_pubkey: txout.script_pubkey.to_bytes(),
+            });
+        }
+    }
+
+    /// Find the outputs and spends of the watched keys in a block
+    pub fn scan_block(block: &Block, watched: &HashSet<Hash160>) -> BitcoinWalletActivity {
+        let mut activity = BitcoinWalletActivity::default();
+        for tx in block.txdata.iter() {
+            activity.scan_tx(tx, watched);
+        }
+        activity
+    }
+}
+
+impl<'a> BurnchainDBTransaction<'a> {
+    /// Store the wallet activity found in a burnchain block.  Called when the block itself is
+    /// stored.
+    pub(crate) fn store_wallet_activity(
+        &self,
+        header: &BurnchainBlockHeader,
+        activity: &BitcoinWalletActivity,
+    ) -> Result<(), BurnchainError> {
+        for output in activity.received.iter() {
+            let args: &[&dyn ToSql] = &[
+                &output.txid,
+                &output.vout,
+                &header.block_hash,
+                &u64_to_sql(header.block_height)?,
+                &u64_to_sql(output.amount)?,
+                &to_hex(&output.script_pubkey),
+            ];
+            self.conn().execute(
+                "INSERT OR REPLACE INTO wallet_outputs (txid, vout, block_hash, block_height, amount, script_pubkey) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                args,
+            )?;
+        }
+        for spend in activity.spent.iter() {
+            let args: &[&dyn ToSql] = &[
+                &spend.txid,
+                &spend.vout,
+                &spend.spending_txid,
+                &header.block_hash,
+                &u64_to_sql(header.block_height)?,
+            ];
+            self.conn().execute(
+                "INSERT OR REPLACE INTO wallet_spends (txid, vout, spending_txid, block_hash, block_height) VALUES (?1, ?2, ?3, ?4, ?5)",
+                args,
+            )?;
+        }
+        if !activity.is_empty() {
+            debug!("Stored wallet activity";
+                   "burn_header_hash" => %header.block_hash,
+                   "block_height" => header.block_height,
+                   "received" => activity.received.len(),
+                   "spent" => activity.spent.len());
+        }
+        self.prune_wallet_activity()
+    }
+
+    /// Delete the wallet activity that has become final on the canonical fork and no longer
+    /// matters: activity from other forks in blocks at or below the final height, and outputs
+    /// spent at or below it, along with their spends.  Activity below the last `2 *
+    /// WALLET_FINALITY_DEPTH` blocks was pruned when earlier blocks were stored.
+    fn prune_wallet_activity(&self) -> Result<(), BurnchainError> {
+        let tip = self.get_canonical_chain_tip()?;
+        let Some(final_height) = tip.block_height.checked_sub(WALLET_FINALITY_DEPTH) else {
+            return Ok(());
+        };
+        let min_height = final_height.saturating_sub(WALLET_FINALITY_DEPTH);
+        let fork_blocks = BurnchainDB::get_wallet_fork_blocks(self.conn(), &tip, min_height)?;
+
+        for table in ["wallet_outputs", "wallet_spends"] {
+            let sql = format!(
+                "SELECT DISTINCT block_hash AS burn_header_hash FROM {} WHERE block_height >= ?1 AND block_height <= ?2",
+                table
+            );
+            let args: &[&dyn ToSql] = &[&u64_to_sql(min_height)?, &u64_to_sql(final_height)?];
+            let block_hashes: Vec<BurnchainHeaderHash> = query_rows(self.conn(), &sql, args)?;
+            for block_hash in block_hashes.iter() {
+                if fork_blocks.contains(block_hash) {
+                    continue;
+                }
+                self.conn().execute(
+                    &format!("DELETE FROM {} WHERE block_hash = ?1", table),
+                    &[block_hash],
+                )?;
+                debug!("Pruned orphaned wallet activity from {}", table;
+                       "burn_header_hash" => %block_hash);
+            }
+        }
+
+        // what is left at or below the final height is on the canonical fork
+        let num_spent = self.conn().execute(
+            "DELETE FROM wallet_outputs WHERE EXISTS (
+                 SELECT 1 FROM wallet_spends
+                 WHERE wallet_spends.txid = wallet_outputs.txid
+                   AND wallet_spends.vout = wallet_outputs.vout
+                   AND wallet_spends.block_height <= ?1)",
+            &[&u64_to_sql(final_height)?],
+        )?;
+        self.conn().execute(
+            "DELETE FROM wallet_spends WHERE block_height <= ?1",
+            &[&u64_to_sql(final_height)?],
+        )?;
+        if num_spent > 0 {
+            debug!("Pruned {} spent wallet outputs", num_spent;
+                   "final_height" => final_height);
+        }
+        Ok(())
+    }
+
+    /// Forget the pending transactions `txids`, as well as any pending transactions that spend
+    /// their outputs.
+    fn evict_wallet_pending_txs(&self, mut txids: Vec<Txid>) -> Result<(), BurnchainError> {
+        let mut evicted = HashSet::new();
+        while let Some(txid) = txids.pop() {
+            if !evicted.insert(txid.clone()) {
+                continue;
+            }
+            let children: Vec<Txid> = query_rows(
+                self.conn(),
+                "SELECT DISTINCT spending_txid FROM wallet_pending_spends WHERE txid = ?1",
+                &[&txid],
+            )?;
+            txids.extend(children);
+
+            self.conn().execute(
+                "DELETE FROM wallet_pending_spends WHERE spending_txid = ?1",
+                &[&txid],
+            )?;
+            self.conn().execute(
+                "DELETE FROM wallet_pending_outputs WHERE txid = ?1",
+                &[&txid],
+            )?;
+            debug!("Evicted pending wallet transaction {}", &txid);
+        }
+        Ok(())
+    }
+}
+
+impl BurnchainDB {
+    /// Create the wallet tables if they do not exist yet
+    pub(crate) fn add_wallet_tables(&mut self) -> Result<(), BurnchainError> {
+        let db_tx = self.tx_begin()?;
+        for stmt in BITCOIN_WALLET_SCHEMA.iter() {
+            db_tx.conn().execute_batch(stmt)?;
+        }
+        db_tx.commit()
+    }
+
+    /// Get the hashes of the blocks on the fork ending at `tip`, down to (and including)
+    /// `min_height`.
+    fn get_wallet_fork_blocks(
+        conn: &DBConn,
+        tip: &BurnchainBlockHeader,
+        min_height: u64,
+    ) -> Result<HashSet<BurnchainHeaderHash>, DBError> {
+        let sql = "WITH RECURSIVE fork(block_hash, parent_block_hash, block_height) AS (
+                       SELECT block_hash, parent_block_hash, block_height FROM burnchain_db_block_headers WHERE block_hash = ?1
+                       UNION ALL
+                       SELECT h.block_hash, h.parent_block_hash, h.block_height FROM burnchain_db_block_headers h
+                       JOIN fork ON h.block_hash = fork.parent_block_hash
+                       WHERE h.block_height >= ?2
+                   )
+                   SELECT block_hash AS burn_header_hash FROM fork";
+        let args: &[&dyn ToSql] = &[&tip.block_hash, &u64_to_sql(min_height)?];
+        let hashes: Vec<BurnchainHeaderHash> = query_rows(conn, sql, args)?;
+        Ok(hashes.into_iter().collect())
+    }
+
+    /// Get the unspent outputs paying to `script_pubkey` on the canonical burnchain fork.
+    /// Outputs spent by a pending transaction are left out, and the outputs of pending
+    /// transactions are included with zero confirmations.  Pending transactions broadcast more
+    /// than `pending_lifetime` blocks ago are ignored.
+    pub fn get_wallet_utxos(
+        &self,
+        script_pubkey: &[u8],
+        pending_lifetime: u64,
+    ) -> Result<Vec<WalletUtxo>, BurnchainError> {
+        let tip = self.get_canonical_chain_tip()?;
+        // activity at or below the final height was pruned down to the canonical fork's, so only
+        // the blocks above it need to be checked
+        let final_height = tip.block_height.saturating_sub(WALLET_FINALITY_DEPTH);
+        let fork_blocks = BurnchainDB::get_wallet_fork_blocks(&self.conn, &tip, final_height)?;
+        let is_canonical = |block_hash: &BurnchainHeaderHash, block_height: u64| {
+            block_height < final_height || fork_blocks.contains(block_hash)
+        };
+
+        let script_pubkey_hex = to_hex(script_pubkey);
+        let outputs: Vec<ConfirmedWalletOutput> = query_rows(
+            &self.conn,
+            "SELECT * FROM wallet_outputs WHERE script_pubkey = ?1 ORDER BY block_height, txid, vout",
+            &[&script_pubkey_hex],
+        )?;
+        let spends: Vec<ConfirmedWalletSpend> = query_rows(
+            &self.conn,
+            "SELECT * FROM wallet_spends WHERE EXISTS (
+                 SELECT 1 FROM wallet_outputs
+                 WHERE wallet_outputs.txid = wallet_spends.txid
+                   AND wallet_outputs.vout = wallet_spends.vout
+                   AND wallet_outputs.script_pubkey = ?1)",
+            &[&script_pubkey_hex],
+        )?;
+
+        let min_broadcast_height = tip.block_height.saturating_sub(pending_lifetime);
+        let pending_spends: Vec<WalletTxSpend> = query_rows(
+            &self.conn,
+            "SELECT * FROM wallet_pending_spends WHERE broadcast_height >= ?1",
+            &[&u64_to_sql(min_broadcast_height)?],
+        )?;
+        let args: &[&dyn ToSql] = &[&u64_to_sql(min_broadcast_height)?, &script_pubkey_hex];
+        let pending_outputs: Vec<WalletTxOutput> = query_rows(
+            &self.conn,
+            "SELECT * FROM wallet_pending_outputs WHERE broadcast_height >= ?1 AND script_pubkey = ?2 ORDER BY txid, vout",
+            args,
+        )?;
+
+        let mut mined_txids = HashSet::new();
+        let mut spent = HashSet::new();
+        for spend in spends.into_iter() {
+            if is_canonical(&spend.block_hash, spend.block_height) {
+                mined_txids.insert(spend.spend.spending_txid);
+                spent.insert((spend.spend.txid, spend.spend.vout));
+            }
+        }
+        for spend in pending_spends.into_iter() {
+            spent.insert((spend.txid, spend.vout));
+        }
+
+        // the same output can be stored once per fork, so dedup by outpoint
+        let mut utxos = vec![];
+        let mut seen = HashSet::new();
+        for output in outputs.into_iter() {
+            if !is_canonical(&output.block_hash, output.block_height) {
+                continue;
+            }
+            let outpoint = (output.output.txid.clone(), output.output.vout);
+            mined_txids.insert(output.output.txid.clone());
+            if spent.contains(&outpoint) || seen.contains(&outpoint) {
+                continue;
+            }
+            let confirmations = tip
+                .block_height
+                .saturating_sub(output.block_height)
+                .saturating_add(1);
+            seen.insert(outpoint);
+            utxos.push(WalletUtxo {
+                txid: output.output.txid,
+                vout: output.output.vout,
+                amount: output.output.amount,
+                script_pubkey: output.output.script_pubkey,
+                confirmations: u32::try_from(confirmations).unwrap_or(u32::MAX),
+            });
+        }
+
+        for output in pending_outputs.into_iter() {
+            // once mined, the output is reported (or not) from the block that contains it
+            if mined_txids.contains(&output.txid)
+                || spent.contains(&(output.txid.clone(), output.vout))
+            {
+                continue;
+            }
+            utxos.push(WalletUtxo {
+                txid: output.txid,
+                vout: output.vout,
+                amount: output.amount,
+                script_pubkey: output.script_pubkey,
+                confirmations: 0,
+            });
+        }
+
+        Ok(utxos)
+    }
+
+    /// Record a transaction this node has broadcast, so that its inputs are not handed out again
+    /// and its change can be spent before it is mined.  Pending transactions that conflict with
+    /// it (e.g. the ones it replaces by fee) are forgotten, as are ones that have been pending for
+    /// longer than `pending_lifetime` blocks.
+    pub fn record_wallet_pending_tx(
+        &mut self,
+        tx: &Transaction,
+        watched: &HashSet<Hash160>,
+        pending_lifetime: u64,
+    ) -> Result<(), BurnchainError> {
+        let mut activity = BitcoinWalletActivity::default();
+        activity.scan_tx(tx, watched);
+        if activity.is_empty() {
+            return Ok(());
+        }
+
+        let txid =
+            Txid::from_vec_be(&tx.txid().as_bytes().to_vec()).expect("FATAL: txid is not 32 bytes");
+        let tip_height = self.get_canonical_chain_tip()?.block_height;
+        let db_tx = self.tx_begin()?;
+
+        let min_broadcast_height = u64_to_sql(tip_height.saturating_sub(pending_lifetime))?;
+        db_tx.conn().execute(
+            "DELETE FROM wallet_pending_spends WHERE broadcast_height < ?1",
+            &[&min_broadcast_height],
+        )?;
+        db_tx.conn().execute(
+            "DELETE FROM wallet_pending_outputs WHERE broadcast_height < ?1",
+            &[&min_broadcast_height],
+        )?;
+
+        let mut conflicts = vec![];
+        for spend in activity.spent.iter() {
+            let args: &[&dyn ToSql] = &[&spend.txid, &spend.vout, &txid];
+            let conflicting: Vec<Txid> = query_rows(
+                db_tx.conn(),
+                "SELECT DISTINCT spending_txid FROM wallet_pending_spends WHERE txid = ?1 AND vout = ?2 AND spending_txid != ?3",
+                args,
+            )?;
+            conflicts.extend(conflicting);
+        }
+        db_tx.evict_wallet_pending_txs(conflicts)?;
+
+        for spend in activity.spent.iter() {
+            let args: &[&dyn ToSql] = &[
+                &spend.txid,
+                &spend.vout,
+                &spend.spending_txid,
+                &u64_to_sql(tip_height)?,
+            ];
+            db_tx.conn().execute(
+                "INSERT OR REPLACE INTO wallet_pending_spends (txid, vout, spending_txid, broadcast_height) VALUES (?1, ?2, ?3, ?4)",
+                args,
+            )?;
+        }
+        for output in activity.received.iter() {
+            let args: &[&dyn ToSql] = &[
+                &output.txid,
+                &output.vout,
+                &u64_to_sql(output.amount)?,
+                &to_hex(&output.script_pubkey),
+                &u64_to_sql(tip_height)?,
+            ];
+            db_tx.conn().execute(
+                "INSERT OR REPLACE INTO wallet_pending_outputs (txid, vout, amount, script_pubkey, broadcast_height) VALUES (?1, ?2, ?3, ?4, ?5)",
+                args,
+            )?;
+        }
+        db_tx.commit()?;
+
+        debug!("Recorded pending wallet transaction";
+               "txid" => %txid,
+               "spent" => activity.spent.len(),
+               "received" => activity.received.len());
+        Ok(())
+    }
+
+    /// Get the lowest burnchain height from which the wallet has been rescanned, if ever
+    pub fn get_wallet_rescan_height(&self) -> Result<Option<u64>, BurnchainError> {
+        let height: Option<u64> = query_row(
+            &self.conn,
+            "SELECT start_height FROM wallet_rescan ORDER BY start_height ASC LIMIT 1",
+            NO_PARAMS,
+        )?;
+        Ok(height)
+    }
+
+    /// Re-download the burnchain blocks this DB has stored from `start_height` up to the
+    /// canonical tip, and record the wallet activity of the indexer's watched keys in them.
+    /// This picks up outputs that were received before the wallet started watching.  Blocks
+    /// below the first burnchain block are not scanned.  Returns the number of blocks scanned.
+    pub fn rescan_wallet(
+        &mut self,
+        indexer: &BitcoinIndexer,
+        start_height: u64,
+    ) -> Result<u64, BurnchainError> {
+        let watched: HashSet<Hash160> = indexer
+            .config
+            .watched_pubkey_hashes
+            .iter()
+            .cloned()
+            .collect();
+        let tip = self.get_canonical_chain_tip()?;
+        let start_height = start_height.max(indexer.get_first_block_height());
+        if watched.is_empty() {
+            return Ok(0);
+        }
+
+        info!(
+            "Rescanning burnchain blocks {}-{} for wallet activity",
+            start_height, tip.block_height
+        );
+        let mut downloader = indexer.downloader();
+        let mut num_scanned = 0;
+        for height in start_height..=tip.block_height {
+            let Some(ipc_header) = indexer.read_headers(height, height + 1)?.into_iter().next()
+            else {
+                break;
+            };
+            let block_hash = BurnchainHeaderHash::from_bitcoin_hash(
+                &ipc_header.block_header.header.bitcoin_hash(),
+            );
+            let Some(header) = BurnchainDB::get_burnchain_header_by_hash(&self.conn, &block_hash)?
+            else {
+                // not processed yet; it will be scanned when it is
+                continue;
+            };
+
+            let ipc_block = downloader.download(&ipc_header)?;
+            let btc_message::NetworkMessage::Block(ref block) = ipc_block.block_message else {
+                return Err(BurnchainError::ParseError);
+            };
+            let activity = BitcoinWalletActivity::scan_block(block, &watched);
+
+            let db_tx = self.tx_begin()?;
+            db_tx.store_wallet_activity(&header, &activity)?;
+            db_tx.commit()?;
+            num_scanned += 1;
+        }
+
+        let db_tx = self.tx_begin()?;
+        db_tx.conn().execute(
+            "INSERT INTO wallet_rescan (start_height) VALUES (?1)",
+            &[&u64_to_sql(start_height)?],
+        )?;
+        db_tx.commit()?;
+        Ok(num_scanned)
+    }
+
+    fn get_burnchain_header_by_hash(
+        conn: &DBConn,
+        block_hash: &BurnchainHeaderHash,
+    ) -> Result<Option<BurnchainBlockHeader>, DBError> {
+        query_row(
+            conn,
+            "SELECT * FROM burnchain_db_block_headers WHERE block_hash = ?1",
+            &[block_hash],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::deps_common::bitcoin::blockdata::script::Builder;
+    use stacks_common::deps_common::bitcoin::blockdata::transaction::{OutPoint, TxOut};
+    use stacks_common::deps_common::bitcoin::util::hash::Sha256dHash;
+    use stacks_common::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
+
+    use super::*;
+    use crate::burnchains::Burnchain;
+
+    fn p2pkh_script(hash: &Hash160) -> Script {
+        let mut bytes = vec![0x76, 0xa9, 0x14];
+        bytes.extend_from_slice(hash.as_bytes());
+        bytes.extend_from_slice(&[0x88, 0xac]);
+        Script::from(bytes)
+    }
+
+    fn p2wpkh_script(hash: &Hash160) -> Script {
+        let mut bytes = vec![0x00, 0x14];
+        bytes.extend_from_slice(hash.as_bytes());
+        Script::from(bytes)
+    }
+
+    fn p2pkh_input(prev: &Txid, vout: u32, pubkey: &Secp256k1PublicKey) -> TxIn {
+        let mut prev_bytes = prev.0.clone();
+        prev_bytes.reverse();
+        TxIn {
+            previous_output: OutPoint {
+                txid: Sha256dHash(prev_bytes),
+                vout,
+            },
+            script_sig: Builder::new()
+                .push_slice(&[0x30; 71])
+                .push_slice(&pubkey.to_bytes())
+                .into_script(),
+            sequence: 0xfffffffd,
+            witness: vec![],
+        }
+    }
+
+    fn p2wpkh_input(prev: &Txid, vout: u32, pubkey: &Secp256k1PublicKey) -> TxIn {
+        let mut input = p2pkh_input(prev, vout, pubkey);
+        input.script_sig = Script::new();
+        input.witness = vec![vec![0x30; 71], pubkey.to_bytes()];
+        input
+    }
+
+    fn make_tx(input: Vec<TxIn>, output: Vec<(u64, Script)>) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input,
+            output: output
+                .into_iter()
+                .map(|(value, script_pubkey)| TxOut {
+                    value,
+                    script_pubkey,
+                })
+                .collect(),
+        }
+    }
+
+    fn tx_txid(tx: &Transaction) -> Txid {
+        Txid::from_vec_be(&tx.txid().as_bytes().to_vec()).unwrap()
+    }
+
+    fn make_header(height: u64, id: u8, parent: &BurnchainBlockHeader) -> BurnchainBlockHeader {
+        BurnchainBlockHeader {
+            block_height: height,
+            block_hash: BurnchainHeaderHash([id; 32]),
+            parent_block_hash: parent.block_hash.clone(),
+            num_txs: 1,
+            timestamp: height,
+        }
+    }
+
+    fn store_block(
+        burnchain_db: &mut BurnchainDB,
+        burnchain: &Burnchain,
+        headers: &mut Vec<BurnchainBlockHeader>,
+        header: &BurnchainBlockHeader,
+        txs: &[Transaction],
+        watched: &HashSet<Hash160>,
+    ) {
+        headers.push(header.clone());
+        burnchain_db
+            .store_new_burnchain_block_ops_unchecked(burnchain, headers, header, &[])
+            .unwrap();
+
+        let mut activity = BitcoinWalletActivity::default();
+        for tx in txs.iter() {
+            activity.scan_tx(tx, watched);
+        }
+        let db_tx = burnchain_db.tx_begin().unwrap();
+        db_tx.store_wallet_activity(header, &activity).unwrap();
+        db_tx.commit().unwrap();
+    }
+
+    #[test]
+    fn test_wallet_pubkey_hashes() {
+        let mut pubkey = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let hashes = wallet_pubkey_hashes(&pubkey);
+        assert_eq!(hashes.len(), 2);
+
+        pubkey.set_compressed(true);
+        assert_eq!(hashes[0], Hash160::from_data(&pubkey.to_bytes()));
+        pubkey.set_compressed(false);
+        assert_eq!(hashes[1], Hash160::from_data(&pubkey.to_bytes()));
+    }
+
+    #[test]
+    fn test_scan_tx() {
+        let pubkey = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let other_pubkey = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let watched: HashSet<Hash160> = wallet_pubkey_hashes(&pubkey).into_iter().collect();
+        let hash = Hash160::from_data(&pubkey.to_bytes());
+        let other_hash = Hash160::from_data(&other_pubkey.to_bytes());
+
+        let prev_txid = Txid([0x11; 32]);
+        let tx = make_tx(
+            vec![
+                p2pkh_input(&prev_txid, 0, &pubkey),
+                p2wpkh_input(&prev_txid, 1, &pubkey),
+                p2pkh_input(&prev_txid, 2, &other_pubkey),
+            ],
+            vec![
+                (0, Builder::new().push_slice(b"id[").into_script()),
+                (1000, p2pkh_script(&hash)),
+                (2000, p2wpkh_script(&hash)),
+                (3000, p2pkh_script(&other_hash)),
+            ],
+        );
+        let txid = tx_txid(&tx);
+
+        let mut activity = BitcoinWalletActivity::default();
+        activity.scan_tx(&tx, &watched);
+        assert_eq!(
+            activity.spent,
+            vec![
+                WalletTxSpend {
+                    txid: prev_txid.clone(),
+                    vout: 0,
+                    spending_txid: txid.clone(),
+                },
+                WalletTxSpend {
+                    txid: prev_txid.clone(),
+                    vout: 1,
+                    spending_txid: txid.clone(),
+                },
+            ]
+        );
+        assert_eq!(
+            activity.received,
+            vec![
+                WalletTxOutput {
+                    txid: txid.clone(),
+                    vout: 1,
+                    amount: 1000,
+                    script_pubkey: p2pkh_script(&hash).to_bytes(),
+                },
+                WalletTxOutput {
+                    txid: txid.clone(),
+                    vout: 2,
+                    amount: 2000,
+                    script_pubkey: p2wpkh_script(&hash).to_bytes(),
+                },
+            ]
+        );
+
+        // nothing is reported if nothing is watched
+        let mut activity = BitcoinWalletActivity::default();
+        activity.scan_tx(&tx, &HashSet::new());
+        assert!(activity.is_empty());
+    }
+
+    #[test]
+    fn test_wallet_utxos_follow_canonical_fork() {
+        let burnchain = Burnchain::regtest(":memory:");
+        let mut burnchain_db = BurnchainDB::connect(":memory:", &burnchain, true).unwrap();
+        let first_header = burnchain_db.get_canonical_chain_tip().unwrap();
+        let mut headers = vec![first_header.clone()];
+
+        let pubkey = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let watched: HashSet<Hash160> = wallet_pubkey_hashes(&pubkey).into_iter().collect();
+        let script = p2pkh_script(&Hash160::from_data(&pubkey.to_bytes()));
+
+        // fund the wallet in block 1
+        let funding_tx = make_tx(
+            vec![p2pkh_input(
+                &Txid([0x22; 32]),
+                0,
+                &Secp256k1PublicKey::new(),
+            )],
+            vec![(100_000, script.clone())],
+        );
+        let funding_txid = tx_txid(&funding_tx);
+        let header_1 = make_header(1, 1, &first_header);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_1,
+            &[funding_tx],
+            &watched,
+        );
+
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, funding_txid);
+        assert_eq!(utxos[0].amount, 100_000);
+        assert_eq!(utxos[0].confirmations, 1);
+
+        // spend it in block 2a
+        let spend_tx = make_tx(
+            vec![p2pkh_input(&funding_txid, 0, &pubkey)],
+            vec![(90_000, script.clone())],
+        );
+        let spend_txid = tx_txid(&spend_tx);
+        let header_2a = make_header(2, 2, &header_1);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_2a,
+            &[spend_tx],
+            &watched,
+        );
+
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, spend_txid);
+        assert_eq!(utxos[0].amount, 90_000);
+        assert_eq!(utxos[0].confirmations, 1);
+
+        // a longer fork without the spend becomes canonical
+        let header_2b = make_header(2, 3, &header_1);
+        let header_3b = make_header(3, 4, &header_2b);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_2b,
+            &[],
+            &watched,
+        );
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_3b,
+            &[],
+            &watched,
+        );
+        assert_eq!(
+            burnchain_db.get_canonical_chain_tip().unwrap().block_hash,
+            header_3b.block_hash
+        );
+
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, funding_txid);
+        assert_eq!(utxos[0].confirmations, 3);
+
+        // only outputs paying to the requested script are returned
+        let other_script = p2wpkh_script(&Hash160::from_data(&pubkey.to_bytes()));
+        assert!(burnchain_db
+            .get_wallet_utxos(&other_script.to_bytes(), 12)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_wallet_reorg_orphans_output_and_spend() {
+        let burnchain = Burnchain::regtest(":memory:");
+        let mut burnchain_db = BurnchainDB::connect(":memory:", &burnchain, true).unwrap();
+        let first_header = burnchain_db.get_canonical_chain_tip().unwrap();
+        let mut headers = vec![first_header.clone()];
+
+        let pubkey = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let watched: HashSet<Hash160> = wallet_pubkey_hashes(&pubkey).into_iter().collect();
+        let script = p2pkh_script(&Hash160::from_data(&pubkey.to_bytes()));
+
+        let funding_tx = make_tx(
+            vec![p2pkh_input(
+                &Txid([0x22; 32]),
+                0,
+                &Secp256k1PublicKey::new(),
+            )],
+            vec![(100_000, script.clone())],
+        );
+        let funding_txid = tx_txid(&funding_tx);
+        let header_1 = make_header(1, 1, &first_header);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_1,
+            &[funding_tx],
+            &watched,
+        );
+
+        // fork a receives an output in block 2a, and spends it along with the funding output in
+        // block 3a
+        let receive_tx = make_tx(
+            vec![p2pkh_input(
+                &Txid([0x33; 32]),
+                0,
+                &Secp256k1PublicKey::new(),
+            )],
+            vec![(50_000, script.clone())],
+        );
+        let receive_txid = tx_txid(&receive_tx);
+        let spend_tx = make_tx(
+            vec![
+                p2pkh_input(&funding_txid, 0, &pubkey),
+                p2pkh_input(&receive_txid, 0, &pubkey),
+            ],
+            vec![(140_000, script.clone())],
+        );
+        let spend_txid = tx_txid(&spend_tx);
+        let header_2a = make_header(2, 2, &header_1);
+        let header_3a = make_header(3, 3, &header_2a);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_2a,
+            &[receive_tx],
+            &watched,
+        );
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 2);
+        assert_eq!(utxos[1].txid, receive_txid);
+
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_3a,
+            &[spend_tx],
+            &watched,
+        );
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, spend_txid);
+        assert_eq!(utxos[0].amount, 140_000);
+
+        // a longer fork b with neither the output nor the spend becomes canonical, leaving only
+        // the funding output
+        let mut parent = header_1.clone();
+        for i in 2..5 {
+            let header = make_header(i, 10 + i as u8, &parent);
+            store_block(
+                &mut burnchain_db,
+                &burnchain,
+                &mut headers,
+                &header,
+                &[],
+                &watched,
+            );
+            parent = header;
+        }
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, funding_txid);
+        assert_eq!(utxos[0].confirmations, 4);
+
+        // fork a becomes canonical again
+        let mut parent = header_3a;
+        for i in 4..6 {
+            let header = make_header(i, 20 + i as u8, &parent);
+            store_block(
+                &mut burnchain_db,
+                &burnchain,
+                &mut headers,
+                &header,
+                &[],
+                &watched,
+            );
+            parent = header;
+        }
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, spend_txid);
+        assert_eq!(utxos[0].confirmations, 3);
+    }
+
+    #[test]
+    fn test_wallet_prunes_final_activity() {
+        let burnchain = Burnchain::regtest(":memory:");
+        let mut burnchain_db = BurnchainDB::connect(":memory:", &burnchain, true).unwrap();
+        let first_header = burnchain_db.get_canonical_chain_tip().unwrap();
+        let mut headers = vec![first_header.clone()];
+
+        let pubkey = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let watched: HashSet<Hash160> = wallet_pubkey_hashes(&pubkey).into_iter().collect();
+        let script = p2pkh_script(&Hash160::from_data(&pubkey.to_bytes()));
+        let count_rows = |burnchain_db: &BurnchainDB, table: &str| -> u64 {
+            query_row(
+                &burnchain_db.conn,
+                &format!("SELECT COUNT(*) FROM {}", table),
+                NO_PARAMS,
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        // fund the wallet in block 1 and spend the output in block 2
+        let funding_tx = make_tx(
+            vec![p2pkh_input(
+                &Txid([0x22; 32]),
+                0,
+                &Secp256k1PublicKey::new(),
+            )],
+            vec![(100_000, script.clone())],
+        );
+        let funding_txid = tx_txid(&funding_tx);
+        let spend_tx = make_tx(
+            vec![p2pkh_input(&funding_txid, 0, &pubkey)],
+            vec![(90_000, script.clone())],
+        );
+        let spend_txid = tx_txid(&spend_tx);
+        let header_1 = make_header(1, 1, &first_header);
+        let header_2 = make_header(2, 2, &header_1);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_1,
+            &[funding_tx],
+            &watched,
+        );
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_2,
+            &[spend_tx],
+            &watched,
+        );
+
+        // a competing block 2 receives an output, but loses
+        let orphan_tx = make_tx(
+            vec![p2pkh_input(
+                &Txid([0x33; 32]),
+                0,
+                &Secp256k1PublicKey::new(),
+            )],
+            vec![(50_000, script.clone())],
+        );
+        let orphan_header = make_header(2, 0xf0, &header_1);
+        let mut parent = header_2.clone();
+        for i in 3..(2 + WALLET_FINALITY_DEPTH) {
+            let header = make_header(i, i as u8, &parent);
+            store_block(
+                &mut burnchain_db,
+                &burnchain,
+                &mut headers,
+                &header,
+                &[],
+                &watched,
+            );
+            parent = header;
+            if i == 3 {
+                store_block(
+                    &mut burnchain_db,
+                    &burnchain,
+                    &mut headers,
+                    &orphan_header,
+                    &[orphan_tx.clone()],
+                    &watched,
+                );
+            }
+        }
+
+        // block 2 is not final yet, so nothing has been pruned
+        assert_eq!(count_rows(&burnchain_db, "wallet_outputs"), 3);
+        assert_eq!(count_rows(&burnchain_db, "wallet_spends"), 1);
+
+        // once it is, the spent output, its spend and the orphaned output are gone
+        let header = make_header(2 + WALLET_FINALITY_DEPTH, 0xf1, &parent);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header,
+            &[],
+            &watched,
+        );
+        assert_eq!(count_rows(&burnchain_db, "wallet_outputs"), 1);
+        assert_eq!(count_rows(&burnchain_db, "wallet_spends"), 0);
+
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, spend_txid);
+        assert_eq!(utxos[0].confirmations, (WALLET_FINALITY_DEPTH + 1) as u32);
+    }
+
+    #[test]
+    fn test_wallet_pending_txs() {
+        let burnchain = Burnchain::regtest(":memory:");
+        let mut burnchain_db = BurnchainDB::connect(":memory:", &burnchain, true).unwrap();
+        let first_header = burnchain_db.get_canonical_chain_tip().unwrap();
+        let mut headers = vec![first_header.clone()];
+
+        let pubkey = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new());
+        let watched: HashSet<Hash160> = wallet_pubkey_hashes(&pubkey).into_iter().collect();
+        let script = p2pkh_script(&Hash160::from_data(&pubkey.to_bytes()));
+
+        let funding_tx = make_tx(
+            vec![p2pkh_input(
+                &Txid([0x22; 32]),
+                0,
+                &Secp256k1PublicKey::new(),
+            )],
+            vec![(100_000, script.clone())],
+        );
+        let funding_txid = tx_txid(&funding_tx);
+        let header_1 = make_header(1, 1, &first_header);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_1,
+            &[funding_tx],
+            &watched,
+        );
+
+        // broadcast a commit that spends the funding output.  Its change can be chained.
+        let commit_tx = make_tx(
+            vec![p2pkh_input(&funding_txid, 0, &pubkey)],
+            vec![
+                (0, Builder::new().push_slice(b"id[").into_script()),
+                (90_000, script.clone()),
+            ],
+        );
+        let commit_txid = tx_txid(&commit_tx);
+        burnchain_db
+            .record_wallet_pending_tx(&commit_tx, &watched, 12)
+            .unwrap();
+
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, commit_txid);
+        assert_eq!(utxos[0].vout, 1);
+        assert_eq!(utxos[0].confirmations, 0);
+
+        // replace it by fee.  The replaced commit's change goes away.
+        let rbf_tx = make_tx(
+            vec![p2pkh_input(&funding_txid, 0, &pubkey)],
+            vec![
+                (0, Builder::new().push_slice(b"id[").into_script()),
+                (80_000, script.clone()),
+            ],
+        );
+        let rbf_txid = tx_txid(&rbf_tx);
+        burnchain_db
+            .record_wallet_pending_tx(&rbf_tx, &watched, 12)
+            .unwrap();
+
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, rbf_txid);
+        assert_eq!(utxos[0].amount, 80_000);
+        assert_eq!(utxos[0].confirmations, 0);
+
+        // once mined, the change is reported from the block
+        let header_2 = make_header(2, 2, &header_1);
+        store_block(
+            &mut burnchain_db,
+            &burnchain,
+            &mut headers,
+            &header_2,
+            &[rbf_tx],
+            &watched,
+        );
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 12)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, rbf_txid);
+        assert_eq!(utxos[0].confirmations, 1);
+
+        // a pending tx that never gets mined stops locking its inputs after its lifetime
+        let stuck_tx = make_tx(
+            vec![p2pkh_input(&rbf_txid, 1, &pubkey)],
+            vec![(70_000, script.clone())],
+        );
+        burnchain_db
+            .record_wallet_pending_tx(&stuck_tx, &watched, 1)
+            .unwrap();
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 1)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, tx_txid(&stuck_tx));
+
+        let mut parent = header_2;
+        for i in 3..5 {
+            let header = make_header(i, i as u8, &parent);
+            store_block(
+                &mut burnchain_db,
+                &burnchain,
+                &mut headers,
+                &header,
+                &[],
+                &watched,
+            );
+            parent = header;
+        }
+        let utxos = burnchain_db
+            .get_wallet_utxos(&script.to_bytes(), 1)
+            .unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, rbf_txid);
+        assert_eq!(utxos[0].confirmations, 3);
+    }
+}
@@ -24,6 +24,7 @@ use serde_json;
 use stacks_common::types::chainstate::BurnchainHeaderHash;
 
 use crate::burnchains::affirmation::*;
+use crate::burnchains::bitcoin::wallet::BitcoinWalletActivity;
 use crate::burnchains::{
     Burnchain, BurnchainBlock, BurnchainBlockHeader, Error as BurnchainError, Txid,
 };
@@ -1073,6 +1074,7 @@ impl BurnchainDB {
 
         if readwrite {
             db.add_indexes()?;
+            db.add_wallet_tables()?;
         }
         Ok(db)
     }
@@ -1088,6 +1090,7 @@ impl BurnchainDB {
 
         if readwrite {
             db.add_indexes()?;
+            db.add_wallet_tables()?;
         }
         Ok(db)
     }
@@ -1113,6 +1116,62 @@ impl BurnchainDB {
         BurnchainDB::inner_get_canonical_chain_tip(&self.conn)
     }
 
+    /// Drop every burnchain block above `height`, along with its ops, block-commit metadata and
+    /// wallet activity, and forget the anchor blocks of reward cycles whose prepare phases are
+    /// no longer complete.  The dropped blocks are downloaded and processed again on the next
+    /// sync.  Used to roll back a burnchain DB left inconsistent by a crash.
+    pub fn drop_blocks_above(
+        &mut self,
+        burnchain: &Burnchain,
+        height: u64,
+    ) -> Result<(), BurnchainError> {
+        // first reward cycle whose prepare phase ends above `height`
+        let mut first_incomplete_reward_cycle =
+            burnchain.block_height_to_reward_cycle(height).unwrap_or(0);
+        while burnchain.reward_cycle_to_block_height(first_incomplete_reward_cycle) <= height + 1 {
+            first_incomplete_reward_cycle += 1;
+        }
+
+        let db_tx = self.tx_begin()?;
+        let args: &[&dyn ToSql] = &[&u64_to_sql(height)?];
+        db_tx.conn().execute(
+            "DELETE FROM burnchain_db_block_ops WHERE block_hash IN (SELECT block_hash FROM burnchain_db_block_headers WHERE block_height > ?1)",
+            args,
+        )?;
+        db_tx.conn().execute(
+            "DELETE FROM block_commit_metadata WHERE block_height > ?1",
+            args,
+        )?;
+        db_tx
+            .conn()
+            .execute("DELETE FROM wallet_outputs WHERE block_height > ?1", args)?;
+        db_tx
+            .conn()
+            .execute("DELETE FROM wallet_spends WHERE block_height > ?1", args)?;
+        db_tx.conn().execute(
+            "DELETE FROM burnchain_db_block_headers WHERE block_height > ?1",
+            args,
+        )?;
+
+        let args: &[&dyn ToSql] = &[&u64_to_sql(first_incomplete_reward_cycle)?];
+        db_tx.conn().execute(
+            "UPDATE block_commit_metadata SET anchor_block = NULL WHERE anchor_block >= ?1",
+            args,
+        )?;
+        db_tx.conn().execute(
+            "UPDATE block_commit_metadata SET anchor_block_descendant = NULL WHERE anchor_block_descendant >= ?1",
+            args,
+        )?;
+        db_tx
+            .conn()
+            .execute("DELETE FROM anchor_blocks WHERE reward_cycle >= ?1", args)?;
+
+        info!("Dropped burnchain blocks";
+              "above_height" => height,
+              "first_incomplete_reward_cycle" => first_incomplete_reward_cycle);
+        db_tx.commit()
+    }
+
     pub fn has_burnchain_block_at_height(
         conn: &DBConn,
         height: u64,
@@ -1392,6 +1451,17 @@ impl BurnchainDB {
         indexer: &B,
         block_header: &BurnchainBlockHeader,
         blockstack_ops: &[BlockstackOperationType],
+    ) -> Result<(), BurnchainError> {
+        self.inner_store_new_burnchain_block(burnchain, indexer, block_header, blockstack_ops, None)
+    }
+
+    fn inner_store_new_burnchain_block<B: BurnchainHeaderReader>(
+        &mut self,
+        burnchain: &Burnchain,
+        indexer: &B,
+        block_header: &BurnchainBlockHeader,
+        blockstack_ops: &[BlockstackOperationType],
+        wallet_activity: Option<&BitcoinWalletActivity>,
     ) -> Result<(), BurnchainError> {
         let db_tx = self.tx_begin()?;
 
@@ -1403,6 +1473,9 @@ impl BurnchainDB {
         );
         db_tx.store_burnchain_db_entry(block_header)?;
         db_tx.store_blockstack_ops(burnchain, indexer, &block_header, blockstack_ops)?;
+        if let Some(wallet_activity) = wallet_activity {
+            db_tx.store_wallet_activity(block_header, wallet_activity)?;
+        }
 
         db_tx.commit()?;
         Ok(())
@@ -1422,7 +1495,16 @@ impl BurnchainDB {
             self.get_blockstack_transactions(burnchain, indexer, block, &header, epoch_id);
         apply_blockstack_txs_safety_checks(header.block_height, &mut blockstack_ops);
 
-        self.store_new_burnchain_block_ops_unchecked(burnchain, indexer, &header, &blockstack_ops)?;
+        let wallet_activity = match block {
+            BurnchainBlock::Bitcoin(ref data) => Some(&data.wallet_activity),
+        };
+        self.inner_store_new_burnchain_block(
+            burnchain,
+            indexer,
+            &header,
+            &blockstack_ops,
+            wallet_activity,
+        )?;
         Ok(blockstack_ops)
     }
 
@@ -1617,6 +1699,22 @@ impl BurnchainDB {
         Ok(am_opt)
     }
 
+    /// Load all overridden affirmation maps, ordered by reward cycle.
+    pub fn get_override_affirmation_maps(
+        conn: &DBConn,
+    ) -> Result<Vec<(u64, AffirmationMap)>, DBError> {
+        let mut stmt = conn
+            .prepare("SELECT reward_cycle, affirmation_map FROM overrides ORDER BY reward_cycle")?;
+        let mut rows = stmt.query(NO_PARAMS)?;
+        let mut overrides = vec![];
+        while let Some(row) = rows.next()? {
+            let reward_cycle = u64::from_column(row, "reward_cycle")?;
+            let am = AffirmationMap::from_column(row, "affirmation_map")?;
+            overrides.push((reward_cycle, am));
+        }
+        Ok(overrides)
+    }
+
     /// Get the canonical affirmation map.  This is the heaviest anchor block affirmation map, but
     /// accounting for any subsequent reward cycles whose anchor blocks either aren't on the
     /// heaviest anchor block affirmation map, or which have no anchor blocks.
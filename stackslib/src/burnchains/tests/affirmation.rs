@@ -2938,3 +2938,75 @@ fn test_update_pox_affirmation_fork_duel() {
     assert_eq!(heaviest_am, AffirmationMap::decode("apa").unwrap());
     assert_eq!(canonical_am, AffirmationMap::decode("apap").unwrap());
 }
+
+#[test]
+fn test_check_affirmation_override() {
+    let first_bhh = BurnchainHeaderHash([0; 32]);
+    let first_timestamp = 0;
+    let first_height = 0;
+
+    let mut burnchain = Burnchain::regtest(":memory:");
+    burnchain.pox_constants = make_test_pox(10, 5, 3, 3);
+    burnchain.first_block_height = first_height;
+    burnchain.first_block_hash = first_bhh.clone();
+    burnchain.first_block_timestamp = first_timestamp;
+
+    let mut burnchain_db = BurnchainDB::connect(":memory:", &burnchain, true).unwrap();
+
+    let first_block_header = burnchain_db.get_canonical_chain_tip().unwrap();
+
+    let mut headers = vec![first_block_header.clone()];
+    let key_register = make_simple_key_register(&first_block_header.block_hash, 0, 1);
+
+    // reward cycle 1 has an anchor block, and reward cycle 2 does not
+    let (next_headers, commits_0) = make_simple_reward_cycle(
+        &mut burnchain_db,
+        &burnchain,
+        &key_register,
+        &mut headers,
+        None,
+    );
+    update_pox_affirmation_maps(&mut burnchain_db, &headers, 0, &burnchain).unwrap();
+
+    let (next_headers, commits_1) = make_reward_cycle_without_anchor(
+        &mut burnchain_db,
+        &burnchain,
+        &key_register,
+        &mut headers,
+        vec![commits_0.last().unwrap().clone()],
+    );
+    update_pox_affirmation_maps(&mut burnchain_db, &headers, 1, &burnchain).unwrap();
+
+    assert!(BurnchainDB::has_anchor_block(burnchain_db.conn(), 1).unwrap());
+    assert!(!BurnchainDB::has_anchor_block(burnchain_db.conn(), 2).unwrap());
+
+    // matches what was elected
+    let warnings =
+        check_affirmation_override(&burnchain_db, 3, &AffirmationMap::decode("pn").unwrap())
+            .unwrap();
+    assert!(warnings.is_empty());
+
+    // allowed, but suspicious
+    let warnings =
+        check_affirmation_override(&burnchain_db, 3, &AffirmationMap::decode("aa").unwrap())
+            .unwrap();
+    assert_eq!(warnings.len(), 1);
+
+    let warnings =
+        check_affirmation_override(&burnchain_db, 3, &AffirmationMap::decode("nn").unwrap())
+            .unwrap();
+    assert_eq!(warnings.len(), 1);
+
+    // can't affirm an anchor block that was never elected
+    assert!(
+        check_affirmation_override(&burnchain_db, 3, &AffirmationMap::decode("pp").unwrap())
+            .is_err()
+    );
+
+    // wrong length
+    assert!(
+        check_affirmation_override(&burnchain_db, 3, &AffirmationMap::decode("p").unwrap())
+            .is_err()
+    );
+    assert!(check_affirmation_override(&burnchain_db, 0, &AffirmationMap::empty()).is_err());
+}
@@ -772,6 +772,109 @@ fn test_get_set_check_anchor_block() {
     .unwrap());
 }
 
+#[test]
+fn test_drop_blocks_above() {
+    let first_bhh = BurnchainHeaderHash::from_hex(BITCOIN_REGTEST_FIRST_BLOCK_HASH).unwrap();
+    let first_timestamp = 0;
+    let first_height = 1;
+
+    let mut burnchain = Burnchain::regtest(":memory:");
+    burnchain.pox_constants = burn_db_test_pox();
+    burnchain.first_block_height = first_height;
+    burnchain.first_block_hash = first_bhh.clone();
+    burnchain.first_block_timestamp = first_timestamp;
+
+    let mut burnchain_db = BurnchainDB::connect(":memory:", &burnchain, true).unwrap();
+
+    let first_block_header = burnchain_db.get_canonical_chain_tip().unwrap();
+
+    let mut headers = vec![first_block_header.clone()];
+    let mut parent = None;
+    let mut parent_block_header: Option<BurnchainBlockHeader> = None;
+    let mut cmts = vec![];
+
+    for i in 0..5 {
+        let hdr = BurnchainHeaderHash([(i + 1) as u8; 32]);
+        let block_header = BurnchainBlockHeader {
+            block_height: (first_height + i) as u64,
+            block_hash: hdr,
+            parent_block_hash: parent_block_header
+                .as_ref()
+                .map(|blk| blk.block_hash.clone())
+                .unwrap_or(first_block_header.block_hash.clone()),
+            num_txs: 1,
+            timestamp: i as u64,
+        };
+
+        headers.push(block_header.clone());
+        parent_block_header = Some(block_header);
+    }
+
+    for i in 0..5 {
+        let block_header = &headers[i + 1];
+
+        let cmt = make_simple_block_commit(
+            &burnchain,
+            parent.as_ref(),
+            block_header,
+            BlockHeaderHash([((i + 1) as u8) | 0x80; 32]),
+        );
+        burnchain_db
+            .store_new_burnchain_block_ops_unchecked(
+                &burnchain,
+                &headers,
+                block_header,
+                &vec![BlockstackOperationType::LeaderBlockCommit(cmt.clone())],
+            )
+            .unwrap();
+
+        cmts.push(cmt.clone());
+        parent = Some(cmt);
+    }
+
+    // reward cycle 1's prepare phase ends at height 6
+    {
+        let tx = burnchain_db.tx_begin().unwrap();
+        tx.set_anchor_block(&cmts[1], 1).unwrap();
+        tx.commit().unwrap();
+    }
+    assert!(BurnchainDB::has_anchor_block(burnchain_db.conn(), 1).unwrap());
+
+    burnchain_db.drop_blocks_above(&burnchain, 3).unwrap();
+
+    let tip = burnchain_db.get_canonical_chain_tip().unwrap();
+    assert_eq!(tip.block_height, 3);
+    assert_eq!(tip.block_hash, headers[3].block_hash);
+
+    // blocks above the new tip are gone, along with their block-commits
+    for header in headers[4..].iter() {
+        assert!(BurnchainDB::get_burnchain_block(burnchain_db.conn(), &header.block_hash).is_err());
+    }
+    for cmt in cmts[3..].iter() {
+        assert!(BurnchainDB::get_commit_metadata(
+            burnchain_db.conn(),
+            &cmt.burn_header_hash,
+            &cmt.txid
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    // blocks below it are kept, but reward cycle 1 no longer has an anchor block
+    let block =
+        BurnchainDB::get_burnchain_block(burnchain_db.conn(), &headers[2].block_hash).unwrap();
+    assert_eq!(block.ops.len(), 1);
+    let metadata = BurnchainDB::get_commit_metadata(
+        burnchain_db.conn(),
+        &cmts[1].burn_header_hash,
+        &cmts[1].txid,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(metadata.anchor_block, None);
+    assert!(!BurnchainDB::has_anchor_block(burnchain_db.conn(), 1).unwrap());
+}
+
 #[test]
 fn test_update_block_descendancy() {
     let first_bhh = BurnchainHeaderHash::from_hex(BITCOIN_REGTEST_FIRST_BLOCK_HASH).unwrap();
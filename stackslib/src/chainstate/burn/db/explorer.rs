@@ -0,0 +1,231 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Explains what the sortition DB made of each burnchain operation in a burnchain block: which
+//! ops were accepted, which commit won the sortition, and why the others were rejected.
+//!
+//! The burnchain DB keeps every op the indexer parsed, but the sortition DB only keeps the ones
+//! it accepted.  To recover the reason an op was rejected, its `check()` is re-run against the
+//! sortition of the block's parent, in a transaction that is never committed.
+
+use std::collections::HashSet;
+
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, SortitionId, VRFSeed,
+};
+
+use crate::burnchains::db::BurnchainDB;
+use crate::burnchains::{Burnchain, Error as BurnchainError, Txid};
+use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionHandleTx};
+use crate::chainstate::burn::operations::BlockstackOperationType;
+use crate::chainstate::burn::ConsensusHash;
+
+/// One burnchain op in a burnchain block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurnOpReport {
+    pub txid: Txid,
+    pub vtxindex: u32,
+    /// e.g. `leader_block_commit` or `stack_stx`
+    pub op_type: String,
+    /// The op's sender, if it has one
+    pub sender: Option<String>,
+    /// BTC burnt by a block-commit
+    pub burn_fee: Option<u64>,
+    pub accepted: bool,
+    /// Whether this is the block-commit that won the sortition
+    pub won_sortition: bool,
+    /// Why the op was rejected, if it was
+    pub rejected_reason: Option<String>,
+    pub op: BlockstackOperationType,
+}
+
+/// The ops in a burnchain block, and the sortition they produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurnBlockReport {
+    pub block_height: u64,
+    pub burn_header_hash: BurnchainHeaderHash,
+    pub consensus_hash: ConsensusHash,
+    /// Whether a block-commit won this sortition
+    pub sortition: bool,
+    pub winning_block_txid: Option<Txid>,
+    pub winning_stacks_block_hash: Option<BlockHeaderHash>,
+    /// The VRF seed committed to by the winning block-commit
+    pub winning_vrf_seed: Option<VRFSeed>,
+    /// Total BTC burnt by this block's accepted block-commits
+    pub block_burn: u64,
+    /// Ops in `vtxindex` order
+    pub ops: Vec<BurnOpReport>,
+}
+
+fn op_type_name(op: &BlockstackOperationType) -> &'static str {
+    match op {
+        BlockstackOperationType::LeaderKeyRegister(_) => "leader_key_register",
+        BlockstackOperationType::LeaderBlockCommit(_) => "leader_block_commit",
+        BlockstackOperationType::PreStx(_) => "pre_stx",
+        BlockstackOperationType::StackStx(_) => "stack_stx",
+        BlockstackOperationType::TransferStx(_) => "transfer_stx",
+        BlockstackOperationType::DelegateStx(_) => "delegate_stx",
+        BlockstackOperationType::VoteForAggregateKey(_) => "vote_for_aggregate_key",
+    }
+}
+
+fn op_sender(op: &BlockstackOperationType) -> Option<String> {
+    match op {
+        BlockstackOperationType::LeaderKeyRegister(_) => None,
+        BlockstackOperationType::LeaderBlockCommit(op) => Some(op.apparent_sender.to_string()),
+        BlockstackOperationType::PreStx(op) => Some(op.output.to_string()),
+        BlockstackOperationType::StackStx(op) => Some(op.sender.to_string()),
+        BlockstackOperationType::TransferStx(op) => Some(op.sender.to_string()),
+        BlockstackOperationType::DelegateStx(op) => Some(op.sender.to_string()),
+        BlockstackOperationType::VoteForAggregateKey(op) => Some(op.sender.to_string()),
+    }
+}
+
+/// Explain the ops in the burnchain block at `block_height` on the sortition fork that ends at
+/// `sortition_tip`.  Returns `None` if that fork has no sortition at this height.
+///
+/// `sortdb` must be open read-write, since re-checking ops needs a (never-committed)
+/// transaction.
+pub fn explain_burn_block(
+    sortdb: &mut SortitionDB,
+    burnchain_db: &BurnchainDB,
+    burnchain: &Burnchain,
+    sortition_tip: &SortitionId,
+    block_height: u64,
+) -> Result<Option<BurnBlockReport>, BurnchainError> {
+    if block_height <= burnchain.first_block_height {
+        return Ok(None);
+    }
+    let Some(snapshot) =
+        SortitionDB::get_ancestor_snapshot(&sortdb.index_conn(), block_height, sortition_tip)?
+    else {
+        return Ok(None);
+    };
+    let parent_snapshot =
+        SortitionDB::get_ancestor_snapshot(&sortdb.index_conn(), block_height - 1, sortition_tip)?
+            .ok_or(BurnchainError::MissingParentBlock)?;
+
+    let (_, transition_ops) = sortdb
+        .get_sortition_result(&snapshot.sortition_id)?
+        .ok_or(BurnchainError::MissingParentBlock)?;
+    let accepted_txids: HashSet<_> = transition_ops
+        .accepted_ops
+        .iter()
+        .map(|op| op.txid())
+        .collect();
+
+    let mut ops =
+        BurnchainDB::get_burnchain_block(burnchain_db.conn(), &snapshot.burn_header_hash)?.ops;
+    ops.sort_by_key(|op| op.vtxindex());
+
+    // the first block of a reward cycle picks its PoX recipients from the reward set the
+    // coordinator computed for it; all other blocks pick them from the sortition DB.
+    let next_pox_info = if burnchain.is_reward_cycle_start(block_height) {
+        sortdb.get_preprocessed_reward_set_of(&snapshot.sortition_id)?
+    } else {
+        None
+    };
+    let reward_set_info =
+        sortdb.get_next_block_recipients(burnchain, &parent_snapshot, next_pox_info.as_ref())?;
+
+    let check_results = {
+        let mut sort_tx = SortitionHandleTx::begin(sortdb, &parent_snapshot.sortition_id)?;
+        sort_tx.check_block_ops(burnchain, &ops, reward_set_info.as_ref())
+    };
+
+    let winning_commit = transition_ops.accepted_ops.iter().find_map(|op| match op {
+        BlockstackOperationType::LeaderBlockCommit(commit)
+            if snapshot.sortition && commit.txid == snapshot.winning_block_txid =>
+        {
+            Some(commit.clone())
+        }
+        _ => None,
+    });
+
+    let mut block_burn = 0u64;
+    let mut op_reports = vec![];
+    for (op, check_result) in ops.into_iter().zip(check_results.into_iter()) {
+        let txid = op.txid();
+        let accepted = accepted_txids.contains(&txid);
+        let burn_fee = match &op {
+            BlockstackOperationType::LeaderBlockCommit(commit) => Some(commit.burn_fee),
+            _ => None,
+        };
+        if accepted {
+            block_burn = block_burn.saturating_add(burn_fee.unwrap_or(0));
+        }
+        let rejected_reason = match (accepted, check_result) {
+            (true, _) => None,
+            (false, Err(e)) => Some(e.to_string()),
+            (false, Ok(())) => match &op {
+                BlockstackOperationType::LeaderKeyRegister(_) => {
+                    Some("Another op in this block registered the same VRF key".to_string())
+                }
+                _ => Some("Passed its checks, but was not accepted by the sortition".to_string()),
+            },
+        };
+        op_reports.push(BurnOpReport {
+            won_sortition: winning_commit
+                .as_ref()
+                .map(|commit| commit.txid == txid)
+                .unwrap_or(false),
+            txid,
+            vtxindex: op.vtxindex(),
+            op_type: op_type_name(&op).to_string(),
+            sender: op_sender(&op),
+            burn_fee,
+            accepted,
+            rejected_reason,
+            op,
+        });
+    }
+
+    Ok(Some(BurnBlockReport {
+        block_height,
+        burn_header_hash: snapshot.burn_header_hash,
+        consensus_hash: snapshot.consensus_hash,
+        sortition: snapshot.sortition,
+        winning_block_txid: winning_commit.as_ref().map(|commit| commit.txid.clone()),
+        winning_stacks_block_hash: winning_commit
+            .as_ref()
+            .map(|commit| commit.block_header_hash.clone()),
+        winning_vrf_seed: winning_commit
+            .as_ref()
+            .map(|commit| commit.new_seed.clone()),
+        block_burn,
+        ops: op_reports,
+    }))
+}
+
+/// Explain the ops in each burnchain block in `[start_height, end_height)` on the canonical
+/// sortition fork.  Heights past the canonical tip are skipped.
+pub fn explain_burn_blocks(
+    sortdb: &mut SortitionDB,
+    burnchain_db: &BurnchainDB,
+    burnchain: &Burnchain,
+    start_height: u64,
+    end_height: u64,
+) -> Result<Vec<BurnBlockReport>, BurnchainError> {
+    let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+    let mut reports = vec![];
+    for height in start_height..end_height.min(tip.block_height + 1) {
+        if let Some(report) =
+            explain_burn_block(sortdb, burnchain_db, burnchain, &tip.sortition_id, height)?
+        {
+            reports.push(report);
+        }
+    }
+    Ok(reports)
+}
@@ -34,8 +34,10 @@ use crate::chainstate::stacks::StacksPublicKey;
 use crate::util_lib::db;
 use crate::util_lib::db::{Error as db_error, FromColumn};
 
+pub mod explorer;
 pub mod processing;
 pub mod sortdb;
+pub mod verify;
 
 pub type DBConn = Connection;
 
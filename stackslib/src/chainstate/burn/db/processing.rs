@@ -103,6 +103,21 @@ impl<'a> SortitionHandleTx<'a> {
         }
     }
 
+    /// Run `check()` on each of a burnchain block's ops against this handle's chain tip, which
+    /// must be the sortition of the block's parent.  Nothing is processed or stored.  Returns the
+    /// result of each op's check, in the order given.
+    pub fn check_block_ops(
+        &mut self,
+        burnchain: &Burnchain,
+        blockstack_txs: &[BlockstackOperationType],
+        reward_set_info: Option<&RewardSetInfo>,
+    ) -> Vec<Result<(), BurnchainError>> {
+        blockstack_txs
+            .iter()
+            .map(|blockstack_op| self.check_transaction(burnchain, blockstack_op, reward_set_info))
+            .collect()
+    }
+
     /// Process all block's checked transactions
     /// * make the burn distribution
     /// * insert the ones that went into the burn distribution
@@ -458,4 +473,87 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_check_block_ops() {
+        let first_burn_hash = BurnchainHeaderHash([0; 32]);
+
+        let leader_key = LeaderKeyRegisterOp {
+            consensus_hash: ConsensusHash([0x22; 20]),
+            public_key: VRFPublicKey::from_hex(
+                "a366b51292bef4edd64063d9145c617fec373bceb0758e98cd72becd84d54c7a",
+            )
+            .unwrap(),
+            memo: vec![01, 02, 03, 04, 05],
+
+            txid: Txid::from_bytes_be(
+                &hex_bytes("1bfa831b5fc56c858198acb8e77e5863c1e9d8ac26d49ddb914e24d8d4083562")
+                    .unwrap(),
+            )
+            .unwrap(),
+            vtxindex: 400,
+            block_height: 101,
+            burn_header_hash: BurnchainHeaderHash([0x01; 32]),
+        };
+
+        let block_commit = LeaderBlockCommitOp {
+            sunset_burn: 0,
+            block_header_hash: BlockHeaderHash([0x22; 32]),
+            new_seed: VRFSeed::from_hex(
+                "3333333333333333333333333333333333333333333333333333333333333333",
+            )
+            .unwrap(),
+            parent_block_ptr: 0,
+            parent_vtxindex: 0,
+            key_block_ptr: 101,
+            key_vtxindex: 400,
+            memo: vec![0x80],
+            apparent_sender: BurnchainSigner("hello-world".to_string()),
+
+            commit_outs: vec![],
+            burn_fee: 12345,
+            input: (Txid([0; 32]), 0),
+
+            txid: Txid::from_bytes_be(
+                &hex_bytes("3c07a0a93360bc85047bbaadd49e30c8af770f73a37e10fec400174d2e5f27cf")
+                    .unwrap(),
+            )
+            .unwrap(),
+            vtxindex: 400,
+            block_height: 102,
+            burn_parent_modulus: (101 % BURN_BLOCK_MINED_AT_MODULUS) as u8,
+            burn_header_hash: BurnchainHeaderHash([0x03; 32]),
+        };
+
+        // same commit, but for a key that was never registered
+        let mut bad_block_commit = block_commit.clone();
+        bad_block_commit.key_vtxindex = 401;
+        bad_block_commit.vtxindex = 401;
+        bad_block_commit.txid = Txid([0x44; 32]);
+
+        let burnchain = Burnchain::default_unittest(100, &first_burn_hash);
+        let mut db = SortitionDB::connect_test(100, &first_burn_hash).unwrap();
+
+        let snapshot = test_append_snapshot(
+            &mut db,
+            BurnchainHeaderHash([0x01; 32]),
+            &vec![BlockstackOperationType::LeaderKeyRegister(leader_key)],
+        );
+
+        let mut ic = SortitionHandleTx::begin(&mut db, &snapshot.sortition_id).unwrap();
+        let results = ic.check_block_ops(
+            &burnchain,
+            &[
+                BlockstackOperationType::LeaderBlockCommit(block_commit),
+                BlockstackOperationType::LeaderBlockCommit(bad_block_commit),
+            ],
+            None,
+        );
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(BurnchainError::OpError(OpError::BlockCommitNoLeaderKey)) => {}
+            x => panic!("Expected BlockCommitNoLeaderKey, got {:?}", x),
+        }
+    }
 }
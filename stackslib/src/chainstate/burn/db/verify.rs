@@ -0,0 +1,443 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks that the sortition DB, the burnchain DB and the SPV headers agree with one another.
+//!
+//! A node that crashes part-way through writing a burnchain block can leave these databases
+//! disagreeing.  Each snapshot on the canonical sortition fork is checked against the SPV header
+//! and burnchain DB block at its height, and is then re-derived from the burnchain DB's ops by
+//! re-evaluating its sortition in dry-run mode, so nothing is written.  Every field that differs
+//! is reported.
+
+use std::fmt::Display;
+
+use stacks_common::types::chainstate::SortitionId;
+
+use crate::burnchains::db::{BurnchainDB, BurnchainHeaderReader};
+use crate::burnchains::indexer::BurnchainIndexer;
+use crate::burnchains::{Burnchain, Error as BurnchainError, Txid};
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::burn::BlockSnapshot;
+
+/// One disagreement found at a burnchain block height
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurnchainMismatch {
+    pub block_height: u64,
+    /// What disagreed, e.g. `consensus_hash`, `pox_id` or `spv_header.block_hash`
+    pub field: String,
+    /// What the sortition DB (or the burnchain DB) has stored
+    pub stored: String,
+    /// What it should be, according to the SPV headers or the re-derived snapshot
+    pub expected: String,
+}
+
+impl BurnchainMismatch {
+    fn new<S: Display, E: Display>(block_height: u64, field: &str, stored: S, expected: E) -> Self {
+        Self {
+            block_height,
+            field: field.to_string(),
+            stored: stored.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+}
+
+/// The result of checking a range of burnchain blocks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BurnchainVerifyReport {
+    pub start_height: u64,
+    /// The last height that was checked
+    pub end_height: u64,
+    /// The highest height below which every checked block is consistent
+    pub last_consistent_height: u64,
+    pub mismatches: Vec<BurnchainMismatch>,
+}
+
+/// Compare a stored value with the expected value, and record a mismatch if they differ
+fn check_field<T: PartialEq + Display>(
+    mismatches: &mut Vec<BurnchainMismatch>,
+    block_height: u64,
+    field: &str,
+    stored: &T,
+    expected: &T,
+) {
+    if stored != expected {
+        mismatches.push(BurnchainMismatch::new(
+            block_height,
+            field,
+            stored,
+            expected,
+        ));
+    }
+}
+
+/// Check the snapshot at `block_height` on the sortition fork that ends at `sortition_tip`
+/// against the SPV headers and the burnchain DB, and against the snapshot re-derived from the
+/// burnchain DB's ops.  Returns the mismatches found.
+///
+/// `sortdb` must be open read-write and in dry-run mode, since re-evaluating the sortition
+/// needs a (never-committed) transaction.
+pub fn verify_burn_block<B: BurnchainHeaderReader>(
+    sortdb: &mut SortitionDB,
+    burnchain_db: &BurnchainDB,
+    burnchain: &Burnchain,
+    headers: &B,
+    sortition_tip: &SortitionId,
+    block_height: u64,
+) -> Result<Vec<BurnchainMismatch>, BurnchainError> {
+    assert!(sortdb.dryrun, "BUG: sortition DB must be in dry-run mode");
+
+    let mut mismatches = vec![];
+    if block_height <= burnchain.first_block_height {
+        return Ok(mismatches);
+    }
+
+    let Some(snapshot) =
+        SortitionDB::get_ancestor_snapshot(&sortdb.index_conn(), block_height, sortition_tip)?
+    else {
+        mismatches.push(BurnchainMismatch::new(
+            block_height,
+            "snapshot",
+            "(none)",
+            "a snapshot",
+        ));
+        return Ok(mismatches);
+    };
+    let Some(parent_snapshot) =
+        SortitionDB::get_ancestor_snapshot(&sortdb.index_conn(), block_height - 1, sortition_tip)?
+    else {
+        mismatches.push(BurnchainMismatch::new(
+            block_height,
+            "parent_snapshot",
+            "(none)",
+            "a snapshot",
+        ));
+        return Ok(mismatches);
+    };
+    check_field(
+        &mut mismatches,
+        block_height,
+        "parent_burn_header_hash",
+        &snapshot.parent_burn_header_hash,
+        &parent_snapshot.burn_header_hash,
+    );
+
+    // the SPV headers are the source of truth for the burnchain's block hashes
+    match headers.read_burnchain_header(block_height)? {
+        Some(header) => {
+            check_field(
+                &mut mismatches,
+                block_height,
+                "spv_header.block_hash",
+                &snapshot.burn_header_hash,
+                &header.block_hash,
+            );
+            check_field(
+                &mut mismatches,
+                block_height,
+                "spv_header.parent_block_hash",
+                &snapshot.parent_burn_header_hash,
+                &header.parent_block_hash,
+            );
+            check_field(
+                &mut mismatches,
+                block_height,
+                "spv_header.timestamp",
+                &snapshot.burn_header_timestamp,
+                &header.timestamp,
+            );
+        }
+        None => {
+            mismatches.push(BurnchainMismatch::new(
+                block_height,
+                "spv_header",
+                &snapshot.burn_header_hash,
+                "(none)",
+            ));
+        }
+    }
+
+    let block =
+        match BurnchainDB::get_burnchain_block(burnchain_db.conn(), &snapshot.burn_header_hash) {
+            Ok(block) => block,
+            Err(BurnchainError::UnknownBlock(_)) => {
+                mismatches.push(BurnchainMismatch::new(
+                    block_height,
+                    "burnchain_db_block",
+                    "(none)",
+                    &snapshot.burn_header_hash,
+                ));
+                return Ok(mismatches);
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        };
+    check_field(
+        &mut mismatches,
+        block_height,
+        "burnchain_db_block.block_height",
+        &block.header.block_height,
+        &snapshot.block_height,
+    );
+    check_field(
+        &mut mismatches,
+        block_height,
+        "burnchain_db_block.parent_block_hash",
+        &block.header.parent_block_hash,
+        &snapshot.parent_burn_header_hash,
+    );
+    check_field(
+        &mut mismatches,
+        block_height,
+        "burnchain_db_block.timestamp",
+        &block.header.timestamp,
+        &snapshot.burn_header_timestamp,
+    );
+    if block.header.block_height != block_height
+        || block.header.parent_block_hash != parent_snapshot.burn_header_hash
+    {
+        // can't re-derive a snapshot from a block that doesn't extend its parent
+        return Ok(mismatches);
+    }
+
+    // re-derive the snapshot from the burnchain DB's ops.  The first block of a reward cycle
+    // extends the PoX ID with the reward cycle info the coordinator stored for it.
+    let next_pox_info = if burnchain.is_reward_cycle_start(block_height) {
+        let next_pox_info = sortdb.get_preprocessed_reward_set_of(&snapshot.sortition_id)?;
+        if next_pox_info.is_none() {
+            mismatches.push(BurnchainMismatch::new(
+                block_height,
+                "reward_cycle_info",
+                "(none)",
+                "a preprocessed reward set",
+            ));
+        }
+        next_pox_info
+    } else {
+        None
+    };
+    let parent_pox = sortdb.get_pox_id(&parent_snapshot.sortition_id)?;
+    let expected_pox = SortitionDB::make_next_pox_id(parent_pox, next_pox_info.as_ref());
+    let stored_pox = sortdb.get_pox_id(&snapshot.sortition_id)?;
+    check_field(
+        &mut mismatches,
+        block_height,
+        "pox_id",
+        &stored_pox,
+        &expected_pox,
+    );
+
+    let (expected, state_transition) = sortdb.evaluate_sortition(
+        &block.header,
+        block.ops,
+        burnchain,
+        sortition_tip,
+        next_pox_info,
+        |_| {},
+    )?;
+    check_snapshot_fields(&mut mismatches, &snapshot, &expected);
+
+    let mut expected_txids: Vec<_> = state_transition
+        .accepted_ops
+        .iter()
+        .map(|op| op.txid())
+        .collect();
+    expected_txids.sort();
+    let mut stored_txids: Vec<_> = sortdb
+        .get_sortition_result(&snapshot.sortition_id)?
+        .map(|(_, ops)| ops.accepted_ops.iter().map(|op| op.txid()).collect())
+        .unwrap_or_default();
+    stored_txids.sort();
+    if stored_txids != expected_txids {
+        mismatches.push(BurnchainMismatch::new(
+            block_height,
+            "accepted_ops",
+            join_txids(&stored_txids),
+            join_txids(&expected_txids),
+        ));
+    }
+
+    Ok(mismatches)
+}
+
+fn join_txids(txids: &[Txid]) -> String {
+    txids
+        .iter()
+        .map(|txid| txid.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Compare the fields of a stored snapshot that are derived from its burnchain block's ops with
+/// those of the re-derived snapshot.  The index root is not compared, since a dry run never
+/// computes it.
+fn check_snapshot_fields(
+    mismatches: &mut Vec<BurnchainMismatch>,
+    stored: &BlockSnapshot,
+    expected: &BlockSnapshot,
+) {
+    let height = stored.block_height;
+    check_field(
+        mismatches,
+        height,
+        "sortition_id",
+        &stored.sortition_id,
+        &expected.sortition_id,
+    );
+    check_field(
+        mismatches,
+        height,
+        "parent_sortition_id",
+        &stored.parent_sortition_id,
+        &expected.parent_sortition_id,
+    );
+    check_field(
+        mismatches,
+        height,
+        "consensus_hash",
+        &stored.consensus_hash,
+        &expected.consensus_hash,
+    );
+    check_field(
+        mismatches,
+        height,
+        "ops_hash",
+        &stored.ops_hash,
+        &expected.ops_hash,
+    );
+    check_field(
+        mismatches,
+        height,
+        "total_burn",
+        &stored.total_burn,
+        &expected.total_burn,
+    );
+    check_field(
+        mismatches,
+        height,
+        "sortition",
+        &stored.sortition,
+        &expected.sortition,
+    );
+    check_field(
+        mismatches,
+        height,
+        "sortition_hash",
+        &stored.sortition_hash,
+        &expected.sortition_hash,
+    );
+    check_field(
+        mismatches,
+        height,
+        "winning_block_txid",
+        &stored.winning_block_txid,
+        &expected.winning_block_txid,
+    );
+    check_field(
+        mismatches,
+        height,
+        "winning_stacks_block_hash",
+        &stored.winning_stacks_block_hash,
+        &expected.winning_stacks_block_hash,
+    );
+    check_field(
+        mismatches,
+        height,
+        "num_sortitions",
+        &stored.num_sortitions,
+        &expected.num_sortitions,
+    );
+    check_field(
+        mismatches,
+        height,
+        "accumulated_coinbase_ustx",
+        &stored.accumulated_coinbase_ustx,
+        &expected.accumulated_coinbase_ustx,
+    );
+}
+
+/// Check each burnchain block in `[start_height, end_height)` on the canonical sortition fork.
+/// Heights past the canonical tip are skipped.
+pub fn verify_burnchain<B: BurnchainHeaderReader>(
+    sortdb: &mut SortitionDB,
+    burnchain_db: &BurnchainDB,
+    burnchain: &Burnchain,
+    headers: &B,
+    start_height: u64,
+    end_height: u64,
+) -> Result<BurnchainVerifyReport, BurnchainError> {
+    let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+    let start_height = start_height.max(burnchain.first_block_height + 1);
+    let end_height = end_height.min(tip.block_height + 1);
+
+    let mut mismatches = vec![];
+    let mut last_consistent_height = start_height.saturating_sub(1);
+    for height in start_height..end_height {
+        let block_mismatches = verify_burn_block(
+            sortdb,
+            burnchain_db,
+            burnchain,
+            headers,
+            &tip.sortition_id,
+            height,
+        )?;
+        if block_mismatches.is_empty() && mismatches.is_empty() {
+            last_consistent_height = height;
+        }
+        if !block_mismatches.is_empty() {
+            warn!("Burnchain block is inconsistent";
+                  "block_height" => height,
+                  "mismatches" => block_mismatches.len());
+        }
+        mismatches.extend(block_mismatches);
+    }
+
+    Ok(BurnchainVerifyReport {
+        start_height,
+        end_height: end_height.saturating_sub(1),
+        last_consistent_height,
+        mismatches,
+    })
+}
+
+/// Roll the node's burnchain state back to `height`: drop the SPV headers and burnchain DB
+/// blocks above it, and invalidate the sortitions above it so the canonical sortition tip falls
+/// back to it.  The node re-downloads and re-processes the dropped blocks when it restarts.
+///
+/// Sortitions above `height` are invalidated, not deleted.  If the stored snapshots themselves
+/// are wrong, the sortition DB must be rebuilt, since re-processing a burnchain block revalidates
+/// an existing snapshot with the same sortition ID instead of re-deriving it.
+pub fn truncate_burnchain<I: BurnchainIndexer>(
+    sortdb: &mut SortitionDB,
+    burnchain_db: &mut BurnchainDB,
+    burnchain: &Burnchain,
+    indexer: &mut I,
+    height: u64,
+) -> Result<(), BurnchainError> {
+    let tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())?;
+    let last_consistent =
+        SortitionDB::get_ancestor_snapshot(&sortdb.index_conn(), height, &tip.sortition_id)?
+            .ok_or(BurnchainError::MissingParentBlock)?;
+
+    info!("Truncating burnchain state";
+          "block_height" => height,
+          "burn_header_hash" => %last_consistent.burn_header_hash);
+
+    sortdb.invalidate_descendants_of(&last_consistent.burn_header_hash)?;
+    burnchain_db.drop_blocks_above(burnchain, height)?;
+    indexer.drop_headers(height)?;
+    Ok(())
+}
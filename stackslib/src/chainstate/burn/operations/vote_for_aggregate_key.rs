@@ -110,6 +110,11 @@ impl VoteForAggregateKeyOp {
         match tx {
             BurnchainTransaction::Bitcoin(ref btc) => match btc.inputs.get(0) {
                 Some(BitcoinTxInput::Raw(input)) => {
+                    if input.is_p2tr_key_spend() {
+                        // the signer's key is not recoverable from a Schnorr signature
+                        warn!("Invalid tx: VoteForAggregateKey sender cannot be a Taproot key-path spend");
+                        return Err(op_error::InvalidInput);
+                    }
                     let script_sig = Builder::from(input.scriptSig.clone()).into_script();
                     let structured_input = BitcoinTxInputStructured::from_bitcoin_p2pkh_script_sig(
                         &parse_script(&script_sig),
@@ -0,0 +1,319 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Optional index of the transactions that touched each principal.
+//!
+//! When enabled (see `StacksChainState::principal_tx_index`), every processed block's
+//! transaction receipts are scanned for the principals they involve -- the sender, sponsor,
+//! token-transfer recipient, called or instantiated contract, coinbase recipient, the principals
+//! in burnchain-originated operations, and the participants in every asset event.  Each
+//! (principal, txid) pair is stored along with the index block hash of the block that included
+//! it, so the index remains correct across forks: queries only return rows whose block is an
+//! ancestor of the requested tip.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use clarity::vm::events::{FTEventType, NFTEventType, STXEventType, StacksTransactionEvent};
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData};
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::operations::BlockstackOperationType;
+use crate::chainstate::stacks::db::{ChainstateTx, StacksChainState, StacksHeaderInfo};
+use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
+use crate::chainstate::stacks::{Error, TransactionPayload};
+use crate::util_lib::db::{query_rows, u64_to_sql, Error as db_error, FromColumn, FromRow};
+
+/// Number of index rows to load per query when filtering them down to a single fork
+const PRINCIPAL_TX_BATCH_SIZE: u32 = 256;
+
+/// A transaction that touched a principal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrincipalTransaction {
+    pub txid: Txid,
+    pub index_block_hash: StacksBlockId,
+    pub block_height: u64,
+    pub tx_index: u32,
+}
+
+impl FromRow<PrincipalTransaction> for PrincipalTransaction {
+    fn from_row<'a>(row: &'a Row) -> Result<PrincipalTransaction, db_error> {
+        let txid = Txid::from_column(row, "txid")?;
+        let index_block_hash = StacksBlockId::from_column(row, "index_block_hash")?;
+        let block_height = u64::from_column(row, "block_height")?;
+        let tx_index: u32 = row.get_unwrap("tx_index");
+        Ok(PrincipalTransaction {
+            txid,
+            index_block_hash,
+            block_height,
+            tx_index,
+        })
+    }
+}
+
+/// Position in a principal's transaction history, newest first.  A page of history contains the
+/// transactions strictly older than the cursor.  Encoded as `{block_height}:{tx_index}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PrincipalTxCursor {
+    pub block_height: u64,
+    pub tx_index: u32,
+}
+
+impl PrincipalTxCursor {
+    /// The cursor that starts a page at (and including) the given block height
+    pub fn at_height(block_height: u64) -> Self {
+        Self {
+            block_height: block_height.saturating_add(1),
+            tx_index: 0,
+        }
+    }
+}
+
+impl fmt::Display for PrincipalTxCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.block_height, self.tx_index)
+    }
+}
+
+impl FromStr for PrincipalTxCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (height_str, index_str) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid cursor '{}'", s))?;
+        let block_height = height_str
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid cursor block height '{}'", height_str))?;
+        let tx_index = index_str
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid cursor tx index '{}'", index_str))?;
+        Ok(Self {
+            block_height,
+            tx_index,
+        })
+    }
+}
+
+fn insert_address(principals: &mut HashSet<PrincipalData>, addr: &StacksAddress) {
+    principals.insert(PrincipalData::from(addr.clone()));
+}
+
+/// Get the principals involved in a burnchain-originated Stacks operation
+fn get_burn_op_principals(op: &BlockstackOperationType, principals: &mut HashSet<PrincipalData>) {
+    match op {
+        BlockstackOperationType::PreStx(op) => {
+            insert_address(principals, &op.output);
+        }
+        BlockstackOperationType::StackStx(op) => {
+            insert_address(principals, &op.sender);
+        }
+        BlockstackOperationType::TransferStx(op) => {
+            insert_address(principals, &op.sender);
+            insert_address(principals, &op.recipient);
+        }
+        BlockstackOperationType::DelegateStx(op) => {
+            insert_address(principals, &op.sender);
+            insert_address(principals, &op.delegate_to);
+        }
+        BlockstackOperationType::VoteForAggregateKey(op) => {
+            insert_address(principals, &op.sender);
+        }
+        BlockstackOperationType::LeaderKeyRegister(_)
+        | BlockstackOperationType::LeaderBlockCommit(_) => {}
+    }
+}
+
+/// Get the principals involved in an event
+fn get_event_principals(event: &StacksTransactionEvent, principals: &mut HashSet<PrincipalData>) {
+    match event {
+        StacksTransactionEvent::SmartContractEvent(data) => {
+            principals.insert(PrincipalData::Contract(data.key.0.clone()));
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXTransferEvent(data)) => {
+            principals.insert(data.sender.clone());
+            principals.insert(data.recipient.clone());
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXMintEvent(data)) => {
+            principals.insert(data.recipient.clone());
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXBurnEvent(data)) => {
+            principals.insert(data.sender.clone());
+        }
+        StacksTransactionEvent::STXEvent(STXEventType::STXLockEvent(data)) => {
+            principals.insert(data.locked_address.clone());
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTTransferEvent(data)) => {
+            principals.insert(data.sender.clone());
+            principals.insert(data.recipient.clone());
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTMintEvent(data)) => {
+            principals.insert(data.recipient.clone());
+        }
+        StacksTransactionEvent::NFTEvent(NFTEventType::NFTBurnEvent(data)) => {
+            principals.insert(data.sender.clone());
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTTransferEvent(data)) => {
+            principals.insert(data.sender.clone());
+            principals.insert(data.recipient.clone());
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTMintEvent(data)) => {
+            principals.insert(data.recipient.clone());
+        }
+        StacksTransactionEvent::FTEvent(FTEventType::FTBurnEvent(data)) => {
+            principals.insert(data.sender.clone());
+        }
+    }
+}
+
+/// Get the set of principals that a transaction touched
+pub fn get_receipt_principals(receipt: &StacksTransactionReceipt) -> HashSet<PrincipalData> {
+    let mut principals = HashSet::new();
+    match &receipt.transaction {
+        TransactionOrigin::Stacks(tx) => {
+            let origin = tx.origin_address();
+            insert_address(&mut principals, &origin);
+            if let Some(sponsor) = tx.sponsor_address() {
+                insert_address(&mut principals, &sponsor);
+            }
+            match &tx.payload {
+                TransactionPayload::TokenTransfer(recipient, ..) => {
+                    principals.insert(recipient.clone());
+                }
+                TransactionPayload::ContractCall(cc) => {
+                    principals.insert(PrincipalData::Contract(cc.contract_identifier()));
+                }
+                TransactionPayload::SmartContract(sc, ..) => {
+                    let contract_id = QualifiedContractIdentifier::new(
+                        StandardPrincipalData::from(origin),
+                        sc.name.clone(),
+                    );
+                    principals.insert(PrincipalData::Contract(contract_id));
+                }
+                TransactionPayload::Coinbase(_, Some(recipient), _) => {
+                    principals.insert(recipient.clone());
+                }
+                TransactionPayload::Coinbase(_, None, _)
+                | TransactionPayload::PoisonMicroblock(..)
+                | TransactionPayload::TenureChange(..) => {}
+            }
+        }
+        TransactionOrigin::Burn(op) => {
+            get_burn_op_principals(op, &mut principals);
+        }
+    }
+    for event in receipt.events.iter() {
+        get_event_principals(event, &mut principals);
+    }
+    principals
+}
+
+impl<'a> ChainstateTx<'a> {
+    /// Record the principals touched by each of a newly-processed block's transactions, if the
+    /// principal transaction index is enabled.
+    pub fn index_principal_transactions(
+        &self,
+        header: &StacksHeaderInfo,
+        receipts: &[StacksTransactionReceipt],
+    ) -> Result<(), Error> {
+        if !self.principal_tx_index {
+            return Ok(());
+        }
+        let index_block_hash = header.index_block_hash();
+        let block_height = u64_to_sql(header.stacks_block_height)?;
+        let sql = "INSERT OR REPLACE INTO principal_transactions
+                   (principal, txid, index_block_hash, block_height, tx_index)
+                   VALUES (?1, ?2, ?3, ?4, ?5)";
+        for receipt in receipts.iter() {
+            let txid = receipt.transaction.txid();
+            for principal in get_receipt_principals(receipt).into_iter() {
+                let args: &[&dyn ToSql] = &[
+                    &principal.to_string(),
+                    &txid,
+                    &index_block_hash,
+                    &block_height,
+                    &receipt.tx_index,
+                ];
+                self.tx.tx().execute(sql, args)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StacksChainState {
+    /// Get a page of the transactions that touched `principal` in the fork ending at `tip`,
+    /// newest first, starting just before `cursor`.  Returns at most `limit` transactions, as
+    /// well as the cursor for the next page if there is one.
+    pub fn get_principal_transactions(
+        &self,
+        tip: &StacksBlockId,
+        principal: &PrincipalData,
+        cursor: PrincipalTxCursor,
+        limit: u32,
+    ) -> Result<(Vec<PrincipalTransaction>, Option<PrincipalTxCursor>), Error> {
+        let index_conn = self.index_conn()?;
+        let principal_str = principal.to_string();
+        let sql = "SELECT txid, index_block_hash, block_height, tx_index
+                   FROM principal_transactions
+                   WHERE principal = ?1 AND (block_height < ?2 OR (block_height = ?2 AND tx_index < ?3))
+                   ORDER BY block_height DESC, tx_index DESC
+                   LIMIT ?4";
+
+        let mut txs = vec![];
+        let mut next_cursor = cursor;
+        loop {
+            let args: &[&dyn ToSql] = &[
+                &principal_str,
+                &u64_to_sql(next_cursor.block_height)?,
+                &next_cursor.tx_index,
+                &PRINCIPAL_TX_BATCH_SIZE,
+            ];
+            let batch: Vec<PrincipalTransaction> = query_rows(self.db(), sql, args)?;
+            let exhausted = batch.len() < (PRINCIPAL_TX_BATCH_SIZE as usize);
+
+            for principal_tx in batch.into_iter() {
+                next_cursor = PrincipalTxCursor {
+                    block_height: principal_tx.block_height,
+                    tx_index: principal_tx.tx_index,
+                };
+                let ancestor_opt =
+                    index_conn.get_ancestor_block_hash(principal_tx.block_height, tip)?;
+                if ancestor_opt.as_ref() != Some(&principal_tx.index_block_hash) {
+                    // not in this fork
+                    continue;
+                }
+                if txs.len() >= (limit as usize) {
+                    // there's at least one more transaction, so the caller can ask for the next page
+                    let last = txs
+                        .last()
+                        .map(|last_tx: &PrincipalTransaction| PrincipalTxCursor {
+                            block_height: last_tx.block_height,
+                            tx_index: last_tx.tx_index,
+                        });
+                    return Ok((txs, last));
+                }
+                txs.push(principal_tx);
+            }
+            if exhausted {
+                return Ok((txs, None));
+            }
+        }
+    }
+}
@@ -21,6 +21,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::io::{Cursor, Seek, SeekFrom};
 
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::proofs::verify_marf_proof;
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::hash::to_hex;
@@ -204,6 +206,20 @@ pub fn merkle_test_marf_key_value(
 
     assert!(proof.verify(&triepath, &marf_value, &root_hash, &root_to_block));
 
+    // the light-client verifier in stacks-common must agree
+    let root_to_block_ids = root_to_block
+        .iter()
+        .map(|(root, block)| (root.clone(), StacksBlockId(block.0)))
+        .collect();
+    verify_marf_proof(
+        key,
+        value,
+        &proof.serialize_to_vec(),
+        &root_hash,
+        &root_to_block_ids,
+    )
+    .unwrap();
+
     root_to_block
 }
 
@@ -6,5 +6,8 @@ pub mod special;
 /// Stacks blockchain specific Clarity database implementations and wrappers
 pub mod database;
 
+/// Verification of MARF proofs of Clarity state, for light clients
+pub mod proofs;
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,81 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proofs of Clarity state.  The proof types and their verification live in
+//! `stacks_common::proofs`, so that light clients can verify proofs without depending on this
+//! crate.  This module adds the node-side helpers for building them.
+
+use stacks_common::codec::StacksMessageCodec;
+pub use stacks_common::proofs::{
+    verify_marf_proof, ClarityProofEntry, ClarityProofKey, ClarityProofs, Error, ProofAnchorHeader,
+};
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::util::hash::to_hex;
+
+use crate::chainstate::stacks::db::{StacksBlockHeaderTypes, StacksHeaderInfo};
+use crate::chainstate::stacks::index::node::is_backptr;
+use crate::chainstate::stacks::index::{TrieMerkleProof, TrieMerkleProofType};
+
+/// Make the anchor header for a block whose state a proof is anchored to
+pub fn make_anchor_header(header_info: &StacksHeaderInfo) -> ProofAnchorHeader {
+    let (nakamoto, header_bytes) = match header_info.anchored_header {
+        StacksBlockHeaderTypes::Epoch2(ref header) => (false, header.serialize_to_vec()),
+        StacksBlockHeaderTypes::Nakamoto(ref header) => (true, header.serialize_to_vec()),
+    };
+    ProofAnchorHeader {
+        index_block_hash: header_info.index_block_hash(),
+        consensus_hash: header_info.consensus_hash.clone(),
+        nakamoto,
+        header: to_hex(&header_bytes),
+    }
+}
+
+/// Find the ancestor blocks whose tries a proof passes through.  Each segment proof that ends in
+/// a back-pointer (rather than a leaf) starts with the node holding that back-pointer, and the
+/// back-pointer names the block in which the next (older) segment lives.  A verifier needs the
+/// `state_index_root` of each of these blocks.
+pub fn get_proof_ancestor_blocks(proof: &TrieMerkleProof<StacksBlockId>) -> Vec<StacksBlockId> {
+    let mut blocks = vec![];
+    let mut segment_start = true;
+    for proof_node in proof.0.iter() {
+        let (chr, node) = match proof_node {
+            TrieMerkleProofType::Shunt(..) => {
+                segment_start = true;
+                continue;
+            }
+            TrieMerkleProofType::Leaf(..) => {
+                segment_start = false;
+                continue;
+            }
+            TrieMerkleProofType::Node4((chr, node, _))
+            | TrieMerkleProofType::Node16((chr, node, _))
+            | TrieMerkleProofType::Node48((chr, node, _))
+            | TrieMerkleProofType::Node256((chr, node, _)) => (chr, node),
+        };
+        if segment_start {
+            if let Some(ptr) = node
+                .ptrs
+                .iter()
+                .find(|ptr| ptr.chr == *chr && is_backptr(ptr.id))
+            {
+                if !blocks.contains(&ptr.back_block) {
+                    blocks.push(ptr.back_block.clone());
+                }
+            }
+        }
+        segment_start = false;
+    }
+    blocks
+}
@@ -39,17 +39,26 @@ use std::io::prelude::*;
 use std::io::BufReader;
 use std::{env, fs, io, process, thread};
 
+use blockstack_lib::burnchains::affirmation::{
+    check_affirmation_override, get_reward_cycle_affirmation, AffirmationMap,
+};
+use blockstack_lib::burnchains::bitcoin::blocks::BitcoinBlockParser;
 use blockstack_lib::burnchains::bitcoin::indexer::{
     BitcoinIndexer, BitcoinIndexerConfig, BitcoinIndexerRuntime,
 };
+use blockstack_lib::burnchains::bitcoin::psbt::{
+    validate_signed_psbt, BurnOpPsbtBuilder, Psbt, PsbtFundingUtxo,
+};
 use blockstack_lib::burnchains::bitcoin::{spv, BitcoinNetworkType};
 use blockstack_lib::burnchains::db::{BurnchainBlockData, BurnchainDB};
 use blockstack_lib::burnchains::{
-    Address, Burnchain, PoxConstants, Txid, BLOCKSTACK_MAGIC_MAINNET,
+    Address, Burnchain, MagicBytes, PoxConstants, Txid, BLOCKSTACK_MAGIC_MAINNET,
 };
+use blockstack_lib::chainstate::burn::db::explorer::explain_burn_blocks;
 use blockstack_lib::chainstate::burn::db::sortdb::{
     get_block_commit_by_txid, SortitionDB, SortitionHandle,
 };
+use blockstack_lib::chainstate::burn::db::verify::{truncate_burnchain, verify_burnchain};
 use blockstack_lib::chainstate::burn::operations::BlockstackOperationType;
 use blockstack_lib::chainstate::burn::{BlockSnapshot, ConsensusHash};
 use blockstack_lib::chainstate::coordinator::{get_reward_cycle_info, OnChainRewardSetProvider};
@@ -70,6 +79,7 @@ use blockstack_lib::clarity_cli::vm_execute;
 use blockstack_lib::core::{MemPoolDB, *};
 use blockstack_lib::cost_estimates::metrics::UnitMetric;
 use blockstack_lib::cost_estimates::UnitEstimator;
+use blockstack_lib::net::capture::{read_capture, CaptureDirection, CaptureFilter};
 use blockstack_lib::net::db::LocalPeer;
 use blockstack_lib::net::p2p::PeerNetwork;
 use blockstack_lib::net::relay::Relayer;
@@ -81,6 +91,10 @@ use rusqlite::types::ToSql;
 use rusqlite::{Connection, OpenFlags};
 use serde_json::{json, Value};
 use stacks_common::codec::{read_next, StacksMessageCodec};
+use stacks_common::deps_common::bitcoin::blockdata::script::Script;
+use stacks_common::deps_common::bitcoin::network::serialize::{
+    deserialize as btc_deserialize, serialize as btc_serialize,
+};
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, PoxId, StacksAddress, StacksBlockId,
 };
@@ -268,6 +282,106 @@ fn main() {
         }
     }
 
+    if argv[1] == "decode-capture" {
+        let usage = format!(
+            "Usage: {} decode-capture CAPTURE_PATH [--direction in|out] [--event-id ID] [--peer ADDR|PUBKEY_HASH] [--type MESSAGE_TYPE]... [--start TIMESTAMP_MS] [--end TIMESTAMP_MS] [--json]
+
+Decode the p2p messages recorded in a capture file (see connection_options.p2p_capture_path).
+With --json, matching records are printed as capture-file lines, so the output can itself be
+used as a (filtered) capture.
+",
+            argv[0]
+        );
+        if argv.len() < 3 {
+            eprintln!("{}", &usage);
+            process::exit(1);
+        }
+
+        let capture_path = &argv[2];
+        let mut filter = CaptureFilter::default();
+        let mut json = false;
+        let mut i = 3;
+        while i < argv.len() {
+            let flag = argv[i].as_str();
+            if flag == "--json" {
+                json = true;
+                i += 1;
+                continue;
+            }
+            let Some(value) = argv.get(i + 1) else {
+                eprintln!("{}", &usage);
+                process::exit(1);
+            };
+            match flag {
+                "--direction" => {
+                    filter.direction =
+                        Some(CaptureDirection::from_str(value).unwrap_or_else(|| {
+                            eprintln!("Invalid direction '{}': expected 'in' or 'out'", value);
+                            process::exit(1);
+                        }));
+                }
+                "--event-id" => {
+                    filter.event_id = Some(value.parse().unwrap_or_else(|_| {
+                        eprintln!("Invalid event ID '{}'\n{}", value, &usage);
+                        process::exit(1);
+                    }));
+                }
+                "--peer" => {
+                    filter.peer = Some(value.clone());
+                }
+                "--type" => {
+                    filter.message_types.push(value.clone());
+                }
+                "--start" => {
+                    filter.start_ms = Some(value.parse().unwrap_or_else(|_| {
+                        eprintln!("Invalid start timestamp '{}'\n{}", value, &usage);
+                        process::exit(1);
+                    }));
+                }
+                "--end" => {
+                    filter.end_ms = Some(value.parse().unwrap_or_else(|_| {
+                        eprintln!("Invalid end timestamp '{}'\n{}", value, &usage);
+                        process::exit(1);
+                    }));
+                }
+                _ => {
+                    eprintln!("{}", &usage);
+                    process::exit(1);
+                }
+            }
+            i += 2;
+        }
+
+        let records = read_capture(capture_path, &filter).unwrap_or_else(|e| {
+            eprintln!("Failed to read capture {}: {:?}", capture_path, &e);
+            process::exit(1);
+        });
+        for record in records.iter() {
+            if json {
+                println!("{}", serde_json::to_string(record).unwrap());
+                continue;
+            }
+            println!(
+                "{} {} event={} peer={} pubkh={} {}",
+                record.timestamp_ms,
+                record.direction,
+                record.event_id,
+                &record.peer_addr,
+                record
+                    .peer_public_key_hash
+                    .as_ref()
+                    .map(|pubkh| pubkh.to_hex())
+                    .unwrap_or("unknown".to_string()),
+                &record.message_type
+            );
+            match record.decode_message() {
+                Ok(msg) => println!("{:#?}", &msg),
+                Err(e) => println!("Failed to decode message: {:?}", &e),
+            }
+        }
+        process::exit(0);
+    }
+
     if argv[1] == "get-tenure" {
         if argv.len() < 4 {
             eprintln!("Usage: {} get-tenure CHAIN_STATE_DIR BLOCK_HASH", argv[0]);
@@ -867,6 +981,17 @@ simulating a miner.
         return;
     }
 
+    if argv[1] == "docgen_rpc" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(
+                &*blockstack_lib::net::api::getopenapi::RPC_OPENAPI_DOCUMENT
+            )
+            .expect("FATAL: failed to serialize OpenAPI document")
+        );
+        return;
+    }
+
     if argv[1] == "local" {
         clarity_cli::invoke_command(&format!("{} {}", argv[0], argv[1]), &argv[2..]);
         return;
@@ -1021,12 +1146,42 @@ simulating a miner.
         process::exit(0);
     }
 
+    if argv[1] == "burn-op-psbt" {
+        burn_op_psbt(argv);
+        // should be unreachable
+        process::exit(1);
+    }
+
     if argv[1] == "analyze-sortition-mev" {
         analyze_sortition_mev(argv);
         // should be unreachable
         process::exit(1);
     }
 
+    if argv[1] == "burn-ops" {
+        burn_ops(argv);
+        // should be unreachable
+        process::exit(1);
+    }
+
+    if argv[1] == "verify-burnchain" {
+        verify_burnchain_cmd(argv);
+        // should be unreachable
+        process::exit(1);
+    }
+
+    if argv[1] == "affirmations" {
+        affirmations(argv);
+        // should be unreachable
+        process::exit(1);
+    }
+
+    if argv[1] == "check-affirmation-override" {
+        check_affirmation_override_cmd(argv);
+        // should be unreachable
+        process::exit(1);
+    }
+
     if argv[1] == "replay-chainstate" {
         if argv.len() < 7 {
             eprintln!("Usage: {} OLD_CHAINSTATE_PATH OLD_SORTITION_DB_PATH OLD_BURNCHAIN_DB_PATH NEW_CHAINSTATE_PATH NEW_BURNCHAIN_DB_PATH", &argv[0]);
@@ -1963,3 +2118,603 @@ fn analyze_sortition_mev(argv: Vec<String>) {
 
     process::exit(0);
 }
+
+fn burn_ops(argv: Vec<String>) {
+    if argv.len() < 7 {
+        eprintln!(
+            "Usage: {} burn-ops NETWORK /path/to/burnchain/db /path/to/sortition/db start_height end_height [json|table]
+
+Lists every burnchain op parsed from each burnchain block in [start_height, end_height) on the
+canonical sortition fork, whether it was accepted, and if not, why.  NETWORK is mainnet, testnet
+or regtest.  Output is a table by default.",
+            &argv[0]
+        );
+        process::exit(1);
+    }
+
+    let network = argv[2].as_str();
+    let burnchaindb_path = argv[3].clone();
+    let sortdb_path = argv[4].clone();
+    let start_height: u64 = argv[5].parse().expect("Failed to parse start_height");
+    let end_height: u64 = argv[6].parse().expect("Failed to parse end_height");
+    let as_json = match argv.get(7).map(|s| s.as_str()) {
+        None | Some("table") => false,
+        Some("json") => true,
+        Some(other) => {
+            eprintln!("Unknown output format '{}': expected json or table", other);
+            process::exit(1);
+        }
+    };
+
+    let burnchain = Burnchain::new(&burnchaindb_path, "bitcoin", network).unwrap_or_else(|_| {
+        eprintln!("NETWORK must be mainnet, testnet or regtest");
+        process::exit(1);
+    });
+    // opened read-write only because re-checking ops needs a transaction, which is never committed
+    let mut sortdb = SortitionDB::open(&sortdb_path, true, burnchain.pox_constants.clone())
+        .unwrap_or_else(|e| panic!("Failed to open {}: {:?}", &sortdb_path, &e));
+    sortdb.dryrun = true;
+    let burnchaindb = BurnchainDB::connect(&burnchaindb_path, &burnchain, false)
+        .unwrap_or_else(|e| panic!("Failed to open {}: {:?}", &burnchaindb_path, &e));
+
+    let reports = explain_burn_blocks(
+        &mut sortdb,
+        &burnchaindb,
+        &burnchain,
+        start_height,
+        end_height,
+    )
+    .unwrap_or_else(|e| panic!("Failed to load burnchain ops: {:?}", &e));
+
+    if as_json {
+        println!("{}", &serde_json::to_string_pretty(&reports).unwrap());
+        process::exit(0);
+    }
+
+    for report in reports.iter() {
+        println!(
+            "Block {} ({}), consensus hash {}, block burn {}",
+            report.block_height,
+            &report.burn_header_hash,
+            &report.consensus_hash,
+            report.block_burn
+        );
+        match (
+            report.winning_block_txid.as_ref(),
+            report.winning_stacks_block_hash.as_ref(),
+            report.winning_vrf_seed.as_ref(),
+        ) {
+            (Some(txid), Some(block_hash), Some(vrf_seed)) => println!(
+                "Sortition winner: {} (Stacks block {}, VRF seed {})",
+                txid, block_hash, vrf_seed
+            ),
+            _ => println!("Sortition winner: (none)"),
+        }
+        println!("vtxindex,txid,op_type,sender,burn_fee,status,reason");
+        for op in report.ops.iter() {
+            let status = if op.won_sortition {
+                "won"
+            } else if op.accepted {
+                "accepted"
+            } else {
+                "rejected"
+            };
+            println!(
+                "{},{},{},{},{},{},{}",
+                op.vtxindex,
+                &op.txid,
+                &op.op_type,
+                op.sender.as_deref().unwrap_or(""),
+                op.burn_fee.map(|fee| fee.to_string()).unwrap_or_default(),
+                status,
+                op.rejected_reason.as_deref().unwrap_or("")
+            );
+        }
+        println!();
+    }
+
+    process::exit(0);
+}
+
+fn verify_burnchain_cmd(mut argv: Vec<String>) {
+    let truncate = if let Some(idx) = argv.iter().position(|arg| arg == "--truncate") {
+        argv.remove(idx);
+        true
+    } else {
+        false
+    };
+    let as_json = if let Some(idx) = argv.iter().position(|arg| arg == "--json") {
+        argv.remove(idx);
+        true
+    } else {
+        false
+    };
+    if argv.len() < 6 {
+        eprintln!(
+            "Usage: {} verify-burnchain [--json] [--truncate] NETWORK /path/to/burnchain/db /path/to/sortition/db /path/to/headers.sqlite [start_height [end_height]]
+
+Checks each burnchain block in [start_height, end_height) on the canonical sortition fork
+(default: all of them).  Each snapshot is compared with the SPV header and burnchain DB block at
+its height, and with the snapshot re-derived from the burnchain DB's ops: consensus hash,
+sortition hash, total burn, PoX ID and accepted ops.  Every mismatch is printed with its height,
+and the command exits with status 1 if there were any.
+
+With --truncate, the SPV headers and burnchain DB blocks above the last consistent height are
+dropped and the sortitions above it are invalidated, so the node re-downloads and re-processes
+them when it restarts.  Stop the node first.  NETWORK is mainnet, testnet or regtest.",
+            &argv[0]
+        );
+        process::exit(1);
+    }
+
+    let network = argv[2].as_str();
+    let burnchaindb_path = argv[3].clone();
+    let sortdb_path = argv[4].clone();
+    let headers_path = argv[5].clone();
+    let start_height: u64 = argv
+        .get(6)
+        .map(|s| s.parse().expect("Failed to parse start_height"))
+        .unwrap_or(0);
+    let end_height: u64 = argv
+        .get(7)
+        .map(|s| s.parse().expect("Failed to parse end_height"))
+        .unwrap_or(u64::MAX);
+
+    let network_type = match network {
+        "mainnet" => BitcoinNetworkType::Mainnet,
+        "testnet" => BitcoinNetworkType::Testnet,
+        "regtest" => BitcoinNetworkType::Regtest,
+        _ => {
+            eprintln!("NETWORK must be mainnet, testnet or regtest");
+            process::exit(1);
+        }
+    };
+    let burnchain = Burnchain::new(&burnchaindb_path, "bitcoin", network).unwrap_or_else(|_| {
+        eprintln!("NETWORK must be mainnet, testnet or regtest");
+        process::exit(1);
+    });
+    // opened read-write only because re-evaluating sortitions needs a transaction, which is
+    // never committed
+    let mut sortdb = SortitionDB::open(&sortdb_path, true, burnchain.pox_constants.clone())
+        .unwrap_or_else(|e| panic!("Failed to open {}: {:?}", &sortdb_path, &e));
+    sortdb.dryrun = true;
+    let mut burnchaindb = BurnchainDB::connect(&burnchaindb_path, &burnchain, truncate)
+        .unwrap_or_else(|e| panic!("Failed to open {}: {:?}", &burnchaindb_path, &e));
+    let mut indexer_config = BitcoinIndexerConfig::default(burnchain.first_block_height);
+    indexer_config.spv_headers_path = headers_path;
+    let mut indexer = BitcoinIndexer {
+        config: indexer_config,
+        runtime: BitcoinIndexerRuntime::new(network_type),
+        should_keep_running: None,
+    };
+
+    let report = verify_burnchain(
+        &mut sortdb,
+        &burnchaindb,
+        &burnchain,
+        &indexer,
+        start_height,
+        end_height,
+    )
+    .unwrap_or_else(|e| panic!("Failed to verify burnchain state: {:?}", &e));
+
+    if as_json {
+        println!("{}", &serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        println!(
+            "Checked heights {}-{}: {} mismatches, last consistent height {}",
+            report.start_height,
+            report.end_height,
+            report.mismatches.len(),
+            report.last_consistent_height
+        );
+        if !report.mismatches.is_empty() {
+            println!("block_height,field,stored,expected");
+        }
+        for mismatch in report.mismatches.iter() {
+            println!(
+                "{},{},{},{}",
+                mismatch.block_height, &mismatch.field, &mismatch.stored, &mismatch.expected
+            );
+        }
+    }
+
+    if report.mismatches.is_empty() {
+        process::exit(0);
+    }
+
+    if truncate {
+        sortdb.dryrun = false;
+        truncate_burnchain(
+            &mut sortdb,
+            &mut burnchaindb,
+            &burnchain,
+            &mut indexer,
+            report.last_consistent_height,
+        )
+        .unwrap_or_else(|e| panic!("Failed to truncate burnchain state: {:?}", &e));
+        eprintln!(
+            "Truncated burnchain state to height {}",
+            report.last_consistent_height
+        );
+    }
+    process::exit(1);
+}
+
+fn affirmations(argv: Vec<String>) {
+    if argv.len() < 7 {
+        eprintln!(
+            "Usage: {} affirmations NETWORK /path/to/burnchain/db /path/to/sortition/db start_reward_cycle end_reward_cycle [json|table]
+
+Shows, for each reward cycle in [start_reward_cycle, end_reward_cycle), the anchor block elected
+on the canonical sortition fork, its affirmation map, the prepare-phase block-commits that voted
+for it, and any affirmation map override.  NETWORK is mainnet, testnet or regtest.  Output is a
+table by default.",
+            &argv[0]
+        );
+        process::exit(1);
+    }
+
+    let network = argv[2].as_str();
+    let burnchaindb_path = argv[3].clone();
+    let sortdb_path = argv[4].clone();
+    let start_reward_cycle: u64 = argv[5].parse().expect("Failed to parse start_reward_cycle");
+    let end_reward_cycle: u64 = argv[6].parse().expect("Failed to parse end_reward_cycle");
+    let as_json = match argv.get(7).map(|s| s.as_str()) {
+        None | Some("table") => false,
+        Some("json") => true,
+        Some(other) => {
+            eprintln!("Unknown output format '{}': expected json or table", other);
+            process::exit(1);
+        }
+    };
+
+    let burnchain = Burnchain::new(&burnchaindb_path, "bitcoin", network).unwrap_or_else(|_| {
+        eprintln!("NETWORK must be mainnet, testnet or regtest");
+        process::exit(1);
+    });
+    let sortdb = SortitionDB::open(&sortdb_path, false, burnchain.pox_constants.clone())
+        .unwrap_or_else(|e| panic!("Failed to open {}: {:?}", &sortdb_path, &e));
+    let burnchaindb = BurnchainDB::connect(&burnchaindb_path, &burnchain, false)
+        .unwrap_or_else(|e| panic!("Failed to open {}: {:?}", &burnchaindb_path, &e));
+
+    let reports: Vec<_> = (start_reward_cycle.max(1)..end_reward_cycle)
+        .map(|reward_cycle| {
+            get_reward_cycle_affirmation(&burnchaindb, &sortdb, &burnchain, reward_cycle)
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "Failed to load affirmations for reward cycle {}: {:?}",
+                        reward_cycle, &e
+                    )
+                })
+        })
+        .collect();
+
+    if as_json {
+        println!("{}", &serde_json::to_string_pretty(&reports).unwrap());
+        process::exit(0);
+    }
+
+    for report in reports.iter() {
+        println!("Reward cycle {}", report.reward_cycle);
+        match (
+            report.anchor_block_txid.as_ref(),
+            report.anchor_block_height,
+            report.anchor_stacks_block_hash.as_ref(),
+        ) {
+            (Some(txid), Some(height), Some(block_hash)) => println!(
+                "Anchor block: {} at height {} (Stacks block {}), {} confirmations, {} confirming burn",
+                txid, height, block_hash, report.confirmations, report.confirming_burn
+            ),
+            _ => println!("Anchor block: (none)"),
+        }
+        println!(
+            "Anchor block affirmation map: {} (weight {})",
+            report
+                .anchor_affirmation_map
+                .as_ref()
+                .map(|am| am.encode())
+                .unwrap_or_default(),
+            report
+                .anchor_affirmation_weight
+                .map(|weight| weight.to_string())
+                .unwrap_or_default()
+        );
+        if let Some(am) = report.override_affirmation_map.as_ref() {
+            println!("Override affirmation map: {}", am);
+        }
+        println!(
+            "block_height,vtxindex,txid,sender,burn_fee,confirms_anchor_block,affirmation_map"
+        );
+        for commit in report.prepare_phase_commits.iter() {
+            println!(
+                "{},{},{},{},{},{},{}",
+                commit.block_height,
+                commit.vtxindex,
+                &commit.txid,
+                &commit.apparent_sender,
+                commit.burn_fee,
+                commit.confirms_anchor_block,
+                commit
+                    .affirmation_map
+                    .as_ref()
+                    .map(|am| am.encode())
+                    .unwrap_or_default()
+            );
+        }
+        println!();
+    }
+
+    process::exit(0);
+}
+
+fn check_affirmation_override_cmd(argv: Vec<String>) {
+    if argv.len() < 6 {
+        eprintln!(
+            "Usage: {} check-affirmation-override NETWORK /path/to/burnchain/db reward_cycle AFFIRMATION_MAP
+
+Checks that AFFIRMATION_MAP (e.g. ppnpa) can be used as the affirmation map override for
+reward_cycle, and if so, prints the config file entry that applies it when the node restarts.
+Exits with status 1 if it cannot be used.",
+            &argv[0]
+        );
+        process::exit(1);
+    }
+
+    let network = argv[2].as_str();
+    let burnchaindb_path = argv[3].clone();
+    let reward_cycle: u64 = argv[4].parse().expect("Failed to parse reward_cycle");
+    let affirmation_map = AffirmationMap::decode(&argv[5]).unwrap_or_else(|| {
+        eprintln!(
+            "Invalid affirmation map '{}': expected only p, a and n",
+            &argv[5]
+        );
+        process::exit(1);
+    });
+
+    let burnchain = Burnchain::new(&burnchaindb_path, "bitcoin", network).unwrap_or_else(|_| {
+        eprintln!("NETWORK must be mainnet, testnet or regtest");
+        process::exit(1);
+    });
+    let burnchaindb = BurnchainDB::connect(&burnchaindb_path, &burnchain, false)
+        .unwrap_or_else(|e| panic!("Failed to open {}: {:?}", &burnchaindb_path, &e));
+
+    let warnings = check_affirmation_override(&burnchaindb, reward_cycle, &affirmation_map)
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid affirmation map override: {}", e);
+            process::exit(1);
+        });
+    for warning in warnings.iter() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    println!(
+        "[[burnchain.affirmation_overrides]]\nreward_cycle = {}\naffirmation = \"{}\"",
+        reward_cycle,
+        affirmation_map.encode()
+    );
+    process::exit(0);
+}
+
+fn parse_magic_bytes(magic: &str) -> MagicBytes {
+    let bytes = magic.as_bytes();
+    if bytes.len() != 2 {
+        eprintln!("Magic bytes must be 2 characters (e.g. X2)");
+        process::exit(1);
+    }
+    MagicBytes::from(bytes)
+}
+
+fn read_json_file(path: &str) -> Value {
+    let contents = fs::read_to_string(path).expect(&format!("Failed to read {}", path));
+    serde_json::from_str(&contents).expect(&format!("Failed to parse JSON in {}", path))
+}
+
+/// Read a JSON-encoded `BlockstackOperationType`.  The fields that say where the op was mined
+/// (txid, vtxindex, block_height and burn_header_hash) may be omitted.
+fn read_op_json(path: &str) -> BlockstackOperationType {
+    let mut op_json = read_json_file(path);
+    if let Some(fields) = op_json
+        .as_object_mut()
+        .and_then(|variant| variant.values_mut().next())
+        .and_then(|op| op.as_object_mut())
+    {
+        let location = json!({
+            "txid": Txid([0u8; 32]),
+            "vtxindex": 0,
+            "block_height": 0,
+            "burn_header_hash": BurnchainHeaderHash::zero(),
+        });
+        for (key, value) in location.as_object().unwrap().iter() {
+            fields.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    serde_json::from_value(op_json).expect("Failed to decode burnchain operation")
+}
+
+/// Read a PSBT from base64, or from stdin if `arg` is `-`
+fn read_psbt_arg(arg: &str) -> Psbt {
+    let data = if arg == "-" {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer).unwrap();
+        buffer
+    } else {
+        arg.to_string()
+    };
+    Psbt::from_base64(&data).unwrap_or_else(|e| {
+        eprintln!("Failed to decode PSBT: {}", &e);
+        process::exit(1);
+    })
+}
+
+fn parse_script_hex(hex: &str) -> Script {
+    Script::from(hex_bytes(hex).expect("Failed to decode script hex"))
+}
+
+/// Parse a funding UTXO, given as
+/// `{"txid", "vout", "amount", "script_pubkey", "prev_tx"?, "redeem_script"?, "witness_script"?}`
+/// with all byte strings hex-encoded
+fn parse_psbt_funding_utxo(value: &Value) -> PsbtFundingUtxo {
+    let hex_field = |name: &str| value.get(name).and_then(|v| v.as_str());
+    PsbtFundingUtxo {
+        txid: Txid::from_hex(hex_field("txid").expect("UTXO has no txid"))
+            .expect("Failed to decode UTXO txid"),
+        vout: value
+            .get("vout")
+            .and_then(|v| v.as_u64())
+            .expect("UTXO has no vout") as u32,
+        amount: value
+            .get("amount")
+            .and_then(|v| v.as_u64())
+            .expect("UTXO has no amount"),
+        script_pubkey: parse_script_hex(
+            hex_field("script_pubkey").expect("UTXO has no script_pubkey"),
+        ),
+        prev_tx: hex_field("prev_tx").map(|tx_hex| {
+            btc_deserialize(&hex_bytes(tx_hex).expect("Failed to decode prev_tx hex"))
+                .expect("Failed to decode prev_tx")
+        }),
+        redeem_script: hex_field("redeem_script").map(parse_script_hex),
+        witness_script: hex_field("witness_script").map(parse_script_hex),
+    }
+}
+
+fn psbt_to_json(psbt: &Psbt) -> Value {
+    json!({
+        "psbt": psbt.to_base64(),
+        "unsigned_txid": psbt.unsigned_txid().to_hex(),
+    })
+}
+
+/// Build, combine and check the PSBTs for burnchain ops sent from an external wallet.
+/// Results are printed to stdout as JSON.
+/// Exits with 0 on success, and 1 on failure.
+fn burn_op_psbt(argv: Vec<String>) {
+    let usage = format!(
+        "Usage: {} burn-op-psbt SUBCOMMAND [args...]
+
+Subcommands:
+  build MAGIC OP_JSON_PATH FUNDING_UTXOS_JSON_PATH CHANGE_SCRIPT FEE_RATE [SENDER_REDEEM_SCRIPT [SENDER_WITNESS_SCRIPT]]
+      Build the unsigned PreStxOp PSBT, funded by the UTXOs in FUNDING_UTXOS_JSON_PATH, and the
+      unsigned PSBT for the op in OP_JSON_PATH, which spends the PreStxOp's output to the sender.
+      If a funding UTXO is not segwit, the op PSBT must be rebuilt with build-op once the
+      PreStxOp is signed.
+  build-op MAGIC OP_JSON_PATH SENDER_UTXO_JSON_PATH CHANGE_SCRIPT FEE_RATE
+      Build the unsigned PSBT for the op in OP_JSON_PATH, spending the PreStxOp output in
+      SENDER_UTXO_JSON_PATH.
+  combine PSBT PSBT [PSBT...]
+      Merge the signatures from several copies of a PSBT.
+  verify NETWORK MAGIC OP_JSON_PATH PSBT [PRE_STX_TXID]
+      Finalize a signed PSBT, and check that it is the op in OP_JSON_PATH.
+
+OP_JSON_PATH is a JSON-encoded BlockstackOperationType, whose txid and location fields may be
+omitted.  Funding UTXOs are JSON objects with the
+fields txid, vout, amount, script_pubkey, and optionally prev_tx, redeem_script and
+witness_script, with byte strings in hex.  Scripts are hex.  MAGIC is the burnchain's magic
+bytes (e.g. X2).  NETWORK is mainnet, testnet or regtest.  FEE_RATE is in sats/vbyte.  A PSBT
+is base64, or - to read it from stdin.",
+        &argv[0]
+    );
+    if argv.len() < 3 {
+        eprintln!("{}", &usage);
+        process::exit(1);
+    }
+
+    match argv[2].as_str() {
+        "build" | "build-op" if argv.len() >= 8 => {
+            let builder = BurnOpPsbtBuilder::new(
+                parse_magic_bytes(&argv[3]),
+                argv[7].parse().expect("Failed to parse FEE_RATE"),
+                parse_script_hex(&argv[6]),
+            );
+            let op = read_op_json(&argv[4]);
+            let result = if argv[2] == "build" {
+                let funding: Vec<_> = read_json_file(&argv[5])
+                    .as_array()
+                    .expect("Funding UTXOs must be a JSON list")
+                    .iter()
+                    .map(parse_psbt_funding_utxo)
+                    .collect();
+                builder
+                    .build_pre_stx_and_op(
+                        &op,
+                        &funding,
+                        argv.get(8).map(|hex| parse_script_hex(hex)),
+                        argv.get(9).map(|hex| parse_script_hex(hex)),
+                    )
+                    .map(|(pre_stx, op_psbt)| {
+                        json!({
+                            "pre_stx": psbt_to_json(&pre_stx),
+                            "sender_amount": pre_stx.unsigned_tx.output[1].value,
+                            "op": psbt_to_json(&op_psbt),
+                        })
+                    })
+            } else {
+                let sender_utxo = parse_psbt_funding_utxo(&read_json_file(&argv[5]));
+                builder
+                    .build_op(&op, &sender_utxo)
+                    .map(|op_psbt| json!({ "op": psbt_to_json(&op_psbt) }))
+            };
+            match result {
+                Ok(output) => println!("{}", &serde_json::to_string_pretty(&output).unwrap()),
+                Err(e) => {
+                    eprintln!("Failed to build PSBTs: {}", &e);
+                    process::exit(1);
+                }
+            }
+        }
+        "combine" if argv.len() >= 5 => {
+            let mut psbt = read_psbt_arg(&argv[3]);
+            for arg in argv[4..].iter() {
+                if let Err(e) = psbt.combine(&read_psbt_arg(arg)) {
+                    eprintln!("Failed to combine PSBTs: {}", &e);
+                    process::exit(1);
+                }
+            }
+            println!("{}", &psbt.to_base64());
+        }
+        "verify" if argv.len() >= 7 => {
+            let network = match argv[3].as_str() {
+                "mainnet" => BitcoinNetworkType::Mainnet,
+                "testnet" => BitcoinNetworkType::Testnet,
+                "regtest" => BitcoinNetworkType::Regtest,
+                _ => {
+                    eprintln!("NETWORK must be mainnet, testnet or regtest");
+                    process::exit(1);
+                }
+            };
+            let parser = BitcoinBlockParser::new(network, parse_magic_bytes(&argv[4]));
+            let op = read_op_json(&argv[5]);
+            let psbt = read_psbt_arg(&argv[6]);
+            let pre_stx_txid = argv
+                .get(7)
+                .map(|txid| Txid::from_hex(txid).expect("Failed to decode PRE_STX_TXID"));
+            match validate_signed_psbt(
+                &psbt,
+                &op,
+                &parser,
+                StacksEpochId::latest(),
+                pre_stx_txid.as_ref(),
+            ) {
+                Ok((tx, parsed)) => {
+                    let output = json!({
+                        "txid": parsed.txid().to_hex(),
+                        "tx": to_hex(&btc_serialize(&tx).unwrap()),
+                        "op": parsed.blockstack_op_to_json(),
+                    });
+                    println!("{}", &serde_json::to_string_pretty(&output).unwrap());
+                }
+                Err(e) => {
+                    eprintln!("PSBT is not a valid {:?} op: {}", op.opcode(), &e);
+                    process::exit(1);
+                }
+            }
+        }
+        _ => {
+            eprintln!("{}", &usage);
+            process::exit(1);
+        }
+    }
+
+    process::exit(0);
+}
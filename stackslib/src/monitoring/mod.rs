@@ -169,6 +169,89 @@ pub fn increment_btc_ops_sent_counter() {
     prometheus::BTC_OPS_SENT_COUNTER.inc();
 }
 
+/// Record the fee rate chosen for a burnchain op, and what determined it
+#[allow(unused_variables)]
+pub fn record_btc_fee_rate_decision(op: &str, source: &str, fee_rate: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        prometheus::BTC_FEE_RATE_DECISIONS_COUNTER
+            .with_label_values(&[op, source])
+            .inc();
+        prometheus::BTC_FEE_RATE_GAUGE
+            .with_label_values(&[op])
+            .set(i64::try_from(fee_rate).unwrap_or(i64::MAX));
+    }
+}
+
+#[allow(unused_variables)]
+pub fn update_btc_fee_estimate(fee_rate: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::BTC_FEE_ESTIMATE_GAUGE.set(i64::try_from(fee_rate).unwrap_or(i64::MAX));
+}
+
+#[allow(unused_variables)]
+pub fn update_btc_mempool_min_fee(fee_rate: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::BTC_MEMPOOL_MIN_FEE_GAUGE.set(i64::try_from(fee_rate).unwrap_or(i64::MAX));
+}
+
+/// Record the chain height a bitcoin peer advertised
+#[allow(unused_variables)]
+pub fn update_btc_peer_height(peer: &str, height: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::BTC_PEER_HEIGHT_GAUGE
+        .with_label_values(&[peer])
+        .set(i64::try_from(height).unwrap_or(i64::MAX));
+}
+
+#[allow(unused_variables)]
+pub fn increment_btc_peer_tip_alerts_counter(peer: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::BTC_PEER_TIP_ALERTS_COUNTER
+        .with_label_values(&[peer])
+        .inc();
+}
+
+/// Record the outcome of a StackerDB sync round
+#[allow(unused_variables)]
+pub fn update_stackerdb_sync_stats(
+    contract: &str,
+    connected_replicas: u64,
+    slots_behind: u64,
+    last_sync_ts: u64,
+) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        prometheus::STACKERDB_CONNECTED_REPLICAS_GAUGE
+            .with_label_values(&[contract])
+            .set(i64::try_from(connected_replicas).unwrap_or(i64::MAX));
+        prometheus::STACKERDB_SLOTS_BEHIND_GAUGE
+            .with_label_values(&[contract])
+            .set(i64::try_from(slots_behind).unwrap_or(i64::MAX));
+        prometheus::STACKERDB_LAST_SYNC_GAUGE
+            .with_label_values(&[contract])
+            .set(i64::try_from(last_sync_ts).unwrap_or(i64::MAX));
+    }
+}
+
+/// Record StackerDB chunk data pulled from (`direction` is "pulled") or pushed to ("pushed")
+/// replicas
+#[allow(unused_variables)]
+pub fn increment_stackerdb_bytes_counter(contract: &str, direction: &str, bytes: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::STACKERDB_BYTES_COUNTER
+        .with_label_values(&[contract, direction])
+        .inc_by(i64::try_from(bytes).unwrap_or(i64::MAX));
+}
+
+#[allow(unused_variables)]
+pub fn increment_stackerdb_rejected_chunks_counter(contract: &str, reason: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::STACKERDB_REJECTED_CHUNKS_COUNTER
+        .with_label_values(&[contract, reason])
+        .inc();
+}
+
 pub fn increment_stx_blocks_processed_counter() {
     #[cfg(feature = "monitoring_prom")]
     prometheus::STX_BLOCKS_PROCESSED_COUNTER.inc();
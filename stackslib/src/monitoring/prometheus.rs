@@ -17,8 +17,9 @@
 use lazy_static::lazy_static;
 use prometheus::{
     histogram_opts, labels, opts, register_gauge, register_histogram, register_histogram_vec,
-    register_int_counter, register_int_counter_vec, register_int_gauge, Gauge, Histogram,
-    HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    Gauge, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec,
 };
 
 lazy_static! {
@@ -74,6 +75,70 @@ lazy_static! {
         "Total number of ops (key registrations, block commits, user burn supports) submitted to the burnchain"
     )).unwrap();
 
+    pub static ref BTC_FEE_RATE_DECISIONS_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_btc_fee_rate_decisions_total",
+        "Total number of fee rate decisions made for burnchain ops, by op and by what determined the fee rate",
+        &["op", "source"]
+    ).unwrap();
+
+    pub static ref BTC_FEE_RATE_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_btc_fee_rate_sats_per_vbyte",
+        "Fee rate (sats/vbyte) chosen for the last burnchain op of each kind",
+        &["op"]
+    ).unwrap();
+
+    pub static ref BTC_FEE_ESTIMATE_GAUGE: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_btc_fee_estimate_sats_per_vbyte",
+        "Last fee rate (sats/vbyte) estimated by bitcoind for the configured confirmation target"
+    )).unwrap();
+
+    pub static ref BTC_MEMPOOL_MIN_FEE_GAUGE: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_btc_mempool_min_fee_sats_per_vbyte",
+        "Last minimum fee rate (sats/vbyte) for admission into bitcoind's mempool"
+    )).unwrap();
+
+    pub static ref BTC_PEER_TIP_ALERTS_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_btc_peer_tip_alerts_total",
+        "Total number of times a bitcoin peer disagreed with the node's burnchain headers about the chain tip, by peer",
+        &["peer"]
+    ).unwrap();
+
+    pub static ref BTC_PEER_HEIGHT_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_btc_peer_height",
+        "Last chain height advertised by each bitcoin peer the node syncs headers from",
+        &["peer"]
+    ).unwrap();
+
+    pub static ref STACKERDB_CONNECTED_REPLICAS_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_stackerdb_connected_replicas",
+        "Number of replicas whose chunk inventories were obtained in the last sync round of each StackerDB",
+        &["contract"]
+    ).unwrap();
+
+    pub static ref STACKERDB_SLOTS_BEHIND_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_stackerdb_slots_behind",
+        "Number of slots of each StackerDB for which some replica had a newer version than this node",
+        &["contract"]
+    ).unwrap();
+
+    pub static ref STACKERDB_LAST_SYNC_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "stacks_node_stackerdb_last_sync_timestamp_seconds",
+        "When the last sync round of each StackerDB that reached at least one replica finished",
+        &["contract"]
+    ).unwrap();
+
+    pub static ref STACKERDB_BYTES_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_stackerdb_bytes_total",
+        "Total bytes of StackerDB chunk data pulled from or pushed to replicas, by StackerDB and by direction",
+        &["contract", "direction"]
+    ).unwrap();
+
+    pub static ref STACKERDB_REJECTED_CHUNKS_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_stackerdb_rejected_chunks_total",
+        "Total number of StackerDB chunks from remote peers that were not stored, by StackerDB and by reason",
+        &["contract", "reason"]
+    ).unwrap();
+
     pub static ref STX_BLOCKS_PROCESSED_COUNTER: IntCounter = register_int_counter!(opts!(
         "stacks_node_stx_blocks_processed_total",
         "Total number of stacks blocks processed"
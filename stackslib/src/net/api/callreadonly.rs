@@ -32,6 +32,7 @@ use clarity::vm::types::{
 };
 use clarity::vm::{ClarityName, ClarityVersion, ContractName, SymbolicExpression, Value};
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
@@ -43,13 +44,13 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpContentType, HttpNotFound, HttpRequest,
+    parse_json, Error, HttpBadRequest, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
     HttpRequestContents, HttpRequestPayload, HttpRequestPreamble, HttpResponse,
     HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    request, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -86,6 +87,10 @@ pub struct RPCCallReadOnlyRequestHandler {
     pub sender: Option<PrincipalData>,
     pub sponsor: Option<PrincipalData>,
     pub arguments: Option<Vec<Value>>,
+    /// Caller's remaining read-only execution budget, if it has one
+    pub runtime_budget: Option<u64>,
+    /// Cost of the last call
+    pub execution_cost: Option<ExecutionCost>,
 }
 
 impl RPCCallReadOnlyRequestHandler {
@@ -98,6 +103,8 @@ impl RPCCallReadOnlyRequestHandler {
             sender: None,
             sponsor: None,
             arguments: None,
+            runtime_budget: None,
+            execution_cost: None,
         }
     }
 }
@@ -175,6 +182,70 @@ impl HttpRequest for RPCCallReadOnlyRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/contracts/call-read/{address}/{contract}/{function}",
+                "Call a read-only function",
+            )
+            .description(
+                "Call a read-only public function on a given smart contract.  The function \
+                 arguments are hex-encoded serialized Clarity values.  The call is subject to \
+                 the node's read-only execution budget.",
+            )
+            .tag("Smart Contracts")
+            .contract_params()
+            .path_param(
+                "function",
+                "Name of the read-only function to call",
+                json!({ "type": "string" }),
+                "get-pox-info",
+            )
+            .tip_param()
+            .body(
+                HttpContentType::JSON,
+                json!({
+                    "type": "object",
+                    "required": ["sender", "arguments"],
+                    "properties": {
+                        "sender": {
+                            "type": "string",
+                            "description": "Principal to use as `tx-sender`"
+                        },
+                        "sponsor": {
+                            "type": "string",
+                            "description": "Principal to use as `tx-sponsor?`"
+                        },
+                        "arguments": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Hex-encoded serialized Clarity values"
+                        }
+                    }
+                }),
+            )
+            .json_response(
+                200,
+                "The result of the call.  `okay` is false if the call failed, in which case \
+                 `cause` explains why.",
+                json!({
+                    "type": "object",
+                    "required": ["okay"],
+                    "properties": {
+                        "okay": { "type": "boolean" },
+                        "result": {
+                            "type": "string",
+                            "description": "Hex-encoded serialized Clarity value"
+                        },
+                        "cause": { "type": "string" }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The chain tip was not found"),
+        )
+    }
 }
 
 /// Handle the HTTP request
@@ -186,6 +257,20 @@ impl RPCRequestHandler for RPCCallReadOnlyRequestHandler {
         self.sender = None;
         self.sponsor = None;
         self.arguments = None;
+        self.runtime_budget = None;
+        self.execution_cost = None;
+    }
+
+    fn is_metered(&self) -> bool {
+        true
+    }
+
+    fn set_runtime_budget(&mut self, runtime: u64) {
+        self.runtime_budget = Some(runtime);
+    }
+
+    fn take_execution_cost(&mut self) -> Option<ExecutionCost> {
+        self.execution_cost.take()
     }
 
     /// Make the response
@@ -221,6 +306,7 @@ impl RPCRequestHandler for RPCCallReadOnlyRequestHandler {
             .ok_or(NetError::SendError("Missing `arguments`".into()))?;
 
         // run the read-only call
+        let mut execution_cost = None;
         let data_resp =
             node.with_node_state(|_network, sortdb, chainstate, _mempool, _rpc_args| {
                 let args: Vec<_> = arguments
@@ -233,6 +319,9 @@ impl RPCRequestHandler for RPCCallReadOnlyRequestHandler {
                 let mut cost_limit = self.read_only_call_limit.clone();
                 cost_limit.write_length = 0;
                 cost_limit.write_count = 0;
+                if let Some(runtime_budget) = self.runtime_budget {
+                    cost_limit.runtime = cost_limit.runtime.min(runtime_budget);
+                }
 
                 chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), &tip, |clarity_tx| {
                     let epoch = clarity_tx.get_epoch();
@@ -271,16 +360,19 @@ impl RPCRequestHandler for RPCCallReadOnlyRequestHandler {
                             // can be called, and also circumvents limitations on `define-read-only`
                             // functions that can not use `contrac-call?`, even when calling other
                             // read-only functions
-                            env.execute_contract(
+                            let result = env.execute_contract(
                                 &contract_identifier,
                                 function.as_str(),
                                 &args,
                                 false,
-                            )
+                            );
+                            execution_cost = Some(env.global_context.cost_track.get_total());
+                            result
                         },
                     )
                 })
             });
+        self.execution_cost = execution_cost;
 
         // decode the response
         let data_resp = match data_resp {
@@ -23,6 +23,7 @@ use clarity::vm::representations::PRINCIPAL_DATA_REGEX_STRING;
 use clarity::vm::types::{PrincipalData, StandardPrincipalData};
 use clarity::vm::ClarityVersion;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::{to_hex, Sha256Sum};
@@ -34,12 +35,13 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpEndpointDoc, HttpNotFound, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -116,6 +118,43 @@ impl HttpRequest for RPCGetAccountRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/accounts/{principal}", "Get account info")
+                .description(
+                    "Get the account data for the provided principal.  Balances are hex-encoded \
+                     128-bit integers, in microSTX.",
+                )
+                .tag("Accounts")
+                .path_param(
+                    "principal",
+                    "Stacks address or contract identifier",
+                    json!({ "type": "string" }),
+                    "SP000000000000000000002Q6VF78",
+                )
+                .tip_param()
+                .proof_param()
+                .json_response(
+                    200,
+                    "The account's balance and nonce",
+                    json!({
+                        "type": "object",
+                        "required": ["balance", "locked", "unlock_height", "nonce"],
+                        "properties": {
+                            "balance": { "type": "string" },
+                            "locked": { "type": "string" },
+                            "unlock_height": { "type": "integer" },
+                            "nonce": { "type": "integer" },
+                            "balance_proof": { "type": "string" },
+                            "nonce_proof": { "type": "string" }
+                        }
+                    }),
+                )
+                .error_response(400, "The request was malformed")
+                .error_response(404, "The chain tip was not found"),
+        )
+    }
 }
 
 /// Handle the HTTP request
@@ -0,0 +1,353 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clarity::vm::representations::PRINCIPAL_DATA_REGEX_STRING;
+use clarity::vm::types::PrincipalData;
+use regex::{Captures, Regex};
+use serde_json::json;
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::types::net::PeerHost;
+
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::db::txindex::{PrincipalTransaction, PrincipalTxCursor};
+use crate::net::http::{
+    parse_json, Error, HttpBadRequest, HttpEndpointDoc, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+};
+use crate::net::httpcore::{
+    hex_schema, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::{Error as NetError, StacksNodeState, TipRequest};
+
+/// Number of transactions returned per page if the client does not ask for a page size
+pub const DEFAULT_ADDRESS_TRANSACTIONS_PAGE_SIZE: u32 = 50;
+/// Maximum number of transactions returned per page
+pub const MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressTransactionsResponse {
+    pub principal: String,
+    /// The block whose fork was searched
+    pub tip: StacksBlockId,
+    /// Transactions that touched the principal, newest first
+    pub transactions: Vec<PrincipalTransaction>,
+    /// Pass this as the `cursor` query argument to get the next page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct RPCGetAddressTransactionsRequestHandler {
+    pub principal: Option<PrincipalData>,
+    pub limit: Option<u32>,
+    pub cursor: Option<PrincipalTxCursor>,
+}
+
+impl RPCGetAddressTransactionsRequestHandler {
+    pub fn new() -> Self {
+        Self {
+            principal: None,
+            limit: None,
+            cursor: None,
+        }
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCGetAddressTransactionsRequestHandler {
+    fn verb(&self) -> &'static str {
+        "GET"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!(
+            "^/v3/addresses/(?P<principal>{})/transactions$",
+            *PRINCIPAL_DATA_REGEX_STRING
+        ))
+        .unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v3/addresses/:principal/transactions"
+    }
+
+    /// Try to decode this request.
+    /// The page is selected with the optional `limit` and `cursor` query arguments.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        if preamble.get_content_length() != 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+
+        let principal = if let Some(value) = captures.name("principal") {
+            PrincipalData::parse(value.into())
+                .map_err(|_e| Error::DecodeError("Failed to parse `principal` field".to_string()))?
+        } else {
+            return Err(Error::DecodeError(
+                "Missing in request path: `principal`".into(),
+            ));
+        };
+
+        let contents = HttpRequestContents::new().query_string(query);
+
+        let limit = match contents.get_query_arg("limit") {
+            Some(limit_str) => {
+                let limit = limit_str
+                    .parse::<u32>()
+                    .map_err(|_e| Error::DecodeError("Failed to parse `limit`".to_string()))?;
+                if limit == 0 || limit > MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE {
+                    return Err(Error::DecodeError(format!(
+                        "Invalid `limit`: expected between 1 and {}",
+                        MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE
+                    )));
+                }
+                limit
+            }
+            None => DEFAULT_ADDRESS_TRANSACTIONS_PAGE_SIZE,
+        };
+
+        let cursor = match contents.get_query_arg("cursor") {
+            Some(cursor_str) => Some(
+                cursor_str
+                    .parse::<PrincipalTxCursor>()
+                    .map_err(|e| Error::DecodeError(e))?,
+            ),
+            None => None,
+        };
+
+        self.principal = Some(principal);
+        self.limit = Some(limit);
+        self.cursor = cursor;
+
+        Ok(contents)
+    }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v3/addresses/{principal}/transactions",
+                "Get the transactions that involve a principal",
+            )
+            .description(
+                "Get a page of the confirmed transactions that involve the given principal, \
+                 newest first.  Only available if the node maintains a principal transaction \
+                 index.",
+            )
+            .tag("Transactions")
+            .path_param(
+                "principal",
+                "Stacks address or contract identifier",
+                json!({ "type": "string" }),
+                "SP000000000000000000002Q6VF78",
+            )
+            .tip_param()
+            .query_param(
+                "limit",
+                &format!(
+                    "Maximum number of transactions to return.  Defaults to {}.",
+                    DEFAULT_ADDRESS_TRANSACTIONS_PAGE_SIZE
+                ),
+                false,
+                json!({
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE
+                }),
+            )
+            .query_param(
+                "cursor",
+                "The `next_cursor` value from the previous page",
+                false,
+                json!({ "type": "string" }),
+            )
+            .json_response(
+                200,
+                "A page of transactions",
+                json!({
+                    "type": "object",
+                    "required": ["principal", "tip", "transactions"],
+                    "properties": {
+                        "principal": { "type": "string" },
+                        "tip": hex_schema(32),
+                        "transactions": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["txid", "index_block_hash", "block_height", "tx_index"],
+                                "properties": {
+                                    "txid": hex_schema(32),
+                                    "index_block_hash": hex_schema(32),
+                                    "block_height": { "type": "integer" },
+                                    "tx_index": { "type": "integer" }
+                                }
+                            }
+                        },
+                        "next_cursor": {
+                            "type": "string",
+                            "nullable": true,
+                            "description": "Cursor for the next page, or null if this is the last page"
+                        }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed, or the index is not maintained")
+            .error_response(404, "The chain tip was not found"),
+        )
+    }
+}
+
+/// Handle the HTTP request
+impl RPCRequestHandler for RPCGetAddressTransactionsRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.principal = None;
+        self.limit = None;
+        self.cursor = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let principal = self
+            .principal
+            .take()
+            .ok_or(NetError::SendError("Missing `principal`".into()))?;
+        let limit = self
+            .limit
+            .take()
+            .ok_or(NetError::SendError("Missing `limit`".into()))?;
+        let cursor_opt = self.cursor.take();
+
+        let tip = match node.load_stacks_chain_tip(&preamble, &contents) {
+            Ok(tip) => tip,
+            Err(error_resp) => {
+                return error_resp.try_into_contents().map_err(NetError::from);
+            }
+        };
+
+        let txs_res = node.with_node_state(|_network, _sortdb, chainstate, _mempool, _rpc_args| {
+            if !chainstate.principal_tx_index {
+                return Ok(Err(StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpBadRequest::new(
+                        "This node does not maintain a principal transaction index".to_string(),
+                    ),
+                )));
+            }
+            let Some(tip_header) = NakamotoChainState::get_block_header(chainstate.db(), &tip)?
+            else {
+                return Ok(Err(StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpNotFound::new(format!("No such block {}", &tip)),
+                )));
+            };
+            let cursor =
+                cursor_opt.unwrap_or(PrincipalTxCursor::at_height(tip_header.stacks_block_height));
+            chainstate
+                .get_principal_transactions(&tip, &principal, cursor, limit)
+                .map(Ok)
+        });
+
+        let (transactions, next_cursor) = match txs_res {
+            Ok(Ok(page)) => page,
+            Ok(Err(error_resp)) => {
+                return error_resp.try_into_contents().map_err(NetError::from);
+            }
+            Err(e) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpServerError::new(format!(
+                        "Failed to load transactions for {}: {:?}",
+                        &principal, &e
+                    )),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+        };
+
+        let resp = AddressTransactionsResponse {
+            principal: principal.to_string(),
+            tip,
+            transactions,
+            next_cursor: next_cursor.map(|cursor| cursor.to_string()),
+        };
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&resp)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCGetAddressTransactionsRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let resp: AddressTransactionsResponse = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(resp)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request for a page of the transactions that touched a principal
+    pub fn new_get_address_transactions(
+        host: PeerHost,
+        principal: PrincipalData,
+        limit: Option<u32>,
+        cursor: Option<String>,
+        tip_req: TipRequest,
+    ) -> StacksHttpRequest {
+        let mut contents = HttpRequestContents::new().for_tip(tip_req);
+        if let Some(limit) = limit {
+            contents = contents.query_arg("limit".into(), format!("{}", limit));
+        }
+        if let Some(cursor) = cursor {
+            contents = contents.query_arg("cursor".into(), cursor);
+        }
+        StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            format!("/v3/addresses/{}/transactions", &principal),
+            contents,
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_address_transactions(self) -> Result<AddressTransactionsResponse, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let contents_json: serde_json::Value = contents.try_into()?;
+        let resp: AddressTransactionsResponse = serde_json::from_value(contents_json)
+            .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
+        Ok(resp)
+    }
+}
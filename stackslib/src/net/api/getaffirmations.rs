@@ -0,0 +1,286 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use regex::{Captures, Regex};
+use serde_json::json;
+use stacks_common::types::net::PeerHost;
+
+use crate::burnchains::affirmation::{
+    get_reward_cycle_affirmation, AffirmationMap, RewardCycleAffirmation,
+};
+use crate::burnchains::db::BurnchainDB;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::net::http::{
+    parse_json, Error, HttpBadRequest, HttpEndpointDoc, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
+};
+use crate::net::httpcore::{
+    HttpEndpointDocExtensions, HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest,
+    StacksHttpResponse,
+};
+use crate::net::{Error as NetError, StacksNodeState};
+
+/// An operator-set affirmation map override
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AffirmationOverride {
+    pub reward_cycle: u64,
+    pub affirmation_map: AffirmationMap,
+}
+
+/// The node's affirmation maps, and how the anchor block of one reward cycle was affirmed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RPCAffirmationsData {
+    /// The current reward cycle, as of the canonical burnchain tip
+    pub current_reward_cycle: u64,
+    pub heaviest_affirmation_map: AffirmationMap,
+    pub stacks_tip_affirmation_map: AffirmationMap,
+    pub sortition_tip_affirmation_map: AffirmationMap,
+    pub tentative_best_affirmation_map: AffirmationMap,
+    /// Affirmation maps set in the node's `burnchain.affirmation_overrides`
+    pub overrides: Vec<AffirmationOverride>,
+    pub reward_cycle: RewardCycleAffirmation,
+}
+
+#[derive(Clone)]
+pub struct RPCGetAffirmationsRequestHandler {
+    pub reward_cycle: Option<u64>,
+}
+
+impl RPCGetAffirmationsRequestHandler {
+    pub fn new() -> Self {
+        Self { reward_cycle: None }
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCGetAffirmationsRequestHandler {
+    fn verb(&self) -> &'static str {
+        "GET"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(r#"^/v3/affirmations(/(?P<reward_cycle>[0-9]{1,20}))?$"#).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v3/affirmations/:reward_cycle"
+    }
+
+    /// Try to decode this request.
+    /// There's nothing to load here, so just make sure the request is well-formed.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        if preamble.get_content_length() != 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+
+        self.reward_cycle = match captures.name("reward_cycle") {
+            Some(reward_cycle_str) => {
+                Some(reward_cycle_str.as_str().parse::<u64>().map_err(|e| {
+                    Error::DecodeError(format!("Failed to parse reward cycle: {e}"))
+                })?)
+            }
+            None => None,
+        };
+
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v3/affirmations", "Get affirmation maps")
+                .alt_path("/v3/affirmations/{reward_cycle}")
+                .description(
+                    "Get this node's affirmation maps and affirmation map overrides, and how the \
+                     anchor block of the given reward cycle was elected and affirmed on the \
+                     canonical burnchain fork.  If the reward cycle is omitted, the current reward \
+                     cycle is used.",
+                )
+                .tag("Info")
+                .path_param(
+                    "reward_cycle",
+                    "Reward cycle number",
+                    json!({ "type": "integer", "minimum": 1 }),
+                    "80",
+                )
+                .json_response(
+                    200,
+                    "The affirmation maps",
+                    json!({
+                        "type": "object",
+                        "required": [
+                            "current_reward_cycle",
+                            "heaviest_affirmation_map",
+                            "stacks_tip_affirmation_map",
+                            "sortition_tip_affirmation_map",
+                            "tentative_best_affirmation_map",
+                            "overrides",
+                            "reward_cycle"
+                        ],
+                        "properties": {
+                            "current_reward_cycle": { "type": "integer" },
+                            "heaviest_affirmation_map": { "type": "string" },
+                            "stacks_tip_affirmation_map": { "type": "string" },
+                            "sortition_tip_affirmation_map": { "type": "string" },
+                            "tentative_best_affirmation_map": { "type": "string" },
+                            "overrides": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["reward_cycle", "affirmation_map"],
+                                    "properties": {
+                                        "reward_cycle": { "type": "integer" },
+                                        "affirmation_map": { "type": "string" }
+                                    }
+                                }
+                            },
+                            "reward_cycle": { "type": "object" }
+                        }
+                    }),
+                )
+                .error_response(400, "The reward cycle is 0 or in the future")
+                .error_response(500, "The affirmation maps could not be loaded"),
+        )
+    }
+}
+
+impl RPCRequestHandler for RPCGetAffirmationsRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.reward_cycle = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let reward_cycle_opt = self.reward_cycle.take();
+        let affirmations_resp =
+            node.with_node_state(|network, sortdb, _chainstate, _mempool, _rpc_args| {
+                let burnchain = network.get_burnchain();
+                let sort_tip = SortitionDB::get_canonical_burn_chain_tip(sortdb.conn())
+                    .map_err(|e| {
+                        StacksHttpResponse::new_error(
+                            &preamble,
+                            &HttpServerError::new(format!("Failed to load burnchain tip: {e:?}")),
+                        )
+                    })?;
+                let current_reward_cycle = burnchain
+                    .block_height_to_reward_cycle(sort_tip.block_height)
+                    .unwrap_or(0);
+                let reward_cycle = reward_cycle_opt.unwrap_or(current_reward_cycle);
+                if reward_cycle == 0 || reward_cycle > current_reward_cycle {
+                    return Err(StacksHttpResponse::new_error(
+                        &preamble,
+                        &HttpBadRequest::new(format!(
+                            "Reward cycle must be between 1 and {current_reward_cycle}"
+                        )),
+                    ));
+                }
+
+                let load = || {
+                    let burnchain_db = burnchain.open_burnchain_db(false)?;
+                    let overrides =
+                        BurnchainDB::get_override_affirmation_maps(burnchain_db.conn())?
+                            .into_iter()
+                            .map(|(reward_cycle, affirmation_map)| AffirmationOverride {
+                                reward_cycle,
+                                affirmation_map,
+                            })
+                            .collect();
+                    let reward_cycle_affirmation =
+                        get_reward_cycle_affirmation(&burnchain_db, sortdb, burnchain, reward_cycle)?;
+                    Ok::<_, crate::burnchains::Error>(RPCAffirmationsData {
+                        current_reward_cycle,
+                        heaviest_affirmation_map: network.heaviest_affirmation_map.clone(),
+                        stacks_tip_affirmation_map: network.stacks_tip_affirmation_map.clone(),
+                        sortition_tip_affirmation_map: network
+                            .sortition_tip_affirmation_map
+                            .clone(),
+                        tentative_best_affirmation_map: network
+                            .tentative_best_affirmation_map
+                            .clone(),
+                        overrides,
+                        reward_cycle: reward_cycle_affirmation,
+                    })
+                };
+                load().map_err(|e| {
+                    warn!("Failed to load affirmation maps"; "reward_cycle" => reward_cycle, "error" => ?e);
+                    StacksHttpResponse::new_error(
+                        &preamble,
+                        &HttpServerError::new("Unable to load affirmation maps".to_string()),
+                    )
+                })
+            });
+
+        let data = match affirmations_resp {
+            Ok(data) => data,
+            Err(response) => {
+                return response.try_into_contents().map_err(NetError::from);
+            }
+        };
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&data)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCGetAffirmationsRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let data: RPCAffirmationsData = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(data)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request for the node's affirmation maps.  If `reward_cycle` is `None`, then the
+    /// current reward cycle is described.
+    pub fn new_get_affirmations(host: PeerHost, reward_cycle: Option<u64>) -> StacksHttpRequest {
+        let path = match reward_cycle {
+            Some(reward_cycle) => format!("/v3/affirmations/{reward_cycle}"),
+            None => "/v3/affirmations".to_string(),
+        };
+        StacksHttpRequest::new_for_peer(host, "GET".into(), path, HttpRequestContents::new())
+            .expect("FATAL: failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_affirmations(self) -> Result<RPCAffirmationsData, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let response_json: serde_json::Value = contents.try_into()?;
+        let data: RPCAffirmationsData = serde_json::from_value(response_json)
+            .map_err(|_e| Error::DecodeError("Failed to decode JSON".to_string()))?;
+        Ok(data)
+    }
+}
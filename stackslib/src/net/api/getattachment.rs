@@ -18,6 +18,7 @@ use std::collections::HashSet;
 use std::io::{Read, Write};
 
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::Hash160;
 use url::form_urlencoded;
@@ -26,12 +27,12 @@ use crate::net::atlas::{
     AttachmentPage, GetAttachmentResponse, MAX_ATTACHMENT_INV_PAGES_PER_REQUEST,
 };
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpNotFound, HttpRequest, HttpRequestContents,
-    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
-    HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpBadRequest, HttpEndpointDoc, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+    hex_schema, HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState};
@@ -92,6 +93,27 @@ impl HttpRequest for RPCGetAttachmentRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/attachments/{attachment_hash}", "Get an attachment")
+                .description("Get the contents of an Atlas attachment, hex-encoded.")
+                .tag("Atlas")
+                .path_param(
+                    "attachment_hash",
+                    "Hash160 of the attachment",
+                    hex_schema(20),
+                    "0123456789abcdef0123456789abcdef01234567",
+                )
+                .json_response(
+                    200,
+                    "The hex-encoded attachment",
+                    json!({ "type": "string", "pattern": "^([0-9a-f]{2})*$" }),
+                )
+                .error_response(400, "The request was malformed")
+                .error_response(404, "The attachment was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCGetAttachmentRequestHandler {
@@ -18,6 +18,7 @@ use std::collections::HashSet;
 use std::io::{Read, Write};
 
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{ConsensusHash, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use url::form_urlencoded;
@@ -26,12 +27,12 @@ use crate::net::atlas::{
     AttachmentPage, GetAttachmentsInvResponse, MAX_ATTACHMENT_INV_PAGES_PER_REQUEST,
 };
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpNotFound, HttpRequest, HttpRequestContents,
-    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
-    HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpBadRequest, HttpEndpointDoc, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+    hex_schema, HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState};
@@ -128,6 +129,60 @@ impl HttpRequest for RPCGetAttachmentsInvRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/attachments/inv", "Get an attachments inventory")
+                .description(
+                    "Get the inventory of Atlas attachments available at the given pages, as of \
+                     the given Stacks block.",
+                )
+                .tag("Atlas")
+                .query_param(
+                    "index_block_hash",
+                    "Index block hash of the Stacks block to query",
+                    true,
+                    hex_schema(32),
+                )
+                .query_param(
+                    "pages_indexes",
+                    &format!(
+                        "Comma-separated list of up to {} page indexes",
+                        MAX_ATTACHMENT_INV_PAGES_PER_REQUEST
+                    ),
+                    true,
+                    json!({ "type": "string", "pattern": "^[0-9]+(,[0-9]+)*$" }),
+                )
+                .json_response(
+                    200,
+                    "The inventory of each requested page",
+                    json!({
+                        "type": "object",
+                        "required": ["block_id", "pages"],
+                        "properties": {
+                            "block_id": hex_schema(32),
+                            "pages": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["index", "inventory"],
+                                    "properties": {
+                                        "index": { "type": "integer" },
+                                        "inventory": {
+                                            "type": "array",
+                                            "items": { "type": "integer", "enum": [0, 1] },
+                                            "description": "1 for each available attachment, 0 otherwise"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }),
+                )
+                .error_response(400, "The request was malformed")
+                .error_response(404, "The Atlas DB could not be read"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCGetAttachmentsInvRequestHandler {
@@ -19,22 +19,23 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::{fs, io};
 
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksBlock};
 use crate::net::http::{
-    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
+    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
 };
 use crate::net::httpcore::{
-    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    hex_schema, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
     StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
@@ -122,6 +123,28 @@ impl HttpRequest for RPCBlocksRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/blocks/{block_id}", "Get a Stacks 2.x block")
+                .description("Get the consensus-serialized bytes of a Stacks 2.x block.")
+                .tag("Blocks")
+                .path_param(
+                    "block_id",
+                    "Index block hash of the block",
+                    hex_schema(32),
+                    "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+                )
+                .response(
+                    200,
+                    "The serialized block",
+                    HttpContentType::Bytes,
+                    json!({ "type": "string", "format": "binary" }),
+                )
+                .error_response(400, "The request was malformed")
+                .error_response(404, "The block was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCBlocksRequestHandler {
@@ -28,6 +28,7 @@ use clarity::vm::representations::{
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData};
 use clarity::vm::{ClarityName, ClarityVersion, ContractName};
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
@@ -39,12 +40,13 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpEndpointDoc, HttpNotFound, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    request, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -104,6 +106,49 @@ impl HttpRequest for RPCGetContractAbiRequestHandler {
         let contents = HttpRequestContents::new().query_string(query);
         Ok(contents)
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/contracts/interface/{address}/{contract}",
+                "Get a contract's interface",
+            )
+            .description(
+                "Get the interface of a smart contract: its functions, variables, maps, and \
+                 tokens, with their Clarity types.",
+            )
+            .tag("Smart Contracts")
+            .contract_params()
+            .tip_param()
+            .json_response(
+                200,
+                "The contract interface",
+                json!({
+                    "type": "object",
+                    "required": [
+                        "functions",
+                        "variables",
+                        "maps",
+                        "fungible_tokens",
+                        "non_fungible_tokens",
+                        "epoch",
+                        "clarity_version"
+                    ],
+                    "properties": {
+                        "functions": { "type": "array", "items": { "type": "object" } },
+                        "variables": { "type": "array", "items": { "type": "object" } },
+                        "maps": { "type": "array", "items": { "type": "object" } },
+                        "fungible_tokens": { "type": "array", "items": { "type": "object" } },
+                        "non_fungible_tokens": { "type": "array", "items": { "type": "object" } },
+                        "epoch": { "type": "string" },
+                        "clarity_version": { "type": "string" }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The contract or the chain tip was not found"),
+        )
+    }
 }
 
 /// Handle the HTTP request
@@ -26,6 +26,7 @@ use clarity::vm::representations::{
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData};
 use clarity::vm::{ClarityName, ClarityVersion, ContractName};
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
@@ -37,12 +38,13 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpEndpointDoc, HttpNotFound, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    request, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -114,6 +116,46 @@ impl HttpRequest for RPCGetDataVarRequestHandler {
         let contents = HttpRequestContents::new().query_string(query);
         Ok(contents)
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/data_var/{address}/{contract}/{varname}",
+                "Get the value of a data variable",
+            )
+            .description(
+                "Get the value of a data variable defined in a smart contract, as a hex-encoded \
+                 serialized Clarity value.",
+            )
+            .tag("Smart Contracts")
+            .contract_params()
+            .path_param(
+                "varname",
+                "Name of the data variable",
+                json!({ "type": "string" }),
+                "configured",
+            )
+            .tip_param()
+            .proof_param()
+            .json_response(
+                200,
+                "The variable's value",
+                json!({
+                    "type": "object",
+                    "required": ["data"],
+                    "properties": {
+                        "data": {
+                            "type": "string",
+                            "description": "Hex-encoded serialized Clarity value"
+                        },
+                        "proof": { "type": "string" }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The variable or the chain tip was not found"),
+        )
+    }
 }
 
 /// Handle the HTTP request
@@ -17,22 +17,23 @@
 use std::io::{Read, Write};
 
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::{ExtendedStacksHeader, StacksChainState};
 use crate::chainstate::stacks::Error as ChainError;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
-    StacksHttpResponse,
+    hex_schema, request, HttpEndpointDocExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
 use crate::util_lib::db::{DBConn, Error as DBError};
@@ -135,6 +136,45 @@ impl HttpRequest for RPCHeadersRequestHandler {
 
         Ok(contents)
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/headers/{quantity}", "Get Stacks 2.x block headers")
+                .description(
+                    "Get up to `quantity` Stacks 2.x block headers, starting from the chain tip \
+                     and walking back towards genesis.",
+                )
+                .tag("Blocks")
+                .path_param(
+                    "quantity",
+                    &format!("Number of headers to fetch, at most {}", MAX_HEADERS),
+                    json!({ "type": "integer", "minimum": 0, "maximum": MAX_HEADERS }),
+                    "10",
+                )
+                .tip_param()
+                .json_response(
+                    200,
+                    "The headers, newest first",
+                    json!({
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["consensus_hash", "header", "parent_block_id"],
+                            "properties": {
+                                "consensus_hash": hex_schema(20),
+                                "header": {
+                                    "type": "string",
+                                    "description": "Hex-encoded serialized block header"
+                                },
+                                "parent_block_id": hex_schema(32)
+                            }
+                        }
+                    }),
+                )
+                .error_response(400, "The request was malformed")
+                .error_response(404, "The chain tip was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCHeadersRequestHandler {
@@ -17,6 +17,7 @@
 use std::io::{Read, Write};
 
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksBlockId, StacksPublicKey,
 };
@@ -30,11 +31,11 @@ use crate::chainstate::burn::db::sortdb::SortitionDB;
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
-    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+    parse_json, Error, HttpEndpointDoc, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+    hex_schema, HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState};
@@ -199,6 +200,78 @@ impl HttpRequest for RPCPeerInfoRequestHandler {
         }
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/info", "Get core node info")
+                .description("Get information about the node and the state of its chains.")
+                .tag("Info")
+                .json_response(
+                    200,
+                    "The node's information",
+                    json!({
+                        "type": "object",
+                        "required": [
+                            "peer_version",
+                            "pox_consensus",
+                            "burn_block_height",
+                            "stable_pox_consensus",
+                            "stable_burn_block_height",
+                            "server_version",
+                            "network_id",
+                            "parent_network_id",
+                            "stacks_tip_height",
+                            "stacks_tip",
+                            "stacks_tip_consensus_hash",
+                            "genesis_chainstate_hash"
+                        ],
+                        "properties": {
+                            "peer_version": { "type": "integer" },
+                            "pox_consensus": hex_schema(20),
+                            "burn_block_height": { "type": "integer" },
+                            "stable_pox_consensus": hex_schema(20),
+                            "stable_burn_block_height": { "type": "integer" },
+                            "server_version": { "type": "string" },
+                            "network_id": { "type": "integer" },
+                            "parent_network_id": { "type": "integer" },
+                            "stacks_tip_height": { "type": "integer" },
+                            "stacks_tip": hex_schema(32),
+                            "stacks_tip_consensus_hash": hex_schema(20),
+                            "genesis_chainstate_hash": hex_schema(32),
+                            "unanchored_tip": {
+                                "type": "string",
+                                "nullable": true,
+                                "pattern": "^[0-9a-f]{64}$"
+                            },
+                            "unanchored_seq": { "type": "integer", "nullable": true },
+                            "exit_at_block_height": { "type": "integer", "nullable": true },
+                            "node_public_key": hex_schema(33),
+                            "node_public_key_hash": hex_schema(20),
+                            "affirmations": {
+                                "type": "object",
+                                "properties": {
+                                    "heaviest": { "type": "string" },
+                                    "stacks_tip": { "type": "string" },
+                                    "sortition_tip": { "type": "string" },
+                                    "tentative_best": { "type": "string" }
+                                }
+                            },
+                            "last_pox_anchor": {
+                                "type": "object",
+                                "properties": {
+                                    "anchor_block_hash": hex_schema(32),
+                                    "anchor_block_txid": hex_schema(32)
+                                }
+                            },
+                            "stackerdbs": {
+                                "type": "array",
+                                "items": { "type": "string" }
+                            }
+                        }
+                    }),
+                ),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCPeerInfoRequestHandler {
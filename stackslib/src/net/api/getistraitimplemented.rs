@@ -28,6 +28,7 @@ use clarity::vm::types::{
 };
 use clarity::vm::{ClarityName, ClarityVersion, ContractName};
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
@@ -39,12 +40,13 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpEndpointDoc, HttpNotFound, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    request, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -116,6 +118,53 @@ impl HttpRequest for RPCGetIsTraitImplementedRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/traits/{address}/{contract}/{traitContractAddr}/{traitContractName}/{traitName}",
+                "Check whether a contract implements a trait",
+            )
+            .description(
+                "Determine whether a smart contract is compliant with a trait defined in another \
+                 smart contract.",
+            )
+            .tag("Smart Contracts")
+            .contract_params()
+            .path_param(
+                "traitContractAddr",
+                "Stacks address of the trait's contract deployer",
+                json!({ "type": "string" }),
+                "SP000000000000000000002Q6VF78",
+            )
+            .path_param(
+                "traitContractName",
+                "Name of the trait's contract",
+                json!({ "type": "string" }),
+                "sip-010-trait",
+            )
+            .path_param(
+                "traitName",
+                "Name of the trait",
+                json!({ "type": "string" }),
+                "sip-010-trait",
+            )
+            .tip_param()
+            .json_response(
+                200,
+                "Whether the trait is implemented",
+                json!({
+                    "type": "object",
+                    "required": ["is_implemented"],
+                    "properties": {
+                        "is_implemented": { "type": "boolean" }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The contract, the trait, or the chain tip was not found"),
+        )
+    }
 }
 
 /// Handle the HTTP request
@@ -29,6 +29,7 @@ use clarity::vm::types::{
 };
 use clarity::vm::{ClarityName, ClarityVersion, ContractName, Value};
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{StacksAddress, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
@@ -40,13 +41,13 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpContentType, HttpNotFound, HttpRequest, HttpRequestContents,
-    HttpRequestPayload, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPayload, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    request, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -136,6 +137,55 @@ impl HttpRequest for RPCGetMapEntryRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/map_entry/{address}/{contract}/{map}",
+                "Get a map entry",
+            )
+            .description(
+                "Get the value stored under a key in a smart contract's data map.  The key is a \
+                 hex-encoded serialized Clarity value, sent as a JSON string.  The value is \
+                 returned as a serialized Clarity optional, which is `none` if there is no entry \
+                 for the key.",
+            )
+            .tag("Smart Contracts")
+            .contract_params()
+            .path_param(
+                "map",
+                "Name of the data map",
+                json!({ "type": "string" }),
+                "stacking-state",
+            )
+            .tip_param()
+            .proof_param()
+            .body(
+                HttpContentType::JSON,
+                json!({
+                    "type": "string",
+                    "description": "Hex-encoded serialized Clarity value of the key"
+                }),
+            )
+            .json_response(
+                200,
+                "The map entry",
+                json!({
+                    "type": "object",
+                    "required": ["data"],
+                    "properties": {
+                        "data": {
+                            "type": "string",
+                            "description": "Hex-encoded serialized Clarity value"
+                        },
+                        "proof": { "type": "string" }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The chain tip was not found"),
+        )
+    }
 }
 
 /// Handle the HTTP request
@@ -19,23 +19,24 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::{fs, io};
 
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{read_next, StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::{BlockHeaderHash, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksBlockHeader, StacksMicroblock};
 use crate::net::http::{
-    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
+    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
 };
 use crate::net::httpcore::{
-    request, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
-    StacksHttpResponse,
+    hex_schema, request, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
+    StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
 use crate::util_lib::db::{DBConn, Error as DBError};
@@ -137,6 +138,34 @@ impl HttpRequest for RPCMicroblocksIndexedRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/microblocks/{tail_microblock_id}",
+                "Get a confirmed microblock stream by its tail",
+            )
+            .description(
+                "Get the stream of confirmed microblocks that ends with the given microblock, \
+                 as a sequence of consensus-serialized microblocks.",
+            )
+            .tag("Microblocks")
+            .path_param(
+                "tail_microblock_id",
+                "Index microblock hash of the last microblock in the stream",
+                hex_schema(32),
+                "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            )
+            .response(
+                200,
+                "The serialized microblocks",
+                HttpContentType::Bytes,
+                json!({ "type": "string", "format": "binary" }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The microblock was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCMicroblocksIndexedRequestHandler {
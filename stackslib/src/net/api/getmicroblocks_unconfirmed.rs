@@ -19,24 +19,25 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::{fs, io};
 
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{read_next, Error as CodecError, StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::{BlockHeaderHash, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
 use stacks_common::util::retry::BoundReader;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksBlockHeader, StacksMicroblock};
 use crate::net::http::{
-    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
-    StacksHttpResponse,
+    hex_schema, request, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
+    StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::{
     Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS, MAX_MICROBLOCKS_UNCONFIRMED,
@@ -139,6 +140,41 @@ impl HttpRequest for RPCMicroblocksUnconfirmedRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/microblocks/unconfirmed/{parent_block_id}/{start_sequence}",
+                "Get unconfirmed microblocks",
+            )
+            .description(
+                "Get the unconfirmed microblocks built on the given Stacks 2.x block, starting \
+                 from the given sequence number, as a sequence of consensus-serialized \
+                 microblocks.",
+            )
+            .tag("Microblocks")
+            .path_param(
+                "parent_block_id",
+                "Index block hash of the block the microblocks are built on",
+                hex_schema(32),
+                "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            )
+            .path_param(
+                "start_sequence",
+                "Sequence number of the first microblock to fetch",
+                json!({ "type": "integer", "minimum": 0, "maximum": u16::MAX }),
+                "0",
+            )
+            .response(
+                200,
+                "The serialized microblocks",
+                HttpContentType::Bytes,
+                json!({ "type": "string", "format": "binary" }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The block was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCMicroblocksUnconfirmedRequestHandler {
@@ -18,16 +18,19 @@ use std::io::{Read, Write};
 
 use clarity::vm::types::QualifiedContractIdentifier;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::net::{PeerAddress, PeerHost};
 use stacks_common::util::hash::Hash160;
 
 use crate::net::db::PeerDB;
 use crate::net::http::{
-    parse_json, Error, HttpContentType, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpVersion,
+    parse_json, Error, HttpContentType, HttpEndpointDoc, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpVersion,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
+    hex_schema, HttpPreambleExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, NeighborKey, StacksNodeState, MAX_NEIGHBORS_DATA_LEN};
@@ -199,6 +202,57 @@ impl HttpRequest for RPCNeighborsRequestHandler {
         }
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        let neighbors = json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "required": [
+                    "network_id",
+                    "peer_version",
+                    "ip",
+                    "port",
+                    "public_key_hash",
+                    "authenticated"
+                ],
+                "properties": {
+                    "network_id": { "type": "integer" },
+                    "peer_version": { "type": "integer" },
+                    "ip": { "type": "string" },
+                    "port": { "type": "integer" },
+                    "public_key_hash": hex_schema(20),
+                    "authenticated": { "type": "boolean" },
+                    "stackerdbs": {
+                        "type": "array",
+                        "items": { "type": "string" }
+                    }
+                }
+            }
+        });
+        Some(
+            HttpEndpointDoc::new("/v2/neighbors", "Get the node's neighbors")
+                .description(
+                    "Get the node's bootstrap peers, a sample of the peers it knows about, and \
+                     its current inbound and outbound peers.",
+                )
+                .tag("Info")
+                .json_response(
+                    200,
+                    "The node's neighbors",
+                    json!({
+                        "type": "object",
+                        "required": ["bootstrap", "sample", "inbound", "outbound"],
+                        "properties": {
+                            "bootstrap": neighbors,
+                            "sample": neighbors,
+                            "inbound": neighbors,
+                            "outbound": neighbors
+                        }
+                    }),
+                ),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCNeighborsRequestHandler {
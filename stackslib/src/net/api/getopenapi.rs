@@ -0,0 +1,144 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::json;
+use stacks_common::types::net::PeerHost;
+
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::{
+    parse_json, Error, HttpEndpointDoc, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse};
+use crate::net::{Error as NetError, StacksNodeState};
+
+lazy_static! {
+    /// The OpenAPI document served by this endpoint.  It only depends on which request handlers
+    /// are registered, so it is generated once.
+    pub static ref RPC_OPENAPI_DOCUMENT: serde_json::Value = StacksHttp::new(
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0),
+        &ConnectionOptions::default()
+    )
+    .openapi_document();
+}
+
+/// The request to GET /v3/openapi.json
+#[derive(Clone)]
+pub struct RPCGetOpenAPIRequestHandler {}
+impl RPCGetOpenAPIRequestHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCGetOpenAPIRequestHandler {
+    fn verb(&self) -> &'static str {
+        "GET"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(r#"^/v3/openapi\.json$"#).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v3/openapi.json"
+    }
+
+    /// Try to decode this request.
+    /// There's nothing to load here, so just make sure the request is well-formed.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        _body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        if preamble.get_content_length() != 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected 0-length body for GetOpenAPI".to_string(),
+            ));
+        }
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v3/openapi.json",
+                "Get the OpenAPI description of this API",
+            )
+            .description(
+                "Get the OpenAPI 3 document describing every RPC endpoint served by this node.",
+            )
+            .tag("Info")
+            .json_response(200, "The OpenAPI document", json!({ "type": "object" })),
+        )
+    }
+}
+
+impl RPCRequestHandler for RPCGetOpenAPIRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {}
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        _node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let preamble = HttpResponsePreamble::ok_json(&preamble);
+        let body = HttpResponseContents::try_from_json(&*RPC_OPENAPI_DOCUMENT)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCGetOpenAPIRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let document: serde_json::Value = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(document)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request for the OpenAPI document
+    pub fn new_getopenapi(host: PeerHost) -> StacksHttpRequest {
+        StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            "/v3/openapi.json".into(),
+            HttpRequestContents::new(),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_openapi_document(self) -> Result<serde_json::Value, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let document: serde_json::Value = contents.try_into()?;
+        Ok(document)
+    }
+}
@@ -21,6 +21,7 @@ use clarity::vm::costs::{ExecutionCost, LimitedCostTracker};
 use clarity::vm::types::{PrincipalData, StandardPrincipalData};
 use clarity::vm::ClarityVersion;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::StacksEpochId;
@@ -34,12 +35,13 @@ use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::core::StacksEpoch;
 use crate::net::http::{
-    parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpEndpointDoc, HttpNotFound, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -461,6 +463,106 @@ impl HttpRequest for RPCPoxInfoRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/pox", "Get PoX details")
+                .description(
+                    "Get Proof-of-Transfer details, such as the active PoX contract, the current \
+                     and next reward cycles, and the epoch schedule.  Amounts are in microSTX.",
+                )
+                .tag("Info")
+                .tip_param()
+                .json_response(
+                    200,
+                    "The PoX details",
+                    json!({
+                        "type": "object",
+                        "required": [
+                            "contract_id",
+                            "first_burnchain_block_height",
+                            "current_burnchain_block_height",
+                            "current_cycle",
+                            "next_cycle",
+                            "epochs",
+                            "contract_versions"
+                        ],
+                        "properties": {
+                            "contract_id": { "type": "string" },
+                            "pox_activation_threshold_ustx": { "type": "integer" },
+                            "first_burnchain_block_height": { "type": "integer" },
+                            "current_burnchain_block_height": { "type": "integer" },
+                            "prepare_phase_block_length": { "type": "integer" },
+                            "reward_phase_block_length": { "type": "integer" },
+                            "reward_slots": { "type": "integer" },
+                            "rejection_fraction": { "type": "integer", "nullable": true },
+                            "total_liquid_supply_ustx": { "type": "integer" },
+                            "current_cycle": {
+                                "type": "object",
+                                "properties": {
+                                    "id": { "type": "integer" },
+                                    "min_threshold_ustx": { "type": "integer" },
+                                    "stacked_ustx": { "type": "integer" },
+                                    "is_pox_active": { "type": "boolean" }
+                                }
+                            },
+                            "next_cycle": {
+                                "type": "object",
+                                "properties": {
+                                    "id": { "type": "integer" },
+                                    "min_threshold_ustx": { "type": "integer" },
+                                    "min_increment_ustx": { "type": "integer" },
+                                    "stacked_ustx": { "type": "integer" },
+                                    "prepare_phase_start_block_height": { "type": "integer" },
+                                    "blocks_until_prepare_phase": { "type": "integer" },
+                                    "reward_phase_start_block_height": { "type": "integer" },
+                                    "blocks_until_reward_phase": { "type": "integer" },
+                                    "ustx_until_pox_rejection": {
+                                        "type": "integer",
+                                        "nullable": true
+                                    }
+                                }
+                            },
+                            "epochs": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "epoch_id": { "type": "string" },
+                                        "start_height": { "type": "integer" },
+                                        "end_height": { "type": "integer" },
+                                        "block_limit": { "type": "object" },
+                                        "network_epoch": { "type": "integer" }
+                                    }
+                                }
+                            },
+                            "min_amount_ustx": { "type": "integer" },
+                            "prepare_cycle_length": { "type": "integer" },
+                            "reward_cycle_id": { "type": "integer" },
+                            "reward_cycle_length": { "type": "integer" },
+                            "rejection_votes_left_required": {
+                                "type": "integer",
+                                "nullable": true
+                            },
+                            "next_reward_cycle_in": { "type": "integer" },
+                            "contract_versions": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "contract_id": { "type": "string" },
+                                        "activation_burnchain_block_height": { "type": "integer" },
+                                        "first_reward_cycle_id": { "type": "integer" }
+                                    }
+                                }
+                            }
+                        }
+                    }),
+                )
+                .error_response(404, "The chain tip was not found")
+                .error_response(500, "The PoX details could not be loaded"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCPoxInfoRequestHandler {
@@ -27,23 +27,24 @@ use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPri
 use clarity::vm::{ClarityName, ContractName};
 use libstackerdb::{SlotMetadata, STACKERDB_MAX_CHUNK_SIZE};
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksBlock};
 use crate::net::http::{
-    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    request, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
 use crate::util_lib::db::{DBConn, Error as DBError};
@@ -110,6 +111,42 @@ impl HttpRequest for RPCGetStackerDBChunkRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/stackerdb/{address}/{contract}/{slot_id}",
+                "Get a StackerDB chunk",
+            )
+            .alt_path("/v2/stackerdb/{address}/{contract}/{slot_id}/{slot_version}")
+            .description(
+                "Get the data stored in a StackerDB slot.  If `slot_version` is given, then the \
+                 chunk is only returned if it has that version.",
+            )
+            .tag("StackerDB")
+            .contract_params()
+            .path_param(
+                "slot_id",
+                "ID of the slot",
+                json!({ "type": "integer", "minimum": 0 }),
+                "0",
+            )
+            .path_param(
+                "slot_version",
+                "Version of the chunk to fetch",
+                json!({ "type": "integer", "minimum": 0 }),
+                "1",
+            )
+            .response(
+                200,
+                "The chunk's data",
+                HttpContentType::Bytes,
+                json!({ "type": "string", "format": "binary" }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The StackerDB or the chunk was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCGetStackerDBChunkRequestHandler {
@@ -26,23 +26,25 @@ use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPri
 use clarity::vm::{ClarityName, ContractName};
 use libstackerdb::SlotMetadata;
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksBlock};
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    hex_schema, request, HttpEndpointDocExtensions, HttpPreambleExtensions,
+    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
 use crate::util_lib::db::{DBConn, Error as DBError};
@@ -97,6 +99,37 @@ impl HttpRequest for RPCGetStackerDBMetadataRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/stackerdb/{address}/{contract}",
+                "Get StackerDB slot metadata",
+            )
+            .description("Get the metadata of every slot in a StackerDB.")
+            .tag("StackerDB")
+            .contract_params()
+            .json_response(
+                200,
+                "The metadata of each slot",
+                json!({
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["slot_id", "slot_version", "data_hash", "signature"],
+                        "properties": {
+                            "slot_id": { "type": "integer" },
+                            "slot_version": { "type": "integer" },
+                            "data_hash": hex_schema(32),
+                            "signature": hex_schema(65)
+                        }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The StackerDB was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCGetStackerDBMetadataRequestHandler {
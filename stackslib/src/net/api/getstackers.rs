@@ -28,13 +28,13 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpNotFound, HttpRequest, HttpRequestContents,
-    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
-    HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpBadRequest, HttpEndpointDoc, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    hex_schema, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttp, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState, TipRequest};
@@ -130,6 +130,71 @@ impl HttpRequest for GetStackersRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/stacker_set/{cycle_num}", "Get the stacker set")
+                .description(
+                    "Get the stacker and signer set information for the given reward cycle.  \
+                     Signer information is only available for Nakamoto reward cycles.",
+                )
+                .tag("Info")
+                .path_param(
+                    "cycle_num",
+                    "Reward cycle number",
+                    json!({ "type": "integer", "minimum": 0 }),
+                    "80",
+                )
+                .tip_param()
+                .json_response(
+                    200,
+                    "The reward set of the cycle",
+                    json!({
+                        "type": "object",
+                        "required": ["stacker_set"],
+                        "properties": {
+                            "stacker_set": {
+                                "type": "object",
+                                "required": ["rewarded_addresses", "start_cycle_state"],
+                                "properties": {
+                                    "rewarded_addresses": {
+                                        "type": "array",
+                                        "items": { "type": "object" }
+                                    },
+                                    "start_cycle_state": { "type": "object" },
+                                    "signers": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "required": ["signing_key", "stacked_amt", "weight"],
+                                            "properties": {
+                                                "signing_key": hex_schema(33),
+                                                "stacked_amt": { "type": "integer" },
+                                                "weight": { "type": "integer" }
+                                            }
+                                        }
+                                    },
+                                    "pox_ustx_threshold": { "type": "integer", "nullable": true }
+                                }
+                            }
+                        }
+                    }),
+                )
+                .json_response(
+                    400,
+                    "The reward set could not be loaded",
+                    json!({
+                        "type": "object",
+                        "required": ["response", "err_msg"],
+                        "properties": {
+                            "response": { "type": "string", "enum": ["error"] },
+                            "err_msg": { "type": "string" }
+                        }
+                    }),
+                )
+                .error_response(404, "The chain tip was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for GetStackersRequestHandler {
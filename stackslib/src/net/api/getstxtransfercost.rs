@@ -18,6 +18,7 @@ use std::io::{Read, Write};
 
 use clarity::vm::costs::ExecutionCost;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksBlockId, StacksPublicKey,
 };
@@ -34,8 +35,9 @@ use crate::chainstate::stacks::db::StacksChainState;
 use crate::core::mempool::MemPoolDB;
 use crate::net::api::postfeerate::RPCPostFeeRateRequestHandler;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble,
+    parse_json, Error, HttpBadRequest, HttpEndpointDoc, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble,
 };
 use crate::net::httpcore::{
     HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
@@ -85,6 +87,20 @@ impl HttpRequest for RPCGetStxTransferCostRequestHandler {
         }
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/fees/transfer", "Get the STX transfer fee rate")
+                .description(
+                    "Get an estimated fee rate for STX transfer transactions, in microSTX per \
+                     byte.  This falls back to the minimum fee rate if the node does not estimate \
+                     fees.",
+                )
+                .tag("Fees")
+                .json_response(200, "The fee rate", json!({ "type": "integer" }))
+                .error_response(500, "The fee rate could not be estimated"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCGetStxTransferCostRequestHandler {
@@ -18,24 +18,25 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::{fs, io};
 
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::{ConsensusHash, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::nakamoto::{NakamotoBlock, NakamotoChainState, NakamotoStagingBlocksConn};
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::Error as ChainError;
 use crate::net::api::getblock_v3::NakamotoBlockStream;
 use crate::net::http::{
-    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
+    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
 };
 use crate::net::httpcore::{
-    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    hex_schema, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
     StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
@@ -199,6 +200,41 @@ impl HttpRequest for RPCNakamotoTenureRequestHandler {
 
         Ok(req_contents)
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v3/tenures/{block_id}",
+                "Get the blocks of a Nakamoto tenure",
+            )
+            .description(
+                "Get the Nakamoto blocks of a tenure, starting from the given block and \
+                     walking back towards the tenure's start block.  The response is a \
+                     sequence of consensus-serialized blocks, newest first.",
+            )
+            .tag("Blocks")
+            .path_param(
+                "block_id",
+                "Index block hash of the highest block to fetch",
+                hex_schema(32),
+                "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            )
+            .query_param(
+                "stop",
+                "Index block hash of a block at which to stop, exclusive",
+                false,
+                hex_schema(32),
+            )
+            .response(
+                200,
+                "The serialized blocks",
+                HttpContentType::Bytes,
+                json!({ "type": "string", "format": "binary" }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The block was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCNakamotoTenureRequestHandler {
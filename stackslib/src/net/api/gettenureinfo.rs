@@ -18,12 +18,13 @@ use std::io::{Read, Seek, SeekFrom, Write};
 use std::{fs, io};
 
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::{ConsensusHash, StacksBlockId};
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::nakamoto::{NakamotoBlock, NakamotoChainState, NakamotoStagingBlocksConn};
 use crate::chainstate::stacks::db::StacksChainState;
@@ -31,11 +32,12 @@ use crate::chainstate::stacks::Error as ChainError;
 use crate::net::api::getblock_v3::NakamotoBlockStream;
 use crate::net::http::{
     parse_bytes, parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType,
-    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
-    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
+    HttpEndpointDoc, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
+    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    HttpVersion,
 };
 use crate::net::httpcore::{
-    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    hex_schema, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
     StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
@@ -101,6 +103,39 @@ impl HttpRequest for RPCNakamotoTenureInfoRequestHandler {
         }
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v3/tenures/info", "Get the current Nakamoto tenure")
+                .description("Get information about the node's current Nakamoto tenure.")
+                .tag("Blocks")
+                .json_response(
+                    200,
+                    "The current tenure",
+                    json!({
+                        "type": "object",
+                        "required": [
+                            "consensus_hash",
+                            "tenure_start_block_id",
+                            "parent_consensus_hash",
+                            "parent_tenure_start_block_id",
+                            "tip_block_id",
+                            "tip_height",
+                            "reward_cycle"
+                        ],
+                        "properties": {
+                            "consensus_hash": hex_schema(20),
+                            "tenure_start_block_id": hex_schema(32),
+                            "parent_consensus_hash": hex_schema(20),
+                            "parent_tenure_start_block_id": hex_schema(32),
+                            "tip_block_id": hex_schema(32),
+                            "tip_height": { "type": "integer" },
+                            "reward_cycle": { "type": "integer" }
+                        }
+                    }),
+                ),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCNakamotoTenureInfoRequestHandler {
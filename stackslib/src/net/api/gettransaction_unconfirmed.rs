@@ -17,6 +17,7 @@
 use std::io::{Read, Write};
 
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::codec::StacksMessageCodec;
 use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksBlockId, StacksPublicKey,
@@ -31,11 +32,13 @@ use crate::chainstate::burn::db::sortdb::SortitionDB;
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::core::mempool::MemPoolDB;
 use crate::net::http::{
-    parse_json, Error, HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble,
-    HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpEndpointDoc, HttpNotFound, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+    hex_schema, request, HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest,
+    StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::{Error as NetError, StacksNodeState};
@@ -100,6 +103,57 @@ impl HttpRequest for RPCGetTransactionUnconfirmedRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/transactions/unconfirmed/{txid}",
+                "Get an unconfirmed transaction",
+            )
+            .description(
+                "Get a transaction that is in the mempool or in an unconfirmed microblock, as a \
+                 hex-encoded serialized transaction.",
+            )
+            .tag("Transactions")
+            .path_param(
+                "txid",
+                "ID of the transaction",
+                hex_schema(32),
+                "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            )
+            .json_response(
+                200,
+                "The transaction and where it was found",
+                json!({
+                    "type": "object",
+                    "required": ["tx", "status"],
+                    "properties": {
+                        "tx": { "type": "string" },
+                        "status": {
+                            "oneOf": [
+                                { "type": "string", "enum": ["Mempool"] },
+                                {
+                                    "type": "object",
+                                    "required": ["Microblock"],
+                                    "properties": {
+                                        "Microblock": {
+                                            "type": "object",
+                                            "properties": {
+                                                "block_hash": hex_schema(32),
+                                                "seq": { "type": "integer" }
+                                            }
+                                        }
+                                    }
+                                }
+                            ]
+                        }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The transaction was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCGetTransactionUnconfirmedRequestHandler {
@@ -24,41 +24,56 @@ use clarity::vm::representations::{
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StandardPrincipalData};
 use clarity::vm::{ClarityName, ContractName};
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::hash::to_hex;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksBlock};
 use crate::net::db::PeerDB;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    hex_schema, request, HttpEndpointDocExtensions, HttpPreambleExtensions,
+    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    StacksHttpResponse,
 };
+use crate::net::stackerdb::StackerDBReplicationStats;
 use crate::net::{Error as NetError, NeighborAddress, StacksNodeState, TipRequest, MAX_HEADERS};
 use crate::util_lib::db::{DBConn, Error as DBError};
 
 /// Largest number of replicas returned
 pub const MAX_LIST_REPLICAS: usize = 64;
 
+/// The replicas of a StackerDB, along with this node's replication health.
+/// Returned instead of the bare list of replicas when the request sets `stats=1`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RPCStackerDBReplicasData {
+    pub replicas: Vec<NeighborAddress>,
+    /// None if this node does not replicate the StackerDB
+    pub stats: Option<StackerDBReplicationStats>,
+}
+
 #[derive(Clone)]
 pub struct RPCListStackerDBReplicasRequestHandler {
     pub contract_identifier: Option<QualifiedContractIdentifier>,
+    /// Whether or not to include this node's replication health
+    pub with_stats: bool,
 }
 
 impl RPCListStackerDBReplicasRequestHandler {
     pub fn new() -> Self {
         Self {
             contract_identifier: None,
+            with_stats: false,
         }
     }
 }
@@ -97,16 +112,103 @@ impl HttpRequest for RPCListStackerDBReplicasRequestHandler {
         }
 
         let contract_identifier = request::get_contract_address(captures, "address", "contract")?;
+        let contents = HttpRequestContents::new().query_string(query);
+
         self.contract_identifier = Some(contract_identifier);
+        self.with_stats = contents.get_query_arg("stats").map(|x| x.as_str()) == Some("1");
 
-        Ok(HttpRequestContents::new().query_string(query))
+        Ok(contents)
+    }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/stackerdb/{address}/{contract}/replicas",
+                "List StackerDB replicas",
+            )
+            .description(
+                "List the addresses of known peers that replicate the given StackerDB, \
+                 including this node if it does.  With `stats=1`, also report how well this \
+                 node's replica is keeping up: the replicas reached in the last sync round, the \
+                 slots for which a replica had a newer version, when the last sync round \
+                 finished, the chunk bytes pulled and pushed, and the chunks from peers that \
+                 were not stored, by reason.",
+            )
+            .tag("StackerDB")
+            .contract_params()
+            .query_param(
+                "stats",
+                "If `1`, return an object with the replicas and this node's replication health \
+                 (`null` if this node does not replicate the StackerDB)",
+                false,
+                json!({ "type": "string", "enum": ["0", "1"] }),
+            )
+            .json_response(
+                200,
+                "The replicas' addresses, and the replication health if requested",
+                json!({
+                    "oneOf": [
+                        replicas_schema(),
+                        {
+                            "type": "object",
+                            "required": ["replicas", "stats"],
+                            "properties": {
+                                "replicas": replicas_schema(),
+                                "stats": {
+                                    "type": "object",
+                                    "nullable": true,
+                                    "required": [
+                                        "connected_replicas",
+                                        "slots_behind",
+                                        "last_sync_ts",
+                                        "bytes_pulled",
+                                        "bytes_pushed",
+                                        "rejected_chunks"
+                                    ],
+                                    "properties": {
+                                        "connected_replicas": { "type": "integer" },
+                                        "slots_behind": { "type": "integer" },
+                                        "last_sync_ts": { "type": "integer" },
+                                        "bytes_pulled": { "type": "integer" },
+                                        "bytes_pushed": { "type": "integer" },
+                                        "rejected_chunks": {
+                                            "type": "object",
+                                            "additionalProperties": { "type": "integer" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    ]
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(500, "The replicas could not be listed"),
+        )
     }
 }
 
+/// Schema of a list of replica addresses
+fn replicas_schema() -> serde_json::Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["ip", "port", "public_key_hash"],
+            "properties": {
+                "ip": { "type": "string" },
+                "port": { "type": "integer" },
+                "public_key_hash": hex_schema(20)
+            }
+        }
+    })
+}
+
 impl RPCRequestHandler for RPCListStackerDBReplicasRequestHandler {
     /// Reset internal state
     fn restart(&mut self) {
         self.contract_identifier = None;
+        self.with_stats = false;
     }
 
     /// Make the response
@@ -121,7 +223,7 @@ impl RPCRequestHandler for RPCListStackerDBReplicasRequestHandler {
             .take()
             .ok_or(NetError::SendError("`contract_identifier` not set".into()))?;
 
-        let (replicas_resp, local_peer, allow_private) =
+        let (replicas_resp, local_peer, allow_private, stats) =
             node.with_node_state(|network, _sortdb, _chainstate, _mempool, _rpc_args| {
                 let replicas_resp = PeerDB::find_stacker_db_replicas(
                     network.peerdb_conn(),
@@ -138,7 +240,8 @@ impl RPCRequestHandler for RPCListStackerDBReplicasRequestHandler {
                     )
                 });
                 let local_peer_resp = network.get_local_peer().clone();
-                (replicas_resp, local_peer_resp, network.get_connection_opts().private_neighbors)
+                let stats = network.get_stackerdb_replication_stats(&contract_identifier);
+                (replicas_resp, local_peer_resp, network.get_connection_opts().private_neighbors, stats)
             });
 
         let mut naddrs = match replicas_resp {
@@ -173,7 +276,14 @@ impl RPCRequestHandler for RPCListStackerDBReplicasRequestHandler {
 
         let mut preamble = HttpResponsePreamble::ok_json(&preamble);
         preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
-        let body = HttpResponseContents::try_from_json(&naddrs)?;
+        let body = if self.with_stats {
+            HttpResponseContents::try_from_json(&RPCStackerDBReplicasData {
+                replicas: naddrs,
+                stats,
+            })?
+        } else {
+            HttpResponseContents::try_from_json(&naddrs)?
+        };
         Ok((preamble, body))
     }
 }
@@ -187,7 +297,10 @@ impl HttpResponse for RPCListStackerDBReplicasRequestHandler {
         preamble: &HttpResponsePreamble,
         body: &[u8],
     ) -> Result<HttpResponsePayload, Error> {
-        let metadata: Vec<NeighborAddress> = parse_json(preamble, body)?;
+        if let Ok(metadata) = parse_json::<Vec<NeighborAddress>>(preamble, body) {
+            return Ok(HttpResponsePayload::try_from_json(metadata)?);
+        }
+        let metadata: RPCStackerDBReplicasData = parse_json(preamble, body)?;
         Ok(HttpResponsePayload::try_from_json(metadata)?)
     }
 }
@@ -208,6 +321,22 @@ impl StacksHttpRequest {
         )
         .expect("FATAL: failed to construct request from infallible data")
     }
+
+    pub fn new_list_stackerdb_replicas_with_stats(
+        host: PeerHost,
+        stackerdb_contract_id: QualifiedContractIdentifier,
+    ) -> StacksHttpRequest {
+        StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            format!(
+                "/v2/stackerdb/{}/{}/replicas",
+                &stackerdb_contract_id.issuer, &stackerdb_contract_id.name
+            ),
+            HttpRequestContents::new().query_arg("stats".into(), "1".into()),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
 }
 
 impl StacksHttpResponse {
@@ -220,4 +349,16 @@ impl StacksHttpResponse {
             .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
         Ok(resp)
     }
+
+    /// Decode an HTTP response into a list of replicas and this node's replication health
+    /// If it fails, return Self::Error(..)
+    pub fn decode_stackerdb_replicas_with_stats(
+        self,
+    ) -> Result<RPCStackerDBReplicasData, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let contents_json: serde_json::Value = contents.try_into()?;
+        let resp: RPCStackerDBReplicasData = serde_json::from_value(contents_json)
+            .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
+        Ok(resp)
+    }
 }
@@ -33,6 +33,8 @@ use crate::stacks_common::codec::StacksMessageCodec;
 
 pub mod callreadonly;
 pub mod getaccount;
+pub mod getaddresstransactions;
+pub mod getaffirmations;
 pub mod getattachment;
 pub mod getattachmentsinv;
 pub mod getblock;
@@ -49,6 +51,7 @@ pub mod getmicroblocks_confirmed;
 pub mod getmicroblocks_indexed;
 pub mod getmicroblocks_unconfirmed;
 pub mod getneighbors;
+pub mod getopenapi;
 pub mod getpoxinfo;
 pub mod getstackerdbchunk;
 pub mod getstackerdbmetadata;
@@ -60,10 +63,12 @@ pub mod gettransaction_unconfirmed;
 pub mod liststackerdbreplicas;
 pub mod postblock;
 pub mod postblock_proposal;
+pub mod postclarityproofs;
 pub mod postfeerate;
 pub mod postmempoolquery;
 pub mod postmicroblock;
 pub mod poststackerdbchunk;
+pub mod poststackerdbsubscribe;
 pub mod posttransaction;
 
 #[cfg(test)]
@@ -78,6 +83,10 @@ impl StacksHttp {
             self.read_only_call_limit.clone(),
         ));
         self.register_rpc_endpoint(getaccount::RPCGetAccountRequestHandler::new());
+        self.register_rpc_endpoint(getaffirmations::RPCGetAffirmationsRequestHandler::new());
+        self.register_rpc_endpoint(
+            getaddresstransactions::RPCGetAddressTransactionsRequestHandler::new(),
+        );
         self.register_rpc_endpoint(getattachment::RPCGetAttachmentRequestHandler::new());
         self.register_rpc_endpoint(getattachmentsinv::RPCGetAttachmentsInvRequestHandler::new());
         self.register_rpc_endpoint(getblock::RPCBlocksRequestHandler::new());
@@ -102,6 +111,7 @@ impl StacksHttp {
             getmicroblocks_unconfirmed::RPCMicroblocksUnconfirmedRequestHandler::new(),
         );
         self.register_rpc_endpoint(getneighbors::RPCNeighborsRequestHandler::new());
+        self.register_rpc_endpoint(getopenapi::RPCGetOpenAPIRequestHandler::new());
         self.register_rpc_endpoint(getstxtransfercost::RPCGetStxTransferCostRequestHandler::new());
         self.register_rpc_endpoint(getstackerdbchunk::RPCGetStackerDBChunkRequestHandler::new());
         self.register_rpc_endpoint(getpoxinfo::RPCPoxInfoRequestHandler::new());
@@ -121,10 +131,14 @@ impl StacksHttp {
         self.register_rpc_endpoint(postblock_proposal::RPCBlockProposalRequestHandler::new(
             self.block_proposal_token.clone(),
         ));
+        self.register_rpc_endpoint(postclarityproofs::RPCPostClarityProofsRequestHandler::new());
         self.register_rpc_endpoint(postfeerate::RPCPostFeeRateRequestHandler::new());
         self.register_rpc_endpoint(postmempoolquery::RPCMempoolQueryRequestHandler::new());
         self.register_rpc_endpoint(postmicroblock::RPCPostMicroblockRequestHandler::new());
         self.register_rpc_endpoint(poststackerdbchunk::RPCPostStackerDBChunkRequestHandler::new());
+        self.register_rpc_endpoint(
+            poststackerdbsubscribe::RPCPostStackerDBSubscribeRequestHandler::new(),
+        );
         self.register_rpc_endpoint(posttransaction::RPCPostTransactionRequestHandler::new());
         self.register_rpc_endpoint(getstackers::GetStackersRequestHandler::default());
     }
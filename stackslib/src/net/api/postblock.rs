@@ -18,6 +18,7 @@ use std::io::{Read, Write};
 
 use clarity::vm::costs::ExecutionCost;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::codec::{Error as CodecError, StacksMessageCodec, MAX_PAYLOAD_LEN};
 use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksBlockId, StacksPublicKey,
@@ -38,12 +39,12 @@ use crate::chainstate::stacks::{
 use crate::core::mempool::MemPoolDB;
 use crate::cost_estimates::FeeRateEstimate;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpContentType, HttpNotFound, HttpRequest,
+    parse_json, Error, HttpBadRequest, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
     HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
     HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler,
+    hex_schema, request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler,
     StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
@@ -134,6 +135,44 @@ impl HttpRequest for RPCPostBlockRequestHandler {
         self.block = Some(block);
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/blocks/upload/{consensus_hash}",
+                "Upload a Stacks 2.x block",
+            )
+            .description(
+                "Upload a consensus-serialized Stacks 2.x block that was mined in the \
+                     sortition with the given consensus hash.",
+            )
+            .tag("Blocks")
+            .path_param(
+                "consensus_hash",
+                "Consensus hash of the block's sortition",
+                hex_schema(20),
+                "0123456789abcdef0123456789abcdef01234567",
+            )
+            .body(
+                HttpContentType::Bytes,
+                json!({ "type": "string", "format": "binary" }),
+            )
+            .json_response(
+                200,
+                "Whether the block was accepted for processing",
+                json!({
+                    "type": "object",
+                    "required": ["stacks_block_id", "accepted"],
+                    "properties": {
+                        "stacks_block_id": hex_schema(32),
+                        "accepted": { "type": "boolean" }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The block's sortition was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCPostBlockRequestHandler {
@@ -21,6 +21,7 @@ use clarity::vm::ast::ASTRules;
 use clarity::vm::costs::ExecutionCost;
 use regex::{Captures, Regex};
 use serde::Deserialize;
+use serde_json::json;
 use stacks_common::codec::{
     read_next, write_next, Error as CodecError, StacksMessageCodec, MAX_PAYLOAD_LEN,
 };
@@ -48,8 +49,8 @@ use crate::chainstate::stacks::{
 use crate::core::mempool::{MemPoolDB, ProposalCallbackReceiver};
 use crate::cost_estimates::FeeRateEstimate;
 use crate::net::http::{
-    http_reason, parse_json, Error, HttpBadRequest, HttpContentType, HttpNotFound, HttpRequest,
-    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    http_reason, parse_json, Error, HttpBadRequest, HttpContentType, HttpEndpointDoc, HttpNotFound,
+    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
     HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
@@ -429,6 +430,68 @@ impl HttpRequest for RPCBlockProposalRequestHandler {
         self.block_proposal = Some(block_proposal);
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/block_proposal", "Validate a proposed Nakamoto block")
+                .description(
+                    "Ask the node to validate a proposed Nakamoto block.  Validation happens in \
+                     the background, and its result is sent to the node's event observer.  This \
+                     endpoint is only enabled if the node is configured with an authorization \
+                     token.",
+                )
+                .tag("Mining")
+                .header_param(
+                    "authorization",
+                    "The node's block proposal authorization token",
+                    true,
+                    json!({ "type": "string" }),
+                )
+                .body(
+                    HttpContentType::JSON,
+                    json!({
+                        "type": "object",
+                        "required": ["block", "chain_id"],
+                        "properties": {
+                            "block": {
+                                "type": "string",
+                                "description": "Hex-encoded serialized Nakamoto block"
+                            },
+                            "chain_id": { "type": "integer" }
+                        }
+                    }),
+                )
+                .json_response(
+                    202,
+                    "The proposal is being validated",
+                    json!({
+                        "type": "object",
+                        "required": ["result", "message"],
+                        "properties": {
+                            "result": { "type": "string", "enum": ["Accepted"] },
+                            "message": { "type": "string" }
+                        }
+                    }),
+                )
+                .error_response(
+                    400,
+                    "The request was malformed, or the endpoint is disabled",
+                )
+                .error_response(401, "The authorization token was missing or wrong")
+                .json_response(
+                    429,
+                    "Another proposal is already being validated",
+                    json!({
+                        "type": "object",
+                        "required": ["result", "message"],
+                        "properties": {
+                            "result": { "type": "string", "enum": ["Error"] },
+                            "message": { "type": "string" }
+                        }
+                    }),
+                ),
+        )
+    }
 }
 
 struct ProposalThreadInfo {
@@ -0,0 +1,374 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clarity::vm::clarity::ClarityConnection;
+use regex::{Captures, Regex};
+use serde_json::json;
+use stacks_common::codec::{StacksMessageCodec, MAX_PAYLOAD_LEN};
+use stacks_common::types::chainstate::StacksBlockId;
+use stacks_common::types::net::PeerHost;
+use stacks_common::util::hash::to_hex;
+
+use crate::chainstate::nakamoto::NakamotoChainState;
+use crate::chainstate::stacks::index::TrieMerkleProof;
+use crate::clarity_vm::proofs::{
+    get_proof_ancestor_blocks, make_anchor_header, ClarityProofEntry, ClarityProofKey,
+    ClarityProofs,
+};
+use crate::net::http::{
+    parse_json, Error, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+};
+use crate::net::httpcore::{
+    HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::{Error as NetError, StacksNodeState, TipRequest};
+
+/// Maximum number of entries that can be proven in one request
+pub const MAX_CLARITY_PROOF_KEYS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClarityProofsRequestBody {
+    pub keys: Vec<ClarityProofKey>,
+}
+
+#[derive(Clone)]
+pub struct RPCPostClarityProofsRequestHandler {
+    pub keys: Option<Vec<(ClarityProofKey, String)>>,
+}
+
+impl RPCPostClarityProofsRequestHandler {
+    pub fn new() -> Self {
+        Self { keys: None }
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCPostClarityProofsRequestHandler {
+    fn verb(&self) -> &'static str {
+        "POST"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(r#"^/v3/clarity/proofs$"#).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v3/clarity/proofs"
+    }
+
+    /// Try to decode this request.
+    /// The body is a JSON object with a list of `ClarityProofKey`s to prove.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        _captures: &Captures,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        let content_len = preamble.get_content_length();
+        if !(content_len > 0 && content_len < MAX_PAYLOAD_LEN) {
+            return Err(Error::DecodeError(format!(
+                "Invalid Http request: invalid body length for ClarityProofs ({})",
+                content_len
+            )));
+        }
+
+        if preamble.content_type != Some(HttpContentType::JSON) {
+            return Err(Error::DecodeError(
+                "Invalid content-type: expected application/json".to_string(),
+            ));
+        }
+
+        let body: ClarityProofsRequestBody = serde_json::from_slice(body)
+            .map_err(|e| Error::DecodeError(format!("Failed to parse JSON body: {}", e)))?;
+
+        if body.keys.len() == 0 || body.keys.len() > MAX_CLARITY_PROOF_KEYS {
+            return Err(Error::DecodeError(format!(
+                "Invalid number of keys: expected between 1 and {}",
+                MAX_CLARITY_PROOF_KEYS
+            )));
+        }
+
+        let mut keys = Vec::with_capacity(body.keys.len());
+        for key in body.keys.into_iter() {
+            let marf_key = key
+                .to_marf_key()
+                .map_err(|e| Error::DecodeError(format!("{}", &e)))?;
+            keys.push((key, marf_key));
+        }
+
+        self.keys = Some(keys);
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v3/clarity/proofs", "Get proofs of Clarity state")
+                .description(
+                    "Get MARF proofs for a batch of Clarity state entries (account balances and \
+                     nonces, data vars, and map entries), together with the block headers a \
+                     light client needs to verify them against a trusted block.",
+                )
+                .tag("Smart Contracts")
+                .tip_param()
+                .body(
+                    HttpContentType::JSON,
+                    json!({
+                        "type": "object",
+                        "required": ["keys"],
+                        "properties": {
+                            "keys": {
+                                "type": "array",
+                                "maxItems": MAX_CLARITY_PROOF_KEYS,
+                                "items": {
+                                    "type": "object",
+                                    "minProperties": 1,
+                                    "maxProperties": 1,
+                                    "properties": {
+                                        "account_balance": {
+                                            "type": "object",
+                                            "required": ["principal"],
+                                            "properties": { "principal": { "type": "string" } }
+                                        },
+                                        "account_nonce": {
+                                            "type": "object",
+                                            "required": ["principal"],
+                                            "properties": { "principal": { "type": "string" } }
+                                        },
+                                        "data_var": {
+                                            "type": "object",
+                                            "required": ["contract", "var"],
+                                            "properties": {
+                                                "contract": { "type": "string" },
+                                                "var": { "type": "string" }
+                                            }
+                                        },
+                                        "map_entry": {
+                                            "type": "object",
+                                            "required": ["contract", "map", "key"],
+                                            "properties": {
+                                                "contract": { "type": "string" },
+                                                "map": { "type": "string" },
+                                                "key": {
+                                                    "type": "string",
+                                                    "description": "Hex-encoded serialized Clarity value"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }),
+                )
+                .json_response(
+                    200,
+                    "The proven entries, in the order they were requested",
+                    json!({
+                        "type": "object",
+                        "required": ["tip", "anchors", "entries"],
+                        "properties": {
+                            "tip": { "type": "object" },
+                            "anchors": { "type": "array", "items": { "type": "object" } },
+                            "entries": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["key", "marf_key"],
+                                    "properties": {
+                                        "key": { "type": "object" },
+                                        "marf_key": { "type": "string" },
+                                        "value": { "type": "string", "nullable": true },
+                                        "proof": { "type": "string", "nullable": true }
+                                    }
+                                }
+                            }
+                        }
+                    }),
+                )
+                .error_response(400, "The request was malformed")
+                .error_response(404, "The chain tip was not found"),
+        )
+    }
+}
+
+/// Handle the HTTP request
+impl RPCRequestHandler for RPCPostClarityProofsRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.keys = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let keys = self
+            .keys
+            .take()
+            .ok_or(NetError::SendError("`keys` not set".into()))?;
+
+        let tip = match node.load_stacks_chain_tip(&preamble, &contents) {
+            Ok(tip) => tip,
+            Err(error_resp) => {
+                return error_resp.try_into_contents().map_err(NetError::from);
+            }
+        };
+
+        let proofs_res: Result<Option<ClarityProofs>, NetError> =
+            node.with_node_state(|_network, sortdb, chainstate, _mempool, _rpc_args| {
+                // proofs can only be anchored to blocks with headers (i.e. not unconfirmed state)
+                let Some(tip_header) = NakamotoChainState::get_block_header(chainstate.db(), &tip)?
+                else {
+                    return Ok(None);
+                };
+
+                let entries_opt = chainstate.maybe_read_only_clarity_tx(
+                    &sortdb.index_conn(),
+                    &tip,
+                    |clarity_tx| {
+                        clarity_tx.with_clarity_db_readonly(|clarity_db| {
+                            let mut entries = Vec::with_capacity(keys.len());
+                            for (key, marf_key) in keys.into_iter() {
+                                let (value, proof): (Option<String>, Option<Vec<u8>>) = clarity_db
+                                    .get_data_with_proof(&marf_key)?
+                                    .map(|(value, proof)| (Some(value), Some(proof)))
+                                    .unwrap_or((None, None));
+                                entries.push((key, marf_key, value, proof));
+                            }
+                            Ok::<_, NetError>(entries)
+                        })
+                    },
+                )?;
+
+                let Some(entries) = entries_opt else {
+                    return Ok(None);
+                };
+                let entries = entries?;
+
+                let mut ancestors: Vec<StacksBlockId> = vec![];
+                let mut proof_entries = Vec::with_capacity(entries.len());
+                for (key, marf_key, value, proof_bytes) in entries.into_iter() {
+                    if let Some(proof_bytes) = proof_bytes.as_ref() {
+                        let proof = TrieMerkleProof::<StacksBlockId>::consensus_deserialize(
+                            &mut &proof_bytes[..],
+                        )?;
+                        for block_id in get_proof_ancestor_blocks(&proof).into_iter() {
+                            if !ancestors.contains(&block_id) {
+                                ancestors.push(block_id);
+                            }
+                        }
+                    }
+                    proof_entries.push(ClarityProofEntry {
+                        key,
+                        marf_key,
+                        value,
+                        proof: proof_bytes.map(|bytes| to_hex(&bytes)),
+                    });
+                }
+
+                let mut anchors = Vec::with_capacity(ancestors.len());
+                for block_id in ancestors.iter() {
+                    let header_info =
+                        NakamotoChainState::get_block_header(chainstate.db(), block_id)?.ok_or(
+                            NetError::ChainstateError(format!(
+                                "No header for ancestor block {}",
+                                block_id
+                            )),
+                        )?;
+                    anchors.push(make_anchor_header(&header_info));
+                }
+
+                Ok(Some(ClarityProofs {
+                    tip: make_anchor_header(&tip_header),
+                    anchors,
+                    entries: proof_entries,
+                }))
+            });
+
+        let proofs: ClarityProofs = match proofs_res {
+            Ok(Some(proofs)) => proofs,
+            Ok(None) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpNotFound::new(format!("Chain tip '{}' not found", &tip)),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+            Err(e) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpServerError::new(format!("Failed to load proofs: {:?}", &e)),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+        };
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&proofs)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCPostClarityProofsRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let proofs: ClarityProofs = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(proofs)?)
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request for proofs of Clarity state
+    pub fn new_post_clarity_proofs(
+        host: PeerHost,
+        keys: Vec<ClarityProofKey>,
+        tip_req: TipRequest,
+    ) -> StacksHttpRequest {
+        StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            "/v3/clarity/proofs".into(),
+            HttpRequestContents::new().for_tip(tip_req).payload_json(
+                serde_json::to_value(ClarityProofsRequestBody { keys })
+                    .expect("FATAL: failed to encode infallible data"),
+            ),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_clarity_proofs(self) -> Result<ClarityProofs, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let contents_json: serde_json::Value = contents.try_into()?;
+        let resp: ClarityProofs = serde_json::from_value(contents_json)
+            .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
+        Ok(resp)
+    }
+}
@@ -18,6 +18,7 @@ use std::io::{Read, Write};
 
 use clarity::vm::costs::ExecutionCost;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::codec::{StacksMessageCodec, MAX_PAYLOAD_LEN};
 use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksBlockId, StacksPublicKey,
@@ -38,7 +39,7 @@ use crate::core::StacksEpoch;
 use crate::cost_estimates::metrics::CostMetric;
 use crate::cost_estimates::{CostEstimator, FeeEstimator, FeeRateEstimate};
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpContentType, HttpNotFound, HttpRequest,
+    parse_json, Error, HttpBadRequest, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
     HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
     HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
@@ -204,6 +205,76 @@ impl HttpRequest for RPCPostFeeRateRequestHandler {
         self.estimated_len = Some(estimated_len);
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/fees/transaction", "Estimate a transaction's fee")
+                .description(
+                    "Estimate the execution cost and fee of a transaction, given its payload.  \
+                     Three estimates are returned, from lowest to highest fee.",
+                )
+                .tag("Fees")
+                .body(
+                    HttpContentType::JSON,
+                    json!({
+                        "type": "object",
+                        "required": ["transaction_payload"],
+                        "properties": {
+                            "transaction_payload": {
+                                "type": "string",
+                                "description": "Hex-encoded serialized transaction payload"
+                            },
+                            "estimated_len": {
+                                "type": "integer",
+                                "description": "Estimated length of the whole transaction, in bytes"
+                            }
+                        }
+                    }),
+                )
+                .json_response(
+                    200,
+                    "The estimated cost and fees",
+                    json!({
+                        "type": "object",
+                        "required": [
+                            "estimated_cost",
+                            "estimated_cost_scalar",
+                            "estimations",
+                            "cost_scalar_change_by_byte"
+                        ],
+                        "properties": {
+                            "estimated_cost": {
+                                "type": "object",
+                                "properties": {
+                                    "write_length": { "type": "integer" },
+                                    "write_count": { "type": "integer" },
+                                    "read_length": { "type": "integer" },
+                                    "read_count": { "type": "integer" },
+                                    "runtime": { "type": "integer" }
+                                }
+                            },
+                            "estimated_cost_scalar": { "type": "integer" },
+                            "estimations": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "required": ["fee_rate", "fee"],
+                                    "properties": {
+                                        "fee_rate": { "type": "number" },
+                                        "fee": { "type": "integer" }
+                                    }
+                                }
+                            },
+                            "cost_scalar_change_by_byte": { "type": "number" }
+                        }
+                    }),
+                )
+                .error_response(
+                    400,
+                    "The request was malformed, or the node does not estimate fees",
+                ),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCPostFeeRateRequestHandler {
@@ -20,25 +20,26 @@ use std::{fs, io};
 
 use rand::{thread_rng, Rng};
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
 use url::form_urlencoded;
-use {serde, serde_json};
 
 use crate::burnchains::Txid;
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksTransaction};
 use crate::core::mempool::{decode_tx_stream, MemPoolDB, MemPoolSyncData};
 use crate::net::http::{
-    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_bytes, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    hex_schema, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
     StacksHttpResponse,
 };
 use crate::net::{Error as NetError, StacksNodeState, TipRequest, MAX_HEADERS};
@@ -252,6 +253,37 @@ impl HttpRequest for RPCMempoolQueryRequestHandler {
         }
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/mempool/query", "Query the mempool")
+                .description(
+                    "Get the mempool transactions that the caller does not have.  The body is a \
+                     consensus-serialized `MemPoolSyncData` describing the transactions the \
+                     caller already has.  The response is a stream of consensus-serialized \
+                     transactions, followed by the ID of the next page if there is one.",
+                )
+                .tag("Transactions")
+                .query_param(
+                    "page_id",
+                    "Transaction ID at which to resume a previous query",
+                    false,
+                    hex_schema(32),
+                )
+                .body(
+                    HttpContentType::Bytes,
+                    json!({ "type": "string", "format": "binary" }),
+                )
+                .response(
+                    200,
+                    "The serialized transactions",
+                    HttpContentType::Bytes,
+                    json!({ "type": "string", "format": "binary" }),
+                )
+                .error_response(400, "The request was malformed")
+                .error_response(500, "The mempool could not be read"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCMempoolQueryRequestHandler {
@@ -18,6 +18,7 @@ use std::io::{Read, Write};
 
 use clarity::vm::costs::ExecutionCost;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::codec::{Error as CodecError, StacksMessageCodec, MAX_PAYLOAD_LEN};
 use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksBlockId, StacksPublicKey,
@@ -38,13 +39,13 @@ use crate::chainstate::stacks::{
 use crate::core::mempool::MemPoolDB;
 use crate::cost_estimates::FeeRateEstimate;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpContentType, HttpNotFound, HttpRequest,
+    parse_json, Error, HttpBadRequest, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
     HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
     HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttpRequest,
-    StacksHttpResponse,
+    hex_schema, HttpEndpointDocExtensions, HttpPreambleExtensions, HttpRequestContentsExtensions,
+    RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::relay::Relayer;
@@ -123,6 +124,37 @@ impl HttpRequest for RPCPostMicroblockRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/microblocks", "Upload a microblock")
+                .description(
+                    "Upload a consensus-serialized microblock that extends the given (or the \
+                     canonical) Stacks 2.x chain tip.",
+                )
+                .tag("Microblocks")
+                .tip_param()
+                .body(
+                    HttpContentType::Bytes,
+                    json!({ "type": "string", "format": "binary" }),
+                )
+                .json_response(200, "The microblock's hash", hex_schema(32))
+                .json_response(
+                    400,
+                    "The microblock was invalid",
+                    json!({
+                        "type": "object",
+                        "required": ["error", "reason", "reason_data"],
+                        "properties": {
+                            "error": { "type": "string" },
+                            "reason": { "type": "string" },
+                            "reason_data": { "type": "string" }
+                        }
+                    }),
+                )
+                .error_response(404, "The chain tip was not found"),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCPostMicroblockRequestHandler {
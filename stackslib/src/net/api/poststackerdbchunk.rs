@@ -29,25 +29,26 @@ use libstackerdb::{
     SlotMetadata, StackerDBChunkAckData, StackerDBChunkData, STACKERDB_MAX_CHUNK_SIZE,
 };
 use regex::{Captures, Regex};
+use serde;
 use serde::de::Error as de_Error;
-use serde_json::json;
+use serde_json::{self, json};
 use stacks_common::codec::{StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
 use stacks_common::util::hash::to_hex;
 use stacks_common::util::secp256k1::MessageSignature;
-use {serde, serde_json};
 
 use crate::chainstate::stacks::db::StacksChainState;
 use crate::chainstate::stacks::{Error as ChainError, StacksBlock};
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpNotFound,
-    HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
-    HttpResponsePayload, HttpResponsePreamble, HttpServerError,
+    parse_json, Error, HttpBadRequest, HttpChunkGenerator, HttpContentType, HttpEndpointDoc,
+    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
+    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError,
 };
 use crate::net::httpcore::{
-    request, HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
-    StacksHttpRequest, StacksHttpResponse,
+    hex_schema, request, HttpEndpointDocExtensions, HttpPreambleExtensions,
+    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+    StacksHttpResponse,
 };
 use crate::net::{
     Error as NetError, StackerDBPushChunkData, StacksMessageType, StacksNodeState, TipRequest,
@@ -115,6 +116,62 @@ impl HttpRequest for RPCPostStackerDBChunkRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/stackerdb/{address}/{contract}/chunks",
+                "Write a StackerDB chunk",
+            )
+            .description(
+                "Write a signed chunk to a StackerDB slot, and relay it to the StackerDB's other \
+                 replicas if it is accepted.  A chunk that is not accepted is acknowledged with \
+                 the reason why.",
+            )
+            .tag("StackerDB")
+            .contract_params()
+            .body(
+                HttpContentType::JSON,
+                json!({
+                    "type": "object",
+                    "required": ["slot_id", "slot_version", "sig", "data"],
+                    "properties": {
+                        "slot_id": { "type": "integer" },
+                        "slot_version": { "type": "integer" },
+                        "sig": hex_schema(65),
+                        "data": {
+                            "type": "string",
+                            "description": "Hex-encoded chunk data"
+                        }
+                    }
+                }),
+            )
+            .json_response(
+                200,
+                "The acknowledgement of the chunk",
+                json!({
+                    "type": "object",
+                    "required": ["accepted"],
+                    "properties": {
+                        "accepted": { "type": "boolean" },
+                        "reason": { "type": "string" },
+                        "metadata": {
+                            "type": "object",
+                            "properties": {
+                                "slot_id": { "type": "integer" },
+                                "slot_version": { "type": "integer" },
+                                "data_hash": hex_schema(32),
+                                "signature": hex_schema(65)
+                            }
+                        },
+                        "code": { "type": "integer" }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The StackerDB was not found"),
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
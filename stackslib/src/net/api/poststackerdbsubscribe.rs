@@ -0,0 +1,286 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use clarity::vm::representations::{CONTRACT_NAME_REGEX_STRING, STANDARD_PRINCIPAL_REGEX_STRING};
+use clarity::vm::types::QualifiedContractIdentifier;
+use libstackerdb::{SlotMetadata, StackerDBSubscribeData, STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS};
+use regex::{Captures, Regex};
+use serde_json::json;
+use stacks_common::types::net::PeerHost;
+
+use crate::net::http::{
+    parse_json, Error, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
+    HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
+    HttpResponsePayload, HttpResponsePreamble,
+};
+use crate::net::httpcore::{
+    hex_schema, request, HttpEndpointDocExtensions, HttpPreambleExtensions,
+    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+};
+use crate::net::{Error as NetError, StacksNodeState};
+
+/// Long-poll for writes to a StackerDB.  The request is deferred until a slot is written past
+/// the subscriber's slot versions, or until its timeout expires.
+#[derive(Clone)]
+pub struct RPCPostStackerDBSubscribeRequestHandler {
+    pub contract_identifier: Option<QualifiedContractIdentifier>,
+    pub subscription: Option<StackerDBSubscribeData>,
+    /// When to stop waiting for a write
+    pub deadline: Option<Instant>,
+}
+impl RPCPostStackerDBSubscribeRequestHandler {
+    pub fn new() -> Self {
+        Self {
+            contract_identifier: None,
+            subscription: None,
+            deadline: None,
+        }
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCPostStackerDBSubscribeRequestHandler {
+    fn verb(&self) -> &'static str {
+        "POST"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(&format!(
+            r#"^/v2/stackerdb/(?P<address>{})/(?P<contract>{})/subscribe$"#,
+            *STANDARD_PRINCIPAL_REGEX_STRING, *CONTRACT_NAME_REGEX_STRING
+        ))
+        .unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v2/stackerdb/:principal/:contract_name/subscribe"
+    }
+
+    /// Try to decode this request.
+    /// The subscription's deadline starts when the request is decoded.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        if preamble.get_content_length() == 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected non-empty body".to_string(),
+            ));
+        }
+
+        let contract_identifier = request::get_contract_address(captures, "address", "contract")?;
+        let subscription: StackerDBSubscribeData =
+            serde_json::from_slice(body).map_err(Error::JsonError)?;
+        let timeout = Duration::from_secs(
+            subscription
+                .timeout_secs
+                .min(STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS),
+        );
+
+        self.contract_identifier = Some(contract_identifier);
+        self.subscription = Some(subscription);
+        self.deadline = Some(Instant::now() + timeout);
+
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new(
+                "/v2/stackerdb/{address}/{contract}/subscribe",
+                "Wait for StackerDB writes",
+            )
+            .description(
+                "Wait until any slot in a StackerDB is written past the given slot versions, or \
+                 until the timeout expires, and return the metadata of the slots that changed.  \
+                 Slots past the end of `slot_versions` are taken to be at version 0.  An empty \
+                 list means that the timeout expired.",
+            )
+            .tag("StackerDB")
+            .contract_params()
+            .body(
+                HttpContentType::JSON,
+                json!({
+                    "type": "object",
+                    "required": ["slot_versions", "timeout_secs"],
+                    "properties": {
+                        "slot_versions": {
+                            "type": "array",
+                            "items": { "type": "integer" }
+                        },
+                        "timeout_secs": {
+                            "type": "integer",
+                            "maximum": STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS
+                        }
+                    }
+                }),
+            )
+            .json_response(
+                200,
+                "The metadata of each slot that changed",
+                json!({
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["slot_id", "slot_version", "data_hash", "signature"],
+                        "properties": {
+                            "slot_id": { "type": "integer" },
+                            "slot_version": { "type": "integer" },
+                            "data_hash": hex_schema(32),
+                            "signature": hex_schema(65)
+                        }
+                    }
+                }),
+            )
+            .error_response(400, "The request was malformed")
+            .error_response(404, "The StackerDB was not found"),
+        )
+    }
+}
+
+impl RPCRequestHandler for RPCPostStackerDBSubscribeRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.contract_identifier = None;
+        self.subscription = None;
+        self.deadline = None;
+    }
+
+    fn can_defer(&self) -> bool {
+        true
+    }
+
+    /// Make the response, or defer the request if no slot has changed yet.
+    /// The parsed request is kept across deferrals.
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        _contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let contract_identifier = self
+            .contract_identifier
+            .as_ref()
+            .ok_or(NetError::SendError("`contract_identifier` not set".into()))?;
+        let subscription = self
+            .subscription
+            .as_ref()
+            .ok_or(NetError::SendError("`subscription` not set".into()))?;
+        let deadline = self
+            .deadline
+            .ok_or(NetError::SendError("`deadline` not set".into()))?;
+
+        let changed_resp: Result<Vec<SlotMetadata>, StacksHttpResponse> =
+            node.with_node_state(|network, _sortdb, _chainstate, _mempool, _rpc_args| {
+                let stackerdbs = network.get_stackerdbs();
+                let not_found = |_e| {
+                    StacksHttpResponse::new_error(
+                        &preamble,
+                        &HttpNotFound::new("StackerDB contract not found".to_string()),
+                    )
+                };
+                // cheap check first, since this runs on every pass of the network loop
+                let slot_versions = stackerdbs
+                    .get_slot_versions(contract_identifier)
+                    .map_err(not_found)?;
+                let any_changed = slot_versions
+                    .iter()
+                    .zip(0u32..)
+                    .any(|(slot_version, slot_id)| subscription.is_newer(slot_id, *slot_version));
+                if !any_changed {
+                    return Ok(vec![]);
+                }
+                let changed: Vec<SlotMetadata> = stackerdbs
+                    .get_db_slot_metadata(contract_identifier)
+                    .map_err(not_found)?
+                    .into_iter()
+                    .filter(|md| subscription.is_newer(md.slot_id, md.slot_version))
+                    .collect();
+                Ok(changed)
+            });
+
+        let changed = match changed_resp {
+            Ok(changed) => changed,
+            Err(response) => {
+                return response.try_into_contents().map_err(NetError::from);
+            }
+        };
+
+        if changed.is_empty() && Instant::now() < deadline {
+            return Err(NetError::RequestDeferred);
+        }
+
+        let mut preamble = HttpResponsePreamble::ok_json(&preamble);
+        preamble.set_canonical_stacks_tip_height(Some(node.canonical_stacks_tip_height()));
+        let body = HttpResponseContents::try_from_json(&changed)?;
+        Ok((preamble, body))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCPostStackerDBSubscribeRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let metadata: Vec<SlotMetadata> = parse_json(preamble, body)?;
+        Ok(HttpResponsePayload::try_from_json(metadata)?)
+    }
+}
+
+impl StacksHttpRequest {
+    pub fn new_post_stackerdb_subscribe(
+        host: PeerHost,
+        stackerdb_contract_id: QualifiedContractIdentifier,
+        slot_versions: Vec<u32>,
+        timeout_secs: u64,
+    ) -> StacksHttpRequest {
+        StacksHttpRequest::new_for_peer(
+            host,
+            "POST".into(),
+            format!(
+                "/v2/stackerdb/{}/{}/subscribe",
+                &stackerdb_contract_id.issuer, &stackerdb_contract_id.name
+            ),
+            HttpRequestContents::new().payload_json(
+                serde_json::to_value(StackerDBSubscribeData {
+                    slot_versions,
+                    timeout_secs,
+                })
+                .expect("FATAL: failed to construct JSON from infallible structure"),
+            ),
+        )
+        .expect("FATAL: failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    /// Decode an HTTP response into the metadata of the slots that changed.
+    /// If it fails, return Self::Error(..)
+    pub fn decode_stackerdb_subscribe(self) -> Result<Vec<SlotMetadata>, NetError> {
+        let contents = self.get_http_payload_ok()?;
+        let contents_json: serde_json::Value = contents.try_into()?;
+        let resp: Vec<SlotMetadata> = serde_json::from_value(contents_json)
+            .map_err(|_e| NetError::DeserializeError("Failed to load from JSON".to_string()))?;
+        Ok(resp)
+    }
+}
@@ -18,6 +18,7 @@ use std::io::{Read, Write};
 
 use clarity::vm::costs::ExecutionCost;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::codec::{Error as CodecError, StacksMessageCodec, MAX_PAYLOAD_LEN};
 use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksBlockId, StacksPublicKey,
@@ -36,12 +37,12 @@ use crate::chainstate::stacks::{StacksTransaction, TransactionPayload};
 use crate::core::mempool::MemPoolDB;
 use crate::cost_estimates::FeeRateEstimate;
 use crate::net::http::{
-    parse_json, Error, HttpBadRequest, HttpContentType, HttpNotFound, HttpRequest,
+    parse_json, Error, HttpBadRequest, HttpContentType, HttpEndpointDoc, HttpNotFound, HttpRequest,
     HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents,
     HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
 };
 use crate::net::httpcore::{
-    HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
+    hex_schema, HttpPreambleExtensions, RPCRequestHandler, StacksHttpRequest, StacksHttpResponse,
 };
 use crate::net::p2p::PeerNetwork;
 use crate::net::relay::Relayer;
@@ -173,6 +174,49 @@ impl HttpRequest for RPCPostTransactionRequestHandler {
 
         Ok(HttpRequestContents::new().query_string(query))
     }
+
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        Some(
+            HttpEndpointDoc::new("/v2/transactions", "Broadcast a transaction")
+                .description(
+                    "Submit a transaction to the node's mempool, and relay it to the network if \
+                     it is accepted.  The body is either a consensus-serialized transaction, or a \
+                     JSON object with a hex-encoded transaction and an optional hex-encoded Atlas \
+                     attachment.",
+                )
+                .tag("Transactions")
+                .body(
+                    HttpContentType::Bytes,
+                    json!({ "type": "string", "format": "binary" }),
+                )
+                .body(
+                    HttpContentType::JSON,
+                    json!({
+                        "type": "object",
+                        "required": ["tx"],
+                        "properties": {
+                            "tx": { "type": "string" },
+                            "attachment": { "type": "string", "nullable": true }
+                        }
+                    }),
+                )
+                .json_response(200, "The transaction's ID", hex_schema(32))
+                .json_response(
+                    400,
+                    "The transaction was rejected",
+                    json!({
+                        "type": "object",
+                        "required": ["error", "reason", "txid"],
+                        "properties": {
+                            "error": { "type": "string" },
+                            "reason": { "type": "string" },
+                            "reason_data": { "type": "object" },
+                            "txid": hex_schema(32)
+                        }
+                    }),
+                ),
+        )
+    }
 }
 
 impl RPCRequestHandler for RPCPostTransactionRequestHandler {
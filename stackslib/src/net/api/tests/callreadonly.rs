@@ -14,7 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
 
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StacksAddressExtensions};
 use clarity::vm::{ClarityName, ContractName};
@@ -23,7 +25,7 @@ use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::types::net::PeerHost;
 use stacks_common::types::Address;
 
-use super::test_rpc;
+use super::{test_rpc, TestRPC};
 use crate::core::BLOCK_LIMIT_MAINNET_21;
 use crate::net::api::*;
 use crate::net::connection::ConnectionOptions;
@@ -31,6 +33,7 @@ use crate::net::httpcore::{
     HttpPreambleExtensions, HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp,
     StacksHttpRequest,
 };
+use crate::net::rpc_access::{RPCAccessControl, RPCAccessTier, RPC_API_KEY_HEADER};
 use crate::net::{ProtocolFamily, TipRequest};
 
 #[test]
@@ -271,3 +274,96 @@ fn test_try_make_response() {
     let (preamble, payload) = response.destruct();
     assert_eq!(preamble.status_code, 404);
 }
+
+#[test]
+fn test_try_make_response_with_access_control() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let make_request = |api_key: Option<&str>| {
+        let mut request = StacksHttpRequest::new_callreadonlyfunction(
+            addr.into(),
+            StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R").unwrap(),
+            "hello-world".try_into().unwrap(),
+            StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+                .unwrap()
+                .to_account_principal(),
+            None,
+            "ro-confirmed".try_into().unwrap(),
+            vec![],
+            TipRequest::UseLatestAnchoredTip,
+        );
+        if let Some(api_key) = api_key {
+            request.add_header(RPC_API_KEY_HEADER.into(), api_key.into());
+        }
+        request
+    };
+
+    let mut requests = vec![];
+
+    // anonymous clients can spend 1 unit of runtime, which isn't enough to run the call
+    requests.push(make_request(None));
+    // ...and then they're out of budget
+    requests.push(make_request(None));
+    // but they can still make unmetered requests
+    requests.push(StacksHttpRequest::new_getinfo(addr.into(), None));
+    // unknown API key
+    requests.push(make_request(Some("bogus")));
+    // keyed clients can make one request per minute, with no budget
+    requests.push(make_request(Some("limited")));
+    requests.push(make_request(Some("limited")));
+
+    let mut api_keys = HashMap::new();
+    api_keys.insert(
+        "limited".to_string(),
+        RPCAccessTier {
+            name: "limited".into(),
+            requests_per_minute: 1,
+            daily_read_only_runtime: 0,
+        },
+    );
+    let rpc_access = RPCAccessControl::new(
+        api_keys,
+        RPCAccessTier {
+            name: "anonymous".into(),
+            requests_per_minute: 0,
+            daily_read_only_runtime: 1,
+        },
+    );
+
+    let mut rpc_test = TestRPC::setup(function_name!());
+    rpc_test
+        .convo_2
+        .set_rpc_access(Arc::new(Mutex::new(rpc_access)));
+    let mut responses = rpc_test.run(requests);
+
+    // ran out of runtime
+    let response = responses.remove(0);
+    let resp = response.decode_call_readonly_response().unwrap();
+    assert!(!resp.okay);
+    assert!(resp.cause.unwrap().find("CostBalanceExceeded").is_some());
+
+    // out of budget
+    let response = responses.remove(0);
+    assert_eq!(response.preamble().status_code, 429);
+    assert!(response.preamble().headers.get("retry-after").is_some());
+
+    // unmetered
+    let response = responses.remove(0);
+    assert_eq!(response.preamble().status_code, 200);
+
+    // unknown key
+    let response = responses.remove(0);
+    assert_eq!(response.preamble().status_code, 401);
+
+    // first keyed request goes through
+    let response = responses.remove(0);
+    let resp = response.decode_call_readonly_response().unwrap();
+    assert!(resp.okay);
+
+    // second is rate-limited
+    let response = responses.remove(0);
+    assert_eq!(response.preamble().status_code, 429);
+    assert_eq!(
+        response.preamble().headers.get("retry-after"),
+        Some(&"60".to_string())
+    );
+}
@@ -0,0 +1,226 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StacksAddressExtensions};
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::Address;
+
+use super::test_rpc;
+use crate::chainstate::stacks::db::txindex::PrincipalTxCursor;
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::httpcore::{
+    HttpRequestContentsExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+};
+use crate::net::{ProtocolFamily, TipRequest};
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let principal: PrincipalData =
+        StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+            .unwrap()
+            .to_account_principal();
+
+    let request = StacksHttpRequest::new_get_address_transactions(
+        addr.into(),
+        principal.clone(),
+        Some(10),
+        Some("12:3".into()),
+        TipRequest::SpecificTip(StacksBlockId([0x22; 32])),
+    );
+    assert_eq!(
+        request.contents().tip_request(),
+        TipRequest::SpecificTip(StacksBlockId([0x22; 32]))
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    debug!("Request:\n{}\n", std::str::from_utf8(&bytes).unwrap());
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = getaddresstransactions::RPCGetAddressTransactionsRequestHandler::new();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    let (preamble, contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+    assert_eq!(handler.principal, Some(principal.clone()));
+    assert_eq!(handler.limit, Some(10));
+    assert_eq!(
+        handler.cursor,
+        Some(PrincipalTxCursor {
+            block_height: 12,
+            tx_index: 3
+        })
+    );
+
+    handler.restart();
+    assert!(handler.principal.is_none());
+    assert!(handler.limit.is_none());
+    assert!(handler.cursor.is_none());
+
+    // bad page sizes and cursors are rejected
+    for (limit, cursor) in [
+        (Some(0), None),
+        (
+            Some(getaddresstransactions::MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE + 1),
+            None,
+        ),
+        (None, Some("12".to_string())),
+        (None, Some("a:b".to_string())),
+    ] {
+        let request = StacksHttpRequest::new_get_address_transactions(
+            addr.into(),
+            principal.clone(),
+            limit,
+            cursor,
+            TipRequest::UseLatestAnchoredTip,
+        );
+        let bytes = request.try_serialize().unwrap();
+        let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+        let mut handler = getaddresstransactions::RPCGetAddressTransactionsRequestHandler::new();
+        assert!(http
+            .handle_try_parse_request(
+                &mut handler,
+                &parsed_preamble.expect_request(),
+                &bytes[offset..],
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let sender: PrincipalData =
+        StacksAddress::from_string("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R")
+            .unwrap()
+            .to_account_principal();
+    let contract: PrincipalData =
+        QualifiedContractIdentifier::parse("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world")
+            .unwrap()
+            .into();
+    let untouched: PrincipalData =
+        StacksAddress::from_string("ST165ZBV86V4NJ0V73F52YZGBMJ0FZAQ1BM43C553")
+            .unwrap()
+            .to_account_principal();
+
+    let mut requests = vec![];
+
+    // everything the sender did
+    let request = StacksHttpRequest::new_get_address_transactions(
+        addr.into(),
+        sender.clone(),
+        None,
+        None,
+        TipRequest::UseLatestAnchoredTip,
+    );
+    requests.push(request);
+
+    // first page of the sender's history
+    let request = StacksHttpRequest::new_get_address_transactions(
+        addr.into(),
+        sender.clone(),
+        Some(1),
+        None,
+        TipRequest::UseLatestAnchoredTip,
+    );
+    requests.push(request);
+
+    // transactions that touched the contract
+    let request = StacksHttpRequest::new_get_address_transactions(
+        addr.into(),
+        contract.clone(),
+        None,
+        None,
+        TipRequest::UseLatestAnchoredTip,
+    );
+    requests.push(request);
+
+    // no transactions
+    let request = StacksHttpRequest::new_get_address_transactions(
+        addr.into(),
+        untouched.clone(),
+        None,
+        None,
+        TipRequest::UseLatestAnchoredTip,
+    );
+    requests.push(request);
+
+    // no such tip
+    let request = StacksHttpRequest::new_get_address_transactions(
+        addr.into(),
+        sender.clone(),
+        None,
+        None,
+        TipRequest::SpecificTip(StacksBlockId([0x11; 32])),
+    );
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    // the coinbase and contract deploy were both sent by the sender (the contract call is in an
+    // unconfirmed microblock, which is not indexed)
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let all_txs = response.decode_address_transactions().unwrap();
+    assert_eq!(all_txs.principal, sender.to_string());
+    assert_eq!(all_txs.transactions.len(), 2);
+    assert!(all_txs.next_cursor.is_none());
+    for (i, tx) in all_txs.transactions.iter().enumerate() {
+        assert_eq!(tx.block_height, 1);
+        assert_eq!(tx.index_block_hash, all_txs.tip);
+        assert_eq!(tx.tx_index, 1 - (i as u32));
+    }
+
+    // pagination
+    let response = responses.remove(0);
+    let page = response.decode_address_transactions().unwrap();
+    assert_eq!(page.transactions, all_txs.transactions[0..1].to_vec());
+    assert_eq!(page.next_cursor, Some("1:1".to_string()));
+
+    // the contract was deployed
+    let response = responses.remove(0);
+    let contract_txs = response.decode_address_transactions().unwrap();
+    assert_eq!(
+        contract_txs.transactions,
+        all_txs.transactions[0..1].to_vec()
+    );
+
+    let response = responses.remove(0);
+    let untouched_txs = response.decode_address_transactions().unwrap();
+    assert!(untouched_txs.transactions.is_empty());
+    assert!(untouched_txs.next_cursor.is_none());
+
+    let response = responses.remove(0);
+    let (preamble, _body) = response.destruct();
+    assert_eq!(preamble.status_code, 404);
+}
@@ -0,0 +1,205 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use super::test_rpc;
+use crate::net::api::getopenapi::RPC_OPENAPI_DOCUMENT;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::openapi::OPENAPI_VERSION;
+use crate::net::http::{HttpEndpointDoc, HttpParamLocation};
+use crate::net::httpcore::{StacksHttp, StacksHttpRequest};
+use crate::net::ProtocolFamily;
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let request = StacksHttpRequest::new_getopenapi(addr.into());
+    let bytes = request.try_serialize().unwrap();
+
+    debug!("Request:\n{}\n", std::str::from_utf8(&bytes).unwrap());
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut parsed_request = http
+        .try_parse_request(&parsed_preamble.expect_request(), &bytes[offset..])
+        .unwrap();
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let request = StacksHttpRequest::new_getopenapi(addr.into());
+    let mut responses = test_rpc(function_name!(), vec![request]);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+
+    let document = response.decode_openapi_document().unwrap();
+    assert_eq!(document, *RPC_OPENAPI_DOCUMENT);
+    assert_eq!(document["openapi"], OPENAPI_VERSION);
+    assert!(document["paths"]["/v3/openapi.json"]["get"].is_object());
+    assert!(document["paths"]["/v2/info"]["get"].is_object());
+    assert!(document["paths"]["/v2/transactions"]["post"].is_object());
+}
+
+/// Every registered request handler must be documented, and its documentation must agree with
+/// its verb and path regex.
+#[test]
+fn test_all_endpoints_documented() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let http = StacksHttp::new(addr, &ConnectionOptions::default());
+
+    let endpoint_docs = http.endpoint_docs();
+    assert!(!endpoint_docs.is_empty());
+
+    for (verb, regex, doc) in endpoint_docs.iter() {
+        let doc = doc
+            .as_ref()
+            .unwrap_or_else(|| panic!("No endpoint documentation for {} {}", verb, regex));
+
+        assert!(!doc.paths.is_empty(), "No paths for {} {}", verb, regex);
+        assert!(!doc.summary.is_empty(), "No summary for {} {}", verb, regex);
+        assert!(!doc.tags.is_empty(), "No tags for {} {}", verb, regex);
+        assert!(
+            doc.responses
+                .iter()
+                .any(|response| response.status >= 200 && response.status < 300),
+            "No successful response for {} {}",
+            verb,
+            regex
+        );
+
+        let capture_names: HashSet<_> = regex.capture_names().flatten().collect();
+        let path_params: HashSet<_> = doc
+            .params
+            .iter()
+            .filter(|param| param.location == HttpParamLocation::Path)
+            .map(|param| param.name.as_str())
+            .collect();
+        assert_eq!(
+            capture_names, path_params,
+            "Path parameters of {} {} do not match its path regex",
+            verb, regex
+        );
+
+        for path in doc.paths.iter() {
+            let mut example_path = path.clone();
+            for name in HttpEndpointDoc::path_template_params(path) {
+                assert!(
+                    path_params.contains(name.as_str()),
+                    "Undocumented parameter {} in {} {}",
+                    &name,
+                    verb,
+                    path
+                );
+                let param = doc.params.iter().find(|param| param.name == name).unwrap();
+                let example = param
+                    .example
+                    .as_ref()
+                    .unwrap_or_else(|| panic!("No example for {} in {} {}", &name, verb, path));
+                example_path = example_path.replace(&format!("{{{}}}", &name), example);
+            }
+            assert!(
+                regex.is_match(&example_path),
+                "Example path {} does not match {} {}",
+                &example_path,
+                verb,
+                regex
+            );
+        }
+    }
+}
+
+#[test]
+fn test_openapi_document() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let http = StacksHttp::new(addr, &ConnectionOptions::default());
+    let document = http.openapi_document();
+
+    // every documented path and verb is in the document
+    let mut num_operations = 0;
+    for (verb, _, doc) in http.endpoint_docs() {
+        let doc = doc.unwrap();
+        for path in doc.paths.iter() {
+            let operation = &document["paths"][path.as_str()][verb.to_lowercase()];
+            assert_eq!(
+                operation["operationId"],
+                HttpEndpointDoc::operation_id(verb, path)
+            );
+            assert_eq!(operation["summary"], doc.summary);
+        }
+    }
+
+    // operation IDs are unique, since client generators name methods after them
+    let mut operation_ids = HashSet::new();
+    for (_, path_item) in document["paths"].as_object().unwrap() {
+        for (_, operation) in path_item.as_object().unwrap() {
+            num_operations += 1;
+            let op_id = operation["operationId"].as_str().unwrap().to_string();
+            assert!(operation_ids.insert(op_id.clone()), "Duplicate {}", &op_id);
+        }
+    }
+    assert!(num_operations > 0);
+
+    // path parameters are only listed on the templates that have them
+    let chunk_op = &document["paths"]["/v2/stackerdb/{address}/{contract}/{slot_id}"]["get"];
+    let chunk_params: Vec<_> = chunk_op["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|param| param["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(chunk_params, vec!["address", "contract", "slot_id"]);
+
+    let versioned_chunk_op =
+        &document["paths"]["/v2/stackerdb/{address}/{contract}/{slot_id}/{slot_version}"]["get"];
+    assert_eq!(
+        versioned_chunk_op["parameters"].as_array().unwrap().len(),
+        4
+    );
+}
+
+#[test]
+fn test_operation_id() {
+    assert_eq!(
+        HttpEndpointDoc::operation_id("GET", "/v2/accounts/{principal}"),
+        "get_v2_accounts_principal"
+    );
+    assert_eq!(
+        HttpEndpointDoc::operation_id(
+            "POST",
+            "/v2/contracts/call-read/{address}/{contract}/{function}"
+        ),
+        "post_v2_contracts_call_read_address_contract_function"
+    );
+    assert_eq!(
+        HttpEndpointDoc::path_template_params("/v2/map_entry/{address}/{contract}/{map}"),
+        vec!["address", "contract", "map"]
+    );
+    assert!(HttpEndpointDoc::path_template_params("/v2/info").is_empty());
+}
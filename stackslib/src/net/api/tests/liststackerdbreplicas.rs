@@ -63,6 +63,7 @@ fn test_try_parse_request() {
         handler.contract_identifier,
         Some(contract_identifier.clone())
     );
+    assert!(!handler.with_stats);
 
     // parsed request consumes headers that would not be in a constructed reqeuest
     parsed_request.clear_headers();
@@ -72,6 +73,37 @@ fn test_try_parse_request() {
 
     handler.restart();
     assert!(handler.contract_identifier.is_none());
+
+    // with replication stats
+    let request = StacksHttpRequest::new_list_stackerdb_replicas_with_stats(
+        addr.into(),
+        contract_identifier.clone(),
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    assert_eq!(
+        handler.contract_identifier,
+        Some(contract_identifier.clone())
+    );
+    assert!(handler.with_stats);
+
+    parsed_request.clear_headers();
+    let (preamble, contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.contract_identifier.is_none());
+    assert!(!handler.with_stats);
 }
 
 #[test]
@@ -99,6 +131,20 @@ fn test_try_make_response() {
     );
     requests.push(request);
 
+    // with replication stats
+    let request = StacksHttpRequest::new_list_stackerdb_replicas_with_stats(
+        addr.into(),
+        contract_identifier.clone(),
+    );
+    requests.push(request);
+
+    // with replication stats, but no contract
+    let request = StacksHttpRequest::new_list_stackerdb_replicas_with_stats(
+        addr.into(),
+        none_contract_identifier.clone(),
+    );
+    requests.push(request);
+
     let mut responses = test_rpc(function_name!(), requests);
 
     let response = responses.remove(0);
@@ -128,4 +174,23 @@ fn test_try_make_response() {
     );
     let resp = response.decode_stackerdb_replicas().unwrap();
     assert_eq!(resp.len(), 0);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let resp = response.decode_stackerdb_replicas_with_stats().unwrap();
+    assert_eq!(resp.replicas.len(), 2);
+    let stats = resp.stats.unwrap();
+    assert!(stats.rejected_chunks.is_empty());
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    let resp = response.decode_stackerdb_replicas_with_stats().unwrap();
+    assert_eq!(resp.replicas.len(), 0);
+    assert!(resp.stats.is_none());
 }
@@ -52,6 +52,8 @@ use crate::net::{
 
 mod callreadonly;
 mod getaccount;
+mod getaddresstransactions;
+mod getaffirmations;
 mod getattachment;
 mod getattachmentsinv;
 mod getblock;
@@ -68,6 +70,7 @@ mod getmicroblocks_confirmed;
 mod getmicroblocks_indexed;
 mod getmicroblocks_unconfirmed;
 mod getneighbors;
+mod getopenapi;
 mod getpoxinfo;
 mod getstackerdbchunk;
 mod getstackerdbmetadata;
@@ -77,10 +80,12 @@ mod gettenureinfo;
 mod gettransaction_unconfirmed;
 mod liststackerdbreplicas;
 mod postblock;
+mod postclarityproofs;
 mod postfeerate;
 mod postmempoolquery;
 mod postmicroblock;
 mod poststackerdbchunk;
+mod poststackerdbsubscribe;
 mod posttransaction;
 
 const TEST_CONTRACT: &'static str = "
@@ -299,6 +304,12 @@ impl<'a> TestRPC<'a> {
         let mut peer_1 = TestPeer::new(peer_1_config);
         let mut peer_2 = TestPeer::new(peer_2_config);
 
+        // blocks are processed by the coordinator's chainstate handle
+        for peer in [&mut peer_1, &mut peer_2] {
+            peer.chainstate().principal_tx_index = true;
+            peer.coord.chain_state_db.principal_tx_index = true;
+        }
+
         // mine one block with a contract in it
         // first the coinbase
         // make a coinbase for this miner
@@ -0,0 +1,187 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use super::test_rpc;
+use crate::clarity_vm::proofs::{ClarityProofKey, Error as ProofError};
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+};
+use crate::net::{ProtocolFamily, TipRequest};
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let keys = vec![
+        ClarityProofKey::AccountNonce {
+            principal: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R".into(),
+        },
+        ClarityProofKey::DataVar {
+            contract: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world".into(),
+            var: "bar".into(),
+        },
+    ];
+    let request = StacksHttpRequest::new_post_clarity_proofs(
+        addr.into(),
+        keys.clone(),
+        TipRequest::UseLatestAnchoredTip,
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    debug!("Request:\n{}\n", std::str::from_utf8(&bytes).unwrap());
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = postclarityproofs::RPCPostClarityProofsRequestHandler::new();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    let parsed_keys: Vec<_> = handler
+        .keys
+        .clone()
+        .unwrap()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    assert_eq!(parsed_keys, keys);
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.keys.is_none());
+
+    // invalid keys are rejected
+    let request = StacksHttpRequest::new_post_clarity_proofs(
+        addr.into(),
+        vec![ClarityProofKey::AccountBalance {
+            principal: "not-a-principal".into(),
+        }],
+        TipRequest::UseLatestAnchoredTip,
+    );
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = postclarityproofs::RPCPostClarityProofsRequestHandler::new();
+    assert!(http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .is_err());
+
+    // empty requests are rejected
+    let request = StacksHttpRequest::new_post_clarity_proofs(
+        addr.into(),
+        vec![],
+        TipRequest::UseLatestAnchoredTip,
+    );
+    let bytes = request.try_serialize().unwrap();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = postclarityproofs::RPCPostClarityProofsRequestHandler::new();
+    assert!(http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .is_err());
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let keys = vec![
+        ClarityProofKey::AccountNonce {
+            principal: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R".into(),
+        },
+        ClarityProofKey::AccountBalance {
+            principal: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R".into(),
+        },
+        ClarityProofKey::DataVar {
+            contract: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world".into(),
+            var: "bar".into(),
+        },
+        ClarityProofKey::DataVar {
+            contract: "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world".into(),
+            var: "does-not-exist".into(),
+        },
+    ];
+
+    let mut requests = vec![];
+    let request = StacksHttpRequest::new_post_clarity_proofs(
+        addr.into(),
+        keys.clone(),
+        TipRequest::UseLatestAnchoredTip,
+    );
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+
+    assert_eq!(
+        response.preamble().get_canonical_stacks_tip_height(),
+        Some(1)
+    );
+
+    let proofs = response.decode_clarity_proofs().unwrap();
+    let tip = proofs.tip.index_block_hash.clone();
+    let verified = proofs.verify(&tip).unwrap();
+    assert_eq!(verified.len(), keys.len());
+    for ((key, value), expected_key) in verified.iter().zip(keys.iter()) {
+        assert_eq!(key, expected_key);
+        if let ClarityProofKey::DataVar { var, .. } = key {
+            if var == "does-not-exist" {
+                assert!(value.is_none());
+                continue;
+            }
+        }
+        assert!(value.is_some());
+    }
+
+    // proofs only verify against the tip they were generated for
+    let mut other_tip = tip.clone();
+    other_tip.0[0] ^= 0x01;
+    assert!(matches!(
+        proofs.verify(&other_tip),
+        Err(ProofError::UntrustedTip(_))
+    ));
+
+    // tampered values do not verify
+    let mut tampered = proofs.clone();
+    tampered.entries[2].value = Some("00".into());
+    assert!(matches!(
+        tampered.verify(&tip),
+        Err(ProofError::InvalidProof(_))
+    ));
+}
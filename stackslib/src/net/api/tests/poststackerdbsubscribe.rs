@@ -0,0 +1,281 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clarity::vm::types::QualifiedContractIdentifier;
+use libstackerdb::{SlotMetadata, StackerDBSubscribeData, STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+use stacks_common::util::pipe::Pipe;
+
+use super::{test_rpc, TestRPC};
+use crate::net::api::*;
+use crate::net::connection::ConnectionOptions;
+use crate::net::httpcore::{
+    HttpPreambleExtensions, RPCRequestHandler, StacksHttp, StacksHttpRequest,
+};
+use crate::net::rpc::ConversationHttp;
+use crate::net::test::TestPeer;
+use crate::net::{ProtocolFamily, RPCHandlerArgs, StackerDBConfig, StacksNodeState};
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let contract_identifier = QualifiedContractIdentifier::parse(
+        "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world-unconfirmed",
+    )
+    .unwrap();
+    // the timeout is capped
+    let request = StacksHttpRequest::new_post_stackerdb_subscribe(
+        addr.into(),
+        contract_identifier.clone(),
+        vec![1, 2, 3],
+        STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS * 10,
+    );
+    let bytes = request.try_serialize().unwrap();
+
+    debug!("Request:\n{}\n", std::str::from_utf8(&bytes).unwrap());
+
+    let before = Instant::now();
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = poststackerdbsubscribe::RPCPostStackerDBSubscribeRequestHandler::new();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    assert_eq!(
+        handler.contract_identifier,
+        Some(contract_identifier.clone())
+    );
+    assert_eq!(
+        handler.subscription,
+        Some(StackerDBSubscribeData {
+            slot_versions: vec![1, 2, 3],
+            timeout_secs: STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS * 10,
+        })
+    );
+    let deadline = handler.deadline.unwrap();
+    assert!(deadline >= before + Duration::from_secs(STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS));
+    assert!(deadline <= Instant::now() + Duration::from_secs(STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS));
+
+    // parsed request consumes headers that would not be in a constructed reqeuest
+    parsed_request.clear_headers();
+    let (preamble, _contents) = parsed_request.destruct();
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.contract_identifier.is_none());
+    assert!(handler.subscription.is_none());
+    assert!(handler.deadline.is_none());
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let mut requests = vec![];
+
+    let contract_identifier =
+        QualifiedContractIdentifier::parse("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world")
+            .unwrap();
+    let none_contract_identifier = QualifiedContractIdentifier::parse(
+        "ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.does-not-ext",
+    )
+    .unwrap();
+
+    // slot 0 was written past this version
+    let request = StacksHttpRequest::new_post_stackerdb_subscribe(
+        addr.into(),
+        contract_identifier.clone(),
+        vec![],
+        0,
+    );
+    requests.push(request);
+
+    // nothing was written past this version, and the timeout expired right away
+    let request = StacksHttpRequest::new_post_stackerdb_subscribe(
+        addr.into(),
+        contract_identifier.clone(),
+        vec![1, 0, 0, 0, 0, 0],
+        0,
+    );
+    requests.push(request);
+
+    // no contract
+    let request = StacksHttpRequest::new_post_stackerdb_subscribe(
+        addr.into(),
+        none_contract_identifier.clone(),
+        vec![],
+        0,
+    );
+    requests.push(request);
+
+    let mut responses = test_rpc(function_name!(), requests);
+
+    let response = responses.remove(0);
+    debug!(
+        "Response:\n{}\n",
+        std::str::from_utf8(&response.try_serialize().unwrap()).unwrap()
+    );
+    assert_eq!(
+        response.preamble().get_canonical_stacks_tip_height(),
+        Some(1)
+    );
+    let changed = response.decode_stackerdb_subscribe().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].slot_id, 0);
+    assert_eq!(changed[0].slot_version, 1);
+    assert_eq!(
+        changed[0].data_hash,
+        Sha512Trunc256Sum::from_data("hello world".as_bytes())
+    );
+
+    let response = responses.remove(0);
+    let changed = response.decode_stackerdb_subscribe().unwrap();
+    assert!(changed.is_empty());
+
+    let response = responses.remove(0);
+    let (preamble, _body) = response.destruct();
+    assert_eq!(preamble.status_code, 404);
+}
+
+/// Feed raw request bytes into the server side of a conversation
+fn server_recv(convo: &mut ConversationHttp, requests: &[StacksHttpRequest]) {
+    let (mut pipe_read, mut pipe_write) = Pipe::new();
+    pipe_read.set_nonblocking(true);
+    let mut bytes = vec![];
+    for request in requests.iter() {
+        bytes.append(&mut request.try_serialize().unwrap());
+    }
+    pipe_write.write_all(&bytes).unwrap();
+    pipe_write.try_flush().unwrap();
+    convo.recv(&mut pipe_read).unwrap();
+}
+
+/// Run one pass of the server side of a conversation against `peer`'s node state, and return
+/// whatever it replied with
+fn server_chat(peer: &mut TestPeer, convo: &mut ConversationHttp) -> String {
+    peer.refresh_burnchain_view();
+    let sortdb = peer.sortdb.take().unwrap();
+    let mut stacks_node = peer.stacks_node.take().unwrap();
+    let mut mempool = peer.mempool.take().unwrap();
+    {
+        let rpc_args = RPCHandlerArgs::default();
+        let mut node_state = StacksNodeState::new(
+            &mut peer.network,
+            &sortdb,
+            &mut stacks_node.chainstate,
+            &mut mempool,
+            &rpc_args,
+        );
+        convo.chat(&mut node_state).unwrap();
+    }
+    peer.sortdb = Some(sortdb);
+    peer.stacks_node = Some(stacks_node);
+    peer.mempool = Some(mempool);
+
+    let mut reply = vec![];
+    convo.send(&mut reply).unwrap();
+    String::from_utf8(reply).unwrap()
+}
+
+#[test]
+fn test_deferred_subscription() {
+    let test = TestRPC::setup(function_name!());
+    let privk1 = test.privk1;
+    let mut peer = test.peer_2;
+    let mut convo = test.convo_2;
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let contract_identifier =
+        QualifiedContractIdentifier::parse("ST2DS4MSWSGJ3W9FBC6BVT0Y92S345HY8N3T6AV7R.hello-world")
+            .unwrap();
+
+    // nothing was written past these versions, so the subscription is parked, and so is the
+    // getinfo request pipelined behind it
+    server_recv(
+        &mut convo,
+        &[
+            StacksHttpRequest::new_post_stackerdb_subscribe(
+                addr.into(),
+                contract_identifier.clone(),
+                vec![1],
+                STACKERDB_SUBSCRIBE_MAX_TIMEOUT_SECS,
+            ),
+            StacksHttpRequest::new_getinfo(addr.into(), None),
+        ],
+    );
+    let reply = server_chat(&mut peer, &mut convo);
+    assert!(reply.is_empty());
+    assert!(convo.has_deferred_request());
+
+    let reply = server_chat(&mut peer, &mut convo);
+    assert!(reply.is_empty());
+    assert!(convo.has_deferred_request());
+
+    // write slot 1
+    let data = "hello subscriber".as_bytes();
+    let mut slot_metadata = SlotMetadata::new_unsigned(1, 1, Sha512Trunc256Sum::from_data(data));
+    slot_metadata.sign(&privk1).unwrap();
+    let tx = peer
+        .network
+        .stackerdbs
+        .tx_begin(StackerDBConfig::noop())
+        .unwrap();
+    tx.try_replace_chunk(&contract_identifier, &slot_metadata, data)
+        .unwrap();
+    tx.commit().unwrap();
+
+    // the subscription is answered with slot 1, and then the getinfo request
+    let reply = server_chat(&mut peer, &mut convo);
+    debug!("Reply:\n{}\n", &reply);
+    assert!(!convo.has_deferred_request());
+    let subscribe_reply_pos = reply.find(r#""slot_id":1,"slot_version":1"#).unwrap();
+    assert!(!reply.contains(r#""slot_id":0"#));
+    let getinfo_reply_pos = reply.find(r#""peer_version""#).unwrap();
+    assert!(subscribe_reply_pos < getinfo_reply_pos);
+    assert_eq!(reply.matches("HTTP/1.1 200").count(), 2);
+
+    // if nothing is written before the deadline, the subscription is answered with an empty list
+    server_recv(
+        &mut convo,
+        &[StacksHttpRequest::new_post_stackerdb_subscribe(
+            addr.into(),
+            contract_identifier.clone(),
+            vec![1, 1],
+            1,
+        )],
+    );
+    let reply = server_chat(&mut peer, &mut convo);
+    assert!(reply.is_empty());
+    assert!(convo.has_deferred_request());
+
+    thread::sleep(Duration::from_millis(1100));
+    let reply = server_chat(&mut peer, &mut convo);
+    debug!("Reply:\n{}\n", &reply);
+    assert!(!convo.has_deferred_request());
+    assert!(reply.starts_with("HTTP/1.1 200"));
+    assert!(reply.ends_with("[]"));
+}
@@ -0,0 +1,501 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Recording and replaying of p2p traffic.
+//!
+//! When `ConnectionOptions::p2p_capture_path` is set, every `StacksMessage` that a
+//! `ConversationP2P` sends or receives is appended to a capture file.  The file is a sequence of
+//! newline-delimited JSON `CaptureRecord`s, each carrying the wire-encoded message as a hex
+//! string.  Captures can be filtered and decoded offline (see `stacks-inspect decode-capture`),
+//! and in test and `testing` builds the inbound half of a capture can be fed back into a
+//! `PeerNetwork` with `PeerNetwork::replay_capture()`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::util::get_epoch_time_ms;
+use stacks_common::util::hash::{hex_bytes, to_hex, Hash160};
+
+use crate::net::{Error as net_error, StacksMessage};
+
+/// Which way a captured message was going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureDirection {
+    /// Received from the remote peer
+    Inbound,
+    /// Sent to the remote peer
+    Outbound,
+}
+
+impl fmt::Display for CaptureDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaptureDirection::Inbound => write!(f, "inbound"),
+            CaptureDirection::Outbound => write!(f, "outbound"),
+        }
+    }
+}
+
+impl CaptureDirection {
+    pub fn from_str(s: &str) -> Option<CaptureDirection> {
+        match s {
+            "in" | "inbound" => Some(CaptureDirection::Inbound),
+            "out" | "outbound" => Some(CaptureDirection::Outbound),
+            _ => None,
+        }
+    }
+}
+
+/// A single captured message, as stored in a capture file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureRecord {
+    /// when the message was sent or received, in milliseconds since the epoch
+    pub timestamp_ms: u128,
+    /// whether we sent or received it
+    pub direction: CaptureDirection,
+    /// the conversation's event ID in the `PeerNetwork`
+    pub event_id: usize,
+    /// the remote peer's socket address
+    pub peer_addr: String,
+    /// the remote peer's public key hash, if the conversation was authenticated
+    pub peer_public_key_hash: Option<Hash160>,
+    /// the message type name (i.e. `StacksMessageType::get_message_name()`)
+    pub message_type: String,
+    /// the consensus-serialized `StacksMessage`, hex-encoded
+    pub message: String,
+}
+
+impl CaptureRecord {
+    pub fn new(
+        direction: CaptureDirection,
+        event_id: usize,
+        peer_addr: String,
+        peer_public_key_hash: Option<Hash160>,
+        msg: &StacksMessage,
+    ) -> CaptureRecord {
+        CaptureRecord {
+            timestamp_ms: get_epoch_time_ms(),
+            direction,
+            event_id,
+            peer_addr,
+            peer_public_key_hash,
+            message_type: msg.payload.get_message_name().to_string(),
+            message: to_hex(&msg.serialize_to_vec()),
+        }
+    }
+
+    /// Decode the captured message
+    pub fn decode_message(&self) -> Result<StacksMessage, net_error> {
+        let bytes = hex_bytes(&self.message)
+            .map_err(|e| net_error::DeserializeError(format!("Invalid message hex: {:?}", &e)))?;
+        StacksMessage::consensus_deserialize(&mut &bytes[..]).map_err(net_error::from)
+    }
+}
+
+/// Criteria for selecting records out of a capture
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptureFilter {
+    /// only match records going this way
+    pub direction: Option<CaptureDirection>,
+    /// only match records from this conversation
+    pub event_id: Option<usize>,
+    /// only match records to or from this peer, by socket address or public key hash
+    pub peer: Option<String>,
+    /// only match these message types (empty means all)
+    pub message_types: Vec<String>,
+    /// only match records at or after this time
+    pub start_ms: Option<u128>,
+    /// only match records strictly before this time
+    pub end_ms: Option<u128>,
+}
+
+impl CaptureFilter {
+    pub fn matches(&self, record: &CaptureRecord) -> bool {
+        if let Some(direction) = self.direction {
+            if record.direction != direction {
+                return false;
+            }
+        }
+        if let Some(event_id) = self.event_id {
+            if record.event_id != event_id {
+                return false;
+            }
+        }
+        if let Some(peer) = self.peer.as_ref() {
+            let pubkh_match = record
+                .peer_public_key_hash
+                .as_ref()
+                .map(|pubkh| &pubkh.to_hex() == peer)
+                .unwrap_or(false);
+            if &record.peer_addr != peer && !pubkh_match {
+                return false;
+            }
+        }
+        if self.message_types.len() > 0 && !self.message_types.contains(&record.message_type) {
+            return false;
+        }
+        if let Some(start_ms) = self.start_ms {
+            if record.timestamp_ms < start_ms {
+                return false;
+            }
+        }
+        if let Some(end_ms) = self.end_ms {
+            if record.timestamp_ms >= end_ms {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The thread that owns a capture file.  Records are handed to it over a channel, so the p2p
+/// thread never waits on the file.  The file is flushed whenever the channel runs dry, and when
+/// the last `MessageCapture` handle is dropped.
+struct CaptureWriter {
+    sender: Option<Sender<CaptureRecord>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureWriter {
+    fn spawn(path: &str, file: File) -> Result<CaptureWriter, net_error> {
+        let (sender, receiver) = channel();
+        let path = path.to_string();
+        let thread = thread::Builder::new()
+            .name("p2p-capture".to_string())
+            .spawn(move || CaptureWriter::run(&path, BufWriter::new(file), receiver))
+            .map_err(net_error::WriteError)?;
+        Ok(CaptureWriter {
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    /// Write records until every sender is gone
+    fn run(path: &str, mut writer: BufWriter<File>, receiver: Receiver<CaptureRecord>) {
+        while let Ok(record) = receiver.recv() {
+            let mut next = Some(record);
+            while let Some(record) = next.take() {
+                if let Err(e) = CaptureWriter::write_line(&mut writer, &record) {
+                    warn!("Failed to write p2p capture record to {}: {:?}", path, &e);
+                }
+                next = receiver.try_recv().ok();
+            }
+            if let Err(e) = writer.flush() {
+                warn!("Failed to flush p2p capture file {}: {:?}", path, &e);
+            }
+        }
+    }
+
+    fn write_line(writer: &mut BufWriter<File>, record: &CaptureRecord) -> Result<(), net_error> {
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| net_error::SerializeError(format!("{:?}", &e)))?;
+        line.push('\n');
+        writer
+            .write_all(line.as_bytes())
+            .map_err(net_error::WriteError)
+    }
+}
+
+impl Drop for CaptureWriter {
+    fn drop(&mut self) {
+        // hang up, and wait for the writer to drain and flush what it has
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Shared handle to an open capture file.  Cloning the handle shares the underlying file, so
+/// every conversation in a `PeerNetwork` writes to the same capture.
+#[derive(Clone)]
+pub struct MessageCapture {
+    path: String,
+    writer: Arc<CaptureWriter>,
+}
+
+impl fmt::Debug for MessageCapture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MessageCapture({})", &self.path)
+    }
+}
+
+impl MessageCapture {
+    /// Open a capture file for appending, creating it if it does not exist
+    pub fn open(path: &str) -> Result<MessageCapture, net_error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(net_error::WriteError)?;
+        Ok(MessageCapture {
+            path: path.to_string(),
+            writer: Arc::new(CaptureWriter::spawn(path, file)?),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Queue a record to be appended to the capture by its writer thread
+    pub fn write_record(&self, record: CaptureRecord) -> Result<(), net_error> {
+        let sender = self.writer.sender.as_ref().ok_or_else(|| {
+            net_error::WriteError(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "capture writer is closed",
+            ))
+        })?;
+        sender.send(record).map_err(|_| {
+            net_error::WriteError(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "capture writer thread exited",
+            ))
+        })
+    }
+
+    /// Record a message.  Capturing is best-effort: failures are logged, and never interrupt
+    /// the conversation.
+    pub fn record(
+        &self,
+        direction: CaptureDirection,
+        event_id: usize,
+        peer_addr: String,
+        peer_public_key_hash: Option<Hash160>,
+        msg: &StacksMessage,
+    ) {
+        let record = CaptureRecord::new(direction, event_id, peer_addr, peer_public_key_hash, msg);
+        if let Err(e) = self.write_record(record) {
+            warn!(
+                "Failed to write p2p capture record to {}: {:?}",
+                &self.path, &e
+            );
+        }
+    }
+}
+
+/// Read all records out of a capture file that match the given filter, in file order.
+/// Blank lines are skipped.
+pub fn read_capture<P: AsRef<Path>>(
+    path: P,
+    filter: &CaptureFilter,
+) -> Result<Vec<CaptureRecord>, net_error> {
+    let file = File::open(path).map_err(net_error::ReadError)?;
+    let mut records = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(net_error::ReadError)?;
+        if line.trim().len() == 0 {
+            continue;
+        }
+        let record: CaptureRecord = serde_json::from_str(&line).map_err(|e| {
+            net_error::DeserializeError(format!(
+                "Invalid capture record on line {}: {:?}",
+                i + 1,
+                &e
+            ))
+        })?;
+        if filter.matches(&record) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use stacks_common::types::chainstate::BurnchainHeaderHash;
+    use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+    use super::*;
+    use crate::burnchains::BurnchainView;
+    use crate::chainstate::burn::ConsensusHash;
+    use crate::net::test::{TestPeer, TestPeerConfig};
+    use crate::net::{PingData, StacksMessageType};
+
+    fn make_ping() -> StacksMessage {
+        let chain_view = BurnchainView {
+            burn_block_height: 12,
+            burn_block_hash: BurnchainHeaderHash([0x11; 32]),
+            burn_stable_block_height: 10,
+            burn_stable_block_hash: BurnchainHeaderHash([0x22; 32]),
+            last_burn_block_hashes: HashMap::new(),
+            rc_consensus_hash: ConsensusHash([0x33; 20]),
+        };
+        let mut msg = StacksMessage::from_chain_view(
+            0x18000000,
+            0x80000000,
+            &chain_view,
+            StacksMessageType::Ping(PingData::new()),
+        );
+        msg.sign(1, &Secp256k1PrivateKey::new()).unwrap();
+        msg
+    }
+
+    #[test]
+    fn test_capture_roundtrip_and_filter() {
+        let path = "/tmp/stacks-node-tests/test_capture_roundtrip_and_filter.jsonl";
+        fs::create_dir_all("/tmp/stacks-node-tests").unwrap();
+        if fs::metadata(path).is_ok() {
+            fs::remove_file(path).unwrap();
+        }
+
+        let msg = make_ping();
+        let capture = MessageCapture::open(path).unwrap();
+        capture.record(
+            CaptureDirection::Inbound,
+            1,
+            "127.0.0.1:20444".to_string(),
+            Some(Hash160([0x01; 20])),
+            &msg,
+        );
+        capture.record(
+            CaptureDirection::Outbound,
+            2,
+            "127.0.0.2:20444".to_string(),
+            None,
+            &msg,
+        );
+        // dropping the last handle flushes the capture
+        drop(capture);
+
+        let records = read_capture(path, &CaptureFilter::default()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].message_type, "Ping");
+        assert_eq!(records[0].decode_message().unwrap(), msg);
+        assert_eq!(records[1].decode_message().unwrap(), msg);
+
+        let inbound = read_capture(
+            path,
+            &CaptureFilter {
+                direction: Some(CaptureDirection::Inbound),
+                ..CaptureFilter::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(inbound.len(), 1);
+        assert_eq!(inbound[0].event_id, 1);
+
+        let by_pubkh = read_capture(
+            path,
+            &CaptureFilter {
+                peer: Some(Hash160([0x01; 20]).to_hex()),
+                ..CaptureFilter::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(by_pubkh, inbound);
+
+        let by_addr = read_capture(
+            path,
+            &CaptureFilter {
+                peer: Some("127.0.0.2:20444".to_string()),
+                ..CaptureFilter::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(by_addr.len(), 1);
+        assert_eq!(by_addr[0].direction, CaptureDirection::Outbound);
+
+        let no_match = read_capture(
+            path,
+            &CaptureFilter {
+                message_types: vec!["Handshake".to_string()],
+                ..CaptureFilter::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(no_match.len(), 0);
+    }
+
+    #[test]
+    fn test_capture_and_replay_peer_traffic() {
+        let path = "/tmp/stacks-node-tests/test_capture_and_replay_peer_traffic.jsonl";
+        fs::create_dir_all("/tmp/stacks-node-tests").unwrap();
+        if fs::metadata(path).is_ok() {
+            fs::remove_file(path).unwrap();
+        }
+
+        let mut peer_1_config = TestPeerConfig::from_port(32740);
+        let peer_2_config = TestPeerConfig::from_port(32742);
+        peer_1_config.connection_opts.p2p_capture_path = Some(path.to_string());
+        peer_1_config.add_neighbor(&peer_2_config.to_neighbor());
+
+        let mut peer_1 = TestPeer::new(peer_1_config);
+        let mut peer_2 = TestPeer::new(peer_2_config);
+
+        let mut inbound = vec![];
+        for _ in 0..100 {
+            let _ = peer_1.step();
+            let _ = peer_2.step();
+
+            inbound = read_capture(
+                path,
+                &CaptureFilter {
+                    direction: Some(CaptureDirection::Inbound),
+                    ..CaptureFilter::default()
+                },
+            )
+            .unwrap();
+            if inbound.len() > 0 {
+                break;
+            }
+        }
+        assert!(inbound.len() > 0);
+
+        let outbound = read_capture(
+            path,
+            &CaptureFilter {
+                direction: Some(CaptureDirection::Outbound),
+                ..CaptureFilter::default()
+            },
+        )
+        .unwrap();
+        assert!(outbound.len() > 0);
+        assert_eq!(outbound[0].message_type, "Handshake");
+
+        for record in inbound.iter().chain(outbound.iter()) {
+            record.decode_message().unwrap();
+        }
+
+        // replay what peer 1 heard back onto its conversation with peer 2
+        let event_id = *peer_1.network.events.values().next().unwrap();
+        let sortdb = peer_1.sortdb.take().unwrap();
+        let node = peer_1.stacks_node.take().unwrap();
+        peer_1
+            .network
+            .replay_capture(&sortdb, &node.chainstate, event_id, &inbound, false)
+            .unwrap();
+
+        // can't replay onto a conversation that doesn't exist
+        assert!(peer_1
+            .network
+            .replay_capture(&sortdb, &node.chainstate, usize::MAX, &inbound, false)
+            .is_err());
+
+        peer_1.sortdb = Some(sortdb);
+        peer_1.stacks_node = Some(node);
+    }
+}
@@ -38,6 +38,7 @@ use crate::chainstate::stacks::StacksPublicKey;
 use crate::core::{StacksEpoch, PEER_VERSION_EPOCH_2_2, PEER_VERSION_EPOCH_2_3};
 use crate::monitoring;
 use crate::net::asn::ASEntry4;
+use crate::net::capture::{CaptureDirection, MessageCapture};
 use crate::net::codec::*;
 use crate::net::connection::{ConnectionOptions, ConnectionP2P, ReplyHandleP2P};
 use crate::net::db::{PeerDB, *};
@@ -372,6 +373,9 @@ pub struct ConversationP2P {
 
     /// system epochs
     epochs: Vec<StacksEpoch>,
+
+    /// if set, every message sent and received on this conversation is recorded here
+    capture: Option<MessageCapture>,
 }
 
 impl fmt::Display for ConversationP2P {
@@ -584,6 +588,28 @@ impl ConversationP2P {
             db_smart_contracts: vec![],
 
             epochs: epochs,
+
+            capture: None,
+        }
+    }
+
+    /// Record all inbound and outbound messages on this conversation to the given capture
+    pub fn set_capture(&mut self, capture: Option<MessageCapture>) {
+        self.capture = capture;
+    }
+
+    /// Write a message to the capture, if capturing is enabled
+    fn capture_message(&self, direction: CaptureDirection, msg: &StacksMessage) {
+        if let Some(capture) = self.capture.as_ref() {
+            capture.record(
+                direction,
+                self.conn_id,
+                self.peer_addrbytes
+                    .to_socketaddr(self.peer_port)
+                    .to_string(),
+                self.get_public_key_hash(),
+                msg,
+            );
         }
     }
 
@@ -990,6 +1016,7 @@ impl ConversationP2P {
         let mut handle = self.connection.make_relay_handle(self.conn_id)?;
         let buf = msg.serialize_to_vec();
         handle.write_all(&buf).map_err(net_error::WriteError)?;
+        self.capture_message(CaptureDirection::Outbound, &msg);
 
         self.stats.msgs_tx += 1;
 
@@ -1016,6 +1043,7 @@ impl ConversationP2P {
                 .make_request_handle(msg.request_id(), ttl, self.conn_id)?;
         let buf = msg.serialize_to_vec();
         handle.write_all(&buf).map_err(net_error::WriteError)?;
+        self.capture_message(CaptureDirection::Outbound, &msg);
 
         self.stats.msgs_tx += 1;
 
@@ -2874,6 +2902,8 @@ impl ConversationP2P {
                 Some(m) => m,
             };
 
+            self.capture_message(CaptureDirection::Inbound, &msg);
+
             if !self.validate_inbound_message(&msg, network.get_chain_view())? {
                 continue;
             }
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
 use std::ops::{Deref, DerefMut};
 use std::sync::mpsc::{
@@ -45,6 +45,7 @@ use crate::net::neighbors::{
     WALK_MAX_DURATION, WALK_MIN_DURATION, WALK_RESET_INTERVAL, WALK_RESET_PROB, WALK_RETRY_COUNT,
     WALK_STATE_TIMEOUT,
 };
+use crate::net::rpc_access::RPCAccessTier;
 use crate::net::{
     Error as net_error, MessageSequence, Preamble, ProtocolFamily, RelayData, StacksHttp, StacksP2P,
 };
@@ -417,6 +418,13 @@ pub struct ConnectionOptions {
     pub force_nakamoto_epoch_transition: bool,
     /// The authorization token to enable the block proposal RPC endpoint
     pub block_proposal_token: Option<String>,
+    /// If set, record every p2p message sent and received to this file (see `net::capture`)
+    pub p2p_capture_path: Option<String>,
+    /// RPC API keys, and the access tier each one grants (see `net::rpc_access`)
+    pub rpc_api_keys: HashMap<String, RPCAccessTier>,
+    /// Access tier for RPC clients that do not present an API key.  Limits are applied per IP
+    /// address.
+    pub rpc_anonymous_tier: RPCAccessTier,
 }
 
 impl std::default::Default for ConnectionOptions {
@@ -511,6 +519,9 @@ impl std::default::Default for ConnectionOptions {
             force_disconnect_interval: None,
             force_nakamoto_epoch_transition: false,
             block_proposal_token: None,
+            p2p_capture_path: None,
+            rpc_api_keys: HashMap::new(),
+            rpc_anonymous_tier: RPCAccessTier::unlimited("anonymous"),
         }
     }
 }
@@ -101,6 +101,8 @@ pub fn http_reason(code: u16) -> &'static str {
         415 => "Unsupported Media Type",
         416 => "Requested range not satisfiable",
         417 => "Expectation Failed",
+        // from RFC 6585
+        429 => "Too Many Requests",
         500 => "Internal Server Error",
         501 => "Not Implemented",
         502 => "Bad Gateway",
@@ -129,6 +131,7 @@ pub fn http_error_from_code_and_text(code: u16, message: String) -> Box<dyn Http
         402 => Box::new(HttpPaymentRequired::new(message)),
         403 => Box::new(HttpForbidden::new(message)),
         404 => Box::new(HttpNotFound::new(message)),
+        429 => Box::new(HttpTooManyRequests::new(message)),
         500 => Box::new(HttpServerError::new(message)),
         503 => Box::new(HttpServiceUnavailable::new(message)),
         _ => Box::new(HttpError::new(code, message)),
@@ -292,6 +295,33 @@ impl HttpErrorResponse for HttpNotFound {
     }
 }
 
+/// HTTP 429
+pub struct HttpTooManyRequests {
+    error_text: String,
+}
+
+impl HttpTooManyRequests {
+    pub fn new(error_text: String) -> Self {
+        Self { error_text }
+    }
+}
+
+impl HttpErrorResponse for HttpTooManyRequests {
+    fn code(&self) -> u16 {
+        429
+    }
+    fn payload(&self) -> HttpResponsePayload {
+        HttpResponsePayload::Text(self.error_text.clone())
+    }
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        try_parse_error_response(preamble.status_code, preamble.content_type, body)
+    }
+}
+
 /// HTTP 500
 pub struct HttpServerError {
     error_text: String,
@@ -16,6 +16,7 @@
 
 pub mod common;
 pub mod error;
+pub mod openapi;
 pub mod request;
 pub mod response;
 pub mod stream;
@@ -39,7 +40,10 @@ pub use crate::net::http::common::{
 pub use crate::net::http::error::{
     http_error_from_code_and_text, http_reason, HttpBadRequest, HttpError, HttpErrorResponse,
     HttpForbidden, HttpNotFound, HttpPaymentRequired, HttpServerError, HttpServiceUnavailable,
-    HttpUnauthorized,
+    HttpTooManyRequests, HttpUnauthorized,
+};
+pub use crate::net::http::openapi::{
+    make_openapi_document, HttpEndpointDoc, HttpParamDoc, HttpParamLocation, HttpResponseDoc,
 };
 pub use crate::net::http::request::{
     HttpRequest, HttpRequestContents, HttpRequestPayload, HttpRequestPreamble,
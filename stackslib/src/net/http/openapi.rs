@@ -0,0 +1,356 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Machine-readable descriptions of HTTP endpoints.
+//!
+//! Each `HttpRequest` implementation may describe itself with an `HttpEndpointDoc`, from which
+//! an OpenAPI 3 document for the whole API is assembled.  Schemas are plain JSON Schema values
+//! (as used by OpenAPI 3.0), written alongside the request handler that produces them.
+
+use serde_json::{json, Map, Value};
+
+use crate::net::http::HttpContentType;
+
+/// The OpenAPI version of the documents produced by `make_openapi_document()`
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Where a request parameter is carried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpParamLocation {
+    Path,
+    Query,
+    Header,
+}
+
+impl HttpParamLocation {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            HttpParamLocation::Path => "path",
+            HttpParamLocation::Query => "query",
+            HttpParamLocation::Header => "header",
+        }
+    }
+}
+
+/// Description of a single request parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpParamDoc {
+    pub name: String,
+    pub location: HttpParamLocation,
+    pub description: String,
+    pub required: bool,
+    pub schema: Value,
+    /// An example value.  Path parameters always have one, so that the documented path
+    /// templates can be checked against the request's path regex.
+    pub example: Option<String>,
+}
+
+impl HttpParamDoc {
+    fn to_json(&self) -> Value {
+        let mut param = json!({
+            "name": self.name,
+            "in": self.location.as_str(),
+            "description": self.description,
+            "required": self.required,
+            "schema": self.schema,
+        });
+        if let Some(example) = self.example.as_ref() {
+            param["example"] = json!(example);
+        }
+        param
+    }
+}
+
+/// Description of a request or response body
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpBodyDoc {
+    pub content_type: HttpContentType,
+    pub schema: Value,
+}
+
+impl HttpBodyDoc {
+    fn to_json(&self) -> Value {
+        json!({
+            self.content_type.as_str(): {
+                "schema": self.schema
+            }
+        })
+    }
+}
+
+/// Description of one possible response to a request
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponseDoc {
+    pub status: u16,
+    pub description: String,
+    pub body: HttpBodyDoc,
+}
+
+/// Description of an HTTP endpoint: the paths it serves, the parameters and body it accepts, and
+/// the responses it produces.  The verb is taken from the `HttpRequest` implementation itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpEndpointDoc {
+    /// OpenAPI path templates, such as `/v2/accounts/{principal}`.  An endpoint whose path
+    /// regex has optional segments is described by one template per form.
+    pub paths: Vec<String>,
+    pub summary: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub params: Vec<HttpParamDoc>,
+    /// The accepted request bodies, one per content type
+    pub request_bodies: Vec<HttpBodyDoc>,
+    pub responses: Vec<HttpResponseDoc>,
+}
+
+impl HttpEndpointDoc {
+    pub fn new(path: &str, summary: &str) -> Self {
+        Self {
+            paths: vec![path.to_string()],
+            summary: summary.to_string(),
+            description: None,
+            tags: vec![],
+            params: vec![],
+            request_bodies: vec![],
+            responses: vec![],
+        }
+    }
+
+    /// Add another path template served by this endpoint
+    pub fn alt_path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Add a parameter captured from the request path.  Path parameters are always required.
+    pub fn path_param(
+        mut self,
+        name: &str,
+        description: &str,
+        schema: Value,
+        example: &str,
+    ) -> Self {
+        self.params.push(HttpParamDoc {
+            name: name.to_string(),
+            location: HttpParamLocation::Path,
+            description: description.to_string(),
+            required: true,
+            schema,
+            example: Some(example.to_string()),
+        });
+        self
+    }
+
+    /// Add a query string parameter
+    pub fn query_param(
+        mut self,
+        name: &str,
+        description: &str,
+        required: bool,
+        schema: Value,
+    ) -> Self {
+        self.params.push(HttpParamDoc {
+            name: name.to_string(),
+            location: HttpParamLocation::Query,
+            description: description.to_string(),
+            required,
+            schema,
+            example: None,
+        });
+        self
+    }
+
+    /// Add a request header parameter
+    pub fn header_param(
+        mut self,
+        name: &str,
+        description: &str,
+        required: bool,
+        schema: Value,
+    ) -> Self {
+        self.params.push(HttpParamDoc {
+            name: name.to_string(),
+            location: HttpParamLocation::Header,
+            description: description.to_string(),
+            required,
+            schema,
+            example: None,
+        });
+        self
+    }
+
+    /// Add an accepted request body
+    pub fn body(mut self, content_type: HttpContentType, schema: Value) -> Self {
+        self.request_bodies.push(HttpBodyDoc {
+            content_type,
+            schema,
+        });
+        self
+    }
+
+    /// Add a response
+    pub fn response(
+        mut self,
+        status: u16,
+        description: &str,
+        content_type: HttpContentType,
+        schema: Value,
+    ) -> Self {
+        self.responses.push(HttpResponseDoc {
+            status,
+            description: description.to_string(),
+            body: HttpBodyDoc {
+                content_type,
+                schema,
+            },
+        });
+        self
+    }
+
+    /// Add a JSON response
+    pub fn json_response(self, status: u16, description: &str, schema: Value) -> Self {
+        self.response(status, description, HttpContentType::JSON, schema)
+    }
+
+    /// Add an error response.  Errors carry a human-readable message.
+    pub fn error_response(self, status: u16, description: &str) -> Self {
+        self.response(
+            status,
+            description,
+            HttpContentType::Text,
+            json!({ "type": "string" }),
+        )
+    }
+
+    /// Get the names of the parameters in a path template, in order
+    pub fn path_template_params(path: &str) -> Vec<String> {
+        path.split('{')
+            .skip(1)
+            .filter_map(|s| s.split_once('}').map(|(name, _)| name.to_string()))
+            .collect()
+    }
+
+    /// Make a stable operation ID from the verb and path template.
+    /// e.g. `GET /v2/accounts/{principal}` becomes `get_v2_accounts_principal`.
+    pub fn operation_id(verb: &str, path: &str) -> String {
+        let mut op_id = verb.to_lowercase();
+        for word in path.split(|c: char| !c.is_ascii_alphanumeric()) {
+            if word.is_empty() {
+                continue;
+            }
+            op_id.push('_');
+            op_id.push_str(&word.to_lowercase());
+        }
+        op_id
+    }
+
+    /// Make the OpenAPI operation object for one of this endpoint's path templates
+    pub fn to_operation(&self, verb: &str, path: &str) -> Value {
+        let template_params = Self::path_template_params(path);
+        let params: Vec<_> = self
+            .params
+            .iter()
+            .filter(|param| {
+                param.location != HttpParamLocation::Path || template_params.contains(&param.name)
+            })
+            .map(|param| param.to_json())
+            .collect();
+
+        let mut responses = Map::new();
+        for response in self.responses.iter() {
+            responses.insert(
+                response.status.to_string(),
+                json!({
+                    "description": response.description,
+                    "content": response.body.to_json(),
+                }),
+            );
+        }
+
+        let mut operation = json!({
+            "operationId": Self::operation_id(verb, path),
+            "summary": self.summary,
+            "tags": self.tags,
+            "responses": responses,
+        });
+        if let Some(description) = self.description.as_ref() {
+            operation["description"] = json!(description);
+        }
+        if !params.is_empty() {
+            operation["parameters"] = json!(params);
+        }
+        if !self.request_bodies.is_empty() {
+            let mut content = Map::new();
+            for body in self.request_bodies.iter() {
+                content.insert(
+                    body.content_type.as_str().to_string(),
+                    json!({ "schema": body.schema }),
+                );
+            }
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": content,
+            });
+        }
+        operation
+    }
+}
+
+/// Assemble an OpenAPI document from a list of (verb, endpoint) pairs.
+/// If two endpoints claim the same verb and path, the first one wins.
+pub fn make_openapi_document<'a>(
+    title: &str,
+    version: &str,
+    description: &str,
+    endpoints: impl IntoIterator<Item = (&'a str, &'a HttpEndpointDoc)>,
+) -> Value {
+    let mut paths = Map::new();
+    for (verb, endpoint) in endpoints {
+        let verb = verb.to_lowercase();
+        for path in endpoint.paths.iter() {
+            let path_item = paths
+                .entry(path.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            let Some(path_item) = path_item.as_object_mut() else {
+                continue;
+            };
+            if path_item.contains_key(&verb) {
+                continue;
+            }
+            path_item.insert(verb.clone(), endpoint.to_operation(&verb, path));
+        }
+    }
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": title,
+            "version": version,
+            "description": description,
+            "license": {
+                "name": "GPL-3.0-or-later",
+            },
+        },
+        "paths": paths,
+    })
+}
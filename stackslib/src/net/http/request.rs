@@ -30,8 +30,8 @@ use crate::net::http::common::{
     HttpReservedHeader, HTTP_PREAMBLE_MAX_ENCODED_SIZE, HTTP_PREAMBLE_MAX_NUM_HEADERS,
 };
 use crate::net::http::{
-    default_accept_header, write_headers, Error, HttpContentType, HttpResponseContents,
-    HttpResponsePreamble, HttpVersion,
+    default_accept_header, write_headers, Error, HttpContentType, HttpEndpointDoc,
+    HttpResponseContents, HttpResponsePreamble, HttpVersion,
 };
 
 /// HTTP request preamble.  This captures "control plane" data for an HTTP request, and contains
@@ -697,4 +697,9 @@ pub trait HttpRequest: Send + HttpRequestClone {
     ) -> Result<HttpRequestContents, Error>;
     /// Get identifier from finite set to be used in metrics
     fn metrics_identifier(&self) -> &str;
+    /// Describe the paths, parameters, and responses of this request, for the OpenAPI document.
+    /// Every request registered with the RPC server should return Some(..).
+    fn endpoint_doc(&self) -> Option<HttpEndpointDoc> {
+        None
+    }
 }
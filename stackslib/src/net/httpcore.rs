@@ -18,6 +18,7 @@
 use std::collections::{BTreeMap, HashMap};
 use std::io::{Read, Write};
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::{fmt, io, mem};
 
 use clarity::vm::costs::ExecutionCost;
@@ -25,6 +26,7 @@ use clarity::vm::types::{QualifiedContractIdentifier, BOUND_VALUE_SERIALIZATION_
 use clarity::vm::{ClarityName, ContractName};
 use percent_encoding::percent_decode_str;
 use regex::{Captures, Regex};
+use serde_json::json;
 use stacks_common::codec::{read_next, Error as CodecError, StacksMessageCodec, MAX_MESSAGE_LEN};
 use stacks_common::types::chainstate::{
     ConsensusHash, StacksAddress, StacksBlockId, StacksPublicKey,
@@ -46,11 +48,13 @@ use crate::core::{MemPoolDB, StacksEpoch};
 use crate::net::connection::ConnectionOptions;
 use crate::net::http::common::HTTP_PREAMBLE_MAX_ENCODED_SIZE;
 use crate::net::http::{
-    http_reason, Error as HttpError, HttpBadRequest, HttpContentType, HttpErrorResponse,
-    HttpNotFound, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse,
-    HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError, HttpVersion,
+    http_reason, make_openapi_document, Error as HttpError, HttpBadRequest, HttpContentType,
+    HttpEndpointDoc, HttpErrorResponse, HttpNotFound, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError, HttpVersion,
 };
 use crate::net::p2p::PeerNetwork;
+use crate::net::rpc_access::RPCAccessControl;
 use crate::net::server::HttpPeer;
 use crate::net::{Error as NetError, MessageSequence, ProtocolFamily, StacksNodeState, UrlString};
 
@@ -340,6 +344,60 @@ impl HttpRequestContentsExtensions for HttpRequestContents {
     }
 }
 
+/// Extension to HttpEndpointDoc to describe Stacks-specific request parameters
+pub trait HttpEndpointDocExtensions {
+    /// Document the optional `tip` query parameter (see `HttpRequestContentsExtensions`)
+    fn tip_param(self) -> Self;
+    /// Document the optional `proof` query parameter (see `HttpRequestContentsExtensions`)
+    fn proof_param(self) -> Self;
+    /// Document the `address` and `contract` path parameters that identify a contract
+    fn contract_params(self) -> Self;
+}
+
+impl HttpEndpointDocExtensions for HttpEndpointDoc {
+    fn tip_param(self) -> Self {
+        self.query_param(
+            "tip",
+            "The Stacks chain tip to query from: an index block hash, or `latest` for the latest \
+             unconfirmed tip.  Defaults to the latest anchored tip.",
+            false,
+            json!({ "type": "string" }),
+        )
+    }
+
+    fn proof_param(self) -> Self {
+        self.query_param(
+            "proof",
+            "Set to `0` to omit the MARF proof from the response.  Defaults to `1`.",
+            false,
+            json!({ "type": "integer", "enum": [0, 1] }),
+        )
+    }
+
+    fn contract_params(self) -> Self {
+        self.path_param(
+            "address",
+            "Stacks address of the contract's deployer",
+            json!({ "type": "string" }),
+            "SP000000000000000000002Q6VF78",
+        )
+        .path_param(
+            "contract",
+            "Name of the contract",
+            json!({ "type": "string" }),
+            "pox-4",
+        )
+    }
+}
+
+/// Make the JSON schema of a hex string that encodes `num_bytes` bytes
+pub fn hex_schema(num_bytes: usize) -> serde_json::Value {
+    json!({
+        "type": "string",
+        "pattern": format!("^[0-9a-f]{{{}}}$", num_bytes * 2),
+    })
+}
+
 /// Work around Clone blanket implementations not being object-safe
 pub trait RPCRequestHandlerClone {
     fn clone_rpc_handler_box(&self) -> Box<dyn RPCRequestHandler>;
@@ -373,6 +431,29 @@ pub trait RPCRequestHandler: HttpRequest + HttpResponse + RPCRequestHandlerClone
         state: &mut StacksNodeState,
     ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError>;
 
+    /// Does this handler execute Clarity code on the caller's behalf?  If so, then requests are
+    /// charged against the caller's daily read-only execution budget (see `net::rpc_access`).
+    fn is_metered(&self) -> bool {
+        false
+    }
+
+    /// Can this handler defer a request (i.e. fail with `NetError::RequestDeferred`) until it can
+    /// be answered?  If so, the handler keeps the state it parsed from the request, and is run
+    /// again on later passes of the network loop until it produces a response.
+    fn can_defer(&self) -> bool {
+        false
+    }
+
+    /// Limit the Clarity runtime cost that the next request may consume.  Only called on
+    /// metered handlers, before `try_handle_request()`.
+    fn set_runtime_budget(&mut self, _runtime: u64) {}
+
+    /// Take the Clarity execution cost consumed by the last request.  Only called on metered
+    /// handlers, after `try_handle_request()`.
+    fn take_execution_cost(&mut self) -> Option<ExecutionCost> {
+        None
+    }
+
     /// Helper to get the canonical sortition tip
     fn get_canonical_burn_chain_tip(
         &self,
@@ -883,6 +964,11 @@ pub struct StacksHttp {
     pub read_only_call_limit: ExecutionCost,
     /// The authorization token to enable the block proposal RPC endpoint
     pub block_proposal_token: Option<String>,
+    /// API keys, rate limits, and execution budgets, shared with the node's other inbound HTTP
+    /// conversations.  Requests are not subject to access control if this is None.
+    pub rpc_access: Option<Arc<Mutex<RPCAccessControl>>>,
+    /// A request whose handler deferred it until it can be answered (e.g. a long poll)
+    deferred_request: Option<StacksHttpRequest>,
 }
 
 impl StacksHttp {
@@ -899,6 +985,8 @@ impl StacksHttp {
             maximum_call_argument_size: conn_opts.maximum_call_argument_size,
             read_only_call_limit: conn_opts.read_only_call_limit.clone(),
             block_proposal_token: conn_opts.block_proposal_token.clone(),
+            rpc_access: None,
+            deferred_request: None,
         };
         http.register_rpc_methods();
         http
@@ -916,6 +1004,32 @@ impl StacksHttp {
         ));
     }
 
+    /// Get the verb, path regex, and documentation of each registered request handler
+    pub fn endpoint_docs(&self) -> Vec<(&str, &Regex, Option<HttpEndpointDoc>)> {
+        self.request_handlers
+            .iter()
+            .map(|(verb, regex, handler)| (verb.as_str(), regex, handler.endpoint_doc()))
+            .collect()
+    }
+
+    /// Generate the OpenAPI document describing all registered request handlers.
+    /// Undocumented handlers are left out.
+    pub fn openapi_document(&self) -> serde_json::Value {
+        let version = option_env!("STACKS_NODE_VERSION")
+            .or(option_env!("CARGO_PKG_VERSION"))
+            .unwrap_or("0.0.0.0");
+        let endpoint_docs = self.endpoint_docs();
+        make_openapi_document(
+            "Stacks node RPC API",
+            version,
+            "The RPC interface of the `stacks-node`.  This document is generated from the node's \
+             request handlers.",
+            endpoint_docs
+                .iter()
+                .filter_map(|(verb, _, doc)| doc.as_ref().map(|doc| (*verb, doc))),
+        )
+    }
+
     /// Find the HTTP request handler to use to process the reply, given the request path.
     /// Returns the index into the list of handlers
     fn find_response_handler(&self, request_verb: &str, request_path: &str) -> Option<usize> {
@@ -1113,14 +1227,98 @@ impl StacksHttp {
             .get_mut(response_handler_index)
             .expect("FATAL: request points to a nonexistent handler");
         let request_preamble = request.preamble.clone();
+        // only requests to handlers that can defer them are kept around for a retry
+        let retry_request = if request_handler.can_defer() {
+            let mut retry_request = request.clone();
+            retry_request.response_handler_index = Some(response_handler_index);
+            Some(retry_request)
+        } else {
+            None
+        };
+
+        let now_ms = u64::try_from(get_epoch_time_ms()).unwrap_or(u64::MAX);
+        let admission = if let Some(rpc_access) = self.rpc_access.as_ref() {
+            let mut rpc_access = rpc_access
+                .lock()
+                .expect("FATAL: RPC access control lock is poisoned");
+            let admission_res = rpc_access
+                .identify(self.peer_addr.ip(), &request_preamble)
+                .and_then(|client| rpc_access.admit(client, request_handler.is_metered(), now_ms));
+            match admission_res {
+                Ok(admission) => Some(admission),
+                Err(e) => {
+                    debug!("Refused RPC request"; "path" => %request_preamble.path_and_query_str, "peer_addr" => %self.peer_addr, "reason" => %e);
+                    return e.try_into_contents(&request_preamble);
+                }
+            }
+        } else {
+            None
+        };
+        if let Some(runtime_budget) = admission.as_ref().and_then(|adm| adm.runtime_budget) {
+            request_handler.set_runtime_budget(runtime_budget);
+        }
+
         let request_result =
             request_handler.try_handle_request(request.preamble, request.contents, node);
+
+        if let (Some(rpc_access), Some(admission)) = (self.rpc_access.as_ref(), admission) {
+            if let Some(cost) = request_handler.take_execution_cost() {
+                rpc_access
+                    .lock()
+                    .expect("FATAL: RPC access control lock is poisoned")
+                    .charge(&admission.client, &cost, now_ms);
+            }
+        }
+        if let Err(NetError::RequestDeferred) = request_result {
+            self.deferred_request = Some(retry_request.ok_or(NetError::InvalidState)?);
+        }
+        Self::finish_request(request_handler, &request_preamble, request_result)
+    }
+
+    /// Run the handler of the deferred request again.  The request was already admitted, so it is
+    /// not subject to access control again.
+    /// Returns Err(NetError::RequestDeferred) if the handler deferred it again.
+    pub fn try_handle_deferred_request(
+        &mut self,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let request = self.deferred_request.take().ok_or(NetError::InvalidState)?;
+        let response_handler_index = request
+            .response_handler_index
+            .ok_or(NetError::InvalidState)?;
+        let (_, _, request_handler) = self
+            .request_handlers
+            .get_mut(response_handler_index)
+            .expect("FATAL: request points to a nonexistent handler");
+        let request_preamble = request.preamble.clone();
+        let request_result = request_handler.try_handle_request(
+            request.preamble.clone(),
+            request.contents.clone(),
+            node,
+        );
+        if let Err(NetError::RequestDeferred) = request_result {
+            self.deferred_request = Some(request);
+        }
+        Self::finish_request(request_handler, &request_preamble, request_result)
+    }
+
+    /// Reset a request handler once it has produced a response, and turn its errors into HTTP
+    /// error responses where possible.  A deferred request keeps its handler's state, so the
+    /// handler can be run again.
+    fn finish_request(
+        request_handler: &mut Box<dyn RPCRequestHandler>,
+        request_preamble: &HttpRequestPreamble,
+        request_result: Result<(HttpResponsePreamble, HttpResponseContents), NetError>,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        if let Err(NetError::RequestDeferred) = request_result {
+            return Err(NetError::RequestDeferred);
+        }
         request_handler.restart();
 
         let (response_preamble, response_contents) = match request_result {
             Ok((rp, rc)) => (rp, rc),
             Err(NetError::Http(e)) => {
-                return StacksHttpResponse::new_error(&request_preamble, &*e.into_http_error())
+                return StacksHttpResponse::new_error(request_preamble, &*e.into_http_error())
                     .try_into_contents()
             }
             Err(e) => {
@@ -105,6 +105,9 @@ pub mod asn;
 /// Implements the Atlas network. This network uses the infrastructure created in `src/net` to
 /// discover peers, query attachment inventories, and download attachments.
 pub mod atlas;
+/// Implements recording of p2p traffic to capture files, and replaying captures against a
+/// `PeerNetwork`.
+pub mod capture;
 /// Implements the `ConversationP2P` object, a host-to-host session abstraction which allows
 /// the node to recieve `StacksMessage` instances. The downstream consumer of this API is `PeerNetwork`.
 /// To use OSI terminology, this module implements the session & presentation layers of the P2P network.
@@ -136,6 +139,8 @@ pub mod poll;
 pub mod prune;
 pub mod relay;
 pub mod rpc;
+/// Implements API keys, rate limits, and execution budgets for RPC clients
+pub mod rpc_access;
 pub mod server;
 pub mod stackerdb;
 
@@ -285,6 +290,8 @@ pub enum Error {
     InvalidState,
     /// Waiting for DNS resolution
     WaitingForDNS,
+    /// The RPC request cannot be answered yet, and should be handled again later
+    RequestDeferred,
 }
 
 impl From<libstackerdb_error> for Error {
@@ -433,6 +440,7 @@ impl fmt::Display for Error {
             Error::Http(e) => fmt::Display::fmt(&e, f),
             Error::InvalidState => write!(f, "Invalid state-machine state reached"),
             Error::WaitingForDNS => write!(f, "Waiting for DNS resolution"),
+            Error::RequestDeferred => write!(f, "Request deferred"),
         }
     }
 }
@@ -506,6 +514,7 @@ impl error::Error for Error {
             Error::Http(ref e) => Some(e),
             Error::InvalidState => None,
             Error::WaitingForDNS => None,
+            Error::RequestDeferred => None,
         }
     }
 }
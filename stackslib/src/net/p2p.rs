@@ -54,6 +54,9 @@ use crate::core::StacksEpoch;
 use crate::monitoring::{update_inbound_neighbors, update_outbound_neighbors};
 use crate::net::asn::ASEntry4;
 use crate::net::atlas::{AtlasDB, AttachmentInstance, AttachmentsDownloader};
+use crate::net::capture::MessageCapture;
+#[cfg(any(test, feature = "testing"))]
+use crate::net::capture::{CaptureDirection, CaptureRecord};
 use crate::net::chat::{ConversationP2P, NeighborStats};
 use crate::net::connection::{ConnectionOptions, NetworkReplyHandle, ReplyHandleP2P};
 use crate::net::db::{LocalPeer, PeerDB};
@@ -408,6 +411,9 @@ pub struct PeerNetwork {
 
     /// Thread handle for the async block proposal endpoint.
     block_proposal_thread: Option<JoinHandle<()>>,
+
+    /// Capture file for p2p traffic, shared by all conversations
+    capture: Option<MessageCapture>,
 }
 
 impl PeerNetwork {
@@ -446,6 +452,17 @@ impl PeerNetwork {
         let first_burn_header_hash = burnchain.first_block_hash.clone();
         let first_burn_header_ts = burnchain.first_block_timestamp;
 
+        let capture = connection_opts.p2p_capture_path.as_ref().and_then(|path| {
+            MessageCapture::open(path)
+                .map_err(|e| {
+                    warn!(
+                        "{:?}: Failed to open p2p capture file {}: {:?}",
+                        &local_peer, path, &e
+                    );
+                })
+                .ok()
+        });
+
         let mut stacker_db_configs = HashMap::new();
         let mut stacker_db_sync_map = HashMap::new();
         for (contract_id, (stacker_db_config, stacker_db_sync)) in stacker_db_syncs.into_iter() {
@@ -566,6 +583,8 @@ impl PeerNetwork {
             nakamoto_inv_generator: InvGenerator::new(),
 
             block_proposal_thread: None,
+
+            capture,
         };
 
         network.init_block_downloader();
@@ -1783,6 +1802,7 @@ impl PeerNetwork {
             self.epochs.clone(),
         );
         new_convo.set_public_key(pubkey_opt);
+        new_convo.set_capture(self.capture.clone());
 
         debug!(
             "{:?}: Registered {} as event {} ({:?},outbound={})",
@@ -5178,6 +5198,44 @@ impl PeerNetwork {
         unhandled
     }
 
+    /// Replay the inbound messages of a capture against this network, as though they had all
+    /// arrived on the conversation identified by `event_id`.  The messages are handed to the same
+    /// unsolicited-message handling that a live conversation would use (i.e. they bypass the
+    /// conversation's own control-plane handling), so this is useful for reproducing relay,
+    /// inventory, and StackerDB push behavior from real network traffic.
+    ///
+    /// Outbound records are ignored.  Returns the messages that would have been forwarded to the
+    /// relayer.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn replay_capture(
+        &mut self,
+        sortdb: &SortitionDB,
+        chainstate: &StacksChainState,
+        event_id: usize,
+        records: &[CaptureRecord],
+        ibd: bool,
+    ) -> Result<HashMap<NeighborKey, Vec<StacksMessage>>, net_error> {
+        if !self.peers.contains_key(&event_id) {
+            return Err(net_error::PeerNotConnected);
+        }
+        let mut messages = vec![];
+        for record in records.iter() {
+            if record.direction != CaptureDirection::Inbound {
+                continue;
+            }
+            messages.push(record.decode_message()?);
+        }
+        debug!(
+            "{:?}: replay {} captured messages on event {}",
+            &self.local_peer,
+            messages.len(),
+            event_id
+        );
+        let mut unsolicited = HashMap::new();
+        unsolicited.insert(event_id, messages);
+        Ok(self.handle_unsolicited_messages(sortdb, chainstate, unsolicited, ibd, false))
+    }
+
     /// Find unauthenticated inbound conversations
     fn find_unauthenticated_inbound_convos(&self) -> Vec<usize> {
         let mut ret = vec![];
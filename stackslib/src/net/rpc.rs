@@ -18,6 +18,7 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::prelude::*;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use std::{fmt, io};
 
@@ -66,12 +67,13 @@ use crate::cost_estimates::{CostEstimator, FeeEstimator};
 use crate::net::atlas::{AtlasDB, Attachment, MAX_ATTACHMENT_INV_PAGES_PER_REQUEST};
 use crate::net::connection::{ConnectionHttp, ConnectionOptions, ReplyHandleHttp};
 use crate::net::db::PeerDB;
-use crate::net::http::{HttpRequestContents, HttpResponseContents};
+use crate::net::http::{HttpRequestContents, HttpResponseContents, HttpResponsePreamble};
 use crate::net::httpcore::{
     StacksHttp, StacksHttpMessage, StacksHttpRequest, StacksHttpResponse, HTTP_REQUEST_ID_RESERVED,
 };
 use crate::net::p2p::{PeerMap, PeerNetwork};
 use crate::net::relay::Relayer;
+use crate::net::rpc_access::RPCAccessControl;
 use crate::net::stackerdb::{StackerDBTx, StackerDBs};
 use crate::net::{Error as net_error, StacksMessageType, StacksNodeState};
 use crate::util_lib::boot::boot_code_id;
@@ -116,6 +118,9 @@ pub struct ConversationHttp {
     pending_response: Option<StacksHttpResponse>,
     /// whether or not there's an error response pending
     pending_error_response: bool,
+    /// if a request handler deferred its request, whether to keep the connection alive once it
+    /// is answered.  Later requests wait for it, so replies go out in the order of the requests.
+    deferred_keep_alive: Option<bool>,
     /// how much data to buffer (i.e. the socket's send buffer size)
     socket_send_buffer_size: u32,
 }
@@ -166,6 +171,7 @@ impl ConversationHttp {
             pending_request: None,
             pending_response: None,
             pending_error_response: false,
+            deferred_keep_alive: None,
             keep_alive: true,
             total_request_count: 0,
             total_reply_count: 0,
@@ -191,6 +197,11 @@ impl ConversationHttp {
         &self.peer_addr
     }
 
+    /// Subject the requests on this conversation to the given (shared) access control
+    pub fn set_rpc_access(&mut self, rpc_access: Arc<Mutex<RPCAccessControl>>) {
+        self.connection.protocol.rpc_access = Some(rpc_access);
+    }
+
     /// Is a request in-progress?
     pub fn is_request_inflight(&self) -> bool {
         self.pending_request.is_some()
@@ -282,8 +293,40 @@ impl ConversationHttp {
     ) -> Result<Option<StacksMessageType>, net_error> {
         // NOTE: This may set node.relay_message
         let keep_alive = req.preamble().keep_alive;
-        let (mut response_preamble, response_body) =
-            self.connection.protocol.try_handle_request(req, node)?;
+        let response = self.connection.protocol.try_handle_request(req, node);
+        self.queue_response(keep_alive, response, node)
+    }
+
+    /// Run the handler of a deferred request again.
+    /// Returns a StacksMessageType option, like `handle_request()`.
+    fn handle_deferred_request(
+        &mut self,
+        node: &mut StacksNodeState,
+    ) -> Result<Option<StacksMessageType>, net_error> {
+        let Some(keep_alive) = self.deferred_keep_alive.take() else {
+            return Ok(None);
+        };
+        let response = self.connection.protocol.try_handle_deferred_request(node);
+        self.queue_response(keep_alive, response, node)
+    }
+
+    /// Queue up a request handler's response to be streamed back, unless the handler deferred
+    /// the request.
+    fn queue_response(
+        &mut self,
+        keep_alive: bool,
+        response: Result<(HttpResponsePreamble, HttpResponseContents), net_error>,
+        node: &mut StacksNodeState,
+    ) -> Result<Option<StacksMessageType>, net_error> {
+        let (mut response_preamble, response_body) = match response {
+            Ok(response) => response,
+            Err(net_error::RequestDeferred) => {
+                // the handler will run again on a later pass
+                self.deferred_keep_alive = Some(keep_alive);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
 
         let mut reply = self.connection.make_relay_handle(self.conn_id)?;
         let relay_msg_opt = node.take_relay_message();
@@ -487,6 +530,11 @@ impl ConversationHttp {
             && self.reply_streams.len() == 0
     }
 
+    /// Is a request waiting for its handler to run again?
+    pub fn has_deferred_request(&self) -> bool {
+        self.deferred_keep_alive.is_some()
+    }
+
     /// Is the conversation out of pending data?
     /// Don't consider it drained if we haven't received anything yet
     pub fn is_drained(&self) -> bool {
@@ -527,9 +575,20 @@ impl ConversationHttp {
             return Ok(vec![]);
         }
 
+        let mut ret = vec![];
+
+        // a deferred request must be answered before any later request
+        if self.has_deferred_request() {
+            if let Some(msg) = self.handle_deferred_request(node)? {
+                ret.push(msg);
+            }
+            if self.has_deferred_request() {
+                return Ok(ret);
+            }
+        }
+
         // handle in-bound HTTP request(s)
         let num_inbound = self.connection.inbox_len();
-        let mut ret = vec![];
         test_debug!("{:?}: {} HTTP requests pending", &self, num_inbound);
 
         for _i in 0..num_inbound {
@@ -563,6 +622,10 @@ impl ConversationHttp {
                     if let Some(msg) = msg_opt {
                         ret.push(msg);
                     }
+                    if self.has_deferred_request() {
+                        // later requests stay in the inbox until this one is answered
+                        break;
+                    }
                 }
                 StacksHttpMessage::Error(path, resp) => {
                     // new request, but resulted in an error when parsing it
@@ -0,0 +1,589 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Access control for the RPC interface.
+//!
+//! Clients may identify themselves with an API key in the `x-api-key` header.  Each configured
+//! key maps to an `RPCAccessTier`, which bounds how many requests per minute the key may issue
+//! and how much Clarity runtime cost its read-only calls may consume per UTC day.  Requests
+//! without a key are held to `ConnectionOptions::rpc_anonymous_tier`, and are accounted for by
+//! the client's IP address.  Clients that exceed a limit get an HTTP 429 with a `Retry-After`
+//! header.
+//!
+//! A single `RPCAccessControl` instance is shared by every inbound HTTP conversation of an
+//! `HttpPeer`, so limits hold across connections.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+
+use clarity::vm::costs::ExecutionCost;
+
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::{
+    HttpErrorResponse, HttpRequestPreamble, HttpResponseContents, HttpResponsePreamble,
+    HttpTooManyRequests, HttpUnauthorized,
+};
+use crate::net::httpcore::StacksHttpResponse;
+use crate::net::Error as NetError;
+
+/// Request header that carries a client's API key
+pub const RPC_API_KEY_HEADER: &str = "x-api-key";
+
+const MS_PER_MINUTE: u64 = 60_000;
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// How often to forget about clients that are back to a clean slate
+const PRUNE_INTERVAL_MS: u64 = 60_000;
+
+/// Limits that apply to a class of RPC clients
+#[derive(Debug, Clone, PartialEq)]
+pub struct RPCAccessTier {
+    pub name: String,
+    /// Maximum sustained number of requests per minute.  0 means unlimited.
+    pub requests_per_minute: u64,
+    /// Maximum total Clarity runtime cost that read-only function calls may consume per UTC
+    /// day.  0 means unlimited.
+    pub daily_read_only_runtime: u64,
+}
+
+impl RPCAccessTier {
+    /// A tier without any limits
+    pub fn unlimited(name: &str) -> RPCAccessTier {
+        RPCAccessTier {
+            name: name.to_string(),
+            requests_per_minute: 0,
+            daily_read_only_runtime: 0,
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute == 0 && self.daily_read_only_runtime == 0
+    }
+}
+
+/// Identity by which a client's usage is accounted
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RPCClientId {
+    ApiKey(String),
+    Ip(IpAddr),
+}
+
+impl fmt::Display for RPCClientId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // don't leak the key into the logs
+            RPCClientId::ApiKey(key) => {
+                write!(f, "key:{}...", key.chars().take(4).collect::<String>())
+            }
+            RPCClientId::Ip(ip) => write!(f, "ip:{}", ip),
+        }
+    }
+}
+
+/// Why a request was turned away
+#[derive(Debug, Clone, PartialEq)]
+pub enum RPCAccessError {
+    /// The API key is not configured on this node
+    UnknownApiKey,
+    /// The client exceeded its request rate, and may retry after this many seconds
+    RateLimited(u64),
+    /// The client spent its read-only execution budget for the day, and may retry after this
+    /// many seconds
+    BudgetExhausted(u64),
+}
+
+impl fmt::Display for RPCAccessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RPCAccessError::UnknownApiKey => write!(f, "Unknown API key"),
+            RPCAccessError::RateLimited(secs) => {
+                write!(f, "Request rate limit exceeded; retry in {} seconds", secs)
+            }
+            RPCAccessError::BudgetExhausted(secs) => write!(
+                f,
+                "Daily read-only execution budget exhausted; retry in {} seconds",
+                secs
+            ),
+        }
+    }
+}
+
+impl RPCAccessError {
+    /// Seconds the client should wait before trying again, if waiting helps
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            RPCAccessError::UnknownApiKey => None,
+            RPCAccessError::RateLimited(secs) | RPCAccessError::BudgetExhausted(secs) => {
+                Some(*secs)
+            }
+        }
+    }
+
+    /// Make the HTTP error response for this refusal
+    pub fn try_into_contents(
+        self,
+        preamble: &HttpRequestPreamble,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let error: Box<dyn HttpErrorResponse> = match self {
+            RPCAccessError::UnknownApiKey => Box::new(HttpUnauthorized::new(self.to_string())),
+            RPCAccessError::RateLimited(..) | RPCAccessError::BudgetExhausted(..) => {
+                Box::new(HttpTooManyRequests::new(self.to_string()))
+            }
+        };
+        let (mut response_preamble, response_contents) =
+            StacksHttpResponse::new_error(preamble, &*error).try_into_contents()?;
+        if let Some(secs) = self.retry_after() {
+            response_preamble.add_header("Retry-After".into(), format!("{}", secs));
+        }
+        Ok((response_preamble, response_contents))
+    }
+}
+
+/// A request that passed access control
+#[derive(Debug, Clone, PartialEq)]
+pub struct RPCAdmission {
+    pub client: RPCClientId,
+    /// If the request is metered and the client's tier has a daily execution budget, this is
+    /// how much Clarity runtime cost the client has left today.
+    pub runtime_budget: Option<u64>,
+}
+
+/// Usage of a single client
+#[derive(Debug, Clone, PartialEq)]
+struct RPCClientUsage {
+    /// Token bucket, in units of 1/60000th of a request so that refills are exact
+    bucket: u64,
+    /// Last time the bucket was refilled
+    last_refill_ms: u64,
+    /// The UTC day that `runtime_spent` covers
+    day: u64,
+    /// Clarity runtime cost spent on read-only calls during `day`
+    runtime_spent: u64,
+}
+
+impl RPCClientUsage {
+    fn new(tier: &RPCAccessTier, now_ms: u64) -> RPCClientUsage {
+        RPCClientUsage {
+            bucket: tier.requests_per_minute.saturating_mul(MS_PER_MINUTE),
+            last_refill_ms: now_ms,
+            day: now_ms / MS_PER_DAY,
+            runtime_spent: 0,
+        }
+    }
+
+    /// Top up the token bucket and roll over the daily budget
+    fn refresh(&mut self, tier: &RPCAccessTier, now_ms: u64) {
+        let capacity = tier.requests_per_minute.saturating_mul(MS_PER_MINUTE);
+        let elapsed = now_ms.saturating_sub(self.last_refill_ms);
+        self.bucket = self
+            .bucket
+            .saturating_add(elapsed.saturating_mul(tier.requests_per_minute))
+            .min(capacity);
+        self.last_refill_ms = now_ms.max(self.last_refill_ms);
+
+        let today = now_ms / MS_PER_DAY;
+        if today != self.day {
+            self.day = today;
+            self.runtime_spent = 0;
+        }
+    }
+
+    /// Is this client indistinguishable from one we have never seen?
+    fn is_idle(&self, tier: &RPCAccessTier) -> bool {
+        self.bucket >= tier.requests_per_minute.saturating_mul(MS_PER_MINUTE)
+            && self.runtime_spent == 0
+    }
+}
+
+/// Shared access-control state for the RPC interface
+#[derive(Debug, Clone, PartialEq)]
+pub struct RPCAccessControl {
+    /// API key to tier
+    api_keys: HashMap<String, RPCAccessTier>,
+    /// Tier for clients that do not present an API key
+    anonymous_tier: RPCAccessTier,
+    /// Usage of each client we are tracking
+    usage: HashMap<RPCClientId, RPCClientUsage>,
+    last_prune_ms: u64,
+}
+
+impl RPCAccessControl {
+    pub fn new(
+        api_keys: HashMap<String, RPCAccessTier>,
+        anonymous_tier: RPCAccessTier,
+    ) -> RPCAccessControl {
+        RPCAccessControl {
+            api_keys,
+            anonymous_tier,
+            usage: HashMap::new(),
+            last_prune_ms: 0,
+        }
+    }
+
+    /// Instantiate from the node's connection options.
+    /// Returns None if no API keys or limits are configured.
+    pub fn from_connection_options(conn_opts: &ConnectionOptions) -> Option<RPCAccessControl> {
+        if conn_opts.rpc_api_keys.is_empty() && conn_opts.rpc_anonymous_tier.is_unlimited() {
+            return None;
+        }
+        Some(RPCAccessControl::new(
+            conn_opts.rpc_api_keys.clone(),
+            conn_opts.rpc_anonymous_tier.clone(),
+        ))
+    }
+
+    fn get_tier(&self, client: &RPCClientId) -> &RPCAccessTier {
+        match client {
+            RPCClientId::ApiKey(key) => self.api_keys.get(key).unwrap_or(&self.anonymous_tier),
+            RPCClientId::Ip(..) => &self.anonymous_tier,
+        }
+    }
+
+    /// Identify the client that sent a request
+    pub fn identify(
+        &self,
+        peer_ip: IpAddr,
+        preamble: &HttpRequestPreamble,
+    ) -> Result<RPCClientId, RPCAccessError> {
+        match preamble.headers.get(RPC_API_KEY_HEADER) {
+            Some(key) if self.api_keys.contains_key(key) => Ok(RPCClientId::ApiKey(key.clone())),
+            Some(_) => Err(RPCAccessError::UnknownApiKey),
+            None => Ok(RPCClientId::Ip(peer_ip)),
+        }
+    }
+
+    /// Decide whether or not to serve a request from `client`.  This consumes one unit of the
+    /// client's request rate.  If `metered` is true, then the request will execute Clarity code
+    /// and is refused if the client's daily read-only budget is spent.
+    pub fn admit(
+        &mut self,
+        client: RPCClientId,
+        metered: bool,
+        now_ms: u64,
+    ) -> Result<RPCAdmission, RPCAccessError> {
+        self.prune(now_ms);
+
+        let tier = self.get_tier(&client).clone();
+        if tier.is_unlimited() {
+            return Ok(RPCAdmission {
+                client,
+                runtime_budget: None,
+            });
+        }
+
+        let usage = self
+            .usage
+            .entry(client.clone())
+            .or_insert_with(|| RPCClientUsage::new(&tier, now_ms));
+        usage.refresh(&tier, now_ms);
+
+        if tier.requests_per_minute > 0 {
+            if usage.bucket < MS_PER_MINUTE {
+                // time until one whole request's worth of tokens has accrued
+                let wait_ms = (MS_PER_MINUTE - usage.bucket).div_ceil(tier.requests_per_minute);
+                return Err(RPCAccessError::RateLimited(wait_ms.div_ceil(1000).max(1)));
+            }
+            usage.bucket -= MS_PER_MINUTE;
+        }
+
+        let mut runtime_budget = None;
+        if metered && tier.daily_read_only_runtime > 0 {
+            let remaining = tier
+                .daily_read_only_runtime
+                .saturating_sub(usage.runtime_spent);
+            if remaining == 0 {
+                let wait_ms = (usage.day + 1) * MS_PER_DAY - now_ms;
+                return Err(RPCAccessError::BudgetExhausted(
+                    wait_ms.div_ceil(1000).max(1),
+                ));
+            }
+            runtime_budget = Some(remaining);
+        }
+
+        Ok(RPCAdmission {
+            client,
+            runtime_budget,
+        })
+    }
+
+    /// Charge the Clarity execution cost of a read-only call to the client that made it
+    pub fn charge(&mut self, client: &RPCClientId, cost: &ExecutionCost, now_ms: u64) {
+        let tier = self.get_tier(client).clone();
+        if tier.daily_read_only_runtime == 0 {
+            return;
+        }
+        let usage = self
+            .usage
+            .entry(client.clone())
+            .or_insert_with(|| RPCClientUsage::new(&tier, now_ms));
+        usage.refresh(&tier, now_ms);
+        usage.runtime_spent = usage.runtime_spent.saturating_add(cost.runtime);
+        debug!(
+            "RPC client {} spent {} of {} read-only runtime today",
+            client, usage.runtime_spent, tier.daily_read_only_runtime
+        );
+    }
+
+    /// How much read-only runtime has this client spent today?
+    pub fn get_runtime_spent(&self, client: &RPCClientId, now_ms: u64) -> u64 {
+        self.usage
+            .get(client)
+            .filter(|usage| usage.day == now_ms / MS_PER_DAY)
+            .map(|usage| usage.runtime_spent)
+            .unwrap_or(0)
+    }
+
+    /// Forget about clients whose usage has fully reset, so the state does not grow without
+    /// bound as new IP addresses show up.
+    fn prune(&mut self, now_ms: u64) {
+        if now_ms.saturating_sub(self.last_prune_ms) < PRUNE_INTERVAL_MS {
+            return;
+        }
+        self.last_prune_ms = now_ms;
+
+        let mut usage = std::mem::take(&mut self.usage);
+        usage.retain(|client, client_usage| {
+            let tier = self.get_tier(client);
+            client_usage.refresh(tier, now_ms);
+            !client_usage.is_idle(tier)
+        });
+        self.usage = usage;
+    }
+
+    #[cfg(test)]
+    pub fn num_tracked_clients(&self) -> usize {
+        self.usage.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use stacks_common::types::net::PeerHost;
+
+    use super::*;
+
+    fn make_access_control() -> RPCAccessControl {
+        let mut api_keys = HashMap::new();
+        api_keys.insert(
+            "premium-key".to_string(),
+            RPCAccessTier {
+                name: "premium".into(),
+                requests_per_minute: 0,
+                daily_read_only_runtime: 1000,
+            },
+        );
+        api_keys.insert(
+            "basic-key".to_string(),
+            RPCAccessTier {
+                name: "basic".into(),
+                requests_per_minute: 6,
+                daily_read_only_runtime: 0,
+            },
+        );
+        RPCAccessControl::new(
+            api_keys,
+            RPCAccessTier {
+                name: "anonymous".into(),
+                requests_per_minute: 2,
+                daily_read_only_runtime: 100,
+            },
+        )
+    }
+
+    fn make_preamble(api_key: Option<&str>) -> HttpRequestPreamble {
+        let mut preamble = HttpRequestPreamble::new_for_peer(
+            PeerHost::from_socketaddr(&"127.0.0.1:20443".parse().unwrap()),
+            "GET".into(),
+            "/v2/info".into(),
+        );
+        if let Some(api_key) = api_key {
+            preamble.add_header(RPC_API_KEY_HEADER.into(), api_key.into());
+        }
+        preamble
+    }
+
+    #[test]
+    fn test_identify() {
+        let access = make_access_control();
+        let ip: IpAddr = "1.2.3.4".parse().unwrap();
+
+        assert_eq!(
+            access.identify(ip, &make_preamble(None)).unwrap(),
+            RPCClientId::Ip(ip)
+        );
+        assert_eq!(
+            access
+                .identify(ip, &make_preamble(Some("basic-key")))
+                .unwrap(),
+            RPCClientId::ApiKey("basic-key".into())
+        );
+        assert_eq!(
+            access
+                .identify(ip, &make_preamble(Some("bogus-key")))
+                .unwrap_err(),
+            RPCAccessError::UnknownApiKey
+        );
+    }
+
+    #[test]
+    fn test_client_id_display() {
+        let ip: IpAddr = "1.2.3.4".parse().unwrap();
+        assert_eq!(RPCClientId::Ip(ip).to_string(), "ip:1.2.3.4");
+        assert_eq!(
+            RPCClientId::ApiKey("basic-key".into()).to_string(),
+            "key:basi..."
+        );
+        assert_eq!(RPCClientId::ApiKey("ab".into()).to_string(), "key:ab...");
+        // multi-byte keys must not be split mid-character
+        assert_eq!(
+            RPCClientId::ApiKey("ключ-123".into()).to_string(),
+            "key:ключ..."
+        );
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut access = make_access_control();
+        let ip_1 = RPCClientId::Ip("1.2.3.4".parse().unwrap());
+        let ip_2 = RPCClientId::Ip("5.6.7.8".parse().unwrap());
+        let basic = RPCClientId::ApiKey("basic-key".into());
+        let premium = RPCClientId::ApiKey("premium-key".into());
+        let now = 1_000_000_000;
+
+        // anonymous clients get a burst of 2
+        access.admit(ip_1.clone(), false, now).unwrap();
+        access.admit(ip_1.clone(), false, now).unwrap();
+
+        // one request accrues every 30 seconds
+        assert_eq!(
+            access.admit(ip_1.clone(), false, now).unwrap_err(),
+            RPCAccessError::RateLimited(30)
+        );
+        assert_eq!(
+            access.admit(ip_1.clone(), false, now + 20_000).unwrap_err(),
+            RPCAccessError::RateLimited(10)
+        );
+
+        // limits are per IP
+        access.admit(ip_2.clone(), false, now).unwrap();
+
+        // and refill over time
+        access.admit(ip_1.clone(), false, now + 30_000).unwrap();
+        assert!(access.admit(ip_1.clone(), false, now + 30_000).is_err());
+
+        // keyed clients use their tier's limit
+        for _ in 0..6 {
+            access.admit(basic.clone(), false, now).unwrap();
+        }
+        assert_eq!(
+            access.admit(basic.clone(), false, now).unwrap_err(),
+            RPCAccessError::RateLimited(10)
+        );
+
+        // unlimited request rate
+        for _ in 0..100 {
+            access.admit(premium.clone(), false, now).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_only_budget() {
+        let mut access = make_access_control();
+        let premium = RPCClientId::ApiKey("premium-key".into());
+        let basic = RPCClientId::ApiKey("basic-key".into());
+        let day = 20_000;
+        let now = day * MS_PER_DAY + 1000;
+
+        let cost = ExecutionCost {
+            runtime: 600,
+            ..ExecutionCost::zero()
+        };
+
+        // unmetered requests don't get a budget
+        let admission = access.admit(premium.clone(), false, now).unwrap();
+        assert_eq!(admission.runtime_budget, None);
+
+        let admission = access.admit(premium.clone(), true, now).unwrap();
+        assert_eq!(admission.runtime_budget, Some(1000));
+        access.charge(&premium, &cost, now);
+        assert_eq!(access.get_runtime_spent(&premium, now), 600);
+
+        let admission = access.admit(premium.clone(), true, now).unwrap();
+        assert_eq!(admission.runtime_budget, Some(400));
+        access.charge(&premium, &cost, now);
+
+        // spent.  Retry at the start of the next day
+        assert_eq!(
+            access.admit(premium.clone(), true, now).unwrap_err(),
+            RPCAccessError::BudgetExhausted(86_399)
+        );
+
+        // unmetered requests still work
+        access.admit(premium.clone(), false, now).unwrap();
+
+        // budget resets the next day
+        let tomorrow = (day + 1) * MS_PER_DAY;
+        assert_eq!(access.get_runtime_spent(&premium, tomorrow), 0);
+        let admission = access.admit(premium.clone(), true, tomorrow).unwrap();
+        assert_eq!(admission.runtime_budget, Some(1000));
+
+        // no budget means no metering
+        let admission = access.admit(basic.clone(), true, now).unwrap();
+        assert_eq!(admission.runtime_budget, None);
+        access.charge(&basic, &cost, now);
+        assert_eq!(access.get_runtime_spent(&basic, now), 0);
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut access = make_access_control();
+        let now = 1_000_000_000;
+        for i in 0..10u8 {
+            let ip = RPCClientId::Ip(IpAddr::from([10, 0, 0, i]));
+            access.admit(ip, false, now).unwrap();
+        }
+        assert_eq!(access.num_tracked_clients(), 10);
+
+        // all buckets have refilled a minute later
+        access
+            .admit(
+                RPCClientId::Ip("1.2.3.4".parse().unwrap()),
+                false,
+                now + 60_000,
+            )
+            .unwrap();
+        assert_eq!(access.num_tracked_clients(), 1);
+    }
+
+    #[test]
+    fn test_error_response() {
+        let preamble = make_preamble(None);
+        let (response_preamble, _) = RPCAccessError::RateLimited(12)
+            .try_into_contents(&preamble)
+            .unwrap();
+        assert_eq!(response_preamble.status_code, 429);
+        assert_eq!(
+            response_preamble.headers.get("retry-after"),
+            Some(&"12".to_string())
+        );
+
+        let (response_preamble, _) = RPCAccessError::UnknownApiKey
+            .try_into_contents(&preamble)
+            .unwrap();
+        assert_eq!(response_preamble.status_code, 401);
+        assert!(response_preamble.headers.get("retry-after").is_none());
+    }
+}
@@ -17,6 +17,7 @@
 use std::collections::{HashMap, VecDeque};
 use std::io::{Error as io_error, ErrorKind, Read, Write};
 use std::sync::mpsc::{sync_channel, Receiver, RecvError, SendError, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
 
 use mio::net as mio_net;
 use stacks_common::types::net::{PeerAddress, PeerHost};
@@ -35,6 +36,7 @@ use crate::net::httpcore::*;
 use crate::net::p2p::{PeerMap, PeerNetwork};
 use crate::net::poll::*;
 use crate::net::rpc::*;
+use crate::net::rpc_access::RPCAccessControl;
 use crate::net::{Error as net_error, *};
 
 #[derive(Debug)]
@@ -62,6 +64,9 @@ pub struct HttpPeer {
 
     /// connection options
     pub connection_opts: ConnectionOptions,
+
+    /// access control for inbound requests, if configured
+    pub rpc_access: Option<Arc<Mutex<RPCAccessControl>>>,
 }
 
 impl HttpPeer {
@@ -78,6 +83,8 @@ impl HttpPeer {
             http_server_handle: server_handle,
             http_server_addr: server_addr,
 
+            rpc_access: RPCAccessControl::from_connection_options(&conn_opts)
+                .map(|rpc_access| Arc::new(Mutex::new(rpc_access))),
             connection_opts: conn_opts,
         }
     }
@@ -258,6 +265,12 @@ impl HttpPeer {
             send_buffer_size,
         );
 
+        if outbound_url.is_none() {
+            if let Some(rpc_access) = self.rpc_access.as_ref() {
+                new_convo.set_rpc_access(rpc_access.clone());
+            }
+        }
+
         debug!(
             "Registered HTTP {:?} as event {} (outbound={:?})",
             &socket, event_id, &outbound_url
@@ -316,6 +329,10 @@ impl HttpPeer {
         }
 
         for (event_id, convo) in self.peers.iter() {
+            if convo.has_deferred_request() {
+                // waiting on us, not on the peer
+                continue;
+            }
             let mut last_request_time = convo.get_last_request_time();
             if last_request_time == 0 {
                 // never got a request
@@ -605,6 +622,41 @@ impl HttpPeer {
         (msgs, to_remove)
     }
 
+    /// Run the handlers of deferred requests again on conversations whose sockets were not ready
+    /// (ready ones were already handled by `process_ready_sockets()`).
+    /// Return the list of events that correspond to failed conversations, as well as the list of
+    /// peer network messages we'll need to forward
+    #[cfg_attr(test, mutants::skip)]
+    fn process_deferred_requests(
+        &mut self,
+        poll_state: &NetworkPollState,
+        node_state: &mut StacksNodeState,
+    ) -> (Vec<StacksMessageType>, Vec<usize>) {
+        let mut to_remove = vec![];
+        let mut msgs = vec![];
+        for (event_id, convo) in self.peers.iter_mut() {
+            if !convo.has_deferred_request() || poll_state.ready.contains(event_id) {
+                continue;
+            }
+            let Some(client_sock) = self.sockets.get_mut(event_id) else {
+                to_remove.push(*event_id);
+                continue;
+            };
+            match HttpPeer::process_http_conversation(node_state, *event_id, client_sock, convo) {
+                Ok((alive, mut new_msgs)) => {
+                    if !alive {
+                        to_remove.push(*event_id);
+                    }
+                    msgs.append(&mut new_msgs);
+                }
+                Err(_e) => {
+                    to_remove.push(*event_id);
+                }
+            }
+        }
+        (msgs, to_remove)
+    }
+
     /// Flush outgoing replies, but don't block.
     /// Drop broken handles.
     /// Return the list of conversation event IDs to close (i.e. they're broken, or the request is done)
@@ -648,7 +700,14 @@ impl HttpPeer {
         self.process_connecting_sockets(network_state, node_state, &mut poll_state);
 
         // run existing conversations, clear out broken ones, and get back messages forwarded to us
-        let (stacks_msgs, error_events) = self.process_ready_sockets(&mut poll_state, node_state);
+        let (mut stacks_msgs, mut error_events) =
+            self.process_ready_sockets(&mut poll_state, node_state);
+
+        // answer deferred requests that can now be answered
+        let (mut deferred_msgs, mut deferred_error_events) =
+            self.process_deferred_requests(&poll_state, node_state);
+        stacks_msgs.append(&mut deferred_msgs);
+        error_events.append(&mut deferred_error_events);
         for error_event in error_events {
             debug!("Failed HTTP connection on event {}", error_event);
             self.deregister_http(network_state, error_event);
@@ -694,7 +753,7 @@ mod test {
     use crate::chainstate::stacks::db::blocks::test::*;
     use crate::chainstate::stacks::db::StacksChainState;
     use crate::chainstate::stacks::test::*;
-    use crate::chainstate::stacks::{Error as chain_error, StacksBlockHeader, *, *};
+    use crate::chainstate::stacks::{Error as chain_error, StacksBlockHeader, *};
     use crate::net::codec::*;
     use crate::net::http::*;
     use crate::net::httpcore::*;
@@ -118,7 +118,7 @@ pub mod config;
 pub mod db;
 pub mod sync;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use clarity::vm::types::QualifiedContractIdentifier;
 use libstackerdb::{SlotMetadata, STACKERDB_MAX_CHUNK_SIZE};
@@ -169,6 +169,49 @@ pub struct StackerDBSyncResult {
     pub num_attempted_connections: u64,
 }
 
+/// Why a chunk received from a remote peer was not stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackerDBChunkRejection {
+    /// The slot does not exist, or has no signer
+    NoSuchSlot,
+    /// The chunk is not signed by the slot's signer
+    BadSignature,
+    /// The chunk is older than the version this replica expects
+    StaleVersion,
+    /// The chunk's version exceeds the DB's maximum number of writes
+    TooManyWrites,
+}
+
+impl StackerDBChunkRejection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoSuchSlot => "no_such_slot",
+            Self::BadSignature => "bad_signature",
+            Self::StaleVersion => "stale_version",
+            Self::TooManyWrites => "too_many_writes",
+        }
+    }
+}
+
+/// Replication health of this node's replica of a StackerDB
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StackerDBReplicationStats {
+    /// Number of replicas whose chunk inventories were obtained in the last sync round
+    pub connected_replicas: u64,
+    /// Number of slots for which some replica had a newer version than this node, as of the
+    /// last chunk inventory round
+    pub slots_behind: u64,
+    /// When the last sync round that reached at least one replica finished (0 if never)
+    pub last_sync_ts: u64,
+    /// Total bytes of chunk data downloaded from replicas
+    pub bytes_pulled: u64,
+    /// Total bytes of chunk data pushed to replicas
+    pub bytes_pushed: u64,
+    /// Total number of chunks from remote peers that were not stored, by reason
+    pub rejected_chunks: BTreeMap<StackerDBChunkRejection, u64>,
+}
+
 /// Settings for the Stacker DB
 #[derive(Clone, Debug, PartialEq)]
 pub struct StackerDBConfig {
@@ -396,6 +439,8 @@ pub struct StackerDBSync<NC: NeighborComms> {
     num_attempted_connections: u64,
     /// How many connections have been made in the last pass (gets reset)
     num_connections: u64,
+    /// Replication health, reported via RPC and metrics (never reset)
+    stats: StackerDBReplicationStats,
 }
 
 impl StackerDBSyncResult {
@@ -505,6 +550,23 @@ impl PeerNetwork {
         data: &StackerDBChunkData,
         expected_versions: &[u32],
     ) -> Result<bool, net_error> {
+        Ok(self
+            .check_received_chunk(smart_contract_id, config, data, expected_versions)?
+            .is_none())
+    }
+
+    /// Validate chunk data -- either pushed to us, or downloaded -- and say why it is invalid.
+    /// NOTE: does not check write frequency, since the caller has different ways of doing this.
+    /// Returns Ok(None) if the chunk is valid
+    /// Returns Ok(Some(..)) with the reason if the chunk is invalid
+    /// Returns Err(..) on DB error
+    pub fn check_received_chunk(
+        &self,
+        smart_contract_id: &QualifiedContractIdentifier,
+        config: &StackerDBConfig,
+        data: &StackerDBChunkData,
+        expected_versions: &[u32],
+    ) -> Result<Option<StackerDBChunkRejection>, net_error> {
         // validate -- must be a valid chunk
         if data.slot_id >= (expected_versions.len() as u32) {
             info!(
@@ -513,7 +575,7 @@ impl PeerNetwork {
                 data.slot_id,
                 expected_versions.len()
             );
-            return Ok(false);
+            return Ok(Some(StackerDBChunkRejection::NoSuchSlot));
         }
 
         // validate -- must be signed by the expected author
@@ -523,7 +585,7 @@ impl PeerNetwork {
         {
             Some(addr) => addr,
             None => {
-                return Ok(false);
+                return Ok(Some(StackerDBChunkRejection::NoSuchSlot));
             }
         };
 
@@ -533,7 +595,7 @@ impl PeerNetwork {
                 "StackerDBChunk for {} ID {} is not signed by {}",
                 smart_contract_id, data.slot_id, &addr
             );
-            return Ok(false);
+            return Ok(Some(StackerDBChunkRejection::BadSignature));
         }
 
         // validate -- must be the current or newer version
@@ -543,7 +605,7 @@ impl PeerNetwork {
                 "Received StackerDBChunk for {} ID {} version {}, which is stale (expected {})",
                 smart_contract_id, data.slot_id, data.slot_version, expected_versions[slot_idx]
             );
-            return Ok(false);
+            return Ok(Some(StackerDBChunkRejection::StaleVersion));
         }
 
         // validate -- must not exceed max writes
@@ -552,10 +614,22 @@ impl PeerNetwork {
                 "Write count exceeded for StackerDBChunk for {} ID {} version {} (max is {})",
                 smart_contract_id, data.slot_id, data.slot_version, config.max_writes
             );
-            return Ok(false);
+            return Ok(Some(StackerDBChunkRejection::TooManyWrites));
         }
 
-        Ok(true)
+        Ok(None)
+    }
+
+    /// Get the replication health of this node's replica of a StackerDB.
+    /// Returns None if this node does not replicate it.
+    pub fn get_stackerdb_replication_stats(
+        &self,
+        contract_id: &QualifiedContractIdentifier,
+    ) -> Option<StackerDBReplicationStats> {
+        self.stacker_db_syncs
+            .as_ref()?
+            .get(contract_id)
+            .map(|stackerdb_sync| stackerdb_sync.get_stats().clone())
     }
 
     /// Handle unsolicited StackerDBPushChunk messages.
@@ -595,12 +669,19 @@ impl PeerNetwork {
                 };
 
                 // sanity check
-                if !self.validate_received_chunk(
+                if let Some(reason) = self.check_received_chunk(
                     &chunk_data.contract_id,
                     stackerdb_config,
                     &chunk_data.chunk_data,
                     &data.slot_versions,
                 )? {
+                    if let Some(stackerdb_syncs) = self.stacker_db_syncs.as_mut() {
+                        if let Some(stackerdb_sync) =
+                            stackerdb_syncs.get_mut(&chunk_data.contract_id)
+                        {
+                            stackerdb_sync.record_rejected_chunk(reason);
+                        }
+                    }
                     return Ok(false);
                 }
 
@@ -24,13 +24,15 @@ use stacks_common::types::chainstate::{ConsensusHash, StacksAddress};
 use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::hash::Hash160;
 
+use crate::monitoring;
 use crate::net::chat::ConversationP2P;
 use crate::net::connection::ReplyHandleP2P;
 use crate::net::db::PeerDB;
 use crate::net::neighbors::NeighborComms;
 use crate::net::p2p::PeerNetwork;
 use crate::net::stackerdb::{
-    StackerDBConfig, StackerDBSync, StackerDBSyncResult, StackerDBSyncState, StackerDBs,
+    StackerDBChunkRejection, StackerDBConfig, StackerDBReplicationStats, StackerDBSync,
+    StackerDBSyncResult, StackerDBSyncState, StackerDBs,
 };
 use crate::net::{
     Error as net_error, NackData, NackErrorCodes, Neighbor, NeighborAddress, NeighborKey,
@@ -75,6 +77,7 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
             stale_neighbors: HashSet::new(),
             num_connections: 0,
             num_attempted_connections: 0,
+            stats: StackerDBReplicationStats::default(),
         };
         dbsync.reset(None, config);
         dbsync
@@ -168,6 +171,7 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
         config: &StackerDBConfig,
     ) -> StackerDBSyncResult {
         debug!("Reset {} with config {:?}", &self.smart_contract_id, config);
+        self.update_round_stats();
         let mut chunks = vec![];
         let downloaded_chunks = mem::replace(&mut self.downloaded_chunks, HashMap::new());
         for (_, mut data) in downloaded_chunks.into_iter() {
@@ -220,6 +224,40 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
         result
     }
 
+    /// Record the outcome of the sync round that is ending.
+    /// Only a round that ran to completion counts as a successful sync.
+    fn update_round_stats(&mut self) {
+        let finished = matches!(self.state, StackerDBSyncState::Finished);
+        self.stats.connected_replicas = if finished {
+            u64::try_from(self.chunk_invs.len()).unwrap_or(u64::MAX)
+        } else {
+            0
+        };
+        if finished && !self.chunk_invs.is_empty() {
+            self.stats.last_sync_ts = get_epoch_time_secs();
+        }
+        monitoring::update_stackerdb_sync_stats(
+            &self.smart_contract_id.to_string(),
+            self.stats.connected_replicas,
+            self.stats.slots_behind,
+            self.stats.last_sync_ts,
+        );
+    }
+
+    /// Record a chunk from a remote peer that was not stored
+    pub fn record_rejected_chunk(&mut self, reason: StackerDBChunkRejection) {
+        *self.stats.rejected_chunks.entry(reason).or_insert(0) += 1;
+        monitoring::increment_stackerdb_rejected_chunks_counter(
+            &self.smart_contract_id.to_string(),
+            reason.as_str(),
+        );
+    }
+
+    /// Get the replication health of this replica
+    pub fn get_stats(&self) -> &StackerDBReplicationStats {
+        &self.stats
+    }
+
     /// Get the set of connection IDs in use
     pub fn get_pinned_connections(&self) -> &HashSet<usize> {
         self.comms.get_pinned_connections()
@@ -443,33 +481,30 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
         Ok(schedule)
     }
 
-    /// Validate a downloaded chunk
+    /// Validate a downloaded chunk.
+    /// Returns Ok(None) if the chunk is valid, or Ok(Some(..)) with the reason it is not
     pub fn validate_downloaded_chunk(
         &self,
         network: &PeerNetwork,
         config: &StackerDBConfig,
         data: &StackerDBChunkData,
-    ) -> Result<bool, net_error> {
+    ) -> Result<Option<StackerDBChunkRejection>, net_error> {
         // validate -- must be a valid chunk
-        if !network.validate_received_chunk(
+        // no need to validate the timestamp, because we already skipped requesting it if it was
+        // written too recently.
+        network.check_received_chunk(
             &self.smart_contract_id,
             &config,
             data,
             &self.expected_versions,
-        )? {
-            return Ok(false);
-        }
-
-        // no need to validate the timestamp, because we already skipped requesting it if it was
-        // written too recently.
-
-        Ok(true)
+        )
     }
 
     /// Store a downloaded chunk to RAM, and update bookkeeping
     pub fn add_downloaded_chunk(&mut self, naddr: NeighborAddress, data: StackerDBChunkData) {
         let slot_id = data.slot_id;
         let _slot_version = data.slot_version;
+        let num_bytes = u64::try_from(data.data.len()).unwrap_or(u64::MAX);
 
         if let Some(data_list) = self.downloaded_chunks.get_mut(&naddr) {
             data_list.push(data);
@@ -487,6 +522,12 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
         }
 
         self.total_stored += 1;
+        self.stats.bytes_pulled = self.stats.bytes_pulled.saturating_add(num_bytes);
+        monitoring::increment_stackerdb_bytes_counter(
+            &self.smart_contract_id.to_string(),
+            "pulled",
+            num_bytes,
+        );
     }
 
     /// Update bookkeeping about which chunks we have pushed.
@@ -865,6 +906,22 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
         let priorities = self.make_chunk_request_schedule(&network, None)?;
         let expected_versions = self.stackerdbs.get_slot_versions(&self.smart_contract_id)?;
 
+        // how far behind the newest versions our replicas have are we?
+        let slots_behind = expected_versions
+            .iter()
+            .enumerate()
+            .filter(|(slot_id, local_version)| {
+                self.chunk_invs.values().any(|chunk_inv| {
+                    chunk_inv
+                        .slot_versions
+                        .get(*slot_id)
+                        .map(|version| version > local_version)
+                        .unwrap_or(false)
+                })
+            })
+            .count();
+        self.stats.slots_behind = u64::try_from(slots_behind).unwrap_or(u64::MAX);
+
         self.chunk_fetch_priorities = priorities;
         self.expected_versions = expected_versions;
         Ok(true)
@@ -986,11 +1043,12 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
             };
 
             // validate
-            if !self.validate_downloaded_chunk(network, config, &data)? {
+            if let Some(reason) = self.validate_downloaded_chunk(network, config, &data)? {
                 info!(
                     "Remote neighbor {:?} served an invalid chunk for ID {}",
                     &naddr, data.slot_id
                 );
+                self.record_rejected_chunk(reason);
                 self.connected_replicas.remove(&naddr);
                 continue;
             }
@@ -1066,6 +1124,7 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
 
             let slot_id = chunk_push.chunk_data.slot_id;
             let slot_version = chunk_push.chunk_data.slot_version;
+            let num_bytes = u64::try_from(chunk_push.chunk_data.data.len()).unwrap_or(u64::MAX);
             if let Err(e) = self.comms.neighbor_send(
                 network,
                 &selected_neighbor,
@@ -1083,6 +1142,12 @@ impl<NC: NeighborComms> StackerDBSync<NC> {
             }
 
             pushed += 1;
+            self.stats.bytes_pushed = self.stats.bytes_pushed.saturating_add(num_bytes);
+            monitoring::increment_stackerdb_bytes_counter(
+                &self.smart_contract_id.to_string(),
+                "pushed",
+                num_bytes,
+            );
 
             // record what we just sent
             self.chunk_push_receipts
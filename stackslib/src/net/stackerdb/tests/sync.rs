@@ -304,6 +304,23 @@ fn test_stackerdb_replica_2_neighbors_1_chunk() {
         }
 
         debug!("Completed stacker DB sync in {} step(s)", i);
+
+        // the chunk was either pulled by peer 2 or pushed by peer 1
+        let stats_1 = peer_1
+            .network
+            .get_stackerdb_replication_stats(&peer_1.config.stacker_dbs[idx_1])
+            .unwrap();
+        let stats_2 = peer_2
+            .network
+            .get_stackerdb_replication_stats(&peer_2.config.stacker_dbs[idx_2])
+            .unwrap();
+        assert!(stats_1.bytes_pushed + stats_2.bytes_pulled >= peer_1_db_chunks[0].1.len() as u64);
+        assert!(stats_1.rejected_chunks.is_empty());
+        assert!(stats_2.rejected_chunks.is_empty());
+        assert!(peer_1
+            .network
+            .get_stackerdb_replication_stats(&QualifiedContractIdentifier::transient())
+            .is_none());
     })
 }
 
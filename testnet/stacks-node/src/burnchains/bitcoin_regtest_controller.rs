@@ -15,10 +15,15 @@ use serde_json::value::RawValue;
 use stacks::burnchains::bitcoin::address::{
     BitcoinAddress, LegacyBitcoinAddress, LegacyBitcoinAddressType, SegwitBitcoinAddress,
 };
+use stacks::burnchains::bitcoin::esplora::{EsploraIndexer, EsploraIndexerConfig, EsploraUtxo};
 use stacks::burnchains::bitcoin::indexer::{
-    BitcoinIndexer, BitcoinIndexerConfig, BitcoinIndexerRuntime,
+    BitcoinIndexer, BitcoinIndexerConfig, BitcoinIndexerRuntime, BitcoinPeerTipAlert,
 };
+use stacks::burnchains::bitcoin::simulator::SimulatedIndexer;
+#[cfg(test)]
+use stacks::burnchains::bitcoin::simulator::SimulatedIndexerConfig;
 use stacks::burnchains::bitcoin::spv::SpvClient;
+use stacks::burnchains::bitcoin::wallet::{wallet_pubkey_hashes, WALLET_PENDING_TX_LIFETIME};
 use stacks::burnchains::bitcoin::BitcoinNetworkType;
 use stacks::burnchains::db::BurnchainDB;
 use stacks::burnchains::indexer::BurnchainIndexer;
@@ -45,9 +50,9 @@ use stacks_common::deps_common::bitcoin::blockdata::transaction::{
     OutPoint, Transaction, TxIn, TxOut,
 };
 use stacks_common::deps_common::bitcoin::network::encodable::ConsensusEncodable;
-#[cfg(test)]
-use stacks_common::deps_common::bitcoin::network::serialize::deserialize as btc_deserialize;
-use stacks_common::deps_common::bitcoin::network::serialize::RawEncoder;
+use stacks_common::deps_common::bitcoin::network::serialize::{
+    deserialize as btc_deserialize, RawEncoder,
+};
 use stacks_common::deps_common::bitcoin::util::hash::Sha256dHash;
 use stacks_common::types::chainstate::BurnchainHeaderHash;
 use stacks_common::util::hash::{hex_bytes, Hash160};
@@ -56,8 +61,10 @@ use stacks_common::util::sleep_ms;
 
 use super::super::operations::BurnchainOpSigner;
 use super::super::Config;
+use super::fee_policy::{can_rbf, get_fee_rate, get_rbf_fee_rate, BurnchainOpKind};
 use super::{BurnchainController, BurnchainTip, Error as BurnchainControllerError};
 use crate::config::BurnchainConfig;
+use crate::Keychain;
 
 /// The number of bitcoin blocks that can have
 ///  passed since the UTXO cache was last refreshed before
@@ -72,6 +79,11 @@ pub static TEST_MAGIC_BYTES: std::sync::Mutex<Option<[u8; 2]>> = std::sync::Mute
 pub struct BitcoinRegtestController {
     config: Config,
     indexer: BitcoinIndexer,
+    /// Set if `burnchain.esplora_url` is given.  Headers and blocks are then synced through it
+    /// (into the same headers DB `indexer` reads), and UTXOs and broadcasts go through it too.
+    esplora_indexer: Option<EsploraIndexer>,
+    /// Set if `burnchain.simulated_burnchain` is given.  Used like `esplora_indexer`.
+    simulated_indexer: Option<SimulatedIndexer>,
     db: Option<SortitionDB>,
     burnchain_db: Option<BurnchainDB>,
     chain_tip: Option<BurnchainTip>,
@@ -135,6 +147,56 @@ pub fn burnchain_params_from_config(config: &BurnchainConfig) -> BurnchainParame
     params
 }
 
+/// Estimated virtual size of a block-commit that spends a single Taproot key-path input, and has
+/// `num_outputs` outputs besides its OP_RETURN.  Outputs are assumed to be at most as large as a
+/// p2tr or p2wsh output.
+fn estimated_taproot_block_commit_vsize(num_outputs: u64) -> u64 {
+    // version, input and output counts, locktime, and the segwit marker and flag (rounded up)
+    let overhead_vsize = 4 + 1 + 1 + 4 + 1;
+    // outpoint, empty scriptSig, sequence, and a witness with a single 64-byte signature
+    // (1 + 1 + 64 weight units, rounded up)
+    let input_vsize = 36 + 1 + 4 + 17;
+    // value, script length, and an OP_RETURN pushing 80 bytes of op data
+    let op_return_vsize = 8 + 1 + 83;
+    // value, script length, and a 34-byte scriptPubKey
+    let output_vsize = 8 + 1 + 34;
+    overhead_vsize + input_vsize + op_return_vsize + num_outputs * output_vsize
+}
+
+/// Get the public key hashes whose UTXOs the burnchain indexer tracks.  This is the miner's key
+/// if it manages its own UTXOs, and nothing otherwise.
+fn get_watched_pubkey_hashes(config: &Config) -> Vec<Hash160> {
+    if !config.burnchain.self_managed_utxos || !config.node.miner {
+        return vec![];
+    }
+    let keychain = Keychain::default(config.node.seed.clone());
+    wallet_pubkey_hashes(&keychain.get_pub_key())
+}
+
+/// Get the scriptPubKey that pays to `address`
+fn address_script_pubkey(address: &BitcoinAddress) -> Script {
+    let tx_out = match address {
+        BitcoinAddress::Legacy(addr) => match addr.addrtype {
+            LegacyBitcoinAddressType::PublicKeyHash => {
+                LegacyBitcoinAddress::to_p2pkh_tx_out(&addr.bytes, 0)
+            }
+            LegacyBitcoinAddressType::ScriptHash => {
+                LegacyBitcoinAddress::to_p2sh_tx_out(&addr.bytes, 0)
+            }
+        },
+        BitcoinAddress::Segwit(SegwitBitcoinAddress::P2WPKH(_, bytes)) => {
+            SegwitBitcoinAddress::to_p2wpkh_tx_out(bytes, 0)
+        }
+        BitcoinAddress::Segwit(SegwitBitcoinAddress::P2WSH(_, bytes)) => {
+            SegwitBitcoinAddress::to_p2wsh_tx_out(bytes, 0)
+        }
+        BitcoinAddress::Segwit(SegwitBitcoinAddress::P2TR(_, bytes)) => {
+            SegwitBitcoinAddress::to_p2tr_tx_out(bytes, 0)
+        }
+    };
+    tx_out.script_pubkey
+}
+
 /// Helper method to create a BitcoinIndexer
 pub fn make_bitcoin_indexer(
     config: &Config,
@@ -155,6 +217,9 @@ pub fn make_bitcoin_indexer(
             first_block: burnchain_params.first_block_height,
             magic_bytes: burnchain_config.magic_bytes,
             epochs: burnchain_config.epochs,
+            watched_pubkey_hashes: get_watched_pubkey_hashes(config),
+            header_checkpoints: burnchain_config.header_checkpoints,
+            extra_peers: burnchain_config.extra_peers,
         }
     };
 
@@ -168,16 +233,64 @@ pub fn make_bitcoin_indexer(
     burnchain_indexer
 }
 
-pub fn get_satoshis_per_byte(config: &Config) -> u64 {
-    config.get_burnchain_config().satoshis_per_byte
+/// Helper method to create an EsploraIndexer, if `burnchain.esplora_url` is set
+fn make_esplora_indexer(
+    config: &Config,
+    should_keep_running: Option<Arc<AtomicBool>>,
+) -> Option<EsploraIndexer> {
+    let esplora_url = config.burnchain.esplora_url.clone()?;
+    let burnchain_params = burnchain_params_from_config(&config.burnchain);
+    let indexer_config = EsploraIndexerConfig {
+        url: esplora_url,
+        timeout: config.burnchain.timeout.into(),
+        spv_headers_path: config.get_spv_headers_file_path(),
+        first_block: burnchain_params.first_block_height,
+        magic_bytes: config.burnchain.magic_bytes,
+        epochs: config.burnchain.epochs.clone(),
+        watched_pubkey_hashes: get_watched_pubkey_hashes(config),
+        header_checkpoints: config.burnchain.header_checkpoints.clone(),
+    };
+    let (_, network_type) = config.burnchain.get_bitcoin_network();
+    let esplora_indexer = EsploraIndexer::new(indexer_config, network_type, should_keep_running)
+        .expect("FATAL: invalid burnchain.esplora_url");
+    Some(esplora_indexer)
 }
 
-pub fn get_rbf_fee_increment(config: &Config) -> u64 {
-    config.get_burnchain_config().rbf_fee_increment
+/// Helper method to create a SimulatedIndexer, if `burnchain.simulated_burnchain` is set
+#[cfg(test)]
+fn make_simulated_indexer(
+    config: &Config,
+    should_keep_running: Option<Arc<AtomicBool>>,
+) -> Option<SimulatedIndexer> {
+    let chain = config.burnchain.simulated_burnchain.clone()?;
+    let (_, network_type) = config.burnchain.get_bitcoin_network();
+    assert_eq!(
+        network_type,
+        BitcoinNetworkType::Regtest,
+        "FATAL: a simulated burnchain can only stand in for regtest"
+    );
+    let burnchain_params = burnchain_params_from_config(&config.burnchain);
+    let indexer_config = SimulatedIndexerConfig {
+        spv_headers_path: config.get_spv_headers_file_path(),
+        first_block: burnchain_params.first_block_height,
+        magic_bytes: config.burnchain.magic_bytes,
+        epochs: config.burnchain.epochs.clone(),
+        watched_pubkey_hashes: get_watched_pubkey_hashes(config),
+    };
+    Some(SimulatedIndexer::new(
+        indexer_config,
+        chain,
+        should_keep_running,
+    ))
 }
 
-pub fn get_max_rbf(config: &Config) -> u64 {
-    config.get_burnchain_config().max_rbf
+/// `burnchain.simulated_burnchain` only exists in test builds
+#[cfg(not(test))]
+fn make_simulated_indexer(
+    _config: &Config,
+    _should_keep_running: Option<Arc<AtomicBool>>,
+) -> Option<SimulatedIndexer> {
+    None
 }
 
 impl LeaderBlockCommitFees {
@@ -185,11 +298,12 @@ impl LeaderBlockCommitFees {
         &self,
         payload: &LeaderBlockCommitOp,
         config: &Config,
+        fee_rate: u64,
     ) -> LeaderBlockCommitFees {
-        let mut fees = LeaderBlockCommitFees::estimated_fees_from_payload(payload, config);
+        let mut fees =
+            LeaderBlockCommitFees::estimated_fees_from_payload(payload, config, fee_rate);
         fees.spent_in_attempts = cmp::max(1, self.spent_in_attempts);
         fees.final_size = self.final_size;
-        fees.fee_rate = self.fee_rate + get_rbf_fee_increment(&config);
         fees.is_rbf_enabled = true;
         fees
     }
@@ -197,6 +311,7 @@ impl LeaderBlockCommitFees {
     pub fn estimated_fees_from_payload(
         payload: &LeaderBlockCommitOp,
         config: &Config,
+        fee_rate: u64,
     ) -> LeaderBlockCommitFees {
         let sunset_fee = if payload.sunset_burn > 0 {
             cmp::max(payload.sunset_burn, DUST_UTXO_LIMIT)
@@ -208,8 +323,17 @@ impl LeaderBlockCommitFees {
         let value_per_transfer = payload.burn_fee / number_of_transfers;
         let sortition_fee = value_per_transfer * number_of_transfers;
         let spent_in_attempts = 0;
-        let fee_rate = get_satoshis_per_byte(config);
-        let default_tx_size = config.burnchain.block_commit_tx_estimated_size;
+        let default_tx_size = if config.miner.taproot {
+            // the configured estimate is for legacy inputs; a Taproot key-path input's
+            // signature is witness data, and is discounted accordingly
+            let num_outputs = number_of_transfers + if sunset_fee > 0 { 2 } else { 1 };
+            cmp::min(
+                config.burnchain.block_commit_tx_estimated_size,
+                estimated_taproot_block_commit_vsize(num_outputs),
+            )
+        } else {
+            config.burnchain.block_commit_tx_estimated_size
+        };
 
         LeaderBlockCommitFees {
             sunset_fee,
@@ -316,6 +440,9 @@ impl BitcoinRegtestController {
                 first_block: burnchain_params.first_block_height,
                 magic_bytes: burnchain_config.magic_bytes,
                 epochs: burnchain_config.epochs,
+                watched_pubkey_hashes: get_watched_pubkey_hashes(&config),
+                header_checkpoints: burnchain_config.header_checkpoints,
+                extra_peers: burnchain_config.extra_peers,
             }
         };
 
@@ -327,10 +454,15 @@ impl BitcoinRegtestController {
             should_keep_running: should_keep_running.clone(),
         };
 
+        let esplora_indexer = make_esplora_indexer(&config, should_keep_running.clone());
+        let simulated_indexer = make_simulated_indexer(&config, should_keep_running.clone());
+
         Self {
             use_coordinator: coordinator_channel,
             config,
             indexer: burnchain_indexer,
+            esplora_indexer,
+            simulated_indexer,
             db: None,
             burnchain_db: None,
             chain_tip: None,
@@ -360,6 +492,9 @@ impl BitcoinRegtestController {
                 first_block: burnchain_params.first_block_height,
                 magic_bytes: burnchain_config.magic_bytes,
                 epochs: burnchain_config.epochs,
+                watched_pubkey_hashes: get_watched_pubkey_hashes(&config),
+                header_checkpoints: burnchain_config.header_checkpoints,
+                extra_peers: burnchain_config.extra_peers,
             }
         };
 
@@ -371,10 +506,15 @@ impl BitcoinRegtestController {
             should_keep_running: None,
         };
 
+        let esplora_indexer = make_esplora_indexer(&config, None);
+        let simulated_indexer = make_simulated_indexer(&config, None);
+
         Self {
             use_coordinator: None,
             config,
             indexer: burnchain_indexer,
+            esplora_indexer,
+            simulated_indexer,
             db: None,
             burnchain_db: None,
             chain_tip: None,
@@ -505,13 +645,33 @@ impl BitcoinRegtestController {
                 return Err(BurnchainControllerError::CoordinatorClosed);
             }
 
-            match burnchain.sync_with_indexer(
-                &mut self.indexer,
-                coordinator_comms.clone(),
-                target_block_height_opt,
-                Some(burnchain.pox_constants.reward_cycle_length as u64),
-                self.should_keep_running.clone(),
-            ) {
+            let max_blocks_opt = Some(burnchain.pox_constants.reward_cycle_length as u64);
+            let sync_result = if let Some(esplora_indexer) = self.esplora_indexer.as_mut() {
+                burnchain.sync_with_indexer(
+                    esplora_indexer,
+                    coordinator_comms.clone(),
+                    target_block_height_opt,
+                    max_blocks_opt,
+                    self.should_keep_running.clone(),
+                )
+            } else if let Some(simulated_indexer) = self.simulated_indexer.as_mut() {
+                burnchain.sync_with_indexer(
+                    simulated_indexer,
+                    coordinator_comms.clone(),
+                    target_block_height_opt,
+                    max_blocks_opt,
+                    self.should_keep_running.clone(),
+                )
+            } else {
+                burnchain.sync_with_indexer(
+                    &mut self.indexer,
+                    coordinator_comms.clone(),
+                    target_block_height_opt,
+                    max_blocks_opt,
+                    self.should_keep_running.clone(),
+                )
+            };
+            match sync_result {
                 Ok(x) => {
                     increment_btc_blocks_received_counter();
 
@@ -594,9 +754,28 @@ impl BitcoinRegtestController {
     pub fn get_all_utxos(&self, public_key: &Secp256k1PublicKey) -> Vec<UTXO> {
         // Configure UTXO filter, disregard what epoch we're in
         let address = self.get_miner_address(StacksEpochId::Epoch21, public_key);
+        if let Some(simulated_indexer) = self.simulated_indexer.as_ref() {
+            let chain = simulated_indexer.chain();
+            let (_, tip_height) = chain.visible_tip();
+            let script_pub_key = address_script_pubkey(&address);
+            return chain
+                .get_utxos(&script_pub_key)
+                .into_iter()
+                .map(|utxo| UTXO {
+                    txid: utxo.txid,
+                    vout: utxo.vout,
+                    script_pub_key: script_pub_key.clone(),
+                    amount: utxo.value,
+                    confirmations: utxo
+                        .block_height
+                        .map(|height| (tip_height.saturating_sub(height) + 1) as u32)
+                        .unwrap_or(0),
+                })
+                .collect();
+        }
         let filter_addresses = vec![addr2str(&address)];
 
-        let pubk = if self.config.miner.segwit {
+        let pubk = if self.config.miner.segwit || self.config.miner.taproot {
             let mut p = public_key.clone();
             p.set_compressed(true);
             p
@@ -687,6 +866,12 @@ impl BitcoinRegtestController {
     /// Checks if the config-supplied wallet exists.
     /// If it does not exist, this function creates it.
     pub fn create_wallet_if_dne(&self) -> RPCResult<()> {
+        if self.config.burnchain.self_managed_utxos
+            || self.esplora_indexer.is_some()
+            || self.simulated_indexer.is_some()
+        {
+            return Ok(());
+        }
         let wallets = BitcoinRPCRequest::list_wallets(&self.config)?;
 
         if !wallets.contains(&self.config.burnchain.wallet_name) {
@@ -695,6 +880,12 @@ impl BitcoinRegtestController {
         Ok(())
     }
 
+    /// Take the alerts raised since the last call about bitcoin peers (`burnchain.extra_peers`)
+    /// whose header chains disagreed with ours.
+    pub fn take_peer_tip_alerts(&mut self) -> Vec<BitcoinPeerTipAlert> {
+        self.indexer.take_peer_tip_alerts()
+    }
+
     pub fn get_utxos(
         &self,
         epoch_id: StacksEpochId,
@@ -708,7 +899,9 @@ impl BitcoinRegtestController {
             return None;
         }
 
-        let pubk = if self.config.miner.segwit && epoch_id >= StacksEpochId::Epoch21 {
+        let pubk = if (self.config.miner.segwit || self.config.miner.taproot)
+            && epoch_id >= StacksEpochId::Epoch21
+        {
             let mut p = public_key.clone();
             p.set_compressed(true);
             p
@@ -726,6 +919,30 @@ impl BitcoinRegtestController {
         );
         let filter_addresses = vec![addr2str(&address)];
 
+        if self.config.burnchain.self_managed_utxos {
+            return self.get_self_managed_utxos(epoch_id, &pubk, total_required, &utxos_to_exclude);
+        }
+
+        if let Some(esplora_indexer) = self.esplora_indexer.as_ref() {
+            return self.get_esplora_utxos(
+                esplora_indexer,
+                &address,
+                total_required,
+                &utxos_to_exclude,
+                block_height,
+            );
+        }
+
+        if let Some(simulated_indexer) = self.simulated_indexer.as_ref() {
+            return self.get_simulated_utxos(
+                simulated_indexer,
+                &address,
+                total_required,
+                &utxos_to_exclude,
+                block_height,
+            );
+        }
+
         let mut utxos = loop {
             let result = BitcoinRPCRequest::list_unspent(
                 &self.config,
@@ -810,6 +1027,241 @@ impl BitcoinRegtestController {
         Some(utxos)
     }
 
+    /// Get the UTXOs for `public_key` from the burnchain DB, which tracks them from the blocks
+    /// this node downloads.  Used instead of bitcoind's wallet if `burnchain.self_managed_utxos`
+    /// is set.  As with `listunspent`, only UTXOs worth at least `minimum_amount` are considered.
+    fn get_self_managed_utxos(
+        &self,
+        epoch_id: StacksEpochId,
+        public_key: &Secp256k1PublicKey,
+        minimum_amount: u64,
+        utxos_to_exclude: &Option<UTXOSet>,
+    ) -> Option<UTXOSet> {
+        let pubkey_hash = Hash160::from_data(&public_key.to_bytes());
+        let script_pubkey = if self.config.miner.segwit && epoch_id >= StacksEpochId::Epoch21 {
+            SegwitBitcoinAddress::to_p2wpkh_tx_out(&pubkey_hash.0, 0).script_pubkey
+        } else {
+            LegacyBitcoinAddress::to_p2pkh_tx_out(&pubkey_hash, 0).script_pubkey
+        };
+
+        let burnchain_db = match self.get_burnchain().open_burnchain_db(true) {
+            Ok(burnchain_db) => burnchain_db,
+            Err(e) => {
+                error!("Failed to open burnchain DB to look up UTXOs: {:?}", &e);
+                return None;
+            }
+        };
+        let (bhh, wallet_utxos) = match burnchain_db.get_canonical_chain_tip().and_then(|tip| {
+            burnchain_db
+                .get_wallet_utxos(script_pubkey.as_bytes(), WALLET_PENDING_TX_LIFETIME)
+                .map(|utxos| (tip.block_hash, utxos))
+        }) {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Failed to look up UTXOs in the burnchain DB: {:?}", &e);
+                return None;
+            }
+        };
+
+        let txids_to_filter: Vec<_> = utxos_to_exclude
+            .as_ref()
+            .map(|utxos| utxos.utxos.iter().map(|utxo| utxo.txid).collect())
+            .unwrap_or_default();
+
+        let mut utxos = vec![];
+        for wallet_utxo in wallet_utxos.into_iter() {
+            if wallet_utxo.amount < minimum_amount {
+                continue;
+            }
+            let mut txid_bytes = wallet_utxo.txid.0;
+            txid_bytes.reverse();
+            let txid = Sha256dHash(txid_bytes);
+            if txids_to_filter.contains(&txid) {
+                continue;
+            }
+            utxos.push(UTXO {
+                txid,
+                vout: wallet_utxo.vout,
+                script_pub_key: Script::from(wallet_utxo.script_pubkey),
+                amount: wallet_utxo.amount,
+                confirmations: wallet_utxo.confirmations,
+            });
+        }
+
+        debug!(
+            "Got {} self-managed UTXOs for {}",
+            utxos.len(),
+            &public_key.to_hex()
+        );
+        if utxos.is_empty() {
+            return None;
+        }
+        let utxos = UTXOSet { bhh, utxos };
+        if utxos.total_available() < minimum_amount {
+            warn!(
+                "Total unspent {} < {} for {:?}",
+                utxos.total_available(),
+                minimum_amount,
+                &public_key.to_hex()
+            );
+            return None;
+        }
+        Some(utxos)
+    }
+
+    /// Get the UTXOs for `address` from the Esplora server.  Used instead of bitcoind's wallet if
+    /// `burnchain.esplora_url` is set.  As with `listunspent`, only UTXOs worth at least
+    /// `minimum_amount` are considered, and unconfirmed UTXOs only if RBF is disabled.
+    fn get_esplora_utxos(
+        &self,
+        esplora_indexer: &EsploraIndexer,
+        address: &BitcoinAddress,
+        minimum_amount: u64,
+        utxos_to_exclude: &Option<UTXOSet>,
+        block_height: u64,
+    ) -> Option<UTXOSet> {
+        let client = esplora_indexer.client();
+        let (bhh, tip_height, esplora_utxos) =
+            match client.get_block_hash(block_height).and_then(|block_hash| {
+                let tip_height = client.get_tip_height()?;
+                let utxos = client.get_address_utxos(&addr2str(address))?;
+                Ok((block_hash, tip_height, utxos))
+            }) {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("Esplora failure: error listing utxos {:?}", &e);
+                    return None;
+                }
+            };
+        let bhh = BurnchainHeaderHash::from_bitcoin_hash(&bhh);
+        self.make_remote_utxo_set(
+            bhh,
+            tip_height,
+            esplora_utxos,
+            address,
+            minimum_amount,
+            utxos_to_exclude,
+        )
+    }
+
+    /// Get the UTXOs for `address` from the simulated burnchain, if
+    /// `burnchain.simulated_burnchain` is set.  Filtered like `get_esplora_utxos()`.
+    fn get_simulated_utxos(
+        &self,
+        simulated_indexer: &SimulatedIndexer,
+        address: &BitcoinAddress,
+        minimum_amount: u64,
+        utxos_to_exclude: &Option<UTXOSet>,
+        block_height: u64,
+    ) -> Option<UTXOSet> {
+        let chain = simulated_indexer.chain();
+        let Some(bhh) = chain.get_block_hash(block_height) else {
+            error!(
+                "Simulated burnchain failure: no block at height {}",
+                block_height
+            );
+            return None;
+        };
+        let (_, tip_height) = chain.visible_tip();
+        let simulated_utxos = chain.get_utxos(&address_script_pubkey(address));
+        self.make_remote_utxo_set(
+            bhh,
+            tip_height,
+            simulated_utxos,
+            address,
+            minimum_amount,
+            utxos_to_exclude,
+        )
+    }
+
+    /// Turn an Esplora-style UTXO listing for `address` into a `UTXOSet` anchored at `bhh`
+    fn make_remote_utxo_set(
+        &self,
+        bhh: BurnchainHeaderHash,
+        tip_height: u64,
+        esplora_utxos: Vec<EsploraUtxo>,
+        address: &BitcoinAddress,
+        minimum_amount: u64,
+        utxos_to_exclude: &Option<UTXOSet>,
+    ) -> Option<UTXOSet> {
+        let script_pub_key = address_script_pubkey(address);
+
+        let txids_to_filter: Vec<_> = utxos_to_exclude
+            .as_ref()
+            .map(|utxos| utxos.utxos.iter().map(|utxo| utxo.txid).collect())
+            .unwrap_or_default();
+
+        let mut utxos = vec![];
+        for esplora_utxo in esplora_utxos.into_iter() {
+            if esplora_utxo.value < minimum_amount || txids_to_filter.contains(&esplora_utxo.txid) {
+                continue;
+            }
+            let confirmations = match esplora_utxo.block_height {
+                Some(height) => tip_height.saturating_sub(height) + 1,
+                None if !self.allow_rbf => 0,
+                None => continue,
+            };
+            utxos.push(UTXO {
+                txid: esplora_utxo.txid,
+                vout: esplora_utxo.vout,
+                script_pub_key: script_pub_key.clone(),
+                amount: esplora_utxo.value,
+                confirmations: u32::try_from(confirmations).unwrap_or(u32::MAX),
+            });
+        }
+
+        debug!("Got {} remote UTXOs for {}", utxos.len(), addr2str(address));
+        if utxos.is_empty() {
+            return None;
+        }
+        let utxos = UTXOSet { bhh, utxos };
+        if utxos.total_available() < minimum_amount {
+            warn!(
+                "Total unspent {} < {} for {}",
+                utxos.total_available(),
+                minimum_amount,
+                addr2str(address)
+            );
+            return None;
+        }
+        Some(utxos)
+    }
+
+    /// Remember a transaction this node has sent, so that its inputs are not spent again and its
+    /// change can be spent before it is mined.  Only needed if `burnchain.self_managed_utxos` is
+    /// set.
+    fn record_self_managed_tx(&self, transaction: &SerializedTx) {
+        let tx: Transaction = match btc_deserialize(&transaction.bytes) {
+            Ok(tx) => tx,
+            Err(e) => {
+                warn!(
+                    "Failed to decode sent transaction {}: {:?}",
+                    &transaction.txid, &e
+                );
+                return;
+            }
+        };
+        let watched = self
+            .indexer
+            .config
+            .watched_pubkey_hashes
+            .iter()
+            .cloned()
+            .collect();
+        let res = self
+            .get_burnchain()
+            .open_burnchain_db(true)
+            .and_then(|mut burnchain_db| {
+                burnchain_db.record_wallet_pending_tx(&tx, &watched, WALLET_PENDING_TX_LIFETIME)
+            });
+        if let Err(e) = res {
+            warn!(
+                "Failed to record sent transaction {} in the burnchain DB: {:?}",
+                &transaction.txid, &e
+            );
+        }
+    }
+
     fn build_leader_key_register_tx(
         &mut self,
         epoch_id: StacksEpochId,
@@ -819,9 +1271,8 @@ impl BitcoinRegtestController {
     ) -> Option<Transaction> {
         let public_key = signer.get_public_key();
 
-        // reload the config to find satoshis_per_byte changes
-        let btc_miner_fee = self.config.burnchain.leader_key_tx_estimated_size
-            * get_satoshis_per_byte(&self.config);
+        let fee_rate = get_fee_rate(&self.config, BurnchainOpKind::LeaderKeyRegister).fee_rate;
+        let btc_miner_fee = self.config.burnchain.leader_key_tx_estimated_size * fee_rate;
         let budget_for_outputs = DUST_UTXO_LIMIT;
         let total_required = btc_miner_fee + budget_for_outputs;
 
@@ -849,8 +1300,6 @@ impl BitcoinRegtestController {
 
         tx.output = vec![consensus_output];
 
-        let fee_rate = get_satoshis_per_byte(&self.config);
-
         self.finalize_tx(
             epoch_id,
             &mut tx,
@@ -943,6 +1392,7 @@ impl BitcoinRegtestController {
     ) -> Option<Transaction> {
         let public_key = signer.get_public_key();
         let max_tx_size = 230;
+        let fee_rate = get_fee_rate(&self.config, BurnchainOpKind::TransferStx).fee_rate;
         let (mut tx, mut utxos) = if let Some(utxo) = utxo_to_use {
             (
                 Transaction {
@@ -960,7 +1410,7 @@ impl BitcoinRegtestController {
             self.prepare_tx(
                 epoch_id,
                 &public_key,
-                DUST_UTXO_LIMIT + max_tx_size * get_satoshis_per_byte(&self.config),
+                DUST_UTXO_LIMIT + max_tx_size * fee_rate,
                 None,
                 None,
                 0,
@@ -994,7 +1444,7 @@ impl BitcoinRegtestController {
             DUST_UTXO_LIMIT,
             0,
             max_tx_size,
-            get_satoshis_per_byte(&self.config),
+            fee_rate,
             &mut utxos,
             signer,
         )?;
@@ -1025,6 +1475,7 @@ impl BitcoinRegtestController {
     ) -> Option<Transaction> {
         let public_key = signer.get_public_key();
         let max_tx_size = 230;
+        let fee_rate = get_fee_rate(&self.config, BurnchainOpKind::DelegateStx).fee_rate;
 
         let (mut tx, mut utxos) = if let Some(utxo) = utxo_to_use {
             (
@@ -1043,7 +1494,7 @@ impl BitcoinRegtestController {
             self.prepare_tx(
                 epoch_id,
                 &public_key,
-                DUST_UTXO_LIMIT + max_tx_size * get_satoshis_per_byte(&self.config),
+                DUST_UTXO_LIMIT + max_tx_size * fee_rate,
                 None,
                 None,
                 0,
@@ -1077,7 +1528,7 @@ impl BitcoinRegtestController {
             DUST_UTXO_LIMIT,
             0,
             max_tx_size,
-            get_satoshis_per_byte(&self.config),
+            fee_rate,
             &mut utxos,
             signer,
         )?;
@@ -1103,6 +1554,7 @@ impl BitcoinRegtestController {
     ) -> Option<Transaction> {
         let public_key = signer.get_public_key();
         let max_tx_size = 230;
+        let fee_rate = get_fee_rate(&self.config, BurnchainOpKind::VoteForAggregateKey).fee_rate;
 
         let (mut tx, mut utxos) = if let Some(utxo) = utxo_to_use {
             (
@@ -1121,7 +1573,7 @@ impl BitcoinRegtestController {
             self.prepare_tx(
                 epoch_id,
                 &public_key,
-                DUST_UTXO_LIMIT + max_tx_size * get_satoshis_per_byte(&self.config),
+                DUST_UTXO_LIMIT + max_tx_size * fee_rate,
                 None,
                 None,
                 0,
@@ -1151,7 +1603,7 @@ impl BitcoinRegtestController {
             DUST_UTXO_LIMIT,
             0,
             max_tx_size,
-            get_satoshis_per_byte(&self.config),
+            fee_rate,
             &mut utxos,
             signer,
         )?;
@@ -1197,8 +1649,9 @@ impl BitcoinRegtestController {
     ) -> Option<Transaction> {
         let public_key = signer.get_public_key();
         let max_tx_size = 280;
+        let fee_rate = get_fee_rate(&self.config, BurnchainOpKind::PreStx).fee_rate;
 
-        let output_amt = DUST_UTXO_LIMIT + max_tx_size * get_satoshis_per_byte(&self.config);
+        let output_amt = DUST_UTXO_LIMIT + max_tx_size * fee_rate;
         let (mut tx, mut utxos) =
             self.prepare_tx(epoch_id, &public_key, output_amt, None, None, 0)?;
 
@@ -1227,7 +1680,7 @@ impl BitcoinRegtestController {
             output_amt,
             0,
             max_tx_size,
-            get_satoshis_per_byte(&self.config),
+            fee_rate,
             &mut utxos,
             signer,
         )?;
@@ -1263,6 +1716,7 @@ impl BitcoinRegtestController {
     ) -> Option<Transaction> {
         let public_key = signer.get_public_key();
         let max_tx_size = 250;
+        let fee_rate = get_fee_rate(&self.config, BurnchainOpKind::StackStx).fee_rate;
 
         let (mut tx, mut utxos) = if let Some(utxo) = utxo_to_use {
             (
@@ -1281,7 +1735,7 @@ impl BitcoinRegtestController {
             self.prepare_tx(
                 epoch_id,
                 &public_key,
-                DUST_UTXO_LIMIT + max_tx_size * get_satoshis_per_byte(&self.config),
+                DUST_UTXO_LIMIT + max_tx_size * fee_rate,
                 None,
                 None,
                 0,
@@ -1313,7 +1767,7 @@ impl BitcoinRegtestController {
             DUST_UTXO_LIMIT,
             0,
             max_tx_size,
-            get_satoshis_per_byte(&self.config),
+            fee_rate,
             &mut utxos,
             signer,
         )?;
@@ -1353,8 +1807,21 @@ impl BitcoinRegtestController {
         previous_txids: &Vec<Txid>,
     ) -> Option<Transaction> {
         let mut estimated_fees = match previous_fees {
-            Some(fees) => fees.fees_from_previous_tx(&payload, &self.config),
-            None => LeaderBlockCommitFees::estimated_fees_from_payload(&payload, &self.config),
+            Some(fees) => {
+                let fee_rate = get_rbf_fee_rate(
+                    &self.config,
+                    BurnchainOpKind::LeaderBlockCommit,
+                    fees.fee_rate,
+                    previous_txids.len() as u64,
+                )
+                .fee_rate;
+                fees.fees_from_previous_tx(&payload, &self.config, fee_rate)
+            }
+            None => {
+                let fee_rate =
+                    get_fee_rate(&self.config, BurnchainOpKind::LeaderBlockCommit).fee_rate;
+                LeaderBlockCommitFees::estimated_fees_from_payload(&payload, &self.config, fee_rate)
+            }
         };
 
         let _ = self.sortdb_mut();
@@ -1473,10 +1940,24 @@ impl BitcoinRegtestController {
             // check if ongoing_op is in the burnchain_db *or* has been confirmed via the bitcoin RPC
             let mined_op = burnchain_db.find_burnchain_op(&self.indexer, txid);
             let ongoing_tx_confirmed = mined_op.is_some()
-                || matches!(
-                    BitcoinRPCRequest::check_transaction_confirmed(&self.config, txid),
-                    Ok(true)
-                );
+                || match (
+                    self.esplora_indexer.as_ref(),
+                    self.simulated_indexer.as_ref(),
+                ) {
+                    (Some(esplora_indexer), _) => {
+                        matches!(
+                            esplora_indexer.client().is_transaction_confirmed(txid),
+                            Ok(true)
+                        )
+                    }
+                    (None, Some(simulated_indexer)) => {
+                        simulated_indexer.chain().is_transaction_confirmed(txid)
+                    }
+                    (None, None) => matches!(
+                        BitcoinRPCRequest::check_transaction_confirmed(&self.config, txid),
+                        Ok(true)
+                    ),
+                };
             if ongoing_tx_confirmed {
                 debug!(
                     "Was able to retrieve confirmation of ongoing burnchain TXID - {}",
@@ -1534,13 +2015,14 @@ impl BitcoinRegtestController {
             return res;
         }
 
-        // Stop as soon as the fee_rate is ${self.config.burnchain.max_rbf} percent higher, stop RBF
-        if ongoing_op.fees.fee_rate
-            > (get_satoshis_per_byte(&self.config) * get_max_rbf(&self.config) / 100)
-        {
+        // Stop RBF once the fee rate reaches the maximum allowed by the fee policy
+        if !can_rbf(
+            &self.config.get_burnchain_config(),
+            ongoing_op.fees.fee_rate,
+        ) {
             warn!(
-                "RBF'd block commits reached {}% satoshi per byte fee rate, not resubmitting",
-                get_max_rbf(&self.config)
+                "RBF'd block commits reached the maximum fee rate, not resubmitting";
+                "fee_rate" => ongoing_op.fees.fee_rate
             );
             self.ongoing_block_commit = Some(ongoing_op);
             return None;
@@ -1602,7 +2084,13 @@ impl BitcoinRegtestController {
     ) -> BitcoinAddress {
         let (_, network_id) = self.config.burnchain.get_bitcoin_network();
 
-        if self.config.miner.segwit && epoch_id >= StacksEpochId::Epoch21 {
+        if self.config.miner.taproot && epoch_id >= StacksEpochId::Epoch21 {
+            let output_key = public_key
+                .to_taproot_output_key()
+                .expect("Public key incorrect");
+            BitcoinAddress::from_bytes_segwit_p2tr(network_id, &output_key)
+                .expect("Public key incorrect")
+        } else if self.config.miner.segwit && epoch_id >= StacksEpochId::Epoch21 {
             let hash160 = Hash160::from_data(&public_key.to_bytes_compressed());
             BitcoinAddress::from_bytes_segwit_p2wpkh(network_id, &hash160.0)
                 .expect("Public key incorrect")
@@ -1705,8 +2193,9 @@ impl BitcoinRegtestController {
                 &mut utxos_cloned,
                 signer,
             );
-            let serialized_tx = SerializedTx::new(tx_cloned);
-            cmp::max(min_tx_size, serialized_tx.bytes.len() as u64)
+            // fee rates are per virtual byte, which discounts witness data
+            let vsize = (tx_cloned.get_weight() + 3) / 4;
+            cmp::max(min_tx_size, vsize)
         };
 
         let rbf_fee = if spent_in_rbf == 0 {
@@ -1727,8 +2216,9 @@ impl BitcoinRegtestController {
 
     /// Sign and serialize a tx, consuming the UTXOs in utxo_set and spending total_to_spend
     /// satoshis.  Uses the key in signer.
-    /// If self.config.miner.segwit is true, the transaction's change address will be a p2wpkh
-    /// output. Otherwise, it will be a p2pkh output.
+    /// If self.config.miner.taproot is true, the transaction's change address will be a p2tr
+    /// output.  Otherwise, if self.config.miner.segwit is true, the transaction's change address
+    /// will be a p2wpkh output. Otherwise, it will be a p2pkh output.
     fn serialize_tx(
         &mut self,
         epoch_id: StacksEpochId,
@@ -1767,7 +2257,13 @@ impl BitcoinRegtestController {
             value, total_consumed, total_to_spend
         );
         if value >= DUST_UTXO_LIMIT {
-            let change_output = if self.config.miner.segwit && epoch_id >= StacksEpochId::Epoch21 {
+            let change_output = if self.config.miner.taproot && epoch_id >= StacksEpochId::Epoch21 {
+                // p2tr
+                let output_key = public_key
+                    .to_taproot_output_key()
+                    .expect("FATAL: failed to compute Taproot output key");
+                SegwitBitcoinAddress::to_p2tr_tx_out(&output_key, value)
+            } else if self.config.miner.segwit && epoch_id >= StacksEpochId::Epoch21 {
                 // p2wpkh
                 public_key.set_compressed(true);
                 let change_address_hash = Hash160::from_data(&public_key.to_bytes());
@@ -1783,7 +2279,8 @@ impl BitcoinRegtestController {
             debug!("Not enough change to clear dust limit. Not adding change address.");
         }
 
-        for (i, utxo) in utxos_set.utxos.iter().enumerate() {
+        // add all inputs before signing any of them, since every sighash commits to all of them
+        for utxo in utxos_set.utxos.iter() {
             let input = TxIn {
                 previous_output: OutPoint {
                     txid: utxo.txid,
@@ -1794,10 +2291,37 @@ impl BitcoinRegtestController {
                 witness: vec![],
             };
             tx.input.push(input);
+        }
+
+        // BIP-341 sighashes also commit to the amounts and scriptPubKeys of every spent output
+        let prevouts: Vec<_> = utxos_set
+            .utxos
+            .iter()
+            .map(|utxo| TxOut {
+                value: utxo.amount,
+                script_pubkey: utxo.script_pub_key.clone(),
+            })
+            .collect();
 
+        for (i, utxo) in utxos_set.utxos.iter().enumerate() {
             let script_pub_key = utxo.script_pub_key.clone();
             let sig_hash_all = 0x01;
 
+            if script_pub_key.as_bytes().len() == 34
+                && script_pub_key.as_bytes()[0..2] == [0x51, 0x20]
+            {
+                // p2tr key-path spend, signed with SIGHASH_DEFAULT
+                let sig_hash = tx
+                    .taproot_key_spend_signature_hash(i, &prevouts, 0x00)
+                    .expect("FATAL: failed to compute Taproot sighash");
+                let sig = signer
+                    .sign_taproot_key_spend(sig_hash.as_bytes())
+                    .expect("Unable to sign message");
+                tx.input[i].script_sig = Script::from(vec![]);
+                tx.input[i].witness = vec![sig.to_vec()];
+                continue;
+            }
+
             let (sig_hash, is_segwit) = if script_pub_key.as_bytes().len() == 22
                 && script_pub_key.as_bytes()[0..2] == [0x00, 0x14]
             {
@@ -1846,10 +2370,30 @@ impl BitcoinRegtestController {
     /// failure.
     pub fn send_transaction(&self, transaction: SerializedTx) -> Option<Txid> {
         debug!("Send raw transaction: {}", transaction.to_hex());
-        let result = BitcoinRPCRequest::send_raw_transaction(&self.config, transaction.to_hex());
+        let result = match (
+            self.esplora_indexer.as_ref(),
+            self.simulated_indexer.as_ref(),
+        ) {
+            (Some(esplora_indexer), _) => esplora_indexer
+                .client()
+                .broadcast_transaction(&transaction.bytes)
+                .map(|_| ())
+                .map_err(|e| RPCError::Network(format!("Esplora broadcast failed: {}", e))),
+            (None, Some(simulated_indexer)) => simulated_indexer
+                .chain()
+                .submit_raw_transaction(&transaction.bytes)
+                .map(|_| ())
+                .map_err(|e| RPCError::Parsing(format!("Simulated broadcast failed: {}", e))),
+            (None, None) => {
+                BitcoinRPCRequest::send_raw_transaction(&self.config, transaction.to_hex())
+            }
+        };
         match result {
             Ok(_) => {
                 debug!("Sent transaction {}", &transaction.txid);
+                if self.config.burnchain.self_managed_utxos {
+                    self.record_self_managed_tx(&transaction);
+                }
                 Some(transaction.txid())
             }
             Err(e) => {
@@ -1921,6 +2465,12 @@ impl BitcoinRegtestController {
         let public_key = Secp256k1PublicKey::from_slice(&public_key_bytes)
             .expect("FATAL: invalid public key bytes");
         let address = self.get_miner_address(StacksEpochId::Epoch21, &public_key);
+        if let Some(simulated_indexer) = self.simulated_indexer.as_ref() {
+            simulated_indexer
+                .chain()
+                .mine_blocks(num_blocks, &address_script_pubkey(&address));
+            return;
+        }
         let result =
             BitcoinRPCRequest::generate_to_address(&self.config, num_blocks, addr2str(&address));
 
@@ -1936,6 +2486,13 @@ impl BitcoinRegtestController {
     #[cfg(test)]
     pub fn invalidate_block(&self, block: &BurnchainHeaderHash) {
         info!("Invalidating block {}", &block);
+        if let Some(simulated_indexer) = self.simulated_indexer.as_ref() {
+            simulated_indexer
+                .chain()
+                .invalidate_block(block)
+                .expect("FATAL: failed to invalidate simulated block");
+            return;
+        }
         let request = BitcoinRPCRequest {
             method: "invalidateblock".into(),
             params: vec![json!(&block.to_string())],
@@ -1948,8 +2505,43 @@ impl BitcoinRegtestController {
         }
     }
 
+    /// Build the transaction for `operation` and mine it into a new simulated block on top of
+    /// `parent`, which need not be on the chain indexers can see.  This is how tests put crafted
+    /// operations into a particular fork.  Returns the new block's hash.
+    #[cfg(test)]
+    pub fn mine_simulated_operation(
+        &mut self,
+        epoch_id: StacksEpochId,
+        operation: BlockstackOperationType,
+        op_signer: &mut BurnchainOpSigner,
+        parent: &BurnchainHeaderHash,
+    ) -> Option<BurnchainHeaderHash> {
+        let chain = self
+            .simulated_indexer
+            .as_ref()
+            .expect("FATAL: burnchain.simulated_burnchain is not set")
+            .chain()
+            .clone();
+        let serialized_tx = self.make_operation_tx(epoch_id, operation, op_signer, 1)?;
+        let tx: Transaction = btc_deserialize(&serialized_tx.bytes)
+            .expect("BUG: failed to decode a transaction we built");
+        match chain.mine_block_on(parent, vec![tx], &Script::new()) {
+            Ok(block_hash) => Some(block_hash),
+            Err(e) => {
+                error!("Failed to mine simulated block on {}: {:?}", parent, &e);
+                None
+            }
+        }
+    }
+
     #[cfg(test)]
     pub fn get_block_hash(&self, height: u64) -> BurnchainHeaderHash {
+        if let Some(simulated_indexer) = self.simulated_indexer.as_ref() {
+            return simulated_indexer
+                .chain()
+                .get_block_hash(height)
+                .expect("FATAL: no simulated block at this height");
+        }
         let request = BitcoinRPCRequest {
             method: "getblockhash".into(),
             params: vec![json!(height)],
@@ -2020,6 +2612,12 @@ impl BitcoinRegtestController {
 
     #[cfg(test)]
     pub fn get_raw_transaction(&self, txid: &Txid) -> Transaction {
+        if let Some(simulated_indexer) = self.simulated_indexer.as_ref() {
+            return simulated_indexer
+                .chain()
+                .get_transaction(txid)
+                .expect("FATAL: no such simulated transaction");
+        }
         let txstr = BitcoinRPCRequest::get_raw_transaction(&self.config, txid).unwrap();
         let tx: Transaction = btc_deserialize(&hex_bytes(&txstr).unwrap()).unwrap();
         tx
@@ -2079,6 +2677,20 @@ impl BurnchainController for BitcoinRegtestController {
             self.indexer.get_first_block_header_timestamp()?,
             self.indexer.get_stacks_epochs(),
         )?;
+
+        if let Some(rescan_height) = self.config.burnchain.utxo_rescan_height {
+            if self.config.burnchain.self_managed_utxos {
+                let mut burnchain_db = burnchain.open_burnchain_db(true)?;
+                let rescanned = burnchain_db
+                    .get_wallet_rescan_height()?
+                    .map(|height| height <= rescan_height)
+                    .unwrap_or(false);
+                if !rescanned {
+                    let num_scanned = burnchain_db.rescan_wallet(&self.indexer, rescan_height)?;
+                    info!("Rescanned {} burnchain blocks for UTXOs", num_scanned);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -2145,10 +2757,17 @@ impl BurnchainController for BitcoinRegtestController {
                 Secp256k1PublicKey::from_hex(local_mining_pubkey).unwrap();
             let address = self.get_miner_address(StacksEpochId::Epoch21, &local_mining_pubkey);
 
-            if self.config.miner.segwit {
+            if self.config.miner.segwit || self.config.miner.taproot {
                 local_mining_pubkey.set_compressed(true);
             }
 
+            if let Some(simulated_indexer) = self.simulated_indexer.as_ref() {
+                simulated_indexer
+                    .chain()
+                    .mine_blocks(num_blocks, &address_script_pubkey(&address));
+                return;
+            }
+
             info!("Creating wallet if it does not exist");
             match self.create_wallet_if_dne() {
                 Err(e) => warn!("Error when creating wallet: {:?}", e),
@@ -2310,6 +2929,12 @@ impl ParsedUTXO {
     }
 }
 
+/// Convert a fee rate in BTC/kvB, as reported by bitcoind, to sats/vbyte, rounding up
+fn btc_per_kvb_to_sats_per_vb(fee_rate: f64) -> u64 {
+    let sats_per_kvb = (fee_rate * 100_000_000.0).round().max(0.0) as u64;
+    (sats_per_kvb + 999) / 1000
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BitcoinRPCRequest {
     /// The name of the RPC call
@@ -2399,6 +3024,60 @@ impl BitcoinRPCRequest {
         Ok(confirmations >= 1)
     }
 
+    /// Ask bitcoind for the fee rate (sats/vbyte) a transaction needs to pay to confirm within
+    /// `conf_target` blocks.  Returns None if bitcoind does not have enough data to say.
+    pub fn estimate_smart_fee(config: &Config, conf_target: u64) -> RPCResult<Option<u64>> {
+        let payload = BitcoinRPCRequest {
+            method: "estimatesmartfee".to_string(),
+            params: vec![conf_target.into()],
+            id: "stacks".to_string(),
+            jsonrpc: "2.0".to_string(),
+        };
+        let res = BitcoinRPCRequest::send(&config, payload)?;
+        let result = res.get("result").ok_or_else(|| {
+            RPCError::Parsing("No 'result' field in bitcoind RPC response".into())
+        })?;
+        let Some(feerate) = result.get("feerate") else {
+            debug!(
+                "bitcoind has no fee estimate for {} blocks: {:?}",
+                conf_target,
+                result.get("errors")
+            );
+            return Ok(None);
+        };
+        let feerate = feerate.as_f64().ok_or_else(|| {
+            RPCError::Parsing(
+                "Expected 'feerate' field to be numeric in bitcoind RPC response".into(),
+            )
+        })?;
+        Ok(Some(btc_per_kvb_to_sats_per_vb(feerate)))
+    }
+
+    /// Get the lowest fee rate (sats/vbyte) bitcoind currently accepts into its mempool
+    pub fn get_mempool_min_fee(config: &Config) -> RPCResult<u64> {
+        let payload = BitcoinRPCRequest {
+            method: "getmempoolinfo".to_string(),
+            params: vec![],
+            id: "stacks".to_string(),
+            jsonrpc: "2.0".to_string(),
+        };
+        let res = BitcoinRPCRequest::send(&config, payload)?;
+        let min_fee = res
+            .get("result")
+            .ok_or_else(|| RPCError::Parsing("No 'result' field in bitcoind RPC response".into()))?
+            .get("mempoolminfee")
+            .ok_or_else(|| {
+                RPCError::Parsing("No 'mempoolminfee' field in bitcoind RPC response".into())
+            })?
+            .as_f64()
+            .ok_or_else(|| {
+                RPCError::Parsing(
+                    "Expected 'mempoolminfee' field to be numeric in bitcoind RPC response".into(),
+                )
+            })?;
+        Ok(btc_per_kvb_to_sats_per_vb(min_fee))
+    }
+
     pub fn generate_to_address(config: &Config, num_blocks: u64, address: String) -> RPCResult<()> {
         debug!("Generate {} blocks to {}", num_blocks, &address);
         let payload = BitcoinRPCRequest {
@@ -2560,7 +3239,8 @@ impl BitcoinRPCRequest {
             .to_vec();
         let (_, network_id) = config.burnchain.get_bitcoin_network();
 
-        // import both the legacy and segwit variants of this public key
+        // import the legacy variant of this public key, and the segwit and Taproot variants if
+        // they are in use
         let mut addresses = vec![BitcoinAddress::from_bytes_legacy(
             network_id,
             LegacyBitcoinAddressType::PublicKeyHash,
@@ -2575,6 +3255,16 @@ impl BitcoinRPCRequest {
             );
         }
 
+        if config.miner.taproot {
+            let output_key = public_key
+                .to_taproot_output_key()
+                .expect("Public key incorrect");
+            addresses.push(
+                BitcoinAddress::from_bytes_segwit_p2tr(network_id, &output_key)
+                    .expect("Public key incorrect"),
+            );
+        }
+
         for address in addresses.into_iter() {
             debug!(
                 "Import address {} for public key {}",
@@ -2740,6 +3430,9 @@ mod tests {
     use std::fs::File;
     use std::io::Write;
 
+    use stacks_common::types::Address;
+    use stacks_common::util::secp256k1::{secp256k1_verify_schnorr, Secp256k1PrivateKey};
+
     use super::*;
     use crate::config::DEFAULT_SATS_PER_VB;
 
@@ -2750,7 +3443,7 @@ mod tests {
 
         let mut config = Config::default();
 
-        let satoshis_per_byte = get_satoshis_per_byte(&config);
+        let satoshis_per_byte = get_fee_rate(&config, BurnchainOpKind::LeaderBlockCommit).fee_rate;
         assert_eq!(satoshis_per_byte, DEFAULT_SATS_PER_VB);
 
         let mut file = File::create(&file_path).unwrap();
@@ -2758,6 +3451,161 @@ mod tests {
         writeln!(file, "satoshis_per_byte = 51").unwrap();
         config.config_path = Some(file_path.to_str().unwrap().to_string());
 
-        assert_eq!(get_satoshis_per_byte(&config), 51);
+        assert_eq!(
+            get_fee_rate(&config, BurnchainOpKind::LeaderBlockCommit).fee_rate,
+            51
+        );
+    }
+
+    #[test]
+    fn test_btc_per_kvb_to_sats_per_vb() {
+        assert_eq!(btc_per_kvb_to_sats_per_vb(0.00001), 1);
+        assert_eq!(btc_per_kvb_to_sats_per_vb(0.00001001), 2);
+        assert_eq!(btc_per_kvb_to_sats_per_vb(0.0005), 50);
+        assert_eq!(btc_per_kvb_to_sats_per_vb(0.0), 0);
+    }
+
+    #[test]
+    fn test_serialize_tx_taproot() {
+        let mut config = Config::default();
+        config.miner.taproot = true;
+        let mut btc_controller = BitcoinRegtestController::new_dummy(config);
+
+        let secret_key = Secp256k1PrivateKey::new();
+        let public_key = Secp256k1PublicKey::from_private(&secret_key);
+        let output_key = public_key.to_taproot_output_key().unwrap();
+        let script_pub_key = SegwitBitcoinAddress::to_p2tr_tx_out(&output_key, 0).script_pubkey;
+
+        let miner_address = btc_controller.get_miner_address(StacksEpochId::Epoch25, &public_key);
+        assert!(miner_address.is_segwit_p2tr());
+        assert_eq!(
+            miner_address.to_bytes(),
+            script_pub_key.as_bytes()[2..].to_vec()
+        );
+
+        let mut utxos = UTXOSet {
+            bhh: BurnchainHeaderHash([0x01; 32]),
+            utxos: (0..3u8)
+                .map(|i| UTXO {
+                    txid: Sha256dHash([i; 32]),
+                    vout: i as u32,
+                    script_pub_key: script_pub_key.clone(),
+                    amount: 100_000 + i as u64,
+                    confirmations: 1,
+                })
+                .collect(),
+        };
+        let mut tx = Transaction {
+            input: vec![],
+            output: vec![TxOut {
+                value: 150_000,
+                script_pubkey: LegacyBitcoinAddress::to_p2pkh_tx_out(&Hash160([0x02; 20]), 0)
+                    .script_pubkey,
+            }],
+            version: 1,
+            lock_time: 0,
+        };
+        let mut signer = BurnchainOpSigner::new(secret_key, false);
+        assert!(btc_controller.serialize_tx(
+            StacksEpochId::Epoch25,
+            &mut tx,
+            160_000,
+            &mut utxos,
+            &mut signer
+        ));
+
+        // two inputs are needed, and the change goes back to the p2tr address
+        assert_eq!(tx.input.len(), 2);
+        assert_eq!(tx.output.len(), 2);
+        assert_eq!(tx.output[1].script_pubkey, script_pub_key);
+        assert_eq!(tx.output[1].value, 200_001 - 160_000);
+
+        let prevouts: Vec<_> = utxos
+            .utxos
+            .iter()
+            .map(|utxo| TxOut {
+                value: utxo.amount,
+                script_pubkey: utxo.script_pub_key.clone(),
+            })
+            .collect();
+        for (i, input) in tx.input.iter().enumerate() {
+            assert!(input.script_sig.is_empty());
+            assert_eq!(input.witness.len(), 1);
+            let sighash = tx
+                .taproot_key_spend_signature_hash(i, &prevouts, 0x00)
+                .unwrap();
+            secp256k1_verify_schnorr(sighash.as_bytes(), &input.witness[0], &output_key).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_estimated_taproot_block_commit_vsize() {
+        let output_key = [0x03; 32];
+        let mut tx = Transaction {
+            input: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: Sha256dHash([0x04; 32]),
+                    vout: 2,
+                },
+                script_sig: Script::new(),
+                sequence: 0xFFFFFFFD,
+                witness: vec![vec![0x05; 64]],
+            }],
+            output: vec![TxOut {
+                value: 0,
+                script_pubkey: Builder::new()
+                    .push_opcode(opcodes::All::OP_RETURN)
+                    .push_slice(&[0x06; 80])
+                    .into_script(),
+            }],
+            version: 1,
+            lock_time: 0,
+        };
+        // two PoX outputs and a change output
+        for _ in 0..3 {
+            tx.output
+                .push(SegwitBitcoinAddress::to_p2tr_tx_out(&output_key, 10_000));
+        }
+
+        let vsize = (tx.get_weight() + 3) / 4;
+        let estimate = estimated_taproot_block_commit_vsize(3);
+        assert!(estimate >= vsize);
+        assert!(estimate - vsize <= 2);
+
+        // much smaller than the default estimate for legacy inputs
+        assert!(estimate < Config::default().burnchain.block_commit_tx_estimated_size);
+    }
+
+    #[test]
+    fn test_address_script_pubkey() {
+        let addrs = vec![
+            BitcoinAddress::from_bytes_legacy(
+                BitcoinNetworkType::Regtest,
+                LegacyBitcoinAddressType::PublicKeyHash,
+                &[0x01; 20],
+            )
+            .unwrap(),
+            BitcoinAddress::from_bytes_legacy(
+                BitcoinNetworkType::Regtest,
+                LegacyBitcoinAddressType::ScriptHash,
+                &[0x02; 20],
+            )
+            .unwrap(),
+            BitcoinAddress::from_bytes_segwit_p2wpkh(BitcoinNetworkType::Regtest, &[0x03; 20])
+                .unwrap(),
+            BitcoinAddress::Segwit(SegwitBitcoinAddress::P2WSH(false, [0x04; 32])),
+            BitcoinAddress::from_bytes_segwit_p2tr(BitcoinNetworkType::Regtest, &[0x05; 32])
+                .unwrap(),
+        ];
+        for addr in addrs.into_iter() {
+            let script_pubkey = address_script_pubkey(&addr);
+            assert_eq!(
+                BitcoinAddress::from_scriptpubkey(
+                    BitcoinNetworkType::Regtest,
+                    script_pubkey.as_bytes()
+                ),
+                Some(addr)
+            );
+        }
     }
 }
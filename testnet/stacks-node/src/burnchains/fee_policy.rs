@@ -0,0 +1,414 @@
+// Copyright (C) 2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fee rate policy for burnchain ops.
+//!
+//! By default, burnchain ops pay `burnchain.satoshis_per_byte`, and each block-commit RBF adds
+//! `burnchain.rbf_fee_increment` until the fee rate passes `burnchain.max_rbf` percent of
+//! `satoshis_per_byte`.
+//!
+//! If `burnchain.dynamic_fee_rate` is set, the first attempt at an op instead pays what bitcoind
+//! estimates it takes to confirm within `burnchain.fee_rate_target_blocks`, and each RBF
+//! re-estimates with a target one block closer (but always bumps by at least
+//! `rbf_fee_increment`).  Fee rates are never below bitcoind's mempool minimum fee, and never
+//! above `burnchain.max_satoshis_per_byte`.  If bitcoind can't produce an estimate,
+//! `satoshis_per_byte` is used instead.
+
+use stacks::monitoring::{
+    record_btc_fee_rate_decision, update_btc_fee_estimate, update_btc_mempool_min_fee,
+};
+
+use super::bitcoin_regtest_controller::BitcoinRPCRequest;
+use crate::config::BurnchainConfig;
+use crate::Config;
+
+/// The kinds of burnchain ops a node sends, for labeling fee rate decisions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BurnchainOpKind {
+    LeaderBlockCommit,
+    LeaderKeyRegister,
+    PreStx,
+    TransferStx,
+    DelegateStx,
+    StackStx,
+    VoteForAggregateKey,
+}
+
+impl BurnchainOpKind {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            BurnchainOpKind::LeaderBlockCommit => "leader_block_commit",
+            BurnchainOpKind::LeaderKeyRegister => "leader_key_register",
+            BurnchainOpKind::PreStx => "pre_stx",
+            BurnchainOpKind::TransferStx => "transfer_stx",
+            BurnchainOpKind::DelegateStx => "delegate_stx",
+            BurnchainOpKind::StackStx => "stack_stx",
+            BurnchainOpKind::VoteForAggregateKey => "vote_for_aggregate_key",
+        }
+    }
+}
+
+/// What determined a fee rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeRateSource {
+    /// `burnchain.satoshis_per_byte`
+    Static,
+    /// bitcoind's fee estimate for the confirmation target
+    Estimate,
+    /// bitcoind's mempool minimum fee, which was higher than the fee rate otherwise chosen
+    MempoolFloor,
+    /// The previous attempt's fee rate plus `burnchain.rbf_fee_increment`
+    Increment,
+    /// `burnchain.max_satoshis_per_byte`, which was lower than the fee rate otherwise chosen
+    Cap,
+}
+
+impl FeeRateSource {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            FeeRateSource::Static => "static",
+            FeeRateSource::Estimate => "estimate",
+            FeeRateSource::MempoolFloor => "mempool_floor",
+            FeeRateSource::Increment => "increment",
+            FeeRateSource::Cap => "cap",
+        }
+    }
+}
+
+/// A fee rate chosen for a burnchain op
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeRateDecision {
+    /// sats/vbyte
+    pub fee_rate: u64,
+    pub source: FeeRateSource,
+    /// The confirmation target the fee rate was chosen for
+    pub target_blocks: u64,
+}
+
+/// What bitcoind reported about current fee rates, in sats/vbyte
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeeRateObservation {
+    pub estimate: Option<u64>,
+    pub mempool_min_fee: Option<u64>,
+}
+
+/// Get the confirmation target for an RBF, given the number of attempts made so far
+pub fn rbf_target_blocks(config: &BurnchainConfig, attempts: u64) -> u64 {
+    config
+        .fee_rate_target_blocks
+        .saturating_sub(attempts)
+        .max(1)
+}
+
+/// Can an op paying `fee_rate` still be replaced by fee?
+pub fn can_rbf(config: &BurnchainConfig, fee_rate: u64) -> bool {
+    if config.dynamic_fee_rate {
+        fee_rate < config.get_max_fee_rate()
+    } else {
+        fee_rate <= config.satoshis_per_byte * config.max_rbf / 100
+    }
+}
+
+/// Raise `fee_rate` to the mempool minimum fee and lower it to the cap, as needed
+fn clamp_fee_rate(
+    config: &BurnchainConfig,
+    observed: &FeeRateObservation,
+    fee_rate: u64,
+    source: FeeRateSource,
+    target_blocks: u64,
+) -> FeeRateDecision {
+    let (mut fee_rate, mut source) = (fee_rate.max(1), source);
+    if let Some(mempool_min_fee) = observed.mempool_min_fee {
+        if mempool_min_fee > fee_rate {
+            fee_rate = mempool_min_fee;
+            source = FeeRateSource::MempoolFloor;
+        }
+    }
+    let max_fee_rate = config.get_max_fee_rate().max(1);
+    if fee_rate > max_fee_rate {
+        fee_rate = max_fee_rate;
+        source = FeeRateSource::Cap;
+    }
+    FeeRateDecision {
+        fee_rate,
+        source,
+        target_blocks,
+    }
+}
+
+/// Decide the fee rate of the first attempt at an op, given what bitcoind reported
+pub fn decide_fee_rate(config: &BurnchainConfig, observed: &FeeRateObservation) -> FeeRateDecision {
+    let (fee_rate, source) = match observed.estimate {
+        Some(estimate) => (estimate, FeeRateSource::Estimate),
+        None => (config.satoshis_per_byte, FeeRateSource::Static),
+    };
+    clamp_fee_rate(
+        config,
+        observed,
+        fee_rate,
+        source,
+        config.fee_rate_target_blocks,
+    )
+}
+
+/// Decide the fee rate of an RBF of an op that paid `previous_fee_rate` over `attempts` attempts,
+/// given what bitcoind reported for the narrowed confirmation target
+pub fn decide_rbf_fee_rate(
+    config: &BurnchainConfig,
+    observed: &FeeRateObservation,
+    previous_fee_rate: u64,
+    attempts: u64,
+) -> FeeRateDecision {
+    let bumped = previous_fee_rate.saturating_add(config.rbf_fee_increment);
+    let (fee_rate, source) = match observed.estimate {
+        Some(estimate) if estimate > bumped => (estimate, FeeRateSource::Estimate),
+        _ => (bumped, FeeRateSource::Increment),
+    };
+    clamp_fee_rate(
+        config,
+        observed,
+        fee_rate,
+        source,
+        rbf_target_blocks(config, attempts),
+    )
+}
+
+/// Ask bitcoind about current fee rates.  Failures are logged, and leave the corresponding
+/// observation empty.
+pub fn observe_fee_rates(config: &Config, target_blocks: u64) -> FeeRateObservation {
+    let estimate = match BitcoinRPCRequest::estimate_smart_fee(config, target_blocks) {
+        Ok(estimate) => estimate,
+        Err(e) => {
+            warn!("Failed to get bitcoin fee estimate: {:?}", &e);
+            None
+        }
+    };
+    let mempool_min_fee = match BitcoinRPCRequest::get_mempool_min_fee(config) {
+        Ok(min_fee) => Some(min_fee),
+        Err(e) => {
+            warn!("Failed to get bitcoin mempool minimum fee: {:?}", &e);
+            None
+        }
+    };
+    if let Some(estimate) = estimate {
+        update_btc_fee_estimate(estimate);
+    }
+    if let Some(min_fee) = mempool_min_fee {
+        update_btc_mempool_min_fee(min_fee);
+    }
+    FeeRateObservation {
+        estimate,
+        mempool_min_fee,
+    }
+}
+
+fn record_decision(op: BurnchainOpKind, decision: &FeeRateDecision, rbf: bool) {
+    info!("Chose burnchain fee rate";
+          "op" => op.as_str(),
+          "rbf" => rbf,
+          "fee_rate" => decision.fee_rate,
+          "source" => decision.source.as_str(),
+          "target_blocks" => decision.target_blocks);
+    record_btc_fee_rate_decision(op.as_str(), decision.source.as_str(), decision.fee_rate);
+}
+
+/// Get the fee rate (sats/vbyte) for the first attempt at an op
+pub fn get_fee_rate(config: &Config, op: BurnchainOpKind) -> FeeRateDecision {
+    // reload the config to find satoshis_per_byte changes
+    let burnchain_config = config.get_burnchain_config();
+    let decision = if burnchain_config.dynamic_fee_rate {
+        let observed = observe_fee_rates(config, burnchain_config.fee_rate_target_blocks);
+        decide_fee_rate(&burnchain_config, &observed)
+    } else {
+        FeeRateDecision {
+            fee_rate: burnchain_config.satoshis_per_byte,
+            source: FeeRateSource::Static,
+            target_blocks: burnchain_config.fee_rate_target_blocks,
+        }
+    };
+    record_decision(op, &decision, false);
+    decision
+}
+
+/// Get the fee rate (sats/vbyte) for an RBF of an op that paid `previous_fee_rate` over
+/// `attempts` attempts.  The caller should check `can_rbf()` first.
+pub fn get_rbf_fee_rate(
+    config: &Config,
+    op: BurnchainOpKind,
+    previous_fee_rate: u64,
+    attempts: u64,
+) -> FeeRateDecision {
+    let burnchain_config = config.get_burnchain_config();
+    let decision = if burnchain_config.dynamic_fee_rate {
+        let observed = observe_fee_rates(config, rbf_target_blocks(&burnchain_config, attempts));
+        decide_rbf_fee_rate(&burnchain_config, &observed, previous_fee_rate, attempts)
+    } else {
+        FeeRateDecision {
+            fee_rate: previous_fee_rate + burnchain_config.rbf_fee_increment,
+            source: FeeRateSource::Increment,
+            target_blocks: burnchain_config.fee_rate_target_blocks,
+        }
+    };
+    record_decision(op, &decision, true);
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dynamic_config() -> BurnchainConfig {
+        let mut config = Config::default().burnchain;
+        config.dynamic_fee_rate = true;
+        config.satoshis_per_byte = 50;
+        config.max_rbf = 150;
+        config.rbf_fee_increment = 5;
+        config.fee_rate_target_blocks = 3;
+        config
+    }
+
+    #[test]
+    fn test_decide_fee_rate() {
+        let mut config = dynamic_config();
+        config.max_satoshis_per_byte = Some(200);
+
+        // use the estimate
+        let observed = FeeRateObservation {
+            estimate: Some(30),
+            mempool_min_fee: Some(1),
+        };
+        assert_eq!(
+            decide_fee_rate(&config, &observed),
+            FeeRateDecision {
+                fee_rate: 30,
+                source: FeeRateSource::Estimate,
+                target_blocks: 3,
+            }
+        );
+
+        // no estimate, so fall back to satoshis_per_byte
+        let observed = FeeRateObservation {
+            estimate: None,
+            mempool_min_fee: None,
+        };
+        assert_eq!(
+            decide_fee_rate(&config, &observed),
+            FeeRateDecision {
+                fee_rate: 50,
+                source: FeeRateSource::Static,
+                target_blocks: 3,
+            }
+        );
+
+        // the mempool won't take the estimate
+        let observed = FeeRateObservation {
+            estimate: Some(30),
+            mempool_min_fee: Some(40),
+        };
+        assert_eq!(
+            decide_fee_rate(&config, &observed),
+            FeeRateDecision {
+                fee_rate: 40,
+                source: FeeRateSource::MempoolFloor,
+                target_blocks: 3,
+            }
+        );
+
+        // fee spike, so cap the fee rate
+        let observed = FeeRateObservation {
+            estimate: Some(500),
+            mempool_min_fee: Some(300),
+        };
+        assert_eq!(
+            decide_fee_rate(&config, &observed),
+            FeeRateDecision {
+                fee_rate: 200,
+                source: FeeRateSource::Cap,
+                target_blocks: 3,
+            }
+        );
+
+        // the default cap is max_rbf percent of satoshis_per_byte
+        config.max_satoshis_per_byte = None;
+        assert_eq!(decide_fee_rate(&config, &observed).fee_rate, 75);
+    }
+
+    #[test]
+    fn test_decide_rbf_fee_rate() {
+        let mut config = dynamic_config();
+        config.max_satoshis_per_byte = Some(100);
+
+        // estimate has not moved, so bump by the increment
+        let observed = FeeRateObservation {
+            estimate: Some(30),
+            mempool_min_fee: Some(1),
+        };
+        assert_eq!(
+            decide_rbf_fee_rate(&config, &observed, 30, 1),
+            FeeRateDecision {
+                fee_rate: 35,
+                source: FeeRateSource::Increment,
+                target_blocks: 2,
+            }
+        );
+
+        // estimate for the narrower target jumped
+        let observed = FeeRateObservation {
+            estimate: Some(60),
+            mempool_min_fee: Some(1),
+        };
+        assert_eq!(
+            decide_rbf_fee_rate(&config, &observed, 30, 2),
+            FeeRateDecision {
+                fee_rate: 60,
+                source: FeeRateSource::Estimate,
+                target_blocks: 1,
+            }
+        );
+
+        // never bid past the cap
+        assert_eq!(
+            decide_rbf_fee_rate(&config, &observed, 98, 5),
+            FeeRateDecision {
+                fee_rate: 100,
+                source: FeeRateSource::Cap,
+                target_blocks: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_can_rbf() {
+        let mut config = dynamic_config();
+        config.max_satoshis_per_byte = Some(100);
+        assert!(can_rbf(&config, 99));
+        assert!(!can_rbf(&config, 100));
+
+        // without dynamic fee rates, RBF stops once the fee rate passes max_rbf percent of
+        // satoshis_per_byte
+        config.dynamic_fee_rate = false;
+        assert!(can_rbf(&config, 75));
+        assert!(!can_rbf(&config, 76));
+    }
+
+    #[test]
+    fn test_rbf_target_blocks() {
+        let config = dynamic_config();
+        assert_eq!(rbf_target_blocks(&config, 0), 3);
+        assert_eq!(rbf_target_blocks(&config, 1), 2);
+        assert_eq!(rbf_target_blocks(&config, 2), 1);
+        assert_eq!(rbf_target_blocks(&config, 10), 1);
+    }
+}
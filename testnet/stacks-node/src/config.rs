@@ -12,6 +12,9 @@ use lazy_static::lazy_static;
 use rand::RngCore;
 use serde::Deserialize;
 use stacks::burnchains::affirmation::AffirmationMap;
+use stacks::burnchains::bitcoin::esplora::EsploraClient;
+#[cfg(test)]
+use stacks::burnchains::bitcoin::simulator::SimulatedBurnchain;
 use stacks::burnchains::bitcoin::BitcoinNetworkType;
 use stacks::burnchains::{Burnchain, MagicBytes, PoxConstants, BLOCKSTACK_MAGIC_MAINNET};
 use stacks::chainstate::nakamoto::signer_set::NakamotoSigners;
@@ -34,6 +37,7 @@ use stacks::cost_estimates::metrics::{CostMetric, ProportionalDotProduct, UnitMe
 use stacks::cost_estimates::{CostEstimator, FeeEstimator, PessimisticEstimator, UnitEstimator};
 use stacks::net::atlas::AtlasConfig;
 use stacks::net::connection::ConnectionOptions;
+use stacks::net::rpc_access::RPCAccessTier;
 use stacks::net::{Neighbor, NeighborKey};
 use stacks::types::chainstate::BurnchainHeaderHash;
 use stacks::util_lib::boot::boot_code_id;
@@ -51,6 +55,9 @@ use crate::chain_data::MinerStats;
 pub const DEFAULT_SATS_PER_VB: u64 = 50;
 const DEFAULT_MAX_RBF_RATE: u64 = 150; // 1.5x
 const DEFAULT_RBF_FEE_RATE_INCREMENT: u64 = 5;
+const DEFAULT_FEE_RATE_TARGET_BLOCKS: u64 = 2;
+/// The largest confirmation target bitcoind's `estimatesmartfee` accepts
+const MAX_FEE_RATE_TARGET_BLOCKS: u64 = 1008;
 const LEADER_KEY_TX_ESTIM_SIZE: u64 = 290;
 const BLOCK_COMMIT_TX_ESTIM_SIZE: u64 = 350;
 const INV_REWARD_CYCLES_TESTNET: u64 = 6;
@@ -208,6 +215,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_load_rpc_api_keys() {
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [connection_options]
+                rpc_anonymous_requests_per_minute = 60
+
+                [[connection_options.rpc_api_tiers]]
+                name = "partner"
+                requests_per_minute = 6000
+                daily_read_only_runtime = 1000000000
+
+                [[connection_options.rpc_api_keys]]
+                key = "secret"
+                tier = "partner"
+                "#,
+            )
+            .unwrap(),
+            false,
+        )
+        .expect("Expected to be able to parse RPC API keys from file");
+
+        assert_eq!(
+            config
+                .connection_options
+                .rpc_anonymous_tier
+                .requests_per_minute,
+            60
+        );
+        assert_eq!(
+            config
+                .connection_options
+                .rpc_anonymous_tier
+                .daily_read_only_runtime,
+            0
+        );
+        assert_eq!(
+            config.connection_options.rpc_api_keys.get("secret"),
+            Some(&RPCAccessTier {
+                name: "partner".into(),
+                requests_per_minute: 6000,
+                daily_read_only_runtime: 1000000000,
+            })
+        );
+
+        let err = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [[connection_options.rpc_api_keys]]
+                key = "secret"
+                tier = "partner"
+                "#,
+            )
+            .unwrap(),
+            false,
+        )
+        .unwrap_err();
+        assert!(err.contains("unknown tier 'partner'"));
+    }
+
     #[test]
     fn should_load_affirmation_map() {
         let affirmation_string = "nnnnnnnnnnnnnnnnnnnnnnnnnnnnnnnnppnnnnnnnnnnnnnnnnnnnnnnnnpppppnnnnnnnnnnnnnnnnnnnnnnnpppppppppppppppnnnnnnnnnnnnnnnnnnnnnnnppppppppppnnnnnnnnnnnnnnnnnnnppppnnnnnnnnnnnnnnnnnnnnnnnppppppppnnnnnnnnnnnnnnnnnnnnnnnppnppnnnnnnnnnnnnnnnnnnnnnnnppppnnnnnnnnnnnnnnnnnnnnnnnnnppppppnnnnnnnnnnnnnnnnnnnnnnnnnppnnnnnnnnnnnnnnnnnnnnnnnnnpppppppnnnnnnnnnnnnnnnnnnnnnnnnnnpnnnnnnnnnnnnnnnnnnnnnnnnnpppnppppppppppppppnnppppnpa";
@@ -249,6 +317,128 @@ mod tests {
         assert!(Config::from_config_file(file, false).is_err());
     }
 
+    #[test]
+    fn should_load_taproot_miner() {
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [miner]
+                taproot = true
+                "#,
+            )
+            .expect("Expected to be able to parse config file from string"),
+            false,
+        )
+        .expect("Expected to be able to load a Taproot miner config");
+        assert!(config.miner.taproot);
+        assert!(!config.miner.segwit);
+
+        let file = ConfigFile::from_str(
+            r#"
+            [burnchain]
+            self_managed_utxos = true
+
+            [miner]
+            taproot = true
+            "#,
+        )
+        .expect("Expected to be able to parse config file from string");
+        assert!(Config::from_config_file(file, false).is_err());
+    }
+
+    #[test]
+    fn should_load_esplora_backend() {
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [burnchain]
+                esplora_url = "http://localhost:3000/api"
+                "#,
+            )
+            .expect("Expected to be able to parse config file from string"),
+            false,
+        )
+        .expect("Expected to be able to load an Esplora burnchain config");
+        assert_eq!(
+            config.burnchain.esplora_url,
+            Some("http://localhost:3000/api".to_string())
+        );
+
+        let file = ConfigFile::from_str(
+            r#"
+            [burnchain]
+            esplora_url = "https://blockstream.info/api"
+            "#,
+        )
+        .expect("Expected to be able to parse config file from string");
+        assert!(Config::from_config_file(file, false).is_err());
+
+        let file = ConfigFile::from_str(
+            r#"
+            [burnchain]
+            esplora_url = "http://localhost:3000/api"
+            dynamic_fee_rate = true
+            "#,
+        )
+        .expect("Expected to be able to parse config file from string");
+        assert!(Config::from_config_file(file, false).is_err());
+    }
+
+    #[test]
+    fn should_load_header_checkpoints_and_extra_peers() {
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [burnchain]
+                extra_peers = ["127.0.0.1:18444", "bitcoin.example.com:8333"]
+
+                [[burnchain.header_checkpoints]]
+                height = 100
+                hash = "0000000000000000000000000000000000000000000000000000000000000064"
+                "#,
+            )
+            .expect("Expected to be able to parse config file from string"),
+            false,
+        )
+        .expect("Expected to be able to load header checkpoints and extra peers");
+        assert_eq!(
+            config.burnchain.header_checkpoints,
+            vec![(
+                100,
+                BurnchainHeaderHash::from_hex(
+                    "0000000000000000000000000000000000000000000000000000000000000064"
+                )
+                .unwrap()
+            )]
+        );
+        assert_eq!(
+            config.burnchain.extra_peers,
+            vec![
+                ("127.0.0.1".to_string(), 18444),
+                ("bitcoin.example.com".to_string(), 8333)
+            ]
+        );
+
+        let file = ConfigFile::from_str(
+            r#"
+            [burnchain]
+            extra_peers = ["127.0.0.1"]
+            "#,
+        )
+        .expect("Expected to be able to parse config file from string");
+        assert!(Config::from_config_file(file, false).is_err());
+
+        let file = ConfigFile::from_str(
+            r#"
+            [[burnchain.header_checkpoints]]
+            height = 100
+            hash = "not-a-hash"
+            "#,
+        )
+        .expect("Expected to be able to parse config file from string");
+        assert!(Config::from_config_file(file, false).is_err());
+    }
+
     #[test]
     fn should_load_empty_affirmation_map() {
         let config = Config::from_config_file(
@@ -1057,6 +1247,14 @@ impl Config {
             None => miner_default_config,
         };
 
+        if miner.taproot && burnchain.self_managed_utxos {
+            // the burnchain DB's wallet identifies the miner's spends by public key hash, which a
+            // Taproot key-path spend does not reveal
+            return Err(
+                "`miner.taproot` cannot be used with `burnchain.self_managed_utxos`".to_string(),
+            );
+        }
+
         let initial_balances: Vec<InitialBalance> = match config_file.ustx_balance {
             Some(balances) => {
                 if is_mainnet && balances.len() > 0 {
@@ -1385,6 +1583,15 @@ pub struct BurnchainConfig {
     pub leader_key_tx_estimated_size: u64,
     pub block_commit_tx_estimated_size: u64,
     pub rbf_fee_increment: u64,
+    /// If true, ask bitcoind for the fee rate of burnchain ops instead of using
+    /// `satoshis_per_byte`, which then only serves as a fallback.
+    pub dynamic_fee_rate: bool,
+    /// Number of bitcoin blocks within which burnchain ops should confirm, if
+    /// `dynamic_fee_rate` is set.  Each RBF attempt narrows the target by one block.
+    pub fee_rate_target_blocks: u64,
+    /// Hard cap on the fee rate (sats/vbyte) of any burnchain op, if `dynamic_fee_rate` is set.
+    /// If not given, it is `satoshis_per_byte * max_rbf / 100`.
+    pub max_satoshis_per_byte: Option<u64>,
     pub first_burn_block_height: Option<u64>,
     pub first_burn_block_timestamp: Option<u32>,
     pub first_burn_block_hash: Option<String>,
@@ -1397,8 +1604,30 @@ pub struct BurnchainConfig {
     pub sunset_start: Option<u32>,
     pub sunset_end: Option<u32>,
     pub wallet_name: String,
+    /// If true, track the miner's UTXOs from the burnchain blocks this node downloads instead of
+    /// asking bitcoind's wallet for them.  `wallet_name` is then unused.
+    pub self_managed_utxos: bool,
+    /// If `self_managed_utxos` is set, rescan the burnchain blocks from this height on startup
+    /// (once) for outputs received before the node started tracking them.
+    pub utxo_rescan_height: Option<u64>,
+    /// If set, get burnchain headers, blocks and UTXOs from the Esplora-compatible REST API at
+    /// this `http://` URL, and broadcast transactions through it, instead of using bitcoind.
+    /// There is no TLS support, so this should be a local server or one reached over a trusted
+    /// network.
+    pub esplora_url: Option<String>,
+    /// If set, use this in-process simulated burnchain instead of bitcoind.  Test builds only;
+    /// clones of this config share the same chain.
+    #[cfg(test)]
+    #[serde(skip)]
+    pub simulated_burnchain: Option<SimulatedBurnchain>,
     pub ast_precheck_size_height: Option<u64>,
     pub affirmation_overrides: HashMap<u64, AffirmationMap>,
+    /// Extra (height, hash) checkpoints that downloaded burnchain headers must match, in addition
+    /// to the built-in ones for mainnet and testnet.
+    pub header_checkpoints: Vec<(u64, BurnchainHeaderHash)>,
+    /// Additional bitcoin peers (host, port) whose header chains are cross-checked against
+    /// `peer_host`'s.  If one of them has more work, the node follows it and emits an alert.
+    pub extra_peers: Vec<(String, u16)>,
 }
 
 impl BurnchainConfig {
@@ -1426,6 +1655,9 @@ impl BurnchainConfig {
             leader_key_tx_estimated_size: LEADER_KEY_TX_ESTIM_SIZE,
             block_commit_tx_estimated_size: BLOCK_COMMIT_TX_ESTIM_SIZE,
             rbf_fee_increment: DEFAULT_RBF_FEE_RATE_INCREMENT,
+            dynamic_fee_rate: false,
+            fee_rate_target_blocks: DEFAULT_FEE_RATE_TARGET_BLOCKS,
+            max_satoshis_per_byte: None,
             first_burn_block_height: None,
             first_burn_block_timestamp: None,
             first_burn_block_hash: None,
@@ -1436,10 +1668,23 @@ impl BurnchainConfig {
             sunset_start: None,
             sunset_end: None,
             wallet_name: "".to_string(),
+            self_managed_utxos: false,
+            utxo_rescan_height: None,
+            esplora_url: None,
+            #[cfg(test)]
+            simulated_burnchain: None,
             ast_precheck_size_height: None,
             affirmation_overrides: HashMap::new(),
+            header_checkpoints: vec![],
+            extra_peers: vec![],
         }
     }
+    /// The highest fee rate (sats/vbyte) a burnchain op may pay when `dynamic_fee_rate` is set
+    pub fn get_max_fee_rate(&self) -> u64 {
+        self.max_satoshis_per_byte
+            .unwrap_or(self.satoshis_per_byte.saturating_mul(self.max_rbf) / 100)
+    }
+
     pub fn get_rpc_url(&self, wallet: Option<String>) -> String {
         let scheme = match self.rpc_ssl {
             true => "https://",
@@ -1498,6 +1743,12 @@ pub struct AffirmationOverride {
     pub affirmation: String,
 }
 
+#[derive(Clone, Deserialize, Default, Debug)]
+pub struct HeaderCheckpointConfigFile {
+    pub height: u64,
+    pub hash: String,
+}
+
 #[derive(Clone, Deserialize, Default, Debug)]
 pub struct BurnchainConfigFile {
     pub chain: Option<String>,
@@ -1520,6 +1771,9 @@ pub struct BurnchainConfigFile {
     pub block_commit_tx_estimated_size: Option<u64>,
     pub rbf_fee_increment: Option<u64>,
     pub max_rbf: Option<u64>,
+    pub dynamic_fee_rate: Option<bool>,
+    pub fee_rate_target_blocks: Option<u64>,
+    pub max_satoshis_per_byte: Option<u64>,
     pub first_burn_block_height: Option<u64>,
     pub first_burn_block_timestamp: Option<u32>,
     pub first_burn_block_hash: Option<String>,
@@ -1530,8 +1784,14 @@ pub struct BurnchainConfigFile {
     pub sunset_start: Option<u32>,
     pub sunset_end: Option<u32>,
     pub wallet_name: Option<String>,
+    pub self_managed_utxos: Option<bool>,
+    pub utxo_rescan_height: Option<u64>,
+    pub esplora_url: Option<String>,
     pub ast_precheck_size_height: Option<u64>,
     pub affirmation_overrides: Option<Vec<AffirmationOverride>>,
+    pub header_checkpoints: Option<Vec<HeaderCheckpointConfigFile>>,
+    /// `host:port` addresses of additional bitcoin peers
+    pub extra_peers: Option<Vec<String>>,
 }
 
 impl BurnchainConfigFile {
@@ -1644,6 +1904,31 @@ impl BurnchainConfigFile {
             }
         }
 
+        let mut header_checkpoints = default_burnchain_config.header_checkpoints;
+        for checkpoint in self.header_checkpoints.unwrap_or_default() {
+            let hash = BurnchainHeaderHash::from_hex(&checkpoint.hash).map_err(|_| {
+                format!(
+                    "Invalid burnchain.header_checkpoints hash at height {}: {}",
+                    checkpoint.height, checkpoint.hash
+                )
+            })?;
+            header_checkpoints.push((checkpoint.height, hash));
+        }
+
+        let mut extra_peers = default_burnchain_config.extra_peers;
+        for peer in self.extra_peers.unwrap_or_default() {
+            let Some((host, port)) = peer.rsplit_once(':') else {
+                return Err(format!(
+                    "Invalid burnchain.extra_peers entry '{}': expected host:port",
+                    peer
+                ));
+            };
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| format!("Invalid burnchain.extra_peers port in '{}'", peer))?;
+            extra_peers.push((host.to_string(), port));
+        }
+
         let mut config = BurnchainConfig {
             chain: self.chain.unwrap_or(default_burnchain_config.chain),
             chain_id: if is_mainnet {
@@ -1716,6 +2001,15 @@ impl BurnchainConfigFile {
             rbf_fee_increment: self
                 .rbf_fee_increment
                 .unwrap_or(default_burnchain_config.rbf_fee_increment),
+            dynamic_fee_rate: self
+                .dynamic_fee_rate
+                .unwrap_or(default_burnchain_config.dynamic_fee_rate),
+            fee_rate_target_blocks: self
+                .fee_rate_target_blocks
+                .unwrap_or(default_burnchain_config.fee_rate_target_blocks),
+            max_satoshis_per_byte: self
+                .max_satoshis_per_byte
+                .or(default_burnchain_config.max_satoshis_per_byte),
             first_burn_block_height: self
                 .first_burn_block_height
                 .or(default_burnchain_config.first_burn_block_height),
@@ -1737,6 +2031,15 @@ impl BurnchainConfigFile {
             wallet_name: self
                 .wallet_name
                 .unwrap_or(default_burnchain_config.wallet_name.clone()),
+            self_managed_utxos: self
+                .self_managed_utxos
+                .unwrap_or(default_burnchain_config.self_managed_utxos),
+            utxo_rescan_height: self
+                .utxo_rescan_height
+                .or(default_burnchain_config.utxo_rescan_height),
+            esplora_url: self.esplora_url.or(default_burnchain_config.esplora_url),
+            #[cfg(test)]
+            simulated_burnchain: None,
             pox_reward_length: self
                 .pox_reward_length
                 .or(default_burnchain_config.pox_reward_length),
@@ -1744,6 +2047,8 @@ impl BurnchainConfigFile {
                 .pox_prepare_length
                 .or(default_burnchain_config.pox_prepare_length),
             affirmation_overrides,
+            header_checkpoints,
+            extra_peers,
         };
 
         if let BitcoinNetworkType::Mainnet = config.get_bitcoin_network().1 {
@@ -1763,6 +2068,27 @@ impl BurnchainConfigFile {
             }
         }
 
+        if let Some(esplora_url) = config.esplora_url.as_ref() {
+            EsploraClient::new(esplora_url, config.timeout.into())
+                .map_err(|e| format!("Invalid burnchain.esplora_url: {}", e))?;
+            if config.dynamic_fee_rate {
+                // fee estimates come from bitcoind
+                return Err(
+                    "`burnchain.dynamic_fee_rate` cannot be used with `burnchain.esplora_url`"
+                        .into(),
+                );
+            }
+        }
+
+        if config.fee_rate_target_blocks == 0
+            || config.fee_rate_target_blocks > MAX_FEE_RATE_TARGET_BLOCKS
+        {
+            return Err(format!(
+                "burnchain.fee_rate_target_blocks must be between 1 and {}",
+                MAX_FEE_RATE_TARGET_BLOCKS
+            ));
+        }
+
         if let Some(ref conf_epochs) = self.epochs {
             config.epochs = Some(Config::make_epochs(
                 conf_epochs,
@@ -1817,6 +2143,9 @@ pub struct NodeConfig {
     pub chain_liveness_poll_time_secs: u64,
     /// stacker DBs we replicate
     pub stacker_dbs: Vec<QualifiedContractIdentifier>,
+    /// Maintain an index of the transactions that touched each principal, so they can be queried
+    /// via `/v3/addresses/:principal/transactions`
+    pub principal_tx_index: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -2098,6 +2427,7 @@ impl Default for NodeConfig {
             fault_injection_hide_blocks: false,
             chain_liveness_poll_time_secs: 300,
             stacker_dbs: vec![],
+            principal_tx_index: false,
         }
     }
 }
@@ -2274,6 +2604,9 @@ pub struct MinerConfig {
     pub block_reward_recipient: Option<PrincipalData>,
     /// If possible, mine with a p2wpkh address
     pub segwit: bool,
+    /// If possible, mine with a p2tr address, spending it through the Taproot key path.
+    /// Takes precedence over `segwit`.
+    pub taproot: bool,
     /// Wait for a downloader pass before mining.
     /// This can only be disabled in testing; it can't be changed in the config file.
     pub wait_for_block_download: bool,
@@ -2324,6 +2657,7 @@ impl Default for MinerConfig {
             probability_pick_no_estimate_tx: 25,
             block_reward_recipient: None,
             segwit: false,
+            taproot: false,
             wait_for_block_download: true,
             nonce_cache_size: 1024 * 1024,
             candidate_retry_cache_size: 1024 * 1024,
@@ -2390,6 +2724,13 @@ pub struct ConnectionOptionsFile {
     pub private_neighbors: Option<bool>,
     pub block_proposal_token: Option<String>,
     pub antientropy_retry: Option<u64>,
+    pub p2p_capture_path: Option<String>,
+    /// Request rate limit for RPC clients without an API key, per IP address
+    pub rpc_anonymous_requests_per_minute: Option<u64>,
+    /// Daily read-only execution budget for RPC clients without an API key, per IP address
+    pub rpc_anonymous_daily_read_only_runtime: Option<u64>,
+    pub rpc_api_tiers: Option<Vec<RPCAccessTierFile>>,
+    pub rpc_api_keys: Option<Vec<RPCApiKeyFile>>,
 }
 
 impl ConnectionOptionsFile {
@@ -2421,6 +2762,36 @@ impl ConnectionOptionsFile {
         self.read_only_call_limit_runtime.map(|x| {
             read_only_call_limit.runtime = x;
         });
+        let mut rpc_api_tiers = HashMap::new();
+        for tier in self.rpc_api_tiers.unwrap_or_default().into_iter() {
+            let tier_name = tier.name.clone();
+            if rpc_api_tiers
+                .insert(tier_name.clone(), tier.into_config())
+                .is_some()
+            {
+                return Err(format!(
+                    "Duplicate connection_options.rpc_api_tiers entry '{}'",
+                    &tier_name
+                ));
+            }
+        }
+        let mut rpc_api_keys = HashMap::new();
+        for api_key in self.rpc_api_keys.unwrap_or_default().into_iter() {
+            let Some(tier) = rpc_api_tiers.get(&api_key.tier) else {
+                return Err(format!(
+                    "connection_options.rpc_api_keys refers to unknown tier '{}'",
+                    &api_key.tier
+                ));
+            };
+            if rpc_api_keys.insert(api_key.key, tier.clone()).is_some() {
+                return Err("Duplicate connection_options.rpc_api_keys entry".to_string());
+            }
+        }
+        let rpc_anonymous_tier = RPCAccessTier {
+            name: "anonymous".into(),
+            requests_per_minute: self.rpc_anonymous_requests_per_minute.unwrap_or(0),
+            daily_read_only_runtime: self.rpc_anonymous_daily_read_only_runtime.unwrap_or(0),
+        };
         let default = ConnectionOptions::default();
         Ok(ConnectionOptions {
             read_only_call_limit,
@@ -2516,11 +2887,42 @@ impl ConnectionOptionsFile {
             private_neighbors: self.private_neighbors.unwrap_or(true),
             block_proposal_token: self.block_proposal_token,
             antientropy_retry: self.antientropy_retry.unwrap_or(default.antientropy_retry),
+            p2p_capture_path: self.p2p_capture_path,
+            rpc_api_keys,
+            rpc_anonymous_tier,
             ..default
         })
     }
 }
 
+/// An access tier for RPC API keys
+#[derive(Clone, Deserialize, Default, Debug)]
+pub struct RPCAccessTierFile {
+    pub name: String,
+    /// Maximum sustained requests per minute.  0 or unset means unlimited.
+    pub requests_per_minute: Option<u64>,
+    /// Maximum Clarity runtime cost of read-only calls per UTC day.  0 or unset means
+    /// unlimited.
+    pub daily_read_only_runtime: Option<u64>,
+}
+
+impl RPCAccessTierFile {
+    fn into_config(self) -> RPCAccessTier {
+        RPCAccessTier {
+            name: self.name,
+            requests_per_minute: self.requests_per_minute.unwrap_or(0),
+            daily_read_only_runtime: self.daily_read_only_runtime.unwrap_or(0),
+        }
+    }
+}
+
+/// An RPC API key, and the name of the tier it grants
+#[derive(Clone, Deserialize, Default, Debug)]
+pub struct RPCApiKeyFile {
+    pub key: String,
+    pub tier: String,
+}
+
 #[derive(Clone, Deserialize, Default, Debug)]
 pub struct NodeConfigFile {
     pub name: Option<String>,
@@ -2554,6 +2956,7 @@ pub struct NodeConfigFile {
     pub chain_liveness_poll_time_secs: Option<u64>,
     /// Stacker DBs we replicate
     pub stacker_dbs: Option<Vec<String>>,
+    pub principal_tx_index: Option<bool>,
 }
 
 impl NodeConfigFile {
@@ -2632,6 +3035,9 @@ impl NodeConfigFile {
                 .iter()
                 .filter_map(|contract_id| QualifiedContractIdentifier::parse(contract_id).ok())
                 .collect(),
+            principal_tx_index: self
+                .principal_tx_index
+                .unwrap_or(default_node_config.principal_tx_index),
         };
         Ok(node_config)
     }
@@ -2657,6 +3063,7 @@ pub struct MinerConfigFile {
     pub probability_pick_no_estimate_tx: Option<u8>,
     pub block_reward_recipient: Option<String>,
     pub segwit: Option<bool>,
+    pub taproot: Option<bool>,
     pub nonce_cache_size: Option<u64>,
     pub candidate_retry_cache_size: Option<u64>,
     pub unprocessed_block_deadline_secs: Option<u64>,
@@ -2704,6 +3111,7 @@ impl MinerConfigFile {
                 })
                 .transpose()?,
             segwit: self.segwit.unwrap_or(miner_default_config.segwit),
+            taproot: self.taproot.unwrap_or(miner_default_config.taproot),
             wait_for_block_download: miner_default_config.wait_for_block_download,
             nonce_cache_size: self
                 .nonce_cache_size
@@ -2831,6 +3239,7 @@ pub enum EventKeyType {
     MinedMicroblocks,
     StackerDBChunks,
     BlockProposal,
+    BurnchainPeerAlerts,
 }
 
 impl EventKeyType {
@@ -2863,6 +3272,10 @@ impl EventKeyType {
             return Some(EventKeyType::BlockProposal);
         }
 
+        if raw_key == "burnchain_peer_alerts" {
+            return Some(EventKeyType::BurnchainPeerAlerts);
+        }
+
         let comps: Vec<_> = raw_key.split("::").collect();
         if comps.len() == 1 {
             let split: Vec<_> = comps[0].split('.').collect();
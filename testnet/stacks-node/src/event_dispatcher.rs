@@ -13,6 +13,7 @@ use clarity::vm::events::{FTEventType, NFTEventType, STXEventType};
 use clarity::vm::types::{AssetIdentifier, QualifiedContractIdentifier, Value};
 use http_types::{Method, Request, Url};
 use serde_json::json;
+use stacks::burnchains::bitcoin::indexer::BitcoinPeerTipAlert;
 use stacks::burnchains::{PoxConstants, Txid};
 use stacks::chainstate::burn::operations::BlockstackOperationType;
 use stacks::chainstate::burn::ConsensusHash;
@@ -79,6 +80,7 @@ pub const PATH_BURN_BLOCK_SUBMIT: &str = "new_burn_block";
 pub const PATH_BLOCK_PROCESSED: &str = "new_block";
 pub const PATH_ATTACHMENT_PROCESSED: &str = "attachments/new";
 pub const PATH_PROPOSAL_RESPONSE: &str = "proposal_response";
+pub const PATH_BURNCHAIN_PEER_ALERT: &str = "burnchain_peer_alert";
 
 pub static STACKER_DB_CHANNEL: StackerDBChannel = StackerDBChannel::new();
 
@@ -562,6 +564,10 @@ impl EventObserver {
         self.send_payload(payload, PATH_BURN_BLOCK_SUBMIT);
     }
 
+    fn send_burnchain_peer_alert(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_BURNCHAIN_PEER_ALERT);
+    }
+
     fn make_new_block_processed_payload(
         &self,
         filtered_events: Vec<(usize, &(bool, Txid, &StacksTransactionEvent))>,
@@ -680,6 +686,7 @@ pub struct EventDispatcher {
     mined_microblocks_observers_lookup: HashSet<u16>,
     stackerdb_observers_lookup: HashSet<u16>,
     block_proposal_observers_lookup: HashSet<u16>,
+    burnchain_peer_alert_observers_lookup: HashSet<u16>,
 }
 
 /// This struct is used specifically for receiving proposal responses.
@@ -874,6 +881,7 @@ impl EventDispatcher {
             mined_microblocks_observers_lookup: HashSet::new(),
             stackerdb_observers_lookup: HashSet::new(),
             block_proposal_observers_lookup: HashSet::new(),
+            burnchain_peer_alert_observers_lookup: HashSet::new(),
         }
     }
 
@@ -1306,6 +1314,23 @@ impl EventDispatcher {
         }
     }
 
+    /// Forward an alert about a bitcoin peer whose header chain disagreed with ours to
+    /// `burnchain_peer_alerts` observers.
+    pub fn process_burnchain_peer_alert(&self, alert: &BitcoinPeerTipAlert) {
+        let interested_observers =
+            self.filter_observers(&self.burnchain_peer_alert_observers_lookup, false);
+        if interested_observers.is_empty() {
+            return;
+        }
+
+        let payload = serde_json::to_value(alert)
+            .expect("FATAL: failed to serialize BitcoinPeerTipAlert to JSON");
+
+        for observer in interested_observers.iter() {
+            observer.send_burnchain_peer_alert(&payload);
+        }
+    }
+
     pub fn process_dropped_mempool_txs(&self, txs: Vec<Txid>, reason: MemPoolDropReason) {
         // lazily assemble payload only if we have observers
         let interested_observers = self.filter_observers(&self.mempool_observers_lookup, true);
@@ -1424,6 +1449,10 @@ impl EventDispatcher {
                 EventKeyType::BlockProposal => {
                     self.block_proposal_observers_lookup.insert(observer_index);
                 }
+                EventKeyType::BurnchainPeerAlerts => {
+                    self.burnchain_peer_alert_observers_lookup
+                        .insert(observer_index);
+                }
             }
         }
 
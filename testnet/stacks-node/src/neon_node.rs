@@ -368,6 +368,7 @@ pub(crate) fn open_chainstate_with_faults(
     )?;
 
     chainstate.fault_injection.hide_blocks = config.node.fault_injection_hide_blocks;
+    chainstate.principal_tx_index = config.node.principal_tx_index;
     Ok(chainstate)
 }
 
@@ -1914,6 +1915,19 @@ impl BlockMinerThread {
                 .expect("FATAL: failed to construct segwit p2wpkh address"),
             );
         }
+        if config.miner.taproot {
+            btc_addrs.push(
+                // segwit p2tr
+                BitcoinAddress::from_bytes_segwit_p2tr(
+                    config.burnchain.get_bitcoin_network().1,
+                    &op_signer
+                        .get_public_key()
+                        .to_taproot_output_key()
+                        .expect("FATAL: failed to compute Taproot output key"),
+                )
+                .expect("FATAL: failed to construct segwit p2tr address"),
+            );
+        }
         btc_addrs
             .into_iter()
             .map(|addr| format!("{}", &addr))
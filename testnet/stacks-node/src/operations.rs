@@ -57,6 +57,30 @@ impl BurnchainOpSigner {
         Some(signature)
     }
 
+    /// Sign a BIP-341 sighash for a Taproot key-path spend of this signer's p2tr address.
+    /// Counts as a usage, just like `sign_message()`.
+    pub fn sign_taproot_key_spend(&mut self, sighash: &[u8]) -> Option<[u8; 64]> {
+        if self.is_disposed {
+            debug!("Signer is disposed");
+            return None;
+        }
+
+        let signature = match self.secret_key.sign_taproot_key_spend(sighash) {
+            Ok(r) => r,
+            Err(e) => {
+                debug!("Secret key error: {:?}", &e);
+                return None;
+            }
+        };
+        self.usages += 1;
+
+        if self.is_one_off && self.usages == 1 {
+            self.is_disposed = true;
+        }
+
+        Some(signature)
+    }
+
     pub fn dispose(&mut self) {
         self.is_disposed = true;
     }
@@ -64,7 +88,9 @@ impl BurnchainOpSigner {
 
 #[cfg(test)]
 mod test {
-    use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+    use stacks_common::util::secp256k1::{
+        secp256k1_verify_schnorr, Secp256k1PrivateKey, Secp256k1PublicKey,
+    };
 
     use super::BurnchainOpSigner;
 
@@ -80,4 +106,20 @@ mod test {
             assert_eq!(expected_wif, &op_signer.get_sk_as_wif());
         }
     }
+
+    #[test]
+    fn test_sign_taproot_key_spend() {
+        let secp_k = Secp256k1PrivateKey::new();
+        let output_key = Secp256k1PublicKey::from_private(&secp_k)
+            .to_taproot_output_key()
+            .unwrap();
+        let sighash = [0x11; 32];
+
+        let mut op_signer = BurnchainOpSigner::new(secp_k, true);
+        let sig = op_signer.sign_taproot_key_spend(&sighash).unwrap();
+        secp256k1_verify_schnorr(&sighash, &sig, &output_key).unwrap();
+
+        // one-off signers are disposed after one use
+        assert!(op_signer.sign_taproot_key_spend(&sighash).is_none());
+    }
 }
@@ -336,6 +336,11 @@ impl RunLoop {
     /// If there's a network error, then assume that we're not a miner.
     fn check_is_miner(&mut self, burnchain: &mut BitcoinRegtestController) -> bool {
         if self.config.node.miner {
+            if self.config.burnchain.self_managed_utxos {
+                // UTXOs are found as burnchain blocks are processed, which may not have happened yet
+                info!("Miner node: tracking UTXOs from burnchain blocks");
+                return true;
+            }
             let keychain = Keychain::default(self.config.node.seed.clone());
             let mut op_signer = keychain.generate_op_signer();
             match burnchain.create_wallet_if_dne() {
@@ -352,7 +357,20 @@ impl RunLoop {
                 )
                 .expect("FATAL: failed to construct legacy bitcoin address"),
             )];
-            if self.config.miner.segwit {
+            if self.config.miner.taproot {
+                btc_addrs.push((
+                    StacksEpochId::Epoch21,
+                    // segwit p2tr
+                    BitcoinAddress::from_bytes_segwit_p2tr(
+                        self.config.burnchain.get_bitcoin_network().1,
+                        &op_signer
+                            .get_public_key()
+                            .to_taproot_output_key()
+                            .expect("FATAL: failed to compute Taproot output key"),
+                    )
+                    .expect("FATAL: failed to construct segwit p2tr address"),
+                ));
+            } else if self.config.miner.segwit {
                 btc_addrs.push((
                     StacksEpochId::Epoch21,
                     // segwit p2wpkh
@@ -514,7 +532,7 @@ impl RunLoop {
         };
 
         info!("About to call open_and_exec");
-        let (chain_state_db, receipts) = StacksChainState::open_and_exec(
+        let (mut chain_state_db, receipts) = StacksChainState::open_and_exec(
             self.config.is_mainnet(),
             self.config.burnchain.chain_id,
             &self.config.get_chainstate_path_str(),
@@ -522,6 +540,7 @@ impl RunLoop {
             Some(self.config.node.get_marf_opts()),
         )
         .unwrap();
+        chain_state_db.principal_tx_index = self.config.node.principal_tx_index;
         run_loop::announce_boot_receipts(
             &mut self.event_dispatcher,
             &chain_state_db,
@@ -1217,6 +1236,10 @@ impl RunLoop {
                 burnchain_tip = next_burnchain_tip;
                 burnchain_height = tip_burnchain_height;
 
+                for alert in burnchain.take_peer_tip_alerts() {
+                    self.event_dispatcher.process_burnchain_peer_alert(&alert);
+                }
+
                 let sortition_tip = &burnchain_tip.block_snapshot.sortition_id;
                 let next_sortition_height = burnchain_tip.block_snapshot.block_height;
 
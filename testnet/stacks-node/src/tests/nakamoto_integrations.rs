@@ -30,6 +30,8 @@ use lazy_static::lazy_static;
 use libsigner::v1::messages::SignerMessage;
 use libsigner::{BlockProposal, SignerSession, StackerDBSession};
 use rand::RngCore;
+use stacks::burnchains::bitcoin::simulator::SimulatedBurnchain;
+use stacks::burnchains::db::BurnchainDB;
 use stacks::burnchains::{MagicBytes, Txid};
 use stacks::chainstate::burn::db::sortdb::SortitionDB;
 use stacks::chainstate::burn::operations::{
@@ -67,6 +69,7 @@ use stacks_common::address::AddressHashMode;
 use stacks_common::bitvec::BitVec;
 use stacks_common::codec::StacksMessageCodec;
 use stacks_common::consts::{CHAIN_ID_TESTNET, STACKS_EPOCH_MAX};
+use stacks_common::deps_common::bitcoin::blockdata::script::Script;
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksPrivateKey, StacksPublicKey,
 };
@@ -1202,6 +1205,190 @@ fn simple_neon_integration() {
     run_loop_thread.join().unwrap();
 }
 
+#[test]
+#[ignore]
+/// Like `simple_neon_integration`, but against a `SimulatedBurnchain`, so it needs no bitcoind.
+/// After a few Nakamoto tenures, a pre-stx op is mined into a fork off of the burnchain tip's
+/// parent, and the fork is made the longer chain.  This test asserts that:
+///  * the node indexes the op in the fork block
+///  * the miner abandons the orphaned tenure and mines Nakamoto blocks on the fork
+fn simulated_burnchain_nakamoto_integration() {
+    let signers = TestSigners::default();
+    let (mut naka_conf, _miner_account) = naka_neon_integration_conf(None);
+    let simulated_chain = SimulatedBurnchain::new();
+    naka_conf.burnchain.simulated_burnchain = Some(simulated_chain.clone());
+    naka_conf.miner.wait_on_interim_blocks = Duration::from_secs(1000);
+    let sender_signer_sk = Secp256k1PrivateKey::new();
+    let sender_signer_addr = tests::to_addr(&sender_signer_sk);
+    naka_conf.add_initial_balance(PrincipalData::from(sender_signer_addr).to_string(), 100000);
+    let stacker_sk = setup_stacker(&mut naka_conf);
+
+    let mut btc_regtest_controller = BitcoinRegtestController::new(naka_conf.clone(), None);
+    btc_regtest_controller.bootstrap_chain(201);
+
+    let mut run_loop = boot_nakamoto::BootRunLoop::new(naka_conf.clone()).unwrap();
+    let run_loop_stopper = run_loop.get_termination_switch();
+    let Counters {
+        blocks_processed,
+        naka_submitted_vrfs: vrfs_submitted,
+        naka_submitted_commits: commits_submitted,
+        naka_proposed_blocks: proposals_submitted,
+        ..
+    } = run_loop.counters();
+
+    let coord_channel = run_loop.coordinator_channels();
+
+    let run_loop_thread = thread::spawn(move || run_loop.start(None, 0));
+    wait_for_runloop(&blocks_processed);
+
+    // simulated burnchain blocks arrive much faster than bitcoind's, so make sure that the first
+    // Epoch 2.5 stacks block (which instantiates pox-4) is processed before `boot_to_epoch_3()`
+    // sends its stack-stx tx
+    for _i in 0..10 {
+        if get_chain_info(&naka_conf).stacks_tip_height >= 1 {
+            break;
+        }
+        next_block_and_wait(&mut btc_regtest_controller, &blocks_processed);
+    }
+    assert!(get_chain_info(&naka_conf).stacks_tip_height >= 1);
+
+    boot_to_epoch_3(
+        &naka_conf,
+        &blocks_processed,
+        &[stacker_sk],
+        &[sender_signer_sk],
+        Some(&signers),
+        &mut btc_regtest_controller,
+    );
+
+    info!("Bootstrapped to Epoch-3.0 boundary, starting nakamoto miner");
+
+    let burnchain = naka_conf.get_burnchain();
+    let sortdb = burnchain.open_sortition_db(true).unwrap();
+    let (chainstate, _) = StacksChainState::open(
+        naka_conf.is_mainnet(),
+        naka_conf.burnchain.chain_id,
+        &naka_conf.get_chainstate_path_str(),
+        None,
+    )
+    .unwrap();
+
+    blind_signer(&naka_conf, &signers, proposals_submitted);
+
+    // first block wakes up the run loop, wait until a key registration has been submitted.
+    next_block_and(&mut btc_regtest_controller, 60, || {
+        let vrf_count = vrfs_submitted.load(Ordering::SeqCst);
+        Ok(vrf_count >= 1)
+    })
+    .unwrap();
+
+    // second block should confirm the VRF register, wait until a block commit is submitted
+    next_block_and(&mut btc_regtest_controller, 60, || {
+        let commits_count = commits_submitted.load(Ordering::SeqCst);
+        Ok(commits_count >= 1)
+    })
+    .unwrap();
+
+    // Mine 3 nakamoto tenures
+    for _i in 0..3 {
+        next_block_and_mine_commit(
+            &mut btc_regtest_controller,
+            60,
+            &coord_channel,
+            &commits_submitted,
+        )
+        .unwrap();
+
+        signer_vote_if_needed(
+            &btc_regtest_controller,
+            &naka_conf,
+            &[sender_signer_sk],
+            &signers,
+        );
+    }
+
+    let tip = NakamotoChainState::get_canonical_block_header(chainstate.db(), &sortdb)
+        .unwrap()
+        .unwrap();
+    assert!(tip.anchored_header.as_stacks_nakamoto().is_some());
+    let block_height_pre_fork = tip.stacks_block_height;
+
+    // replace the burnchain tip with a two-block fork whose first block holds a pre-stx op
+    let (_, burn_height_pre_fork) = simulated_chain.visible_tip();
+    let fork_parent = simulated_chain
+        .get_block_hash(burn_height_pre_fork - 1)
+        .unwrap();
+    let pre_stx_op = PreStxOp {
+        output: sender_signer_addr,
+        // to be filled in
+        txid: Txid([0u8; 32]),
+        vtxindex: 0,
+        block_height: 0,
+        burn_header_hash: BurnchainHeaderHash([0u8; 32]),
+    };
+    let mut op_signer = Keychain::default(naka_conf.node.seed.clone()).generate_op_signer();
+    let op_block = btc_regtest_controller
+        .mine_simulated_operation(
+            StacksEpochId::Epoch30,
+            BlockstackOperationType::PreStx(pre_stx_op),
+            &mut op_signer,
+            &fork_parent,
+        )
+        .expect("Failed to mine the pre-stx op into the fork");
+    let fork_tip = simulated_chain
+        .mine_block_on(&op_block, vec![], &Script::new())
+        .unwrap();
+    simulated_chain.set_chain_tip(&fork_tip).unwrap();
+
+    // the tenure started at the old burnchain tip is orphaned, so mine until a tenure started on
+    // the fork produces a block
+    for _i in 0..10 {
+        if next_block_and_process_new_stacks_block(&mut btc_regtest_controller, 60, &coord_channel)
+            .is_err()
+        {
+            continue;
+        }
+        let tip = NakamotoChainState::get_canonical_block_header(chainstate.db(), &sortdb)
+            .unwrap()
+            .unwrap();
+        if u64::from(tip.burn_header_height) > burn_height_pre_fork {
+            break;
+        }
+    }
+
+    let burnchain_db = BurnchainDB::open(&burnchain.get_burnchaindb_path(), false).unwrap();
+    let op_burn_block = BurnchainDB::get_burnchain_block(burnchain_db.conn(), &op_block).unwrap();
+    assert!(
+        op_burn_block.ops.iter().any(|op| matches!(
+            op,
+            BlockstackOperationType::PreStx(op) if op.output == sender_signer_addr
+        )),
+        "Pre-stx op was not indexed in the fork block"
+    );
+
+    let tip = NakamotoChainState::get_canonical_block_header(chainstate.db(), &sortdb)
+        .unwrap()
+        .unwrap();
+    info!(
+        "Latest tip";
+        "height" => tip.stacks_block_height,
+        "burn_height" => tip.burn_header_height,
+        "is_nakamoto" => tip.anchored_header.as_stacks_nakamoto().is_some(),
+    );
+    assert!(tip.anchored_header.as_stacks_nakamoto().is_some());
+    assert!(u64::from(tip.burn_header_height) > burn_height_pre_fork);
+    // the miner may have rebuilt the orphaned tenure's blocks
+    assert!(tip.stacks_block_height >= block_height_pre_fork);
+
+    coord_channel
+        .lock()
+        .expect("Mutex poisoned")
+        .stop_chains_coordinator();
+    run_loop_stopper.store(false, Ordering::SeqCst);
+
+    run_loop_thread.join().unwrap();
+}
+
 #[test]
 #[ignore]
 /// This test spins up a nakamoto-neon node.
@@ -16,6 +16,7 @@ use rusqlite::types::ToSql;
 use serde::Deserialize;
 use serde_json::json;
 use stacks::burnchains::bitcoin::address::{BitcoinAddress, LegacyBitcoinAddressType};
+use stacks::burnchains::bitcoin::simulator::SimulatedBurnchain;
 use stacks::burnchains::bitcoin::BitcoinNetworkType;
 use stacks::burnchains::db::BurnchainDB;
 use stacks::burnchains::{Address, Burnchain, PoxConstants, Txid};
@@ -66,6 +67,7 @@ use stacks::util_lib::signed_structured_data::pox4::{
     make_pox_4_signer_key_signature, Pox4SignatureTopic,
 };
 use stacks_common::address::AddressHashMode;
+use stacks_common::deps_common::bitcoin::blockdata::script::Script;
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksBlockId,
 };
@@ -3236,6 +3238,89 @@ fn bitcoind_forking_test() {
     channel.stop_chains_coordinator();
 }
 
+/// Like `bitcoind_forking_test`, but against a `SimulatedBurnchain`: the fork is built while
+/// withheld, delivered all at once, and then the original chain is delivered back.
+#[test]
+#[ignore]
+fn simulated_burnchain_forking_test() {
+    let (mut conf, miner_account) = neon_integration_test_conf();
+    let simulated_chain = SimulatedBurnchain::new();
+    conf.burnchain.simulated_burnchain = Some(simulated_chain.clone());
+
+    let mut btc_regtest_controller = BitcoinRegtestController::new(conf.clone(), None);
+    let http_origin = format!("http://{}", &conf.node.rpc_bind);
+
+    btc_regtest_controller.bootstrap_chain(201);
+
+    eprintln!("Chain bootstrapped...");
+
+    let mut run_loop = neon::RunLoop::new(conf.clone());
+    let blocks_processed = run_loop.get_blocks_processed_arc();
+
+    let channel = run_loop.get_coordinator_channel().unwrap();
+
+    thread::spawn(move || run_loop.start(None, 0));
+
+    // give the run loop some time to start up!
+    wait_for_runloop(&blocks_processed);
+
+    // first block wakes up the run loop
+    next_block_and_wait(&mut btc_regtest_controller, &blocks_processed);
+
+    // first block will hold our VRF registration
+    next_block_and_wait(&mut btc_regtest_controller, &blocks_processed);
+
+    let mut sort_height = channel.get_sortitions_processed();
+    while sort_height < 210 {
+        next_block_and_wait(&mut btc_regtest_controller, &blocks_processed);
+        sort_height = channel.get_sortitions_processed();
+        eprintln!("Sort height: {}", sort_height);
+    }
+
+    let account = get_account(&http_origin, &miner_account);
+    assert_eq!(account.nonce, 7);
+
+    // build a longer, empty fork off of height 205 while it is withheld
+    let (original_tip, original_height) = simulated_chain.visible_tip();
+    let fork_point = simulated_chain.get_block_hash(205).unwrap();
+    let mut fork_tip = fork_point.clone();
+    let mut fork = vec![];
+    for _ in 205..(original_height + 1) {
+        fork_tip = simulated_chain
+            .mine_block_on(&fork_tip, vec![], &Script::new())
+            .unwrap();
+        fork.push(fork_tip.clone());
+    }
+    simulated_chain.withhold_block(&fork[0]).unwrap();
+    simulated_chain.set_chain_tip(&fork_tip).unwrap();
+    assert_eq!(simulated_chain.visible_tip(), (fork_point, 205));
+
+    // deliver the fork
+    simulated_chain.release_block(&fork[0]);
+    eprintln!("Wait for block off of the fork");
+    next_block_and_wait(&mut btc_regtest_controller, &blocks_processed);
+
+    let info = get_chain_info(&conf);
+    assert_eq!(info.burn_block_height, original_height + 2);
+    let account = get_account(&http_origin, &miner_account);
+    assert!(account.nonce < 7);
+    let fork_nonce = account.nonce;
+
+    // deliver the original chain again, now that it is the shorter one
+    simulated_chain.set_chain_tip(&original_tip).unwrap();
+    simulated_chain.mine_blocks(2, &Script::new());
+    eprintln!("Wait for block off of the original chain");
+    next_block_and_wait(&mut btc_regtest_controller, &blocks_processed);
+
+    let info = get_chain_info(&conf);
+    assert_eq!(info.burn_block_height, original_height + 3);
+    let account = get_account(&http_origin, &miner_account);
+    assert!(account.nonce > fork_nonce);
+
+    eprintln!("End of test");
+    channel.stop_chains_coordinator();
+}
+
 #[test]
 #[ignore]
 fn should_fix_2771() {
@@ -574,7 +574,7 @@ impl SignerTest {
             contract_name.clone(),
             SIGNERS_VOTING_FUNCTION_NAME.into(),
             &valid_function_args,
-            &signer_private_key,
+            &StacksPublicKey::from_private(&signer_private_key),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             1,
@@ -586,7 +586,7 @@ impl SignerTest {
             "bad-signers-contract-name".into(),
             SIGNERS_VOTING_FUNCTION_NAME.into(),
             &valid_function_args,
-            &signer_private_key,
+            &StacksPublicKey::from_private(&signer_private_key),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             1,
@@ -598,7 +598,7 @@ impl SignerTest {
             contract_name.clone(),
             "some-other-function".into(),
             &valid_function_args,
-            &signer_private_key,
+            &StacksPublicKey::from_private(&signer_private_key),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             1,
@@ -616,7 +616,7 @@ impl SignerTest {
                     round_arg.clone(),
                     reward_cycle_arg.clone(),
                 ],
-                &signer_private_key,
+                &StacksPublicKey::from_private(&signer_private_key),
                 TransactionVersion::Testnet,
                 CHAIN_ID_TESTNET,
                 1,
@@ -633,7 +633,7 @@ impl SignerTest {
                 round_arg.clone(),
                 reward_cycle_arg.clone(),
             ],
-            &signer_private_key,
+            &StacksPublicKey::from_private(&signer_private_key),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             1,
@@ -650,7 +650,7 @@ impl SignerTest {
                 point_arg.clone(),
                 reward_cycle_arg.clone(),
             ],
-            &signer_private_key,
+            &StacksPublicKey::from_private(&signer_private_key),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             1,
@@ -668,7 +668,7 @@ impl SignerTest {
                     round_arg.clone(),
                     point_arg.clone(),
                 ],
-                &signer_private_key,
+                &StacksPublicKey::from_private(&signer_private_key),
                 TransactionVersion::Testnet,
                 CHAIN_ID_TESTNET,
                 1,
@@ -680,7 +680,7 @@ impl SignerTest {
             contract_name.clone(),
             SIGNERS_VOTING_FUNCTION_NAME.into(),
             &valid_function_args,
-            &signer_private_key,
+            &StacksPublicKey::from_private(&signer_private_key),
             TransactionVersion::Testnet,
             CHAIN_ID_TESTNET,
             0, // Old nonce